@@ -0,0 +1,49 @@
+//! GTFS-RT parsing over a saved feed: protobuf decode, then extracting
+//! the arrivals at a station.
+//!
+//! The fixture `benches/fixtures/gtfs-ace.pb` is sized like the A/C/E feed
+//! (built by `src/mta/fixture.rs`); set `FEED_FIXTURE` to bench a real
+//! recording.
+
+use std::collections::HashSet;
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use prost::Message;
+
+use subway_sign::mta::client::{trains_from_feed, transit_realtime::FeedMessage};
+
+fn fixture() -> Vec<u8> {
+    let path = std::env::var("FEED_FIXTURE")
+        .unwrap_or_else(|_| concat!(env!("CARGO_MANIFEST_DIR"), "/benches/fixtures/gtfs-ace.pb").to_string());
+    std::fs::read(&path).unwrap_or_else(|e| panic!("cannot read feed fixture {}: {}", path, e))
+}
+
+fn bench_feed(c: &mut Criterion) {
+    let bytes = fixture();
+    let feed = FeedMessage::decode(bytes.as_slice()).expect("fixture is a FeedMessage");
+    // Arrivals are judged against the recording's own clock
+    let now = feed.header.timestamp.unwrap_or(0) as f64;
+    // 42 St-Port Authority, A/C/E both directions
+    let stop_ids: Vec<String> = ["A27N", "A27S"].iter().map(|s| s.to_string()).collect();
+    let routes: HashSet<String> = ["A", "C", "E"].iter().map(|s| s.to_string()).collect();
+
+    let (trains, _) = trains_from_feed(&feed, &stop_ids, &routes, now, None);
+    assert!(!trains.is_empty(), "fixture has no arrivals at {:?}", stop_ids);
+
+    c.bench_function("feed/decode", |b| {
+        b.iter(|| black_box(FeedMessage::decode(black_box(bytes.as_slice())).unwrap()))
+    });
+    c.bench_function("feed/trains_from_feed", |b| {
+        b.iter(|| black_box(trains_from_feed(&feed, &stop_ids, &routes, now, None)))
+    });
+    c.bench_function("feed/decode_and_extract", |b| {
+        b.iter(|| {
+            let feed = FeedMessage::decode(black_box(bytes.as_slice())).unwrap();
+            black_box(trains_from_feed(&feed, &stop_ids, &routes, now, None))
+        })
+    });
+}
+
+criterion_group!(benches, bench_feed);
+criterion_main!(benches);
@@ -0,0 +1,100 @@
+//! Render hot paths: a full frame, plain text, and alert pre-rendering.
+//!
+//! `cargo bench --bench render`; compare runs with `--save-baseline` and
+//! `--baseline`.
+
+use std::collections::HashSet;
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use subway_sign::display::fonts::FontStyle;
+use subway_sign::display::framebuffer::FrameBuffer;
+use subway_sign::display::renderer::{Renderer, CHAR_SPACING};
+use subway_sign::models::{Alert, Direction, DisplaySnapshot, Train};
+
+fn train(route: &str, dest: &str, minutes: i32) -> Train {
+    Train {
+        route: route.into(),
+        destination: dest.into(),
+        minutes,
+        is_express: false,
+        arrival_timestamp: 0.0,
+        direction: Direction::Uptown,
+        stop_id: "127N".into(),
+        delay_secs: None,
+        trip_id: String::new(),
+    }
+}
+
+fn snapshot() -> DisplaySnapshot {
+    DisplaySnapshot {
+        trains: vec![
+            train("1", "Van Cortlandt Park", 2),
+            train("2", "Wakefield-241 St", 4),
+            train("3", "Harlem-148 St", 7),
+            train("7", "Flushing-Main St", 9),
+            train("N", "Astoria-Ditmars Blvd", 12),
+        ],
+        alerts: Vec::new(),
+        fetched_at: 0.0,
+    }
+}
+
+fn alert() -> Alert {
+    Alert {
+        text: "[A] [C] [E] trains are running with delays while we address a signal \
+               problem near 59 St-Columbus Circle. Consider the 1 train."
+            .into(),
+        affected_routes: HashSet::new(),
+        priority: 10,
+        alert_id: "bench".into(),
+        url: None,
+        is_ticker: false,
+    }
+}
+
+fn bench_render_frame(c: &mut Criterion) {
+    let snapshot = snapshot();
+    let alert = alert();
+    let mut renderer = Renderer::new();
+
+    c.bench_function("render_frame/arrivals", |b| {
+        let mut cycle = 0;
+        b.iter(|| {
+            cycle += 1;
+            black_box(renderer.render_frame(&snapshot, cycle, false, 0.0, false, None))
+        })
+    });
+    // Alert buffer cached after the first frame, as while it scrolls
+    c.bench_function("render_frame/alert_scrolling", |b| {
+        let mut offset = 0.0;
+        b.iter(|| {
+            offset = (offset + 0.5) % 600.0;
+            black_box(renderer.render_frame(&snapshot, 0, false, offset, true, Some(&alert)))
+        })
+    });
+}
+
+fn bench_draw_text(c: &mut Criterion) {
+    let mut fb = FrameBuffer::new();
+    for (name, style) in [("regular", FontStyle::Regular), ("italic", FontStyle::Italic)] {
+        c.bench_function(&format!("draw_text/{}", name), |b| {
+            b.iter(|| {
+                let text = black_box("Pelham Bay Park-Westchester Sq");
+                black_box(fb.draw_text(text, 0, 1, (0, 255, 0), style, CHAR_SPACING))
+            })
+        });
+    }
+}
+
+fn bench_alert_prerender(c: &mut Criterion) {
+    let renderer = Renderer::new();
+    let alert = alert();
+    c.bench_function("alert_prerender", |b| {
+        b.iter(|| black_box(renderer.render_alert_with_icons(black_box(&alert.text), FontStyle::Italic)))
+    });
+}
+
+criterion_group!(benches, bench_render_frame, bench_draw_text, bench_alert_prerender);
+criterion_main!(benches);
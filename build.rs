@@ -1,3 +1,35 @@
+use std::path::PathBuf;
+
+/// StopTimeUpdate's own extension range, just after its last field.
+const STOP_TIME_EXTENSIONS: &str = "optional StopTimeProperties stop_time_properties = 6;
+
+    // The extensions namespace allows 3rd-party developers to extend the
+    // GTFS Realtime Specification in order to add and evaluate new features
+    // and modifications to the spec.
+    extensions 1000 to 1999;";
+
 fn main() {
-    prost_build::compile_protos(&["proto/gtfs-realtime.proto"], &["proto/"]).unwrap();
+    println!("cargo:rerun-if-changed=proto");
+
+    // prost drops proto2 extensions, so the NYCT stop time extension (field
+    // 1001, see proto/nyct-subway.proto) goes in as a regular field
+    let spec = std::fs::read_to_string("proto/gtfs-realtime.proto").unwrap();
+    assert!(spec.contains(STOP_TIME_EXTENSIONS), "StopTimeUpdate extension range moved");
+    let spec = spec
+        .replacen(
+            "package transit_realtime;",
+            "package transit_realtime;\nimport \"nyct-subway.proto\";",
+            1,
+        )
+        .replacen(
+            STOP_TIME_EXTENSIONS,
+            &STOP_TIME_EXTENSIONS.replace(
+                "extensions 1000 to 1999;",
+                "optional NyctStopTimeUpdate nyct_stop_time_update = 1001;\n    extensions 1000;\n    extensions 1002 to 1999;",
+            ),
+            1,
+        );
+    let out = PathBuf::from(std::env::var("OUT_DIR").unwrap());
+    std::fs::write(out.join("gtfs-realtime.proto"), spec).unwrap();
+    prost_build::compile_protos(&[out.join("gtfs-realtime.proto")], &[out, PathBuf::from("proto/")]).unwrap();
 }
@@ -0,0 +1,11 @@
+//! `Config::from_json` on arbitrary text: errors are fine, panics aren't.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use subway_sign::config::Config;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(text) = std::str::from_utf8(data) {
+        let _ = Config::from_json(text);
+    }
+});
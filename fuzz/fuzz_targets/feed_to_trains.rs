@@ -0,0 +1,17 @@
+//! GTFS-RT bytes through decoding and the entity-to-Train conversion.
+#![no_main]
+
+use std::collections::HashSet;
+
+use libfuzzer_sys::fuzz_target;
+use prost::Message;
+use subway_sign::mta::client::{trains_from_feed, transit_realtime::FeedMessage};
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(feed) = FeedMessage::decode(data) else { return };
+    let now = feed.header.timestamp.unwrap_or(0) as f64;
+    let stop_ids = ["127N".to_string(), "127S".to_string(), "A27N".to_string()];
+    let routes: HashSet<String> = ["1", "2", "3", "A", "6X"].iter().map(|r| r.to_string()).collect();
+    let (trains, route_trips) = trains_from_feed(&feed, &stop_ids, &routes, now, None);
+    assert!(trains.len() <= route_trips);
+});
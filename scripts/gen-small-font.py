@@ -0,0 +1,123 @@
+#!/usr/bin/env python3
+"""Generate assets/fonts/mta-sign-small.json, the 8px font (FontSize::Small).
+
+Glyphs are drawn by hand below, one string per row ('#' lit), top-aligned
+with lowercase descenders in the last two rows. The output has the same
+format as mta-sign.json (character code -> row bitmasks, bit 0 leftmost)
+and covers every character the 16px font has, plus a few the list and
+board layouts need.
+
+    python3 scripts/gen-small-font.py
+"""
+import json
+import os
+
+FONTS = os.path.join(os.path.dirname(os.path.abspath(__file__)), "..", "assets", "fonts")
+
+G = {}
+def g(c, *rows):
+    rows = list(rows)
+    while len(rows) < 8: rows.append('.' * len(rows[0]))
+    assert len(rows) == 8 and len(set(map(len, rows))) == 1, c
+    G[c] = rows
+# digits (6 rows)
+g('0', '.##.', '#..#', '#.##', '##.#', '#..#', '.##.')
+g('1', '.#', '##', '.#', '.#', '.#', '.#')
+g('2', '.##.', '#..#', '..#.', '.#..', '#...', '####')
+g('3', '###.', '...#', '.##.', '...#', '...#', '###.')
+g('4', '..#.', '.##.', '#.#.', '####', '..#.', '..#.')
+g('5', '####', '#...', '###.', '...#', '...#', '###.')
+g('6', '.##.', '#...', '###.', '#..#', '#..#', '.##.')
+g('7', '####', '...#', '..#.', '.#..', '.#..', '.#..')
+g('8', '.##.', '#..#', '.##.', '#..#', '#..#', '.##.')
+g('9', '.##.', '#..#', '#..#', '.###', '...#', '.##.')
+# uppercase
+g('A', '.##.', '#..#', '#..#', '####', '#..#', '#..#')
+g('B', '###.', '#..#', '###.', '#..#', '#..#', '###.')
+g('C', '.##.', '#..#', '#...', '#...', '#..#', '.##.')
+g('D', '###.', '#..#', '#..#', '#..#', '#..#', '###.')
+g('E', '####', '#...', '###.', '#...', '#...', '####')
+g('F', '####', '#...', '###.', '#...', '#...', '#...')
+g('G', '.##.', '#...', '#.##', '#..#', '#..#', '.###')
+g('H', '#..#', '#..#', '####', '#..#', '#..#', '#..#')
+g('I', '###', '.#.', '.#.', '.#.', '.#.', '###')
+g('J', '...#', '...#', '...#', '...#', '#..#', '.##.')
+g('K', '#..#', '#.#.', '##..', '#.#.', '#..#', '#..#')
+g('L', '#...', '#...', '#...', '#...', '#...', '####')
+g('M', '#...#', '##.##', '#.#.#', '#...#', '#...#', '#...#')
+g('N', '#..#', '##.#', '#.##', '#..#', '#..#', '#..#')
+g('O', '.##.', '#..#', '#..#', '#..#', '#..#', '.##.')
+g('P', '###.', '#..#', '#..#', '###.', '#...', '#...')
+g('Q', '.##.', '#..#', '#..#', '#..#', '#.#.', '.#.#')
+g('R', '###.', '#..#', '#..#', '###.', '#.#.', '#..#')
+g('S', '.###', '#...', '.##.', '...#', '...#', '###.')
+g('T', '###', '.#.', '.#.', '.#.', '.#.', '.#.')
+g('U', '#..#', '#..#', '#..#', '#..#', '#..#', '.##.')
+g('V', '#...#', '#...#', '#...#', '.#.#.', '.#.#.', '..#..')
+g('W', '#...#', '#...#', '#...#', '#.#.#', '##.##', '#...#')
+g('X', '#..#', '#..#', '.##.', '.##.', '#..#', '#..#')
+g('Y', '#...#', '#...#', '.#.#.', '..#..', '..#..', '..#..')
+g('Z', '####', '...#', '..#.', '.#..', '#...', '####')
+# lowercase: x-height rows 2-5, ascenders from row 0, descenders to row 7
+g('a', '....', '....', '.###', '#..#', '#..#', '.###')
+g('b', '#...', '#...', '###.', '#..#', '#..#', '###.')
+g('c', '...', '...', '.##', '#..', '#..', '.##')
+g('d', '...#', '...#', '.###', '#..#', '#..#', '.###')
+g('e', '....', '....', '.##.', '####', '#...', '.##.')
+g('f', '.##', '#..', '###', '#..', '#..', '#..')
+g('g', '....', '....', '.###', '#..#', '#..#', '.###', '...#', '.##.')
+g('h', '#...', '#...', '###.', '#..#', '#..#', '#..#')
+g('i', '#', '.', '#', '#', '#', '#')
+g('j', '..#', '...', '..#', '..#', '..#', '..#', '..#', '##.')
+g('k', '#...', '#...', '#..#', '###.', '#.#.', '#..#')
+g('l', '#.', '#.', '#.', '#.', '#.', '.#')
+g('m', '.....', '.....', '####.', '#.#.#', '#.#.#', '#.#.#')
+g('n', '....', '....', '###.', '#..#', '#..#', '#..#')
+g('o', '....', '....', '.##.', '#..#', '#..#', '.##.')
+g('p', '....', '....', '###.', '#..#', '#..#', '###.', '#...', '#...')
+g('q', '....', '....', '.###', '#..#', '#..#', '.###', '...#', '...#')
+g('r', '...', '...', '#.#', '##.', '#..', '#..')
+g('s', '....', '....', '.###', '##..', '..##', '###.')
+g('t', '.#.', '.#.', '###', '.#.', '.#.', '..#')
+g('u', '....', '....', '#..#', '#..#', '#..#', '.###')
+g('v', '...', '...', '#.#', '#.#', '#.#', '.#.')
+g('w', '.....', '.....', '#...#', '#.#.#', '#.#.#', '.#.#.')
+g('x', '...', '...', '#.#', '.#.', '.#.', '#.#')
+g('y', '....', '....', '#..#', '#..#', '#..#', '.###', '...#', '.##.')
+g('z', '....', '....', '####', '..#.', '.#..', '####')
+# punctuation
+g('!', '#', '#', '#', '#', '.', '#')
+g('#', '.#.#.', '#####', '.#.#.', '.#.#.', '#####', '.#.#.')
+g('$', '.###', '#.#.', '.##.', '..##', '###.', '.#..')
+g('%', '#..#', '...#', '..#.', '.#..', '#...', '#..#')
+g("'", '#', '#', '.', '.', '.', '.')
+g('*', '...', '#.#', '.#.', '#.#', '...', '...')
+g(',', '..', '..', '..', '..', '..', '.#', '#.')
+g('-', '...', '...', '###', '...', '...', '...')
+g('.', '.', '.', '.', '.', '.', '#')
+g('/', '...#', '...#', '..#.', '.#..', '#...', '#...')
+g(':', '.', '#', '.', '.', '#', '.')
+g('<', '..#', '.#.', '#..', '.#.', '..#', '...')
+g('>', '#..', '.#.', '..#', '.#.', '#..', '...')
+g('@', '.###.', '#...#', '#.###', '#.##.', '#....', '.###.')
+g('\\', '#...', '#...', '.#..', '..#.', '...#', '...#')
+g('^', '.#.', '#.#', '...', '...', '...', '...')
+g(' ', '..', '..', '..', '..', '..', '..')
+# beyond the 16px font: list/board destinations and the express marker
+g('&', '.#..', '#.#.', '.#..', '#.##', '#..#', '.##.')
+g('(', '.#', '#.', '#.', '#.', '#.', '.#')
+g(')', '#.', '.#', '.#', '.#', '.#', '#.')
+g('+', '...', '...', '.#.', '###', '.#.', '...')
+g('\u25c6', '.....', '..#..', '.###.', '#####', '.###.', '..#..')
+
+big = json.load(open(os.path.join(FONTS, 'mta-sign.json')))
+want = sorted({chr(int(k)) for k in big if k.isdigit()} | set('&()+\u25c6'))
+missing = [c for c in want if c not in G]
+assert not missing, missing
+# Blank columns left of each glyph, as in mta-sign.json
+PAD = 2
+out = {"name": "MTA sign small"}
+for c in want:
+    out[str(ord(c))] = [sum(1 << (i + PAD) for i, p in enumerate(r) if p == '#') for r in G[c]]
+with open(os.path.join(FONTS, 'mta-sign-small.json'), 'w') as f:
+    json.dump(out, f, separators=(',', ':'))
@@ -0,0 +1,392 @@
+//! Countdown accuracy: what the sign predicted vs when trains arrived.
+//!
+//! Trains carry no trip ID in the snapshot, so they're followed across
+//! fetches by route, direction, platform and a predicted arrival that
+//! stays within `MATCH_TOLERANCE` of the previous one. A train "arrives"
+//! when its countdown first shows 0, or when it drops out of the feed while
+//! (nearly) due. Trains that vanish while still far out were cancelled or
+//! pushed past `max_trains` and are not counted.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::{Direction, Train};
+
+/// Max jump in predicted arrival (seconds) between fetches for the same train.
+const MATCH_TOLERANCE: f64 = 300.0;
+
+/// A train dropping out of the feed counts as arrived if it was predicted
+/// within this many seconds.
+const DROP_WINDOW: f64 = 120.0;
+
+/// Upper bounds (minutes ahead) of the prediction horizon buckets; anything
+/// further out lands in the last bucket.
+const HORIZON_EDGES: [f64; 4] = [2.0, 5.0, 10.0, 20.0];
+
+/// Bucket labels, one more than `HORIZON_EDGES`.
+pub const HORIZON_LABELS: [&str; 5] = ["0-2", "2-5", "5-10", "10-20", "20+"];
+
+fn horizon_bucket(minutes_ahead: f64) -> usize {
+    HORIZON_EDGES
+        .iter()
+        .position(|&edge| minutes_ahead < edge)
+        .unwrap_or(HORIZON_EDGES.len())
+}
+
+/// Running totals of prediction errors (actual minus predicted, seconds;
+/// positive = the train came later than the sign said).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ErrorStats {
+    pub count: u64,
+    pub sum_error: f64,
+    pub sum_abs_error: f64,
+    /// Predictions within a minute either way.
+    pub within_minute: u64,
+}
+
+impl ErrorStats {
+    pub fn add(&mut self, error: f64) {
+        self.count += 1;
+        self.sum_error += error;
+        self.sum_abs_error += error.abs();
+        if error.abs() <= 60.0 {
+            self.within_minute += 1;
+        }
+    }
+
+    pub fn merge(&mut self, other: &ErrorStats) {
+        self.count += other.count;
+        self.sum_error += other.sum_error;
+        self.sum_abs_error += other.sum_abs_error;
+        self.within_minute += other.within_minute;
+    }
+
+    pub fn summary(&self) -> ErrorSummary {
+        let n = self.count.max(1) as f64;
+        ErrorSummary {
+            count: self.count,
+            mean_error_secs: round1(self.sum_error / n),
+            mean_abs_error_secs: round1(self.sum_abs_error / n),
+            within_1min_pct: round1(self.within_minute as f64 * 100.0 / n),
+        }
+    }
+}
+
+fn round1(x: f64) -> f64 {
+    (x * 10.0).round() / 10.0
+}
+
+/// `ErrorStats` as reported by the API.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ErrorSummary {
+    pub count: u64,
+    /// Average bias: positive = trains run later than predicted.
+    pub mean_error_secs: f64,
+    pub mean_abs_error_secs: f64,
+    pub within_1min_pct: f64,
+}
+
+/// `AccuracyStats` as reported by the API.
+#[derive(Debug, Serialize)]
+pub struct AccuracyReport {
+    pub arrivals: u64,
+    pub overall: ErrorSummary,
+    /// In `HORIZON_LABELS` order, buckets without samples omitted.
+    pub by_horizon: Vec<HorizonSummary>,
+    pub by_route: BTreeMap<String, ErrorSummary>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HorizonSummary {
+    /// Minutes ahead the prediction was made, e.g. "5-10".
+    pub horizon: &'static str,
+    #[serde(flatten)]
+    pub stats: ErrorSummary,
+}
+
+/// One day's accuracy aggregates (persisted).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AccuracyStats {
+    /// Trains seen arriving.
+    pub arrivals: u64,
+    /// Every prediction sample, all horizons and routes.
+    pub overall: ErrorStats,
+    /// Keyed by `HORIZON_LABELS`.
+    pub by_horizon: BTreeMap<String, ErrorStats>,
+    pub by_route: BTreeMap<String, ErrorStats>,
+}
+
+impl AccuracyStats {
+    /// Fold one arrival's prediction samples into the totals.
+    pub fn record(&mut self, arrival: &ObservedArrival) {
+        self.arrivals += 1;
+        for &(bucket, predicted) in &arrival.samples {
+            let error = arrival.arrived_at - predicted;
+            self.overall.add(error);
+            self.by_horizon
+                .entry(HORIZON_LABELS[bucket].to_string())
+                .or_default()
+                .add(error);
+            self.by_route
+                .entry(arrival.route.clone())
+                .or_default()
+                .add(error);
+        }
+    }
+
+    pub fn merge(&mut self, other: &AccuracyStats) {
+        self.arrivals += other.arrivals;
+        self.overall.merge(&other.overall);
+        for (key, stats) in &other.by_horizon {
+            self.by_horizon.entry(key.clone()).or_default().merge(stats);
+        }
+        for (key, stats) in &other.by_route {
+            self.by_route.entry(key.clone()).or_default().merge(stats);
+        }
+    }
+
+    pub fn report(&self) -> AccuracyReport {
+        AccuracyReport {
+            arrivals: self.arrivals,
+            overall: self.overall.summary(),
+            by_horizon: HORIZON_LABELS
+                .iter()
+                .filter_map(|&label| {
+                    self.by_horizon.get(label).map(|stats| HorizonSummary {
+                        horizon: label,
+                        stats: stats.summary(),
+                    })
+                })
+                .collect(),
+            by_route: self
+                .by_route
+                .iter()
+                .map(|(route, stats)| (route.clone(), stats.summary()))
+                .collect(),
+        }
+    }
+}
+
+/// A train that reached the platform, with what was predicted for it.
+#[derive(Debug, Clone)]
+pub struct ObservedArrival {
+    pub route: String,
+    pub direction: Direction,
+    pub stop_id: String,
+    /// Unix seconds.
+    pub arrived_at: f64,
+    /// First predicted arrival seen in each horizon bucket: (bucket, unix secs).
+    pub samples: Vec<(usize, f64)>,
+}
+
+#[derive(Debug)]
+struct TrackedTrain {
+    route: String,
+    direction: Direction,
+    stop_id: String,
+    /// Latest predicted arrival (unix secs).
+    predicted: f64,
+    samples: Vec<(usize, f64)>,
+    /// Countdown already hit 0; kept only so it isn't seen as a new train.
+    arrived: bool,
+}
+
+impl TrackedTrain {
+    fn new(train: &Train) -> Self {
+        TrackedTrain {
+            route: train.route.clone(),
+            direction: train.direction,
+            stop_id: train.stop_id.clone(),
+            predicted: train.arrival_timestamp,
+            samples: Vec::new(),
+            arrived: false,
+        }
+    }
+
+    fn same_platform(&self, train: &Train) -> bool {
+        self.route == train.route
+            && self.direction == train.direction
+            && self.stop_id == train.stop_id
+    }
+
+    fn sample(&mut self, now: f64) {
+        let bucket = horizon_bucket((self.predicted - now) / 60.0);
+        if !self.samples.iter().any(|&(b, _)| b == bucket) {
+            self.samples.push((bucket, self.predicted));
+        }
+    }
+
+    fn finish(&mut self, now: f64) -> ObservedArrival {
+        self.arrived = true;
+        ObservedArrival {
+            route: self.route.clone(),
+            direction: self.direction,
+            stop_id: self.stop_id.clone(),
+            arrived_at: now,
+            samples: std::mem::take(&mut self.samples),
+        }
+    }
+}
+
+/// Follows trains across snapshots and reports them as they arrive.
+#[derive(Debug, Default)]
+pub struct ArrivalTracker {
+    trains: Vec<TrackedTrain>,
+}
+
+impl ArrivalTracker {
+    /// Trains currently being followed.
+    pub fn tracking(&self) -> usize {
+        self.trains.iter().filter(|t| !t.arrived).count()
+    }
+
+    /// Forget everything (after a gap in data the matches can't be trusted).
+    pub fn reset(&mut self) {
+        self.trains.clear();
+    }
+
+    /// Feed one snapshot's trains, observed at `now` (unix secs). Returns the
+    /// trains that arrived since the previous call.
+    pub fn observe(&mut self, trains: &[Train], now: f64) -> Vec<ObservedArrival> {
+        let mut arrivals = Vec::new();
+        let mut previous = std::mem::take(&mut self.trains);
+
+        for train in trains.iter().filter(|t| !t.route.is_empty()) {
+            let matched = previous
+                .iter()
+                .enumerate()
+                .filter(|(_, t)| {
+                    t.same_platform(train)
+                        && (t.predicted - train.arrival_timestamp).abs() <= MATCH_TOLERANCE
+                })
+                .min_by(|(_, a), (_, b)| {
+                    let da = (a.predicted - train.arrival_timestamp).abs();
+                    let db = (b.predicted - train.arrival_timestamp).abs();
+                    da.total_cmp(&db)
+                })
+                .map(|(i, _)| i);
+            let mut tracked = match matched {
+                Some(i) => previous.swap_remove(i),
+                None => TrackedTrain::new(train),
+            };
+            tracked.predicted = train.arrival_timestamp;
+            if !tracked.arrived {
+                tracked.sample(now);
+                if train.minutes <= 0 || train.arrival_timestamp <= now {
+                    arrivals.push(tracked.finish(now));
+                }
+            }
+            self.trains.push(tracked);
+        }
+
+        // Whatever didn't match left the feed
+        for mut gone in previous {
+            if !gone.arrived && gone.predicted - now <= DROP_WINDOW {
+                arrivals.push(gone.finish(now));
+            }
+        }
+        arrivals
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const T0: f64 = 1_700_000_000.0;
+
+    fn train(route: &str, arrival: f64, now: f64) -> Train {
+        Train {
+            route: route.into(),
+            destination: "Test".into(),
+            minutes: ((arrival - now) / 60.0).max(0.0) as i32,
+            is_express: false,
+            arrival_timestamp: arrival,
+            direction: Direction::Uptown,
+            stop_id: "R17N".into(),
+            delay_secs: None,
+            trip_id: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_train_followed_to_zero() {
+        let mut tracker = ArrivalTracker::default();
+        // Predicted 8 min out, slips by 30s per fetch, arrives 90s late
+        let mut arrival = T0 + 480.0;
+        let mut now = T0;
+        let mut arrivals = Vec::new();
+        while arrivals.is_empty() {
+            arrivals = tracker.observe(&[train("Q", arrival, now)], now);
+            now += 60.0;
+            arrival += 10.0;
+            assert!(now < T0 + 1200.0, "never arrived");
+        }
+        assert_eq!(arrivals.len(), 1);
+        let a = &arrivals[0];
+        assert_eq!(a.route, "Q");
+        let buckets: Vec<usize> = a.samples.iter().map(|&(b, _)| b).collect();
+        assert_eq!(buckets, vec![2, 1, 0]);
+        let first_error = a.arrived_at - a.samples[0].1;
+        assert!(first_error > 0.0, "train ran late vs the 8-minute prediction");
+
+        // Still at 0 on the next fetch: not counted twice
+        assert!(tracker.observe(&[train("Q", arrival, now)], now).is_empty());
+        assert_eq!(tracker.tracking(), 0);
+    }
+
+    #[test]
+    fn test_dropped_trains() {
+        let mut tracker = ArrivalTracker::default();
+        tracker.observe(&[train("N", T0 + 90.0, T0), train("W", T0 + 900.0, T0)], T0);
+        assert_eq!(tracker.tracking(), 2);
+        // Both vanish: the due N arrived, the distant W was cancelled
+        let arrivals = tracker.observe(&[], T0 + 30.0);
+        assert_eq!(arrivals.len(), 1);
+        assert_eq!(arrivals[0].route, "N");
+        assert_eq!(tracker.tracking(), 0);
+    }
+
+    #[test]
+    fn test_trains_matched_by_platform_and_time() {
+        let mut tracker = ArrivalTracker::default();
+        let now = T0;
+        tracker.observe(&[train("Q", T0 + 300.0, now), train("Q", T0 + 900.0, now)], now);
+        let later = T0 + 60.0;
+        // Order swapped in the feed: matching is by time, not position
+        let arrivals = tracker.observe(
+            &[train("Q", T0 + 930.0, later), train("Q", T0 + 320.0, later)],
+            later,
+        );
+        assert!(arrivals.is_empty());
+        assert_eq!(tracker.tracking(), 2);
+    }
+
+    #[test]
+    fn test_stats_record_and_summary() {
+        let mut stats = AccuracyStats::default();
+        stats.record(&ObservedArrival {
+            route: "Q".into(),
+            direction: Direction::Uptown,
+            stop_id: "R17N".into(),
+            arrived_at: T0,
+            samples: vec![(0, T0 - 30.0), (2, T0 - 150.0)],
+        });
+        assert_eq!(stats.arrivals, 1);
+        let summary = stats.overall.summary();
+        assert_eq!(summary.count, 2);
+        assert_eq!(summary.mean_error_secs, 90.0);
+        assert_eq!(summary.within_1min_pct, 50.0);
+        assert_eq!(stats.by_horizon["5-10"].count, 1);
+        assert_eq!(stats.by_route["Q"].count, 2);
+
+        let mut total = AccuracyStats::default();
+        total.merge(&stats);
+        total.merge(&stats);
+        assert_eq!(total.by_route["Q"].count, 4);
+        let horizons: Vec<&str> = total.report().by_horizon.iter().map(|h| h.horizon).collect();
+        assert_eq!(horizons, vec!["0-2", "5-10"]);
+        assert_eq!(ErrorStats::default().summary().mean_error_secs, 0.0);
+    }
+}
@@ -0,0 +1,245 @@
+//! Service analytics built from watching display snapshots over time.
+//!
+//! `analytics_task` feeds each new snapshot to `Analytics`, which follows
+//! trains to arrival and keeps today's totals (countdown accuracy and
+//! per-route service) in memory. Totals are flushed to the per-day `store`
+//! every few minutes, at midnight and at shutdown.
+
+pub mod accuracy;
+pub mod service;
+pub mod store;
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use chrono::{NaiveDate, Timelike};
+use tracing::{info, warn};
+
+use crate::models::DisplaySnapshot;
+use crate::timezone::Timezone;
+use crate::AppState;
+
+use accuracy::ArrivalTracker;
+use store::{ArrivalRecord, DayRecord, Store};
+
+/// How often the task checks for a new snapshot.
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How often today's totals are written out.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(300);
+
+/// Snapshots further apart than this (seconds) break train matching, so the
+/// tracker starts over.
+const MAX_SNAPSHOT_GAP: f64 = 600.0;
+
+/// Live analytics state: the arrival tracker plus today's record.
+pub struct Analytics {
+    store: Store,
+    tracker: ArrivalTracker,
+    today: DayRecord,
+    today_date: NaiveDate,
+    last_observed: f64,
+}
+
+impl Analytics {
+    /// Start for `today`, resuming its totals if already saved.
+    pub fn new(store: Store, today: NaiveDate) -> Self {
+        let record = store.load(today).unwrap_or_else(|| DayRecord::new(today));
+        Analytics {
+            store,
+            tracker: ArrivalTracker::default(),
+            today: record,
+            today_date: today,
+            last_observed: 0.0,
+        }
+    }
+
+    pub fn store(&self) -> &Store {
+        &self.store
+    }
+
+    pub fn today(&self) -> &DayRecord {
+        &self.today
+    }
+
+    pub fn today_date(&self) -> NaiveDate {
+        self.today_date
+    }
+
+    /// Trains currently being followed to arrival.
+    pub fn tracking(&self) -> usize {
+        self.tracker.tracking()
+    }
+
+    /// Feed a snapshot taken on local date `date` (in `tz`). Returns the
+    /// previous day's final record when the date rolled over (for the caller
+    /// to save).
+    pub fn observe(&mut self, snapshot: &DisplaySnapshot, date: NaiveDate, tz: Timezone) -> Option<DayRecord> {
+        let now = snapshot.fetched_at;
+        if now - self.last_observed > MAX_SNAPSHOT_GAP {
+            self.tracker.reset();
+        }
+        self.last_observed = now;
+
+        let finished = if date != self.today_date {
+            self.today_date = date;
+            let next = DayRecord {
+                service: self.today.service.carry_over(),
+                ..DayRecord::new(date)
+            };
+            Some(std::mem::replace(&mut self.today, next))
+        } else {
+            None
+        };
+
+        for arrival in self.tracker.observe(&snapshot.trains, now) {
+            self.today.accuracy.record(&arrival);
+            self.today.service.record(&arrival, local_hour(arrival.arrived_at, tz));
+            self.today.arrivals.push(ArrivalRecord {
+                route: arrival.route,
+                direction: arrival.direction,
+                stop_id: arrival.stop_id,
+                arrived_at: arrival.arrived_at,
+            });
+        }
+        finished
+    }
+}
+
+/// Local hour (0-23) of a unix timestamp.
+fn local_hour(timestamp: f64, tz: Timezone) -> u32 {
+    tz.from_timestamp(timestamp as i64).map(|t| t.hour()).unwrap_or(0)
+}
+
+/// Write a record off the async runtime.
+async fn save(store: Store, record: DayRecord) {
+    let date = record.date.clone();
+    match tokio::task::spawn_blocking(move || store.save(&record)).await {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => warn!("[ANALYTICS] Failed to save {}: {}", date, e),
+        Err(e) => warn!("[ANALYTICS] Save task failed: {}", e),
+    }
+}
+
+/// Analytics task — observes each fetched snapshot (idle while
+/// `analytics.enabled` is off).
+pub async fn analytics_task(state: Arc<AppState>) {
+    let mut interval = tokio::time::interval(POLL_INTERVAL);
+    let mut last_fetched_at = 0.0;
+    let mut last_flush = Instant::now();
+
+    loop {
+        tokio::select! {
+            _ = state.shutdown.cancelled() => {
+                let (store, record) = {
+                    let analytics = state.analytics.lock().unwrap_or_else(|e| e.into_inner());
+                    (analytics.store().clone(), analytics.today().clone())
+                };
+                save(store, record).await;
+                info!("[ANALYTICS] Shutting down");
+                break;
+            }
+            _ = interval.tick() => {
+                let config = state.config.load();
+                if !config.analytics.enabled {
+                    continue;
+                }
+                let snapshot = state.snapshot.load_full();
+                if snapshot.fetched_at == last_fetched_at {
+                    continue;
+                }
+                last_fetched_at = snapshot.fetched_at;
+
+                let tz = config.tz();
+                let today = tz.today();
+                let (store, finished, current) = {
+                    let mut analytics = state.analytics.lock().unwrap_or_else(|e| e.into_inner());
+                    let finished = analytics.observe(&snapshot, today, tz);
+                    let flush = finished.is_some() || last_flush.elapsed() >= FLUSH_INTERVAL;
+                    let current = flush.then(|| analytics.today().clone());
+                    (analytics.store().clone(), finished, current)
+                };
+
+                if let Some(record) = finished {
+                    info!(
+                        "[ANALYTICS] {}: {} arrivals observed",
+                        record.date, record.accuracy.arrivals
+                    );
+                    save(store.clone(), record).await;
+                    let keep = config.analytics.retention_days;
+                    let pruner = store.clone();
+                    let _ = tokio::task::spawn_blocking(move || pruner.prune(today, keep)).await;
+                }
+                if let Some(record) = current {
+                    save(store, record).await;
+                    last_flush = Instant::now();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Direction, Train};
+
+    fn snapshot(fetched_at: f64, arrival: f64) -> DisplaySnapshot {
+        DisplaySnapshot {
+            trains: vec![Train {
+                route: "Q".into(),
+                destination: "Astoria".into(),
+                minutes: ((arrival - fetched_at) / 60.0).max(0.0) as i32,
+                is_express: false,
+                arrival_timestamp: arrival,
+                direction: Direction::Uptown,
+                stop_id: "R17N".into(),
+                delay_secs: None,
+                trip_id: String::new(),
+            }],
+            alerts: Vec::new(),
+            fetched_at,
+        }
+    }
+
+    #[test]
+    fn test_observe_rolls_over_and_resumes() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = Store::Files(dir.path().to_path_buf());
+        let day1 = NaiveDate::from_ymd_opt(2026, 3, 1).unwrap();
+        let day2 = day1.succ_opt().unwrap();
+        let t0 = 1_700_000_000.0;
+
+        let mut analytics = Analytics::new(store.clone(), day1);
+        assert!(analytics.observe(&snapshot(t0, t0 + 200.0), day1, Timezone::System).is_none());
+        assert!(analytics.observe(&snapshot(t0 + 180.0, t0 + 200.0), day1, Timezone::System).is_none());
+        assert_eq!(analytics.today().accuracy.arrivals, 1);
+        assert_eq!(analytics.today().service.routes["Q"].arrivals, 1);
+        assert_eq!(analytics.today().arrivals[0].arrived_at, t0 + 180.0);
+
+        let finished = analytics.observe(&snapshot(t0 + 240.0, t0 + 900.0), day2, Timezone::System).unwrap();
+        assert_eq!(finished.date, "2026-03-01");
+        assert_eq!(analytics.today().accuracy.arrivals, 0);
+        assert_eq!(analytics.today().service.routes["Q"].arrivals, 0);
+        assert_eq!(analytics.today().service.routes["Q"].last_arrival.len(), 1);
+        analytics.store().save(&finished).unwrap();
+
+        let resumed = Analytics::new(store, day1);
+        assert_eq!(resumed.today().accuracy.arrivals, 1);
+    }
+
+    #[test]
+    fn test_gap_resets_tracking() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = Store::Files(dir.path().to_path_buf());
+        let day = NaiveDate::from_ymd_opt(2026, 3, 1).unwrap();
+        let t0 = 1_700_000_000.0;
+
+        let mut analytics = Analytics::new(store, day);
+        analytics.observe(&snapshot(t0, t0 + 100.0), day, Timezone::System);
+        // Sign was offline for an hour: the stale train is not an arrival
+        analytics.observe(&snapshot(t0 + 3600.0, t0 + 4000.0), day, Timezone::System);
+        assert_eq!(analytics.today().accuracy.arrivals, 0);
+        assert_eq!(analytics.tracking(), 1);
+    }
+}
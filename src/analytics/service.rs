@@ -0,0 +1,195 @@
+//! Daily service: how many trains each route ran and how far apart.
+//!
+//! Built from the same arrivals as the accuracy stats. A headway is the
+//! time since the previous arrival of the same route in the same direction,
+//! filed under the local hour of the later train.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::accuracy::ObservedArrival;
+use crate::models::Direction;
+
+/// Longer gaps aren't headways (service suspended, or the sign was off).
+const MAX_HEADWAY: f64 = 3600.0;
+
+/// One hour of one route.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct HourService {
+    pub arrivals: u64,
+    pub headways: u64,
+    /// Seconds.
+    pub headway_sum: f64,
+}
+
+impl HourService {
+    fn merge(&mut self, other: &HourService) {
+        self.arrivals += other.arrivals;
+        self.headways += other.headways;
+        self.headway_sum += other.headway_sum;
+    }
+}
+
+/// One route's day.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RouteService {
+    pub arrivals: u64,
+    /// Keyed by local hour, 0-23.
+    pub hours: BTreeMap<u32, HourService>,
+    /// Latest arrival per direction (unix secs), for the next headway.
+    pub last_arrival: BTreeMap<Direction, f64>,
+}
+
+/// One day's service totals (persisted).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ServiceStats {
+    pub routes: BTreeMap<String, RouteService>,
+}
+
+impl ServiceStats {
+    /// Count an arrival that happened in local hour `hour`.
+    pub fn record(&mut self, arrival: &ObservedArrival, hour: u32) {
+        let route = self.routes.entry(arrival.route.clone()).or_default();
+        route.arrivals += 1;
+        let slot = route.hours.entry(hour).or_default();
+        slot.arrivals += 1;
+        if let Some(prev) = route.last_arrival.insert(arrival.direction, arrival.arrived_at) {
+            let headway = arrival.arrived_at - prev;
+            if headway > 0.0 && headway <= MAX_HEADWAY {
+                slot.headways += 1;
+                slot.headway_sum += headway;
+            }
+        }
+    }
+
+    /// Empty stats for the next day that still know each route's last
+    /// arrival, so the first headway after midnight counts.
+    pub fn carry_over(&self) -> ServiceStats {
+        ServiceStats {
+            routes: self
+                .routes
+                .iter()
+                .map(|(name, route)| {
+                    let carried = RouteService {
+                        last_arrival: route.last_arrival.clone(),
+                        ..Default::default()
+                    };
+                    (name.clone(), carried)
+                })
+                .collect(),
+        }
+    }
+
+    pub fn report(&self) -> ServiceReport {
+        let routes: Vec<RouteReport> = self
+            .routes
+            .iter()
+            .filter(|(_, r)| r.arrivals > 0)
+            .map(|(name, r)| {
+                let mut day = HourService::default();
+                let hours = r
+                    .hours
+                    .iter()
+                    .map(|(&hour, h)| {
+                        day.merge(h);
+                        HourReport {
+                            hour,
+                            arrivals: h.arrivals,
+                            avg_headway_mins: avg_minutes(h),
+                        }
+                    })
+                    .collect();
+                RouteReport {
+                    route: name.clone(),
+                    arrivals: r.arrivals,
+                    avg_headway_mins: avg_minutes(&day),
+                    hours,
+                }
+            })
+            .collect();
+        ServiceReport {
+            arrivals: routes.iter().map(|r| r.arrivals).sum(),
+            routes,
+        }
+    }
+}
+
+fn avg_minutes(hour: &HourService) -> Option<f64> {
+    (hour.headways > 0)
+        .then(|| (hour.headway_sum / hour.headways as f64 / 60.0 * 10.0).round() / 10.0)
+}
+
+/// `ServiceStats` as reported by the API.
+#[derive(Debug, Serialize)]
+pub struct ServiceReport {
+    pub arrivals: u64,
+    pub routes: Vec<RouteReport>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RouteReport {
+    pub route: String,
+    pub arrivals: u64,
+    /// `None` until two trains have run in the same direction.
+    pub avg_headway_mins: Option<f64>,
+    pub hours: Vec<HourReport>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HourReport {
+    pub hour: u32,
+    pub arrivals: u64,
+    pub avg_headway_mins: Option<f64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn arrival(route: &str, direction: Direction, at: f64) -> ObservedArrival {
+        ObservedArrival {
+            route: route.into(),
+            direction,
+            stop_id: "R17N".into(),
+            arrived_at: at,
+            samples: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_headways_per_direction_and_hour() {
+        let mut stats = ServiceStats::default();
+        stats.record(&arrival("Q", Direction::Uptown, 0.0), 8);
+        stats.record(&arrival("Q", Direction::Downtown, 60.0), 8);
+        stats.record(&arrival("Q", Direction::Uptown, 480.0), 8);
+        stats.record(&arrival("Q", Direction::Uptown, 1200.0), 9);
+        stats.record(&arrival("N", Direction::Uptown, 9000.0), 9);
+        // Over an hour since the last N: not a headway
+        stats.record(&arrival("N", Direction::Uptown, 14000.0), 10);
+
+        let report = stats.report();
+        assert_eq!(report.arrivals, 6);
+        let q = report.routes.iter().find(|r| r.route == "Q").unwrap();
+        assert_eq!(q.arrivals, 4);
+        assert_eq!(q.hours[0].hour, 8);
+        assert_eq!(q.hours[0].avg_headway_mins, Some(8.0));
+        assert_eq!(q.hours[1].avg_headway_mins, Some(12.0));
+        assert_eq!(q.avg_headway_mins, Some(10.0));
+        let n = report.routes.iter().find(|r| r.route == "N").unwrap();
+        assert_eq!(n.avg_headway_mins, None);
+    }
+
+    #[test]
+    fn test_carry_over_keeps_last_arrivals() {
+        let mut stats = ServiceStats::default();
+        stats.record(&arrival("Q", Direction::Uptown, 0.0), 23);
+        let mut next = stats.carry_over();
+        assert!(next.report().routes.is_empty());
+        next.record(&arrival("Q", Direction::Uptown, 600.0), 0);
+        assert_eq!(next.report().routes[0].avg_headway_mins, Some(10.0));
+
+        let json = serde_json::to_string(&next).unwrap();
+        assert_eq!(serde_json::from_str::<ServiceStats>(&json).unwrap(), next);
+    }
+}
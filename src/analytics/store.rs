@@ -0,0 +1,347 @@
+//! Per-day analytics records, in JSON files or the SQLite database.
+//!
+//! Without a database, each local calendar day is one small JSON document
+//! (`<dir>/YYYY-MM-DD.json`) rewritten in place (temp file + rename) as the
+//! day's totals grow.
+
+use std::path::{Path, PathBuf};
+#[cfg(feature = "persistence")]
+use std::sync::Arc;
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use super::accuracy::AccuracyStats;
+use super::service::ServiceStats;
+use crate::models::Direction;
+use crate::timezone::Timezone;
+#[cfg(feature = "persistence")]
+use crate::persistence::Database;
+
+/// Date format used in file names and the API.
+pub const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// Everything recorded for one day.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DayRecord {
+    /// Local date, `YYYY-MM-DD`.
+    pub date: String,
+    #[serde(default)]
+    pub accuracy: AccuracyStats,
+    #[serde(default)]
+    pub service: ServiceStats,
+    /// Every observed arrival, in order.
+    #[serde(default)]
+    pub arrivals: Vec<ArrivalRecord>,
+}
+
+/// One train observed arriving.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArrivalRecord {
+    pub route: String,
+    pub direction: Direction,
+    pub stop_id: String,
+    /// Unix seconds.
+    pub arrived_at: f64,
+}
+
+/// CSV header for `arrivals_csv`.
+const CSV_HEADER: &str = "timestamp,local_time,route,direction,stop_id\n";
+
+/// Arrivals from `records` as CSV, one row per train.
+pub fn arrivals_csv(records: &[DayRecord], tz: Timezone) -> String {
+    let mut csv = String::from(CSV_HEADER);
+    for arrival in records.iter().flat_map(|r| &r.arrivals) {
+        let local_time = tz
+            .from_timestamp(arrival.arrived_at as i64)
+            .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_default();
+        csv.push_str(&format!(
+            "{},{},{},{:?},{}\n",
+            arrival.arrived_at as i64, local_time, arrival.route, arrival.direction, arrival.stop_id
+        ));
+    }
+    csv
+}
+
+impl DayRecord {
+    pub fn new(date: NaiveDate) -> Self {
+        DayRecord {
+            date: date.format(DATE_FORMAT).to_string(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Where day records live.
+#[derive(Debug, Clone)]
+pub enum Store {
+    /// One JSON file per day in a directory.
+    Files(PathBuf),
+    /// Tables in the shared SQLite database.
+    #[cfg(feature = "persistence")]
+    Sqlite(Arc<Database>),
+}
+
+impl Store {
+    /// Load one day; `None` if it was never written or can't be read.
+    pub fn load(&self, date: NaiveDate) -> Option<DayRecord> {
+        let date = date.format(DATE_FORMAT).to_string();
+        let loaded = match self {
+            Store::Files(dir) => load_file(dir, &date),
+            #[cfg(feature = "persistence")]
+            Store::Sqlite(db) => sqlite::load(db, &date),
+        };
+        match loaded {
+            Ok(record) => record,
+            Err(e) => {
+                tracing::warn!("[ANALYTICS] Ignoring unreadable {}: {}", date, e);
+                None
+            }
+        }
+    }
+
+    /// Days in `from..=to` that have records, oldest first.
+    pub fn load_range(&self, from: NaiveDate, to: NaiveDate) -> Vec<DayRecord> {
+        from.iter_days()
+            .take_while(|d| *d <= to)
+            .filter_map(|d| self.load(d))
+            .collect()
+    }
+
+    /// Write (replace) a day's record.
+    pub fn save(&self, record: &DayRecord) -> Result<(), String> {
+        match self {
+            Store::Files(dir) => save_file(dir, record),
+            #[cfg(feature = "persistence")]
+            Store::Sqlite(db) => sqlite::save(db, record),
+        }
+    }
+
+    /// Delete records older than `keep_days` before `today`.
+    pub fn prune(&self, today: NaiveDate, keep_days: u32) {
+        let cutoff = today - chrono::Days::new(keep_days as u64);
+        match self {
+            Store::Files(dir) => prune_files(dir, cutoff),
+            #[cfg(feature = "persistence")]
+            Store::Sqlite(db) => {
+                if let Err(e) = sqlite::prune(db, cutoff) {
+                    tracing::warn!("[ANALYTICS] Failed to prune: {}", e);
+                }
+            }
+        }
+    }
+}
+
+fn day_file(dir: &Path, date: &str) -> PathBuf {
+    dir.join(format!("{}.json", date))
+}
+
+fn load_file(dir: &Path, date: &str) -> Result<Option<DayRecord>, String> {
+    let Ok(bytes) = std::fs::read(day_file(dir, date)) else {
+        return Ok(None);
+    };
+    serde_json::from_slice(&bytes).map(Some).map_err(|e| e.to_string())
+}
+
+/// Write via temp file + rename, creating the directory on first use.
+fn save_file(dir: &Path, record: &DayRecord) -> Result<(), String> {
+    std::fs::create_dir_all(dir).map_err(|e| format!("create {}: {}", dir.display(), e))?;
+    let json = serde_json::to_vec(record).map_err(|e| format!("serialize: {}", e))?;
+    let path = day_file(dir, &record.date);
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, json).map_err(|e| format!("write {}: {}", tmp.display(), e))?;
+    std::fs::rename(&tmp, &path).map_err(|e| format!("rename {}: {}", path.display(), e))
+}
+
+fn prune_files(dir: &Path, cutoff: NaiveDate) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let name = entry.file_name();
+        let Some(stem) = name.to_str().and_then(|n| n.strip_suffix(".json")) else {
+            continue;
+        };
+        let Ok(date) = NaiveDate::parse_from_str(stem, DATE_FORMAT) else {
+            continue;
+        };
+        if date < cutoff {
+            if let Err(e) = std::fs::remove_file(entry.path()) {
+                tracing::warn!("[ANALYTICS] Failed to prune {}: {}", stem, e);
+            }
+        }
+    }
+}
+
+/// `analytics_days` holds each day's totals as JSON; `arrivals` one row
+/// per train so exports can be queried directly.
+#[cfg(feature = "persistence")]
+mod sqlite {
+    use chrono::NaiveDate;
+    use rusqlite::{params, OptionalExtension};
+
+    use super::{ArrivalRecord, DayRecord, DATE_FORMAT};
+    use crate::models::Direction;
+    use crate::persistence::Database;
+
+    fn direction_name(direction: Direction) -> &'static str {
+        match direction {
+            Direction::Uptown => "Uptown",
+            Direction::Downtown => "Downtown",
+        }
+    }
+
+    pub fn load(db: &Database, date: &str) -> Result<Option<DayRecord>, String> {
+        let totals: Option<(String, String)> = db.with(|conn| {
+            conn.query_row(
+                "SELECT accuracy, service FROM analytics_days WHERE date = ?1",
+                [date],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
+        })?;
+        let Some((accuracy, service)) = totals else {
+            return Ok(None);
+        };
+        let arrivals = db.with(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT route, direction, stop_id, arrived_at FROM arrivals
+                 WHERE date = ?1 ORDER BY arrived_at",
+            )?;
+            let rows = stmt.query_map([date], |row| {
+                let direction: String = row.get(1)?;
+                Ok(ArrivalRecord {
+                    route: row.get(0)?,
+                    direction: if direction == "Downtown" {
+                        Direction::Downtown
+                    } else {
+                        Direction::Uptown
+                    },
+                    stop_id: row.get(2)?,
+                    arrived_at: row.get(3)?,
+                })
+            })?;
+            rows.collect::<rusqlite::Result<Vec<_>>>()
+        })?;
+        Ok(Some(DayRecord {
+            date: date.to_string(),
+            accuracy: serde_json::from_str(&accuracy).map_err(|e| e.to_string())?,
+            service: serde_json::from_str(&service).map_err(|e| e.to_string())?,
+            arrivals,
+        }))
+    }
+
+    pub fn save(db: &Database, record: &DayRecord) -> Result<(), String> {
+        let accuracy = serde_json::to_string(&record.accuracy).map_err(|e| e.to_string())?;
+        let service = serde_json::to_string(&record.service).map_err(|e| e.to_string())?;
+        db.with(|conn| {
+            let tx = conn.transaction()?;
+            tx.execute(
+                "INSERT OR REPLACE INTO analytics_days (date, accuracy, service)
+                 VALUES (?1, ?2, ?3)",
+                params![record.date, accuracy, service],
+            )?;
+            tx.execute("DELETE FROM arrivals WHERE date = ?1", [&record.date])?;
+            {
+                let mut insert = tx.prepare(
+                    "INSERT INTO arrivals (date, route, direction, stop_id, arrived_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5)",
+                )?;
+                for a in &record.arrivals {
+                    insert.execute(params![
+                        record.date,
+                        a.route,
+                        direction_name(a.direction),
+                        a.stop_id,
+                        a.arrived_at
+                    ])?;
+                }
+            }
+            tx.commit()
+        })
+    }
+
+    pub fn prune(db: &Database, cutoff: NaiveDate) -> Result<(), String> {
+        let cutoff = cutoff.format(DATE_FORMAT).to_string();
+        db.with(|conn| {
+            let tx = conn.transaction()?;
+            tx.execute("DELETE FROM analytics_days WHERE date < ?1", [&cutoff])?;
+            tx.execute("DELETE FROM arrivals WHERE date < ?1", [&cutoff])?;
+            tx.commit()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, DATE_FORMAT).unwrap()
+    }
+
+    /// Same round trip for every backend.
+    fn check_save_load_range_and_prune(store: Store) {
+        assert!(store.load(date("2026-03-01")).is_none());
+
+        for day in ["2026-03-01", "2026-03-03", "2026-03-10"] {
+            let mut record = DayRecord::new(date(day));
+            record.accuracy.arrivals = 5;
+            store.save(&record).unwrap();
+        }
+        let mut record = DayRecord::new(date("2026-03-03"));
+        record.accuracy.arrivals = 5;
+        record.arrivals.push(ArrivalRecord {
+            route: "Q".into(),
+            direction: Direction::Downtown,
+            stop_id: "R17S".into(),
+            arrived_at: 1_772_500_000.5,
+        });
+        store.save(&record).unwrap();
+        let loaded = store.load(date("2026-03-03")).unwrap();
+        assert_eq!(loaded, record, "save replaces the day");
+
+        let range = store.load_range(date("2026-03-01"), date("2026-03-05"));
+        let dates: Vec<&str> = range.iter().map(|r| r.date.as_str()).collect();
+        assert_eq!(dates, vec!["2026-03-01", "2026-03-03"]);
+
+        let csv = arrivals_csv(&range, Timezone::System);
+        assert_eq!(csv.lines().count(), 2);
+
+        store.prune(date("2026-03-10"), 7);
+        assert!(store.load(date("2026-03-01")).is_none());
+        assert!(store.load(date("2026-03-03")).is_some());
+    }
+
+    #[test]
+    fn test_file_store() {
+        let dir = tempfile::tempdir().unwrap();
+        check_save_load_range_and_prune(Store::Files(dir.path().join("analytics")));
+    }
+
+    #[cfg(feature = "persistence")]
+    #[test]
+    fn test_sqlite_store() {
+        let db = Database::open_in_memory().unwrap();
+        check_save_load_range_and_prune(Store::Sqlite(Arc::new(db)));
+    }
+
+    #[test]
+    fn test_arrivals_csv() {
+        let mut record = DayRecord::new(date("2026-03-01"));
+        record.arrivals.push(ArrivalRecord {
+            route: "Q".into(),
+            direction: Direction::Uptown,
+            stop_id: "R17N".into(),
+            arrived_at: 1_772_370_000.0,
+        });
+        let csv = arrivals_csv(&[record], Timezone::System);
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some(CSV_HEADER.trim_end()));
+        let row: Vec<&str> = lines.next().unwrap().split(',').collect();
+        assert_eq!(row[0], "1772370000");
+        assert_eq!(&row[2..], ["Q", "Uptown", "R17N"]);
+        assert_eq!(lines.next(), None);
+    }
+}
@@ -0,0 +1,150 @@
+//! GPIO push buttons (`buttons` in config, read at startup).
+//!
+//! Each configured pin is polled on a dedicated thread and debounced; a
+//! press runs the button's action.
+
+use std::sync::Arc;
+
+use tracing::warn;
+
+use crate::config::ButtonConfig;
+use crate::AppState;
+
+/// Consecutive identical samples before a level change counts.
+#[cfg(any(feature = "gpio", test))]
+const DEBOUNCE_SAMPLES: u8 = 3;
+
+/// Run a button's action.
+#[cfg(feature = "gpio")]
+fn run_action(state: &AppState, action: crate::config::ButtonAction) {
+    match action {
+        crate::config::ButtonAction::ShowIp => {
+            crate::netinfo::show_ip(state);
+        }
+        crate::config::ButtonAction::DoNotDisturb => {
+            crate::set_do_not_disturb(state, None);
+        }
+    }
+}
+
+/// Debounces one button from periodic samples of its pressed state.
+#[cfg(any(feature = "gpio", test))]
+#[derive(Debug, Default)]
+struct Debouncer {
+    pressed: bool,
+    /// Samples in a row that disagree with `pressed`.
+    pending: u8,
+}
+
+#[cfg(any(feature = "gpio", test))]
+impl Debouncer {
+    /// Feed one sample; true on the sample that completes a press.
+    fn sample(&mut self, pressed: bool) -> bool {
+        if pressed == self.pressed {
+            self.pending = 0;
+            return false;
+        }
+        self.pending += 1;
+        if self.pending < DEBOUNCE_SAMPLES {
+            return false;
+        }
+        self.pending = 0;
+        self.pressed = pressed;
+        pressed
+    }
+}
+
+/// Start polling the configured buttons; `None` if there are none (or this
+/// build can't read GPIO).
+pub fn spawn(state: Arc<AppState>) -> Option<std::thread::JoinHandle<()>> {
+    let buttons = state.config.load().buttons.clone();
+    if buttons.is_empty() {
+        return None;
+    }
+    spawn_poller(state, buttons)
+}
+
+#[cfg(not(feature = "gpio"))]
+fn spawn_poller(_state: Arc<AppState>, buttons: Vec<ButtonConfig>) -> Option<std::thread::JoinHandle<()>> {
+    warn!(
+        "[GPIO] {} button(s) configured, but this build lacks the `gpio` feature",
+        buttons.len()
+    );
+    None
+}
+
+#[cfg(feature = "gpio")]
+fn spawn_poller(state: Arc<AppState>, buttons: Vec<ButtonConfig>) -> Option<std::thread::JoinHandle<()>> {
+    use gpio_cdev::{Chip, LineRequestFlags};
+    use tracing::info;
+
+    const CHIP: &str = "/dev/gpiochip0";
+    const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(15);
+
+    let mut chip = match Chip::new(CHIP) {
+        Ok(chip) => chip,
+        Err(e) => {
+            warn!("[GPIO] Cannot open {}: {}", CHIP, e);
+            return None;
+        }
+    };
+    let mut lines = Vec::new();
+    for button in buttons {
+        let handle = chip
+            .get_line(button.pin)
+            .and_then(|line| line.request(LineRequestFlags::INPUT, 0, "subway-sign"));
+        match handle {
+            Ok(handle) => {
+                info!("[GPIO] Button on pin {}: {:?}", button.pin, button.action);
+                lines.push((handle, button, Debouncer::default()));
+            }
+            Err(e) => warn!("[GPIO] Cannot request pin {}: {}", button.pin, e),
+        }
+    }
+    if lines.is_empty() {
+        return None;
+    }
+
+    let spawned = std::thread::Builder::new()
+        .name("buttons".into())
+        .spawn(move || {
+            while !state.shutdown.is_cancelled() {
+                for (handle, button, debouncer) in lines.iter_mut() {
+                    let Ok(level) = handle.get_value() else {
+                        continue;
+                    };
+                    if debouncer.sample((level == 0) == button.active_low) {
+                        info!("[GPIO] Pin {} pressed", button.pin);
+                        run_action(&state, button.action);
+                    }
+                }
+                std::thread::sleep(POLL_INTERVAL);
+            }
+        });
+    match spawned {
+        Ok(handle) => Some(handle),
+        Err(e) => {
+            warn!("[GPIO] Failed to spawn button thread: {}", e);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_debouncer_ignores_bounce() {
+        let mut d = Debouncer::default();
+        let presses: Vec<bool> = [true, false, true, true, true, true, false, true, false, false, false]
+            .into_iter()
+            .map(|s| d.sample(s))
+            .collect();
+        // One press, reported on the third steady sample; the release and
+        // the bounces around it report nothing
+        assert_eq!(presses.iter().filter(|&&p| p).count(), 1);
+        assert!(presses[4]);
+        assert!(!d.pressed);
+    }
+}
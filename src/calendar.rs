@@ -0,0 +1,240 @@
+//! Away days from an iCal feed (`calendar` in the config), e.g. a shared
+//! vacation calendar. On a day covered by a matching event the sign stays
+//! dark all day, as if in `display.off_hours` (see `display_off`), or at
+//! `away_brightness` if that is set.
+//!
+//! Only what that needs is parsed: each VEVENT's SUMMARY, DTSTART and
+//! DTEND. Recurrence rules are ignored.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use chrono::{NaiveDate, NaiveDateTime};
+use tracing::{info, warn};
+
+use crate::config::CalendarConfig;
+use crate::timezone::Timezone;
+use crate::AppState;
+
+/// Longest event counted, so a stray open-ended event can't darken the sign
+/// for years.
+const MAX_EVENT_DAYS: i64 = 366;
+
+/// How often the task checks for config changes and refresh time.
+const POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Local dates in `tz` covered by events in `ics` whose summary contains
+/// `keyword` (case-insensitive; `None` = every event).
+pub fn away_days(ics: &str, keyword: Option<&str>, tz: Timezone) -> HashSet<NaiveDate> {
+    let keyword = keyword.map(str::to_lowercase);
+    let mut days = HashSet::new();
+    let mut event: Option<Event> = None;
+
+    for line in unfold(ics) {
+        let (name, value) = match line.split_once(':') {
+            Some(parts) => parts,
+            None => continue,
+        };
+        let (property, params) = name.split_once(';').unwrap_or((name, ""));
+        match (property.to_ascii_uppercase().as_str(), event.as_mut()) {
+            ("BEGIN", None) if value.eq_ignore_ascii_case("VEVENT") => event = Some(Event::default()),
+            ("END", Some(_)) if value.eq_ignore_ascii_case("VEVENT") => {
+                let Some(done) = event.take() else { continue };
+                let matches = keyword
+                    .as_deref()
+                    .is_none_or(|k| done.summary.to_lowercase().contains(k));
+                if matches {
+                    days.extend(done.days());
+                }
+            }
+            ("SUMMARY", Some(e)) => e.summary = value.to_string(),
+            ("DTSTART", Some(e)) => e.start = parse_time(params, value, tz),
+            ("DTEND", Some(e)) => e.end = parse_time(params, value, tz),
+            _ => {}
+        }
+    }
+    days
+}
+
+#[derive(Default)]
+struct Event {
+    summary: String,
+    /// Local start, and whether it's a whole day (`VALUE=DATE`).
+    start: Option<(NaiveDateTime, bool)>,
+    end: Option<(NaiveDateTime, bool)>,
+}
+
+impl Event {
+    /// Days the event touches. DTEND is exclusive; without one, the event is
+    /// its start day.
+    fn days(&self) -> Vec<NaiveDate> {
+        let Some((start, _)) = self.start else {
+            return Vec::new();
+        };
+        let last = match self.end {
+            Some((end, true)) => end.date().pred_opt().unwrap_or(end.date()),
+            Some((end, false)) if end > start => (end - chrono::Duration::seconds(1)).date(),
+            _ => start.date(),
+        };
+        start
+            .date()
+            .iter_days()
+            .take_while(|day| *day <= last)
+            .take(MAX_EVENT_DAYS as usize)
+            .collect()
+    }
+}
+
+/// Join folded lines (continuations start with a space or tab).
+fn unfold(ics: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in ics.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.trim_end_matches('\r').to_string()),
+        }
+    }
+    lines
+}
+
+/// A DTSTART/DTEND value as local time in `tz`: a date, UTC ("...Z"), in
+/// a `TZID` zone, or floating (taken as local).
+fn parse_time(params: &str, value: &str, tz: Timezone) -> Option<(NaiveDateTime, bool)> {
+    let value = value.trim();
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y%m%d") {
+        return Some((date.and_time(chrono::NaiveTime::MIN), true));
+    }
+    if let Some(utc) = value.strip_suffix('Z') {
+        let utc = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+        return Some((tz.local(utc.and_utc()), false));
+    }
+    let local = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?;
+    let zone = params
+        .split(';')
+        .find_map(|p| p.strip_prefix("TZID="))
+        .and_then(|name| Timezone::parse(name.trim_matches('"')).ok());
+    match zone {
+        Some(zone) if zone != tz => {
+            let secs = zone.timestamp(local)?;
+            Some((tz.from_timestamp(secs)?, false))
+        }
+        _ => Some((local, false)),
+    }
+}
+
+/// Keep `state.away_days` current from the configured calendar feed:
+/// re-downloaded every `refresh_minutes`, or when the calendar settings
+/// change. A failed download keeps the days already known.
+pub async fn calendar_task(state: Arc<AppState>) {
+    let client = match reqwest::Client::builder()
+        .user_agent("NYC-SubwaySign-Rust/1.0")
+        .timeout(Duration::from_secs(15))
+        .build()
+    {
+        Ok(c) => c,
+        Err(e) => {
+            warn!("[CALENDAR] Failed to create HTTP client: {}", e);
+            return;
+        }
+    };
+    let mut interval = tokio::time::interval(POLL_INTERVAL);
+    let mut loaded: Option<(CalendarConfig, Timezone, Instant)> = None;
+
+    loop {
+        tokio::select! {
+            _ = state.shutdown.cancelled() => {
+                info!("[CALENDAR] Shutting down");
+                break;
+            }
+            _ = interval.tick() => {
+                let config = state.config.load();
+                let Some(calendar) = config.calendar.clone() else {
+                    if loaded.take().is_some() {
+                        state.away_days.store(Arc::new(HashSet::new()));
+                    }
+                    continue;
+                };
+                let tz = config.tz();
+                let refresh = Duration::from_secs(calendar.refresh_minutes as u64 * 60);
+                let current = loaded.as_ref().is_some_and(|(c, z, at)| {
+                    *c == calendar && *z == tz && at.elapsed() < refresh
+                });
+                if current {
+                    continue;
+                }
+
+                match fetch(&client, &calendar.url).await {
+                    Ok(ics) => {
+                        let days = away_days(&ics, calendar.keyword.as_deref(), tz);
+                        info!("[CALENDAR] Loaded {} away day(s) from {}", days.len(), calendar.url);
+                        state.away_days.store(Arc::new(days));
+                    }
+                    Err(e) => warn!("[CALENDAR] Fetch from {} failed: {}", calendar.url, e),
+                }
+                // Retried at the next refresh either way
+                loaded = Some((calendar, tz, Instant::now()));
+            }
+        }
+    }
+}
+
+async fn fetch(client: &reqwest::Client, url: &str) -> Result<String, String> {
+    // webcal:// is plain HTTPS
+    let url = match url.strip_prefix("webcal://") {
+        Some(rest) => format!("https://{}", rest),
+        None => url.to_string(),
+    };
+    let response = client.get(&url).send().await.map_err(|e| format!("HTTP error: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("HTTP {}", response.status().as_u16()));
+    }
+    response.text().await.map_err(|e| format!("Read error: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ICS: &str = "BEGIN:VCALENDAR\r
+VERSION:2.0\r
+BEGIN:VEVENT\r
+SUMMARY:Vacation in\r
+  Maine\r
+DTSTART;VALUE=DATE:20261020\r
+DTEND;VALUE=DATE:20261023\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+SUMMARY:Dentist\r
+DTSTART:20261026T140000Z\r
+DTEND:20261026T150000Z\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+SUMMARY:Vacation flight\r
+DTSTART;TZID=America/Los_Angeles:20261030T230000\r
+DTEND;TZID=America/Los_Angeles:20261031T070000\r
+END:VEVENT\r
+END:VCALENDAR\r
+";
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_away_days() {
+        let tz = Timezone::parse("America/New_York").unwrap();
+        let days = away_days(ICS, Some("vacation"), tz);
+        let mut sorted: Vec<NaiveDate> = days.into_iter().collect();
+        sorted.sort();
+        assert_eq!(
+            sorted,
+            [date("2026-10-20"), date("2026-10-21"), date("2026-10-22"), date("2026-10-31")],
+            "all-day end is exclusive; the LA flight is all on the 31st in New York"
+        );
+
+        let all = away_days(ICS, None, tz);
+        assert!(all.contains(&date("2026-10-26")));
+        assert_eq!(all.len(), 5);
+    }
+}
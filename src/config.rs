@@ -1,10 +1,15 @@
+use std::collections::BTreeMap;
 use std::io::Write;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-use crate::models::{stop_ids_to_station_stops, StationStop};
+use crate::display::fonts::FontStyle;
+use crate::display::post::{Highlight, PostStep};
+use crate::models::{stop_ids_to_station_stops, Alert, Direction, StationStop};
 use crate::mta::stations;
+use crate::selection::{ServiceFilter, TopRowRule};
+use crate::timezone::Timezone;
 
 /// Atomically write config: write to .tmp, sync, backup existing to .bak, rename .tmp to primary.
 pub fn atomic_write_config(path: &Path, json: &str) -> Result<(), ConfigError> {
@@ -31,65 +36,1374 @@ pub fn atomic_write_config(path: &Path, json: &str) -> Result<(), ConfigError> {
     Ok(())
 }
 
+/// Save a config edit: snapshot the current file into its history (see
+/// `snapshot_config`), then write atomically.
+pub fn save_config(path: &Path, json: &str) -> Result<(), ConfigError> {
+    if let Err(e) = snapshot_config(path) {
+        tracing::warn!("Config history snapshot failed: {}", e);
+    }
+    atomic_write_config(path, json)
+}
+
+/// Config snapshots kept in the history directory.
+pub const HISTORY_KEEP: usize = 10;
+
+/// Where prior versions of `path` are kept (`config.history/` next to `config.json`).
+pub fn history_dir(path: &Path) -> PathBuf {
+    path.with_extension("history")
+}
+
+/// Copy the config at `path` into its history directory before it's
+/// overwritten, dropping the oldest snapshots past `HISTORY_KEEP`.
+pub fn snapshot_config(path: &Path) -> Result<(), ConfigError> {
+    if !path.exists() {
+        return Ok(());
+    }
+    let dir = history_dir(path);
+    std::fs::create_dir_all(&dir).map_err(|e| ConfigError::Io(format!("create history dir: {}", e)))?;
+    let millis = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    // Keep names unique (and ordered) when two saves land in the same millisecond
+    let newest = config_history(path).first().and_then(|p| snapshot_millis(p)).unwrap_or(0);
+    let snapshot = dir.join(format!("{}.json", millis.max(newest + 1)));
+    std::fs::copy(path, &snapshot).map_err(|e| ConfigError::Io(format!("snapshot config: {}", e)))?;
+    for old in config_history(path).into_iter().skip(HISTORY_KEEP) {
+        let _ = std::fs::remove_file(old);
+    }
+    Ok(())
+}
+
+/// Snapshots of `path`, newest first.
+pub fn config_history(path: &Path) -> Vec<PathBuf> {
+    let mut snapshots: Vec<(u128, PathBuf)> = std::fs::read_dir(history_dir(path))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter_map(|p| snapshot_millis(&p).map(|millis| (millis, p)))
+        .collect();
+    snapshots.sort_by_key(|(millis, _)| std::cmp::Reverse(*millis));
+    snapshots.into_iter().map(|(_, p)| p).collect()
+}
+
+fn snapshot_millis(path: &Path) -> Option<u128> {
+    if path.extension()? != "json" {
+        return None;
+    }
+    path.file_stem()?.to_str()?.parse().ok()
+}
+
+/// One field that differs between two config documents.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldChange {
+    /// Dotted path, with array indices: `station.stations[0].uptown`.
+    pub path: String,
+    /// "added", "removed" or "changed".
+    pub change: &'static str,
+    pub from: Option<serde_json::Value>,
+    pub to: Option<serde_json::Value>,
+}
+
+/// Field-level differences from `from` to `to`: removed and changed keys
+/// first, then added ones, at each level.
+pub fn diff_json(from: &serde_json::Value, to: &serde_json::Value) -> Vec<FieldChange> {
+    let mut changes = Vec::new();
+    diff_into(String::new(), Some(from), Some(to), &mut changes);
+    changes
+}
+
+/// Secret fields (section, key) of the config JSON, never sent back to the
+/// browser or to fleet members.
+/// The calendar URL counts: private iCal links work without a login.
+const SECRET_FIELDS: [(&str, &str); 3] =
+    [("web", "upload_token"), ("setup", "password"), ("calendar", "url")];
+
+/// Replace each secret in `json` with `"<key>_set": true/false`.
+pub fn redact_secrets(json: &mut serde_json::Value) {
+    for (section, key) in SECRET_FIELDS {
+        if let Some(serde_json::Value::Object(fields)) = json.get_mut(section) {
+            let set = fields.remove(key).is_some_and(|v| !v.is_null() && v != "");
+            fields.insert(format!("{}_set", key), serde_json::Value::Bool(set));
+        }
+    }
+}
+
+/// Undo `redact_secrets` on an edited config: secrets `edited` leaves out
+/// keep their value from `current`, and the `_set` flags are dropped.
+pub fn restore_secrets(edited: &mut serde_json::Value, current: &serde_json::Value) {
+    for (section, key) in SECRET_FIELDS {
+        let Some(serde_json::Value::Object(fields)) = edited.get_mut(section) else {
+            continue;
+        };
+        fields.remove(&format!("{}_set", key));
+        if !fields.contains_key(key) {
+            if let Some(value) = current.get(section).and_then(|s| s.get(key)) {
+                fields.insert(key.to_string(), value.clone());
+            }
+        }
+    }
+}
+
+/// Fields (section, key) only the config file can set: where the daemon
+/// downloads from and writes to, and the upload token guarding the latter.
+const FILE_ONLY_FIELDS: [(&str, &str); 5] = [
+    ("static_gtfs", "url"),
+    ("static_gtfs", "cache_path"),
+    ("web", "upload_token"),
+    ("web", "assets_dir"),
+    ("screenshots", "dir"),
+];
+
+/// Undo web edits to file-only fields: each keeps its value from `current`
+/// (or is left unset if `current` has none).
+pub fn keep_file_only(edited: &mut serde_json::Value, current: &serde_json::Value) {
+    for (section, key) in FILE_ONLY_FIELDS {
+        let Some(serde_json::Value::Object(fields)) = edited.get_mut(section) else {
+            continue;
+        };
+        match current.get(section).and_then(|s| s.get(key)) {
+            Some(value) => fields.insert(key.to_string(), value.clone()),
+            None => fields.remove(key),
+        };
+    }
+}
+
+fn diff_into(
+    path: String,
+    from: Option<&serde_json::Value>,
+    to: Option<&serde_json::Value>,
+    changes: &mut Vec<FieldChange>,
+) {
+    use serde_json::Value;
+    let child = |key: &str| if path.is_empty() { key.to_string() } else { format!("{}.{}", path, key) };
+    match (from, to) {
+        (Some(Value::Object(a)), Some(Value::Object(b))) => {
+            for (key, value) in a {
+                diff_into(child(key), Some(value), b.get(key), changes);
+            }
+            for (key, value) in b.iter().filter(|(key, _)| !a.contains_key(*key)) {
+                diff_into(child(key), None, Some(value), changes);
+            }
+        }
+        (Some(Value::Array(a)), Some(Value::Array(b))) => {
+            for i in 0..a.len().max(b.len()) {
+                diff_into(format!("{}[{}]", path, i), a.get(i), b.get(i), changes);
+            }
+        }
+        (Some(a), Some(b)) if a == b => {}
+        (from, to) => changes.push(FieldChange {
+            path,
+            change: match (from, to) {
+                (None, _) => "added",
+                (_, None) => "removed",
+                _ => "changed",
+            },
+            from: from.cloned(),
+            to: to.cloned(),
+        }),
+    }
+}
+
 /// Top-level configuration file structure.
 #[derive(Debug, Deserialize)]
 struct RawConfig {
     station: RawStationConfig,
     display: DisplayConfig,
     #[serde(default)]
-    refresh: RefreshConfig,
+    refresh: RefreshConfig,
+    #[serde(default)]
+    screenshots: ScreenshotConfig,
+    #[serde(default)]
+    fleet: FleetConfig,
+    #[serde(default)]
+    panel: PanelConfig,
+    #[serde(default)]
+    icons: IconConfig,
+    #[serde(default)]
+    analytics: AnalyticsConfig,
+    #[serde(default)]
+    database: DatabaseConfig,
+    #[serde(default)]
+    journey: JourneyConfig,
+    #[serde(default)]
+    trip: Option<TripConfig>,
+    #[serde(default)]
+    alternates: AlternatesConfig,
+    #[serde(default)]
+    web: WebConfig,
+    #[serde(default)]
+    setup: SetupConfig,
+    #[serde(default)]
+    buttons: Vec<ButtonConfig>,
+    #[serde(default)]
+    route_schedule: Vec<RouteWindow>,
+    #[serde(default)]
+    static_gtfs: StaticGtfsConfig,
+    #[serde(default)]
+    calendar: Option<CalendarConfig>,
+    #[serde(default)]
+    timezone: Option<String>,
+    #[serde(default)]
+    stations_path: Option<PathBuf>,
+}
+
+/// Raw station section — supports all 3 formats via Option fields.
+#[derive(Debug, Deserialize)]
+struct RawStationConfig {
+    station_name: Option<String>,
+    routes: Option<Vec<String>>,
+    stations: Option<Vec<RawStationPair>>,
+    uptown_stop_id: Option<String>,
+    downtown_stop_id: Option<String>,
+}
+
+/// Explicit platform pair (format 2).
+#[derive(Debug, Deserialize)]
+struct RawStationPair {
+    uptown: String,
+    downtown: String,
+}
+
+/// Display settings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DisplayConfig {
+    pub brightness: f64,
+    pub max_trains: u32,
+    pub show_alerts: bool,
+    /// Which trains go on which row.
+    #[serde(default)]
+    pub layout: Layout,
+    /// Pages the sign cycles through, each for its `seconds` (empty =
+    /// trains only).
+    #[serde(default)]
+    pub pages: Vec<Page>,
+    /// What the right edge of each train row shows.
+    #[serde(default)]
+    pub arrival_format: ArrivalFormat,
+    /// With `ArrivalFormat::Hybrid`: trains further out than this many
+    /// minutes show their clock time instead of a countdown.
+    #[serde(default = "default_clock_after_minutes")]
+    pub clock_after_minutes: u32,
+    /// Text for trains arriving within the minute.
+    #[serde(default)]
+    pub arriving_text: ArrivingText,
+    /// Language of on-display strings (see `display::i18n::SUPPORTED_LOCALES`).
+    #[serde(default = "default_locale")]
+    pub locale: String,
+    /// Per-string overrides, e.g. `{"due": "Now"}` (keys in `display::i18n::KEYS`).
+    #[serde(default)]
+    pub strings: BTreeMap<String, String>,
+    /// Time-of-day brightness levels, each holding until the next (wrapping
+    /// past midnight). Replaces `brightness` when set.
+    #[serde(default)]
+    pub schedule: Vec<BrightnessLevel>,
+    /// Daily window with the panel blank and fetches paused.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub off_hours: Option<OffHours>,
+    /// Fade time for brightness changes in milliseconds (0 = instant).
+    #[serde(default = "default_brightness_ramp_ms")]
+    pub brightness_ramp_ms: u64,
+    /// Font style per layout element.
+    #[serde(default)]
+    pub fonts: FontStyles,
+    /// Alert scroll speed in pixels per second.
+    #[serde(default = "default_scroll_speed")]
+    pub scroll_speed: f32,
+    /// Blend neighbouring columns at fractional scroll positions, so speeds
+    /// that aren't a whole number of pixels per frame don't judder.
+    #[serde(default = "default_smooth_scroll")]
+    pub smooth_scroll: bool,
+    /// Slide time in milliseconds when the bottom row moves to the next
+    /// train (0 = instant).
+    #[serde(default = "default_cycle_transition_ms")]
+    pub cycle_transition_ms: u64,
+    /// How many times alerts scroll past.
+    #[serde(default)]
+    pub alert_scroll: AlertScrollConfig,
+    /// Scroll all alerts as one ticker on a timer instead.
+    #[serde(default)]
+    pub alert_ticker: AlertTickerConfig,
+    /// After an alert with a details link scrolls, show a QR code for it
+    /// this many seconds (0 = off).
+    #[serde(default)]
+    pub alert_qr_seconds: u32,
+    /// Which train the top row pins to, as ordered preferences
+    /// (empty = soonest overall).
+    #[serde(default)]
+    pub top_row: Vec<TopRowRule>,
+    /// Post-processing steps applied in order before frames reach the panel.
+    #[serde(default)]
+    pub post: Vec<PostStep>,
+    /// Draw route names written as plain text in alerts ("A and C trains")
+    /// as bullets.
+    #[serde(default = "default_alert_route_icons")]
+    pub alert_route_icons: bool,
+    /// Mark trains running at least this many minutes late (0 = off).
+    #[serde(default = "default_delay_marker_minutes")]
+    pub delay_marker_minutes: u32,
+    /// Show a large clock and the date while there are no trains.
+    #[serde(default)]
+    pub idle_clock: bool,
+    /// Draw row text in the route's color instead of green (arriving
+    /// trains stay red).
+    #[serde(default)]
+    pub color_by_route: bool,
+    /// Show only express or only local trains of these routes, e.g.
+    /// `{"7": "express"}`; other routes show both.
+    #[serde(default)]
+    pub route_service: BTreeMap<String, ServiceFilter>,
+    /// Cycle the trains at each platform together (stations with several
+    /// platform pairs), rather than in plain arrival order.
+    #[serde(default)]
+    pub group_by_platform: bool,
+    /// Scroll destinations too long for their row back and forth instead
+    /// of truncating them.
+    #[serde(default)]
+    pub marquee: bool,
+    /// Cycling row and alerts on top, the next train at the bottom, for
+    /// signs hung above eye level.
+    #[serde(default)]
+    pub flip_rows: bool,
+    /// Flag one route and direction's train visually as it gets close.
+    #[serde(default)]
+    pub watch: Option<Watch>,
+}
+
+fn default_clock_after_minutes() -> u32 {
+    20
+}
+fn default_locale() -> String {
+    "en".to_string()
+}
+fn default_brightness_ramp_ms() -> u64 {
+    1500
+}
+fn default_scroll_speed() -> f32 {
+    60.0
+}
+fn default_smooth_scroll() -> bool {
+    true
+}
+fn default_cycle_transition_ms() -> u64 {
+    170
+}
+fn default_alert_route_icons() -> bool {
+    true
+}
+fn default_delay_marker_minutes() -> u32 {
+    5
+}
+
+/// `display.watch`, e.g. `{"route": "Q", "direction": "downtown",
+/// "minutes": 4}`: when the next such train comes within `minutes`, the
+/// panel shows `effect` for `seconds`, once per train.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Watch {
+    pub route: String,
+    pub direction: Direction,
+    #[serde(default = "default_watch_minutes")]
+    pub minutes: u32,
+    #[serde(default)]
+    pub effect: Highlight,
+    #[serde(default = "default_watch_seconds")]
+    pub seconds: u32,
+}
+
+fn default_watch_minutes() -> u32 {
+    5
+}
+fn default_watch_seconds() -> u32 {
+    10
+}
+
+/// `display.off_hours`, e.g. `{"from": "01:00", "to": "05:30"}` ("HH:MM"
+/// local time; may run past midnight).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OffHours {
+    pub from: String,
+    pub to: String,
+}
+
+/// A `display.schedule` entry, e.g. `{"time": "22:00", "brightness": 0.1}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BrightnessLevel {
+    /// "HH:MM" local time the level starts.
+    pub time: String,
+    /// 0.0-1.0, like `display.brightness`.
+    pub brightness: f64,
+}
+
+impl DisplayConfig {
+    /// Whether local time `now` falls in `off_hours`.
+    pub fn is_off_at(&self, now: chrono::NaiveTime) -> bool {
+        let Some(off) = &self.off_hours else {
+            return false;
+        };
+        match (parse_hhmm(&off.from), parse_hhmm(&off.to)) {
+            (Some(from), Some(to)) if from < to => now >= from && now < to,
+            (Some(from), Some(to)) => now >= from || now < to,
+            _ => false,
+        }
+    }
+
+    /// Brightness at local time `now`: the latest schedule entry at or
+    /// before it (the day's last entry before the first), else `brightness`.
+    pub fn brightness_at(&self, now: chrono::NaiveTime) -> f64 {
+        let levels = || {
+            self.schedule
+                .iter()
+                .filter_map(|level| parse_hhmm(&level.time).map(|time| (time, level.brightness)))
+        };
+        levels()
+            .filter(|(time, _)| *time <= now)
+            .max_by_key(|(time, _)| *time)
+            .or_else(|| levels().max_by_key(|(time, _)| *time))
+            .map_or(self.brightness, |(_, brightness)| brightness)
+    }
+}
+
+/// Font style for each layout element.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FontStyles {
+    #[serde(default)]
+    pub destination: FontStyle,
+    /// Tried before truncating a destination that doesn't fit, e.g.
+    /// `condensed` (same as `destination` = truncate straight away).
+    #[serde(default)]
+    pub destination_overflow: FontStyle,
+    #[serde(default)]
+    pub arrival: FontStyle,
+    #[serde(default = "default_alert_style")]
+    pub alert: FontStyle,
+    /// Alerts with priority `critical_priority` or lower (1 = no service).
+    #[serde(default = "default_alert_style")]
+    pub critical_alert: FontStyle,
+    #[serde(default = "default_critical_priority")]
+    pub critical_priority: i32,
+}
+
+fn default_alert_style() -> FontStyle {
+    FontStyle::Italic
+}
+fn default_critical_priority() -> i32 {
+    2
+}
+
+impl Default for FontStyles {
+    fn default() -> Self {
+        FontStyles {
+            destination: FontStyle::Regular,
+            destination_overflow: FontStyle::Regular,
+            arrival: FontStyle::Regular,
+            alert: default_alert_style(),
+            critical_alert: default_alert_style(),
+            critical_priority: default_critical_priority(),
+        }
+    }
+}
+
+/// How many times an alert scrolls past.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScrollMode {
+    /// A single pass.
+    #[default]
+    Once,
+    /// `loops` passes.
+    Loop,
+    /// Repeat while the train that triggered the alerts is still at the
+    /// platform (still capped by the alert cycle time limit).
+    UntilDeparted,
+}
+
+/// Alert scroll repetition: a global mode plus per-alert rules.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AlertScrollConfig {
+    #[serde(default)]
+    pub mode: ScrollMode,
+    /// Passes for `loop` mode.
+    #[serde(default = "default_scroll_loops")]
+    pub loops: u32,
+    /// Checked in order; the first rule matching an alert decides its mode.
+    #[serde(default)]
+    pub rules: Vec<AlertScrollRule>,
+}
+
+/// Per-alert scroll override. Every criterion given must match.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AlertScrollRule {
+    /// Alert text at least this many characters.
+    #[serde(default)]
+    pub min_length: Option<usize>,
+    /// Alert text at most this many characters.
+    #[serde(default)]
+    pub max_length: Option<usize>,
+    /// Priority at or below this (1 = no service).
+    #[serde(default)]
+    pub max_priority: Option<i32>,
+    /// Alert affects any of these routes.
+    #[serde(default)]
+    pub routes: Vec<String>,
+    pub mode: ScrollMode,
+    /// Passes for `loop` mode (defaults to the global `loops`).
+    #[serde(default)]
+    pub loops: Option<u32>,
+}
+
+fn default_scroll_loops() -> u32 {
+    2
+}
+
+impl Default for AlertScrollConfig {
+    fn default() -> Self {
+        AlertScrollConfig {
+            mode: ScrollMode::Once,
+            loops: default_scroll_loops(),
+            rules: Vec::new(),
+        }
+    }
+}
+
+/// Ticker mode: every queued alert joined into one scroll, repeated on a
+/// timer rather than once per arriving train.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AlertTickerConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Pause between the end of one pass and the next (0 = continuous).
+    #[serde(default = "default_ticker_interval")]
+    pub interval_seconds: u32,
+}
+
+fn default_ticker_interval() -> u32 {
+    60
+}
+
+impl Default for AlertTickerConfig {
+    fn default() -> Self {
+        AlertTickerConfig {
+            enabled: false,
+            interval_seconds: default_ticker_interval(),
+        }
+    }
+}
+
+/// Number of scroll passes decided for one alert.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScrollPasses {
+    Count(u32),
+    UntilDeparted,
+}
+
+impl AlertScrollRule {
+    fn matches(&self, alert: &Alert) -> bool {
+        let len = alert.text.chars().count();
+        self.min_length.is_none_or(|min| len >= min)
+            && self.max_length.is_none_or(|max| len <= max)
+            && self.max_priority.is_none_or(|p| alert.priority <= p)
+            && (self.routes.is_empty()
+                || self.routes.iter().any(|r| alert.affected_routes.contains(r)))
+    }
+}
+
+impl AlertScrollConfig {
+    /// Scroll passes for `alert`: the first matching rule, else the global mode.
+    pub fn passes_for(&self, alert: &Alert) -> ScrollPasses {
+        let (mode, loops) = match self.rules.iter().find(|rule| rule.matches(alert)) {
+            Some(rule) => (rule.mode, rule.loops.unwrap_or(self.loops)),
+            None => (self.mode, self.loops),
+        };
+        match mode {
+            ScrollMode::Once => ScrollPasses::Count(1),
+            ScrollMode::Loop => ScrollPasses::Count(loops.max(1)),
+            ScrollMode::UntilDeparted => ScrollPasses::UntilDeparted,
+        }
+    }
+}
+
+/// How trains are split across the two rows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Layout {
+    /// Pinned train on top (see `top_row`), the rest cycling below.
+    #[default]
+    Standard,
+    /// Next uptown train on top, next downtown train below.
+    ByDirection,
+    /// Every row a train, in a small font (four on a 32px panel).
+    List,
+    /// Departure board: headed columns of time, route, destination and
+    /// track, flipping as they change (best on 64px panels).
+    Board,
+}
+
+/// A `display.pages` entry, e.g. `{"page": "clock", "seconds": 5}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Page {
+    #[serde(flatten)]
+    pub kind: PageKind,
+    #[serde(default = "default_page_seconds")]
+    pub seconds: u32,
+}
+
+fn default_page_seconds() -> u32 {
+    10
+}
+
+/// What a page shows.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "page", rename_all = "snake_case")]
+pub enum PageKind {
+    /// Arrivals, per `layout`.
+    Trains,
+    /// A line per active alert: affected routes and what's wrong.
+    Alerts,
+    /// The large clock and date.
+    Clock,
+    /// One or two fixed lines of text.
+    Message { lines: Vec<String> },
+}
+
+/// Right-edge arrival text for a train row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ArrivalFormat {
+    /// Countdown, e.g. "5min".
+    #[default]
+    Minutes,
+    /// Arrival clock time, e.g. "8:42".
+    Clock,
+    /// Countdown and clock time, alternating within each row cycle.
+    Alternate,
+    /// Countdown for near trains, clock time beyond `clock_after_minutes`.
+    Hybrid,
+}
+
+/// How a train arriving within the minute is shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ArrivingText {
+    /// The locale's `due` string, or "0min" where it's empty.
+    #[default]
+    Auto,
+    /// Always "0min" (with the locale's `min` suffix).
+    Minutes,
+    /// The locale's `now` string.
+    Now,
+    /// The locale's `due` string, "Due" where it's empty.
+    Due,
+    /// Seconds to arrival, e.g. "45s".
+    Seconds,
+}
+
+/// Refresh intervals (optional in config file).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RefreshConfig {
+    #[serde(default = "default_trains_interval")]
+    pub trains_interval: u64,
+    #[serde(default = "default_alerts_interval")]
+    pub alerts_interval: u64,
+    /// Arrivals of one route at one platform this many seconds apart or
+    /// less count as one train (overlapping feeds report some twice).
+    #[serde(default = "default_dedup_window_secs")]
+    pub dedup_window_secs: u64,
+    /// Wait before the first fetch at startup.
+    #[serde(default)]
+    pub startup_delay_secs: u64,
+    /// Until a feed first answers, retry trains this often instead of every
+    /// `trains_interval` (0 = no warmup).
+    #[serde(default = "default_warmup_interval_secs")]
+    pub warmup_interval_secs: u64,
+}
+
+fn default_trains_interval() -> u64 {
+    20
+}
+fn default_alerts_interval() -> u64 {
+    60
+}
+fn default_dedup_window_secs() -> u64 {
+    crate::mta::client::DEFAULT_DEDUP_WINDOW_SECS
+}
+fn default_warmup_interval_secs() -> u64 {
+    2
+}
+
+impl Default for RefreshConfig {
+    fn default() -> Self {
+        RefreshConfig {
+            trains_interval: default_trains_interval(),
+            alerts_interval: default_alerts_interval(),
+            dedup_window_secs: default_dedup_window_secs(),
+            startup_delay_secs: 0,
+            warmup_interval_secs: default_warmup_interval_secs(),
+        }
+    }
+}
+
+/// Screenshot capture settings (optional in config file).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScreenshotConfig {
+    /// Directory screenshots are written to (created on first capture).
+    /// Only the config file can change it (see `keep_file_only`).
+    #[serde(default = "default_screenshot_dir")]
+    pub dir: PathBuf,
+    /// Time-lapse capture interval in minutes (0 = off).
+    #[serde(default)]
+    pub timelapse_minutes: u64,
+    /// PNG upscale factor (1 = native 192x32).
+    #[serde(default = "default_screenshot_scale")]
+    pub scale: usize,
+}
+
+fn default_screenshot_dir() -> PathBuf {
+    PathBuf::from("screenshots")
+}
+fn default_screenshot_scale() -> usize {
+    1
+}
+
+impl Default for ScreenshotConfig {
+    fn default() -> Self {
+        ScreenshotConfig {
+            dir: default_screenshot_dir(),
+            timelapse_minutes: 0,
+            scale: default_screenshot_scale(),
+        }
+    }
+}
+
+/// Physical LED canvas layout (optional in config file).
+///
+/// The canvas is `parallel` chains of `chain_length` panels, each `cols` x
+/// `rows` LEDs (default: three 64x32 panels, 192x32). The sign is rendered
+/// at `active_width` x `active_height` (default: the canvas right of and
+/// below the offset) and drawn at (`offset_x`, `offset_y`) — e.g. to center
+/// the sign on a longer chain, or skip a damaged panel column. Offsets and
+/// active area hot-reload. Changing `backend`, `network_target` or the
+/// panel geometry recreates the display between two frames.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PanelConfig {
+    /// Where frames go (see `DisplayBackend`).
+    #[serde(default)]
+    pub backend: DisplayBackend,
+    /// `host:port` receiving UDP frames for the `network` backend.
+    #[serde(default)]
+    pub network_target: Option<String>,
+    /// LED rows per panel (16, 32 or 64).
+    #[serde(default = "default_panel_rows")]
+    pub rows: u32,
+    /// LED columns per panel (32, 64 or 128).
+    #[serde(default = "default_panel_cols")]
+    pub cols: u32,
+    /// Number of chained panels.
+    #[serde(default = "default_chain_length", alias = "chain")]
+    pub chain_length: u32,
+    /// Parallel chains stacked vertically (1-3, Pi GPIO limit).
+    #[serde(default = "default_parallel")]
+    pub parallel: u32,
+    /// rpi-rgb-led-matrix GPIO mapping, e.g. "regular" or "adafruit-hat".
+    #[serde(default = "default_hardware_mapping")]
+    pub hardware_mapping: String,
+    #[serde(default)]
+    pub offset_x: u32,
+    #[serde(default)]
+    pub offset_y: u32,
+    #[serde(default)]
+    pub active_width: Option<usize>,
+    #[serde(default)]
+    pub active_height: Option<usize>,
+}
+
+/// Display backend. Backends not compiled into this build fall back to
+/// `null` with a warning.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DisplayBackend {
+    /// The LED matrix if built with `hardware`, else the simulator window
+    /// if built with `simulator`, else `null`.
+    #[default]
+    Auto,
+    /// LED matrix panels (`hardware` feature).
+    Hardware,
+    /// Desktop window (`simulator` feature).
+    Simulator,
+    /// ANSI half-block rendering on stdout.
+    Terminal,
+    /// Raw frames over UDP to `panel.network_target`.
+    Network,
+    /// Frames go nowhere (web preview only).
+    Null,
+    /// No panel at all, e.g. serving the data API from a server: like
+    /// `null`, but rendering at a couple of frames a second, only to keep
+    /// the web previews current.
+    Headless,
+}
+
+impl DisplayBackend {
+    pub fn name(self) -> &'static str {
+        match self {
+            DisplayBackend::Auto => "auto",
+            DisplayBackend::Hardware => "hardware",
+            DisplayBackend::Simulator => "simulator",
+            DisplayBackend::Terminal => "terminal",
+            DisplayBackend::Network => "network",
+            DisplayBackend::Null => "null",
+            DisplayBackend::Headless => "headless",
+        }
+    }
+}
+
+fn default_panel_rows() -> u32 {
+    32
+}
+fn default_panel_cols() -> u32 {
+    64
+}
+fn default_chain_length() -> u32 {
+    3
+}
+fn default_parallel() -> u32 {
+    1
+}
+fn default_hardware_mapping() -> String {
+    "regular".to_string()
+}
+
+impl Default for PanelConfig {
+    fn default() -> Self {
+        PanelConfig {
+            backend: DisplayBackend::default(),
+            network_target: None,
+            rows: default_panel_rows(),
+            cols: default_panel_cols(),
+            chain_length: default_chain_length(),
+            parallel: default_parallel(),
+            hardware_mapping: default_hardware_mapping(),
+            offset_x: 0,
+            offset_y: 0,
+            active_width: None,
+            active_height: None,
+        }
+    }
+}
+
+impl PanelConfig {
+    /// Physical canvas size in LEDs (width, height).
+    pub fn canvas_size(&self) -> (usize, usize) {
+        (
+            (self.cols * self.chain_length) as usize,
+            (self.rows * self.parallel) as usize,
+        )
+    }
+
+    /// Size the sign is rendered at (width, height): the active area.
+    pub fn frame_size(&self) -> (usize, usize) {
+        let (canvas_width, canvas_height) = self.canvas_size();
+        (
+            self.active_width
+                .unwrap_or_else(|| canvas_width.saturating_sub(self.offset_x as usize)),
+            self.active_height
+                .unwrap_or_else(|| canvas_height.saturating_sub(self.offset_y as usize)),
+        )
+    }
+
+    /// Whether switching from `old` needs a new display (the panel itself
+    /// or where frames go changed).
+    pub fn needs_new_display(&self, old: &PanelConfig) -> bool {
+        let key = |p: &PanelConfig| {
+            (p.backend, p.network_target.clone(), p.hardware_mapping.clone(), p.rows, p.cols, p.chain_length, p.parallel)
+        };
+        key(self) != key(old)
+    }
+}
+
+/// Route icon customization (optional in config file).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct IconConfig {
+    /// Bullet color per route, e.g. `{"A": "#0039a6", "7": "orange"}`.
+    /// Routes with no icon get a generated bullet in this color.
+    #[serde(default)]
+    pub colors: BTreeMap<String, String>,
+    /// Extra icons loaded from PNG files, e.g. `{"HOME": "icons/home.png"}`.
+    /// Shown in alert text as `[HOME]` like any route bullet.
+    #[serde(default)]
+    pub custom: BTreeMap<String, PathBuf>,
+    /// Developer mode: read the font JSON from this file instead of the
+    /// embedded copy, and reload icons whenever a watched file changes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub font_path: Option<PathBuf>,
+    /// Developer mode: route icon metadata JSON (sizes, colors) from disk.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata_path: Option<PathBuf>,
+}
+
+impl IconConfig {
+    /// True when font or icon metadata is read from disk.
+    pub fn dev_mode(&self) -> bool {
+        self.font_path.is_some() || self.metadata_path.is_some()
+    }
+
+    /// Files whose changes should rebuild the font: the developer JSON
+    /// files and custom icon PNGs.
+    pub fn watched_files(&self) -> Vec<&Path> {
+        self.font_path
+            .iter()
+            .chain(&self.metadata_path)
+            .chain(self.custom.values())
+            .map(PathBuf::as_path)
+            .collect()
+    }
+}
+
+/// Service analytics (optional in config file).
+///
+/// `dir` is read at startup (and unused with a `database`); the other
+/// fields apply live.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnalyticsConfig {
+    /// Observe snapshots and record arrival accuracy.
+    #[serde(default = "default_analytics_enabled")]
+    pub enabled: bool,
+    /// Directory holding one JSON file per day.
+    #[serde(default = "default_analytics_dir")]
+    pub dir: PathBuf,
+    /// Days of history kept on disk.
+    #[serde(default = "default_analytics_retention_days")]
+    pub retention_days: u32,
+}
+
+fn default_analytics_enabled() -> bool {
+    true
+}
+fn default_analytics_dir() -> PathBuf {
+    PathBuf::from("analytics")
+}
+fn default_analytics_retention_days() -> u32 {
+    90
+}
+
+impl Default for AnalyticsConfig {
+    fn default() -> Self {
+        AnalyticsConfig {
+            enabled: default_analytics_enabled(),
+            dir: default_analytics_dir(),
+            retention_days: default_analytics_retention_days(),
+        }
+    }
+}
+
+/// Embedded SQLite database (optional in config file, read at startup).
+///
+/// With a `path`, history features (analytics, ...) keep their data there
+/// instead of in JSON files. Needs the `persistence` build feature.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DatabaseConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+}
+
+/// "Leave now" prompt (optional in config file).
+///
+/// Off until `walk_minutes` lists at least one station. See `journey`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JourneyConfig {
+    /// Minutes to walk to each station, keyed by platform base stop ID
+    /// (e.g. `{"635": 6}`). Trains at other stations never prompt.
+    #[serde(default)]
+    pub walk_minutes: BTreeMap<String, u32>,
+    /// Routes that go where you're headed, most preferred first (empty = any).
+    #[serde(default)]
+    pub routes: Vec<String>,
+    /// Only trains heading this way (none = either).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub direction: Option<Direction>,
+    /// Prompt while leaving now reaches the platform at most this many
+    /// minutes before the train.
+    #[serde(default = "default_journey_window_minutes")]
+    pub window_minutes: u32,
+}
+
+fn default_journey_window_minutes() -> u32 {
+    2
+}
+
+impl Default for JourneyConfig {
+    fn default() -> Self {
+        JourneyConfig {
+            walk_minutes: BTreeMap::new(),
+            routes: Vec::new(),
+            direction: None,
+            window_minutes: default_journey_window_minutes(),
+        }
+    }
+}
+
+/// Two-leg trip with a transfer (optional in config file), e.g. the L from
+/// this sign's station to Union Sq, then the 4/5. See `trip`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TripConfig {
+    /// Boarded at this sign's station.
+    pub first: TripLeg,
+    /// Where the legs meet, by name (fuzzy matched like `station_name`).
+    pub transfer_station: String,
+    /// Boarded at the transfer station.
+    pub second: TripLeg,
+    /// Minutes riding the first leg to the transfer station.
+    pub ride_minutes: u32,
+    /// Minutes to change platforms at the transfer station.
+    #[serde(default = "default_transfer_minutes")]
+    pub transfer_minutes: u32,
+    /// Longest wait for the second leg that still counts as a connection.
+    #[serde(default = "default_max_wait_minutes")]
+    pub max_wait_minutes: u32,
+}
+
+fn default_transfer_minutes() -> u32 {
+    3
+}
+fn default_max_wait_minutes() -> u32 {
+    10
+}
+
+/// One leg of a `TripConfig`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TripLeg {
+    pub routes: Vec<String>,
+    /// Only trains heading this way (none = either).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub direction: Option<Direction>,
+}
+
+impl TripConfig {
+    /// Stop IDs at the transfer station (empty if the name doesn't match).
+    pub fn transfer_stop_ids(&self) -> Vec<String> {
+        stations::get_stop_ids_for_station(&self.transfer_station)
+    }
+}
+
+/// What to ride when your usual route is suspended (optional in config
+/// file). See `selection::Suspension`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AlternatesConfig {
+    /// The route you normally take (none = no suggestions).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub primary: Option<String>,
+    /// Other configured routes to suggest, most preferred first
+    /// (empty = whichever comes soonest).
+    #[serde(default)]
+    pub routes: Vec<String>,
+}
+
+/// Default port the web server listens on (`web.port`).
+pub const DEFAULT_WEB_PORT: u16 = 5001;
+
+/// Hard ceiling for `web.max_upload_kb` (and the upload route's body limit).
+pub const MAX_UPLOAD_KB: u32 = 16 * 1024;
+
+/// Largest UDP payload over IPv4, which bounds `network` backend frames.
+const MAX_UDP_PAYLOAD: usize = 65_507;
+
+/// Highest frame rate `/ws/frames` and `/ws/preview` will stream.
+pub const MAX_FRAME_FPS: u32 = 30;
+
+/// Web server settings (optional in config file).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WebConfig {
+    /// Public mode: refuse every write and hide the config UI, for signs
+    /// reachable from outside a trusted LAN. Turn it off by editing the file.
+    #[serde(default)]
+    pub read_only: bool,
+    /// Which requests are written to the log. `/api/logs/http` keeps the
+    /// recent ones either way.
+    #[serde(default)]
+    pub access_log: AccessLogLevel,
+    /// Bearer token for `POST /api/assets` (none = uploads disabled).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upload_token: Option<String>,
+    /// Largest accepted upload, in KB (up to `web::assets::MAX_UPLOAD_KB`).
+    #[serde(default = "default_max_upload_kb")]
+    pub max_upload_kb: u32,
+    /// Where uploaded assets are stored.
+    #[serde(default = "default_assets_dir")]
+    pub assets_dir: PathBuf,
+    /// Frames per second streamed by `/ws/preview`.
+    #[serde(default = "default_preview_fps")]
+    pub preview_fps: u32,
+    /// Port the web server listens on (read at startup; each instance on a
+    /// host needs its own).
+    #[serde(default = "default_web_port")]
+    pub port: u16,
+}
+
+fn default_web_port() -> u16 {
+    DEFAULT_WEB_PORT
+}
+
+fn default_max_upload_kb() -> u32 {
+    1024
+}
+fn default_assets_dir() -> PathBuf {
+    PathBuf::from("uploads")
+}
+fn default_preview_fps() -> u32 {
+    10
+}
+
+impl Default for WebConfig {
+    fn default() -> Self {
+        WebConfig {
+            read_only: false,
+            access_log: AccessLogLevel::default(),
+            upload_token: None,
+            max_upload_kb: default_max_upload_kb(),
+            assets_dir: default_assets_dir(),
+            preview_fps: default_preview_fps(),
+            port: default_web_port(),
+        }
+    }
+}
+
+/// First-boot Wi-Fi provisioning (optional in config file, read at
+/// startup). See `setup`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SetupConfig {
+    /// When the sign boots with no network, open a Wi-Fi access point with
+    /// a page for entering the home network's credentials.
+    #[serde(default)]
+    pub wifi_portal: bool,
+    /// Name of the setup access point.
+    #[serde(default = "default_setup_ssid")]
+    pub ssid: String,
+    /// WPA2 passphrase for the access point (none = open network).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    /// Wireless interface to use.
+    #[serde(default = "default_setup_interface")]
+    pub interface: String,
 }
 
-/// Raw station section — supports all 3 formats via Option fields.
-#[derive(Debug, Deserialize)]
-struct RawStationConfig {
-    station_name: Option<String>,
-    routes: Option<Vec<String>>,
-    stations: Option<Vec<RawStationPair>>,
-    uptown_stop_id: Option<String>,
-    downtown_stop_id: Option<String>,
+fn default_setup_ssid() -> String {
+    "SubwaySign-Setup".to_string()
+}
+fn default_setup_interface() -> String {
+    "wlan0".to_string()
 }
 
-/// Explicit platform pair (format 2).
-#[derive(Debug, Deserialize)]
-struct RawStationPair {
-    uptown: String,
-    downtown: String,
+impl Default for SetupConfig {
+    fn default() -> Self {
+        SetupConfig {
+            wifi_portal: false,
+            ssid: default_setup_ssid(),
+            password: None,
+            interface: default_setup_interface(),
+        }
+    }
 }
 
-/// Display settings.
-#[derive(Debug, Clone, Deserialize)]
-pub struct DisplayConfig {
-    pub brightness: f64,
-    pub max_trains: u32,
-    pub show_alerts: bool,
+/// A push button on a GPIO pin (optional in config file, read at
+/// startup). Needs the `gpio` build feature (part of `hardware`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ButtonConfig {
+    /// BCM pin number.
+    pub pin: u32,
+    pub action: ButtonAction,
+    /// The button pulls the pin low when pressed (wired to ground).
+    #[serde(default = "default_button_active_low")]
+    pub active_low: bool,
 }
 
-/// Refresh intervals (optional in config file).
-#[derive(Debug, Clone, Deserialize)]
-pub struct RefreshConfig {
-    #[serde(default = "default_trains_interval")]
-    pub trains_interval: u64,
-    #[serde(default = "default_alerts_interval")]
-    pub alerts_interval: u64,
+fn default_button_active_low() -> bool {
+    true
 }
 
-fn default_trains_interval() -> u64 {
-    20
+/// What a button press does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ButtonAction {
+    /// Show the sign's IP address and web port for a few seconds.
+    ShowIp,
+    /// Toggle do-not-disturb (alerts and banners paused).
+    DoNotDisturb,
 }
-fn default_alerts_interval() -> u64 {
+
+/// Access log verbosity.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AccessLogLevel {
+    Off,
+    /// Only requests answered with a 4xx or 5xx.
+    #[default]
+    Errors,
+    All,
+}
+
+/// Fleet mode: several signs sharing one config (optional in config file).
+///
+/// A sign with `publish` serves its config at `GET /api/fleet/config`; a
+/// sign with `source` polls that endpoint and takes the remote `station`,
+/// `display` and `alternates` sections, with its local `overrides` applied
+/// on top. On a member sign the file's own copies of those sections are only
+/// a fallback until the source is first reached.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FleetConfig {
+    /// Serve this sign's config to other signs.
+    #[serde(default)]
+    pub publish: bool,
+    /// Base URL of the publishing sign, e.g. `http://lobby-sign.local:5001`.
+    #[serde(default)]
+    pub source: Option<String>,
+    /// How often to poll the source, in seconds.
+    #[serde(default = "default_fleet_poll_interval")]
+    pub poll_interval: u64,
+    /// Sections applied over the remote config. `station` replaces the remote
+    /// station wholesale; other sections override field by field.
+    #[serde(default)]
+    pub overrides: serde_json::Map<String, serde_json::Value>,
+}
+
+fn default_fleet_poll_interval() -> u64 {
     60
 }
 
-impl Default for RefreshConfig {
+impl Default for FleetConfig {
     fn default() -> Self {
-        RefreshConfig {
-            trains_interval: default_trains_interval(),
-            alerts_interval: default_alerts_interval(),
+        FleetConfig {
+            publish: false,
+            source: None,
+            poll_interval: default_fleet_poll_interval(),
+            overrides: serde_json::Map::new(),
+        }
+    }
+}
+
+impl FleetConfig {
+    /// The configured source URL, if fleet sync is enabled.
+    pub fn source_url(&self) -> Option<&str> {
+        self.source
+            .as_deref()
+            .map(|s| s.trim().trim_end_matches('/'))
+            .filter(|s| !s.is_empty())
+    }
+}
+
+/// A time window with its own route set (optional in config file), e.g. to
+/// drop a route on weekends or nights when it doesn't run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RouteWindow {
+    /// Days the window starts on, as "mon".."sun" (empty = every day).
+    #[serde(default)]
+    pub days: Vec<String>,
+    /// "HH:MM" local time; a window may run past midnight. Missing = all day.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end: Option<String>,
+    /// Routes shown during the window (a subset of the station's routes).
+    pub routes: Vec<String>,
+}
+
+const WEEKDAYS: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+
+fn parse_hhmm(s: &str) -> Option<chrono::NaiveTime> {
+    chrono::NaiveTime::parse_from_str(s, "%H:%M").ok()
+}
+
+impl RouteWindow {
+    fn starts_on(&self, day: chrono::Weekday) -> bool {
+        self.days.is_empty()
+            || self
+                .days
+                .iter()
+                .any(|d| d.eq_ignore_ascii_case(WEEKDAYS[day.num_days_from_monday() as usize]))
+    }
+
+    fn contains(&self, now: chrono::NaiveDateTime) -> bool {
+        use chrono::Datelike;
+        let start = self.start.as_deref().and_then(parse_hhmm).unwrap_or(chrono::NaiveTime::MIN);
+        let end = self.end.as_deref().and_then(parse_hhmm).unwrap_or(chrono::NaiveTime::MIN);
+        let time = now.time();
+        if start < end {
+            self.starts_on(now.weekday()) && time >= start && time < end
+        } else {
+            // Runs past midnight (or all day when start == end)
+            (time >= start && self.starts_on(now.weekday()))
+                || (time < end && self.starts_on(now.weekday().pred()))
+        }
+    }
+}
+
+/// Static GTFS timetable used to skip routes with no scheduled service
+/// (optional in config file). See `mta::schedule`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StaticGtfsConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_static_gtfs_url")]
+    pub url: String,
+    /// Where the downloaded zip is kept between restarts.
+    #[serde(default = "default_static_gtfs_cache_path")]
+    pub cache_path: PathBuf,
+    /// Re-download once the cached copy is this old.
+    #[serde(default = "default_static_gtfs_refresh_hours")]
+    pub refresh_hours: u32,
+}
+
+fn default_static_gtfs_url() -> String {
+    // Includes planned service changes for the coming week
+    "https://rrgtfsfeeds.s3.amazonaws.com/gtfs_supplemented.zip".to_string()
+}
+fn default_static_gtfs_cache_path() -> PathBuf {
+    PathBuf::from("gtfs_static.zip")
+}
+fn default_static_gtfs_refresh_hours() -> u32 {
+    24
+}
+
+impl Default for StaticGtfsConfig {
+    fn default() -> Self {
+        StaticGtfsConfig {
+            enabled: false,
+            url: default_static_gtfs_url(),
+            cache_path: default_static_gtfs_cache_path(),
+            refresh_hours: default_static_gtfs_refresh_hours(),
         }
     }
 }
 
+/// What a config change touches (see `Config::diff`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ConfigChange {
+    /// What gets fetched: stops, routes, schedules, train count.
+    pub data: bool,
+    /// How it's drawn: display options, panel area, icons, time zone.
+    pub display: bool,
+}
+
+/// iCal feed of days the sign stays dark (optional in config file). See
+/// `calendar`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CalendarConfig {
+    /// https:// or webcal:// address of the .ics feed.
+    pub url: String,
+    /// Only events whose title contains this (case-insensitive); missing =
+    /// every event.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keyword: Option<String>,
+    #[serde(default = "default_calendar_refresh_minutes")]
+    pub refresh_minutes: u32,
+    /// On away days, stay on at this brightness (0.0-1.0) instead of going
+    /// dark; it overrides `display.brightness` and `display.schedule`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub away_brightness: Option<f64>,
+}
+
+fn default_calendar_refresh_minutes() -> u32 {
+    60
+}
+
 /// Resolved application configuration.
 #[derive(Debug, Clone)]
 pub struct Config {
@@ -97,6 +1411,27 @@ pub struct Config {
     pub routes: Vec<String>,
     pub display: DisplayConfig,
     pub refresh: RefreshConfig,
+    pub screenshots: ScreenshotConfig,
+    pub fleet: FleetConfig,
+    pub panel: PanelConfig,
+    pub icons: IconConfig,
+    pub analytics: AnalyticsConfig,
+    pub database: DatabaseConfig,
+    pub journey: JourneyConfig,
+    pub trip: Option<TripConfig>,
+    pub alternates: AlternatesConfig,
+    pub web: WebConfig,
+    pub setup: SetupConfig,
+    pub buttons: Vec<ButtonConfig>,
+    pub route_schedule: Vec<RouteWindow>,
+    pub static_gtfs: StaticGtfsConfig,
+    pub calendar: Option<CalendarConfig>,
+    /// IANA time zone for wall-clock times (none = the system's).
+    pub timezone: Option<String>,
+    /// Station database to use instead of the embedded one (same format as
+    /// `assets/mta_stations_complete.json`), relative to the config file's
+    /// directory. Re-read when the config reloads with a different path.
+    pub stations_path: Option<PathBuf>,
 }
 
 impl Config {
@@ -110,7 +1445,7 @@ impl Config {
         let bak_path = path.with_extension("json.bak");
 
         match std::fs::read_to_string(path) {
-            Ok(contents) => match Self::from_json(&contents) {
+            Ok(contents) => match Self::from_json(&with_fleet_cache(path, contents)) {
                 Ok(cfg) => return Ok(cfg),
                 Err(e) => {
                     tracing::warn!("Primary config corrupt ({}), trying backup...", e);
@@ -125,7 +1460,7 @@ impl Config {
         if bak_path.exists() {
             let contents = std::fs::read_to_string(&bak_path)
                 .map_err(|e| ConfigError::Io(format!("read backup: {}", e)))?;
-            let cfg = Self::from_json(&contents)?;
+            let cfg = Self::from_json(&with_fleet_cache(path, contents))?;
             tracing::warn!("Loaded config from backup: {}", bak_path.display());
             // Restore backup as primary
             let _ = std::fs::copy(&bak_path, path);
@@ -138,6 +1473,14 @@ impl Config {
         )))
     }
 
+    /// A saved version (backup or history file) of the config at `path`,
+    /// loaded as it would be if restored.
+    pub fn load_version(path: &Path, version: &Path) -> Result<Self, ConfigError> {
+        let contents = std::fs::read_to_string(version)
+            .map_err(|e| ConfigError::Io(format!("read {}: {}", version.display(), e)))?;
+        Self::from_json(&with_fleet_cache(path, contents))
+    }
+
     /// Parse config from a JSON string (useful for testing).
     pub fn from_json(json: &str) -> Result<Self, ConfigError> {
         let raw: RawConfig =
@@ -180,12 +1523,54 @@ impl Config {
             routes,
             display: raw.display,
             refresh: raw.refresh,
+            screenshots: raw.screenshots,
+            fleet: raw.fleet,
+            panel: raw.panel,
+            icons: raw.icons,
+            analytics: raw.analytics,
+            database: raw.database,
+            journey: raw.journey,
+            trip: raw.trip,
+            alternates: raw.alternates,
+            web: raw.web,
+            setup: raw.setup,
+            buttons: raw.buttons,
+            route_schedule: raw.route_schedule,
+            static_gtfs: raw.static_gtfs,
+            calendar: raw.calendar,
+            timezone: raw.timezone,
+            stations_path: raw.stations_path,
         };
 
         config.validate()?;
         Ok(config)
     }
 
+    /// What differs from `old`, so edits that only change the look don't
+    /// re-fetch trains and data edits don't reset the renderer.
+    pub fn diff(&self, old: &Config) -> ConfigChange {
+        let data = |c: &Config| {
+            serde_json::json!([
+                c.station_stops,
+                c.routes,
+                c.refresh,
+                c.route_schedule,
+                c.static_gtfs,
+                c.trip,
+                c.alternates,
+                c.timezone,
+                c.display.max_trains,
+                c.display.show_alerts,
+                c.display.route_service,
+            ])
+        };
+        let display = |c: &Config| serde_json::json!([c.display, c.panel, c.icons, c.timezone]);
+        ConfigChange {
+            data: data(self) != data(old),
+            display: display(self) != display(old),
+        }
+    }
+
     /// Resolve a station name to stop IDs and routes via the station database.
     fn resolve_station_name(
         station_name: &str,
@@ -212,34 +1597,588 @@ impl Config {
         Ok((stops, routes))
     }
 
+    /// All stop IDs (both directions of every platform) to fetch arrivals for.
+    pub fn all_stop_ids(&self) -> Vec<String> {
+        self.station_stops
+            .iter()
+            .flat_map(|(up, down)| [up.clone(), down.clone()])
+            .collect()
+    }
+
+    /// The sign's time zone (validated at load).
+    pub fn tz(&self) -> Timezone {
+        self.timezone
+            .as_deref()
+            .and_then(|name| Timezone::parse(name).ok())
+            .unwrap_or_default()
+    }
+
+    /// Routes in service at local time `now`: the first matching
+    /// `route_schedule` window's, else all configured routes.
+    pub fn scheduled_routes(&self, now: chrono::NaiveDateTime) -> Vec<String> {
+        self.route_schedule
+            .iter()
+            .find(|w| w.contains(now))
+            .map(|w| w.routes.clone())
+            .unwrap_or_else(|| self.routes.clone())
+    }
+
+    /// Whether it's `display.off_hours` now, in the sign's time zone.
+    pub fn off_hours_now(&self) -> bool {
+        self.display.is_off_at(self.tz().now().time())
+    }
+
     /// Validate config values are within acceptable ranges.
     fn validate(&self) -> Result<(), ConfigError> {
+        if let Some(name) = &self.timezone {
+            Timezone::parse(name).map_err(|e| ConfigError::Validation(format!("timezone: {}", e)))?;
+        }
         if !(0.0..=1.0).contains(&self.display.brightness) {
             return Err(ConfigError::Validation(format!(
-                "brightness must be 0.0-1.0, got {}",
-                self.display.brightness
+                "brightness must be 0.0-1.0, got {}",
+                self.display.brightness
+            )));
+        }
+        if let Some(calendar) = &self.calendar {
+            if !["https://", "http://", "webcal://"].iter().any(|s| calendar.url.starts_with(s)) {
+                return Err(ConfigError::Validation(format!(
+                    "calendar.url must be an http(s):// or webcal:// address, got '{}'",
+                    calendar.url
+                )));
+            }
+            if !(5..=1440).contains(&calendar.refresh_minutes) {
+                return Err(ConfigError::Validation(format!(
+                    "calendar.refresh_minutes must be 5-1440, got {}",
+                    calendar.refresh_minutes
+                )));
+            }
+            if let Some(level) = calendar.away_brightness.filter(|b| !(0.0..=1.0).contains(b)) {
+                return Err(ConfigError::Validation(format!(
+                    "calendar.away_brightness must be 0.0-1.0, got {}",
+                    level
+                )));
+            }
+        }
+        if let Some(off) = &self.display.off_hours {
+            if let Some(time) = [&off.from, &off.to].into_iter().find(|t| parse_hhmm(t).is_none()) {
+                return Err(ConfigError::Validation(format!(
+                    "display.off_hours: time must be HH:MM, got '{}'",
+                    time
+                )));
+            }
+            if off.from == off.to {
+                return Err(ConfigError::Validation(
+                    "display.off_hours: from and to must differ".to_string(),
+                ));
+            }
+        }
+        for level in &self.display.schedule {
+            if parse_hhmm(&level.time).is_none() {
+                return Err(ConfigError::Validation(format!(
+                    "display.schedule: time must be HH:MM, got '{}'",
+                    level.time
+                )));
+            }
+            if !(0.0..=1.0).contains(&level.brightness) {
+                return Err(ConfigError::Validation(format!(
+                    "display.schedule: brightness must be 0.0-1.0, got {}",
+                    level.brightness
+                )));
+            }
+        }
+        if self.display.max_trains < 1 || self.display.max_trains > 20 {
+            return Err(ConfigError::Validation(format!(
+                "max_trains must be 1-20, got {}",
+                self.display.max_trains
+            )));
+        }
+        if self.routes.is_empty() {
+            return Err(ConfigError::Validation(
+                "routes cannot be empty".to_string(),
+            ));
+        }
+        if self.station_stops.is_empty() {
+            return Err(ConfigError::Validation(
+                "station_stops cannot be empty".to_string(),
+            ));
+        }
+        let stop_ids = self.all_stop_ids();
+        for rule in &self.display.top_row {
+            if let TopRowRule::Stop(stop) = rule {
+                if !stop_ids.iter().any(|id| crate::selection::stop_matches(id, stop)) {
+                    return Err(ConfigError::Validation(format!(
+                        "top_row: stop '{}' is not one of the station's stops",
+                        stop
+                    )));
+                }
+            }
+        }
+        for page in &self.display.pages {
+            if !(1..=3600).contains(&page.seconds) {
+                return Err(ConfigError::Validation(format!(
+                    "display.pages: seconds must be 1-3600, got {}",
+                    page.seconds
+                )));
+            }
+            match &page.kind {
+                PageKind::Message { lines } if lines.is_empty() || lines.len() > 2 => {
+                    return Err(ConfigError::Validation(
+                        "display.pages: a message page needs one or two lines".to_string(),
+                    ));
+                }
+                PageKind::Alerts if !self.display.show_alerts => {
+                    return Err(ConfigError::Validation(
+                        "display.pages: the alerts page needs show_alerts".to_string(),
+                    ));
+                }
+                _ => {}
+            }
+        }
+        if self.display.alert_qr_seconds > 60 {
+            return Err(ConfigError::Validation(format!(
+                "display.alert_qr_seconds must be 0-60, got {}",
+                self.display.alert_qr_seconds
+            )));
+        }
+        if let Some(watch) = &self.display.watch {
+            if !self.routes.contains(&watch.route) {
+                return Err(ConfigError::Validation(format!(
+                    "display.watch: the {} isn't one of the configured routes",
+                    watch.route
+                )));
+            }
+            if watch.minutes > 60 || !(1..=120).contains(&watch.seconds) {
+                return Err(ConfigError::Validation(
+                    "display.watch: minutes must be 0-60 and seconds 1-120".to_string(),
+                ));
+            }
+        }
+        for (route, filter) in &self.display.route_service {
+            if *filter != ServiceFilter::Both && !crate::display::colors::is_express_capable(route) {
+                return Err(ConfigError::Validation(format!(
+                    "route_service: the {} never runs express",
+                    route
+                )));
+            }
+        }
+        crate::display::i18n::Strings::new(&self.display.locale, &self.display.strings)
+            .map_err(ConfigError::Validation)?;
+        if self.display.brightness_ramp_ms > 10_000 {
+            return Err(ConfigError::Validation(format!(
+                "brightness_ramp_ms must be 0-10000, got {}",
+                self.display.brightness_ramp_ms
+            )));
+        }
+        if self.display.cycle_transition_ms > 1000 {
+            return Err(ConfigError::Validation(format!(
+                "cycle_transition_ms must be 0-1000, got {}",
+                self.display.cycle_transition_ms
+            )));
+        }
+        if !(10.0..=240.0).contains(&self.display.scroll_speed) {
+            return Err(ConfigError::Validation(format!(
+                "scroll_speed must be 10-240 px/s, got {}",
+                self.display.scroll_speed
+            )));
+        }
+        let scroll = &self.display.alert_scroll;
+        let loops = std::iter::once(scroll.loops).chain(scroll.rules.iter().filter_map(|r| r.loops));
+        for n in loops {
+            if !(1..=10).contains(&n) {
+                return Err(ConfigError::Validation(format!(
+                    "alert_scroll loops must be 1-10, got {}",
+                    n
+                )));
+            }
+        }
+        if self.display.alert_ticker.interval_seconds > 3600 {
+            return Err(ConfigError::Validation(format!(
+                "alert_ticker interval_seconds must be 0-3600, got {}",
+                self.display.alert_ticker.interval_seconds
+            )));
+        }
+        for step in &self.display.post {
+            step.validate().map_err(ConfigError::Validation)?;
+        }
+        if self.display.clock_after_minutes < 1 {
+            return Err(ConfigError::Validation(
+                "clock_after_minutes must be at least 1".to_string(),
+            ));
+        }
+        if self.fleet.source_url().is_some() && self.fleet.poll_interval < 5 {
+            return Err(ConfigError::Validation(format!(
+                "fleet.poll_interval must be at least 5 seconds, got {}",
+                self.fleet.poll_interval
+            )));
+        }
+        self.validate_panel()?;
+        self.validate_icons()?;
+        self.validate_trip()?;
+        self.validate_route_schedule()?;
+        let alternates = &self.alternates;
+        let configured = |route: &&String| self.routes.iter().any(|r| r.eq_ignore_ascii_case(route));
+        if let Some(route) = alternates.primary.iter().chain(&alternates.routes).find(|r| !configured(r)) {
+            return Err(ConfigError::Validation(format!(
+                "alternates: route '{}' is not one of the station's routes",
+                route
+            )));
+        }
+        if !(1..=30).contains(&self.journey.window_minutes) {
+            return Err(ConfigError::Validation(format!(
+                "journey.window_minutes must be 1-30, got {}",
+                self.journey.window_minutes
+            )));
+        }
+        if !(1..=MAX_UPLOAD_KB).contains(&self.web.max_upload_kb) {
+            return Err(ConfigError::Validation(format!(
+                "web.max_upload_kb must be 1-{}, got {}",
+                MAX_UPLOAD_KB,
+                self.web.max_upload_kb
+            )));
+        }
+        if !(1..=MAX_FRAME_FPS).contains(&self.web.preview_fps) {
+            return Err(ConfigError::Validation(format!(
+                "web.preview_fps must be 1-{}, got {}",
+                MAX_FRAME_FPS,
+                self.web.preview_fps
+            )));
+        }
+        if self.setup.ssid.is_empty() || self.setup.ssid.len() > 32 {
+            return Err(ConfigError::Validation(format!(
+                "setup.ssid must be 1-32 bytes, got '{}'",
+                self.setup.ssid
+            )));
+        }
+        if let Some(password) = &self.setup.password {
+            if !(8..=63).contains(&password.len()) {
+                return Err(ConfigError::Validation(
+                    "setup.password must be 8-63 characters (WPA2)".to_string(),
+                ));
+            }
+        }
+        for (i, button) in self.buttons.iter().enumerate() {
+            if button.pin > 27 {
+                return Err(ConfigError::Validation(format!(
+                    "buttons: pin must be 0-27, got {}",
+                    button.pin
+                )));
+            }
+            if self.buttons[..i].iter().any(|b| b.pin == button.pin) {
+                return Err(ConfigError::Validation(format!(
+                    "buttons: pin {} is used twice",
+                    button.pin
+                )));
+            }
+        }
+        if self.refresh.dedup_window_secs > 300 {
+            return Err(ConfigError::Validation(format!(
+                "refresh.dedup_window_secs must be 0-300, got {}",
+                self.refresh.dedup_window_secs
+            )));
+        }
+        if self.refresh.startup_delay_secs > 60 {
+            return Err(ConfigError::Validation(format!(
+                "refresh.startup_delay_secs must be 0-60, got {}",
+                self.refresh.startup_delay_secs
+            )));
+        }
+        if self.static_gtfs.enabled && self.static_gtfs.refresh_hours < 1 {
+            return Err(ConfigError::Validation(
+                "static_gtfs.refresh_hours must be at least 1".to_string(),
+            ));
+        }
+        if self.analytics.retention_days < 1 {
+            return Err(ConfigError::Validation(
+                "analytics.retention_days must be at least 1".to_string(),
+            ));
+        }
+        if !(1..=crate::display::export::MAX_SCALE).contains(&self.screenshots.scale) {
+            return Err(ConfigError::Validation(format!(
+                "screenshots.scale must be 1-{}, got {}",
+                crate::display::export::MAX_SCALE,
+                self.screenshots.scale
+            )));
+        }
+        Ok(())
+    }
+
+    /// Check the trip legs have routes, the transfer station resolves, and
+    /// the ride and wait minutes are in range.
+    fn validate_trip(&self) -> Result<(), ConfigError> {
+        let Some(trip) = &self.trip else {
+            return Ok(());
+        };
+        if trip.first.routes.is_empty() || trip.second.routes.is_empty() {
+            return Err(ConfigError::Validation(
+                "trip legs need at least one route each".to_string(),
+            ));
+        }
+        if trip.transfer_stop_ids().is_empty() {
+            return Err(ConfigError::StationNotFound(trip.transfer_station.clone()));
+        }
+        if !(1..=120).contains(&trip.ride_minutes) {
+            return Err(ConfigError::Validation(format!(
+                "trip.ride_minutes must be 1-120, got {}",
+                trip.ride_minutes
+            )));
+        }
+        if trip.max_wait_minutes < 1 {
+            return Err(ConfigError::Validation(
+                "trip.max_wait_minutes must be at least 1".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn validate_route_schedule(&self) -> Result<(), ConfigError> {
+        for window in &self.route_schedule {
+            if window.routes.is_empty() {
+                return Err(ConfigError::Validation(
+                    "route_schedule: a window needs at least one route".to_string(),
+                ));
+            }
+            if let Some(route) = window
+                .routes
+                .iter()
+                .find(|route| !self.routes.iter().any(|r| r.eq_ignore_ascii_case(route)))
+            {
+                return Err(ConfigError::Validation(format!(
+                    "route_schedule: route '{}' is not one of the station's routes",
+                    route
+                )));
+            }
+            if let Some(day) = window
+                .days
+                .iter()
+                .find(|d| !WEEKDAYS.iter().any(|w| w.eq_ignore_ascii_case(d)))
+            {
+                return Err(ConfigError::Validation(format!(
+                    "route_schedule: unknown day '{}' (use mon..sun)",
+                    day
+                )));
+            }
+            if let Some(time) = [&window.start, &window.end]
+                .into_iter()
+                .flatten()
+                .find(|t| parse_hhmm(t).is_none())
+            {
+                return Err(ConfigError::Validation(format!(
+                    "route_schedule: time must be HH:MM, got '{}'",
+                    time
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Check icon colors parse and icon names are usable as `[NAME]` in alerts.
+    fn validate_icons(&self) -> Result<(), ConfigError> {
+        for (route, color) in &self.icons.colors {
+            if crate::display::colors::parse_color(color).is_none() {
+                return Err(ConfigError::Validation(format!(
+                    "icons.colors.{}: invalid color '{}'",
+                    route, color
+                )));
+            }
+        }
+        for name in self.icons.custom.keys() {
+            let numeric = name.chars().all(|c| c.is_ascii_digit());
+            let letters = name.chars().all(|c| c.is_ascii_uppercase());
+            if name.is_empty() || !(numeric || letters) {
+                return Err(ConfigError::Validation(format!(
+                    "icons.custom: name '{}' must be all digits or all uppercase letters",
+                    name
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Check the panel geometry, and that the active area fits the canvas.
+    fn validate_panel(&self) -> Result<(), ConfigError> {
+        let panel = &self.panel;
+        if ![16, 32, 64].contains(&panel.rows) || ![32, 64, 128].contains(&panel.cols) {
+            return Err(ConfigError::Validation(format!(
+                "panel rows must be 16, 32 or 64 and cols 32, 64 or 128, got {}x{}",
+                panel.cols, panel.rows
+            )));
+        }
+        if !(1..=12).contains(&panel.chain_length) {
+            return Err(ConfigError::Validation(format!(
+                "panel.chain_length must be 1-12, got {}",
+                panel.chain_length
+            )));
+        }
+        if !(1..=3).contains(&panel.parallel) {
+            return Err(ConfigError::Validation(format!(
+                "panel.parallel must be 1-3, got {}",
+                panel.parallel
             )));
         }
-        if self.display.max_trains < 1 || self.display.max_trains > 20 {
+        // Narrowest and shortest frame the two-row layout fits in
+        let (width, height) = panel.frame_size();
+        if width < 64 || height < 32 {
             return Err(ConfigError::Validation(format!(
-                "max_trains must be 1-20, got {}",
-                self.display.max_trains
+                "panel active area must be at least 64x32, got {}x{}",
+                width, height
             )));
         }
-        if self.routes.is_empty() {
-            return Err(ConfigError::Validation(
-                "routes cannot be empty".to_string(),
-            ));
+        let (canvas_width, canvas_height) = panel.canvas_size();
+        if panel.offset_x as usize + width > canvas_width
+            || panel.offset_y as usize + height > canvas_height
+        {
+            return Err(ConfigError::Validation(format!(
+                "panel active area at ({}, {}) overflows the {}x{} canvas",
+                panel.offset_x, panel.offset_y, canvas_width, canvas_height
+            )));
         }
-        if self.station_stops.is_empty() {
-            return Err(ConfigError::Validation(
-                "station_stops cannot be empty".to_string(),
-            ));
+        if panel.backend == DisplayBackend::Network {
+            let target = panel.network_target.as_deref().unwrap_or("");
+            let valid = target
+                .rsplit_once(':')
+                .is_some_and(|(host, port)| !host.is_empty() && port.parse::<u16>().is_ok_and(|p| p > 0));
+            if !valid {
+                return Err(ConfigError::Validation(format!(
+                    "panel.network_target must be host:port for the network backend, got '{}'",
+                    target
+                )));
+            }
+            // One frame per datagram (see `display::network`)
+            if 4 + width * height * 3 > MAX_UDP_PAYLOAD {
+                return Err(ConfigError::Validation(format!(
+                    "panel active area {}x{} is too large for the network backend (one UDP datagram per frame)",
+                    width, height
+                )));
+            }
         }
         Ok(())
     }
 }
 
+/// Find the config.json file (check CWD, then parent directory).
+pub fn find_config_path() -> PathBuf {
+    let candidates = [
+        PathBuf::from("config.json"),
+        PathBuf::from("../config.json"),
+    ];
+    for path in &candidates {
+        if path.exists() {
+            return path.clone();
+        }
+    }
+    // Default even if it doesn't exist yet
+    PathBuf::from("config.json")
+}
+
+/// Where a fleet member caches its source's config (`config.fleet.json`).
+pub fn fleet_cache_path(config_path: &Path) -> PathBuf {
+    config_path.with_extension("fleet.json")
+}
+
+/// If the local config names a fleet source and a cached remote config
+/// exists, return the merged JSON; otherwise return the local JSON as-is.
+fn with_fleet_cache(path: &Path, local_json: String) -> String {
+    let Ok(local) = serde_json::from_str::<serde_json::Value>(&local_json) else {
+        return local_json;
+    };
+    let has_source = local
+        .pointer("/fleet/source")
+        .and_then(|v| v.as_str())
+        .is_some_and(|s| !s.trim().is_empty());
+    if !has_source {
+        return local_json;
+    }
+    let remote = std::fs::read_to_string(fleet_cache_path(path))
+        .ok()
+        .and_then(|c| serde_json::from_str::<serde_json::Value>(&c).ok());
+    match remote {
+        Some(remote) => merge_fleet_config(&local, &remote).to_string(),
+        None => local_json,
+    }
+}
+
+/// The `stations_path` of the config at `path` (fleet-merged, relative
+/// paths against the config's directory), read ahead of `Config::load`
+/// because station names are resolved against it.
+pub fn stations_path(path: &Path) -> Option<PathBuf> {
+    let text = std::fs::read_to_string(path).ok()?;
+    let value: serde_json::Value = serde_json::from_str(&with_fleet_cache(path, text)).ok()?;
+    let stations = Path::new(value.get("stations_path")?.as_str()?);
+    Some(path.parent().map_or_else(|| stations.to_path_buf(), |dir| dir.join(stations)))
+}
+
+/// Sections a fleet member takes from its source. The rest (hardware,
+/// secrets, ports, paths) stay the member's own.
+const FLEET_SHARED_SECTIONS: [&str; 3] = ["station", "display", "alternates"];
+
+/// Build a member sign's effective config: the local config with the
+/// remote's shared sections, then the local `fleet.overrides` on top.
+pub fn merge_fleet_config(
+    local: &serde_json::Value,
+    remote: &serde_json::Value,
+) -> serde_json::Value {
+    use serde_json::Value;
+
+    let mut merged = match local {
+        Value::Object(map) => map.clone(),
+        _ => serde_json::Map::new(),
+    };
+    for section in FLEET_SHARED_SECTIONS {
+        if let Some(value) = remote.get(section) {
+            merged.insert(section.to_string(), value.clone());
+        }
+    }
+
+    let local_fleet = local.get("fleet").cloned().unwrap_or(Value::Null);
+    if let Some(Value::Object(overrides)) = local_fleet.get("overrides") {
+        for (section, value) in overrides {
+            match (merged.get_mut(section), value) {
+                (Some(Value::Object(base)), Value::Object(fields)) if section != "station" => {
+                    for (k, v) in fields {
+                        base.insert(k.clone(), v.clone());
+                    }
+                }
+                _ => {
+                    merged.insert(section.clone(), value.clone());
+                }
+            }
+        }
+    }
+
+    merged.insert("fleet".to_string(), local_fleet);
+    Value::Object(merged)
+}
+
+/// Fetch the published config from a fleet source sign.
+pub async fn fetch_fleet_config(
+    client: &reqwest::Client,
+    source_url: &str,
+) -> Result<serde_json::Value, ConfigError> {
+    let url = format!("{}/api/fleet/config", source_url);
+    let response = client
+        .get(&url)
+        .send()
+        .await
+        .map_err(|e| ConfigError::Io(format!("fetch {}: {}", url, e)))?;
+    if !response.status().is_success() {
+        return Err(ConfigError::Io(format!(
+            "fetch {}: HTTP {}",
+            url,
+            response.status()
+        )));
+    }
+    let body: serde_json::Value = response
+        .json()
+        .await
+        .map_err(|e| ConfigError::Parse(format!("fleet response: {}", e)))?;
+    match body.get("config") {
+        Some(config @ serde_json::Value::Object(_)) => Ok(config.clone()),
+        _ => Err(ConfigError::Parse(
+            "fleet response missing \"config\" object".to_string(),
+        )),
+    }
+}
+
 /// Configuration errors.
 #[derive(Debug)]
 pub enum ConfigError {
@@ -343,6 +2282,12 @@ mod tests {
         let config = Config::from_json(json).unwrap();
         assert_eq!(config.refresh.trains_interval, 20);
         assert_eq!(config.refresh.alerts_interval, 60);
+        assert_eq!(config.refresh.startup_delay_secs, 0);
+        assert_eq!(config.refresh.warmup_interval_secs, 2);
+        assert_eq!(config.display.brightness_ramp_ms, 1500);
+        assert_eq!(config.display.cycle_transition_ms, 170);
+        assert_eq!(config.display.scroll_speed, 60.0);
+        assert!(config.display.smooth_scroll);
     }
 
     #[test]
@@ -359,12 +2304,363 @@ mod tests {
             },
             "refresh": {
                 "trains_interval": 30,
-                "alerts_interval": 120
+                "alerts_interval": 120,
+                "startup_delay_secs": 5,
+                "warmup_interval_secs": 0
             }
         }"#;
         let config = Config::from_json(json).unwrap();
         assert_eq!(config.refresh.trains_interval, 30);
         assert_eq!(config.refresh.alerts_interval, 120);
+        assert_eq!(config.refresh.startup_delay_secs, 5);
+        assert_eq!(config.refresh.warmup_interval_secs, 0);
+    }
+
+    #[test]
+    fn test_screenshot_defaults_and_custom() {
+        let json = r#"{
+            "station": {"stations": [{"uptown": "127N", "downtown": "127S"}], "routes": ["1"]},
+            "display": {"brightness": 0.5, "max_trains": 7, "show_alerts": true}
+        }"#;
+        let config = Config::from_json(json).unwrap();
+        assert_eq!(config.screenshots.dir, PathBuf::from("screenshots"));
+        assert_eq!(config.screenshots.timelapse_minutes, 0);
+
+        let json = r#"{
+            "station": {"stations": [{"uptown": "127N", "downtown": "127S"}], "routes": ["1"]},
+            "display": {"brightness": 0.5, "max_trains": 7, "show_alerts": true},
+            "screenshots": {"dir": "/var/lib/sign/shots", "timelapse_minutes": 15, "scale": 4}
+        }"#;
+        let config = Config::from_json(json).unwrap();
+        assert_eq!(config.screenshots.dir, PathBuf::from("/var/lib/sign/shots"));
+        assert_eq!(config.screenshots.timelapse_minutes, 15);
+        assert_eq!(config.screenshots.scale, 4);
+    }
+
+    #[test]
+    fn test_merge_fleet_config() {
+        let remote = serde_json::json!({
+            "station": {"station_name": "Times Sq-42 St", "routes": ["1", "2", "3"]},
+            "display": {"brightness": 0.5, "max_trains": 7, "show_alerts": true},
+            "fleet": {"publish": true}
+        });
+        let local = serde_json::json!({
+            "station": {"stations": [{"uptown": "A01N", "downtown": "A01S"}], "routes": ["A"]},
+            "display": {"brightness": 0.1, "max_trains": 3, "show_alerts": false},
+            "fleet": {
+                "source": "http://lobby:5001",
+                "overrides": {
+                    "station": {"stations": [{"uptown": "127N", "downtown": "127S"}], "routes": ["1"]},
+                    "display": {"brightness": 0.2}
+                }
+            }
+        });
+        let merged = merge_fleet_config(&local, &remote);
+        let config = Config::from_json(&merged.to_string()).unwrap();
+
+        // Station replaced wholesale (no leftover station_name from remote)
+        assert_eq!(config.station_stops, vec![("127N".to_string(), "127S".to_string())]);
+        assert_eq!(config.routes, vec!["1".to_string()]);
+        // Display overridden field by field; the rest comes from the source
+        assert_eq!(config.display.brightness, 0.2);
+        assert_eq!(config.display.max_trains, 7);
+        assert!(config.display.show_alerts);
+        // Local fleet section wins
+        assert!(!config.fleet.publish);
+        assert_eq!(config.fleet.source_url(), Some("http://lobby:5001"));
+    }
+
+    #[test]
+    fn test_merge_fleet_config_keeps_member_sections() {
+        // What the source serves: secrets redacted, its own hardware
+        let remote = serde_json::json!({
+            "station": {"stations": [{"uptown": "127N", "downtown": "127S"}], "routes": ["1", "2"]},
+            "display": {"brightness": 0.7, "max_trains": 5, "show_alerts": true},
+            "panel": {"chain_length": 4},
+            "web": {"port": 8080, "upload_token_set": true},
+            "setup": {"ssid": "Lobby", "password_set": true},
+            "calendar": {"url_set": true, "keyword": "closed"},
+            "buttons": [{"pin": 5, "action": "show_ip"}],
+            "stations_path": "lobby-stations.json"
+        });
+        let local = serde_json::json!({
+            "station": {"stations": [{"uptown": "A01N", "downtown": "A01S"}], "routes": ["A"]},
+            "display": {"brightness": 0.1, "max_trains": 3, "show_alerts": false},
+            "panel": {"chain_length": 2},
+            "web": {"port": 5002, "upload_token": "member-token"},
+            "setup": {"ssid": "Kitchen", "password": "hunter22"},
+            "calendar": {"url": "https://cal.example/private.ics"},
+            "fleet": {"source": "http://lobby:5001"}
+        });
+        let merged = merge_fleet_config(&local, &remote);
+        let config = Config::from_json(&merged.to_string()).unwrap();
+
+        assert_eq!(config.routes, vec!["1".to_string(), "2".to_string()]);
+        assert_eq!(config.display.brightness, 0.7);
+        assert_eq!(merged["panel"], local["panel"]);
+        assert_eq!(merged["web"], local["web"]);
+        assert_eq!(merged["setup"], local["setup"]);
+        assert_eq!(merged["calendar"], local["calendar"]);
+        assert_eq!(config.web.upload_token.as_deref(), Some("member-token"));
+        assert!(config.buttons.is_empty());
+        assert!(config.stations_path.is_none());
+    }
+
+    #[test]
+    fn test_load_applies_fleet_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.json");
+        std::fs::write(
+            &path,
+            r#"{
+                "station": {"stations": [{"uptown": "A01N", "downtown": "A01S"}], "routes": ["A"]},
+                "display": {"brightness": 0.1, "max_trains": 3, "show_alerts": false},
+                "fleet": {"source": "http://lobby:5001"}
+            }"#,
+        )
+        .unwrap();
+
+        // No cache yet: local sections are the fallback
+        assert_eq!(Config::load(&path).unwrap().routes, vec!["A".to_string()]);
+
+        std::fs::write(
+            fleet_cache_path(&path),
+            r#"{
+                "station": {"stations": [{"uptown": "127N", "downtown": "127S"}], "routes": ["1"]},
+                "display": {"brightness": 0.5, "max_trains": 7, "show_alerts": true}
+            }"#,
+        )
+        .unwrap();
+        let config = Config::load(&path).unwrap();
+        assert_eq!(config.routes, vec!["1".to_string()]);
+        assert_eq!(config.display.max_trains, 7);
+    }
+
+    #[test]
+    fn test_stations_path_from_merged_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.json");
+        std::fs::write(&path, r#"{"stations_path": "stations.json"}"#).unwrap();
+        assert_eq!(stations_path(&path), Some(dir.path().join("stations.json")), "next to the config");
+        std::fs::write(&path, r#"{"stations_path": "/srv/stations.json"}"#).unwrap();
+        assert_eq!(stations_path(&path), Some(PathBuf::from("/srv/stations.json")));
+
+        std::fs::write(&path, r#"{"fleet": {"source": "http://lobby:5001"}}"#).unwrap();
+        assert_eq!(stations_path(&path), None);
+        std::fs::write(fleet_cache_path(&path), r#"{"stations_path": "fleet-stations.json"}"#).unwrap();
+        assert_eq!(stations_path(&path), None, "the fleet source's is its own");
+        std::fs::write(&path, r#"{"stations_path": "stations.json", "fleet": {"source": "http://lobby:5001"}}"#)
+            .unwrap();
+        assert_eq!(stations_path(&path), Some(dir.path().join("stations.json")));
+    }
+
+    #[test]
+    fn test_arrival_format() {
+        let json = r#"{
+            "station": {"stations": [{"uptown": "127N", "downtown": "127S"}], "routes": ["1"]},
+            "display": {"brightness": 0.5, "max_trains": 7, "show_alerts": true}
+        }"#;
+        let config = Config::from_json(json).unwrap();
+        assert_eq!(config.display.arrival_format, ArrivalFormat::Minutes);
+
+        let json = r#"{
+            "station": {"stations": [{"uptown": "127N", "downtown": "127S"}], "routes": ["1"]},
+            "display": {"brightness": 0.5, "max_trains": 7, "show_alerts": true, "arrival_format": "alternate"}
+        }"#;
+        let config = Config::from_json(json).unwrap();
+        assert_eq!(config.display.arrival_format, ArrivalFormat::Alternate);
+        assert_eq!(config.display.clock_after_minutes, 20);
+
+        let json = r#"{
+            "station": {"stations": [{"uptown": "127N", "downtown": "127S"}], "routes": ["1"]},
+            "display": {"brightness": 0.5, "max_trains": 7, "show_alerts": true,
+                        "arrival_format": "hybrid", "clock_after_minutes": 15}
+        }"#;
+        let config = Config::from_json(json).unwrap();
+        assert_eq!(config.display.arrival_format, ArrivalFormat::Hybrid);
+        assert_eq!(config.display.clock_after_minutes, 15);
+    }
+
+    #[test]
+    fn test_locale_and_string_overrides() {
+        let json = r#"{
+            "station": {"stations": [{"uptown": "127N", "downtown": "127S"}], "routes": ["1"]},
+            "display": {"brightness": 0.5, "max_trains": 7, "show_alerts": true,
+                        "locale": "es", "strings": {"due": "Ya"}}
+        }"#;
+        let config = Config::from_json(json).unwrap();
+        assert_eq!(config.display.locale, "es");
+        assert_eq!(config.display.strings["due"], "Ya");
+
+        let json = r#"{
+            "station": {"stations": [{"uptown": "127N", "downtown": "127S"}], "routes": ["1"]},
+            "display": {"brightness": 0.5, "max_trains": 7, "show_alerts": true, "locale": "klingon"}
+        }"#;
+        let err = Config::from_json(json).unwrap_err();
+        assert!(err.to_string().contains("klingon"));
+    }
+
+    #[test]
+    fn test_panel_config() {
+        let base = r#""station": {"stations": [{"uptown": "127N", "downtown": "127S"}], "routes": ["1"]},
+            "display": {"brightness": 0.5, "max_trains": 7, "show_alerts": true}"#;
+
+        let config = Config::from_json(&format!("{{{}}}", base)).unwrap();
+        assert_eq!(config.panel, PanelConfig::default());
+
+        let json = format!(
+            r#"{{{}, "panel": {{"chain_length": 4, "offset_x": 32, "active_width": 192}}}}"#,
+            base
+        );
+        let config = Config::from_json(&json).unwrap();
+        assert_eq!(config.panel.offset_x, 32);
+        assert_eq!(config.panel.chain_length, 4);
+
+        // 192 wide at x=32 doesn't fit three panels (192px); without a
+        // width the active area is what's left of the canvas
+        let json = format!(r#"{{{}, "panel": {{"offset_x": 32, "active_width": 192}}}}"#, base);
+        let err = Config::from_json(&json).unwrap_err();
+        assert!(err.to_string().contains("overflows"));
+        let json = format!(r#"{{{}, "panel": {{"offset_x": 32}}}}"#, base);
+        assert_eq!(Config::from_json(&json).unwrap().panel.frame_size(), (160, 32));
+
+        // Two 128x64 panels: a 256x64 sign
+        let json = format!(r#"{{{}, "panel": {{"rows": 64, "cols": 128, "chain": 2}}}}"#, base);
+        let panel = Config::from_json(&json).unwrap().panel;
+        assert_eq!((panel.canvas_size(), panel.frame_size()), ((256, 64), (256, 64)));
+        assert!(panel.needs_new_display(&PanelConfig::default()));
+        let json = format!(r#"{{{}, "panel": {{"rows": 16, "chain": 1}}}}"#, base);
+        let err = Config::from_json(&json).unwrap_err();
+        assert!(err.to_string().contains("at least 64x32"));
+
+        let json = format!(
+            r#"{{{}, "panel": {{"backend": "network", "network_target": "sign.local:7000"}}}}"#,
+            base
+        );
+        assert_eq!(Config::from_json(&json).unwrap().panel.backend, DisplayBackend::Network);
+        let json = format!(r#"{{{}, "panel": {{"backend": "headless"}}}}"#, base);
+        assert_eq!(Config::from_json(&json).unwrap().panel.backend, DisplayBackend::Headless);
+        let json = format!(r#"{{{}, "panel": {{"backend": "network"}}}}"#, base);
+        let err = Config::from_json(&json).unwrap_err();
+        assert!(err.to_string().contains("network_target"));
+        let json = format!(
+            r#"{{{}, "panel": {{"backend": "network", "network_target": "sign.local:7000",
+                "cols": 128, "chain_length": 3, "rows": 64}}}}"#,
+            base
+        );
+        let err = Config::from_json(&json).unwrap_err();
+        assert!(err.to_string().contains("too large"), "384x64 doesn't fit a datagram");
+    }
+
+    #[test]
+    fn test_alert_scroll_rules() {
+        let json = r#"{
+            "station": {"stations": [{"uptown": "127N", "downtown": "127S"}], "routes": ["1"]},
+            "display": {
+                "brightness": 0.5, "max_trains": 7, "show_alerts": true,
+                "alert_scroll": {
+                    "mode": "once",
+                    "loops": 3,
+                    "rules": [
+                        {"max_priority": 1, "mode": "until_departed"},
+                        {"min_length": 20, "mode": "loop"}
+                    ]
+                }
+            }
+        }"#;
+        let config = Config::from_json(json).unwrap();
+        let scroll = &config.display.alert_scroll;
+        let alert = |text: &str, priority| Alert {
+            text: text.to_string(),
+            affected_routes: Default::default(),
+            priority,
+            alert_id: "x".to_string(),
+            url: None,
+            is_ticker: false,
+        };
+        assert_eq!(scroll.passes_for(&alert("Suspended", 1)), ScrollPasses::UntilDeparted);
+        assert_eq!(
+            scroll.passes_for(&alert("Trains are running with delays", 3)),
+            ScrollPasses::Count(3)
+        );
+        assert_eq!(scroll.passes_for(&alert("Delays", 3)), ScrollPasses::Count(1));
+
+        let bad = json.replace(r#""loops": 3"#, r#""loops": 0"#);
+        assert!(Config::from_json(&bad).is_err());
+        assert!(!config.display.alert_ticker.enabled);
+        let ticker = json.replace(r#""alert_scroll""#, r#""alert_ticker": {"enabled": true, "interval_seconds": 7200}, "alert_scroll""#);
+        assert!(Config::from_json(&ticker).is_err(), "interval over an hour");
+    }
+
+    #[test]
+    fn test_timezone_config() {
+        let with_tz = |tz: &str| {
+            Config::from_json(&format!(
+                r#"{{"station": {{"station_name": "Times Sq-42 St", "routes": ["1"]}}, "display": {{"brightness": 0.5, "max_trains": 7, "show_alerts": true}}{}}}"#,
+                tz
+            ))
+        };
+        assert_eq!(with_tz("").unwrap().tz(), Timezone::System);
+        assert_eq!(
+            with_tz(r#", "timezone": "America/New_York""#).unwrap().tz(),
+            Timezone::parse("America/New_York").unwrap()
+        );
+        assert!(with_tz(r#", "timezone": "Eastern""#).is_err());
+    }
+
+    #[test]
+    fn test_icon_config_validation() {
+        let with_icons = |icons: &str| {
+            Config::from_json(&format!(
+                r#"{{
+                    "station": {{"stations": [{{"uptown": "127N", "downtown": "127S"}}], "routes": ["1"]}},
+                    "display": {{"brightness": 0.5, "max_trains": 7, "show_alerts": true}},
+                    "icons": {}
+                }}"#,
+                icons
+            ))
+        };
+        let config = with_icons(r##"{"colors": {"A": "#0039a6"}, "custom": {"HOME": "home.png"}}"##).unwrap();
+        assert_eq!(config.icons.colors["A"], "#0039a6");
+        assert_eq!(config.icons.custom["HOME"], PathBuf::from("home.png"));
+
+        assert!(with_icons(r#"{"colors": {"A": "blue-ish"}}"#).is_err());
+        assert!(with_icons(r#"{"custom": {"Home": "home.png"}}"#).is_err());
+    }
+
+    #[test]
+    fn test_trip_config() {
+        let with_trip = |trip: &str| {
+            Config::from_json(&format!(
+                r#"{{
+                    "station": {{"stations": [{{"uptown": "L06N", "downtown": "L06S"}}], "routes": ["L"]}},
+                    "display": {{"brightness": 0.5, "max_trains": 7, "show_alerts": true}},
+                    "trip": {}
+                }}"#,
+                trip
+            ))
+        };
+        let config = with_trip(
+            r#"{"first": {"routes": ["L"]}, "transfer_station": "14 St-Union Sq",
+                "second": {"routes": ["4", "5"], "direction": "uptown"}, "ride_minutes": 3}"#,
+        )
+        .unwrap();
+        let trip = config.trip.unwrap();
+        assert_eq!(trip.transfer_minutes, 3);
+        assert!(trip.transfer_stop_ids().contains(&"635N".to_string()));
+
+        assert!(matches!(
+            with_trip(
+                r#"{"first": {"routes": ["L"]}, "transfer_station": "Nowhere Junction",
+                    "second": {"routes": ["4"]}, "ride_minutes": 3}"#
+            ),
+            Err(ConfigError::StationNotFound(_))
+        ));
+        assert!(with_trip(
+            r#"{"first": {"routes": []}, "transfer_station": "14 St-Union Sq",
+                "second": {"routes": ["4"]}, "ride_minutes": 3}"#
+        )
+        .is_err());
     }
 
     #[test]
@@ -474,6 +2770,106 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_snapshot_config_keeps_recent_history() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.json");
+        snapshot_config(&path).unwrap();
+        assert!(config_history(&path).is_empty(), "nothing to snapshot yet");
+
+        for i in 0..HISTORY_KEEP + 2 {
+            std::fs::write(&path, format!("{{\"version\":{}}}", i)).unwrap();
+            snapshot_config(&path).unwrap();
+        }
+        let history = config_history(&path);
+        assert_eq!(history.len(), HISTORY_KEEP);
+        let newest = std::fs::read_to_string(&history[0]).unwrap();
+        assert_eq!(newest, format!("{{\"version\":{}}}", HISTORY_KEEP + 1));
+    }
+
+    #[test]
+    fn test_diff_json_field_paths() {
+        let from = serde_json::json!({
+            "station": {"stations": [{"uptown": "127N"}], "routes": ["1", "2"]},
+            "display": {"brightness": 0.5, "theme": "classic"}
+        });
+        let to = serde_json::json!({
+            "station": {"stations": [{"uptown": "631N"}], "routes": ["1"]},
+            "display": {"brightness": 0.5, "max_trains": 4}
+        });
+        let changes: Vec<(String, &str)> =
+            diff_json(&from, &to).into_iter().map(|c| (c.path, c.change)).collect();
+        assert_eq!(
+            changes,
+            vec![
+                ("display.theme".to_string(), "removed"),
+                ("display.max_trains".to_string(), "added"),
+                ("station.routes[1]".to_string(), "removed"),
+                ("station.stations[0].uptown".to_string(), "changed"),
+            ]
+        );
+        assert!(diff_json(&from, &from).is_empty());
+    }
+
+    #[test]
+    fn test_secrets_redacted_and_restored() {
+        let current = serde_json::json!({ "web": { "port": 5001, "upload_token": "s3cret" } });
+        let mut shown = current.clone();
+        redact_secrets(&mut shown);
+        assert_eq!(shown, serde_json::json!({ "web": { "port": 5001, "upload_token_set": true } }));
+
+        // Posted back unchanged: the token is kept
+        let mut edited = shown.clone();
+        edited["web"]["port"] = 5002.into();
+        restore_secrets(&mut edited, &current);
+        assert_eq!(edited, serde_json::json!({ "web": { "port": 5002, "upload_token": "s3cret" } }));
+
+        // A new token replaces it
+        let mut edited = serde_json::json!({ "web": { "upload_token": "new" } });
+        restore_secrets(&mut edited, &current);
+        assert_eq!(edited["web"]["upload_token"], "new");
+
+        let mut shown = serde_json::json!({ "setup": { "ssid": "Sign", "password": "hunter22" } });
+        redact_secrets(&mut shown);
+        assert_eq!(shown, serde_json::json!({ "setup": { "ssid": "Sign", "password_set": true } }));
+        let mut shown = serde_json::json!({ "setup": { "ssid": "Sign" } });
+        redact_secrets(&mut shown);
+        assert_eq!(shown["setup"]["password_set"], false);
+
+        let current = serde_json::json!({ "calendar": { "url": "https://cal.example/private/abc.ics" } });
+        let mut shown = current.clone();
+        redact_secrets(&mut shown);
+        assert_eq!(shown, serde_json::json!({ "calendar": { "url_set": true } }));
+        restore_secrets(&mut shown, &current);
+        assert_eq!(shown, current);
+    }
+
+    #[test]
+    fn test_file_only_fields_kept() {
+        let current = serde_json::json!({ "static_gtfs": { "enabled": false, "cache_path": "gtfs.zip" } });
+        let mut edited = serde_json::json!({ "static_gtfs": {
+            "enabled": true,
+            "url": "http://evil.example/x.zip",
+            "cache_path": "/root/.ssh/authorized_keys",
+        } });
+        keep_file_only(&mut edited, &current);
+        assert_eq!(edited, serde_json::json!({ "static_gtfs": { "enabled": true, "cache_path": "gtfs.zip" } }));
+
+        // A new upload token can't be set over the web, with or without one set
+        let current = serde_json::json!({ "web": { "port": 5001, "assets_dir": "uploads" } });
+        let mut edited = serde_json::json!({ "web": { "port": 5001, "upload_token": "mine", "assets_dir": "/etc" } });
+        keep_file_only(&mut edited, &current);
+        assert_eq!(edited, current);
+        let current = serde_json::json!({ "web": { "upload_token": "s3cret", "assets_dir": "uploads" } });
+        keep_file_only(&mut edited, &current);
+        assert_eq!(edited["web"]["upload_token"], "s3cret");
+
+        let current = serde_json::json!({ "screenshots": { "dir": "screenshots", "scale": 1 } });
+        let mut edited = serde_json::json!({ "screenshots": { "dir": "/etc/cron.d", "scale": 4 } });
+        keep_file_only(&mut edited, &current);
+        assert_eq!(edited, serde_json::json!({ "screenshots": { "dir": "screenshots", "scale": 4 } }));
+    }
+
     #[test]
     fn test_load_falls_back_to_backup() {
         let dir = tempfile::tempdir().unwrap();
@@ -516,4 +2912,302 @@ mod tests {
         let config = Config::from_json(json).unwrap();
         assert!(!config.routes.is_empty(), "should auto-detect routes");
     }
+
+    #[test]
+    fn test_route_schedule() {
+        let json = |schedule: &str| {
+            format!(
+                r#"{{"station":{{"stations":[{{"uptown":"D14N","downtown":"D14S"}}],"routes":["B","D"]}},
+                "display":{{"brightness":0.5,"max_trains":6,"show_alerts":true}},
+                "route_schedule":{}}}"#,
+                schedule
+            )
+        };
+        let config = Config::from_json(&json(
+            r#"[{"days":["sat","sun"],"routes":["D"]},
+                {"start":"23:00","end":"06:00","routes":["D"]}]"#,
+        ))
+        .unwrap();
+        let at = |date: &str, time: &str| {
+            chrono::NaiveDateTime::parse_from_str(&format!("{} {}", date, time), "%Y-%m-%d %H:%M").unwrap()
+        };
+        // 2026-03-06 is a Friday
+        assert_eq!(config.scheduled_routes(at("2026-03-06", "12:00")), ["B", "D"]);
+        assert_eq!(config.scheduled_routes(at("2026-03-06", "23:30")), ["D"]);
+        assert_eq!(config.scheduled_routes(at("2026-03-07", "05:59")), ["D"], "night window runs past midnight");
+        assert_eq!(config.scheduled_routes(at("2026-03-07", "12:00")), ["D"], "weekend");
+        assert_eq!(config.scheduled_routes(at("2026-03-09", "06:00")), ["B", "D"]);
+
+        assert!(Config::from_json(&json(r#"[{"routes":["A"]}]"#)).is_err(), "unconfigured route");
+        assert!(Config::from_json(&json(r#"[{"days":["weekend"],"routes":["D"]}]"#)).is_err());
+        assert!(Config::from_json(&json(r#"[{"start":"25:00","routes":["D"]}]"#)).is_err());
+    }
+
+    #[test]
+    fn test_brightness_schedule() {
+        let json = |schedule: &str| {
+            format!(
+                r#"{{"station":{{"stations":[{{"uptown":"D14N","downtown":"D14S"}}],"routes":["B","D"]}},
+                "display":{{"brightness":0.5,"max_trains":6,"show_alerts":true,"schedule":{}}}}}"#,
+                schedule
+            )
+        };
+        let at = |time: &str| chrono::NaiveTime::parse_from_str(time, "%H:%M").unwrap();
+
+        let config = Config::from_json(&json("[]")).unwrap();
+        assert_eq!(config.display.brightness_at(at("03:00")), 0.5, "no schedule");
+
+        let config = Config::from_json(&json(
+            r#"[{"time":"22:00","brightness":0.1},{"time":"07:00","brightness":0.6}]"#,
+        ))
+        .unwrap();
+        assert_eq!(config.display.brightness_at(at("12:00")), 0.6);
+        assert_eq!(config.display.brightness_at(at("22:00")), 0.1);
+        assert_eq!(config.display.brightness_at(at("06:59")), 0.1, "night level runs past midnight");
+        assert_eq!(config.display.brightness_at(at("07:00")), 0.6);
+
+        assert!(Config::from_json(&json(r#"[{"time":"7am","brightness":0.6}]"#)).is_err());
+        assert!(Config::from_json(&json(r#"[{"time":"07:00","brightness":60}]"#)).is_err());
+    }
+
+    #[test]
+    fn test_off_hours() {
+        let json = |off: &str| {
+            format!(
+                r#"{{"station":{{"stations":[{{"uptown":"D14N","downtown":"D14S"}}],"routes":["B","D"]}},
+                "display":{{"brightness":0.5,"max_trains":6,"show_alerts":true,"off_hours":{}}}}}"#,
+                off
+            )
+        };
+        let at = |time: &str| chrono::NaiveTime::parse_from_str(time, "%H:%M").unwrap();
+
+        let config = Config::from_json(&json(r#"{"from":"01:00","to":"05:30"}"#)).unwrap();
+        assert!(!config.display.is_off_at(at("00:59")));
+        assert!(config.display.is_off_at(at("01:00")));
+        assert!(!config.display.is_off_at(at("05:30")));
+
+        let config = Config::from_json(&json(r#"{"from":"23:30","to":"06:00"}"#)).unwrap();
+        assert!(config.display.is_off_at(at("23:45")) && config.display.is_off_at(at("03:00")));
+        assert!(!config.display.is_off_at(at("12:00")));
+
+        assert!(Config::from_json(&json(r#"{"from":"1am","to":"05:30"}"#)).is_err());
+        assert!(Config::from_json(&json(r#"{"from":"05:30","to":"05:30"}"#)).is_err());
+    }
+
+    #[test]
+    fn test_config_diff() {
+        let base = rich_config_json();
+        let with = |path: &[&str], value: serde_json::Value| {
+            let mut json = base.clone();
+            let (last, parents) = path.split_last().unwrap();
+            let section = parents.iter().fold(&mut json, |v, key| &mut v[*key]);
+            section[*last] = value;
+            Config::from_json(&json.to_string()).unwrap()
+        };
+        let old = Config::from_json(&base.to_string()).unwrap();
+
+        assert_eq!(old.diff(&old), ConfigChange::default());
+        let brightness = with(&["display", "brightness"], serde_json::json!(0.9));
+        assert_eq!(brightness.diff(&old), ConfigChange { data: false, display: true });
+        let routes = with(&["station", "routes"], serde_json::json!(["L", "6", "5", "4"]));
+        assert_eq!(routes.diff(&old), ConfigChange { data: true, display: false });
+        let max_trains = with(&["display", "max_trains"], serde_json::json!(3));
+        assert_eq!(max_trains.diff(&old), ConfigChange { data: true, display: true });
+    }
+
+    #[test]
+    fn test_top_row_stop_must_be_configured() {
+        let json = |stop: &str| {
+            format!(
+                r#"{{"station":{{"stations":[{{"uptown":"D14N","downtown":"D14S"}}],"routes":["B","D"]}},
+                "display":{{"brightness":0.5,"max_trains":6,"show_alerts":true,"top_row":[{{"stop":"{}"}}]}}}}"#,
+                stop
+            )
+        };
+        assert!(Config::from_json(&json("D14S")).is_ok());
+        assert!(Config::from_json(&json("d14")).is_ok(), "platform without direction");
+        assert!(Config::from_json(&json("635S")).is_err());
+    }
+
+    #[test]
+    fn test_route_service_needs_express_route() {
+        let json = |route: &str| {
+            format!(
+                r#"{{"station":{{"station_name":"Times Sq-42 St"}},
+                "display":{{"brightness":0.5,"max_trains":6,"show_alerts":true,"route_service":{{"{}":"express"}}}}}}"#,
+                route
+            )
+        };
+        let config = Config::from_json(&json("7")).unwrap();
+        assert_eq!(config.display.route_service["7"], ServiceFilter::Express);
+        assert!(Config::from_json(&json("N")).unwrap_err().to_string().contains("never runs express"));
+    }
+
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_pages_config() {
+        let json = |pages: &str| {
+            format!(
+                r#"{{"station":{{"station_name":"Times Sq-42 St"}},
+                "display":{{"brightness":0.5,"max_trains":6,"show_alerts":true,"pages":{}}}}}"#,
+                pages
+            )
+        };
+        let config = Config::from_json(&json(
+            r#"[{"page": "trains", "seconds": 20}, {"page": "clock"}, {"page": "message", "lines": ["Hi"], "seconds": 3}]"#,
+        ))
+        .unwrap();
+        assert_eq!(
+            config.display.pages,
+            vec![
+                Page { kind: PageKind::Trains, seconds: 20 },
+                Page { kind: PageKind::Clock, seconds: 10 },
+                Page { kind: PageKind::Message { lines: vec!["Hi".into()] }, seconds: 3 },
+            ]
+        );
+        assert!(Config::from_json(&json(r#"[{"page": "message", "lines": []}]"#)).is_err());
+        assert!(Config::from_json(&json(r#"[{"page": "clock", "seconds": 0}]"#)).is_err());
+        assert!(Config::from_json(&json(r#"[{"page": "weather"}]"#)).is_err());
+    }
+
+    #[test]
+    fn test_watch_config() {
+        let json = |watch: &str| {
+            format!(
+                r#"{{"station":{{"station_name":"Times Sq-42 St","routes":["1","2"]}},
+                "display":{{"brightness":0.5,"max_trains":6,"show_alerts":true,"watch":{}}}}}"#,
+                watch
+            )
+        };
+        let config = Config::from_json(&json(r#"{"route":"2","direction":"downtown"}"#)).unwrap();
+        let watch = config.display.watch.unwrap();
+        assert_eq!((watch.direction, watch.minutes, watch.effect), (Direction::Downtown, 5, Highlight::Border));
+        let config = Config::from_json(&json(r#"{"route":"1","direction":"uptown","effect":"invert"}"#)).unwrap();
+        assert_eq!(config.display.watch.unwrap().effect, Highlight::Invert);
+        assert!(Config::from_json(&json(r#"{"route":"Q","direction":"uptown"}"#)).is_err());
+        assert!(Config::from_json(&json(r#"{"route":"1","direction":"uptown","seconds":0}"#)).is_err());
+    }
+
+    #[test]
+    fn test_rich_config_is_valid() {
+        Config::from_json(&rich_config_json().to_string()).unwrap();
+    }
+
+    /// A config using most sections, as a base for mutation.
+    fn rich_config_json() -> serde_json::Value {
+        serde_json::json!({
+            "station": {
+                "stations": [{"uptown": "635N", "downtown": "635S"}, {"uptown": "L03N", "downtown": "L03S"}],
+                "routes": ["4", "5", "6", "L"]
+            },
+            "display": {
+                "brightness": 0.3,
+                "max_trains": 7,
+                "show_alerts": true,
+                "alert_ticker": {"enabled": true, "interval_seconds": 30},
+                "layout": "by_direction",
+                "idle_clock": true,
+                "top_row": [{"stop": "L03S"}, {"direction": "downtown"}],
+                "off_hours": {"from": "01:00", "to": "05:30"},
+                "schedule": [{"time": "07:00", "brightness": 0.6}, {"time": "22:00", "brightness": 0.1}],
+                "post": [{"gamma": 2.2}, {"color_temperature": 5000}, {"rotate": 180},
+                         {"pixel_shift": {"pixels": 1, "interval_minutes": 10}}, {"dither": 5}]
+            },
+            "refresh": {"trains_interval": 30, "alerts_interval": 60, "dedup_window_secs": 45},
+            "panel": {"chain_length": 3, "offset_x": 0, "offset_y": 0, "active_width": 192, "active_height": 32},
+            "icons": {"colors": {"L": "#a7a9ac", "6": "green"}},
+            "journey": {"walk_minutes": {"635": 6}, "routes": ["6", "4"], "direction": "downtown"},
+            "trip": {
+                "first": {"routes": ["L"], "direction": "downtown"},
+                "transfer_station": "14 St-Union Sq",
+                "second": {"routes": ["4", "5"], "direction": "uptown"},
+                "ride_minutes": 3,
+                "transfer_minutes": 3
+            },
+            "alternates": {"primary": "6", "routes": ["4", "5"]},
+            "buttons": [{"pin": 17, "action": "show_ip"}],
+            "route_schedule": [
+                {"days": ["sat", "sun"], "routes": ["L"]},
+                {"start": "23:00", "end": "06:00", "routes": ["4", "6"]}
+            ],
+            "static_gtfs": {"enabled": true, "refresh_hours": 24},
+            "fleet": {"publish": true, "poll_interval": 60},
+            "calendar": {"url": "webcal://example.com/vacation.ics", "keyword": "vacation"},
+            "timezone": "America/New_York"
+        })
+    }
+
+    /// Arbitrary JSON, biased towards values near config limits.
+    fn any_json() -> impl Strategy<Value = serde_json::Value> {
+        use serde_json::Value;
+        let leaf = prop_oneof![
+            Just(Value::Null),
+            any::<bool>().prop_map(Value::Bool),
+            any::<i64>().prop_map(Value::from),
+            any::<u64>().prop_map(Value::from),
+            (-1000i64..1000).prop_map(Value::from),
+            any::<f64>().prop_map(Value::from),
+            (-2.0f64..2.0).prop_map(Value::from),
+            "\\PC{0,12}".prop_map(Value::from),
+            "[0-9:]{0,6}".prop_map(Value::from),
+            prop_oneof![Just(""), Just("25:00"), Just("é"), Just("6X"), Just("sat"), Just("N")].prop_map(Value::from),
+        ];
+        leaf.prop_recursive(3, 24, 4, |inner| {
+            prop_oneof![
+                prop::collection::vec(inner.clone(), 0..4).prop_map(Value::Array),
+                prop::collection::btree_map("[a-z_]{1,10}", inner, 0..4)
+                    .prop_map(|m| Value::Object(m.into_iter().collect())),
+            ]
+        })
+    }
+
+    /// Every node of `value` by JSON pointer.
+    fn json_pointers(value: &serde_json::Value, path: String, out: &mut Vec<String>) {
+        match value {
+            serde_json::Value::Object(map) => {
+                for (k, v) in map {
+                    json_pointers(v, format!("{}/{}", path, k), out);
+                }
+            }
+            serde_json::Value::Array(items) => {
+                for (i, v) in items.iter().enumerate() {
+                    json_pointers(v, format!("{}/{}", path, i), out);
+                }
+            }
+            _ => {}
+        }
+        out.push(path);
+    }
+
+    proptest! {
+        #[test]
+        fn prop_from_json_never_panics(text in "\\PC{0,64}") {
+            let _ = Config::from_json(&text);
+        }
+
+        /// Hand edits: one value anywhere in a valid config replaced. A
+        /// config that loads must also be usable downstream.
+        #[test]
+        fn prop_mutated_config_never_panics(target in any::<prop::sample::Index>(), value in any_json(), minute in 0u32..(7 * 24 * 60)) {
+            let mut json = rich_config_json();
+            let mut pointers = Vec::new();
+            json_pointers(&json, String::new(), &mut pointers);
+            let pointer = target.get(&pointers);
+            if pointer.is_empty() {
+                json = value;
+            } else {
+                *json.pointer_mut(pointer).unwrap() = value;
+            }
+
+            if let Ok(config) = Config::from_json(&json.to_string()) {
+                // 2026-03-02 is a Monday
+                let monday = chrono::NaiveDate::from_ymd_opt(2026, 3, 2).unwrap().and_hms_opt(0, 0, 0).unwrap();
+                config.scheduled_routes(monday + chrono::Duration::minutes(minute as i64));
+                let fb = crate::display::framebuffer::FrameBuffer::new();
+                let elapsed = std::time::Duration::from_secs(minute as u64 * 60);
+                crate::display::post::Pipeline::new(&config.display.post).apply(&fb, elapsed);
+            }
+        }
+    }
 }
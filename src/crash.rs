@@ -0,0 +1,243 @@
+//! Crash reports: what the process was doing when it panicked.
+//!
+//! The panic hook writes one JSON report per panic to `crash-reports/`
+//! next to config.json (newest `MAX_REPORTS` kept). `/api/status` shows the
+//! latest, so an overnight crash that systemd quietly restarted from can
+//! still be diagnosed in the morning.
+
+use std::any::Any;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::logging::LogLine;
+use crate::models::DisplaySnapshot;
+
+/// Report directory name, kept next to config.json.
+const CRASH_DIR: &str = "crash-reports";
+
+/// Reports kept on disk; older ones are deleted when a new one is written.
+const MAX_REPORTS: usize = 10;
+
+/// Log lines included in a report.
+const LOG_LINES: usize = 50;
+
+/// Trains listed in the snapshot summary.
+const SNAPSHOT_TRAINS: usize = 3;
+
+/// Everything captured at panic time.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CrashReport {
+    /// RFC 3339 local time of the panic.
+    pub time: String,
+    pub version: String,
+    pub thread: String,
+    pub message: String,
+    /// `file:line:column` of the panic.
+    pub location: Option<String>,
+    pub backtrace: String,
+    pub snapshot: SnapshotSummary,
+    /// Most recent log lines, oldest first.
+    pub recent_logs: Vec<String>,
+}
+
+/// What the sign was showing.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SnapshotSummary {
+    pub trains: usize,
+    pub alerts: usize,
+    pub fetched_at: f64,
+    /// First few trains, e.g. "Q 3min Astoria-Ditmars Blvd".
+    pub next: Vec<String>,
+}
+
+impl SnapshotSummary {
+    pub fn new(snapshot: &DisplaySnapshot) -> Self {
+        SnapshotSummary {
+            trains: snapshot.trains.len(),
+            alerts: snapshot.alerts.len(),
+            fetched_at: snapshot.fetched_at,
+            next: snapshot
+                .trains
+                .iter()
+                .take(SNAPSHOT_TRAINS)
+                .map(|t| format!("{} {}min {}", t.route, t.minutes, t.destination))
+                .collect(),
+        }
+    }
+}
+
+/// Newest report, as shown in `/api/status`.
+#[derive(Debug, Serialize)]
+pub struct CrashSummary {
+    pub time: String,
+    pub version: String,
+    pub message: String,
+    pub location: Option<String>,
+    /// Report file name inside the crash directory.
+    pub file: String,
+}
+
+impl CrashReport {
+    /// Capture a report for the current panic. `logs` may be empty if the
+    /// log buffer was unavailable.
+    pub fn capture(
+        message: String,
+        location: Option<String>,
+        snapshot: &DisplaySnapshot,
+        logs: &[LogLine],
+    ) -> Self {
+        let thread = std::thread::current();
+        CrashReport {
+            time: chrono::Local::now().to_rfc3339(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            thread: thread.name().unwrap_or("unnamed").to_string(),
+            message,
+            location,
+            backtrace: std::backtrace::Backtrace::force_capture().to_string(),
+            snapshot: SnapshotSummary::new(snapshot),
+            recent_logs: logs[logs.len().saturating_sub(LOG_LINES)..]
+                .iter()
+                .map(|l| format!("{} {} {}: {}", l.timestamp, l.level, l.target, l.message))
+                .collect(),
+        }
+    }
+}
+
+/// Text of a panic payload (`panic!` with a literal or a format string).
+pub fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "(non-string panic payload)".to_string()
+    }
+}
+
+/// Location of the crash directory for a given config path.
+pub fn crash_dir(config_path: &Path) -> PathBuf {
+    match config_path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.join(CRASH_DIR),
+        _ => PathBuf::from(CRASH_DIR),
+    }
+}
+
+/// Report files, oldest first (names sort by time).
+fn report_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|e| e.path())
+                .filter(|p| {
+                    p.file_name()
+                        .and_then(|n| n.to_str())
+                        .is_some_and(|n| n.starts_with("crash-") && n.ends_with(".json"))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    files.sort();
+    files
+}
+
+/// Write a report as `<dir>/crash-YYYYmmdd-HHMMSS-mmm.json`, then prune old ones.
+pub fn write_report(dir: &Path, report: &CrashReport) -> Result<PathBuf, String> {
+    std::fs::create_dir_all(dir).map_err(|e| format!("create {}: {}", dir.display(), e))?;
+    let name = format!(
+        "crash-{}.json",
+        chrono::Local::now().format("%Y%m%d-%H%M%S-%3f")
+    );
+    let path = dir.join(name);
+    let json = serde_json::to_vec_pretty(report).map_err(|e| format!("serialize: {}", e))?;
+    std::fs::write(&path, json).map_err(|e| format!("write {}: {}", path.display(), e))?;
+
+    let files = report_files(dir);
+    for old in &files[..files.len().saturating_sub(MAX_REPORTS)] {
+        let _ = std::fs::remove_file(old);
+    }
+    Ok(path)
+}
+
+/// Summary of the newest readable report, if any.
+pub fn latest_report(dir: &Path) -> Option<CrashSummary> {
+    let path = report_files(dir).pop()?;
+    let report: CrashReport = serde_json::from_slice(&std::fs::read(&path).ok()?).ok()?;
+    Some(CrashSummary {
+        time: report.time,
+        version: report.version,
+        message: report.message,
+        location: report.location,
+        file: path.file_name()?.to_string_lossy().into_owned(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Direction, Train};
+
+    fn log(message: &str) -> LogLine {
+        LogLine {
+            timestamp: "2026-03-01T03:12:00-05:00".into(),
+            level: "WARN".into(),
+            target: "subway_sign".into(),
+            message: message.into(),
+        }
+    }
+
+    #[test]
+    fn test_report_roundtrip_and_latest() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(latest_report(dir.path()).is_none());
+
+        let mut snapshot = DisplaySnapshot::empty();
+        snapshot.trains.push(Train {
+            route: "Q".into(),
+            destination: "Astoria".into(),
+            minutes: 3,
+            is_express: false,
+            arrival_timestamp: 0.0,
+            direction: Direction::Uptown,
+            stop_id: "R17N".into(),
+            delay_secs: None,
+            trip_id: String::new(),
+        });
+        let logs: Vec<LogLine> = (0..LOG_LINES + 5).map(|i| log(&i.to_string())).collect();
+        let report = CrashReport::capture(
+            "index out of bounds".into(),
+            Some("src/main.rs:1:1".into()),
+            &snapshot,
+            &logs,
+        );
+        assert_eq!(report.snapshot.next, vec!["Q 3min Astoria"]);
+        assert_eq!(report.recent_logs.len(), LOG_LINES);
+        assert!(report.recent_logs.last().unwrap().ends_with(": 54"));
+
+        let path = write_report(dir.path(), &report).unwrap();
+        let latest = latest_report(dir.path()).unwrap();
+        assert_eq!(latest.message, "index out of bounds");
+        assert_eq!(latest.file, path.file_name().unwrap().to_str().unwrap());
+    }
+
+    #[test]
+    fn test_old_reports_pruned() {
+        let dir = tempfile::tempdir().unwrap();
+        for i in 0..MAX_REPORTS + 3 {
+            std::fs::write(dir.path().join(format!("crash-20260101-0000{:02}-000.json", i)), "{}")
+                .unwrap();
+        }
+        let report = CrashReport::capture("boom".into(), None, &DisplaySnapshot::empty(), &[]);
+        write_report(dir.path(), &report).unwrap();
+        assert_eq!(report_files(dir.path()).len(), MAX_REPORTS);
+    }
+
+    #[test]
+    fn test_panic_message() {
+        let literal = std::panic::catch_unwind(|| panic!("literal")).unwrap_err();
+        assert_eq!(panic_message(literal.as_ref()), "literal");
+        let formatted = std::panic::catch_unwind(|| panic!("code {}", 7)).unwrap_err();
+        assert_eq!(panic_message(formatted.as_ref()), "code 7");
+    }
+}
@@ -0,0 +1,2020 @@
+//! The sign daemon: task orchestration, config hot-reload and the render
+//! loop, as run by the `subway-sign` binary.
+
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Instant, SystemTime};
+
+use arc_swap::ArcSwap;
+use tokio::signal;
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, info_span, warn, Instrument};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::Layer as _;
+use tracing_subscriber::util::SubscriberInitExt;
+
+use crate::{
+    analytics, buttons, calendar, config, crash, display, instance, journey, lifecycle, logging,
+    models, mta, screenshot, selection, setup, telemetry, trip, web,
+};
+#[cfg(feature = "persistence")]
+use crate::persistence;
+use crate::{config_mismatch_secs, unix_now_secs, AppState, FRAME_UPDATES_CAPACITY};
+
+use analytics::Analytics;
+use config::{Config, DisplayBackend, PanelConfig};
+use display::activity::FrameActivity;
+use display::brightness::BrightnessRamp;
+use display::clock::ClockRenderer;
+use display::framebuffer::FrameBuffer;
+use display::matrix::{create_display, MockDisplay, SwapDedup, Viewport};
+use display::post::Pipeline;
+use display::timing::FrameTimings;
+use display::renderer::{Banner, Renderer};
+use lifecycle::RestartControl;
+use instance::Instance;
+use logging::{InstancePrefix, LogHub};
+use models::{Alert, DisplaySnapshot, Train};
+use selection::Suspension;
+use web::access_log::RequestLog;
+use web::raw_frame::FrameHistory;
+use mta::alerts::AlertManager;
+use mta::client::MtaClient;
+
+/// Run the sign: load the config, start the fetch, web and render tasks,
+/// and return once shut down. An `instance` runs out of its own directory.
+pub async fn run(instance: Option<Instance>) {
+    // Initialize tracing (structured logging), also feeding the web log stream
+    let logs = LogHub::new();
+    let format = InstancePrefix {
+        name: instance.as_ref().map(|i| i.name.clone()),
+        inner: tracing_subscriber::fmt::format(),
+    };
+    let registry = tracing_subscriber::registry()
+        .with(tracing_subscriber::fmt::layer().event_format(format).with_filter(logging::console_filter()))
+        .with(logs.clone().with_filter(logging::stream_filter()));
+    #[cfg(feature = "otlp")]
+    let registry = registry.with(telemetry::otlp_layer().with_filter(logging::console_filter()));
+    registry.init();
+
+    info!("NYC Subway Sign (Rust) starting");
+
+    if let Some(instance) = &instance {
+        if let Err(e) = instance.enter() {
+            error!("{}", e);
+            std::process::exit(1);
+        }
+        info!("Instance '{}' in {}", instance.name, instance.dir.display());
+    }
+
+    // Find config file (an instance's is always its own)
+    let config_path = match &instance {
+        Some(_) => PathBuf::from("config.json"),
+        None => config::find_config_path(),
+    };
+    info!("Config file: {}", config_path.display());
+
+    crate::load_station_database(&config_path);
+
+    // Load initial config
+    let initial_config = match Config::load(&config_path) {
+        Ok(cfg) => {
+            info!(
+                "Config loaded: {} platforms, routes: {}, brightness: {:.0}%",
+                cfg.station_stops.len(),
+                cfg.routes.join(","),
+                cfg.display.brightness * 100.0
+            );
+            cfg
+        }
+        Err(e) => {
+            error!("Failed to load config: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    // Icon customizations are baked into the font before anything renders
+    if initial_config.icons.dev_mode() {
+        info!("[FONT] Developer mode: font/icons read from disk and reloaded on change");
+    }
+    display::fonts::init_font(&initial_config.icons);
+    display::glyphs::log_coverage();
+
+    // Pick up the previous process's display state, if it just restarted
+    let handoff_path = lifecycle::handoff_path(&config_path);
+    let restored = lifecycle::take_handoff(&handoff_path);
+    let (restored_frame, initial_snapshot) = match restored {
+        Some(r) => {
+            info!(
+                "[RESTORE] Showing previous display ({} trains) until first fetch",
+                r.snapshot.trains.len()
+            );
+            (Some(r.frame), r.snapshot)
+        }
+        None => (None, DisplaySnapshot::empty()),
+    };
+
+    // Build shared state
+    let state = Arc::new(AppState {
+        config: ArcSwap::from_pointee(initial_config.clone()),
+        snapshot: ArcSwap::from_pointee(initial_snapshot),
+        trips: ArcSwap::from_pointee(HashMap::new()),
+        transfer_trains: ArcSwap::from_pointee(Vec::new()),
+        latest_frame: ArcSwap::from_pointee(FrameBuffer::new()),
+        frame_updates: tokio::sync::broadcast::channel(FRAME_UPDATES_CAPACITY).0,
+        frame_activity: Mutex::new(FrameActivity::new()),
+        frame_timing: ArcSwap::from_pointee(Vec::new()),
+        fetch_trace: ArcSwap::from_pointee(telemetry::FetchTrace::none()),
+        pipeline: ArcSwap::from_pointee(Default::default()),
+        alert_manager: Mutex::new(AlertManager::new()),
+        fetched_alerts: ArcSwap::from_pointee(Vec::new()),
+        suspended_routes: ArcSwap::from_pointee(HashSet::new()),
+        active_routes: ArcSwap::from_pointee(
+            initial_config.scheduled_routes(initial_config.tz().now()),
+        ),
+        degraded_routes: ArcSwap::from_pointee(Vec::new()),
+        service_schedule: ArcSwap::from_pointee(None),
+        setup_ssid: ArcSwap::from_pointee(None),
+        info_screen: ArcSwap::from_pointee(None),
+        analytics: Mutex::new(Analytics::new(
+            analytics_store(&initial_config),
+            initial_config.tz().today(),
+        )),
+        logs,
+        http_log: RequestLog::new(),
+        served_frames: FrameHistory::new(),
+        config_path: config_path.clone(),
+        instance: instance.map(|i| i.name),
+        shutdown: CancellationToken::new(),
+        restart: RestartControl::new(),
+        config_changed: tokio::sync::Notify::new(),
+        fleet_sync: tokio::sync::Notify::new(),
+        last_fetch_success: AtomicU64::new(0),
+        no_match_since: AtomicU64::new(0),
+        away_days: ArcSwap::from_pointee(HashSet::new()),
+        do_not_disturb: AtomicBool::new(false),
+        recording: AtomicBool::new(false),
+        favorites_lock: Mutex::new(()),
+        last_render_tick: AtomicU64::new(0),
+    });
+
+    // On panic: write a crash report, then hand the display over
+    // (systemd restarts us)
+    let panic_state = Arc::clone(&state);
+    let panic_handoff = handoff_path.clone();
+    let crash_dir = crash::crash_dir(&config_path);
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let report = crash::CrashReport::capture(
+            crash::panic_message(info.payload()),
+            info.location().map(|l| l.to_string()),
+            &panic_state.snapshot.load(),
+            &panic_state.logs.try_recent().unwrap_or_default(),
+        );
+        match crash::write_report(&crash_dir, &report) {
+            Ok(path) => eprintln!("Crash report written to {}", path.display()),
+            Err(e) => eprintln!("Failed to write crash report: {}", e),
+        }
+        let _ = lifecycle::save_handoff(
+            &panic_handoff,
+            &panic_state.latest_frame.load(),
+            &panic_state.snapshot.load(),
+        );
+        default_hook(info);
+    }));
+
+    // Spawn fetch task
+    let fetch_state = Arc::clone(&state);
+    let fetch_handle = tokio::spawn(fetch_task(fetch_state));
+
+    // Spawn config watcher task
+    let config_state = Arc::clone(&state);
+    let config_handle = tokio::spawn(config_watcher_task(config_state));
+
+    // Spawn route schedule task (swaps the active route set)
+    let schedule_state = Arc::clone(&state);
+    let schedule_handle = tokio::spawn(route_schedule_task(schedule_state));
+
+    // Spawn static GTFS task (idle unless static_gtfs.enabled)
+    let gtfs_state = Arc::clone(&state);
+    let gtfs_handle = tokio::spawn(static_gtfs_task(gtfs_state));
+
+    // Spawn calendar task (idle unless calendar is set)
+    let calendar_state = Arc::clone(&state);
+    let calendar_handle = tokio::spawn(calendar::calendar_task(calendar_state));
+
+    // Spawn font watcher task (rebuilds icons when their config or files change)
+    let font_state = Arc::clone(&state);
+    let font_handle = tokio::spawn(font_watcher_task(font_state));
+
+    // Spawn fleet sync task (idle unless fleet.source is set)
+    let fleet_state = Arc::clone(&state);
+    let fleet_handle = tokio::spawn(fleet_sync_task(fleet_state));
+
+    // Spawn screenshot time-lapse task
+    let timelapse_state = Arc::clone(&state);
+    let timelapse_handle = tokio::spawn(screenshot::timelapse_task(timelapse_state));
+
+    // Spawn analytics task (follows trains in each snapshot to arrival)
+    let analytics_state = Arc::clone(&state);
+    let analytics_handle = tokio::spawn(analytics::analytics_task(analytics_state));
+
+    // Spawn Wi-Fi setup portal (returns at once unless enabled and offline)
+    let setup_state = Arc::clone(&state);
+    let setup_handle = tokio::spawn(setup::portal_task(setup_state));
+
+    // Start GPIO button polling (dedicated OS thread, only if configured)
+    let buttons_thread = buttons::spawn(Arc::clone(&state));
+
+    // Spawn web server task
+    let web_state = Arc::clone(&state);
+    let web_handle = tokio::spawn(web::server::run(web_state));
+
+    // Spawn render thread (dedicated OS thread, not tokio)
+    let render_state = Arc::clone(&state);
+    let render_running = Arc::new(AtomicBool::new(true));
+    let render_flag = Arc::clone(&render_running);
+    let render_thread = match std::thread::Builder::new()
+        .name("render".into())
+        .spawn(move || render_loop(render_state, render_flag, restored_frame))
+    {
+        Ok(handle) => handle,
+        Err(e) => {
+            error!("Failed to spawn render thread: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    info!("All tasks started — rendering at 60fps");
+
+    // Wait for shutdown signal (or a restart requested over the web API)
+    tokio::select! {
+        _ = shutdown_signal() => info!("Shutdown signal received"),
+        _ = state.shutdown.cancelled() => info!("Restart requested — shutting down"),
+    }
+
+    // Signal all tasks to stop
+    state.shutdown.cancel();
+    render_running.store(false, Ordering::Relaxed);
+
+    // Wait for tasks to finish
+    let _ = fetch_handle.await;
+    let _ = config_handle.await;
+    let _ = schedule_handle.await;
+    let _ = gtfs_handle.await;
+    let _ = calendar_handle.await;
+    let _ = font_handle.await;
+    let _ = fleet_handle.await;
+    let _ = timelapse_handle.await;
+    let _ = analytics_handle.await;
+    let _ = setup_handle.await;
+    let _ = web_handle.await;
+    render_thread.join().ok();
+    if let Some(thread) = buttons_thread {
+        thread.join().ok();
+    }
+
+    // Leave the last frame for whichever process starts next
+    if let Err(e) = lifecycle::save_handoff(
+        &handoff_path,
+        &state.latest_frame.load(),
+        &state.snapshot.load(),
+    ) {
+        warn!("[RESTORE] Failed to save display handoff: {}", e);
+    }
+
+    info!("Shutdown complete");
+    telemetry::shutdown();
+
+    if let Some(mode) = state.restart.requested() {
+        lifecycle::restart_process(mode);
+    }
+}
+
+/// Analytics storage: the SQLite database when configured (and compiled
+/// in), otherwise per-day JSON files in `analytics.dir`.
+fn analytics_store(config: &Config) -> analytics::store::Store {
+    #[cfg(feature = "persistence")]
+    if let Some(db) = persistence::open_configured(&config.database) {
+        return analytics::store::Store::Sqlite(db);
+    }
+    #[cfg(not(feature = "persistence"))]
+    if config.database.path.is_some() {
+        warn!("[DB] database.path is set but this build lacks the `persistence` feature; using JSON files");
+    }
+    analytics::store::Store::Files(config.analytics.dir.clone())
+}
+
+/// Fetch trains for the current config and update the snapshot; true if
+/// any feed answered.
+async fn do_train_fetch(
+    client: &mut MtaClient,
+    state: &AppState,
+    cached_alerts: &[models::Alert],
+    last_train_count: &mut i32,
+) -> bool {
+    let config = state.config.load();
+
+    let all_stop_ids = config.all_stop_ids();
+
+    let routes: HashSet<String> = state.active_routes.load().iter().cloned().collect();
+
+    client.set_schedule(state.service_schedule.load_full());
+    client.set_dedup_window(config.refresh.dedup_window_secs);
+    client.set_route_service(config.display.route_service.clone());
+    let fetch_id = state.fetch_trace.load().id + 1;
+    let cycle = info_span!("fetch_cycle", fetch_id, trains = tracing::field::Empty);
+    let started = Instant::now();
+    let trains = client
+        .fetch_trains(&all_stop_ids, &routes, config.display.max_trains as usize)
+        .instrument(info_span!(parent: &cycle, "fetch", fetch_id))
+        .await;
+
+    let train_count = trains.len() as i32;
+    cycle.record("trains", train_count);
+    for train in &trains {
+        display::glyphs::check_text("destination", &train.destination);
+    }
+
+    let snapshot = DisplaySnapshot {
+        trains,
+        alerts: cached_alerts.to_vec(),
+        fetched_at: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs_f64(),
+    };
+
+    // Published first: the render loop matches snapshots to it
+    state.fetch_trace.store(Arc::new(telemetry::FetchTrace {
+        id: fetch_id,
+        started,
+        fetch_time: started.elapsed(),
+        fetched_at: snapshot.fetched_at,
+        span: cycle,
+    }));
+    state.snapshot.store(Arc::new(snapshot));
+    state.trips.store(Arc::new(client.trips().clone()));
+    let degraded = client.degraded_routes(&routes);
+    if degraded != **state.degraded_routes.load() {
+        if degraded.is_empty() {
+            info!("[FETCH] All feeds answering again");
+        } else {
+            warn!("[FETCH] Feed down for {} (showing cached trains)", degraded.join(", "));
+        }
+        state.degraded_routes.store(Arc::new(degraded));
+    }
+    let now = unix_now_secs();
+    state.last_fetch_success.store(now, Ordering::Relaxed);
+
+    // Healthy feeds running our routes, yet nothing at our stops: likely a
+    // station/routes config mistake rather than an outage
+    let summary = client.last_summary();
+    let mismatch = train_count == 0 && summary.feeds_failed == 0 && summary.route_trips > 0;
+    if !mismatch {
+        state.no_match_since.store(0, Ordering::Relaxed);
+    } else if state.no_match_since.load(Ordering::Relaxed) == 0 {
+        state.no_match_since.store(now, Ordering::Relaxed);
+        info!(
+            "[FETCH] Feeds carry {} trip(s) on the configured routes, but none at the configured stops",
+            summary.route_trips
+        );
+    }
+
+    if train_count != *last_train_count {
+        info!("[FETCH] {} trains fetched", train_count);
+        *last_train_count = train_count;
+    }
+    summary.feeds_ok > 0
+}
+
+/// Fetch second-leg arrivals at the trip's transfer station, if a trip is
+/// configured. Uses its own client: feed caches hold trains filtered to the
+/// stops they were fetched for.
+async fn do_transfer_fetch(client: &mut MtaClient, state: &AppState) {
+    let config = state.config.load();
+    let trains = match &config.trip {
+        Some(trip) => {
+            let routes: HashSet<String> = trip.second.routes.iter().cloned().collect();
+            client.set_dedup_window(config.refresh.dedup_window_secs);
+            client
+                .fetch_trains(&trip.transfer_stop_ids(), &routes, trip::TRANSFER_TRAINS)
+                .await
+        }
+        None => Vec::new(),
+    };
+    state.transfer_trains.store(Arc::new(trains));
+}
+
+/// Background fetch task — runs train + alert fetches on separate intervals.
+async fn fetch_task(state: Arc<AppState>) {
+    let (mut client, mut transfer_client) = match (MtaClient::new(), MtaClient::new()) {
+        (Ok(client), Ok(transfer_client)) => (client, transfer_client),
+        (Err(e), _) | (_, Err(e)) => {
+            error!("[FETCH] {}", e);
+            return;
+        }
+    };
+    let mut last_train_count: i32 = -1;
+    let mut cached_alerts: Vec<models::Alert> = Vec::new();
+
+    info!("[FETCH] Background fetch task started");
+
+    let config = state.config.load();
+    if config.refresh.startup_delay_secs > 0 {
+        tokio::time::sleep(std::time::Duration::from_secs(config.refresh.startup_delay_secs)).await;
+    }
+
+    // Use configured intervals (not hardcoded)
+    let mut train_interval = tokio::time::interval(
+        std::time::Duration::from_secs(config.refresh.trains_interval),
+    );
+    let mut alert_interval = tokio::time::interval(
+        std::time::Duration::from_secs(config.refresh.alerts_interval),
+    );
+    // No fetching while the display is off; checked often so data is fresh
+    // on resume
+    let mut display_off_interval = tokio::time::interval(std::time::Duration::from_secs(5));
+    let mut display_off = crate::display_off(&state);
+
+    // First paint: trains right away, ahead of alerts, then retried every
+    // warmup_interval_secs until a feed answers (for at most WARMUP_LIMIT)
+    const WARMUP_LIMIT: std::time::Duration = std::time::Duration::from_secs(60);
+    let warmup_secs = config.refresh.warmup_interval_secs;
+    let mut warmup = tokio::time::interval(std::time::Duration::from_secs(warmup_secs.max(1)));
+    let warmup_started = Instant::now();
+    let mut warming = false;
+    if !display_off {
+        warming = !do_train_fetch(&mut client, &state, &cached_alerts, &mut last_train_count).await
+            && warmup_secs > 0;
+        do_transfer_fetch(&mut transfer_client, &state).await;
+        train_interval.reset();
+        warmup.reset();
+        if warming {
+            info!("[FETCH] No feed answered yet, retrying every {}s", warmup_secs);
+        }
+    }
+
+    loop {
+        tokio::select! {
+            _ = state.shutdown.cancelled() => {
+                info!("[FETCH] Shutting down");
+                break;
+            }
+            _ = display_off_interval.tick() => {
+                let now_off = crate::display_off(&state);
+                if now_off != display_off {
+                    display_off = now_off;
+                    if display_off {
+                        info!("[FETCH] Display off, pausing fetches");
+                    } else {
+                        info!("[FETCH] Display back on, resuming fetches");
+                        alert_interval.reset_immediately();
+                        train_interval.reset_immediately();
+                    }
+                }
+            }
+            _ = state.config_changed.notified(), if !display_off => {
+                info!("[FETCH] Config changed — re-fetching");
+                do_train_fetch(&mut client, &state, &cached_alerts, &mut last_train_count).await;
+                do_transfer_fetch(&mut transfer_client, &state).await;
+            }
+            _ = warmup.tick(), if warming && !display_off => {
+                let answered = do_train_fetch(&mut client, &state, &cached_alerts, &mut last_train_count).await;
+                if answered {
+                    info!("[FETCH] First feed answered after {}s", warmup_started.elapsed().as_secs());
+                } else if warmup_started.elapsed() >= WARMUP_LIMIT {
+                    warn!("[FETCH] Still no feed answering, back to every {}s", config.refresh.trains_interval);
+                }
+                if answered || warmup_started.elapsed() >= WARMUP_LIMIT {
+                    warming = false;
+                    do_transfer_fetch(&mut transfer_client, &state).await;
+                    train_interval.reset();
+                }
+            }
+            _ = alert_interval.tick(), if !display_off => {
+                let config = state.config.load();
+                // Alternates need suspensions even with alerts hidden
+                let raw_alerts = if config.display.show_alerts || config.alternates.primary.is_some() {
+                    let routes: HashSet<String> = state.active_routes.load().iter().cloned().collect();
+                    client.fetch_alerts(&routes).await
+                } else {
+                    Vec::new()
+                };
+                // Rebuilt every poll, so lifted (or no longer fetched) suspensions clear
+                state.suspended_routes.store(Arc::new(mta::alerts::suspended_routes(&raw_alerts)));
+                if config.display.show_alerts {
+                    let mut am = state.alert_manager.lock()
+                        .unwrap_or_else(|e| e.into_inner());
+                    cached_alerts = am.filter_and_sort(&raw_alerts);
+                    for alert in &cached_alerts {
+                        display::glyphs::check_alert_text(&alert.text);
+                    }
+                    if **state.fetched_alerts.load() != raw_alerts {
+                        state.fetched_alerts.store(Arc::new(raw_alerts));
+                    }
+                }
+            }
+            _ = train_interval.tick(), if !display_off => {
+                do_train_fetch(&mut client, &state, &cached_alerts, &mut last_train_count).await;
+                do_transfer_fetch(&mut transfer_client, &state).await;
+            }
+        }
+    }
+}
+
+/// Config watcher — polls config file mtime every 5 seconds.
+async fn config_watcher_task(state: Arc<AppState>) {
+    let mut last_mtime = std::fs::metadata(&state.config_path)
+        .and_then(|m| m.modified())
+        .ok();
+
+    let mut interval = tokio::time::interval(std::time::Duration::from_secs(5));
+
+    loop {
+        tokio::select! {
+            _ = state.shutdown.cancelled() => {
+                info!("[CONFIG] Shutting down");
+                break;
+            }
+            _ = interval.tick() => {
+                let current_mtime = std::fs::metadata(&state.config_path)
+                    .and_then(|m| m.modified())
+                    .ok();
+
+                if current_mtime != last_mtime {
+                    info!("[CONFIG] File changed, reloading...");
+                    crate::load_station_database(&state.config_path);
+                    match Config::load(&state.config_path) {
+                        Ok(new_config) => {
+                            info!(
+                                "[CONFIG] Reloaded: {} platforms, routes: {}",
+                                new_config.station_stops.len(),
+                                new_config.routes.join(",")
+                            );
+                            crate::apply_config(&state, new_config);
+                            last_mtime = current_mtime;
+                        }
+                        Err(e) => {
+                            warn!("[CONFIG] Reload failed: {}", e);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Store the routes in service at `now` — those `route_schedule` picks, less
+/// any the static GTFS schedule has no service for; true if the set changed.
+fn update_active_routes(state: &AppState, now: chrono::NaiveDateTime) -> bool {
+    let mut routes = state.config.load().scheduled_routes(now);
+    if let Some(schedule) = &**state.service_schedule.load() {
+        let unscheduled = schedule.unscheduled(&routes, now);
+        routes.retain(|r| !unscheduled.contains(r));
+    }
+    if **state.active_routes.load() == routes {
+        return false;
+    }
+    info!("[SCHEDULE] Active routes: {}", routes.join(","));
+    state.active_routes.store(Arc::new(routes));
+    true
+}
+
+/// Route schedule — re-evaluates the active route set every 5 seconds (which
+/// also picks up config reloads and newly loaded GTFS schedules) and has the
+/// fetch task re-fetch when it changes.
+async fn route_schedule_task(state: Arc<AppState>) {
+    let mut interval = tokio::time::interval(std::time::Duration::from_secs(5));
+
+    loop {
+        tokio::select! {
+            _ = state.shutdown.cancelled() => {
+                info!("[SCHEDULE] Shutting down");
+                break;
+            }
+            _ = interval.tick() => {
+                let now = state.config.load().tz().now();
+                if update_active_routes(&state, now) {
+                    state.config_changed.notify_one();
+                }
+            }
+        }
+    }
+}
+
+/// Static GTFS task — keeps `AppState::service_schedule` loaded from a
+/// cached download, refreshed every `static_gtfs.refresh_hours`.
+async fn static_gtfs_task(state: Arc<AppState>) {
+    // Re-checks the (hot-reloadable) config and cache age
+    let mut interval = tokio::time::interval(std::time::Duration::from_secs(15 * 60));
+    let mut loaded: Option<(PathBuf, SystemTime, HashSet<String>, crate::timezone::Timezone)> = None;
+
+    loop {
+        tokio::select! {
+            _ = state.shutdown.cancelled() => {
+                info!("[GTFS] Shutting down");
+                break;
+            }
+            _ = interval.tick() => {
+                let config = state.config.load().static_gtfs.clone();
+                if !config.enabled {
+                    if loaded.take().is_some() {
+                        state.service_schedule.store(Arc::new(None));
+                    }
+                    continue;
+                }
+
+                let path = config.cache_path.clone();
+                let stops: HashSet<String> = state.config.load().all_stop_ids().into_iter().collect();
+                let timezone = state.config.load().tz();
+                if mta::schedule::cache_is_stale(&path, std::time::Duration::from_secs(config.refresh_hours as u64 * 3600)) {
+                    info!("[GTFS] Downloading static schedule from {}", config.url);
+                    if let Err(e) = mta::schedule::download(&config.url, &path).await {
+                        warn!("[GTFS] Download failed: {}", e);
+                    }
+                }
+
+                let Ok(mtime) = std::fs::metadata(&path).and_then(|m| m.modified()) else {
+                    continue;
+                };
+                // Re-parse for a new download, a different station or zone
+                let key = (path.clone(), mtime, stops, timezone);
+                if loaded.as_ref() == Some(&key) {
+                    continue;
+                }
+                let (parse_path, parse_stops) = (path.clone(), key.2.clone());
+                match tokio::task::spawn_blocking(move || mta::schedule::ServiceSchedule::from_zip(&parse_path, &parse_stops)).await {
+                    Ok(Ok(schedule)) => {
+                        let schedule = schedule.with_timezone(timezone);
+                        let (routes, calendars) = schedule.counts();
+                        info!("[GTFS] Loaded static schedule: {} routes, {} calendars", routes, calendars);
+                        state.service_schedule.store(Arc::new(Some(schedule)));
+                        loaded = Some(key);
+                    }
+                    Ok(Err(e)) => warn!("[GTFS] Failed to parse {}: {}", path.display(), e),
+                    Err(e) => warn!("[GTFS] Parse task failed: {}", e),
+                }
+            }
+        }
+    }
+}
+
+/// Font watcher — rebuilds the global font when the `icons` config section
+/// or any file it references changes (polled every 2 seconds).
+async fn font_watcher_task(state: Arc<AppState>) {
+    fn fingerprint(icons: &config::IconConfig) -> Vec<Option<SystemTime>> {
+        icons
+            .watched_files()
+            .into_iter()
+            .map(|path| std::fs::metadata(path).and_then(|m| m.modified()).ok())
+            .collect()
+    }
+
+    let mut icons = state.config.load().icons.clone();
+    let mut mtimes = fingerprint(&icons);
+    let mut interval = tokio::time::interval(std::time::Duration::from_secs(2));
+
+    loop {
+        tokio::select! {
+            _ = state.shutdown.cancelled() => {
+                info!("[FONT] Shutting down");
+                break;
+            }
+            _ = interval.tick() => {
+                let current = state.config.load().icons.clone();
+                let current_mtimes = fingerprint(&current);
+                if current == icons && current_mtimes == mtimes {
+                    continue;
+                }
+                icons = current;
+                mtimes = current_mtimes;
+
+                // Decoding is a few ms of CPU; keep it off the async workers
+                let build_icons = icons.clone();
+                match tokio::task::spawn_blocking(move || display::fonts::build_font(&build_icons)).await {
+                    Ok(Ok(font)) => {
+                        display::fonts::set_font(font);
+                        info!("[FONT] Reloaded font and icons");
+                    }
+                    Ok(Err(e)) => warn!("[FONT] Reload failed, keeping current font: {}", e),
+                    Err(e) => warn!("[FONT] Reload task failed: {}", e),
+                }
+            }
+        }
+    }
+}
+
+/// Fleet sync — polls `fleet.source` and applies its config with local overrides.
+async fn fleet_sync_task(state: Arc<AppState>) {
+    const IDLE_RECHECK: std::time::Duration = std::time::Duration::from_secs(30);
+
+    let client = match reqwest::Client::builder()
+        .user_agent("NYC-SubwaySign-Rust/1.0")
+        .timeout(std::time::Duration::from_secs(10))
+        .build()
+    {
+        Ok(c) => c,
+        Err(e) => {
+            error!("[FLEET] Failed to create HTTP client: {}", e);
+            return;
+        }
+    };
+    let cache_path = config::fleet_cache_path(&state.config_path);
+    let mut last_remote: Option<serde_json::Value> = std::fs::read_to_string(&cache_path)
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok());
+
+    loop {
+        let fleet = state.config.load().fleet.clone();
+        let delay = match fleet.source_url() {
+            Some(source) => {
+                sync_fleet_config(&client, &state, source, &mut last_remote).await;
+                std::time::Duration::from_secs(fleet.poll_interval)
+            }
+            None => IDLE_RECHECK,
+        };
+
+        tokio::select! {
+            _ = state.shutdown.cancelled() => {
+                info!("[FLEET] Shutting down");
+                break;
+            }
+            _ = state.fleet_sync.notified() => {}
+            _ = tokio::time::sleep(delay) => {}
+        }
+    }
+}
+
+/// Fetch the source's config once; cache and apply it if it changed and is valid.
+async fn sync_fleet_config(
+    client: &reqwest::Client,
+    state: &AppState,
+    source: &str,
+    last_remote: &mut Option<serde_json::Value>,
+) {
+    let remote = match config::fetch_fleet_config(client, source).await {
+        Ok(remote) => remote,
+        Err(e) => {
+            warn!("[FLEET] Sync from {} failed: {}", source, e);
+            return;
+        }
+    };
+    if last_remote.as_ref() == Some(&remote) {
+        return;
+    }
+
+    // Validate the merged result before caching, so a bad push from the
+    // source can't leave this sign with an unloadable config
+    let local: serde_json::Value = match std::fs::read_to_string(&state.config_path)
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+    {
+        Some(v) => v,
+        None => {
+            warn!("[FLEET] Cannot read local config, skipping sync");
+            return;
+        }
+    };
+    let merged = config::merge_fleet_config(&local, &remote);
+    let new_config = match Config::from_json(&merged.to_string()) {
+        Ok(cfg) => cfg,
+        Err(e) => {
+            warn!("[FLEET] Rejected config from {}: {}", source, e);
+            return;
+        }
+    };
+
+    let cache_json = serde_json::to_string_pretty(&remote).unwrap_or_default();
+    let cache_path = config::fleet_cache_path(&state.config_path);
+    if let Err(e) = config::atomic_write_config(&cache_path, &cache_json) {
+        warn!("[FLEET] Failed to cache remote config: {}", e);
+    }
+
+    info!(
+        "[FLEET] Applied config from {}: {} platforms, routes: {}",
+        source,
+        new_config.station_stops.len(),
+        new_config.routes.join(",")
+    );
+    crate::apply_config(state, new_config);
+    *last_remote = Some(remote);
+}
+
+/// Alert display state machine.
+///
+/// Tracks whether an alert is currently showing, which alert it is,
+/// the scroll position, and what train triggered the alert cycle.
+/// Extracted from the render loop to reduce parameter sprawl.
+struct AlertState {
+    show_alert: bool,
+    current_alert: Option<Alert>,
+    scroll_offset: f32,
+    /// Passes the current alert gets, and how many it has finished.
+    passes: config::ScrollPasses,
+    passes_done: u32,
+    triggered_by: Option<(String, String)>,
+    cycle_start_time: Instant,
+    /// Ticker mode: when the last pass ended.
+    ticker_done: Option<Instant>,
+    /// While a new NO_SERVICE alert interrupts: the state to go back to.
+    parked: Option<Box<AlertState>>,
+    /// Showing the current alert's QR page until then (see
+    /// `display.alert_qr_seconds`).
+    qr_until: Option<Instant>,
+}
+
+impl AlertState {
+    fn new() -> Self {
+        Self {
+            show_alert: false,
+            current_alert: None,
+            scroll_offset: 0.0,
+            passes: config::ScrollPasses::Count(1),
+            passes_done: 0,
+            triggered_by: None,
+            cycle_start_time: Instant::now(),
+            ticker_done: None,
+            parked: None,
+            qr_until: None,
+        }
+    }
+
+    /// The current alert's details link, while its QR page is up.
+    fn qr_url(&self) -> Option<&str> {
+        self.qr_until.filter(|until| Instant::now() < *until)?;
+        self.current_alert.as_ref()?.url.as_deref()
+    }
+
+    /// Start scrolling `alert` from the right edge.
+    fn begin(&mut self, state: &AppState, alert: Alert) {
+        self.passes = state.config.load().display.alert_scroll.passes_for(&alert);
+        self.passes_done = 0;
+        self.current_alert = Some(alert);
+        self.scroll_offset = 0.0;
+        self.qr_until = None;
+    }
+
+    /// Do-not-disturb: drop whatever is showing (including an interrupting
+    /// alert) and start nothing new.
+    fn pause(&mut self) {
+        self.clear();
+        self.parked = None;
+    }
+
+    /// Reset all alert display state to idle.
+    fn clear(&mut self) {
+        self.show_alert = false;
+        self.current_alert = None;
+        self.scroll_offset = 0.0;
+        self.triggered_by = None;
+        self.qr_until = None;
+    }
+
+    /// Update the alert state machine for one frame.
+    ///
+    /// Triggers alert display when a train arrives (minutes == 0), cycles through
+    /// queued alerts with scrolling, and clears when all alerts have been shown
+    /// or the triggering train departs.
+    fn update(
+        &mut self,
+        state: &AppState,
+        snapshot: &DisplaySnapshot,
+        renderer: &mut Renderer,
+        scroll_speed: f32,
+        max_duration: std::time::Duration,
+    ) {
+        // A new NO_SERVICE alert scrolls once straight away, then whatever
+        // it interrupted picks up where it left off
+        if self.parked.is_some() {
+            self.scroll_offset += scroll_speed;
+            if self.scroll_offset >= renderer.get_scroll_complete_distance() as f32 {
+                if let Some(mut parked) = self.parked.take() {
+                    parked.cycle_start_time += self.cycle_start_time.elapsed();
+                    *self = *parked;
+                }
+            }
+            return;
+        }
+        let urgent = state.alert_manager.lock().unwrap_or_else(|e| e.into_inner()).take_urgent();
+        if let Some(alert) = urgent {
+            info!("[ALERTS] New service suspension, showing now: {}", alert.text);
+            let parked = std::mem::replace(self, AlertState::new());
+            self.ticker_done = parked.ticker_done;
+            self.current_alert = Some(alert);
+            self.show_alert = true;
+            self.parked = Some(Box::new(parked));
+            return;
+        }
+
+        let ticker = state.config.load().display.alert_ticker.clone();
+        if ticker.enabled {
+            self.update_ticker(state, renderer, scroll_speed, &ticker);
+            return;
+        }
+        if self.current_alert.as_ref().is_some_and(|a| a.is_ticker) {
+            // Ticker mode was just switched off
+            self.clear();
+        }
+
+        let first_train = snapshot.get_first_train();
+        let train_at_zero = first_train.minutes == 0;
+
+        // Skip mutex entirely when no alerts are active and none could trigger
+        if !train_at_zero && !self.show_alert {
+            return;
+        }
+
+        // Check if the train that triggered alerts has departed
+        let triggering_train_departed = self.show_alert
+            && self.triggered_by.as_ref().is_some_and(|(route, dest)| {
+                !snapshot.trains.iter().any(|t| {
+                    t.route == *route && t.destination == *dest && t.minutes == 0
+                })
+            });
+
+        let mut am = state.alert_manager.lock()
+            .unwrap_or_else(|e| e.into_inner());
+
+        // Start showing alerts when a train arrives and alerts are queued
+        if train_at_zero && !self.show_alert && am.has_alerts() {
+            am.reset_cycle();
+            if let Some(alert) = am.get_next_alert() {
+                self.begin(state, alert.clone());
+                self.show_alert = true;
+                self.triggered_by = Some((first_train.route.clone(), first_train.destination.clone()));
+                self.cycle_start_time = Instant::now();
+            }
+        }
+
+        // Process active alert display
+        if self.show_alert && self.current_alert.is_some() {
+            if self.cycle_start_time.elapsed() > max_duration {
+                self.clear();
+                am.periodic_cleanup();
+                return;
+            }
+            if self.qr_url().is_some() {
+                am.periodic_cleanup();
+                return;
+            }
+
+            self.scroll_offset += scroll_speed;
+
+            let scroll_complete = self.scroll_offset >= renderer.get_scroll_complete_distance() as f32;
+            if !scroll_complete {
+                am.periodic_cleanup();
+                return;
+            }
+
+            // Another pass of the same alert?
+            self.passes_done += 1;
+            let again = match self.passes {
+                config::ScrollPasses::Count(n) => self.passes_done < n,
+                config::ScrollPasses::UntilDeparted => !triggering_train_departed,
+            };
+            if again {
+                self.scroll_offset = 0.0;
+                am.periodic_cleanup();
+                return;
+            }
+
+            // Then its QR page, if it links to details that fit the panel
+            let qr_seconds = state.config.load().display.alert_qr_seconds;
+            let url = self.current_alert.as_ref().and_then(|a| a.url.as_deref());
+            if qr_seconds > 0 && self.qr_until.is_none() && url.is_some_and(|u| renderer.render_qr_page(u).is_some()) {
+                self.qr_until = Some(Instant::now() + std::time::Duration::from_secs(qr_seconds as u64));
+                am.periodic_cleanup();
+                return;
+            }
+
+            // Current alert finished scrolling -- mark it displayed
+            if let Some(ref alert) = self.current_alert {
+                am.mark_displayed(alert);
+            }
+
+            // Decide what to show next
+            let next = if triggering_train_departed && train_at_zero && am.has_alerts() {
+                // Train departed but another arrived -- restart the cycle
+                am.reset_cycle();
+                am.get_next_alert().cloned()
+            } else if !triggering_train_departed && !am.all_shown_this_cycle() {
+                am.get_next_alert().cloned()
+            } else {
+                None
+            };
+
+            if let Some(alert) = next {
+                self.begin(state, alert);
+                if triggering_train_departed {
+                    self.triggered_by = Some((
+                        first_train.route.clone(),
+                        first_train.destination.clone(),
+                    ));
+                    self.cycle_start_time = Instant::now();
+                }
+            } else {
+                self.clear();
+            }
+        }
+
+        am.periodic_cleanup();
+    }
+
+    /// Ticker mode: every `interval_seconds`, scroll all queued alerts once
+    /// as a single ticker, regardless of arriving trains. With an interval of
+    /// 0 the next pass starts on the frame the last one ends, so the bottom
+    /// row stays a ticker band while any alert is queued.
+    fn update_ticker(
+        &mut self,
+        state: &AppState,
+        renderer: &Renderer,
+        scroll_speed: f32,
+        ticker: &config::AlertTickerConfig,
+    ) {
+        if self.show_alert {
+            self.scroll_offset += scroll_speed;
+            if self.scroll_offset < renderer.get_scroll_complete_distance() as f32 {
+                return;
+            }
+            self.clear();
+            self.ticker_done = Some(Instant::now());
+        }
+
+        let interval = std::time::Duration::from_secs(ticker.interval_seconds as u64);
+        if self.ticker_done.is_some_and(|done| done.elapsed() < interval) {
+            return;
+        }
+        let am = state.alert_manager.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(alert) = mta::alerts::ticker_alert(am.queued()) {
+            self.current_alert = Some(alert);
+            self.scroll_offset = 0.0;
+            self.passes = config::ScrollPasses::Count(1);
+            self.passes_done = 0;
+            self.show_alert = true;
+            self.cycle_start_time = Instant::now();
+        }
+    }
+}
+
+/// `display.watch` cue: starts when the next watched train comes within
+/// range (once per train) and pulses for the configured time.
+#[derive(Default)]
+struct WatchCue {
+    /// Trip ID of the train last cued, while it's still in range.
+    cued: Option<String>,
+    started: Option<Instant>,
+}
+
+impl WatchCue {
+    /// Pulse on/off period.
+    const PULSE: std::time::Duration = std::time::Duration::from_millis(250);
+
+    /// Update for one frame; true while the effect should be drawn.
+    fn update(&mut self, watch: Option<&config::Watch>, trains: &[Train], now: Instant) -> bool {
+        let Some(watch) = watch else {
+            *self = Self::default();
+            return false;
+        };
+        let next = trains
+            .iter()
+            .filter(|t| t.route == watch.route && t.direction == watch.direction)
+            .min_by_key(|t| t.minutes)
+            .filter(|t| t.minutes >= 0 && t.minutes as u32 <= watch.minutes);
+        match next {
+            Some(train) if self.cued.as_ref() != Some(&train.trip_id) => {
+                info!("[WATCH] {} train {} min away", watch.route, train.minutes);
+                self.cued = Some(train.trip_id.clone());
+                self.started = Some(now);
+            }
+            Some(_) => {}
+            None => self.cued = None,
+        }
+        let Some(elapsed) = self.started.map(|started| now.saturating_duration_since(started)) else {
+            return false;
+        };
+        if elapsed >= std::time::Duration::from_secs(watch.seconds as u64) {
+            self.started = None;
+            return false;
+        }
+        (elapsed.as_millis() / Self::PULSE.as_millis()) % 2 == 0
+    }
+}
+
+/// `display.pages` rotation: which page is up and since when.
+#[derive(Default)]
+struct PageCycle {
+    index: usize,
+    since: Option<Instant>,
+}
+
+impl PageCycle {
+    /// The page to show at `now`, moving on once the current one's time is
+    /// up; `None` with no pages configured.
+    fn current<'a>(&mut self, pages: &'a [config::Page], now: Instant) -> Option<&'a config::Page> {
+        if pages.is_empty() {
+            return None;
+        }
+        let since = *self.since.get_or_insert(now);
+        self.index %= pages.len();
+        if now.saturating_duration_since(since) >= std::time::Duration::from_secs(pages[self.index].seconds as u64) {
+            self.index = (self.index + 1) % pages.len();
+            self.since = Some(now);
+        }
+        Some(&pages[self.index])
+    }
+}
+
+/// Render loop — runs in a dedicated OS thread at 60fps.
+///
+/// This is NOT a tokio task. It's a real thread because:
+/// - It runs perpetually at 60fps with precise timing
+/// - It calls blocking FFI (LED matrix VSync) on hardware
+/// - spawn_blocking is for short-lived operations, not permanent loops
+///
+/// `restored` is the previous process's last frame; it is shown with a
+/// restart badge until the first fetch completes (or `RESTORE_HOLD` passes).
+fn render_loop(state: Arc<AppState>, running: Arc<AtomicBool>, mut restored: Option<FrameBuffer>) {
+    let config = state.config.load();
+    let brightness = brightness_percent(&config, crate::away_today(&state));
+    let mut display = create_display(brightness, &config.panel);
+    let mut current_viewport = Viewport::from_config(&config.panel);
+    let mut current_panel = config.panel.clone();
+    let mut post = Pipeline::new(&config.display.post);
+    let mut renderer = Renderer::new();
+    renderer.apply_config(&config);
+    // A frame saved at another panel size can't be shown
+    restored = restored.filter(|frame| (frame.width(), frame.height()) == renderer.size());
+    let mut rendered_config = state.config.load_full();
+    let mut clock = ClockRenderer::new();
+    let mut idle_clock = config.display.idle_clock;
+    let mut timezone = config.tz();
+    let mut display_off = crate::display_off(&state);
+    let mut alert_state = AlertState::new();
+    let mut watch = config.display.watch.clone();
+    let mut watch_cue = WatchCue::default();
+    let mut pages = config.display.pages.clone();
+    let mut page_cycle = PageCycle::default();
+    let mut snapshot_frames = telemetry::SnapshotFrames::default();
+    let mut swaps = SwapDedup::default();
+
+    let mut brightness_ramp = BrightnessRamp::new(brightness, Instant::now());
+    // Frame rate follows the backend; pixels per frame follow the frame
+    // rate (fractional speeds are smoothed by the renderer)
+    let mut fps = render_fps(&config.panel);
+    let mut frame_time = std::time::Duration::from_secs_f64(1.0 / fps);
+    let mut scroll_speed = config.display.scroll_speed / fps as f32;
+    let mut cycle_index: usize = 0;
+    // Slide time between cycling trains (none before the first advance)
+    let mut cycle_transition = std::time::Duration::from_millis(config.display.cycle_transition_ms);
+    let mut cycled = false;
+    let mut flash_state = false;
+
+    let mut last_cycle_time = Instant::now();
+    let mut last_flash_time = Instant::now();
+    let mut frame_count: u64 = 0;
+    let mut missed_frames: u64 = 0;
+    let mut max_frame_us: u64 = 0;
+    let mut total_frame_us: u64 = 0;
+    let mut last_stats_time = Instant::now();
+    let mut last_poll_time = Instant::now();
+    let mut timings = [
+        FrameTimings::new(std::time::Duration::from_secs(60)),
+        FrameTimings::new(std::time::Duration::from_secs(300)),
+    ];
+
+    const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+    const CYCLE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(3);
+    const FLASH_INTERVAL: std::time::Duration = std::time::Duration::from_millis(500);
+    const MAX_ALERT_CYCLE_DURATION: std::time::Duration = std::time::Duration::from_secs(90);
+    const STATS_INTERVAL: std::time::Duration = std::time::Duration::from_secs(300);
+    const RESTORE_HOLD: std::time::Duration = std::time::Duration::from_secs(30);
+    let started = Instant::now();
+
+    info!("[RENDER] Render loop started ({}fps)", fps);
+
+    while running.load(Ordering::Relaxed) {
+        let frame_start = Instant::now();
+
+        // Load latest snapshot (lock-free)
+        let snapshot = state.snapshot.load();
+
+        // Update cycle index
+        if last_cycle_time.elapsed() >= CYCLE_INTERVAL {
+            last_cycle_time = Instant::now();
+            cycle_index = (cycle_index + 1) % 6;
+            cycled = true;
+        }
+        let since_cycle = last_cycle_time.elapsed();
+        renderer.set_cycle_transition(
+            (cycled && since_cycle < cycle_transition)
+                .then(|| ((cycle_index + 5) % 6, since_cycle.as_secs_f32() / cycle_transition.as_secs_f32())),
+        );
+
+        // Update flash state
+        if last_flash_time.elapsed() >= FLASH_INTERVAL {
+            last_flash_time = Instant::now();
+            flash_state = !flash_state;
+        }
+
+        // Fade toward the configured brightness (no-op once settled)
+        if let Some(level) = brightness_ramp.step(frame_start) {
+            display.set_brightness(level);
+            swaps.invalidate();
+        }
+
+        // Alternating arrival format: countdown first, clock time second half of each cycle
+        renderer.set_alternate_phase(last_cycle_time.elapsed() >= CYCLE_INTERVAL / 2);
+        renderer.set_clock(started.elapsed());
+
+        // Alert state machine (held while do-not-disturb is on)
+        let do_not_disturb = state.do_not_disturb.load(Ordering::Relaxed);
+        renderer.set_do_not_disturb(do_not_disturb);
+        if do_not_disturb {
+            alert_state.pause();
+        } else {
+            alert_state.update(
+                &state,
+                &snapshot,
+                &mut renderer,
+                scroll_speed,
+                MAX_ALERT_CYCLE_DURATION,
+            );
+        }
+
+        // Drop the restored frame once live data arrives
+        if restored.is_some()
+            && (state.last_fetch_success.load(Ordering::Relaxed) != 0
+                || started.elapsed() >= RESTORE_HOLD)
+        {
+            restored = None;
+            info!("[RESTORE] Resuming live rendering");
+        }
+
+        // Render frame
+        let info_screen = state.info_screen.load();
+        let info_lines = info_screen
+            .as_ref()
+            .as_ref()
+            .filter(|info| frame_start < info.until)
+            .map(|info| &info.lines);
+        let qr_page = alert_state
+            .qr_url()
+            .filter(|_| !do_not_disturb)
+            .and_then(|url| renderer.render_qr_page(url));
+        let showing_qr = qr_page.is_some();
+        // An alert scrolling for an arriving train holds the trains page
+        let page = page_cycle
+            .current(&pages, frame_start)
+            .map(|p| &p.kind)
+            .filter(|_| !alert_state.show_alert && !showing_qr);
+        let trains_page = matches!(page, None | Some(config::PageKind::Trains));
+        // No trains: the idle clock, unless a banner or alert has something to say
+        let idle = idle_clock
+            && snapshot.trains.is_empty()
+            && !alert_state.show_alert
+            && renderer.banner().is_none();
+        let mut from_renderer = false;
+        let frame = match (&restored, info_lines) {
+            (_, Some(lines)) => renderer.render_text_screen(lines),
+            _ if display_off => renderer.blank_frame(),
+            (Some(previous), None) => {
+                let mut frame = previous.clone();
+                lifecycle::draw_restart_badge(&mut frame, flash_state);
+                frame
+            }
+            (None, None) => match page {
+                Some(config::PageKind::Clock) => clock.render(timezone.now(), renderer.strings(), renderer.size()),
+                Some(config::PageKind::Message { lines }) => renderer.render_text_screen(&[
+                    lines.first().cloned().unwrap_or_default(),
+                    lines.get(1).cloned().unwrap_or_default(),
+                ]),
+                Some(config::PageKind::Alerts) => renderer.render_alerts_page(&state.fetched_alerts.load_full()),
+                _ if idle => clock.render(timezone.now(), renderer.strings(), renderer.size()),
+                _ => qr_page.unwrap_or_else(|| {
+                    from_renderer = true;
+                    renderer.render_frame(
+                        &snapshot,
+                        cycle_index,
+                        flash_state,
+                        alert_state.scroll_offset,
+                        alert_state.show_alert,
+                        alert_state.current_alert.as_ref(),
+                    )
+                }),
+            },
+        };
+
+        // Watched train coming: pulse the highlight on live arrivals only
+        let live =
+            restored.is_none() && info_lines.is_none() && !display_off && !idle && !showing_qr && trains_page;
+        let cued = (watch_cue.update(watch.as_ref(), &snapshot.trains, frame_start) && live && !do_not_disturb)
+            .then_some(watch.as_ref())
+            .flatten()
+            .map(|w| {
+                let color = display::colors::route_color(&w.route).unwrap_or((255, 255, 255));
+                display::post::highlight(&frame, w.effect, renderer.row_of(&w.route, w.direction), color)
+            });
+
+        // Push to display (post-processed) unless the renderer handed back
+        // the frame already there, then publish for screenshots/previews
+        let generation = (from_renderer && cued.is_none()).then(|| renderer.frame_generation());
+        if swaps.changed(generation, frame_start) {
+            display.swap(&post.apply(cued.as_ref().unwrap_or(&frame), started.elapsed()));
+        }
+        snapshot_frames.record(snapshot.fetched_at, &state.fetch_trace.load(), Instant::now());
+        let frame = Arc::new(frame);
+        if state.frame_updates.receiver_count() > 0 {
+            let _ = state.frame_updates.send(frame.clone());
+        }
+        state.latest_frame.store(frame);
+
+        // Measure work time (render + swap/vsync) before compensating sleep
+        let work_time = frame_start.elapsed();
+        let work_us = work_time.as_micros() as u64;
+        total_frame_us += work_us;
+        if work_us > max_frame_us {
+            max_frame_us = work_us;
+        }
+        if work_time > frame_time {
+            missed_frames += 1;
+        }
+        for window in &mut timings {
+            window.record(frame_start, work_time);
+        }
+
+        frame_count += 1;
+
+        // Poll for brightness and config changes every second
+        if last_poll_time.elapsed() >= POLL_INTERVAL {
+            last_poll_time = Instant::now();
+            let cfg = state.config.load_full();
+            let new_brightness = brightness_percent(&cfg, crate::away_today(&state));
+            if new_brightness != brightness_ramp.target() {
+                let ramp = std::time::Duration::from_millis(cfg.display.brightness_ramp_ms);
+                brightness_ramp.set_target(new_brightness, ramp, Instant::now());
+                info!("[RENDER] Brightness ramping to {}% over {}ms", new_brightness, ramp.as_millis());
+            }
+            // Drawing options only change with the config's display side
+            if !Arc::ptr_eq(&cfg, &rendered_config) {
+                if cfg.diff(&rendered_config).display {
+                    renderer.apply_config(&cfg);
+                }
+                rendered_config = Arc::clone(&cfg);
+            }
+            idle_clock = cfg.display.idle_clock;
+            watch = cfg.display.watch.clone();
+            if cfg.display.pages != pages {
+                pages = cfg.display.pages.clone();
+                page_cycle = PageCycle::default();
+            }
+            cycle_transition = std::time::Duration::from_millis(cfg.display.cycle_transition_ms);
+            timezone = cfg.tz();
+            if crate::display_off(&state) != display_off {
+                display_off = !display_off;
+                info!("[RENDER] {}", if display_off { "Display off, panel blank" } else { "Display back on" });
+            }
+            let suspension = Suspension::new(
+                (**state.suspended_routes.load()).clone(),
+                &snapshot.trains,
+                &cfg.alternates,
+            );
+            let leave = journey::leave_now(&snapshot.trains, &cfg.journey, unix_now_secs() as f64)
+                .filter(|l| !suspension.routes.contains(&l.route));
+            let mismatch = config_mismatch_secs(&state, unix_now_secs()).is_some();
+            let banner = match (&**state.setup_ssid.load(), mismatch, &suspension.instead) {
+                (Some(ssid), _, _) => Some(Banner::Setup { ssid: ssid.clone() }),
+                (None, true, _) => Some(Banner::CheckConfig),
+                (None, false, Some(t)) => Some(Banner::UseInstead {
+                    route: t.route.clone(),
+                    is_express: t.is_express,
+                }),
+                (None, false, None) => leave.map(|l| Banner::LeaveNow {
+                    route: l.route,
+                    is_express: l.is_express,
+                }),
+            };
+            // Do-not-disturb keeps only the setup and config problem banners
+            let banner = banner.filter(|b| {
+                !do_not_disturb || matches!(b, Banner::Setup { .. } | Banner::CheckConfig)
+            });
+            renderer.set_banner(banner);
+            renderer.set_suspension(suspension);
+            renderer.set_stale_routes(state.degraded_routes.load().iter().cloned().collect());
+            let connections = cfg.trip.as_ref().map_or_else(Vec::new, |t| {
+                trip::connections(&snapshot.trains, &state.transfer_trains.load(), t)
+            });
+            renderer.set_connections(connections);
+            scroll_speed = cfg.display.scroll_speed / fps as f32;
+
+            // Backend handoff between frames: the old display is dropped
+            // (releasing the panel, window or socket) before the new one
+            // starts, at the brightness the ramp last applied
+            let panel = &cfg.panel;
+            if panel.needs_new_display(&current_panel) {
+                drop(std::mem::replace(&mut display, Box::new(MockDisplay)));
+                display = create_display(brightness_ramp.applied(), panel);
+                current_viewport = Viewport::from_config(panel);
+                fps = render_fps(panel);
+                frame_time = std::time::Duration::from_secs_f64(1.0 / fps);
+                scroll_speed = cfg.display.scroll_speed / fps as f32;
+                swaps.invalidate();
+                info!("[RENDER] Display backend switched to {} ({}fps)", panel.backend.name(), fps);
+            }
+            current_panel = panel.clone();
+
+            let viewport = Viewport::from_config(&cfg.panel);
+            if viewport != current_viewport {
+                display.set_viewport(viewport);
+                current_viewport = viewport;
+                swaps.invalidate();
+                info!(
+                    "[RENDER] Viewport updated to {}x{} at ({}, {})",
+                    viewport.width, viewport.height, viewport.offset_x, viewport.offset_y
+                );
+            }
+
+            if cfg.display.post != post.steps() {
+                post = Pipeline::new(&cfg.display.post);
+                swaps.invalidate();
+                info!("[RENDER] Post-processing updated: {:?}", cfg.display.post);
+            }
+
+            state
+                .frame_timing
+                .store(Arc::new(timings.iter().map(FrameTimings::report).collect()));
+            state.pipeline.store(Arc::new(snapshot_frames.report()));
+
+            let now = unix_now_secs();
+            state.last_render_tick.store(now, Ordering::Relaxed);
+            state
+                .frame_activity
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .record(&state.latest_frame.load(), now);
+        }
+
+        // Stats logging every 5 minutes
+        if last_stats_time.elapsed() >= STATS_INTERVAL {
+            let fps = frame_count as f64 / last_stats_time.elapsed().as_secs_f64();
+            let window = timings[1].report();
+            info!(
+                "[STATS] FPS: {:.1} | Missed: {}/{} ({:.1}%) | Frame: avg {:.1}ms, p50 {:.1}ms, p95 {:.1}ms, p99 {:.1}ms, max {:.1}ms, stall {:.1}ms | Unchanged: {} | Trains: {} | Alerts: {}",
+                fps,
+                missed_frames, frame_count,
+                if frame_count > 0 { missed_frames as f64 / frame_count as f64 * 100.0 } else { 0.0 },
+                if frame_count > 0 { total_frame_us as f64 / frame_count as f64 / 1000.0 } else { 0.0 },
+                window.p50_us as f64 / 1000.0,
+                window.p95_us as f64 / 1000.0,
+                window.p99_us as f64 / 1000.0,
+                max_frame_us as f64 / 1000.0,
+                window.longest_stall_us as f64 / 1000.0,
+                swaps.take_skipped(),
+                snapshot.trains.len(),
+                snapshot.alerts.len(),
+            );
+            frame_count = 0;
+            missed_frames = 0;
+            max_frame_us = 0;
+            total_frame_us = 0;
+            last_stats_time = Instant::now();
+        }
+
+        // Sleep to maintain target FPS
+        let elapsed = frame_start.elapsed();
+        if elapsed < frame_time {
+            std::thread::sleep(frame_time - elapsed);
+        }
+    }
+
+    info!("[RENDER] Render loop stopped");
+}
+
+/// Render loop frame rate: 60fps, or just enough to keep the web previews
+/// current when headless.
+fn render_fps(panel: &PanelConfig) -> f64 {
+    match panel.backend {
+        DisplayBackend::Headless => 2.0,
+        _ => 60.0,
+    }
+}
+
+/// Panel brightness (1-100%) for the current local time, per
+/// `display.schedule` or the static `display.brightness`; on an `away` day,
+/// the calendar's `away_brightness` if it has one.
+fn brightness_percent(config: &Config, away: bool) -> u8 {
+    let away_level = config.calendar.as_ref().and_then(|c| c.away_brightness).filter(|_| away);
+    let level = away_level.unwrap_or_else(|| config.display.brightness_at(config.tz().now().time()));
+    ((level * 100.0).round() as u8).clamp(1, 100)
+}
+
+/// Wait for SIGTERM or SIGINT (Ctrl-C).
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = signal::ctrl_c().await {
+            error!("Failed to install Ctrl-C handler: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match signal::unix::signal(signal::unix::SignalKind::terminate()) {
+            Ok(mut sig) => {
+                sig.recv().await;
+            }
+            Err(e) => {
+                error!("Failed to install SIGTERM handler: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use crate::CONFIG_MISMATCH_AFTER_SECS;
+
+    use models::{Alert, Direction, DisplaySnapshot, Train};
+
+    fn test_config() -> Config {
+        Config {
+            station_stops: vec![("127N".to_string(), "127S".to_string())],
+            routes: vec!["1".to_string()],
+            display: config::DisplayConfig {
+                brightness: 0.5,
+                max_trains: 10,
+                show_alerts: true,
+                layout: config::Layout::Standard,
+                arrival_format: config::ArrivalFormat::Minutes,
+                clock_after_minutes: 20,
+                locale: "en".to_string(),
+                strings: Default::default(),
+                schedule: Vec::new(),
+                off_hours: None,
+                brightness_ramp_ms: 1500,
+                fonts: config::FontStyles::default(),
+                scroll_speed: 60.0,
+                smooth_scroll: true,
+                cycle_transition_ms: 170,
+                alert_scroll: config::AlertScrollConfig::default(),
+                alert_ticker: config::AlertTickerConfig::default(),
+                alert_qr_seconds: 0,
+                top_row: Vec::new(),
+                post: Vec::new(),
+                alert_route_icons: true,
+                delay_marker_minutes: 5,
+                idle_clock: false,
+                color_by_route: false,
+                route_service: Default::default(),
+                group_by_platform: false,
+                marquee: false,
+                flip_rows: false,
+                watch: None,
+                pages: Vec::new(),
+                arriving_text: config::ArrivingText::Auto,
+            },
+            refresh: config::RefreshConfig::default(),
+            screenshots: config::ScreenshotConfig::default(),
+            fleet: config::FleetConfig::default(),
+            panel: config::PanelConfig::default(),
+            icons: config::IconConfig::default(),
+            analytics: config::AnalyticsConfig::default(),
+            database: config::DatabaseConfig::default(),
+            journey: config::JourneyConfig::default(),
+            trip: None,
+            alternates: config::AlternatesConfig::default(),
+            web: config::WebConfig::default(),
+            setup: config::SetupConfig::default(),
+            buttons: Vec::new(),
+            route_schedule: Vec::new(),
+            static_gtfs: config::StaticGtfsConfig::default(),
+            calendar: None,
+            timezone: None,
+            stations_path: None,
+        }
+    }
+
+    fn make_state(alerts: Vec<Alert>) -> Arc<AppState> {
+        let mut am = mta::alerts::AlertManager::new();
+        am.filter_and_sort(&alerts);
+        Arc::new(AppState {
+            config: ArcSwap::from_pointee(test_config()),
+            snapshot: ArcSwap::from_pointee(DisplaySnapshot::empty()),
+            trips: ArcSwap::from_pointee(HashMap::new()),
+            transfer_trains: ArcSwap::from_pointee(Vec::new()),
+            latest_frame: ArcSwap::from_pointee(FrameBuffer::new()),
+            frame_updates: tokio::sync::broadcast::channel(FRAME_UPDATES_CAPACITY).0,
+            frame_activity: Mutex::new(FrameActivity::new()),
+            frame_timing: ArcSwap::from_pointee(Vec::new()),
+            fetch_trace: ArcSwap::from_pointee(telemetry::FetchTrace::none()),
+            pipeline: ArcSwap::from_pointee(Default::default()),
+            alert_manager: Mutex::new(am),
+            fetched_alerts: ArcSwap::from_pointee(Vec::new()),
+            suspended_routes: ArcSwap::from_pointee(HashSet::new()),
+            active_routes: ArcSwap::from_pointee(vec!["1".to_string()]),
+            degraded_routes: ArcSwap::from_pointee(Vec::new()),
+            service_schedule: ArcSwap::from_pointee(None),
+            setup_ssid: ArcSwap::from_pointee(None),
+            info_screen: ArcSwap::from_pointee(None),
+            analytics: Mutex::new(Analytics::new(
+                analytics::store::Store::Files(PathBuf::from("analytics")),
+                chrono::Local::now().date_naive(),
+            )),
+            logs: LogHub::new(),
+            http_log: RequestLog::new(),
+            served_frames: FrameHistory::new(),
+            config_path: PathBuf::from("config.json"),
+            instance: None,
+            shutdown: CancellationToken::new(),
+            restart: RestartControl::new(),
+            config_changed: tokio::sync::Notify::new(),
+            fleet_sync: tokio::sync::Notify::new(),
+            last_fetch_success: AtomicU64::new(0),
+            no_match_since: AtomicU64::new(0),
+            away_days: ArcSwap::from_pointee(HashSet::new()),
+            do_not_disturb: AtomicBool::new(false),
+            recording: AtomicBool::new(false),
+            favorites_lock: Mutex::new(()),
+            last_render_tick: AtomicU64::new(0),
+        })
+    }
+
+    fn make_train(route: &str, dest: &str, minutes: i32) -> Train {
+        Train {
+            route: route.into(),
+            destination: dest.into(),
+            minutes,
+            is_express: false,
+            arrival_timestamp: 0.0,
+            direction: Direction::Uptown,
+            stop_id: "127N".into(),
+            delay_secs: None,
+            trip_id: String::new(),
+        }
+    }
+
+    fn make_alert(id: &str) -> Alert {
+        Alert {
+            text: format!("Alert {}", id),
+            affected_routes: HashSet::from(["1".to_string()]),
+            priority: 1,
+            alert_id: id.to_string(),
+            url: None,
+            is_ticker: false,
+        }
+    }
+
+    #[test]
+    fn test_alert_triggers_on_arrival() {
+        let state = make_state(vec![make_alert("a1")]);
+        let snapshot = DisplaySnapshot {
+            trains: vec![make_train("1", "Uptown", 0)], // arriving!
+            alerts: vec![make_alert("a1")],
+            fetched_at: 0.0,
+        };
+        let mut renderer = display::renderer::Renderer::new();
+        let mut alert = AlertState::new();
+
+        assert!(!alert.show_alert);
+
+        alert.update(&state, &snapshot, &mut renderer, 1.0, Duration::from_secs(90));
+
+        assert!(alert.show_alert, "alert should trigger when train at 0 min");
+        assert!(alert.current_alert.is_some());
+        assert_eq!(alert.triggered_by.as_ref().unwrap().0, "1");
+    }
+
+    #[test]
+    fn test_alert_does_not_trigger_without_arrival() {
+        let state = make_state(vec![make_alert("a1")]);
+        let snapshot = DisplaySnapshot {
+            trains: vec![make_train("1", "Uptown", 3)], // not arriving
+            alerts: vec![make_alert("a1")],
+            fetched_at: 0.0,
+        };
+        let mut renderer = display::renderer::Renderer::new();
+        let mut alert = AlertState::new();
+
+        alert.update(&state, &snapshot, &mut renderer, 1.0, Duration::from_secs(90));
+
+        assert!(!alert.show_alert, "alert should not trigger when no train at 0 min");
+    }
+
+    #[test]
+    fn test_alert_clears_when_all_shown() {
+        let state = make_state(vec![make_alert("a1")]);
+        let snapshot = DisplaySnapshot {
+            trains: vec![make_train("1", "Uptown", 0)],
+            alerts: vec![make_alert("a1")],
+            fetched_at: 0.0,
+        };
+        let mut renderer = display::renderer::Renderer::new();
+        let mut alert = AlertState::new();
+
+        // Trigger alert
+        alert.update(&state, &snapshot, &mut renderer, 1.0, Duration::from_secs(90));
+        assert!(alert.show_alert);
+
+        // Simulate scroll completing by setting offset past the threshold
+        let complete_dist = renderer.get_scroll_complete_distance() as f32;
+        alert.scroll_offset = complete_dist + 1.0;
+
+        // Update should mark as displayed and clear (only one alert)
+        alert.update(&state, &snapshot, &mut renderer, 0.0, Duration::from_secs(90));
+
+        assert!(!alert.show_alert, "alert should clear after all shown this cycle");
+    }
+
+    #[test]
+    fn test_alert_loops_before_clearing() {
+        let state = make_state(vec![make_alert("a1")]);
+        let mut cfg = test_config();
+        cfg.display.alert_scroll.mode = config::ScrollMode::Loop;
+        state.config.store(Arc::new(cfg));
+        let snapshot = DisplaySnapshot {
+            trains: vec![make_train("1", "Uptown", 0)],
+            alerts: vec![make_alert("a1")],
+            fetched_at: 0.0,
+        };
+        let mut renderer = display::renderer::Renderer::new();
+        let mut alert = AlertState::new();
+        alert.update(&state, &snapshot, &mut renderer, 1.0, Duration::from_secs(90));
+        let complete_dist = renderer.get_scroll_complete_distance() as f32;
+
+        // First pass ends: same alert starts over
+        alert.scroll_offset = complete_dist + 1.0;
+        alert.update(&state, &snapshot, &mut renderer, 0.0, Duration::from_secs(90));
+        assert!(alert.show_alert);
+        assert_eq!(alert.scroll_offset, 0.0);
+        assert_eq!(alert.current_alert.as_ref().unwrap().alert_id, "a1");
+
+        // Second (last) pass ends
+        alert.scroll_offset = complete_dist + 1.0;
+        alert.update(&state, &snapshot, &mut renderer, 0.0, Duration::from_secs(90));
+        assert!(!alert.show_alert);
+    }
+
+    #[test]
+    fn test_alert_qr_page_follows_linked_alert() {
+        let linked = Alert { url: Some("https://mta.info/alerts".to_string()), ..make_alert("a1") };
+        let state = make_state(vec![linked.clone(), make_alert("a2")]);
+        let mut cfg = test_config();
+        cfg.display.alert_qr_seconds = 5;
+        state.config.store(Arc::new(cfg));
+        let snapshot = DisplaySnapshot {
+            trains: vec![make_train("1", "Uptown", 0)],
+            alerts: vec![linked, make_alert("a2")],
+            fetched_at: 0.0,
+        };
+        let mut renderer = display::renderer::Renderer::new();
+        let mut alert = AlertState::new();
+        alert.update(&state, &snapshot, &mut renderer, 1.0, Duration::from_secs(90));
+        let complete_dist = renderer.get_scroll_complete_distance() as f32;
+        assert_eq!(alert.qr_url(), None, "not while scrolling");
+
+        alert.scroll_offset = complete_dist + 1.0;
+        alert.update(&state, &snapshot, &mut renderer, 0.0, Duration::from_secs(90));
+        assert_eq!(alert.qr_url(), Some("https://mta.info/alerts"));
+        alert.update(&state, &snapshot, &mut renderer, 0.0, Duration::from_secs(90));
+        assert_eq!(alert.current_alert.as_ref().unwrap().alert_id, "a1", "held on the page");
+
+        // Page time up: on to the next alert, which has no link
+        alert.qr_until = Some(Instant::now() - Duration::from_millis(1));
+        alert.update(&state, &snapshot, &mut renderer, 0.0, Duration::from_secs(90));
+        assert_eq!(alert.current_alert.as_ref().unwrap().alert_id, "a2");
+        alert.scroll_offset = complete_dist + 1.0;
+        alert.update(&state, &snapshot, &mut renderer, 0.0, Duration::from_secs(90));
+        assert_eq!(alert.qr_url(), None);
+    }
+
+    #[test]
+    fn test_alert_ticker_runs_on_interval() {
+        let state = make_state(vec![make_alert("a1"), make_alert("a2")]);
+        let mut cfg = test_config();
+        cfg.display.alert_ticker.enabled = true;
+        cfg.display.alert_ticker.interval_seconds = 60;
+        state.config.store(Arc::new(cfg));
+        let snapshot = DisplaySnapshot {
+            trains: vec![make_train("1", "Uptown", 5)], // no arrival needed
+            alerts: vec![make_alert("a1"), make_alert("a2")],
+            fetched_at: 0.0,
+        };
+        let mut renderer = display::renderer::Renderer::new();
+        let mut alert = AlertState::new();
+        alert.update(&state, &snapshot, &mut renderer, 1.0, Duration::from_secs(90));
+        assert!(alert.show_alert);
+        let ticker = alert.current_alert.as_ref().unwrap();
+        assert!(ticker.is_ticker);
+        assert_eq!(ticker.text.matches(mta::alerts::TICKER_SEPARATOR).count(), 1);
+
+        // One pass, then nothing until the interval is up
+        alert.scroll_offset = renderer.get_scroll_complete_distance() as f32;
+        alert.update(&state, &snapshot, &mut renderer, 1.0, Duration::from_secs(90));
+        assert!(!alert.show_alert);
+        alert.update(&state, &snapshot, &mut renderer, 1.0, Duration::from_secs(90));
+        assert!(!alert.show_alert);
+        alert.ticker_done = Some(Instant::now() - Duration::from_secs(61));
+        alert.update(&state, &snapshot, &mut renderer, 1.0, Duration::from_secs(90));
+        assert!(alert.show_alert);
+    }
+
+    #[test]
+    fn test_continuous_ticker_never_gives_the_row_back() {
+        let state = make_state(vec![make_alert("a1")]);
+        let mut cfg = test_config();
+        cfg.display.alert_ticker.enabled = true;
+        cfg.display.alert_ticker.interval_seconds = 0;
+        state.config.store(Arc::new(cfg));
+        let snapshot = DisplaySnapshot {
+            trains: vec![make_train("1", "Uptown", 5)],
+            alerts: vec![make_alert("a1")],
+            fetched_at: 0.0,
+        };
+        let mut renderer = display::renderer::Renderer::new();
+        let mut alert = AlertState::new();
+        alert.update(&state, &snapshot, &mut renderer, 1.0, Duration::from_secs(90));
+        alert.scroll_offset = renderer.get_scroll_complete_distance() as f32;
+        alert.update(&state, &snapshot, &mut renderer, 1.0, Duration::from_secs(90));
+        assert!(alert.show_alert, "next pass starts on the same frame");
+        assert_eq!(alert.scroll_offset, 0.0);
+
+        // Queue emptied: the trains get the row back after the pass
+        state.alert_manager.lock().unwrap().filter_and_sort(&[]);
+        alert.scroll_offset = renderer.get_scroll_complete_distance() as f32;
+        alert.update(&state, &snapshot, &mut renderer, 1.0, Duration::from_secs(90));
+        assert!(!alert.show_alert);
+    }
+
+    #[test]
+    fn test_new_suspension_interrupts_and_resumes() {
+        let state = make_state(vec![make_alert("a1"), make_alert("a2")]);
+        let snapshot = DisplaySnapshot {
+            trains: vec![make_train("1", "Uptown", 0)],
+            alerts: vec![make_alert("a1"), make_alert("a2")],
+            fetched_at: 0.0,
+        };
+        let mut renderer = display::renderer::Renderer::new();
+        let mut alert = AlertState::new();
+        alert.update(&state, &snapshot, &mut renderer, 1.0, Duration::from_secs(90));
+        alert.scroll_offset = 50.0;
+
+        // Next fetch brings a new suspension
+        let mut suspended = make_alert("a3");
+        suspended.priority = mta::alerts::NO_SERVICE_PRIORITY;
+        state.alert_manager.lock().unwrap().filter_and_sort(&[make_alert("a1"), make_alert("a2"), suspended]);
+        alert.update(&state, &snapshot, &mut renderer, 1.0, Duration::from_secs(90));
+        assert_eq!(alert.current_alert.as_ref().unwrap().alert_id, "a3");
+        assert_eq!(alert.scroll_offset, 0.0);
+
+        alert.scroll_offset = renderer.get_scroll_complete_distance() as f32;
+        alert.update(&state, &snapshot, &mut renderer, 1.0, Duration::from_secs(90));
+        assert_eq!(alert.current_alert.as_ref().unwrap().alert_id, "a1", "back to the rotation");
+        assert_eq!(alert.scroll_offset, 50.0);
+        assert!(alert.show_alert);
+
+        // Shown once only
+        alert.update(&state, &snapshot, &mut renderer, 1.0, Duration::from_secs(90));
+        assert_eq!(alert.current_alert.as_ref().unwrap().alert_id, "a1");
+    }
+
+    #[test]
+    fn test_alert_until_departed_stops_with_train() {
+        let state = make_state(vec![make_alert("a1")]);
+        let mut cfg = test_config();
+        cfg.display.alert_scroll.mode = config::ScrollMode::UntilDeparted;
+        state.config.store(Arc::new(cfg));
+        let at_platform = DisplaySnapshot {
+            trains: vec![make_train("1", "Uptown", 0)],
+            alerts: vec![make_alert("a1")],
+            fetched_at: 0.0,
+        };
+        let mut renderer = display::renderer::Renderer::new();
+        let mut alert = AlertState::new();
+        alert.update(&state, &at_platform, &mut renderer, 1.0, Duration::from_secs(90));
+        let complete_dist = renderer.get_scroll_complete_distance() as f32;
+
+        for _ in 0..3 {
+            alert.scroll_offset = complete_dist + 1.0;
+            alert.update(&state, &at_platform, &mut renderer, 0.0, Duration::from_secs(90));
+            assert!(alert.show_alert, "keeps looping while the train is in");
+        }
+
+        let departed = DisplaySnapshot {
+            trains: vec![make_train("1", "Uptown", 4)],
+            ..at_platform
+        };
+        alert.scroll_offset = complete_dist + 1.0;
+        alert.update(&state, &departed, &mut renderer, 0.0, Duration::from_secs(90));
+        assert!(!alert.show_alert);
+    }
+
+    #[test]
+    fn test_alert_max_duration_timeout() {
+        let state = make_state(vec![make_alert("a1")]);
+        let snapshot = DisplaySnapshot {
+            trains: vec![make_train("1", "Uptown", 0)],
+            alerts: vec![make_alert("a1")],
+            fetched_at: 0.0,
+        };
+        let mut renderer = display::renderer::Renderer::new();
+        let mut alert = AlertState::new();
+
+        // Trigger alert
+        alert.update(&state, &snapshot, &mut renderer, 1.0, Duration::from_secs(90));
+        assert!(alert.show_alert);
+
+        // Simulate timeout by setting cycle_start_time far in the past
+        alert.cycle_start_time = Instant::now() - Duration::from_secs(100);
+
+        // Update with a very short max_duration to trigger timeout
+        alert.update(&state, &snapshot, &mut renderer, 1.0, Duration::from_secs(90));
+
+        assert!(!alert.show_alert, "alert should clear after max duration timeout");
+    }
+
+    #[test]
+    fn test_alert_departure_resets_cycle() {
+        let alerts = vec![make_alert("a1"), make_alert("a2")];
+        let state = make_state(alerts.clone());
+        let mut renderer = display::renderer::Renderer::new();
+        let mut alert = AlertState::new();
+
+        // Train arrives, triggers alerts
+        let snapshot_arrive = DisplaySnapshot {
+            trains: vec![make_train("1", "Uptown", 0)],
+            alerts: alerts.clone(),
+            fetched_at: 0.0,
+        };
+        alert.update(&state, &snapshot_arrive, &mut renderer, 1.0, Duration::from_secs(90));
+        assert!(alert.show_alert);
+        assert_eq!(alert.triggered_by.as_ref().unwrap(), &("1".to_string(), "Uptown".to_string()));
+    }
+
+    #[test]
+    fn test_watch_cue_fires_once_per_train() {
+        let watch = config::Watch {
+            route: "1".to_string(),
+            direction: Direction::Uptown,
+            minutes: 3,
+            effect: Default::default(),
+            seconds: 2,
+        };
+        let train = |trip: &str, minutes| Train { trip_id: trip.to_string(), ..make_train("1", "Van Cortlandt Park", minutes) };
+        let t0 = Instant::now();
+        let at = |ms| t0 + std::time::Duration::from_millis(ms);
+        let mut cue = WatchCue::default();
+
+        assert!(!cue.update(Some(&watch), &[train("a", 5)], t0), "not in range yet");
+        assert!(cue.update(Some(&watch), &[train("a", 3)], at(0)));
+        assert!(!cue.update(Some(&watch), &[train("a", 3)], at(300)), "pulses off");
+        assert!(cue.update(Some(&watch), &[train("a", 2)], at(500)), "and on");
+        assert!(!cue.update(Some(&watch), &[train("a", 1)], at(2000)), "for its seconds");
+        assert!(!cue.update(Some(&watch), &[train("a", 1)], at(2500)), "once per train");
+        assert!(cue.update(Some(&watch), &[train("b", 3)], at(3000)), "the next train cues again");
+        assert!(!cue.update(None, &[train("b", 3)], at(3000)), "off without a watch");
+    }
+
+    #[test]
+    fn test_page_cycle_rotates() {
+        let pages = vec![
+            config::Page { kind: config::PageKind::Trains, seconds: 10 },
+            config::Page { kind: config::PageKind::Clock, seconds: 5 },
+        ];
+        let t0 = Instant::now();
+        let at = |secs| t0 + std::time::Duration::from_secs(secs);
+        let mut cycle = PageCycle::default();
+
+        assert!(cycle.current(&[], t0).is_none(), "no pages = trains only");
+        assert_eq!(cycle.current(&pages, t0).unwrap().kind, config::PageKind::Trains);
+        assert_eq!(cycle.current(&pages, at(9)).unwrap().kind, config::PageKind::Trains);
+        assert_eq!(cycle.current(&pages, at(10)).unwrap().kind, config::PageKind::Clock);
+        assert_eq!(cycle.current(&pages, at(14)).unwrap().kind, config::PageKind::Clock);
+        assert_eq!(cycle.current(&pages, at(15)).unwrap().kind, config::PageKind::Trains, "wraps around");
+    }
+
+    #[test]
+    fn test_config_mismatch_needs_sustained_gap() {
+        let state = make_state(Vec::new());
+        let now = 1_700_000_000;
+        assert_eq!(config_mismatch_secs(&state, now), None);
+
+        state.no_match_since.store(now - 60, Ordering::Relaxed);
+        assert_eq!(config_mismatch_secs(&state, now), None, "short gaps are normal");
+
+        state.no_match_since.store(now - CONFIG_MISMATCH_AFTER_SECS, Ordering::Relaxed);
+        assert_eq!(config_mismatch_secs(&state, now), Some(CONFIG_MISMATCH_AFTER_SECS));
+    }
+}
@@ -0,0 +1,104 @@
+//! Whether the panel's picture is changing, so remote monitoring can tell
+//! a frozen panel from a working one without streaming frames (see
+//! `/api/healthz`).
+//!
+//! The render loop samples the frame on screen once a second. A working
+//! sign changes at least every minute (train rotation, countdowns); a sign
+//! with nothing to show, e.g. blanked overnight, reads as inactive too.
+
+use std::collections::VecDeque;
+
+use super::framebuffer::FrameBuffer;
+
+/// Distinct frames covered by the fingerprint.
+const RECENT_FRAMES: usize = 8;
+
+/// The display counts as active if its picture changed this recently.
+pub const ACTIVE_WITHIN_SECS: u64 = 120;
+
+/// Recent distinct frames on the panel.
+#[derive(Default)]
+pub struct FrameActivity {
+    /// (frame hash, unix secs it appeared), oldest first.
+    recent: VecDeque<(u64, u64)>,
+    /// Picture changes since startup.
+    changes: u64,
+}
+
+impl FrameActivity {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Note the frame on the panel at `now` (unix secs).
+    pub fn record(&mut self, frame: &FrameBuffer, now: u64) {
+        let hash = frame.fingerprint();
+        if self.current() == Some(hash) {
+            return;
+        }
+        if self.recent.len() == RECENT_FRAMES {
+            self.recent.pop_front();
+        }
+        self.recent.push_back((hash, now));
+        self.changes += 1;
+    }
+
+    /// Hash of the frame on the panel.
+    pub fn current(&self) -> Option<u64> {
+        self.recent.back().map(|&(hash, _)| hash)
+    }
+
+    /// Combined hash of the recent distinct frames and when they appeared.
+    pub fn fingerprint(&self) -> u64 {
+        self.recent.iter().fold(0xcbf2_9ce4_8422_2325, |acc, &(hash, at)| {
+            (acc ^ hash ^ at.rotate_left(32)).wrapping_mul(0x0100_0000_01b3)
+        })
+    }
+
+    pub fn changes(&self) -> u64 {
+        self.changes
+    }
+
+    /// Seconds the current picture has been up.
+    pub fn unchanged_secs(&self, now: u64) -> Option<u64> {
+        self.recent.back().map(|&(_, at)| now.saturating_sub(at))
+    }
+
+    /// The picture changed within `ACTIVE_WITHIN_SECS`.
+    pub fn is_active(&self, now: u64) -> bool {
+        self.recent.len() > 1 && self.unchanged_secs(now).is_some_and(|s| s <= ACTIVE_WITHIN_SECS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_activity() {
+        let mut activity = FrameActivity::new();
+        assert!(!activity.is_active(0));
+
+        let blank = FrameBuffer::new();
+        let mut lit = FrameBuffer::new();
+        lit.set_pixel(0, 0, (255, 255, 255));
+
+        activity.record(&blank, 100);
+        assert!(!activity.is_active(100), "one frame says nothing yet");
+        activity.record(&lit, 110);
+        let fingerprint = activity.fingerprint();
+        assert!(activity.is_active(110 + ACTIVE_WITHIN_SECS));
+        assert_eq!(activity.current(), Some(lit.fingerprint()));
+
+        // The same frame again changes nothing
+        activity.record(&lit, 200);
+        assert_eq!(activity.fingerprint(), fingerprint);
+        assert_eq!(activity.changes(), 2);
+        assert_eq!(activity.unchanged_secs(200), Some(90));
+        assert!(!activity.is_active(111 + ACTIVE_WITHIN_SECS), "frozen");
+
+        activity.record(&blank, 300);
+        assert_ne!(activity.fingerprint(), fingerprint);
+        assert!(activity.is_active(300));
+    }
+}
@@ -0,0 +1,119 @@
+//! Brightness ramping between config changes.
+//!
+//! The render loop feeds target levels in and calls `step` every frame;
+//! the ramp interpolates linearly so a change (web UI, schedule, sensor)
+//! fades over a second or two instead of jumping.
+
+use std::time::{Duration, Instant};
+
+/// Linear brightness animator (levels are percent, 1-100).
+pub struct BrightnessRamp {
+    /// Level last handed to the display.
+    applied: u8,
+    from: u8,
+    target: u8,
+    started: Instant,
+    duration: Duration,
+}
+
+impl BrightnessRamp {
+    /// Start settled at `level` (the display was created at this brightness).
+    pub fn new(level: u8, now: Instant) -> Self {
+        BrightnessRamp {
+            applied: level,
+            from: level,
+            target: level,
+            started: now,
+            duration: Duration::ZERO,
+        }
+    }
+
+    /// Current ramp target.
+    pub fn target(&self) -> u8 {
+        self.target
+    }
+
+    /// Level last handed to the display.
+    pub fn applied(&self) -> u8 {
+        self.applied
+    }
+
+    /// Begin ramping from the current level to `target` over `duration`.
+    /// A zero duration jumps on the next `step`.
+    pub fn set_target(&mut self, target: u8, duration: Duration, now: Instant) {
+        if target == self.target {
+            return;
+        }
+        self.from = self.applied;
+        self.target = target;
+        self.started = now;
+        self.duration = duration;
+    }
+
+    /// Advance the ramp. Returns the new level when it differs from the
+    /// last one returned, so the caller only touches hardware on change.
+    pub fn step(&mut self, now: Instant) -> Option<u8> {
+        if self.applied == self.target {
+            return None;
+        }
+        let elapsed = now.saturating_duration_since(self.started);
+        let level = if self.duration.is_zero() || elapsed >= self.duration {
+            self.target
+        } else {
+            let t = elapsed.as_secs_f32() / self.duration.as_secs_f32();
+            let from = self.from as f32;
+            (from + (self.target as f32 - from) * t).round() as u8
+        };
+        if level == self.applied {
+            return None;
+        }
+        self.applied = level;
+        Some(level)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ramp_reaches_target_monotonically() {
+        let t0 = Instant::now();
+        let mut ramp = BrightnessRamp::new(20, t0);
+        assert_eq!(ramp.step(t0), None, "settled ramp is idle");
+
+        ramp.set_target(80, Duration::from_secs(2), t0);
+        let mut last = 20;
+        for ms in (0..=2000).step_by(100) {
+            if let Some(level) = ramp.step(t0 + Duration::from_millis(ms)) {
+                assert!(level > last);
+                last = level;
+            }
+        }
+        assert_eq!(last, 80);
+        assert_eq!(ramp.step(t0 + Duration::from_secs(3)), None);
+    }
+
+    #[test]
+    fn test_retarget_mid_ramp_starts_from_current_level() {
+        let t0 = Instant::now();
+        let mut ramp = BrightnessRamp::new(100, t0);
+        ramp.set_target(0, Duration::from_secs(1), t0);
+        let mid = ramp.step(t0 + Duration::from_millis(500)).unwrap();
+        assert_eq!(mid, 50);
+
+        // Reverse direction: no jump back to 100
+        let t1 = t0 + Duration::from_millis(500);
+        ramp.set_target(100, Duration::from_secs(1), t1);
+        let next = ramp.step(t1 + Duration::from_millis(100)).unwrap();
+        assert_eq!(next, 55);
+    }
+
+    #[test]
+    fn test_zero_duration_jumps() {
+        let t0 = Instant::now();
+        let mut ramp = BrightnessRamp::new(10, t0);
+        ramp.set_target(90, Duration::ZERO, t0);
+        assert_eq!(ramp.step(t0), Some(90));
+    }
+}
@@ -0,0 +1,132 @@
+//! Idle screen: a large clock with the weekday and date beside it, shown
+//! instead of placeholder rows when there are no trains (overnight, feed
+//! outage). Enabled by `display.idle_clock`.
+
+use chrono::{Datelike, NaiveDateTime};
+
+use super::colors::{COLOR_GREEN, COLOR_ORANGE};
+use super::fonts::{self, FontStyle};
+use super::framebuffer::FrameBuffer;
+use super::i18n::Strings;
+use super::renderer::CHAR_SPACING;
+
+/// Weekday string keys, Monday first (see `i18n::KEYS`).
+const WEEKDAYS: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+/// Largest scale for the clock digits.
+const MAX_SCALE: usize = 3;
+/// Gap between the clock and the date column.
+const DATE_GAP: i32 = 8;
+
+/// Draws the idle clock. Redraws only when the text on screen changes.
+pub struct ClockRenderer {
+    /// Last frame, keyed by its text, size and the font generation.
+    cache: Option<(String, (usize, usize), u64, FrameBuffer)>,
+}
+
+impl ClockRenderer {
+    pub fn new() -> Self {
+        ClockRenderer { cache: None }
+    }
+
+    /// The idle screen for wall-clock time `now`, in `strings`' formats, as a
+    /// (width, height) frame.
+    pub fn render(&mut self, now: NaiveDateTime, strings: &Strings, size: (usize, usize)) -> FrameBuffer {
+        let time = now.format(strings.get("clock_format")).to_string();
+        let weekday = strings.get(WEEKDAYS[now.weekday().num_days_from_monday() as usize]);
+        let date = now.format(strings.get("date_format")).to_string();
+        let key = format!("{}\n{}\n{}", time, weekday, date);
+        let font_generation = fonts::font_generation();
+        if let Some((cached, cached_size, generation, frame)) = &self.cache {
+            if *cached == key && *cached_size == size && *generation == font_generation {
+                return frame.clone();
+            }
+        }
+
+        let frame = Self::draw(&time, weekday, &date, size);
+        self.cache = Some((key, size, font_generation, frame.clone()));
+        frame
+    }
+
+    fn draw(time: &str, weekday: &str, date: &str, (width, height): (usize, usize)) -> FrameBuffer {
+        let font = fonts::get_font();
+        let style = FontStyle::Regular;
+        let measure = |text: &str| font.measure_text(text, CHAR_SPACING, style) as i32;
+
+        // Clock text at 1x, cropped to its lit rows, then scaled up to fill
+        // the panel height
+        let mut small = FrameBuffer::with_size(measure(time).max(1) as usize, fonts::FONT_HEIGHT);
+        small.draw_text(time, 0, 0, COLOR_GREEN, style, CHAR_SPACING);
+        let lit_rows: Vec<usize> = (0..small.height())
+            .filter(|&y| (0..small.width()).any(|x| small.get_pixel(x, y) != (0, 0, 0)))
+            .collect();
+        let (top, bottom) = match (lit_rows.first(), lit_rows.last()) {
+            (Some(&top), Some(&bottom)) => (top, bottom),
+            _ => (0, 0),
+        };
+        let scale = (height / (bottom - top + 1)).clamp(1, MAX_SCALE);
+
+        let date_width = measure(weekday).max(measure(date));
+        let clock_width = (small.width() * scale) as i32;
+        let left = ((width as i32 - clock_width - DATE_GAP - date_width) / 2).max(0);
+        let clock_top = (height as i32 - ((bottom - top + 1) * scale) as i32) / 2;
+
+        let mut fb = FrameBuffer::with_size(width, height);
+        for y in top..=bottom {
+            for x in 0..small.width() {
+                let px = small.get_pixel(x, y);
+                if px == (0, 0, 0) {
+                    continue;
+                }
+                for (dx, dy) in (0..scale).flat_map(|dx| (0..scale).map(move |dy| (dx, dy))) {
+                    fb.set_pixel(
+                        left + (x * scale + dx) as i32,
+                        clock_top + ((y - top) * scale + dy) as i32,
+                        px,
+                    );
+                }
+            }
+        }
+
+        // Weekday over date, each centered in the column right of the clock
+        let column = left + clock_width + DATE_GAP;
+        let date_top = (height as i32 - 32) / 2;
+        for (text, y) in [(weekday, date_top), (date, date_top + 16)] {
+            let x = column + (date_width - measure(text)) / 2;
+            fb.draw_text(text, x, y, COLOR_ORANGE, style, CHAR_SPACING);
+        }
+        fb
+    }
+}
+
+impl Default for ClockRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::display::framebuffer::{DISPLAY_HEIGHT, DISPLAY_WIDTH};
+
+    const SIZE: (usize, usize) = (DISPLAY_WIDTH, DISPLAY_HEIGHT);
+
+    #[test]
+    fn test_clock_fills_panel_and_caches() {
+        let strings = Strings::default();
+        let at = |s: &str| NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").unwrap();
+        let mut clock = ClockRenderer::new();
+        let frame = clock.render(at("2026-10-16 10:42:05"), &strings, SIZE);
+
+        let lit = |fb: &FrameBuffer, color| {
+            (0..fb.height()).filter(move |&y| (0..fb.width()).any(|x| fb.get_pixel(x, y) == color)).count()
+        };
+        assert!(lit(&frame, COLOR_GREEN) > DISPLAY_HEIGHT / 2, "clock taller than a text row");
+        assert!(lit(&frame, COLOR_ORANGE) > 0, "date drawn");
+
+        // Same minute: same frame; next minute: redrawn
+        let pixels = |fb: FrameBuffer| fb.raw_pixels().to_vec();
+        assert_eq!(pixels(clock.render(at("2026-10-16 10:42:59"), &strings, SIZE)), frame.raw_pixels());
+        assert_ne!(pixels(clock.render(at("2026-10-16 10:43:00"), &strings, SIZE)), frame.raw_pixels());
+    }
+}
@@ -12,6 +12,37 @@ pub fn is_express_capable(route: &str) -> bool {
     matches!(route, "2" | "3" | "4" | "5" | "6" | "7" | "A" | "D" | "E")
 }
 
+/// Every route `route_color` knows, in display order.
+pub const KNOWN_ROUTES: &[&str] = &[
+    "1", "2", "3", "4", "5", "6", "7", "A", "B", "C", "D", "E", "F", "G", "J", "L", "M", "N", "Q",
+    "R", "S", "W", "Z", "GS", "FS", "H", "SI", "SIR",
+];
+
+/// Official MTA bullet color for a route (the LED icons use brighter
+/// variants of some of these).
+pub fn route_color(route: &str) -> Option<Rgb> {
+    let hex = match route {
+        "1" | "2" | "3" => "#EE352E",
+        "4" | "5" | "6" => "#00933C",
+        "7" => "#B933AD",
+        "A" | "C" | "E" => "#0039A6",
+        "B" | "D" | "F" | "M" => "#FF6319",
+        "G" => "#6CBE45",
+        "J" | "Z" => "#996633",
+        "L" => "#A7A9AC",
+        "N" | "Q" | "R" | "W" => "#FCCC0A",
+        "S" | "GS" | "FS" | "H" => "#808183",
+        "SI" | "SIR" => "#1D2F6F",
+        _ => return None,
+    };
+    Some(hex_to_rgb(hex))
+}
+
+/// Format an RGB color as `#RRGGBB`.
+pub fn rgb_to_hex(color: Rgb) -> String {
+    format!("#{:02X}{:02X}{:02X}", color.0, color.1, color.2)
+}
+
 /// Convert a hex color string (e.g., "#FF6644") to RGB.
 pub fn hex_to_rgb(hex: &str) -> Rgb {
     let hex = hex.trim_start_matches('#');
@@ -24,6 +55,27 @@ pub fn hex_to_rgb(hex: &str) -> Rgb {
     (r, g, b)
 }
 
+/// Parse a user-supplied color: a display color name (`green`, `red`,
+/// `orange`, `black`) or a 6-digit hex string with optional `#`.
+///
+/// Unlike `hex_to_rgb`, returns None for malformed input instead of a default.
+pub fn parse_color(value: &str) -> Option<Rgb> {
+    match value.trim().to_lowercase().as_str() {
+        "green" => Some(COLOR_GREEN),
+        "red" => Some(COLOR_RED),
+        "orange" => Some(COLOR_ORANGE),
+        "black" => Some(COLOR_BLACK),
+        other => {
+            let hex = other.trim_start_matches('#');
+            if hex.len() == 6 && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+                Some(hex_to_rgb(hex))
+            } else {
+                None
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -42,4 +94,23 @@ mod tests {
         assert_eq!(hex_to_rgb("00FF00"), (0, 255, 0));
         assert_eq!(hex_to_rgb("#000000"), (0, 0, 0));
     }
+
+    #[test]
+    fn test_route_color() {
+        assert_eq!(route_color("A").map(rgb_to_hex).as_deref(), Some("#0039A6"));
+        assert_eq!(route_color("N"), route_color("W"));
+        assert_eq!(route_color("X"), None);
+        assert!(KNOWN_ROUTES.iter().all(|r| route_color(r).is_some()));
+    }
+
+    #[test]
+    fn test_parse_color() {
+        assert_eq!(parse_color("orange"), Some(COLOR_ORANGE));
+        assert_eq!(parse_color("Green"), Some(COLOR_GREEN));
+        assert_eq!(parse_color("#ff6644"), Some((0xFF, 0x66, 0x44)));
+        assert_eq!(parse_color("0000FF"), Some((0, 0, 255)));
+        assert_eq!(parse_color("#FFF"), None);
+        assert_eq!(parse_color("purple"), None);
+        assert_eq!(parse_color("zzzzzz"), None);
+    }
 }
@@ -0,0 +1,227 @@
+//! Image export for framebuffers and route icons (plus PNG icon import).
+//!
+//! Used by the web preview endpoints to serve pixel-accurate PNGs of what the
+//! sign draws. Each source pixel becomes a `scale` x `scale` block (nearest
+//! neighbor) so the LED grid stays crisp when viewed in a browser.
+
+use std::time::Duration;
+
+use super::fonts::RouteIcon;
+use super::framebuffer::FrameBuffer;
+
+/// Largest accepted upscale factor (192x32 at 16x is 3072x512).
+pub const MAX_SCALE: usize = 16;
+
+/// Encode a framebuffer as an RGB PNG, upscaled by `scale`.
+pub fn framebuffer_to_png(fb: &FrameBuffer, scale: usize) -> Result<Vec<u8>, String> {
+    let scale = scale.clamp(1, MAX_SCALE);
+    let data = upscale(fb.raw_pixels(), fb.width(), fb.height(), 3, scale);
+    encode_png(
+        &data,
+        fb.width() * scale,
+        fb.height() * scale,
+        png::ColorType::Rgb,
+    )
+}
+
+/// Encode frames of the same size as an animated PNG (looping), each shown
+/// for its duration, upscaled by `scale`.
+pub fn frames_to_apng(frames: &[(&FrameBuffer, Duration)], scale: usize) -> Result<Vec<u8>, String> {
+    let scale = scale.clamp(1, MAX_SCALE);
+    let (first, _) = frames.first().ok_or_else(|| "no frames".to_string())?;
+    let (width, height) = (first.width(), first.height());
+    if frames.iter().any(|(fb, _)| (fb.width(), fb.height()) != (width, height)) {
+        return Err("frames differ in size".to_string());
+    }
+
+    let mut out = Vec::new();
+    let mut encoder = png::Encoder::new(&mut out, (width * scale) as u32, (height * scale) as u32);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .set_animated(frames.len() as u32, 0)
+        .map_err(|e| format!("PNG animation: {}", e))?;
+    let mut writer = encoder
+        .write_header()
+        .map_err(|e| format!("PNG header: {}", e))?;
+    for (fb, duration) in frames {
+        let millis = duration.as_millis().clamp(1, u16::MAX as u128) as u16;
+        writer
+            .set_frame_delay(millis, 1000)
+            .map_err(|e| format!("PNG frame: {}", e))?;
+        writer
+            .write_image_data(&upscale(fb.raw_pixels(), width, height, 3, scale))
+            .map_err(|e| format!("PNG data: {}", e))?;
+    }
+    writer.finish().map_err(|e| format!("PNG finish: {}", e))?;
+    Ok(out)
+}
+
+/// Encode a route icon as an RGBA PNG (transparent background), upscaled by `scale`.
+pub fn icon_to_png(icon: &RouteIcon, scale: usize) -> Result<Vec<u8>, String> {
+    let scale = scale.clamp(1, MAX_SCALE);
+    let height = icon.pixels.len();
+    let rgba: Vec<u8> = icon
+        .pixels
+        .iter()
+        .flat_map(|row| row.iter().flat_map(|&(r, g, b, a)| [r, g, b, a]))
+        .collect();
+    let data = upscale(&rgba, icon.width, height, 4, scale);
+    encode_png(
+        &data,
+        icon.width * scale,
+        height * scale,
+        png::ColorType::Rgba,
+    )
+}
+
+/// Decode a PNG (any 8/16-bit color type) into an icon bitmap.
+///
+/// Pixels with non-zero alpha are drawn; the rest are transparent.
+pub fn png_to_icon(bytes: &[u8]) -> Result<RouteIcon, String> {
+    let mut decoder = png::Decoder::new(std::io::Cursor::new(bytes));
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().map_err(|e| format!("PNG header: {}", e))?;
+    let size = reader
+        .output_buffer_size()
+        .ok_or_else(|| "PNG too large".to_string())?;
+    let mut buf = vec![0; size];
+    let info = reader
+        .next_frame(&mut buf)
+        .map_err(|e| format!("PNG data: {}", e))?;
+
+    let channels = match info.color_type {
+        png::ColorType::Grayscale => 1,
+        png::ColorType::GrayscaleAlpha => 2,
+        png::ColorType::Rgb => 3,
+        png::ColorType::Rgba => 4,
+        png::ColorType::Indexed => return Err("unexpanded palette PNG".to_string()),
+    };
+    let width = info.width as usize;
+    let pixels = buf[..info.buffer_size()]
+        .chunks_exact(info.line_size)
+        .map(|line| {
+            line[..width * channels]
+                .chunks_exact(channels)
+                .map(|px| match px {
+                    [v] => (*v, *v, *v, 255),
+                    [v, a] => (*v, *v, *v, *a),
+                    [r, g, b] => (*r, *g, *b, 255),
+                    [r, g, b, a] => (*r, *g, *b, *a),
+                    _ => unreachable!("chunks_exact yields 1-4 channels"),
+                })
+                .collect()
+        })
+        .collect();
+
+    Ok(RouteIcon {
+        pixels,
+        width,
+        baseline_offset: 0,
+    })
+}
+
+/// Nearest-neighbor upscale of a packed pixel buffer.
+fn upscale(src: &[u8], width: usize, height: usize, channels: usize, scale: usize) -> Vec<u8> {
+    if scale == 1 {
+        return src.to_vec();
+    }
+    let out_row_len = width * scale * channels;
+    let mut out = Vec::with_capacity(out_row_len * height * scale);
+    for y in 0..height {
+        let row = &src[y * width * channels..(y + 1) * width * channels];
+        let start = out.len();
+        for px in row.chunks_exact(channels) {
+            for _ in 0..scale {
+                out.extend_from_slice(px);
+            }
+        }
+        // Repeat the widened row for the remaining scanlines
+        for _ in 1..scale {
+            out.extend_from_within(start..start + out_row_len);
+        }
+    }
+    out
+}
+
+/// Encode a packed 8-bit pixel buffer to PNG bytes.
+fn encode_png(
+    data: &[u8],
+    width: usize,
+    height: usize,
+    color: png::ColorType,
+) -> Result<Vec<u8>, String> {
+    let mut out = Vec::new();
+    let mut encoder = png::Encoder::new(&mut out, width as u32, height as u32);
+    encoder.set_color(color);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder
+        .write_header()
+        .map_err(|e| format!("PNG header: {}", e))?;
+    writer
+        .write_image_data(data)
+        .map_err(|e| format!("PNG data: {}", e))?;
+    writer.finish().map_err(|e| format!("PNG finish: {}", e))?;
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PNG_MAGIC: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+    #[test]
+    fn test_upscale() {
+        // 2x1 RGB image → 4x2
+        let src = [1, 2, 3, 4, 5, 6];
+        let out = upscale(&src, 2, 1, 3, 2);
+        assert_eq!(out.len(), 4 * 2 * 3);
+        assert_eq!(&out[0..6], &[1, 2, 3, 1, 2, 3]);
+        assert_eq!(&out[6..12], &[4, 5, 6, 4, 5, 6]);
+        assert_eq!(&out[0..12], &out[12..24], "second scanline repeats the first");
+    }
+
+    #[test]
+    fn test_framebuffer_to_png() {
+        let fb = FrameBuffer::new();
+        let bytes = framebuffer_to_png(&fb, 2).unwrap();
+        assert!(bytes.starts_with(PNG_MAGIC));
+    }
+
+    #[test]
+    fn test_frames_to_apng() {
+        let blank = FrameBuffer::new();
+        let mut lit = FrameBuffer::new();
+        lit.set_pixel(0, 0, (255, 0, 0));
+        let frames = [(&blank, Duration::from_millis(500)), (&lit, Duration::from_millis(250))];
+        let bytes = frames_to_apng(&frames, 2).unwrap();
+
+        let reader = png::Decoder::new(std::io::Cursor::new(bytes)).read_info().unwrap();
+        let info = reader.info();
+        assert_eq!((info.width, info.height), (384, 64));
+        assert_eq!(info.animation_control.unwrap().num_frames, 2);
+
+        let small = FrameBuffer::with_size(64, 32);
+        assert!(frames_to_apng(&[(&blank, Duration::ZERO), (&small, Duration::ZERO)], 1).is_err());
+        assert!(frames_to_apng(&[], 1).is_err());
+    }
+
+    #[test]
+    fn test_icon_to_png() {
+        let font = super::super::fonts::get_font();
+        let icon = font.get_route_icon("A", false).unwrap();
+        let bytes = icon_to_png(icon, 4).unwrap();
+        assert!(bytes.starts_with(PNG_MAGIC));
+    }
+
+    #[test]
+    fn test_png_to_icon_roundtrip() {
+        let font = super::super::fonts::get_font();
+        let icon = font.get_route_icon("7", false).unwrap();
+        let decoded = png_to_icon(&icon_to_png(icon, 1).unwrap()).unwrap();
+        assert_eq!(decoded.width, icon.width);
+        assert_eq!(decoded.pixels, icon.pixels);
+        assert!(png_to_icon(b"not a png").is_err());
+    }
+}
@@ -1,14 +1,26 @@
-use std::collections::HashMap;
-use std::sync::OnceLock;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
 
-use serde::Deserialize;
+use arc_swap::ArcSwap;
+use serde::{Deserialize, Serialize};
+
+use crate::config::IconConfig;
+use crate::display::colors::Rgb;
 
 /// Font height in pixels (from the JSON font definition).
 pub const FONT_HEIGHT: usize = 16;
 
+/// Height of the small font (`FontSize::Small`).
+pub const FONT_HEIGHT_SMALL: usize = 8;
+
 /// Embedded font JSON (compiled into the binary).
 const FONT_JSON: &str = include_str!("../../assets/fonts/mta-sign.json");
 
+/// Embedded small font JSON, same format (no route icons).
+const SMALL_FONT_JSON: &str = include_str!("../../assets/fonts/mta-sign-small.json");
+
 /// Embedded route icon metadata JSON.
 const ICON_METADATA_JSON: &str =
     include_str!("../../assets/icons/route_icon_metadata.json");
@@ -31,93 +43,262 @@ pub struct RouteIcon {
     pub baseline_offset: i32,
 }
 
+impl RouteIcon {
+    /// Repaint every opaque pixel with `color`.
+    fn recolor(&mut self, color: Rgb) {
+        for px in self.pixels.iter_mut().flatten() {
+            if px.3 > 0 {
+                *px = (color.0, color.1, color.2, px.3);
+            }
+        }
+    }
+}
+
+/// Widest custom icon accepted (route bullets are 14px).
+const MAX_CUSTOM_ICON_WIDTH: usize = 32;
+
+/// Size of generated route bullets, matching the embedded circles.
+const BULLET_WIDTH: usize = 14;
+const BULLET_HEIGHT: usize = 13;
+/// Rows the route character is scaled down to inside a generated bullet.
+const BULLET_GLYPH_HEIGHT: usize = 7;
+/// Generated bullet color for routes with no known color.
+const UNKNOWN_ROUTE_COLOR: Rgb = (0x80, 0x81, 0x83);
+
 /// Character bitmap: one `Vec<bool>` per row, LSB-first decoded.
 pub type CharBitmap = Vec<Vec<bool>>;
 
+/// Glyph style. Only regular glyphs are stored in the font JSON; the
+/// others are generated from them at load time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FontStyle {
+    #[default]
+    Regular,
+    /// Top half shifted right; kerned tighter (alert text).
+    Italic,
+    /// Strokes widened by one pixel.
+    Bold,
+    /// Repeated columns collapsed (2px strokes become 1px).
+    Condensed,
+}
+
+/// Glyph size. The small font has only regular glyphs, for layouts that
+/// need more than two rows on a 32px panel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FontSize {
+    #[default]
+    Regular,
+    Small,
+}
+
+impl FontSize {
+    pub fn height(self) -> usize {
+        match self {
+            FontSize::Regular => FONT_HEIGHT,
+            FontSize::Small => FONT_HEIGHT_SMALL,
+        }
+    }
+}
+
+/// Width and left padding of one glyph.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct GlyphMetrics {
+    pub width: usize,
+    pub left_padding: usize,
+}
+
+/// Size of a route icon.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct IconMetrics {
+    pub width: usize,
+    pub height: usize,
+    pub baseline_offset: i32,
+}
+
+/// Everything needed to lay out text off-device exactly as `layout_text`
+/// does (see GET /api/fonts/metrics).
+#[derive(Debug, Clone, Serialize)]
+pub struct FontMetrics {
+    pub height: usize,
+    /// Glyph metrics per style, keyed by character.
+    pub styles: BTreeMap<FontStyle, BTreeMap<char, GlyphMetrics>>,
+    pub small_height: usize,
+    /// Glyph metrics of the small font.
+    pub small: BTreeMap<char, GlyphMetrics>,
+    pub icons: BTreeMap<String, IconMetrics>,
+}
+
 /// The MTA bitmap font with pre-decoded character glyphs and route icons.
 ///
 /// All bitmaps are decoded at load time — zero per-frame allocations.
 pub struct MtaFont {
     /// Pre-decoded character bitmaps (regular).
     chars_decoded: HashMap<char, CharBitmap>,
-    /// Pre-decoded character bitmaps (italic).
-    italic_decoded: HashMap<char, CharBitmap>,
+    /// Pre-decoded character bitmaps (generated styles).
+    styled_decoded: HashMap<(char, FontStyle), CharBitmap>,
     /// Pre-computed character widths.
-    char_widths: HashMap<(char, bool), usize>,
+    char_widths: HashMap<(char, FontStyle), usize>,
     /// Pre-computed left padding (empty columns before first lit pixel).
-    char_left_padding: HashMap<(char, bool), usize>,
+    char_left_padding: HashMap<(char, FontStyle), usize>,
+    /// Pre-decoded small font bitmaps.
+    small_decoded: HashMap<char, CharBitmap>,
+    /// Pre-computed small font widths.
+    small_widths: HashMap<char, usize>,
     /// Route icon bitmaps.
     route_icons: HashMap<String, RouteIcon>,
 }
 
-static MTA_FONT: OnceLock<MtaFont> = OnceLock::new();
+static MTA_FONT: OnceLock<ArcSwap<MtaFont>> = OnceLock::new();
+
+/// Bumped every time the global font is replaced.
+static FONT_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+fn font_slot() -> &'static ArcSwap<MtaFont> {
+    MTA_FONT.get_or_init(|| ArcSwap::from_pointee(MtaFont::load()))
+}
 
-/// Get the global MTA font instance (loaded once).
-pub fn get_font() -> &'static MtaFont {
-    MTA_FONT.get_or_init(MtaFont::load)
+/// Get the global MTA font instance (embedded font until `init_font`).
+pub fn get_font() -> Arc<MtaFont> {
+    font_slot().load_full()
+}
+
+/// Changes whenever the global font is replaced; lets callers drop
+/// anything they pre-rendered with the old glyphs.
+pub fn font_generation() -> u64 {
+    FONT_GENERATION.load(Ordering::Relaxed)
+}
+
+/// Replace the global font.
+pub fn set_font(font: MtaFont) {
+    font_slot().store(Arc::new(font));
+    FONT_GENERATION.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Build the font described by `icons`: font/metadata JSON from disk in
+/// developer mode (embedded copies otherwise), then colors and custom icons.
+pub fn build_font(icons: &IconConfig) -> Result<MtaFont, String> {
+    let read = |path: &std::path::Path| {
+        std::fs::read_to_string(path).map_err(|e| format!("read {}: {}", path.display(), e))
+    };
+    let font_json = match &icons.font_path {
+        Some(path) => Cow::Owned(read(path)?),
+        None => Cow::Borrowed(FONT_JSON),
+    };
+    let metadata_json = match &icons.metadata_path {
+        Some(path) => Cow::Owned(read(path)?),
+        None => Cow::Borrowed(ICON_METADATA_JSON),
+    };
+    let mut font = MtaFont::parse(&font_json, &metadata_json)?;
+    font.apply_icon_config(icons);
+    Ok(font)
+}
+
+/// Install the startup font. A broken developer font falls back to the
+/// embedded one so the sign still comes up.
+pub fn init_font(icons: &IconConfig) {
+    let font = build_font(icons).unwrap_or_else(|e| {
+        tracing::warn!("[FONT] {}; using embedded font", e);
+        let mut font = MtaFont::load();
+        font.apply_icon_config(icons);
+        font
+    });
+    set_font(font);
 }
 
 impl MtaFont {
     fn load() -> Self {
+        Self::parse(FONT_JSON, ICON_METADATA_JSON).expect("embedded font JSON is valid")
+    }
+
+    /// Decode a font from its glyph JSON and route icon metadata JSON.
+    fn parse(font_json: &str, metadata_json: &str) -> Result<Self, String> {
         // Parse font JSON as generic map
         let font_data: HashMap<String, serde_json::Value> =
-            serde_json::from_str(FONT_JSON).expect("embedded font JSON is valid");
-
-        // Extract character glyphs (numeric keys = ASCII codes) as raw u64 rows
-        let mut raw_chars: HashMap<char, Vec<u64>> = HashMap::new();
-        for (key, value) in &font_data {
-            if let Ok(code) = key.parse::<u32>() {
-                if let Some(ch) = char::from_u32(code) {
-                    if let Some(rows) = value.as_array() {
-                        let row_vals: Vec<u64> = rows
-                            .iter()
-                            .filter_map(|v| v.as_u64())
-                            .collect();
-                        raw_chars.insert(ch, row_vals);
-                    }
-                }
-            }
-        }
+            serde_json::from_str(font_json).map_err(|e| format!("font JSON: {}", e))?;
 
-        // Generate italic raw data
-        let raw_italic = Self::generate_italic_raw(&raw_chars);
+        let raw_chars = Self::raw_glyphs(&font_data);
 
-        // Pre-decode all bitmaps
+        // Pre-decode all bitmaps, generating the derived styles
         let chars_decoded: HashMap<char, CharBitmap> = raw_chars
             .iter()
             .map(|(&ch, rows)| (ch, Self::decode_bitmap(rows)))
             .collect();
 
-        let italic_decoded: HashMap<char, CharBitmap> = raw_italic
-            .iter()
-            .map(|(&ch, rows)| (ch, Self::decode_bitmap(rows)))
-            .collect();
+        let mut styled_decoded = HashMap::new();
+        for (style, raw) in [
+            (FontStyle::Italic, Self::generate_italic_raw(&raw_chars)),
+            (FontStyle::Bold, Self::generate_bold_raw(&raw_chars)),
+            (FontStyle::Condensed, Self::generate_condensed_raw(&raw_chars)),
+        ] {
+            for (ch, rows) in raw {
+                styled_decoded.insert((ch, style), Self::decode_bitmap(&rows));
+            }
+        }
 
-        // Pre-compute widths and left-padding for all chars in both styles
+        // Pre-compute widths and left-padding for all chars in all styles
         let mut char_widths = HashMap::new();
         let mut char_left_padding = HashMap::new();
 
-        for (&ch, bitmap) in &chars_decoded {
-            let w = if ch == ' ' { 4 } else { Self::compute_width(bitmap) };
-            char_widths.insert((ch, false), w);
-            char_left_padding.insert((ch, false), Self::compute_left_padding(bitmap));
-        }
-        for (&ch, bitmap) in &italic_decoded {
-            let w = if ch == ' ' { 4 } else { Self::compute_width(bitmap) };
-            char_widths.insert((ch, true), w);
-            char_left_padding.insert((ch, true), Self::compute_left_padding(bitmap));
+        let all = chars_decoded
+            .iter()
+            .map(|(&ch, bitmap)| ((ch, FontStyle::Regular), bitmap))
+            .chain(styled_decoded.iter().map(|(&key, bitmap)| (key, bitmap)));
+        for ((ch, style), bitmap) in all {
+            let w = match (ch, style) {
+                (' ', FontStyle::Condensed) => 3,
+                (' ', _) => 4,
+                _ => Self::compute_width(bitmap),
+            };
+            char_widths.insert((ch, style), w);
+            char_left_padding.insert((ch, style), Self::compute_left_padding(bitmap));
         }
 
+        let small_data: HashMap<String, serde_json::Value> =
+            serde_json::from_str(SMALL_FONT_JSON).map_err(|e| format!("small font JSON: {}", e))?;
+        let small_decoded: HashMap<char, CharBitmap> = Self::raw_glyphs(&small_data)
+            .iter()
+            .map(|(&ch, rows)| (ch, Self::decode_bitmap(rows)))
+            .collect();
+        let small_widths = small_decoded
+            .iter()
+            .map(|(&ch, bitmap)| (ch, if ch == ' ' { 3 } else { Self::compute_width(bitmap) }))
+            .collect();
+
         // Load route icons
-        let route_icons = Self::load_route_icons(&font_data);
+        let route_icons = Self::load_route_icons(&font_data, metadata_json)?;
 
-        MtaFont {
+        let mut font = MtaFont {
             chars_decoded,
-            italic_decoded,
+            styled_decoded,
             char_widths,
             char_left_padding,
+            small_decoded,
+            small_widths,
             route_icons,
+        };
+        font.add_missing_bullets();
+        Ok(font)
+    }
+
+    /// Character glyphs (numeric keys = ASCII codes) as raw u64 rows.
+    fn raw_glyphs(font_data: &HashMap<String, serde_json::Value>) -> HashMap<char, Vec<u64>> {
+        let mut raw_chars = HashMap::new();
+        for (key, value) in font_data {
+            if let Ok(code) = key.parse::<u32>() {
+                if let Some(ch) = char::from_u32(code) {
+                    if let Some(rows) = value.as_array() {
+                        let row_vals: Vec<u64> = rows
+                            .iter()
+                            .filter_map(|v| v.as_u64())
+                            .collect();
+                        raw_chars.insert(ch, row_vals);
+                    }
+                }
+            }
         }
+        raw_chars
     }
 
     /// Generate italic font via simple midpoint shift.
@@ -146,6 +327,50 @@ impl MtaFont {
         italic
     }
 
+    /// Generate bold font by smearing every row 1px to the right.
+    fn generate_bold_raw(chars: &HashMap<char, Vec<u64>>) -> HashMap<char, Vec<u64>> {
+        chars
+            .iter()
+            .map(|(&ch, rows)| (ch, rows.iter().map(|&row| row | (row << 1)).collect()))
+            .collect()
+    }
+
+    /// Generate condensed font by dropping one column from every run of
+    /// identical lit columns, so 2px strokes become 1px. Blank columns
+    /// (letter spacing) are kept.
+    fn generate_condensed_raw(chars: &HashMap<char, Vec<u64>>) -> HashMap<char, Vec<u64>> {
+        let column = |rows: &[u64], x: u32| -> u64 {
+            rows.iter()
+                .enumerate()
+                .fold(0, |col, (y, &row)| col | ((row >> x) & 1) << y)
+        };
+
+        chars
+            .iter()
+            .map(|(&ch, rows)| {
+                let width = rows.iter().map(|r| 64 - r.leading_zeros()).max().unwrap_or(0);
+                let mut condensed = vec![0u64; rows.len()];
+                let mut out_x = 0;
+                for x in 0..width {
+                    let col = column(rows, x);
+                    // Drop the first column of each lit run of two or more
+                    if col != 0 && x + 1 < width && column(rows, x + 1) == col
+                        && (x == 0 || column(rows, x - 1) != col)
+                    {
+                        continue;
+                    }
+                    for (y, row) in rows.iter().enumerate() {
+                        if row & (1 << x) != 0 {
+                            condensed[y] |= 1 << out_x;
+                        }
+                    }
+                    out_x += 1;
+                }
+                (ch, condensed)
+            })
+            .collect()
+    }
+
     /// Decode raw u64 row data into a CharBitmap (LSB-first).
     fn decode_bitmap(rows: &[u64]) -> CharBitmap {
         let mut bitmap = Vec::with_capacity(rows.len());
@@ -184,9 +409,12 @@ impl MtaFont {
     }
 
     /// Load route icon bitmaps from font data + metadata.
-    fn load_route_icons(font_data: &HashMap<String, serde_json::Value>) -> HashMap<String, RouteIcon> {
+    fn load_route_icons(
+        font_data: &HashMap<String, serde_json::Value>,
+        metadata_json: &str,
+    ) -> Result<HashMap<String, RouteIcon>, String> {
         let metadata: HashMap<String, IconMeta> =
-            serde_json::from_str(ICON_METADATA_JSON).expect("embedded icon metadata is valid");
+            serde_json::from_str(metadata_json).map_err(|e| format!("icon metadata JSON: {}", e))?;
 
         let mut icons = HashMap::new();
 
@@ -230,58 +458,366 @@ impl MtaFont {
             );
         }
 
-        icons
+        Ok(icons)
+    }
+
+    /// Apply route color overrides and register custom PNG icons.
+    ///
+    /// Bad entries are logged and skipped — a typo shouldn't take the sign down.
+    fn apply_icon_config(&mut self, icons: &IconConfig) {
+        for (route, color) in &icons.colors {
+            let Some(rgb) = crate::display::colors::parse_color(color) else {
+                tracing::warn!("[FONT] Invalid color '{}' for route {}", color, route);
+                continue;
+            };
+            let mut found = false;
+            for shape in ["CIRCLE", "DIAMOND"] {
+                let name = format!("ROUTE_{}_{}", route.to_uppercase(), shape);
+                if let Some(icon) = self.route_icons.get_mut(&name) {
+                    icon.recolor(rgb);
+                    found = true;
+                }
+            }
+            if !found {
+                let route = route.to_uppercase();
+                if route.is_empty() {
+                    continue;
+                }
+                tracing::info!("[FONT] Generating bullet for route {}", route);
+                self.insert_bullet(&route, false, rgb);
+                if crate::display::colors::is_express_capable(&route) {
+                    self.insert_bullet(&route, true, rgb);
+                }
+            }
+        }
+
+        for (name, path) in &icons.custom {
+            let icon = std::fs::read(path)
+                .map_err(|e| e.to_string())
+                .and_then(|bytes| crate::display::export::png_to_icon(&bytes));
+            let icon = match icon {
+                Ok(icon) => icon,
+                Err(e) => {
+                    tracing::warn!("[FONT] Cannot load custom icon {}: {}", path.display(), e);
+                    continue;
+                }
+            };
+            let height = icon.pixels.len();
+            if icon.width > MAX_CUSTOM_ICON_WIDTH || height > FONT_HEIGHT {
+                tracing::warn!(
+                    "[FONT] Custom icon {} is {}x{}, max is {}x{}",
+                    path.display(),
+                    icon.width,
+                    height,
+                    MAX_CUSTOM_ICON_WIDTH,
+                    FONT_HEIGHT
+                );
+                continue;
+            }
+            tracing::info!(
+                "[FONT] Custom icon [{}] loaded from {} ({}x{})",
+                name,
+                path.display(),
+                icon.width,
+                height
+            );
+            self.route_icons.insert(format!("ROUTE_{}_CIRCLE", name), icon);
+        }
+    }
+
+    /// Generate bullets for known routes the icon data doesn't cover: a
+    /// circle for every route, a diamond for express-capable ones. Colors
+    /// follow the route's circle icon when there is one.
+    fn add_missing_bullets(&mut self) {
+        use crate::display::colors;
+
+        for &route in colors::KNOWN_ROUTES {
+            let color = self
+                .route_icons
+                .get(&format!("ROUTE_{}_CIRCLE", route))
+                .and_then(|icon| icon.pixels.iter().flatten().find(|p| p.3 > 0))
+                .map(|p| (p.0, p.1, p.2))
+                .or_else(|| colors::route_color(route))
+                .unwrap_or(UNKNOWN_ROUTE_COLOR);
+            self.insert_bullet(route, false, color);
+            if colors::is_express_capable(route) {
+                self.insert_bullet(route, true, color);
+            }
+        }
+    }
+
+    /// Add a generated bullet unless the route already has that icon.
+    fn insert_bullet(&mut self, route: &str, is_express: bool, color: Rgb) {
+        let shape = if is_express { "DIAMOND" } else { "CIRCLE" };
+        let name = format!("ROUTE_{}_{}", route, shape);
+        if !self.route_icons.contains_key(&name) {
+            let bullet = self.generate_bullet(route, is_express, color);
+            self.route_icons.insert(name, bullet);
+        }
+    }
+
+    /// Draw a route bullet: a filled circle (diamond for express) in `color`
+    /// with the route's character cut out, scaled down from the font glyph.
+    fn generate_bullet(&self, route: &str, is_express: bool, color: Rgb) -> RouteIcon {
+        let (cx, cy) = ((BULLET_WIDTH - 1) as f32 / 2.0, (BULLET_HEIGHT - 1) as f32 / 2.0);
+        let mut pixels: Vec<Vec<(u8, u8, u8, u8)>> = (0..BULLET_HEIGHT)
+            .map(|y| {
+                (0..BULLET_WIDTH)
+                    .map(|x| {
+                        let (dx, dy) = (x as f32 - cx, y as f32 - cy);
+                        let inside = if is_express {
+                            dx.abs() + dy.abs() <= cx
+                        } else {
+                            dx * dx + dy * dy <= (cy + 0.6) * (cy + 0.6)
+                        };
+                        if inside { (color.0, color.1, color.2, 255) } else { (0, 0, 0, 0) }
+                    })
+                    .collect()
+            })
+            .collect();
+
+        // Shuttles are signed "S" whatever their feed ID
+        let label = match route {
+            "GS" | "FS" | "H" => Some('S'),
+            _ => route.chars().next(),
+        };
+        let glyph = label
+            .and_then(|ch| self.chars_decoded.get(&ch.to_ascii_uppercase()))
+            .map(|bitmap| Self::shrink_glyph(bitmap, BULLET_GLYPH_HEIGHT))
+            .unwrap_or_default();
+        let glyph_width = glyph.first().map_or(0, |row| row.len());
+        let (ox, oy) = ((BULLET_WIDTH - glyph_width) / 2, (BULLET_HEIGHT - glyph.len()) / 2);
+        for (y, row) in glyph.iter().enumerate() {
+            for (x, &lit) in row.iter().enumerate() {
+                if lit {
+                    pixels[oy + y][ox + x] = (0, 0, 0, 0);
+                }
+            }
+        }
+
+        RouteIcon {
+            pixels,
+            width: BULLET_WIDTH,
+            baseline_offset: 0,
+        }
+    }
+
+    /// Crop a glyph to its lit pixels and area-scale it to `height` rows
+    /// (width in proportion). A pixel stays lit when 60% of it was covered.
+    fn shrink_glyph(bitmap: &CharBitmap, height: usize) -> Vec<Vec<bool>> {
+        let lit = |x: usize, y: usize| bitmap[y].get(x).copied().unwrap_or(false);
+        let rows: Vec<usize> = (0..bitmap.len()).filter(|&y| bitmap[y].iter().any(|&p| p)).collect();
+        let (Some(&top), Some(&bottom)) = (rows.first(), rows.last()) else {
+            return Vec::new();
+        };
+        let left = rows.iter().filter_map(|&y| bitmap[y].iter().position(|&p| p)).min().unwrap_or(0);
+        let right = rows.iter().filter_map(|&y| bitmap[y].iter().rposition(|&p| p)).max().unwrap_or(0);
+        let (src_w, src_h) = (right - left + 1, bottom - top + 1);
+        let scale = height.min(src_h) as f32 / src_h as f32;
+        let (dst_w, dst_h) = (((src_w as f32 * scale).round() as usize).clamp(1, BULLET_WIDTH - 4), height.min(src_h));
+
+        (0..dst_h)
+            .map(|ty| {
+                (0..dst_w)
+                    .map(|tx| {
+                        let (y0, y1) = (ty as f32 / scale, (ty + 1) as f32 / scale);
+                        let (x0, x1) = (tx as f32 / scale, (tx + 1) as f32 / scale);
+                        let (mut covered, mut area) = (0.0, 0.0);
+                        for y in y0.floor() as usize..(y1.ceil() as usize).min(src_h) {
+                            let oy = (y1.min(y as f32 + 1.0) - y0.max(y as f32)).max(0.0);
+                            for x in x0.floor() as usize..(x1.ceil() as usize).min(src_w) {
+                                let ox = (x1.min(x as f32 + 1.0) - x0.max(x as f32)).max(0.0);
+                                area += ox * oy;
+                                if lit(left + x, top + y) {
+                                    covered += ox * oy;
+                                }
+                            }
+                        }
+                        area > 0.0 && covered / area >= 0.6
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// A route's bullet icon; routes without one get a generated gray bullet
+    /// if their name is a single character (a route added after the icon
+    /// data), so they never render as a blank gap.
+    pub fn route_bullet(&self, route: &str, is_express: bool) -> Option<Cow<'_, RouteIcon>> {
+        if let Some(icon) = self.get_route_icon(route, is_express) {
+            return Some(Cow::Borrowed(icon));
+        }
+        // Longer unknown tokens are more likely words than routes
+        if route.chars().count() != 1 {
+            return None;
+        }
+        Some(Cow::Owned(self.generate_bullet(route, is_express, UNKNOWN_ROUTE_COLOR)))
+    }
+
+    /// Number of characters with a glyph.
+    pub fn glyph_count(&self) -> usize {
+        self.chars_decoded.len()
+    }
+
+    /// Characters in `text` with no glyph (each listed once, in order).
+    /// Control characters are ignored.
+    pub fn missing_chars(&self, text: &str) -> Vec<char> {
+        let mut missing = Vec::new();
+        for ch in text.chars() {
+            if !ch.is_control() && !self.chars_decoded.contains_key(&ch) && !missing.contains(&ch) {
+                missing.push(ch);
+            }
+        }
+        missing
     }
 
     /// Get the pre-decoded bitmap for a character.
     ///
     /// Returns None if the character is not in the font.
-    /// Falls back to regular bitmap if italic variant doesn't exist.
-    pub fn get_char_bitmap(&self, ch: char, italic: bool) -> Option<&CharBitmap> {
-        if italic {
-            self.italic_decoded.get(&ch).or_else(|| self.chars_decoded.get(&ch))
-        } else {
+    /// Falls back to regular bitmap if the styled variant doesn't exist.
+    pub fn get_char_bitmap(&self, ch: char, style: FontStyle) -> Option<&CharBitmap> {
+        if style == FontStyle::Regular {
             self.chars_decoded.get(&ch)
+        } else {
+            self.styled_decoded.get(&(ch, style)).or_else(|| self.chars_decoded.get(&ch))
+        }
+    }
+
+    /// Get the bitmap for a character in the given size (regular style).
+    pub fn get_char_bitmap_sized(&self, ch: char, size: FontSize) -> Option<&CharBitmap> {
+        match size {
+            FontSize::Regular => self.get_char_bitmap(ch, FontStyle::Regular),
+            FontSize::Small => self.small_decoded.get(&ch),
+        }
+    }
+
+    /// Get the width of a character in the given size (regular style).
+    pub fn get_char_width_sized(&self, ch: char, size: FontSize) -> usize {
+        match size {
+            FontSize::Regular => self.get_char_width(ch, FontStyle::Regular),
+            FontSize::Small => self.small_widths.get(&ch).copied().unwrap_or(3),
         }
     }
 
     /// Get the width of a character in pixels.
-    pub fn get_char_width(&self, ch: char, italic: bool) -> usize {
-        *self.char_widths.get(&(ch, italic))
-            .or_else(|| self.char_widths.get(&(ch, false)))
+    pub fn get_char_width(&self, ch: char, style: FontStyle) -> usize {
+        *self.char_widths.get(&(ch, style))
+            .or_else(|| self.char_widths.get(&(ch, FontStyle::Regular)))
             .unwrap_or(&4)
     }
 
     /// Get left padding (empty columns before first lit pixel).
-    pub fn get_char_left_padding(&self, ch: char, italic: bool) -> usize {
-        *self.char_left_padding.get(&(ch, italic))
-            .or_else(|| self.char_left_padding.get(&(ch, false)))
+    pub fn get_char_left_padding(&self, ch: char, style: FontStyle) -> usize {
+        *self.char_left_padding.get(&(ch, style))
+            .or_else(|| self.char_left_padding.get(&(ch, FontStyle::Regular)))
             .unwrap_or(&0)
     }
 
-    /// Measure the total width of a text string.
-    pub fn measure_text(&self, text: &str, spacing: i32, italic: bool) -> usize {
-        if text.is_empty() {
-            return 0;
-        }
+    /// Lay out `text`, calling `glyph(ch, x)` with each character's x
+    /// offset; returns the total width. Drawing and measuring both go
+    /// through here, so they can't disagree.
+    pub fn layout_text(
+        &self,
+        text: &str,
+        spacing: i32,
+        style: FontStyle,
+        glyph: impl FnMut(char, i32),
+    ) -> usize {
+        self.layout(text, spacing, style, FontSize::Regular, glyph)
+    }
+
+    /// `layout_text` for a font size (regular style).
+    pub fn layout_text_sized(
+        &self,
+        text: &str,
+        spacing: i32,
+        size: FontSize,
+        glyph: impl FnMut(char, i32),
+    ) -> usize {
+        self.layout(text, spacing, FontStyle::Regular, size, glyph)
+    }
+
+    fn layout(
+        &self,
+        text: &str,
+        spacing: i32,
+        style: FontStyle,
+        size: FontSize,
+        mut glyph: impl FnMut(char, i32),
+    ) -> usize {
         let mut chars = text.chars().peekable();
-        let mut total: i32 = 0;
+        let mut x: i32 = 0;
+        let mut width: i32 = 0;
 
         while let Some(ch) = chars.next() {
-            total += self.get_char_width(ch, italic) as i32;
-            if let Some(&next_ch) = chars.peek() {
-                if italic {
+            glyph(ch, x);
+            let char_width = match size {
+                FontSize::Regular => self.get_char_width(ch, style),
+                FontSize::Small => self.get_char_width_sized(ch, size),
+            };
+            width = x + char_width as i32;
+            x = width + spacing;
+            if style == FontStyle::Italic {
+                if let Some(&next_ch) = chars.peek() {
                     // Per-character overlap for italic (matching Python's algorithm)
-                    let next_padding = self.get_char_left_padding(next_ch, italic) as i32;
-                    let overlap = (next_padding - 2).max(0);
-                    total += spacing - overlap;
-                } else {
-                    total += spacing;
+                    let next_padding = self.get_char_left_padding(next_ch, style) as i32;
+                    x -= (next_padding - 2).max(0);
                 }
             }
         }
 
-        total.max(0) as usize
+        width.max(0) as usize
+    }
+
+    /// Measure the total width of a text string.
+    pub fn measure_text(&self, text: &str, spacing: i32, style: FontStyle) -> usize {
+        self.layout_text(text, spacing, style, |_, _| {})
+    }
+
+    /// Measure text in the given size (regular style).
+    pub fn measure_text_sized(&self, text: &str, spacing: i32, size: FontSize) -> usize {
+        self.layout_text_sized(text, spacing, size, |_, _| {})
+    }
+
+    /// Glyph and icon metrics, for laying out text off-device.
+    pub fn metrics(&self) -> FontMetrics {
+        let styles = [FontStyle::Regular, FontStyle::Italic, FontStyle::Bold, FontStyle::Condensed]
+            .into_iter()
+            .map(|style| {
+                let glyphs = self
+                    .chars_decoded
+                    .keys()
+                    .map(|&ch| {
+                        let width = self.get_char_width(ch, style);
+                        let left_padding = self.get_char_left_padding(ch, style);
+                        (ch, GlyphMetrics { width, left_padding })
+                    })
+                    .collect();
+                (style, glyphs)
+            })
+            .collect();
+        let icons = self
+            .route_icons
+            .iter()
+            .map(|(name, icon)| {
+                let metrics = IconMetrics {
+                    width: icon.width,
+                    height: icon.pixels.len(),
+                    baseline_offset: icon.baseline_offset,
+                };
+                (name.clone(), metrics)
+            })
+            .collect();
+        let small = self
+            .small_decoded
+            .iter()
+            .map(|(&ch, bitmap)| {
+                let width = self.get_char_width_sized(ch, FontSize::Small);
+                let left_padding = Self::compute_left_padding(bitmap);
+                (ch, GlyphMetrics { width, left_padding })
+            })
+            .collect();
+        FontMetrics { height: FONT_HEIGHT, styles, small_height: FONT_HEIGHT_SMALL, small, icons }
     }
 
     /// Get a route icon by route ID and express status.
@@ -312,16 +848,16 @@ mod tests {
     #[test]
     fn test_font_loads() {
         let font = get_font();
-        assert!(font.get_char_bitmap('A', false).is_some());
-        assert!(font.get_char_bitmap('0', false).is_some());
-        assert!(font.get_char_bitmap(' ', false).is_some());
-        assert!(font.get_char_bitmap('\u{FFFF}', false).is_none());
+        assert!(font.get_char_bitmap('A', FontStyle::Regular).is_some());
+        assert!(font.get_char_bitmap('0', FontStyle::Regular).is_some());
+        assert!(font.get_char_bitmap(' ', FontStyle::Regular).is_some());
+        assert!(font.get_char_bitmap('\u{FFFF}', FontStyle::Regular).is_none());
     }
 
     #[test]
     fn test_char_bitmap_a() {
         let font = get_font();
-        let bitmap = font.get_char_bitmap('A', false).expect("A should exist");
+        let bitmap = font.get_char_bitmap('A', FontStyle::Regular).expect("A should exist");
         assert_eq!(bitmap.len(), FONT_HEIGHT, "A should have {FONT_HEIGHT} rows");
         // A's first row is 0 (empty), so no lit pixels
         assert!(bitmap[0].iter().all(|&p| !p), "first row of A should be blank");
@@ -330,30 +866,30 @@ mod tests {
     #[test]
     fn test_char_width() {
         let font = get_font();
-        assert!(font.get_char_width('A', false) > 0);
-        assert_eq!(font.get_char_width(' ', false), 4);
+        assert!(font.get_char_width('A', FontStyle::Regular) > 0);
+        assert_eq!(font.get_char_width(' ', FontStyle::Regular), 4);
     }
 
     #[test]
     fn test_measure_text() {
         let font = get_font();
-        let w = font.measure_text("A", -1, false);
+        let w = font.measure_text("A", -1, FontStyle::Regular);
         assert!(w > 0);
 
-        let w2 = font.measure_text("AB", -1, false);
+        let w2 = font.measure_text("AB", -1, FontStyle::Regular);
         // Two chars with spacing=-1 should be roughly: width_A + width_B - 1
-        let wa = font.get_char_width('A', false);
-        let wb = font.get_char_width('B', false);
+        let wa = font.get_char_width('A', FontStyle::Regular);
+        let wb = font.get_char_width('B', FontStyle::Regular);
         assert_eq!(w2, wa + wb - 1);
 
-        assert_eq!(font.measure_text("", -1, false), 0);
+        assert_eq!(font.measure_text("", -1, FontStyle::Regular), 0);
     }
 
     #[test]
     fn test_italic_generation() {
         let font = get_font();
-        let regular = font.get_char_bitmap('A', false).unwrap();
-        let italic = font.get_char_bitmap('A', true).unwrap();
+        let regular = font.get_char_bitmap('A', FontStyle::Regular).unwrap();
+        let italic = font.get_char_bitmap('A', FontStyle::Italic).unwrap();
         assert_eq!(regular.len(), italic.len(), "same number of rows");
         // Italic top half should be wider (shifted right)
         let reg_top_width = regular[1].len(); // row 1 (row 0 is blank for A)
@@ -364,12 +900,31 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_bold_and_condensed_generation() {
+        let font = get_font();
+        let regular = font.get_char_width('M', FontStyle::Regular);
+        assert_eq!(font.get_char_width('M', FontStyle::Bold), regular + 1);
+
+        // M: both 2px stems thin to 1px; letter spacing is kept
+        let condensed = font.get_char_bitmap('M', FontStyle::Condensed).unwrap();
+        assert_eq!(font.get_char_width('M', FontStyle::Condensed), regular - 2);
+        assert_eq!(condensed.len(), FONT_HEIGHT);
+        assert_eq!(font.get_char_left_padding('M', FontStyle::Condensed), 2);
+
+        let text = "Coney Island-Stillwell Av";
+        assert!(
+            font.measure_text(text, -1, FontStyle::Condensed)
+                < font.measure_text(text, -1, FontStyle::Regular)
+        );
+    }
+
     #[test]
     fn test_lsb_first_encoding() {
         // Verify LSB-first: for value 224 = 0b11100000,
         // bits 5,6,7 are set → pixels at x=5,6,7 should be lit
         let font = get_font();
-        let bitmap = font.get_char_bitmap('A', false).unwrap();
+        let bitmap = font.get_char_bitmap('A', FontStyle::Regular).unwrap();
         // Row 1 of 'A' has value 224
         // 224 = 0b11100000 → bits 5,6,7 set
         // LSB-first means bit 0 = x=0, bit 5 = x=5, etc.
@@ -438,17 +993,138 @@ mod tests {
         assert_eq!(diamond.width, 14);
     }
 
+    #[test]
+    fn test_generated_bullets_fill_gaps() {
+        let font = get_font();
+        let opaque = |icon: &RouteIcon, y: usize| icon.pixels[y].iter().filter(|p| p.3 > 0).count();
+
+        // SIR has no icon data: a circle in its route color, the S cut out
+        let sir = font.get_route_icon("SIR", false).unwrap();
+        assert_eq!((sir.width, sir.pixels.len()), (BULLET_WIDTH, BULLET_HEIGHT));
+        let color = crate::display::colors::route_color("SIR").unwrap();
+        assert!(sir.pixels.iter().flatten().all(|p| p.3 == 0 || (p.0, p.1, p.2) == color));
+        assert!(opaque(sir, BULLET_HEIGHT / 2) < BULLET_WIDTH, "label is cut out");
+
+        // Express diamond, colored like the route's circle
+        let diamond = font.get_route_icon("6", true).unwrap();
+        assert_eq!(opaque(diamond, 0), 2);
+        let circle_color = font.get_route_icon("6", false).unwrap().pixels[0][6];
+        assert_eq!(diamond.pixels[0][6], circle_color);
+
+        // Unknown single-letter routes get a gray bullet; words don't
+        assert!(font.route_bullet("X", false).is_some());
+        assert!(font.route_bullet("SHUTTLE", false).is_none());
+    }
+
+    #[test]
+    fn test_icon_config_recolors_and_adds_custom() {
+        let dir = tempfile::tempdir().unwrap();
+        let home = dir.path().join("home.png");
+        let embedded = MtaFont::load();
+        let seven = embedded.get_route_icon("7", false).unwrap();
+        std::fs::write(&home, crate::display::export::icon_to_png(seven, 1).unwrap()).unwrap();
+
+        let icons = IconConfig {
+            colors: [("a".to_string(), "#010203".to_string())].into(),
+            custom: [
+                ("HOME".to_string(), home),
+                ("GONE".to_string(), dir.path().join("missing.png")),
+            ]
+            .into(),
+            ..Default::default()
+        };
+        let mut font = MtaFont::load();
+        font.apply_icon_config(&icons);
+
+        let a = font.get_route_icon("A", false).unwrap();
+        assert!(a.pixels.iter().flatten().all(|p| p.3 == 0 || (p.0, p.1, p.2) == (1, 2, 3)));
+        assert_eq!(font.get_route_icon("HOME", false).unwrap().pixels, seven.pixels);
+        assert!(font.get_route_icon("GONE", false).is_none());
+    }
+
+    #[test]
+    fn test_build_font_from_dev_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let font_path = dir.path().join("font.json");
+        let metadata_path = dir.path().join("metadata.json");
+        // Recolor route 7 purely through the metadata file
+        let metadata = ICON_METADATA_JSON.replacen("\"ROUTE_7_CIRCLE\"", "\"ROUTE_7_X\"", 1);
+        std::fs::write(&font_path, FONT_JSON).unwrap();
+        std::fs::write(&metadata_path, metadata).unwrap();
+
+        let mut icons = IconConfig {
+            font_path: Some(font_path.clone()),
+            metadata_path: Some(metadata_path),
+            ..Default::default()
+        };
+        let font = build_font(&icons).unwrap();
+        assert!(font.get_char_bitmap('A', FontStyle::Regular).is_some());
+        // Metadata read from disk: route 7's icon is gone, so it's generated
+        let embedded = MtaFont::load();
+        assert_ne!(
+            font.get_route_icon("7", false).unwrap().pixels,
+            embedded.get_route_icon("7", false).unwrap().pixels
+        );
+
+        std::fs::write(&font_path, "{ not json").unwrap();
+        assert!(build_font(&icons).err().unwrap().contains("font JSON"));
+        icons.font_path = Some(dir.path().join("missing.json"));
+        assert!(build_font(&icons).err().unwrap().contains("missing.json"));
+    }
+
     #[test]
     fn test_space_width() {
         let font = get_font();
-        assert_eq!(font.get_char_width(' ', false), 4);
-        assert_eq!(font.get_char_width(' ', true), 4);
+        assert_eq!(font.get_char_width(' ', FontStyle::Regular), 4);
+        assert_eq!(font.get_char_width(' ', FontStyle::Italic), 4);
+    }
+
+    #[test]
+    fn test_draw_and_measure_agree() {
+        let font = get_font();
+        let text = "Coney Island-Stillwell Av";
+        for style in [FontStyle::Regular, FontStyle::Italic, FontStyle::Condensed] {
+            let mut offsets = Vec::new();
+            let width = font.layout_text(text, -1, style, |_, x| offsets.push(x));
+            assert_eq!(offsets.len(), text.chars().count());
+            assert_eq!(offsets[0], 0);
+
+            let mut fb = crate::display::framebuffer::FrameBuffer::with_size(width + 8, FONT_HEIGHT);
+            assert_eq!(fb.draw_text(text, 0, 0, (0, 255, 0), style, -1), width, "{:?}", style);
+            assert_eq!(font.measure_text(text, -1, style), width);
+        }
+        assert_eq!(font.measure_text("", -1, FontStyle::Regular), 0);
+
+        let metrics = font.metrics();
+        let a = metrics.styles[&FontStyle::Regular][&'A'];
+        assert_eq!((a.width, a.left_padding), (font.get_char_width('A', FontStyle::Regular), 2));
+        assert!(metrics.icons.contains_key("ROUTE_1_CIRCLE"));
+    }
+
+    #[test]
+    fn test_small_font() {
+        let font = get_font();
+        let bitmap = font.get_char_bitmap_sized('A', FontSize::Small).expect("A should exist");
+        assert_eq!(bitmap.len(), FONT_HEIGHT_SMALL);
+        for ch in font.chars_decoded.keys().chain(&['&', '\u{25C6}']) {
+            assert!(font.get_char_bitmap_sized(*ch, FontSize::Small).is_some(), "{:?} missing", ch);
+        }
+
+        let text = "Van Cortlandt Park";
+        let small = font.measure_text_sized(text, -1, FontSize::Small);
+        assert!(small < font.measure_text(text, -1, FontStyle::Regular));
+        let mut fb = crate::display::framebuffer::FrameBuffer::with_size(small, FONT_HEIGHT_SMALL);
+        assert_eq!(fb.draw_text_sized(text, 0, 0, (0, 255, 0), FontSize::Small, -1), small);
+        assert_eq!(
+            font.get_char_bitmap_sized('A', FontSize::Regular),
+            font.get_char_bitmap('A', FontStyle::Regular)
+        );
     }
 
     #[test]
     fn test_left_padding() {
         let font = get_font();
-        let padding = font.get_char_left_padding('A', false);
+        let padding = font.get_char_left_padding('A', FontStyle::Regular);
         // 'A' widest rows (e.g., 1548=0b11000001100) have leftmost pixel at col 2.
         // get_char_left_padding returns the minimum across all rows.
         assert_eq!(padding, 2, "A should have 2px left padding (from widest rows)");
@@ -458,19 +1134,19 @@ mod tests {
     fn render_text_to_pixels(
         text: &str,
         color: Rgb,
-        italic: bool,
+        style: FontStyle,
         scale: usize,
     ) -> (usize, usize, Vec<u8>) {
         let font = get_font();
         let spacing: i32 = -1;
-        let width = font.measure_text(text, spacing, italic).max(1);
+        let width = font.measure_text(text, spacing, style).max(1);
         let height = FONT_HEIGHT;
 
         let mut pixels = vec![0u8; width * height * 3 * scale * scale];
 
         let mut x_offset: i32 = 0;
         for ch in text.chars() {
-            if let Some(bitmap) = font.get_char_bitmap(ch, italic) {
+            if let Some(bitmap) = font.get_char_bitmap(ch, style) {
                 for (y, row) in bitmap.iter().enumerate() {
                     for (x, &lit) in row.iter().enumerate() {
                         if lit {
@@ -491,7 +1167,7 @@ mod tests {
                     }
                 }
             }
-            x_offset += font.get_char_width(ch, italic) as i32 + spacing;
+            x_offset += font.get_char_width(ch, style) as i32 + spacing;
         }
 
         (width * scale, height * scale, pixels)
@@ -538,17 +1214,17 @@ mod tests {
 
         // 1. Render "Times Sq-42 St" in green
         let (w, h, px) =
-            render_text_to_pixels("Times Sq-42 St", (0x00, 0xFF, 0x00), false, scale);
+            render_text_to_pixels("Times Sq-42 St", (0x00, 0xFF, 0x00), FontStyle::Regular, scale);
         write_ppm("/tmp/mta_font_station.ppm", w, h, &px);
 
         // 2. Render "2 min" in green
         let (w, h, px) =
-            render_text_to_pixels("2 min", (0x00, 0xFF, 0x00), false, scale);
+            render_text_to_pixels("2 min", (0x00, 0xFF, 0x00), FontStyle::Regular, scale);
         write_ppm("/tmp/mta_font_minutes.ppm", w, h, &px);
 
         // 3. Render italic "Downtown" in green
         let (w, h, px) =
-            render_text_to_pixels("Downtown", (0x00, 0xFF, 0x00), true, scale);
+            render_text_to_pixels("Downtown", (0x00, 0xFF, 0x00), FontStyle::Italic, scale);
         write_ppm("/tmp/mta_font_italic.ppm", w, h, &px);
 
         // 4. Render a composite with route icons for 1,2,3,A,N
@@ -1,14 +1,16 @@
 use super::colors::Rgb;
-use super::fonts::{CharBitmap, RouteIcon};
+use super::fonts::{CharBitmap, FontSize, FontStyle, RouteIcon};
 
-/// Display dimensions.
+/// Default display dimensions (three chained 64x32 panels); the actual
+/// frame size comes from `panel` in the config (`PanelConfig::frame_size`).
 pub const DISPLAY_WIDTH: usize = 192;
 pub const DISPLAY_HEIGHT: usize = 32;
 
-/// A 192x32 RGB framebuffer for the LED matrix display.
+/// An RGB framebuffer for the LED matrix display, 192x32 by default.
 ///
 /// Stores pixels as a flat `Vec<u8>` in row-major order (R, G, B per pixel).
-/// Total size: 192 * 32 * 3 = 18,432 bytes.
+/// Total size at 192x32: 192 * 32 * 3 = 18,432 bytes.
+#[derive(Clone)]
 pub struct FrameBuffer {
     pixels: Vec<u8>,
     width: usize,
@@ -16,12 +18,12 @@ pub struct FrameBuffer {
 }
 
 impl FrameBuffer {
-    /// Create a new framebuffer filled with black.
+    /// Create a new default-size framebuffer filled with black.
     pub fn new() -> Self {
         Self::with_size(DISPLAY_WIDTH, DISPLAY_HEIGHT)
     }
 
-    /// Create a framebuffer with custom dimensions (for testing).
+    /// Create a framebuffer with custom dimensions.
     pub fn with_size(width: usize, height: usize) -> Self {
         FrameBuffer {
             pixels: vec![0u8; width * height * 3],
@@ -30,6 +32,21 @@ impl FrameBuffer {
         }
     }
 
+    /// Rebuild a default-size framebuffer from a raw pixel buffer (as
+    /// returned by `raw_pixels`). Returns None if the length doesn't match.
+    pub fn from_raw_pixels(pixels: Vec<u8>) -> Option<Self> {
+        Self::from_raw_parts(pixels, DISPLAY_WIDTH, DISPLAY_HEIGHT)
+    }
+
+    /// Rebuild a `width` x `height` framebuffer from raw pixels. Returns None
+    /// if the length doesn't match.
+    pub fn from_raw_parts(pixels: Vec<u8>, width: usize, height: usize) -> Option<Self> {
+        if pixels.len() != width * height * 3 {
+            return None;
+        }
+        Some(FrameBuffer { pixels, width, height })
+    }
+
     pub fn width(&self) -> usize {
         self.width
     }
@@ -94,50 +111,70 @@ impl FrameBuffer {
 
     /// Draw text string at (x, y) with the given color.
     ///
-    /// Uses the global MTA font. Returns the total width drawn in pixels.
+    /// Uses the global MTA font. Returns the width drawn in pixels, the same
+    /// as `MtaFont::measure_text`.
     pub fn draw_text(
         &mut self,
         text: &str,
         x: i32,
         y: i32,
         color: Rgb,
-        italic: bool,
+        style: FontStyle,
         spacing: i32,
     ) -> usize {
         let font = super::fonts::get_font();
-        let mut x_offset: i32 = 0;
-        let mut chars = text.chars().peekable();
-
-        while let Some(ch) = chars.next() {
-            if let Some(bitmap) = font.get_char_bitmap(ch, italic) {
-                self.blit_char(bitmap, x + x_offset, y, color);
+        font.layout_text(text, spacing, style, |ch, dx| {
+            if let Some(bitmap) = font.get_char_bitmap(ch, style) {
+                self.blit_char(bitmap, x + dx, y, color);
             }
+        })
+    }
 
-            let char_width = font.get_char_width(ch, italic) as i32;
-
-            if italic {
-                if let Some(&next_ch) = chars.peek() {
-                    // Per-character overlap for italic: tighten based on next char's left padding
-                    let next_padding = font.get_char_left_padding(next_ch, italic) as i32;
-                    let overlap = (next_padding - 2).max(0);
-                    x_offset += char_width - overlap + spacing;
-                } else {
-                    x_offset += char_width + spacing;
-                }
-            } else {
-                x_offset += char_width + spacing;
+    /// `draw_text` in the given font size (regular style).
+    pub fn draw_text_sized(
+        &mut self,
+        text: &str,
+        x: i32,
+        y: i32,
+        color: Rgb,
+        size: FontSize,
+        spacing: i32,
+    ) -> usize {
+        let font = super::fonts::get_font();
+        font.layout_text_sized(text, spacing, size, |ch, dx| {
+            if let Some(bitmap) = font.get_char_bitmap_sized(ch, size) {
+                self.blit_char(bitmap, x + dx, y, color);
             }
-        }
-
-        x_offset.max(0) as usize
+        })
     }
 
-    /// Get the raw pixel buffer for passing to the LED matrix driver.
-    /// Only used in the hardware feature build (via set_image FFI).
-    #[cfg_attr(not(feature = "hardware"), allow(dead_code))]
+    /// Get the raw pixel buffer (row-major RGB) for the LED matrix driver
+    /// (via set_image FFI) and image export.
     pub fn raw_pixels(&self) -> &[u8] {
         &self.pixels
     }
+
+    /// Mutable raw pixel buffer, for whole-frame post-processing.
+    pub fn raw_pixels_mut(&mut self) -> &mut [u8] {
+        &mut self.pixels
+    }
+
+    /// FNV-1a hash of the size and pixels: equal frames, equal hashes.
+    pub fn fingerprint(&self) -> u64 {
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        let size = [self.width as u16, self.height as u16];
+        for byte in size.iter().flat_map(|v| v.to_be_bytes()).chain(self.pixels.iter().copied()) {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+        hash
+    }
+}
+
+impl Default for FrameBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 #[cfg(test)]
@@ -178,7 +215,7 @@ mod tests {
     #[test]
     fn test_draw_text() {
         let mut fb = FrameBuffer::new();
-        let width = fb.draw_text("A", 0, 0, (0, 255, 0), false, -1);
+        let width = fb.draw_text("A", 0, 0, (0, 255, 0), FontStyle::Regular, -1);
         assert!(width > 0, "draw_text should return non-zero width");
         // Some pixels should be green
         let mut found_green = false;
@@ -219,4 +256,13 @@ mod tests {
         let fb = FrameBuffer::new();
         assert_eq!(fb.raw_pixels().len(), DISPLAY_WIDTH * DISPLAY_HEIGHT * 3);
     }
+
+    #[test]
+    fn test_from_raw_pixels_roundtrip() {
+        let mut fb = FrameBuffer::new();
+        fb.set_pixel(3, 4, (1, 2, 3));
+        let restored = FrameBuffer::from_raw_pixels(fb.raw_pixels().to_vec()).unwrap();
+        assert_eq!(restored.get_pixel(3, 4), (1, 2, 3));
+        assert!(FrameBuffer::from_raw_pixels(vec![0; 10]).is_none());
+    }
 }
@@ -0,0 +1,217 @@
+//! Glyph coverage: characters the sign font cannot draw.
+//!
+//! `draw_text` silently skips characters missing from the font, which can
+//! make an alert read as nonsense. Feed text is checked here as it arrives;
+//! offenders are tallied for the coverage report and logged at most once
+//! per string every `LOG_INTERVAL`.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use regex::Regex;
+use serde::Serialize;
+
+use super::fonts::{self, FontStyle, MtaFont};
+use super::renderer::ROUTE_TOKEN_PATTERN;
+
+/// Minimum time between log lines for the same offending string.
+const LOG_INTERVAL: Duration = Duration::from_secs(600);
+
+/// Cap on remembered strings; older entries are pruned past this.
+const MAX_TRACKED_STRINGS: usize = 256;
+
+/// Non-ASCII characters that show up in MTA feed text (curly quotes,
+/// dashes, ellipsis, accented station names, bullets).
+const COMMON_EXTRAS: &[char] = &[
+    '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2013}', '\u{2014}', '\u{2026}',
+    '\u{2022}', '\u{00B0}', '\u{00E9}', '\u{00F1}',
+];
+
+/// A missing character seen in live text.
+#[derive(Debug, Clone, Serialize)]
+pub struct MissingGlyph {
+    /// The character itself, e.g. "’".
+    pub char: String,
+    /// Unicode code point, e.g. "U+2019".
+    pub code: String,
+    /// Times it appeared in checked text.
+    pub count: u64,
+    /// Most recent text containing it.
+    pub example: String,
+}
+
+/// Font coverage summary for `/api/font/coverage`.
+#[derive(Debug, Serialize)]
+pub struct CoverageReport {
+    pub glyph_count: usize,
+    /// Printable ASCII characters without a glyph.
+    pub missing_ascii: Vec<String>,
+    /// Common feed characters (curly quotes, dashes, ...) without a glyph.
+    pub missing_common: Vec<String>,
+    /// Missing characters actually seen in alerts and destinations.
+    pub seen_missing: Vec<MissingGlyph>,
+}
+
+/// Tally of missing characters plus the log rate limiter.
+#[derive(Default)]
+struct GlyphTracker {
+    seen: BTreeMap<char, MissingGlyph>,
+    last_logged: HashMap<String, Instant>,
+}
+
+impl GlyphTracker {
+    /// Record `missing` characters found in `text`. Returns true when the
+    /// string hasn't been logged within `LOG_INTERVAL`.
+    fn record(&mut self, text: &str, missing: &[char], now: Instant) -> bool {
+        for &ch in missing {
+            let entry = self.seen.entry(ch).or_insert_with(|| MissingGlyph {
+                char: ch.to_string(),
+                code: code_point(ch),
+                count: 0,
+                example: String::new(),
+            });
+            entry.count += 1;
+            entry.example = text.to_string();
+        }
+
+        if let Some(&last) = self.last_logged.get(text) {
+            if now.duration_since(last) < LOG_INTERVAL {
+                return false;
+            }
+        }
+        if self.last_logged.len() >= MAX_TRACKED_STRINGS {
+            self.last_logged
+                .retain(|_, &mut last| now.duration_since(last) < LOG_INTERVAL);
+        }
+        if self.last_logged.len() >= MAX_TRACKED_STRINGS {
+            let oldest = self
+                .last_logged
+                .iter()
+                .min_by_key(|(_, &last)| last)
+                .map(|(text, _)| text.clone());
+            if let Some(oldest) = oldest {
+                self.last_logged.remove(&oldest);
+            }
+        }
+        self.last_logged.insert(text.to_string(), now);
+        true
+    }
+}
+
+fn tracker() -> &'static Mutex<GlyphTracker> {
+    static TRACKER: OnceLock<Mutex<GlyphTracker>> = OnceLock::new();
+    TRACKER.get_or_init(Default::default)
+}
+
+fn code_point(ch: char) -> String {
+    format!("U+{:04X}", ch as u32)
+}
+
+/// Check feed text against the current font; tally and (rate-limited) log
+/// any characters it can't draw. `source` names the text in the log line.
+pub fn check_text(source: &str, text: &str) {
+    let missing = fonts::get_font().missing_chars(text);
+    if missing.is_empty() {
+        return;
+    }
+    let log = tracker()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .record(text, &missing, Instant::now());
+    if log {
+        let chars: Vec<String> = missing
+            .iter()
+            .map(|&ch| format!("'{}' ({})", ch, code_point(ch)))
+            .collect();
+        tracing::warn!(
+            "[FONT] Unrenderable characters in {}: {} in {:?}",
+            source,
+            chars.join(", "),
+            text
+        );
+    }
+}
+
+/// `check_text` for alert text, ignoring `[route]` icon tokens (drawn as
+/// bitmaps, not glyphs).
+pub fn check_alert_text(text: &str) {
+    static TOKENS: OnceLock<Regex> = OnceLock::new();
+    let tokens = TOKENS.get_or_init(|| Regex::new(ROUTE_TOKEN_PATTERN).unwrap());
+    check_text("alert", &tokens.replace_all(text, ""));
+}
+
+/// Build the coverage report for `font` plus everything seen so far.
+pub fn coverage_report(font: &MtaFont) -> CoverageReport {
+    let missing = |chars: &mut dyn Iterator<Item = char>| -> Vec<String> {
+        chars
+            .filter(|&ch| font.get_char_bitmap(ch, FontStyle::Regular).is_none())
+            .map(String::from)
+            .collect()
+    };
+    let seen = tracker().lock().unwrap_or_else(|e| e.into_inner());
+    CoverageReport {
+        glyph_count: font.glyph_count(),
+        missing_ascii: missing(&mut (' '..='~')),
+        missing_common: missing(&mut COMMON_EXTRAS.iter().copied()),
+        seen_missing: seen.seen.values().cloned().collect(),
+    }
+}
+
+/// Log a one-line coverage summary (called at startup).
+pub fn log_coverage() {
+    let report = coverage_report(&fonts::get_font());
+    let list = |chars: &[String]| {
+        if chars.is_empty() {
+            "none".to_string()
+        } else {
+            chars.concat()
+        }
+    };
+    tracing::info!(
+        "[FONT] {} glyphs; missing ASCII: {}; missing common: {}",
+        report.glyph_count,
+        list(&report.missing_ascii),
+        list(&report.missing_common)
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_rate_limits_per_string() {
+        let mut tracker = GlyphTracker::default();
+        let t0 = Instant::now();
+        assert!(tracker.record("Rock\u{2019}n", &['\u{2019}'], t0));
+        assert!(!tracker.record("Rock\u{2019}n", &['\u{2019}'], t0 + Duration::from_secs(60)));
+        assert!(tracker.record("Caf\u{00E9}", &['\u{00E9}'], t0), "other strings still log");
+        assert!(tracker.record("Rock\u{2019}n", &['\u{2019}'], t0 + LOG_INTERVAL));
+
+        let quote = &tracker.seen[&'\u{2019}'];
+        assert_eq!(quote.count, 3);
+        assert_eq!(quote.code, "U+2019");
+    }
+
+    #[test]
+    fn test_tracked_strings_bounded() {
+        let mut tracker = GlyphTracker::default();
+        let t0 = Instant::now();
+        for i in 0..MAX_TRACKED_STRINGS * 2 {
+            tracker.record(&format!("text {}\u{2014}", i), &['\u{2014}'], t0);
+        }
+        assert_eq!(tracker.last_logged.len(), MAX_TRACKED_STRINGS);
+    }
+
+    #[test]
+    fn test_coverage_report() {
+        let font = fonts::get_font();
+        let report = coverage_report(&font);
+        assert!(report.glyph_count > 70);
+        assert!(report.missing_ascii.contains(&"(".to_string()));
+        assert!(!report.missing_ascii.contains(&"A".to_string()));
+        assert!(report.missing_common.contains(&"\u{2019}".to_string()));
+        assert_eq!(font.missing_chars("A\u{2019}B\u{2019}\n"), vec!['\u{2019}']);
+    }
+}
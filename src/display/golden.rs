@@ -0,0 +1,199 @@
+//! Golden-frame tests: canonical snapshots rendered and compared pixel for
+//! pixel against PNGs checked in under `tests/golden/`.
+//!
+//! On a mismatch the actual frame and a diff (differing pixels magenta,
+//! matching ones dimmed) are written to `target/golden-diff/`. After an
+//! intended layout change, regenerate the goldens with
+//! `UPDATE_GOLDEN=1 cargo test golden` and review the new PNGs.
+
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use crate::models::{Alert, Direction, DisplaySnapshot, Train};
+
+use super::export;
+use super::framebuffer::{FrameBuffer, DISPLAY_WIDTH};
+use super::renderer::{Banner, Renderer};
+
+/// Upscale of the diff image, so single pixels are visible.
+const DIFF_SCALE: usize = 4;
+
+fn golden_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/golden")
+}
+
+fn diff_dir() -> PathBuf {
+    std::env::var_os("CARGO_TARGET_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("target"))
+        .join("golden-diff")
+}
+
+/// Decode an RGB PNG written by `export::framebuffer_to_png` at scale 1.
+fn decode_png(bytes: &[u8]) -> Result<FrameBuffer, String> {
+    let decoder = png::Decoder::new(std::io::Cursor::new(bytes));
+    let mut reader = decoder.read_info().map_err(|e| e.to_string())?;
+    let mut buf = vec![0; reader.output_buffer_size().ok_or("PNG too large")?];
+    let info = reader.next_frame(&mut buf).map_err(|e| e.to_string())?;
+    if info.color_type != png::ColorType::Rgb || info.bit_depth != png::BitDepth::Eight {
+        return Err(format!("expected 8-bit RGB, got {:?} {:?}", info.color_type, info.bit_depth));
+    }
+    buf.truncate(info.buffer_size());
+    let mut fb = FrameBuffer::with_size(info.width as usize, info.height as usize);
+    for (i, px) in buf.chunks_exact(3).enumerate() {
+        let (x, y) = (i % fb.width(), i / fb.width());
+        fb.set_pixel(x as i32, y as i32, (px[0], px[1], px[2]));
+    }
+    Ok(fb)
+}
+
+/// Differing pixels magenta, the rest of `actual` at quarter brightness.
+fn diff_image(expected: &FrameBuffer, actual: &FrameBuffer) -> FrameBuffer {
+    let mut diff = FrameBuffer::with_size(actual.width(), actual.height());
+    for y in 0..actual.height() {
+        for x in 0..actual.width() {
+            let (r, g, b) = actual.get_pixel(x, y);
+            let same = x < expected.width() && y < expected.height() && expected.get_pixel(x, y) == (r, g, b);
+            let color = if same { (r / 4, g / 4, b / 4) } else { (255, 0, 255) };
+            diff.set_pixel(x as i32, y as i32, color);
+        }
+    }
+    diff
+}
+
+/// Compare `actual` with `tests/golden/<name>.png` (or rewrite it with
+/// `UPDATE_GOLDEN` set); returns a failure description.
+fn check(name: &str, actual: &FrameBuffer) -> Option<String> {
+    let path = golden_dir().join(format!("{}.png", name));
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::create_dir_all(golden_dir()).unwrap();
+        std::fs::write(&path, export::framebuffer_to_png(actual, 1).unwrap()).unwrap();
+        return None;
+    }
+
+    let expected = match std::fs::read(&path).map_err(|e| e.to_string()).and_then(|b| decode_png(&b)) {
+        Ok(fb) => fb,
+        Err(e) => return Some(format!("{}: cannot read {} ({})", name, path.display(), e)),
+    };
+    if expected.width() == actual.width()
+        && expected.height() == actual.height()
+        && expected.raw_pixels() == actual.raw_pixels()
+    {
+        return None;
+    }
+
+    let differing = (0..actual.height())
+        .flat_map(|y| (0..actual.width()).map(move |x| (x, y)))
+        .filter(|&(x, y)| x >= expected.width() || y >= expected.height() || expected.get_pixel(x, y) != actual.get_pixel(x, y))
+        .count();
+    let dir = diff_dir();
+    std::fs::create_dir_all(&dir).unwrap();
+    let actual_path = dir.join(format!("{}.actual.png", name));
+    let diff_path = dir.join(format!("{}.diff.png", name));
+    std::fs::write(&actual_path, export::framebuffer_to_png(actual, 1).unwrap()).unwrap();
+    std::fs::write(&diff_path, export::framebuffer_to_png(&diff_image(&expected, actual), DIFF_SCALE).unwrap()).unwrap();
+    Some(format!(
+        "{}: {} pixels differ (actual: {}, diff: {})",
+        name,
+        differing,
+        actual_path.display(),
+        diff_path.display()
+    ))
+}
+
+fn train(route: &str, dest: &str, minutes: i32, is_express: bool) -> Train {
+    Train {
+        route: route.into(),
+        destination: dest.into(),
+        minutes,
+        is_express,
+        arrival_timestamp: 0.0,
+        direction: Direction::Uptown,
+        stop_id: "127N".into(),
+        delay_secs: None,
+        trip_id: String::new(),
+    }
+}
+
+fn snapshot(trains: Vec<Train>) -> DisplaySnapshot {
+    DisplaySnapshot {
+        trains,
+        alerts: Vec::new(),
+        fetched_at: 0.0,
+    }
+}
+
+fn alert(text: &str) -> Alert {
+    Alert {
+        text: text.into(),
+        affected_routes: HashSet::new(),
+        priority: 10,
+        alert_id: "golden".into(),
+        url: None,
+        is_ticker: false,
+    }
+}
+
+/// The canonical frames, by golden file name.
+fn cases() -> Vec<(&'static str, FrameBuffer)> {
+    let arrivals = snapshot(vec![
+        train("1", "Van Cortlandt Park", 2, false),
+        train("A", "Far Rockaway", 5, false),
+        train("7", "Flushing", 8, false),
+    ]);
+    let arriving = snapshot(vec![
+        train("Q", "96 St", 0, false),
+        train("N", "Astoria-Ditmars Blvd", 4, false),
+    ]);
+    let long_names = snapshot(vec![
+        train("F", "Jamaica-179 St via Queens Blvd Express", 12, false),
+        train("6", "Pelham Bay Park-Westchester Sq", 143, true),
+    ]);
+
+    let mut r = Renderer::new();
+    let mut frames = vec![
+        ("arrivals", r.render_frame(&arrivals, 0, false, 0.0, false, None)),
+        ("arrivals_cycled", r.render_frame(&arrivals, 1, false, 0.0, false, None)),
+        ("arriving_flash_on", r.render_frame(&arriving, 0, true, 0.0, false, None)),
+        ("arriving_flash_off", r.render_frame(&arriving, 0, false, 0.0, false, None)),
+        ("truncated_express", r.render_frame(&long_names, 0, false, 0.0, false, None)),
+        ("no_trains", r.render_frame(&snapshot(Vec::new()), 0, false, 0.0, false, None)),
+    ];
+
+    let icons = alert("[A] [C] [E] trains are delayed while we fix a signal problem");
+    let width = DISPLAY_WIDTH as f32;
+    frames.push(("alert_icons", r.render_frame(&arrivals, 0, false, width, true, Some(&icons))));
+    frames.push(("alert_subpixel", r.render_frame(&arrivals, 0, false, width + 40.5, true, Some(&icons))));
+    let plain = alert("No 1 trains between 96 St and 137 St");
+    frames.push(("alert_route_names", r.render_frame(&arrivals, 0, false, width, true, Some(&plain))));
+
+    r.set_banner(Some(Banner::LeaveNow { route: "6".into(), is_express: false }));
+    frames.push(("banner_leave_now", r.render_frame(&arrivals, 0, false, 0.0, false, None)));
+    frames
+}
+
+#[test]
+fn test_golden_frames() {
+    let failures: Vec<String> = cases()
+        .iter()
+        .filter_map(|(name, frame)| check(name, frame))
+        .collect();
+    assert!(
+        failures.is_empty(),
+        "frames differ from tests/golden (run with UPDATE_GOLDEN=1 if intended):\n{}",
+        failures.join("\n")
+    );
+}
+
+#[test]
+fn test_diff_marks_changed_pixels() {
+    let expected = FrameBuffer::new();
+    let mut actual = FrameBuffer::new();
+    actual.set_pixel(3, 4, (0, 255, 0));
+    let diff = diff_image(&expected, &actual);
+    assert_eq!(diff.get_pixel(3, 4), (255, 0, 255));
+    assert_eq!(diff.get_pixel(0, 0), (0, 0, 0));
+
+    let png = export::framebuffer_to_png(&actual, 1).unwrap();
+    assert_eq!(decode_png(&png).unwrap().raw_pixels(), actual.raw_pixels());
+}
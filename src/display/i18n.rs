@@ -0,0 +1,200 @@
+//! On-display strings, per locale, with per-string overrides from config.
+//!
+//! The sign font only covers printable ASCII, so built-in translations are
+//! written without accents (e.g. "Mie" rather than "Mié").
+
+use std::collections::{BTreeMap, HashMap};
+
+/// Locales with built-in string tables.
+pub const SUPPORTED_LOCALES: &[&str] = &["en", "es", "fr", "de"];
+
+/// Keys every locale defines (and the only keys overrides may set).
+///
+/// - `min`: countdown suffix ("5min")
+/// - `due`: replaces the countdown for an arriving train; empty = "0min"
+/// - `now`: arriving-train text for `display.arriving_text: "now"`
+/// - `sec`: seconds suffix for `display.arriving_text: "seconds"` ("45s")
+/// - `delays`: short label for delay alerts
+/// - `clock_format`: chrono format for arrival clock times
+/// - `date_format`: chrono format for the idle clock's date (the sign font
+///   draws `/` as an arrow)
+/// - `leave_now`: journey prompt; `{route}` becomes the route icon
+/// - `use_instead`: alternate to a suspended route; `{route}` as above
+/// - `setup_wifi`: Wi-Fi setup prompt; `{ssid}` is the access point name
+/// - `check_config`: shown when the configured stops never match a train
+/// - `mon`..`sun`: abbreviated weekday names
+/// - `departs`, `to`, `track`: departure board column headings
+/// - `scan_details`: beside an alert's QR code
+/// - `good_service`, `no_service`, `service_change`: alerts page labels
+pub const KEYS: &[&str] = &[
+    "min",
+    "due",
+    "now",
+    "sec",
+    "delays",
+    "clock_format",
+    "date_format",
+    "leave_now",
+    "use_instead",
+    "setup_wifi",
+    "check_config",
+    "mon",
+    "tue",
+    "wed",
+    "thu",
+    "fri",
+    "sat",
+    "sun",
+    "departs",
+    "to",
+    "track",
+    "scan_details",
+    "good_service",
+    "no_service",
+    "service_change",
+];
+
+const EN: [&str; 25] = [
+    "min", "", "Now", "s", "Delays", "%-I:%M", "%b %-d",
+    "Leave now for the {route}", "Use the {route} instead",
+    "Join Wi-Fi {ssid}", "Check station/routes config",
+    "Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun",
+    "Departs", "To", "Track", "Scan for details",
+    "Good service", "No service", "Service change",
+];
+const ES: [&str; 25] = [
+    "min", "Llega", "Ahora", "s", "Demoras", "%H:%M", "%-d-%-m",
+    "Sal ya para el {route}", "Toma el {route}",
+    "Conectate a {ssid}", "Revisa estacion/lineas",
+    "Lun", "Mar", "Mie", "Jue", "Vie", "Sab", "Dom",
+    "Sale", "Destino", "Via", "Escanea para mas",
+    "Servicio normal", "Sin servicio", "Cambio de servicio",
+];
+const FR: [&str; 25] = [
+    "min", "Arrive", "Imminent", "s", "Retards", "%H:%M", "%-d-%-m",
+    "Partez pour le {route}", "Prenez le {route}",
+    "Rejoignez {ssid}", "Verifiez station/lignes",
+    "Lun", "Mar", "Mer", "Jeu", "Ven", "Sam", "Dim",
+    "Depart", "Vers", "Voie", "Scannez pour plus",
+    "Trafic normal", "Pas de service", "Service modifie",
+];
+const DE: [&str; 25] = [
+    "Min", "Jetzt", "Sofort", "s", "Verspaetung", "%H:%M", "%-d.%-m.",
+    "Jetzt los zur {route}", "Nimm die {route}",
+    "WLAN {ssid} waehlen", "Station/Linien pruefen",
+    "Mo", "Di", "Mi", "Do", "Fr", "Sa", "So",
+    "Ab", "Nach", "Gleis", "Details: scannen",
+    "Normaler Betrieb", "Kein Betrieb", "Betriebsaenderung",
+];
+
+/// Resolved string table for one locale plus user overrides.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Strings {
+    table: HashMap<&'static str, String>,
+}
+
+impl Strings {
+    /// Build the table for `locale`, applying `overrides` on top.
+    ///
+    /// Errors name the unsupported locale or unknown override key.
+    pub fn new(locale: &str, overrides: &BTreeMap<String, String>) -> Result<Self, String> {
+        let base = match locale.to_lowercase().as_str() {
+            "en" => EN,
+            "es" => ES,
+            "fr" => FR,
+            "de" => DE,
+            other => {
+                return Err(format!(
+                    "unsupported locale '{}' (supported: {})",
+                    other,
+                    SUPPORTED_LOCALES.join(", ")
+                ))
+            }
+        };
+
+        let mut table: HashMap<&'static str, String> = KEYS
+            .iter()
+            .zip(base)
+            .map(|(&k, v)| (k, v.to_string()))
+            .collect();
+
+        for (key, value) in overrides {
+            match KEYS.iter().find(|&&k| k == key) {
+                Some(&k) => {
+                    table.insert(k, value.clone());
+                }
+                None => {
+                    return Err(format!(
+                        "unknown display string '{}' (known: {})",
+                        key,
+                        KEYS.join(", ")
+                    ))
+                }
+            }
+        }
+
+        // A bad format string would make chrono panic at render time
+        for key in ["clock_format", "date_format"] {
+            let format = &table[key];
+            if chrono::format::StrftimeItems::new(format)
+                .any(|item| matches!(item, chrono::format::Item::Error))
+            {
+                return Err(format!("invalid {} '{}'", key, format));
+            }
+        }
+
+        Ok(Strings { table })
+    }
+
+    /// Look up a string by key. Unknown keys return "".
+    pub fn get(&self, key: &str) -> &str {
+        self.table.get(key).map(String::as_str).unwrap_or("")
+    }
+}
+
+impl Default for Strings {
+    fn default() -> Self {
+        Strings::new("en", &BTreeMap::new()).expect("built-in en table")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_locales_and_overrides() {
+        let en = Strings::default();
+        assert_eq!(en.get("min"), "min");
+        assert_eq!(en.get("due"), "");
+        assert_eq!(en.get("now"), "Now");
+        assert_eq!(en.get("sun"), "Sun");
+
+        let es = Strings::new("ES", &BTreeMap::new()).unwrap();
+        assert_eq!(es.get("wed"), "Mie");
+        assert_eq!(es.get("clock_format"), "%H:%M");
+
+        let overrides = BTreeMap::from([("due".to_string(), "Now".to_string())]);
+        let custom = Strings::new("en", &overrides).unwrap();
+        assert_eq!(custom.get("due"), "Now");
+        assert_eq!(custom.get("min"), "min");
+    }
+
+    #[test]
+    fn test_rejects_unknown_locale_and_key() {
+        assert!(Strings::new("xx", &BTreeMap::new()).unwrap_err().contains("xx"));
+        let overrides = BTreeMap::from([("minutes".to_string(), "m".to_string())]);
+        assert!(Strings::new("en", &overrides).unwrap_err().contains("minutes"));
+        let overrides = BTreeMap::from([("clock_format".to_string(), "%Q".to_string())]);
+        assert!(Strings::new("en", &overrides).unwrap_err().contains("clock_format"));
+        let overrides = BTreeMap::from([("date_format".to_string(), "%Q".to_string())]);
+        assert!(Strings::new("en", &overrides).unwrap_err().contains("date_format"));
+    }
+
+    #[test]
+    fn test_builtin_tables_are_ascii() {
+        for table in [EN, ES, FR, DE] {
+            assert!(table.iter().all(|s| s.is_ascii()));
+        }
+    }
+}
@@ -0,0 +1,83 @@
+//! The renderer's layer stack: what `Renderer::render_frame` draws, in
+//! which order, and whether at all.
+//!
+//! A frame is composed bottom to top from the visible layers. New on-screen
+//! elements get a layer (or join one) instead of another special case in
+//! `render_frame`.
+
+/// One layer of a frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Layer {
+    /// A fixed image under everything (see `Renderer::set_background`).
+    Background,
+    /// Train rows, per the layout. The alert/banner row is left free while
+    /// `Notice` is visible and has something to show.
+    Trains,
+    /// The scrolling alert or a banner.
+    Notice,
+    /// Small status glyphs (do-not-disturb).
+    Status,
+}
+
+/// Layers bottom to top, each shown or hidden.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayerStack {
+    layers: Vec<(Layer, bool)>,
+}
+
+impl Default for LayerStack {
+    fn default() -> Self {
+        LayerStack {
+            layers: [Layer::Background, Layer::Trains, Layer::Notice, Layer::Status]
+                .into_iter()
+                .map(|layer| (layer, true))
+                .collect(),
+        }
+    }
+}
+
+impl LayerStack {
+    pub fn visible(&self, layer: Layer) -> bool {
+        self.layers.iter().any(|&(l, shown)| l == layer && shown)
+    }
+
+    pub fn set_visible(&mut self, layer: Layer, visible: bool) {
+        for (l, shown) in &mut self.layers {
+            if *l == layer {
+                *shown = visible;
+            }
+        }
+    }
+
+    /// Move `layer` to position `z` (0 = bottom; past the top = top).
+    pub fn set_z(&mut self, layer: Layer, z: usize) {
+        if let Some(i) = self.layers.iter().position(|&(l, _)| l == layer) {
+            let entry = self.layers.remove(i);
+            self.layers.insert(z.min(self.layers.len()), entry);
+        }
+    }
+
+    /// Visible layers, bottom to top.
+    pub fn drawn(&self) -> Vec<Layer> {
+        self.layers.iter().filter(|&&(_, shown)| shown).map(|&(l, _)| l).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layer_stack_order_and_visibility() {
+        let mut stack = LayerStack::default();
+        assert_eq!(stack.drawn(), vec![Layer::Background, Layer::Trains, Layer::Notice, Layer::Status]);
+
+        stack.set_z(Layer::Trains, 99);
+        stack.set_visible(Layer::Background, false);
+        assert_eq!(stack.drawn(), vec![Layer::Notice, Layer::Status, Layer::Trains]);
+        assert!(!stack.visible(Layer::Background));
+
+        stack.set_z(Layer::Trains, 0);
+        assert_eq!(stack.drawn(), vec![Layer::Trains, Layer::Notice, Layer::Status]);
+    }
+}
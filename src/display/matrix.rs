@@ -19,18 +19,114 @@
 //! shared across threads. The `Send` bound is required because `Box<dyn
 //! DisplayTarget>` is moved into that thread, but no concurrent access occurs.
 
+use std::borrow::Cow;
+use std::time::{Duration, Instant};
+
 use super::framebuffer::FrameBuffer;
+use super::network::NetworkDisplay;
+use super::terminal::TerminalDisplay;
+use crate::config::{DisplayBackend, PanelConfig};
 
 /// Abstraction over the LED matrix hardware.
 ///
-/// The `mock` feature (default) uses `MockDisplay` (no-op).
-/// The `hardware` feature uses `LedMatrixDisplay` (real Pi hardware).
+/// `panel.backend` picks the implementation: `LedMatrixDisplay` (real Pi
+/// hardware, `hardware` feature), `WindowDisplay` (a desktop window,
+/// `simulator` feature), `TerminalDisplay`, `NetworkDisplay`, or
+/// `MockDisplay` (no-op).
 pub trait DisplayTarget: Send {
     /// Push a rendered frame to the display.
     fn swap(&mut self, frame: &FrameBuffer);
 
     /// Update display brightness (0-100).
     fn set_brightness(&mut self, brightness: u8);
+
+    /// Move/resize the area of the physical canvas the frame is drawn into.
+    fn set_viewport(&mut self, viewport: Viewport);
+}
+
+/// Placement of the framebuffer on the physical LED canvas.
+///
+/// The top-left `width` x `height` of the frame is drawn at
+/// (`offset_x`, `offset_y`); everything else on the canvas stays dark.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Viewport {
+    pub offset_x: u32,
+    pub offset_y: u32,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Viewport {
+    pub fn from_config(panel: &PanelConfig) -> Self {
+        let (width, height) = panel.frame_size();
+        Viewport {
+            offset_x: panel.offset_x,
+            offset_y: panel.offset_y,
+            width,
+            height,
+        }
+    }
+
+    /// Pixels of the active area, row-major RGB. Borrows when the active
+    /// area is the whole frame (the common case), copies otherwise.
+    pub fn active_pixels<'a>(&self, frame: &'a FrameBuffer) -> Cow<'a, [u8]> {
+        let width = self.width.min(frame.width());
+        let height = self.height.min(frame.height());
+        let pixels = frame.raw_pixels();
+        if width == frame.width() {
+            return Cow::Borrowed(&pixels[..width * height * 3]);
+        }
+        let row_len = frame.width() * 3;
+        Cow::Owned(
+            pixels
+                .chunks_exact(row_len)
+                .take(height)
+                .flat_map(|row| &row[..width * 3])
+                .copied()
+                .collect(),
+        )
+    }
+}
+
+/// Skips swapping in the frame the renderer drew last time (see
+/// `Renderer::frame_generation`): the panel keeps showing it, and the post
+/// processing, copy and vsync wait are saved. A frame still goes out every
+/// `REFRESH`, so a network receiver that (re)starts catches up.
+#[derive(Default)]
+pub struct SwapDedup {
+    /// Generation of the last frame swapped, and when.
+    last: Option<(u64, Instant)>,
+    skipped: u64,
+}
+
+impl SwapDedup {
+    const REFRESH: Duration = Duration::from_secs(1);
+
+    /// Whether the frame needs swapping in at `now`; `generation` is `None`
+    /// for frames the renderer didn't draw (always swapped).
+    pub fn changed(&mut self, generation: Option<u64>, now: Instant) -> bool {
+        match (generation, self.last) {
+            (Some(id), Some((last, at))) if id == last && now.saturating_duration_since(at) < Self::REFRESH => {
+                self.skipped += 1;
+                false
+            }
+            _ => {
+                self.last = generation.map(|id| (id, now));
+                true
+            }
+        }
+    }
+
+    /// Swap the next frame whatever it is (the backend, viewport or
+    /// brightness changed).
+    pub fn invalidate(&mut self) {
+        self.last = None;
+    }
+
+    /// Frames skipped since the last call.
+    pub fn take_skipped(&mut self) -> u64 {
+        std::mem::take(&mut self.skipped)
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -38,7 +134,8 @@ pub trait DisplayTarget: Send {
 // ---------------------------------------------------------------------------
 #[cfg(feature = "hardware")]
 mod hw {
-    use super::{DisplayTarget, FrameBuffer};
+    use super::{DisplayTarget, FrameBuffer, Viewport};
+    use crate::config::PanelConfig;
     use rpi_led_matrix::{LedCanvas, LedMatrix, LedMatrixOptions, LedRuntimeOptions};
 
     // Direct FFI to hzeller's C API.
@@ -65,17 +162,21 @@ mod hw {
         matrix: LedMatrix,
         canvas: Option<LedCanvas>,
         matrix_ptr: *mut std::ffi::c_void,
+        viewport: Viewport,
+        /// Canvases still holding pixels from a previous viewport (0-2).
+        stale_canvases: u8,
     }
 
     impl LedMatrixDisplay {
-        /// Create and configure the LED matrix with our panel layout:
-        /// 3 chained 64x32 panels = 192x32.
-        pub fn new(brightness: u8) -> Self {
+        /// Create and configure the LED matrix with the panel geometry from
+        /// the config (default: three chained 64x32 panels, 192x32).
+        pub fn new(brightness: u8, panel: &PanelConfig, viewport: Viewport) -> Result<Self, String> {
             let mut options = LedMatrixOptions::new();
-            let _ = options.set_rows(32);
-            let _ = options.set_cols(64);
-            let _ = options.set_chain_length(3);
-            let _ = options.set_hardware_mapping("regular");
+            let _ = options.set_rows(panel.rows);
+            let _ = options.set_cols(panel.cols);
+            let _ = options.set_chain_length(panel.chain_length);
+            let _ = options.set_parallel(panel.parallel);
+            let _ = options.set_hardware_mapping(&panel.hardware_mapping);
             let _ = options.set_pwm_bits(11);
             let _ = options.set_pwm_lsb_nanoseconds(130);
             let _ = options.set_pwm_dither_bits(0);
@@ -88,13 +189,8 @@ mod hw {
             let _ = rt_options.set_gpio_slowdown(3);
             let _ = rt_options.set_drop_privileges(false);
 
-            let matrix = match LedMatrix::new(Some(options), Some(rt_options)) {
-                Ok(m) => m,
-                Err(e) => {
-                    tracing::error!("Failed to initialize LED matrix: {}", e);
-                    std::process::exit(1);
-                }
-            };
+            let matrix = LedMatrix::new(Some(options), Some(rt_options))
+                .map_err(|e| format!("Failed to initialize LED matrix: {}", e))?;
 
             let canvas = matrix.offscreen_canvas();
 
@@ -105,22 +201,27 @@ mod hw {
                 *(&matrix as *const LedMatrix as *const *mut std::ffi::c_void)
             };
             let readback = unsafe { led_matrix_get_brightness(matrix_ptr) };
-            assert_eq!(
-                readback, brightness,
-                "LedMatrix pointer extraction failed — brightness mismatch ({} != {})",
-                readback, brightness
-            );
+            if readback != brightness {
+                return Err(format!(
+                    "LedMatrix pointer extraction failed — brightness mismatch ({} != {})",
+                    readback, brightness
+                ));
+            }
 
             tracing::info!(
-                "LED matrix initialized (192x32, brightness={}%, pulsing=hw, pwm={}/{}ns, dither=0, refresh_cap=120Hz)",
-                brightness, 11, 130
+                "LED matrix initialized ({}x{}, mapping={}, brightness={}%, pulsing=hw, pwm={}/{}ns, dither=0, refresh_cap=120Hz, viewport={}x{}@{},{})",
+                panel.cols * panel.chain_length, panel.rows * panel.parallel, panel.hardware_mapping,
+                brightness, 11, 130,
+                viewport.width, viewport.height, viewport.offset_x, viewport.offset_y
             );
 
-            LedMatrixDisplay {
+            Ok(LedMatrixDisplay {
                 matrix,
                 canvas: Some(canvas),
                 matrix_ptr,
-            }
+                viewport,
+                stale_canvases: 0,
+            })
         }
     }
 
@@ -133,10 +234,18 @@ mod hw {
 
     impl DisplayTarget for LedMatrixDisplay {
         fn swap(&mut self, frame: &FrameBuffer) {
-            if let Some(canvas) = self.canvas.take() {
-                let pixels = frame.raw_pixels();
-                let width = frame.width();
-                let height = frame.height();
+            if let Some(mut canvas) = self.canvas.take() {
+                // After a viewport change, both double-buffered canvases still
+                // hold the old placement; blank each once before drawing
+                if self.stale_canvases > 0 {
+                    canvas.clear();
+                    self.stale_canvases -= 1;
+                }
+
+                let viewport = self.viewport;
+                let pixels = viewport.active_pixels(frame);
+                let width = viewport.width.min(frame.width());
+                let height = viewport.height.min(frame.height());
 
                 // Extract the raw C pointer from LedCanvas.
                 // Safety: LedCanvas is a single-field newtype wrapping
@@ -152,15 +261,15 @@ mod hw {
                     *(&canvas as *const LedCanvas as *const *mut std::ffi::c_void)
                 };
 
-                // Bulk copy the entire framebuffer in one FFI call.
+                // Bulk copy the active area in one FFI call.
                 // Safety: canvas_ptr is valid (just extracted from live LedCanvas),
-                // pixels buffer is valid for its length, dimensions match our
-                // framebuffer layout (192x32 RGB, row-major).
+                // pixels buffer is valid for its length, dimensions match the
+                // active-area layout (width x height RGB, row-major).
                 unsafe {
                     set_image(
                         canvas_ptr,
-                        0,
-                        0,
+                        viewport.offset_x as std::ffi::c_int,
+                        viewport.offset_y as std::ffi::c_int,
                         pixels.as_ptr(),
                         pixels.len(),
                         width as std::ffi::c_int,
@@ -177,9 +286,166 @@ mod hw {
         fn set_brightness(&mut self, brightness: u8) {
             unsafe { led_matrix_set_brightness(self.matrix_ptr, brightness); }
         }
+
+        fn set_viewport(&mut self, viewport: Viewport) {
+            if viewport != self.viewport {
+                self.viewport = viewport;
+                self.stale_canvases = 2;
+            }
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Desktop window implementation (behind `simulator` feature flag)
+// ---------------------------------------------------------------------------
+#[cfg(feature = "simulator")]
+mod sim {
+    use std::sync::mpsc::{self, Receiver, RecvTimeoutError, SyncSender};
+    use std::time::Duration;
+
+    use minifb::{Window, WindowOptions};
+
+    use super::{DisplayTarget, FrameBuffer, Viewport};
+
+    /// Window pixels per LED, including the dark gap between LEDs.
+    const SCALE: usize = 6;
+    /// Color of an unlit LED, so the panel's grid stays visible.
+    const DARK_LED: u32 = 0x00_14_14_14;
+
+    /// Simulated LED canvas in a desktop window: the frame is drawn the way
+    /// the panel would show it (viewport, brightness, one dot per LED).
+    ///
+    /// The window lives on its own thread (window handles aren't `Send`, and
+    /// it has to keep handling events between frames); frames reach it over a
+    /// one-slot channel, dropped when the window falls behind.
+    pub struct WindowDisplay {
+        frames: SyncSender<Vec<u32>>,
+        canvas_width: usize,
+        canvas_height: usize,
+        brightness: u8,
+        viewport: Viewport,
+    }
+
+    impl WindowDisplay {
+        /// Open a window for a `canvas_width` x `canvas_height` LED canvas.
+        pub fn new(brightness: u8, (canvas_width, canvas_height): (usize, usize), viewport: Viewport) -> Self {
+            let (frames, rx) = mpsc::sync_channel(1);
+            let (width, height) = (canvas_width * SCALE, canvas_height * SCALE);
+            std::thread::Builder::new()
+                .name("simulator".into())
+                .spawn(move || window_loop(rx, width, height))
+                .expect("failed to spawn simulator window thread");
+
+            tracing::info!(
+                "Simulator display initialized ({}x{}, brightness={}, scale={})",
+                canvas_width, canvas_height, brightness, SCALE
+            );
+            WindowDisplay { frames, canvas_width, canvas_height, brightness, viewport }
+        }
+    }
+
+    fn window_loop(frames: Receiver<Vec<u32>>, width: usize, height: usize) {
+        let mut window = match Window::new("Subway Sign", width, height, WindowOptions::default()) {
+            Ok(window) => window,
+            Err(e) => {
+                tracing::error!("Failed to open simulator window: {}", e);
+                return;
+            }
+        };
+        while window.is_open() {
+            let result = match frames.recv_timeout(Duration::from_millis(16)) {
+                Ok(buffer) => window.update_with_buffer(&buffer, width, height),
+                Err(RecvTimeoutError::Timeout) => {
+                    window.update();
+                    Ok(())
+                }
+                Err(RecvTimeoutError::Disconnected) => return,
+            };
+            if let Err(e) = result {
+                tracing::warn!("Simulator window update failed: {}", e);
+            }
+        }
+        tracing::info!("Simulator window closed");
+    }
+
+    /// Draw the frame's active area onto a scaled `canvas_width` x
+    /// `canvas_height` canvas as 0RGB window pixels.
+    fn led_pixels(
+        frame: &FrameBuffer,
+        viewport: Viewport,
+        brightness: u8,
+        canvas_width: usize,
+        canvas_height: usize,
+    ) -> Vec<u32> {
+        let width = viewport.width.min(frame.width());
+        let height = viewport.height.min(frame.height());
+        let pixels = viewport.active_pixels(frame);
+        let dim = |c: u8| (c as u32 * brightness.min(100) as u32 / 100) & 0xff;
+
+        let row_len = canvas_width * SCALE;
+        let mut out = vec![0u32; row_len * canvas_height * SCALE];
+        for y in 0..canvas_height {
+            for x in 0..canvas_width {
+                let fx = x.checked_sub(viewport.offset_x as usize);
+                let fy = y.checked_sub(viewport.offset_y as usize);
+                let color = match (fx, fy) {
+                    (Some(fx), Some(fy)) if fx < width && fy < height => {
+                        let i = (fy * width + fx) * 3;
+                        (dim(pixels[i]) << 16) | (dim(pixels[i + 1]) << 8) | dim(pixels[i + 2])
+                    }
+                    _ => 0,
+                };
+                let color = if color == 0 { DARK_LED } else { color };
+                // Leave the last row/column of each dot dark
+                for dy in 0..SCALE - 1 {
+                    let start = (y * SCALE + dy) * row_len + x * SCALE;
+                    out[start..start + SCALE - 1].fill(color);
+                }
+            }
+        }
+        out
+    }
+
+    impl DisplayTarget for WindowDisplay {
+        fn swap(&mut self, frame: &FrameBuffer) {
+            let buffer = led_pixels(frame, self.viewport, self.brightness, self.canvas_width, self.canvas_height);
+            // Full or closed: skip this frame
+            let _ = self.frames.try_send(buffer);
+        }
+
+        fn set_brightness(&mut self, brightness: u8) {
+            self.brightness = brightness;
+        }
+
+        fn set_viewport(&mut self, viewport: Viewport) {
+            self.viewport = viewport;
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_led_pixels_places_and_dims_frame() {
+            let mut frame = FrameBuffer::with_size(4, 2);
+            frame.set_pixel(0, 0, (200, 100, 0));
+            let viewport = Viewport { offset_x: 1, offset_y: 1, width: 4, height: 2 };
+            let out = led_pixels(&frame, viewport, 50, 8, 4);
+            let row_len = 8 * SCALE;
+            let at = |x: usize, y: usize| out[y * row_len + x];
+            assert_eq!(out.len(), 8 * 4 * SCALE * SCALE);
+            assert_eq!(at(SCALE, SCALE), 0x00_64_32_00, "offset and half brightness");
+            assert_eq!(at(2 * SCALE - 1, SCALE), 0, "gap between dots");
+            assert_eq!(at(0, 0), DARK_LED, "outside the viewport");
+        }
     }
 }
 
+#[cfg(feature = "simulator")]
+pub use sim::WindowDisplay;
+
 // ---------------------------------------------------------------------------
 // Mock implementation (macOS dev)
 // ---------------------------------------------------------------------------
@@ -200,19 +466,115 @@ impl DisplayTarget for MockDisplay {
     fn swap(&mut self, _frame: &FrameBuffer) {}
 
     fn set_brightness(&mut self, _brightness: u8) {}
+
+    fn set_viewport(&mut self, _viewport: Viewport) {}
 }
 
 // ---------------------------------------------------------------------------
 // Factory function
 // ---------------------------------------------------------------------------
 
-/// Create the appropriate display target based on compile-time features.
+/// Create the display target `panel.backend` asks for. A backend this build
+/// lacks (or that fails to start, like an LED matrix that won't initialize or
+/// a network target that can't be resolved) falls back to `MockDisplay` with
+/// a warning.
+pub fn create_display(brightness: u8, panel: &PanelConfig) -> Box<dyn DisplayTarget> {
+    let viewport = Viewport::from_config(panel);
+    let display = match panel.backend {
+        DisplayBackend::Auto => hardware_display(brightness, panel)
+            .or_else(|| simulator_display(brightness, panel)),
+        DisplayBackend::Hardware => hardware_display(brightness, panel),
+        DisplayBackend::Simulator => simulator_display(brightness, panel),
+        DisplayBackend::Terminal => Some(Box::new(TerminalDisplay::new(brightness, viewport)) as _),
+        DisplayBackend::Network => {
+            let target = panel.network_target.as_deref().unwrap_or("");
+            match NetworkDisplay::new(brightness, viewport, target) {
+                Ok(display) => Some(Box::new(display) as _),
+                Err(e) => {
+                    tracing::warn!("Network display unavailable: {}", e);
+                    None
+                }
+            }
+        }
+        DisplayBackend::Null | DisplayBackend::Headless => None,
+    };
+    display.unwrap_or_else(|| {
+        if !matches!(panel.backend, DisplayBackend::Auto | DisplayBackend::Null | DisplayBackend::Headless) {
+            tracing::warn!("Display backend '{}' unavailable, using mock display", panel.backend.name());
+        }
+        Box::new(MockDisplay::new(brightness))
+    })
+}
+
 #[cfg(feature = "hardware")]
-pub fn create_display(brightness: u8) -> Box<dyn DisplayTarget> {
-    Box::new(hw::LedMatrixDisplay::new(brightness))
+fn hardware_display(brightness: u8, panel: &PanelConfig) -> Option<Box<dyn DisplayTarget>> {
+    match hw::LedMatrixDisplay::new(brightness, panel, Viewport::from_config(panel)) {
+        Ok(display) => Some(Box::new(display) as _),
+        Err(e) => {
+            tracing::error!("{}", e);
+            None
+        }
+    }
 }
 
 #[cfg(not(feature = "hardware"))]
-pub fn create_display(brightness: u8) -> Box<dyn DisplayTarget> {
-    Box::new(MockDisplay::new(brightness))
+fn hardware_display(_brightness: u8, _panel: &PanelConfig) -> Option<Box<dyn DisplayTarget>> {
+    None
+}
+
+#[cfg(feature = "simulator")]
+fn simulator_display(brightness: u8, panel: &PanelConfig) -> Option<Box<dyn DisplayTarget>> {
+    Some(Box::new(WindowDisplay::new(
+        brightness,
+        panel.canvas_size(),
+        Viewport::from_config(panel),
+    )))
+}
+
+#[cfg(not(feature = "simulator"))]
+fn simulator_display(_brightness: u8, _panel: &PanelConfig) -> Option<Box<dyn DisplayTarget>> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_active_pixels_full_frame_borrows() {
+        let frame = FrameBuffer::new();
+        let viewport = Viewport::from_config(&PanelConfig::default());
+        assert!(matches!(viewport.active_pixels(&frame), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_swap_dedup_skips_repeats() {
+        let t0 = Instant::now();
+        let at = |ms| t0 + Duration::from_millis(ms);
+        let mut dedup = SwapDedup::default();
+
+        assert!(dedup.changed(Some(1), t0));
+        assert!(!dedup.changed(Some(1), at(16)));
+        assert!(dedup.changed(Some(2), at(33)));
+        assert!(!dedup.changed(Some(2), at(50)));
+        assert!(dedup.changed(Some(2), at(1033)), "refreshed every second");
+        dedup.invalidate();
+        assert!(dedup.changed(Some(2), at(1050)));
+        assert!(dedup.changed(None, at(1066)), "other screens always swap");
+        assert!(dedup.changed(Some(2), at(1083)));
+        assert_eq!(dedup.take_skipped(), 2);
+        assert_eq!(dedup.take_skipped(), 0);
+    }
+
+    #[test]
+    fn test_active_pixels_crops_rows() {
+        let mut frame = FrameBuffer::with_size(4, 3);
+        frame.set_pixel(1, 1, (9, 9, 9));
+        frame.set_pixel(3, 1, (7, 7, 7)); // outside the 2-wide crop
+        let viewport = Viewport { offset_x: 0, offset_y: 0, width: 2, height: 2 };
+        let pixels = viewport.active_pixels(&frame);
+        assert_eq!(pixels.len(), 2 * 2 * 3);
+        assert_eq!(&pixels[9..12], &[9, 9, 9]);
+        assert!(!pixels.contains(&7));
+    }
 }
@@ -1,5 +1,20 @@
+pub mod activity;
+pub mod brightness;
+pub mod clock;
 pub mod colors;
+pub mod export;
 pub mod fonts;
 pub mod framebuffer;
+pub mod glyphs;
+#[cfg(test)]
+mod golden;
+pub mod i18n;
+pub mod layers;
 pub mod matrix;
+pub mod network;
+pub mod post;
+pub mod qr;
 pub mod renderer;
+pub mod route_names;
+pub mod terminal;
+pub mod timing;
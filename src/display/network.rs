@@ -0,0 +1,123 @@
+//! `network` display backend: frames sent as UDP datagrams to
+//! `panel.network_target`, e.g. an ESP32 driving a panel elsewhere.
+//!
+//! Each datagram is one whole frame: big-endian u16 width, u16 height, then
+//! the active area as row-major RGB888 with brightness already applied.
+//! Config validation keeps the active area small enough to fit.
+
+use std::net::UdpSocket;
+use std::time::{Duration, Instant};
+
+use super::framebuffer::FrameBuffer;
+use super::matrix::{DisplayTarget, Viewport};
+
+/// Unchanged frames are still resent this often, so a receiver that
+/// restarts picks the picture back up.
+const RESEND_INTERVAL: Duration = Duration::from_secs(1);
+
+pub struct NetworkDisplay {
+    socket: UdpSocket,
+    target: String,
+    brightness: u8,
+    viewport: Viewport,
+    last_sent: Option<(Vec<u8>, Instant)>,
+    /// Whether the last send failed (logged once per outage).
+    failing: bool,
+}
+
+impl NetworkDisplay {
+    /// Resolves `target` (`host:port`) once; the socket never blocks.
+    pub fn new(brightness: u8, viewport: Viewport, target: &str) -> Result<Self, String> {
+        let socket = UdpSocket::bind("0.0.0.0:0").map_err(|e| format!("bind failed: {}", e))?;
+        socket
+            .connect(target)
+            .map_err(|e| format!("can't reach {}: {}", target, e))?;
+        socket
+            .set_nonblocking(true)
+            .map_err(|e| format!("socket setup failed: {}", e))?;
+        tracing::info!(
+            "Network display initialized ({}x{} to {}, brightness={})",
+            viewport.width, viewport.height, target, brightness
+        );
+        Ok(NetworkDisplay {
+            socket,
+            target: target.to_string(),
+            brightness,
+            viewport,
+            last_sent: None,
+            failing: false,
+        })
+    }
+}
+
+/// One datagram for `pixels` (`width` x `height`, row-major RGB).
+fn packet(pixels: &[u8], width: usize, height: usize, brightness: u8) -> Vec<u8> {
+    let mut out = Vec::with_capacity(4 + pixels.len());
+    out.extend_from_slice(&(width as u16).to_be_bytes());
+    out.extend_from_slice(&(height as u16).to_be_bytes());
+    out.extend(pixels.iter().map(|&c| (c as u32 * brightness.min(100) as u32 / 100) as u8));
+    out
+}
+
+impl DisplayTarget for NetworkDisplay {
+    fn swap(&mut self, frame: &FrameBuffer) {
+        let width = self.viewport.width.min(frame.width());
+        let height = self.viewport.height.min(frame.height());
+        let data = packet(&self.viewport.active_pixels(frame), width, height, self.brightness);
+        let fresh = self
+            .last_sent
+            .as_ref()
+            .is_some_and(|(last, at)| *last == data && at.elapsed() < RESEND_INTERVAL);
+        if fresh {
+            return;
+        }
+        match self.socket.send(&data) {
+            Ok(_) => {
+                if self.failing {
+                    tracing::info!("[RENDER] Network display {} reachable again", self.target);
+                    self.failing = false;
+                }
+            }
+            Err(e) if !self.failing => {
+                tracing::warn!("[RENDER] Network display send to {} failed: {}", self.target, e);
+                self.failing = true;
+            }
+            Err(_) => {}
+        }
+        self.last_sent = Some((data, Instant::now()));
+    }
+
+    fn set_brightness(&mut self, brightness: u8) {
+        self.brightness = brightness;
+    }
+
+    fn set_viewport(&mut self, viewport: Viewport) {
+        self.viewport = viewport;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frames_arrive_as_datagrams() {
+        let receiver = UdpSocket::bind("127.0.0.1:0").unwrap();
+        receiver.set_read_timeout(Some(Duration::from_secs(2))).unwrap();
+        let target = receiver.local_addr().unwrap().to_string();
+        let viewport = Viewport { offset_x: 0, offset_y: 0, width: 2, height: 1 };
+        let mut display = NetworkDisplay::new(50, viewport, &target).unwrap();
+
+        let mut frame = FrameBuffer::with_size(4, 2);
+        frame.set_pixel(1, 0, (200, 100, 0));
+        display.swap(&frame);
+        let mut buf = [0u8; 64];
+        let len = receiver.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..len], &[0, 2, 0, 1, 0, 0, 0, 100, 50, 0], "active area, half brightness");
+
+        // Same frame again within the resend interval: nothing sent
+        display.swap(&frame);
+        receiver.set_nonblocking(true).unwrap();
+        assert!(receiver.recv(&mut buf).is_err());
+    }
+}
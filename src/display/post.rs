@@ -0,0 +1,371 @@
+//! Post-processing applied to each frame just before it goes to the panel.
+//!
+//! `display.post` lists the steps in order, e.g.
+//! `[{"gamma": 2.2}, {"color_temperature": 5000}, {"rotate": 180}]`. The
+//! rendered frame itself (previews, screenshots, `/api/display/*`) stays
+//! unprocessed; only the panel sees the result.
+
+use std::borrow::Cow;
+use std::ops::Range;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use super::colors::Rgb;
+use super::framebuffer::FrameBuffer;
+
+/// One post-processing step.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PostStep {
+    /// Gamma exponent (0.1-5.0); above 1 darkens midtones to match how
+    /// LEDs look next to a monitor.
+    Gamma(f32),
+    /// White point in kelvin (1000-12000); 6600 is neutral, lower is warmer.
+    ColorTemperature(u32),
+    /// Rotation in degrees: 0 or 180 (for panels mounted upside down).
+    Rotate(u16),
+    /// Move the whole image around a small square to spread LED wear.
+    PixelShift(PixelShift),
+    /// Ordered dithering down to this many bits per channel (1-7).
+    Dither(u8),
+}
+
+/// Pixel shift orbit: `pixels` out, stepping every `interval_minutes`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PixelShift {
+    #[serde(default = "default_shift_pixels")]
+    pub pixels: u8,
+    #[serde(default = "default_shift_interval")]
+    pub interval_minutes: u32,
+}
+
+fn default_shift_pixels() -> u8 {
+    1
+}
+fn default_shift_interval() -> u32 {
+    10
+}
+
+impl PostStep {
+    /// Check the step's parameters.
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            PostStep::Gamma(g) if !(0.1..=5.0).contains(g) => {
+                Err(format!("display.post: gamma must be 0.1-5.0, got {}", g))
+            }
+            PostStep::ColorTemperature(k) if !(1000..=12000).contains(k) => {
+                Err(format!("display.post: color_temperature must be 1000-12000, got {}", k))
+            }
+            PostStep::Rotate(d) if *d != 0 && *d != 180 => {
+                Err(format!("display.post: rotate must be 0 or 180, got {}", d))
+            }
+            PostStep::PixelShift(s) if !(1..=4).contains(&s.pixels) || s.interval_minutes < 1 => {
+                Err("display.post: pixel_shift needs pixels 1-4 and interval_minutes of at least 1"
+                    .to_string())
+            }
+            PostStep::Dither(bits) if !(1..=7).contains(bits) => {
+                Err(format!("display.post: dither must be 1-7 bits, got {}", bits))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Attention effect for a watched train coming in (see `display.watch`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Highlight {
+    /// A 1px frame round the panel edge.
+    #[default]
+    Border,
+    /// The train's row inverted (the border if it isn't on screen).
+    Invert,
+}
+
+/// `frame` with `highlight` drawn over it: the border in `color`, or the
+/// pixel rows `rows` inverted.
+pub fn highlight(frame: &FrameBuffer, highlight: Highlight, rows: Option<Range<usize>>, color: Rgb) -> FrameBuffer {
+    let mut out = frame.clone();
+    let (width, height) = (out.width() as i32, out.height() as i32);
+    match (highlight, rows) {
+        (Highlight::Invert, Some(rows)) => {
+            let start = rows.start.min(out.height()) * out.width() * 3;
+            let end = rows.end.min(out.height()) * out.width() * 3;
+            for v in &mut out.raw_pixels_mut()[start..end] {
+                *v = 255 - *v;
+            }
+        }
+        _ => {
+            for x in 0..width {
+                out.set_pixel(x, 0, color);
+                out.set_pixel(x, height - 1, color);
+            }
+            for y in 0..height {
+                out.set_pixel(0, y, color);
+                out.set_pixel(width - 1, y, color);
+            }
+        }
+    }
+    out
+}
+
+/// Per-channel lookup tables (R, G, B).
+type Lut = [[u8; 256]; 3];
+
+enum Stage {
+    /// Gamma and color temperature; adjacent ones are folded into one table.
+    Lut(Box<Lut>),
+    Rotate180,
+    Shift { pixels: i32, interval: Duration },
+    Dither { bits: u8 },
+}
+
+/// The configured steps, prepared for applying every frame.
+pub struct Pipeline {
+    steps: Vec<PostStep>,
+    stages: Vec<Stage>,
+}
+
+impl Pipeline {
+    pub fn new(steps: &[PostStep]) -> Self {
+        let mut stages: Vec<Stage> = Vec::new();
+        for step in steps {
+            let stage = match step {
+                PostStep::Gamma(g) => Stage::Lut(Box::new(gamma_lut(*g))),
+                PostStep::ColorTemperature(k) => Stage::Lut(Box::new(temperature_lut(*k))),
+                PostStep::Rotate(0) => continue,
+                PostStep::Rotate(_) => Stage::Rotate180,
+                PostStep::PixelShift(s) => Stage::Shift {
+                    pixels: s.pixels as i32,
+                    interval: Duration::from_secs(s.interval_minutes as u64 * 60),
+                },
+                PostStep::Dither(bits) => Stage::Dither { bits: *bits },
+            };
+            match (stages.last_mut(), stage) {
+                (Some(Stage::Lut(prev)), Stage::Lut(next)) => {
+                    for (p, n) in prev.iter_mut().zip(next.iter()) {
+                        for v in p.iter_mut() {
+                            *v = n[*v as usize];
+                        }
+                    }
+                }
+                (_, stage) => stages.push(stage),
+            }
+        }
+        Pipeline {
+            steps: steps.to_vec(),
+            stages,
+        }
+    }
+
+    /// The steps this pipeline was built from.
+    pub fn steps(&self) -> &[PostStep] {
+        &self.steps
+    }
+
+    /// Run the steps over `frame`; `elapsed` (time since start) drives the
+    /// pixel shift. Borrows the frame untouched when there's nothing to do.
+    pub fn apply<'a>(&self, frame: &'a FrameBuffer, elapsed: Duration) -> Cow<'a, FrameBuffer> {
+        if self.stages.is_empty() {
+            return Cow::Borrowed(frame);
+        }
+        let mut out = frame.clone();
+        let (width, height) = (out.width(), out.height());
+        for stage in &self.stages {
+            let pixels = out.raw_pixels_mut();
+            match stage {
+                Stage::Lut(lut) => {
+                    for px in pixels.chunks_exact_mut(3) {
+                        for (c, v) in px.iter_mut().enumerate() {
+                            *v = lut[c][*v as usize];
+                        }
+                    }
+                }
+                Stage::Rotate180 => rotate_180(pixels),
+                Stage::Shift { pixels: n, interval } => {
+                    let step = elapsed.as_secs() / interval.as_secs().max(1);
+                    let (dx, dy) = shift_offset(*n, step);
+                    shift(pixels, width, height, dx, dy);
+                }
+                Stage::Dither { bits } => dither(pixels, width, *bits),
+            }
+        }
+        Cow::Owned(out)
+    }
+}
+
+fn gamma_lut(gamma: f32) -> Lut {
+    let mut table = [0u8; 256];
+    for (i, v) in table.iter_mut().enumerate() {
+        *v = ((i as f32 / 255.0).powf(gamma) * 255.0).round() as u8;
+    }
+    [table; 3]
+}
+
+/// Channel scales for a white point, after Tanner Helland's fit of the
+/// black-body curve.
+fn temperature_lut(kelvin: u32) -> Lut {
+    let t = kelvin as f32 / 100.0;
+    let red = if t <= 66.0 { 255.0 } else { 329.698_73 * (t - 60.0).powf(-0.133_204_76) };
+    let green = if t <= 66.0 {
+        99.470_8 * t.ln() - 161.119_57
+    } else {
+        288.122_17 * (t - 60.0).powf(-0.075_514_85)
+    };
+    let blue = if t >= 66.0 {
+        255.0
+    } else if t <= 19.0 {
+        0.0
+    } else {
+        138.517_73 * (t - 10.0).ln() - 305.044_8
+    };
+
+    let mut lut = [[0u8; 256]; 3];
+    for (table, scale) in lut.iter_mut().zip([red, green, blue]) {
+        let scale = scale.clamp(0.0, 255.0) / 255.0;
+        for (i, v) in table.iter_mut().enumerate() {
+            *v = (i as f32 * scale).round() as u8;
+        }
+    }
+    lut
+}
+
+/// Reversing the pixel order turns the image upside down.
+fn rotate_180(pixels: &mut [u8]) {
+    let n = pixels.len() / 3;
+    for i in 0..n / 2 {
+        let j = n - 1 - i;
+        for c in 0..3 {
+            pixels.swap(i * 3 + c, j * 3 + c);
+        }
+    }
+}
+
+/// Offset for orbit step `step`: around the corners of an `n`-pixel square.
+fn shift_offset(n: i32, step: u64) -> (i32, i32) {
+    [(0, 0), (n, 0), (n, n), (0, n)][(step % 4) as usize]
+}
+
+/// Move the image right/down by (`dx`, `dy`); uncovered pixels go black.
+fn shift(pixels: &mut [u8], width: usize, height: usize, dx: i32, dy: i32) {
+    if dx == 0 && dy == 0 {
+        return;
+    }
+    let source = pixels.to_vec();
+    pixels.fill(0);
+    for y in 0..height as i32 - dy {
+        for x in 0..width as i32 - dx {
+            let from = (y as usize * width + x as usize) * 3;
+            let to = ((y + dy) as usize * width + (x + dx) as usize) * 3;
+            pixels[to..to + 3].copy_from_slice(&source[from..from + 3]);
+        }
+    }
+}
+
+/// 4x4 Bayer thresholds, in sixteenths.
+const BAYER: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+/// Quantize each channel to `bits` bits, spreading the rounding error in a
+/// fixed pattern so gradients don't band.
+fn dither(pixels: &mut [u8], width: usize, bits: u8) {
+    let levels = ((1u32 << bits) - 1) as f32;
+    for (i, px) in pixels.chunks_exact_mut(3).enumerate() {
+        let (x, y) = (i % width, i / width);
+        let threshold = (BAYER[y % 4][x % 4] as f32 + 0.5) / 16.0;
+        for v in px.iter_mut() {
+            let scaled = *v as f32 * levels / 255.0;
+            let level = scaled.floor() + if scaled.fract() > threshold { 1.0 } else { 0.0 };
+            *v = (level * 255.0 / levels).round() as u8;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::display::framebuffer::{DISPLAY_HEIGHT, DISPLAY_WIDTH};
+
+    fn steps(json: &str) -> Vec<PostStep> {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_highlight() {
+        let mut frame = FrameBuffer::new();
+        frame.set_pixel(5, 5, (255, 0, 0));
+
+        let bordered = highlight(&frame, Highlight::Border, None, (255, 255, 255));
+        assert_eq!(bordered.get_pixel(0, 10), (255, 255, 255));
+        assert_eq!(bordered.get_pixel(DISPLAY_WIDTH - 1, DISPLAY_HEIGHT - 1), (255, 255, 255));
+        assert_eq!(bordered.get_pixel(5, 5), (255, 0, 0), "inside untouched");
+
+        let inverted = highlight(&frame, Highlight::Invert, Some(0..16), (255, 255, 255));
+        assert_eq!(inverted.get_pixel(5, 5), (0, 255, 255));
+        assert_eq!(inverted.get_pixel(0, 0), (255, 255, 255));
+        assert_eq!(inverted.get_pixel(0, 16), (0, 0, 0), "other rows untouched");
+        assert_eq!(
+            highlight(&frame, Highlight::Invert, None, (255, 255, 255)).raw_pixels(),
+            bordered.raw_pixels(),
+            "border when the row isn't showing"
+        );
+    }
+
+    #[test]
+    fn test_config_form_and_validation() {
+        let parsed =
+            steps(r#"[{"gamma": 2.2}, {"rotate": 180}, {"pixel_shift": {}}, {"dither": 5}]"#);
+        assert_eq!(parsed[0], PostStep::Gamma(2.2));
+        assert_eq!(
+            parsed[2],
+            PostStep::PixelShift(PixelShift { pixels: 1, interval_minutes: 10 })
+        );
+        assert!(parsed.iter().all(|s| s.validate().is_ok()));
+        assert!(PostStep::Rotate(90).validate().is_err());
+        assert!(PostStep::Dither(8).validate().is_err());
+        assert!(PostStep::ColorTemperature(500).validate().is_err());
+    }
+
+    #[test]
+    fn test_steps_apply_in_order() {
+        let mut frame = FrameBuffer::with_size(4, 2);
+        frame.set_pixel(0, 0, (255, 128, 0));
+        let at = |f: &FrameBuffer, x: usize, y: usize| {
+            let i = (y * f.width() + x) * 3;
+            (f.raw_pixels()[i], f.raw_pixels()[i + 1], f.raw_pixels()[i + 2])
+        };
+
+        assert!(matches!(Pipeline::new(&[]).apply(&frame, Duration::ZERO), Cow::Borrowed(_)));
+
+        let rotated = Pipeline::new(&steps(r#"[{"rotate": 180}]"#)).apply(&frame, Duration::ZERO);
+        assert_eq!(at(&rotated, 3, 1), (255, 128, 0));
+        assert_eq!(at(&rotated, 0, 0), (0, 0, 0));
+
+        // Gamma and temperature fold into one table, applied in order
+        let graded = Pipeline::new(&steps(r#"[{"gamma": 2.0}, {"color_temperature": 6600}]"#));
+        assert_eq!(graded.stages.len(), 1);
+        assert_eq!(at(&graded.apply(&frame, Duration::ZERO), 0, 0), (255, 64, 0));
+
+        let shift = steps(r#"[{"pixel_shift": {"pixels": 1, "interval_minutes": 1}}]"#);
+        let shifted = Pipeline::new(&shift);
+        assert_eq!(at(&shifted.apply(&frame, Duration::from_secs(30)), 0, 0), (255, 128, 0));
+        let moved = shifted.apply(&frame, Duration::from_secs(60));
+        assert_eq!(at(&moved, 1, 0), (255, 128, 0));
+        assert_eq!(at(&moved, 0, 0), (0, 0, 0));
+    }
+
+    #[test]
+    fn test_dither_keeps_extremes_and_average() {
+        let gray =
+            FrameBuffer::from_raw_pixels(vec![100; DISPLAY_WIDTH * DISPLAY_HEIGHT * 3]).unwrap();
+        let out = Pipeline::new(&[PostStep::Dither(1)]).apply(&gray, Duration::ZERO);
+        let pixels = out.raw_pixels();
+        assert!(pixels.iter().all(|&v| v == 0 || v == 255));
+        let mean = pixels.iter().map(|&v| v as f32).sum::<f32>() / pixels.len() as f32;
+        assert!((mean - 100.0).abs() < 8.0, "mean {}", mean);
+
+        let black = FrameBuffer::new();
+        let out = Pipeline::new(&[PostStep::Dither(3)]).apply(&black, Duration::ZERO);
+        assert!(out.raw_pixels().iter().all(|&v| v == 0));
+    }
+}
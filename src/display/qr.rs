@@ -0,0 +1,86 @@
+//! QR codes linking to alert details (see `display.alert_qr_seconds`),
+//! drawn as lit squares around unlit modules so phone cameras read them
+//! like dark-on-light print.
+
+use qrcode::{Color, EcLevel, QrCode};
+
+use super::colors::Rgb;
+use super::framebuffer::FrameBuffer;
+
+/// Lit modules around the code. The spec asks for four; one is what fits
+/// a 32px panel, and scanners cope with the black border beyond it.
+const QUIET_ZONE: usize = 1;
+
+/// Color of the light modules.
+const LIGHT: Rgb = (0xFF, 0xFF, 0xFF);
+
+/// An encoded QR code, kept to draw again without re-encoding.
+pub struct QrMatrix {
+    /// Modules per side.
+    modules: usize,
+    /// Dark modules, row by row.
+    dark: Vec<bool>,
+}
+
+impl QrMatrix {
+    /// Encode `text`; `None` if it's too long for any QR code.
+    pub fn new(text: &str) -> Option<Self> {
+        let code = QrCode::with_error_correction_level(text.as_bytes(), EcLevel::L).ok()?;
+        Some(QrMatrix {
+            modules: code.width(),
+            dark: code.to_colors().into_iter().map(|c| c == Color::Dark).collect(),
+        })
+    }
+
+    /// Side in pixels, scaled up as far as `max_size` allows; `None` if the
+    /// code doesn't fit.
+    pub fn side(&self, max_size: usize) -> Option<usize> {
+        let span = self.modules + 2 * QUIET_ZONE;
+        let scale = max_size / span;
+        (scale > 0).then_some(span * scale)
+    }
+
+    /// Draw the code with its top-left corner at (x, y), scaled up as far
+    /// as `max_size` pixels allows; returns the side drawn, or `None` if the
+    /// code doesn't fit.
+    pub fn draw(&self, fb: &mut FrameBuffer, x: i32, y: i32, max_size: usize) -> Option<usize> {
+        let side = self.side(max_size)?;
+        let scale = side / (self.modules + 2 * QUIET_ZONE);
+        for dy in 0..side as i32 {
+            for dx in 0..side as i32 {
+                fb.set_pixel(x + dx, y + dy, LIGHT);
+            }
+        }
+        for (i, _) in self.dark.iter().enumerate().filter(|(_, dark)| **dark) {
+            let (mx, my) = (i % self.modules + QUIET_ZONE, i / self.modules + QUIET_ZONE);
+            for dy in 0..scale {
+                for dx in 0..scale {
+                    fb.set_pixel(x + (mx * scale + dx) as i32, y + (my * scale + dy) as i32, (0, 0, 0));
+                }
+            }
+        }
+        Some(side)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_draw_qr_scales_to_fit() {
+        let mut fb = FrameBuffer::with_size(64, 64);
+        let code = QrMatrix::new("https://mta.info").unwrap();
+        // Version 1 (21 modules) plus the quiet zone, doubled
+        assert_eq!(code.draw(&mut fb, 0, 0, 64), Some(46));
+        assert_eq!(fb.get_pixel(0, 0), LIGHT, "quiet zone");
+        assert_eq!(fb.get_pixel(2, 2), (0, 0, 0), "finder pattern corner");
+        assert_eq!(fb.get_pixel(46, 0), (0, 0, 0), "nothing beyond the code");
+
+        let mut small = FrameBuffer::new();
+        assert_eq!(code.draw(&mut small, 0, 0, 32), Some(23));
+        let long = QrMatrix::new(&format!("https://new.mta.info/alerts/{}", "x".repeat(200))).unwrap();
+        assert_eq!(long.side(32), None, "too many modules for the panel");
+        assert_eq!(long.draw(&mut small, 0, 0, 32), None);
+    }
+}
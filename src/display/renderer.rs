@@ -1,13 +1,27 @@
-use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::sync::Arc;
+use std::time::Duration;
 
-use crate::models::{Alert, DisplaySnapshot, Train};
+use regex::Regex;
 
-use super::colors::{self, COLOR_BLACK, COLOR_GREEN, COLOR_RED};
-use super::fonts::{self, MtaFont};
-use super::framebuffer::{FrameBuffer, DISPLAY_WIDTH};
+use crate::config::{ArrivalFormat, ArrivingText, Config, FontStyles, Layout};
+use crate::models::{Alert, Direction, DisplaySnapshot, Train};
+use crate::mta::alerts::{NO_SERVICE_PRIORITY, SIGNIFICANT_DELAYS_PRIORITY, TICKER_SEPARATOR};
+use crate::selection::{self, Suspension, TopRowRule};
+use crate::timezone::Timezone;
+use crate::trip::{Connection, ConnectionStatus};
+
+use super::colors::{self, Rgb, COLOR_BLACK, COLOR_GREEN, COLOR_ORANGE, COLOR_RED};
+use super::fonts::{self, FontSize, FontStyle, MtaFont, FONT_HEIGHT_SMALL};
+use super::framebuffer::{FrameBuffer, DISPLAY_HEIGHT, DISPLAY_WIDTH};
+use super::i18n::Strings;
+use super::layers::{Layer, LayerStack};
+use super::qr::QrMatrix;
+use super::route_names;
 
 /// Character spacing for the MTA font (kerning of -1px, matching Python).
-const CHAR_SPACING: i32 = -1;
+pub const CHAR_SPACING: i32 = -1;
 
 /// Width of a route icon (circle/diamond) in pixels.
 const ICON_WIDTH: i32 = 14;
@@ -15,14 +29,34 @@ const ICON_WIDTH: i32 = 14;
 const EMPTY_TRAIN_SENTINEL: i32 = 999;
 /// Y offset of the top train row (shifted up to align with V1 sign).
 const TOP_ROW_Y_ADJUST: i32 = -4;
-/// Y offset where the bottom train row starts.
-const BOTTOM_ROW_Y: i32 = 16;
+/// Height of a train row; the bottom row is the last one on the panel.
+const ROW_HEIGHT: i32 = 16;
 /// Gap between route icon and destination text in a train row.
 const ICON_TEXT_GAP: i32 = 3;
 /// Right margin before the arrival time text.
 const TIME_RIGHT_MARGIN: i32 = 5;
-/// Y position of the scrolling alert row.
-const ALERT_ROW_Y: i32 = 15;
+/// The scrolling alert starts this far above the bottom row.
+const ALERT_ROW_RAISE: i32 = 1;
+/// Marquee: hold at each end of a long destination this long (seconds).
+const MARQUEE_PAUSE: f32 = 1.5;
+/// Marquee scroll speed, pixels per second.
+const MARQUEE_SPEED: f32 = 20.0;
+/// Height of a row in the list layout.
+const LIST_ROW_HEIGHT: i32 = FONT_HEIGHT_SMALL as i32;
+/// Where list-layout destinations start, past the widest route label.
+const LIST_DEST_X: i32 = 16;
+/// Gap between a list-layout destination and its time.
+const LIST_TIME_GAP: i32 = 3;
+/// Departure board: x of the route bullet, past the widest clock time.
+const BOARD_BULLET_X: i32 = 23;
+/// Departure board: x of the destination column.
+const BOARD_DEST_X: i32 = 32;
+/// Alert links whose QR codes are kept encoded.
+const QR_CACHE_SIZE: usize = 16;
+/// Blank columns before each small-font glyph.
+const SMALL_FONT_PAD: i32 = 2;
+/// Departure board: how long a row takes to flip over (seconds).
+const BOARD_FLIP_SECS: f32 = 0.4;
 
 /// Gap before an icon (text → icon) in alerts.
 const TEXT_TO_ICON_GAP: i32 = 5;
@@ -30,6 +64,40 @@ const TEXT_TO_ICON_GAP: i32 = 5;
 const ICON_TO_TEXT_GAP: i32 = 2;
 /// Gap between consecutive icons in alerts.
 const ICON_ICON_GAP: i32 = 1;
+/// Gap between a connection glyph and the arrival time.
+const GLYPH_GAP: i32 = 3;
+/// Connection status glyphs (7x7, `#` = lit), drawn before the arrival time
+/// of first-leg trip trains.
+const GLYPH_FEASIBLE: [&str; 7] = [
+    ".......", "......#", ".....##", "#...##.", "##.##..", ".###...", "..#....",
+];
+const GLYPH_TIGHT: [&str; 7] = [
+    "...#...", "...#...", "...#...", "...#...", "...#...", ".......", "...#...",
+];
+const GLYPH_INFEASIBLE: [&str; 7] = [
+    "#.....#", ".#...#.", "..#.#..", "...#...", "..#.#..", ".#...#.", "#.....#",
+];
+/// Separator between alerts in a ticker (5x5), with `TICKER_GAP` either side.
+const GLYPH_TICKER_SEPARATOR: [&str; 5] = ["..#..", ".###.", "#####", ".###.", "..#.."];
+const TICKER_GAP: i32 = 8;
+/// Do-not-disturb indicator, a small crescent in the empty bottom-right corner.
+const GLYPH_DO_NOT_DISTURB: [&str; 3] = [".##", "#..", ".##"];
+
+const GLYPH_BULLET: [&str; 7] = [
+    "..###..", ".#####.", "#######", "#######", "#######", ".#####.", "..###..",
+];
+const GLYPH_BULLET_EXPRESS: [&str; 7] = [
+    "...#...", "..###..", ".#####.", "#######", ".#####.", "..###..", "...#...",
+];
+/// Corner dot on the bullet of a route whose feed is down (trains from cache).
+const GLYPH_STALE: [&str; 2] = ["##", "##"];
+/// Clock glyph marking a train running late (see `set_delay_marker_minutes`).
+const GLYPH_DELAYED: [&str; 7] = [
+    "..###..", ".#...#.", "#..#..#", "#..##.#", "#.....#", ".#...#.", "..###..",
+];
+
+/// Inline route icon token in alert text, e.g. `[A]` or `[6X]`.
+pub const ROUTE_TOKEN_PATTERN: &str = r"\[(\d+|[A-Z]+)([xX])?\]";
 
 /// Pure rendering engine for the subway sign display.
 ///
@@ -42,11 +110,131 @@ pub struct Renderer {
     alert_cache: Option<AlertCacheEntry>,
     /// Regex for matching `[route]` patterns in alert text.
     route_pattern: Regex,
+    /// Which trains go on which row.
+    layout: Layout,
+    /// What the right edge of train rows shows.
+    arrival_format: ArrivalFormat,
+    /// Text for trains arriving within the minute.
+    arriving_text: ArrivingText,
+    /// For `ArrivalFormat::Alternate`: show the clock time this frame.
+    alternate_clock: bool,
+    /// For `ArrivalFormat::Hybrid`: countdown up to this many minutes.
+    clock_after_minutes: i32,
+    /// Localized on-display strings.
+    strings: Strings,
+    /// Font style per layout element.
+    styles: FontStyles,
+    /// Interpolate the alert at fractional scroll offsets.
+    smooth_scroll: bool,
+    /// Top-row train preferences (see `selection`).
+    top_row: Vec<TopRowRule>,
+    /// Suspended routes and the alternate to promote.
+    suspension: Suspension,
+    /// Routes whose feed is down (see `set_stale_routes`).
+    stale_routes: HashSet<String>,
+    /// Message replacing the cycling train in the bottom row.
+    banner: Option<Banner>,
+    /// Trip connections from first-leg trains (see `trip`).
+    connections: Vec<Connection>,
+    /// Mark trains at least this many seconds late (`None` = never).
+    delay_marker_secs: Option<i32>,
+    /// Draw row text in the route's color instead of green.
+    color_by_route: bool,
+    /// Cycle trains platform by platform (see `selection::group_by_platform`).
+    group_by_platform: bool,
+    /// Draw plain-text route names in alerts as bullets (see `route_names`).
+    route_names: bool,
+    /// Zone for arrival clock times.
+    timezone: Timezone,
+    /// Draw the do-not-disturb indicator.
+    do_not_disturb: bool,
+    /// Bottom row sliding in from the previous cycle index, with progress
+    /// 0-1 (see `set_cycle_transition`).
+    cycle_transition: Option<(usize, f32)>,
+    /// Scroll destinations that don't fit instead of truncating them.
+    marquee: bool,
+    /// Alert/cycling row on top, next train at the bottom.
+    flip_rows: bool,
+    /// What `render_frame` draws, bottom to top.
+    layers: LayerStack,
+    /// Image for the `Background` layer.
+    background: Option<FrameBuffer>,
+    /// Time source for animations (see `set_clock`).
+    clock: Duration,
+    /// When each destination on screen appeared, so its marquee starts
+    /// from the beginning.
+    marquee_starts: HashMap<String, Duration>,
+    /// Departure board rows as last drawn, top to bottom.
+    board: Vec<BoardFlap>,
+    /// Pixel rows each train took in the last frame (see `row_of`).
+    drawn_rows: Vec<(String, Direction, Range<usize>)>,
+    /// Frame size in pixels (see `set_size`).
+    width: usize,
+    height: usize,
+    /// Last frame `render_frame` drew and what it drew it from; setters
+    /// clear it.
+    frame_cache: Option<FrameCache>,
+    /// Frames `render_frame` has drawn (see `frame_generation`).
+    generation: u64,
+    /// Encoded QR codes by URL (`None`: too long to encode).
+    qr_codes: HashMap<String, Option<QrMatrix>>,
+    /// The alerts page as last drawn, for the alert list and font
+    /// generation it was drawn from; `apply_config` clears it.
+    alerts_page: Option<(Arc<Vec<Alert>>, u64, FrameBuffer)>,
+}
+
+/// The per-frame inputs to `render_frame`, beyond the renderer's settings.
+#[derive(Clone, Copy, PartialEq)]
+struct FrameInputs {
+    cycle_index: usize,
+    flash_state: bool,
+    alert_scroll_offset: f32,
+    show_alert: bool,
+    alternate_clock: bool,
+    cycle_transition: Option<(usize, f32)>,
+    do_not_disturb: bool,
+    /// Only while something on screen animates by it (marquee, board flips).
+    clock: Option<Duration>,
+    font_generation: u64,
+}
+
+struct FrameCache {
+    inputs: FrameInputs,
+    snapshot: DisplaySnapshot,
+    alert: Option<Alert>,
+    frame: FrameBuffer,
+}
+
+/// A bottom-row message, shown instead of the cycling train (alerts still
+/// take precedence).
+#[derive(Debug, Clone, PartialEq)]
+pub enum Banner {
+    /// "Leave now for the `[6]`" (see `journey`).
+    LeaveNow { route: String, is_express: bool },
+    /// "Use `[N]` instead" while the primary route is suspended.
+    UseInstead { route: String, is_express: bool },
+    /// "Join Wi-Fi SubwaySign-Setup" while the setup portal is open.
+    Setup { ssid: String },
+    /// "Check station/routes config" when the configured stops never match
+    /// a train although the feeds are up.
+    CheckConfig,
+}
+
+/// One departure board row: what it shows, and what it showed before while
+/// flipping over to it.
+struct BoardFlap {
+    key: String,
+    buffer: FrameBuffer,
+    /// Previous row and when the flip started.
+    flipping_from: Option<(FrameBuffer, Duration)>,
 }
 
 struct AlertCacheEntry {
     text: String,
     routes_key: String,
+    style: FontStyle,
+    /// Font the buffer was drawn with (see `fonts::font_generation`).
+    font_generation: u64,
     /// Pre-rendered alert as a small framebuffer (variable width x 17 height).
     buffer: FrameBuffer,
 }
@@ -60,13 +248,312 @@ impl Renderer {
         Renderer {
             last_alert_width: 0,
             alert_cache: None,
-            route_pattern: Regex::new(r"\[(\d+|[A-Z]+)([xX])?\]").unwrap(),
+            route_pattern: Regex::new(ROUTE_TOKEN_PATTERN).unwrap(),
+            layout: Layout::default(),
+            arrival_format: ArrivalFormat::default(),
+            arriving_text: ArrivingText::default(),
+            alternate_clock: false,
+            clock_after_minutes: 20,
+            strings: Strings::default(),
+            styles: FontStyles::default(),
+            smooth_scroll: true,
+            top_row: Vec::new(),
+            suspension: Suspension::default(),
+            stale_routes: HashSet::new(),
+            banner: None,
+            connections: Vec::new(),
+            delay_marker_secs: None,
+            color_by_route: false,
+            group_by_platform: false,
+            route_names: true,
+            timezone: Timezone::System,
+            do_not_disturb: false,
+            cycle_transition: None,
+            marquee: false,
+            flip_rows: false,
+            layers: LayerStack::default(),
+            background: None,
+            clock: Duration::ZERO,
+            marquee_starts: HashMap::new(),
+            board: Vec::new(),
+            drawn_rows: Vec::new(),
+            width: DISPLAY_WIDTH,
+            height: DISPLAY_HEIGHT,
+            frame_cache: None,
+            alerts_page: None,
+            generation: 0,
+            qr_codes: HashMap::new(),
+        }
+    }
+
+    /// Take the hot-reloadable display options from `config`.
+    pub fn apply_config(&mut self, config: &Config) {
+        self.alerts_page = None;
+        let display = &config.display;
+        let (width, height) = config.panel.frame_size();
+        self.set_size(width, height);
+        self.set_timezone(config.tz());
+        self.set_layout(display.layout);
+        self.set_arrival_format(display.arrival_format);
+        self.set_arriving_text(display.arriving_text);
+        self.set_clock_after_minutes(display.clock_after_minutes);
+        self.set_font_styles(display.fonts.clone());
+        self.set_smooth_scroll(display.smooth_scroll);
+        self.set_top_row(display.top_row.clone());
+        self.set_group_by_platform(display.group_by_platform);
+        self.set_marquee(display.marquee);
+        self.set_flip_rows(display.flip_rows);
+        self.set_delay_marker_minutes(display.delay_marker_minutes);
+        self.set_color_by_route(display.color_by_route);
+        self.set_route_names(display.alert_route_icons);
+        // Already validated at config load; a failure here just keeps the old strings
+        if let Ok(strings) = Strings::new(&display.locale, &display.strings) {
+            self.set_strings(strings);
+        }
+    }
+
+    /// Render frames at `width` x `height` (default 192x32). Rows are
+    /// right-aligned to the width; panels taller than two rows show the
+    /// trains after the top one in the rows between.
+    pub fn set_size(&mut self, width: usize, height: usize) {
+        self.width = width;
+        self.height = height;
+        self.frame_cache = None;
+    }
+
+    /// Frame size in pixels.
+    pub fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    /// A black frame of the renderer's size.
+    pub fn blank_frame(&self) -> FrameBuffer {
+        FrameBuffer::with_size(self.width, self.height)
+    }
+
+    /// Train rows that fit the frame (at least two).
+    fn row_count(&self) -> usize {
+        (self.height / ROW_HEIGHT as usize).max(2)
+    }
+
+    /// Y offset where the bottom row starts.
+    fn bottom_row_y(&self) -> i32 {
+        self.height as i32 - ROW_HEIGHT
+    }
+
+    /// Row for the next train (top, or bottom with `flip_rows`).
+    fn headline_y(&self) -> i32 {
+        if self.flip_rows { self.bottom_row_y() } else { 0 }
+    }
+
+    /// Row alerts, banners and the cycling train take (the other end).
+    fn dock_y(&self) -> i32 {
+        if self.flip_rows { 0 } else { self.bottom_row_y() }
+    }
+
+    /// Set the trip connections shown as glyphs on first-leg train rows.
+    pub fn set_connections(&mut self, connections: Vec<Connection>) {
+        if self.connections != connections {
+            self.connections = connections;
+            self.frame_cache = None;
+        }
+    }
+
+    /// Set which routes are suspended (and what replaces them).
+    pub fn set_suspension(&mut self, suspension: Suspension) {
+        if self.suspension != suspension {
+            self.suspension = suspension;
+            self.frame_cache = None;
+        }
+    }
+
+    /// Set the routes whose feed is down; their bullets get a corner dot.
+    pub fn set_stale_routes(&mut self, routes: HashSet<String>) {
+        if self.stale_routes != routes {
+            self.stale_routes = routes;
+            self.frame_cache = None;
+        }
+    }
+
+    /// Set (or clear) the bottom-row banner.
+    pub fn set_banner(&mut self, banner: Option<Banner>) {
+        if self.banner != banner {
+            self.banner = banner;
+            self.frame_cache = None;
         }
     }
 
-    /// Render a complete frame.
+    /// Set which train the top row pins to.
+    pub fn set_top_row(&mut self, rules: Vec<TopRowRule>) {
+        self.top_row = rules;
+        self.frame_cache = None;
+    }
+
+    /// Enable or disable sub-pixel alert scrolling.
+    pub fn set_smooth_scroll(&mut self, enabled: bool) {
+        self.smooth_scroll = enabled;
+        self.frame_cache = None;
+    }
+
+    /// Set the font style for each layout element.
+    pub fn set_font_styles(&mut self, styles: FontStyles) {
+        self.styles = styles;
+        self.frame_cache = None;
+    }
+
+    /// Set the localized strings used for arrival text.
+    pub fn set_strings(&mut self, strings: Strings) {
+        self.strings = strings;
+        self.frame_cache = None;
+    }
+
+    /// Set what the right edge of train rows shows.
+    pub fn set_arrival_format(&mut self, format: ArrivalFormat) {
+        self.arrival_format = format;
+        self.frame_cache = None;
+    }
+
+    /// How trains arriving within the minute are shown.
+    pub fn set_arriving_text(&mut self, text: ArrivingText) {
+        self.arriving_text = text;
+        self.frame_cache = None;
+    }
+
+    /// Set the countdown cutoff for `ArrivalFormat::Hybrid`.
+    pub fn set_clock_after_minutes(&mut self, minutes: u32) {
+        self.clock_after_minutes = minutes.min(EMPTY_TRAIN_SENTINEL as u32) as i32;
+        self.frame_cache = None;
+    }
+
+    /// Mark trains running at least `minutes` late (0 = off).
+    pub fn set_delay_marker_minutes(&mut self, minutes: u32) {
+        self.delay_marker_secs = (minutes > 0).then(|| minutes.saturating_mul(60).min(i32::MAX as u32) as i32);
+        self.frame_cache = None;
+    }
+
+    /// Scroll long destinations back and forth instead of truncating them.
+    pub fn set_marquee(&mut self, enabled: bool) {
+        self.marquee = enabled;
+        self.frame_cache = None;
+    }
+
+    /// Set which layers `render_frame` draws, and in what order.
+    pub fn set_layers(&mut self, layers: LayerStack) {
+        self.layers = layers;
+        self.frame_cache = None;
+    }
+
+    pub fn layers(&self) -> &LayerStack {
+        &self.layers
+    }
+
+    /// Set (or clear) the `Background` layer's image, drawn at the top left.
+    pub fn set_background(&mut self, background: Option<FrameBuffer>) {
+        self.background = background;
+        self.frame_cache = None;
+    }
+
+    /// Put the alert/cycling row on top and the next train at the bottom.
+    pub fn set_flip_rows(&mut self, enabled: bool) {
+        self.flip_rows = enabled;
+        self.frame_cache = None;
+    }
+
+    /// Advance the animation clock (any monotonic time, e.g. since
+    /// startup), which drives the marquee and departure board flips.
+    pub fn set_clock(&mut self, now: Duration) {
+        self.clock = now;
+    }
+
+    /// Cycle the trains at each platform together.
+    pub fn set_group_by_platform(&mut self, enabled: bool) {
+        self.group_by_platform = enabled;
+        self.frame_cache = None;
+    }
+
+    /// Draw train numbers, destinations and times in the route's color.
+    pub fn set_color_by_route(&mut self, enabled: bool) {
+        self.color_by_route = enabled;
+        self.frame_cache = None;
+    }
+
+    /// Show or hide the do-not-disturb indicator (bottom-right corner).
+    pub fn set_do_not_disturb(&mut self, enabled: bool) {
+        self.do_not_disturb = enabled;
+    }
+
+    /// Current on-display strings.
+    pub fn strings(&self) -> &Strings {
+        &self.strings
+    }
+
+    /// Current bottom-row banner, if any.
+    pub fn banner(&self) -> Option<&Banner> {
+        self.banner.as_ref()
+    }
+
+    /// Set which trains go on which row.
+    pub fn set_layout(&mut self, layout: Layout) {
+        self.layout = layout;
+        self.frame_cache = None;
+    }
+
+    /// Set the zone arrival clock times are shown in.
+    pub fn set_timezone(&mut self, timezone: Timezone) {
+        self.timezone = timezone;
+        self.frame_cache = None;
+    }
+
+    /// Enable or disable bullets for plain-text route names in alerts.
+    pub fn set_route_names(&mut self, enabled: bool) {
+        if enabled != self.route_names {
+            self.route_names = enabled;
+            self.alert_cache = None;
+            self.frame_cache = None;
+        }
+    }
+
+    /// Select the half of the alternation to show (`true` = clock time).
+    /// Only matters with `ArrivalFormat::Alternate`.
+    pub fn set_alternate_phase(&mut self, show_clock: bool) {
+        self.alternate_clock = show_clock;
+    }
+
+    /// Slide the cycling row over from `previous` cycle index, `progress`
+    /// (0-1) of the way; `None` shows the current train still.
+    pub fn set_cycle_transition(&mut self, transition: Option<(usize, f32)>) {
+        self.cycle_transition = transition;
+    }
+
+    /// Counts the frames `render_frame` drew; stays put while it hands back
+    /// the last one because nothing it draws from changed.
+    pub fn frame_generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Pixel rows the next `route` train going `direction` was drawn in,
+    /// last frame; `None` if it wasn't on screen.
+    pub fn row_of(&self, route: &str, direction: Direction) -> Option<Range<usize>> {
+        self.drawn_rows
+            .iter()
+            .find(|(r, d, _)| r == route && *d == direction)
+            .map(|(_, _, rows)| rows.clone())
+    }
+
+    /// Note that `train` was drawn `height` pixels down from `y`.
+    fn mark_row(&mut self, train: &Train, y: i32, height: i32) {
+        if !train.route.is_empty() {
+            let rows = y.max(0) as usize..(y + height).max(0) as usize;
+            self.drawn_rows.push((train.route.clone(), train.direction, rows));
+        }
+    }
+
+    /// Render a complete frame: the visible layers, bottom to top (see
+    /// `layers`).
     ///
-    /// This is the main entry point called at 60fps.
+    /// This is the main entry point called at 60fps. When the snapshot,
+    /// alert, timing inputs and settings all match the last call, that
+    /// frame comes back without drawing.
     pub fn render_frame(
         &mut self,
         snapshot: &DisplaySnapshot,
@@ -76,26 +563,445 @@ impl Renderer {
         show_alert: bool,
         current_alert: Option<&Alert>,
     ) -> FrameBuffer {
-        let mut fb = FrameBuffer::new();
+        let animated = self.marquee || self.layout == Layout::Board;
+        let inputs = FrameInputs {
+            cycle_index,
+            flash_state,
+            alert_scroll_offset,
+            show_alert,
+            alternate_clock: self.alternate_clock,
+            cycle_transition: self.cycle_transition,
+            do_not_disturb: self.do_not_disturb,
+            clock: animated.then_some(self.clock),
+            font_generation: fonts::font_generation(),
+        };
+        if let Some(cache) = &self.frame_cache {
+            if cache.inputs == inputs && cache.snapshot == *snapshot && cache.alert.as_ref() == current_alert {
+                return cache.frame.clone();
+            }
+        }
+
+        let fb = self.draw_frame(snapshot, cycle_index, flash_state, alert_scroll_offset, show_alert, current_alert);
+        self.generation += 1;
+        self.frame_cache = Some(FrameCache {
+            inputs,
+            snapshot: snapshot.clone(),
+            alert: current_alert.cloned(),
+            frame: fb.clone(),
+        });
+        fb
+    }
 
-        // Top row: next arriving train (any direction)
-        let first_train = snapshot.get_first_train();
-        self.render_train_row(&mut fb, first_train, 0, 1, flash_state);
+    /// Draw the layers for `render_frame`.
+    fn draw_frame(
+        &mut self,
+        snapshot: &DisplaySnapshot,
+        cycle_index: usize,
+        flash_state: bool,
+        alert_scroll_offset: f32,
+        show_alert: bool,
+        current_alert: Option<&Alert>,
+    ) -> FrameBuffer {
+        let mut fb = self.blank_frame();
+        self.drawn_rows.clear();
+
+        let notice = self.layers.visible(Layer::Notice) && (show_alert || self.banner.is_some());
+        for layer in self.layers.drawn() {
+            match layer {
+                Layer::Background => {
+                    if let Some(background) = &self.background {
+                        self.blit_framebuffer(&mut fb, background, 0, 0);
+                    }
+                }
+                Layer::Trains => self.render_trains(&mut fb, snapshot, cycle_index, flash_state, notice),
+                Layer::Notice => {
+                    self.render_notice_row(&mut fb, show_alert, current_alert, alert_scroll_offset);
+                }
+                Layer::Status => self.render_do_not_disturb(&mut fb),
+            }
+        }
+        fb
+    }
 
-        // Bottom row: cycling train OR scrolling alert
+    /// The `Trains` layer; `notice` leaves the alert/banner row free.
+    fn render_trains(
+        &mut self,
+        fb: &mut FrameBuffer,
+        snapshot: &DisplaySnapshot,
+        cycle_index: usize,
+        flash_state: bool,
+        notice: bool,
+    ) {
+        if matches!(self.layout, Layout::List | Layout::Board) {
+            let list_height = if notice { self.height as i32 - ROW_HEIGHT } else { self.height as i32 };
+            if notice && self.flip_rows {
+                // List below the notice row
+                let mut list = FrameBuffer::with_size(self.width, list_height as usize);
+                self.render_list_layout(&mut list, snapshot, list_height, flash_state);
+                self.blit_framebuffer(fb, &list, 0, ROW_HEIGHT);
+                for (_, _, rows) in &mut self.drawn_rows {
+                    *rows = rows.start + ROW_HEIGHT as usize..rows.end + ROW_HEIGHT as usize;
+                }
+            } else {
+                self.render_list_layout(fb, snapshot, list_height, flash_state);
+            }
+            return;
+        }
+
+        // Top row: pinned train per the top_row rules (default: soonest),
+        // or the alternate to a suspended route. On taller panels, the next
+        // trains in the rows between. Bottom: the cycling train, as (train,
+        // row number, flashes), and the one it's sliding in over. By
+        // direction: next each way.
+        let middle_rows = self.row_count() - 2;
+        let (first_train, middle, second, outgoing) = match self.layout {
+            Layout::Standard => {
+                let (first, mut cycling) =
+                    selection::arrange(snapshot, &self.top_row, &self.suspension, 6 + middle_rows);
+                if self.group_by_platform {
+                    selection::group_by_platform(snapshot, &first, &mut cycling, &self.suspension);
+                }
+                let at = |index: usize| {
+                    let idx = (middle_rows + index).min(cycling.len().saturating_sub(1));
+                    (cycling[idx].clone(), idx + 2, false)
+                };
+                let outgoing = self.cycle_transition.map(|(previous, progress)| (at(previous), progress));
+                let middle = cycling[..middle_rows].to_vec();
+                (first, middle, at(cycle_index), outgoing)
+            }
+            // (List and board rows are drawn above)
+            Layout::ByDirection | Layout::List | Layout::Board => {
+                let (uptown, downtown) = selection::by_direction(snapshot, &self.suspension);
+                (uptown, Vec::new(), (downtown, 1, flash_state), None)
+            }
+        };
+        if self.marquee {
+            let on_screen: Vec<&str> = std::iter::once(&first_train)
+                .chain(&middle)
+                .chain(std::iter::once(&second.0))
+                .map(|t| t.destination.as_str())
+                .collect();
+            let now = self.clock;
+            self.marquee_starts.retain(|dest, _| on_screen.contains(&dest.as_str()));
+            for dest in on_screen {
+                self.marquee_starts.entry(dest.to_string()).or_insert(now);
+            }
+        }
+        self.render_train_row(fb, &first_train, self.headline_y(), 1, flash_state);
+        self.mark_row(&first_train, self.headline_y(), ROW_HEIGHT);
+        for (i, train) in middle.iter().enumerate() {
+            self.render_train_row(fb, train, (i as i32 + 1) * ROW_HEIGHT, i + 2, false);
+            self.mark_row(train, (i as i32 + 1) * ROW_HEIGHT, ROW_HEIGHT);
+        }
+
+        // Bottom (or with flip_rows, top) row: the second train, unless an
+        // alert or banner has it
+        if !notice {
+            let (train, number, flash) = second;
+            match outgoing {
+                Some(((previous, previous_number, _), progress)) if previous_number != number => {
+                    self.render_slide(fb, (&previous, previous_number), (&train, number), progress);
+                }
+                _ => self.render_train_row(fb, &train, self.dock_y(), number, flash),
+            }
+            self.mark_row(&train, self.dock_y(), ROW_HEIGHT);
+        }
+    }
+
+    /// The `Notice` layer: the alert (while `show_alert`) or banner.
+    fn render_notice_row(
+        &mut self,
+        fb: &mut FrameBuffer,
+        show_alert: bool,
+        current_alert: Option<&Alert>,
+        alert_scroll_offset: f32,
+    ) {
         if show_alert {
             if let Some(alert) = current_alert {
-                self.render_alert_row(&mut fb, alert, alert_scroll_offset);
+                self.render_alert_row(fb, alert, alert_scroll_offset);
             }
+        } else if let Some(banner) = &self.banner {
+            self.render_banner_row(fb, banner);
+        }
+    }
+
+    fn render_do_not_disturb(&self, fb: &mut FrameBuffer) {
+        if self.do_not_disturb {
+            let x = self.width as i32 - GLYPH_DO_NOT_DISTURB[0].len() as i32;
+            let y = self.height as i32 - GLYPH_DO_NOT_DISTURB.len() as i32;
+            Self::draw_glyph(fb, &GLYPH_DO_NOT_DISTURB, x, y, COLOR_ORANGE);
+        }
+    }
+
+    /// List or board rows, `height` pixels of them.
+    fn render_list_layout(&mut self, fb: &mut FrameBuffer, snapshot: &DisplaySnapshot, height: i32, flash_state: bool) {
+        if self.layout == Layout::Board {
+            self.render_board(fb, snapshot, height);
         } else {
-            let cycling = snapshot.get_cycling_trains(6);
-            let idx = cycle_index.min(cycling.len().saturating_sub(1));
-            self.render_train_row(&mut fb, &cycling[idx], BOTTOM_ROW_Y, idx + 2, false);
+            self.render_list_rows(fb, snapshot, height, flash_state);
         }
+    }
 
+    /// List layout: one small-font row per train down to `height`, the
+    /// first picked by the `top_row` rules, the rest in arrival order.
+    fn render_list_rows(&mut self, fb: &mut FrameBuffer, snapshot: &DisplaySnapshot, height: i32, flash_state: bool) {
+        let rows = (height / LIST_ROW_HEIGHT).max(1) as usize;
+        let (first, mut rest) = selection::arrange(snapshot, &self.top_row, &self.suspension, rows - 1);
+        if self.group_by_platform {
+            selection::group_by_platform(snapshot, &first, &mut rest, &self.suspension);
+        }
+        let trains = std::iter::once(&first).chain(&rest);
+        // Placeholder rows only when there's nothing at all
+        for (i, train) in trains.enumerate().filter(|(i, t)| *i == 0 || !t.route.is_empty()) {
+            self.render_list_row(fb, train, i as i32 * LIST_ROW_HEIGHT, flash_state);
+            self.mark_row(train, i as i32 * LIST_ROW_HEIGHT, LIST_ROW_HEIGHT);
+        }
+    }
+
+    /// One list-layout row: route (◆ for express) in its color,
+    /// destination, and the time right-aligned.
+    fn render_list_row(&self, fb: &mut FrameBuffer, train: &Train, y: i32, flash_state: bool) {
+        let (time_color, text_color) = self.row_colors(train, flash_state);
+
+        let route = if train.is_express { format!("{}\u{25C6}", train.route) } else { train.route.clone() };
+        let route_color = colors::route_color(&train.route).unwrap_or(text_color);
+        let route_width = Self::draw_small(fb, &route, 0, y, route_color);
+        if self.stale_routes.contains(&train.route) {
+            Self::draw_stale_marker(fb, route_width as i32 + 1, y);
+        }
+
+        let time_text = self.time_text(train);
+        let time_x = self.width as i32 - Self::measure_small(&time_text) as i32;
+        let available = (time_x - LIST_DEST_X - LIST_TIME_GAP).max(0) as usize;
+        let destination = Self::truncate_small(&train.destination, available);
+        Self::draw_small(fb, &destination, LIST_DEST_X, y, text_color);
+        Self::draw_small(fb, &time_text, time_x, y, time_color);
+    }
+
+    /// Departure board layout: a heading, then a row per train down to
+    /// `height` (the first picked by the `top_row` rules). Rows whose
+    /// contents change flip over to the new ones.
+    fn render_board(&mut self, fb: &mut FrameBuffer, snapshot: &DisplaySnapshot, height: i32) {
+        let track = self.strings.get("track");
+        Self::draw_small(fb, self.strings.get("departs"), 0, 0, COLOR_ORANGE);
+        Self::draw_small(fb, self.strings.get("to"), BOARD_DEST_X, 0, COLOR_ORANGE);
+        let track_x = self.width as i32 - Self::measure_small(track) as i32;
+        Self::draw_small(fb, track, track_x, 0, COLOR_ORANGE);
+
+        let rows = (height / LIST_ROW_HEIGHT - 1).max(1) as usize;
+        let (first, mut rest) = selection::arrange(snapshot, &self.top_row, &self.suspension, rows - 1);
+        if self.group_by_platform {
+            selection::group_by_platform(snapshot, &first, &mut rest, &self.suspension);
+        }
+        let trains: Vec<Option<&Train>> = std::iter::once(&first)
+            .chain(&rest)
+            .enumerate()
+            .map(|(i, t)| Some(t).filter(|t| i == 0 || !t.route.is_empty()))
+            .collect();
+
+        let now = self.clock;
+        self.board.truncate(rows);
+        for (i, train) in trains.into_iter().enumerate() {
+            let key = train
+                .map(|t| {
+                    format!(
+                        "{}|{}|{}|{}|{}|{}|{}",
+                        self.clock_text(t),
+                        t.route,
+                        t.is_express,
+                        t.destination,
+                        t.stop_id,
+                        t.minutes == 0,
+                        self.stale_routes.contains(&t.route)
+                    )
+                })
+                .unwrap_or_default();
+            if self.board.get(i).is_none_or(|flap| flap.key != key) {
+                let buffer = self.render_board_row(train);
+                match self.board.get_mut(i) {
+                    Some(flap) => {
+                        let previous = std::mem::replace(&mut flap.buffer, buffer);
+                        flap.key = key;
+                        flap.flipping_from = Some((previous, now));
+                    }
+                    None => self.board.push(BoardFlap { key, buffer, flipping_from: None }),
+                }
+            }
+
+            let row_y = (i as i32 + 1) * LIST_ROW_HEIGHT;
+            if let Some(train) = train {
+                self.mark_row(train, row_y, LIST_ROW_HEIGHT);
+            }
+            let flap = &mut self.board[i];
+            let progress = flap
+                .flipping_from
+                .as_ref()
+                .map(|(_, started)| now.saturating_sub(*started).as_secs_f32() / BOARD_FLIP_SECS);
+            match progress {
+                // First half: the old row folds away; second: the new one unfolds
+                Some(p) if p < 0.5 => Self::blit_folded(fb, &flap.flipping_from.as_ref().unwrap().0, row_y, 1.0 - 2.0 * p),
+                Some(p) if p < 1.0 => Self::blit_folded(fb, &flap.buffer, row_y, 2.0 * p - 1.0),
+                _ => {
+                    flap.flipping_from = None;
+                    Self::blit_folded(fb, &flap.buffer, row_y, 1.0);
+                }
+            }
+        }
+    }
+
+    /// One departure board row: clock time, route bullet, destination and
+    /// track (the platform's stop ID); blank for no train.
+    fn render_board_row(&self, train: Option<&Train>) -> FrameBuffer {
+        let mut fb = FrameBuffer::with_size(self.width, LIST_ROW_HEIGHT as usize);
+        let Some(train) = train else { return fb };
+        let (time_color, text_color) = self.row_colors(train, false);
+
+        if !train.route.is_empty() {
+            Self::draw_small(&mut fb, &self.clock_text(train), 0, 0, time_color);
+            let bullet = if train.is_express { &GLYPH_BULLET_EXPRESS } else { &GLYPH_BULLET };
+            let color = colors::route_color(&train.route).unwrap_or(text_color);
+            Self::draw_glyph(&mut fb, bullet, BOARD_BULLET_X, 0, color);
+            // Centered in the bullet (the glyphs start SMALL_FONT_PAD in)
+            let route_width = Self::measure_small(&train.route) as i32 - SMALL_FONT_PAD;
+            let route_x = BOARD_BULLET_X + (bullet[0].len() as i32 - route_width) / 2 - SMALL_FONT_PAD;
+            Self::draw_small(&mut fb, &train.route, route_x, 1, COLOR_BLACK);
+            if self.stale_routes.contains(&train.route) {
+                Self::draw_stale_marker(&mut fb, BOARD_BULLET_X + bullet[0].len() as i32, 0);
+            }
+        }
+
+        let track = train.stop_id.trim_end_matches(['N', 'S']);
+        let track_x = self.width as i32 - Self::measure_small(track) as i32;
+        Self::draw_small(&mut fb, track, track_x, 0, text_color);
+        let available = (track_x - BOARD_DEST_X - LIST_TIME_GAP).max(0) as usize;
+        let destination = Self::truncate_small(&train.destination, available);
+        Self::draw_small(&mut fb, &destination, BOARD_DEST_X, 0, text_color);
+        fb
+    }
+
+    /// Copy `row` to `y`, squashed toward its middle line to `scale` (0-1)
+    /// of its height, like a flap part way over.
+    fn blit_folded(fb: &mut FrameBuffer, row: &FrameBuffer, y: i32, scale: f32) {
+        let middle = row.height() as f32 / 2.0;
+        for dy in 0..row.height() {
+            let src = middle + (dy as f32 + 0.5 - middle) / scale.max(f32::EPSILON);
+            if src < 0.0 || src >= row.height() as f32 {
+                continue;
+            }
+            for x in 0..row.width().min(fb.width()) {
+                let color = row.get_pixel(x, src as usize);
+                if color != COLOR_BLACK {
+                    fb.set_pixel(x as i32, y + dy as i32, color);
+                }
+            }
+        }
+    }
+
+    /// Alerts page: a line per alert, most urgent first, as the affected
+    /// route bullets and a label ("No service", "Delays", ...); "Good
+    /// service" when there are none. Redrawn only when the alert list (a new
+    /// `Arc`) or the fonts change.
+    pub fn render_alerts_page(&mut self, alerts: &Arc<Vec<Alert>>) -> FrameBuffer {
+        let font_generation = fonts::font_generation();
+        if let Some((drawn, generation, frame)) = &self.alerts_page {
+            if Arc::ptr_eq(drawn, alerts) && *generation == font_generation {
+                return frame.clone();
+            }
+        }
+        let fb = self.draw_alerts_page(alerts);
+        self.alerts_page = Some((Arc::clone(alerts), font_generation, fb.clone()));
         fb
     }
 
+    fn draw_alerts_page(&self, alerts: &[Alert]) -> FrameBuffer {
+        let mut fb = self.blank_frame();
+        if alerts.is_empty() {
+            self.render_banner_text(&mut fb, self.strings.get("good_service"), TOP_ROW_Y_ADJUST + 4, COLOR_GREEN);
+            return fb;
+        }
+        let mut alerts: Vec<&Alert> = alerts.iter().collect();
+        alerts.sort_by_key(|a| a.priority);
+        for (row, alert) in alerts.iter().take(self.row_count()).enumerate() {
+            let mut routes: Vec<&String> = alert.affected_routes.iter().collect();
+            routes.sort();
+            let label = match alert.priority {
+                NO_SERVICE_PRIORITY => "no_service",
+                SIGNIFICANT_DELAYS_PRIORITY => "delays",
+                _ => "service_change",
+            };
+            let bullets: String = routes.iter().map(|r| format!("[{}]", r)).collect();
+            let line = format!("{} {}", bullets, self.strings.get(label));
+            let style = if alert.priority <= self.styles.critical_priority {
+                self.styles.critical_alert
+            } else {
+                self.styles.alert
+            };
+            let buffer = self.render_alert_with_icons(line.trim(), style);
+            self.blit_framebuffer(&mut fb, &buffer, 0, row as i32 * ROW_HEIGHT - ALERT_ROW_RAISE);
+        }
+        fb
+    }
+
+    /// QR code for `url` at the left, "Scan for details" centered beside
+    /// it; `None` if the code doesn't fit the panel (logged the first time).
+    /// Each URL is encoded once.
+    pub fn render_qr_page(&mut self, url: &str) -> Option<FrameBuffer> {
+        if !self.qr_codes.contains_key(url) {
+            if self.qr_codes.len() >= QR_CACHE_SIZE {
+                self.qr_codes.clear();
+            }
+            let code = QrMatrix::new(url);
+            if code.as_ref().and_then(|c| c.side(self.height)).is_none() {
+                tracing::info!("[QR] Link too long for a QR code on a {}px panel, no QR page: {}", self.height, url);
+            }
+            self.qr_codes.insert(url.to_string(), code);
+        }
+        let mut fb = self.blank_frame();
+        let side = self.qr_codes[url].as_ref()?.draw(&mut fb, 0, 0, self.height)? as i32;
+        let font = fonts::get_font();
+        let style = self.styles.alert;
+        let available = (self.width as i32 - side).max(0) as usize;
+        let text = self.truncate_text(&font, self.strings.get("scan_details"), available, style);
+        let width = font.measure_text(&text, CHAR_SPACING, style) as i32;
+        let x = side + ((available as i32 - width) / 2).max(0);
+        let y = (self.height as i32 - ROW_HEIGHT) / 2 + TOP_ROW_Y_ADJUST + 4;
+        fb.draw_text(&text, x, y, COLOR_GREEN, style, CHAR_SPACING);
+        Some(fb)
+    }
+
+    /// Render one centered line of text per row, instead of arrivals.
+    pub fn render_text_screen(&self, lines: &[String; 2]) -> FrameBuffer {
+        let mut fb = self.blank_frame();
+        let font = fonts::get_font();
+        let style = self.styles.alert;
+        for (line, row_y) in lines.iter().zip([0, self.bottom_row_y()]) {
+            let text = self.truncate_text(&font, line, self.width, style);
+            let width = font.measure_text(&text, CHAR_SPACING, style) as i32;
+            let x = ((self.width as i32 - width) / 2).max(0);
+            fb.draw_text(&text, x, row_y + TOP_ROW_Y_ADJUST + 4, COLOR_GREEN, style, CHAR_SPACING);
+        }
+        fb
+    }
+
+    /// Bottom row mid-slide: `from` moving up out of the row as `to` comes
+    /// in below it, `progress` (0-1) of the way.
+    fn render_slide(&self, fb: &mut FrameBuffer, from: (&Train, usize), to: (&Train, usize), progress: f32) {
+        let row_height = ROW_HEIGHT;
+        let mut rows = FrameBuffer::with_size(self.width, 2 * row_height as usize);
+        self.render_train_row(&mut rows, from.0, 0, from.1, false);
+        self.render_train_row(&mut rows, to.0, row_height, to.1, false);
+
+        let shift = (progress.clamp(0.0, 1.0) * row_height as f32).round() as usize;
+        for y in 0..row_height as usize {
+            for x in 0..self.width {
+                let px = rows.get_pixel(x, y + shift);
+                if px != COLOR_BLACK {
+                    fb.set_pixel(x as i32, self.dock_y() + y as i32, px);
+                }
+            }
+        }
+    }
+
     /// Render a single train row at the given y_offset.
     fn render_train_row(
         &self,
@@ -109,22 +1015,15 @@ impl Renderer {
 
         let y = y_offset + TOP_ROW_Y_ADJUST;
 
-        // Determine colors based on arrival state
-        let is_arriving = train.minutes == 0;
-        let (time_color, text_color) = if is_arriving && flash_state {
-            (COLOR_BLACK, COLOR_RED) // Flash to black
-        } else if is_arriving {
-            (COLOR_RED, COLOR_RED)
-        } else {
-            (COLOR_GREEN, COLOR_GREEN)
-        };
+        let (time_color, text_color) = self.row_colors(train, flash_state);
 
         // 1. Train number (e.g., "1.", "2.")
         let num_text = format!("{}.", train_number);
-        let num_width = fb.draw_text(&num_text, -2, y + 4, text_color, false, CHAR_SPACING);
+        let num_width =
+            fb.draw_text(&num_text, -2, y + 4, text_color, FontStyle::Regular, CHAR_SPACING);
 
-        // 2. Route icon
-        let icon_x = num_width as i32;
+        // 2. Route icon, where a next character would go
+        let icon_x = num_width as i32 + CHAR_SPACING;
         if !train.route.is_empty() {
             self.render_route_icon(fb, &train.route, train.is_express, icon_x, y + 4);
         }
@@ -133,21 +1032,210 @@ impl Renderer {
         let station_x = icon_x + ICON_WIDTH + ICON_TEXT_GAP;
 
         // 4. Arrival time (right-aligned)
-        let time_text = if train.minutes < EMPTY_TRAIN_SENTINEL {
-            format!("{}min", train.minutes)
-        } else {
-            "---min".to_string()
+        let arrival_style = self.styles.arrival;
+        let measure = |text: &str| font.measure_text(text, CHAR_SPACING, arrival_style) as i32;
+        let time_text = self.time_text(train);
+        let reserved_width = match self.arrival_format {
+            // Reserve the wider of the two so the destination doesn't
+            // re-truncate every time the time text flips
+            ArrivalFormat::Alternate => measure(&self.minutes_text(train)).max(measure(&self.clock_text(train))),
+            _ => measure(&time_text),
         };
-        let time_width = font.measure_text(&time_text, CHAR_SPACING, false) as i32;
-        let time_x = DISPLAY_WIDTH as i32 - time_width;
+        let time_width = measure(&time_text);
+        let time_x = self.width as i32 - time_width;
+        let mut reserved_x = self.width as i32 - reserved_width;
+
+        // Trip connection glyph, left of the time
+        if let Some(connection) = self.connection_for(train) {
+            let (glyph, color) = match connection.status {
+                ConnectionStatus::Feasible => (&GLYPH_FEASIBLE, COLOR_GREEN),
+                ConnectionStatus::Tight => (&GLYPH_TIGHT, COLOR_ORANGE),
+                ConnectionStatus::Infeasible => (&GLYPH_INFEASIBLE, COLOR_RED),
+            };
+            reserved_x -= glyph[0].len() as i32 + GLYPH_GAP;
+            Self::draw_glyph(fb, glyph, reserved_x, y + 7, color);
+        }
+
+        // Delay marker, left of that
+        let delayed = self
+            .delay_marker_secs
+            .zip(train.delay_secs)
+            .is_some_and(|(threshold, delay)| delay >= threshold);
+        if delayed {
+            reserved_x -= GLYPH_DELAYED[0].len() as i32 + GLYPH_GAP;
+            Self::draw_glyph(fb, &GLYPH_DELAYED, reserved_x, y + 7, COLOR_ORANGE);
+        }
 
         // Truncate destination to fit between icon and time
-        let available_width = (time_x - station_x - TIME_RIGHT_MARGIN).max(0) as usize;
-        let dest_text = self.truncate_text(font, &train.destination, available_width);
-        fb.draw_text(&dest_text, station_x, y + 4, text_color, false, CHAR_SPACING);
+        let available_width = (reserved_x - station_x - TIME_RIGHT_MARGIN).max(0) as usize;
+        // Fall back to the overflow style (e.g. condensed) before truncating
+        let mut dest_style = self.styles.destination;
+        if font.measure_text(&train.destination, CHAR_SPACING, dest_style) > available_width {
+            dest_style = self.styles.destination_overflow;
+        }
+        let dest_width = font.measure_text(&train.destination, CHAR_SPACING, dest_style);
+        if self.marquee && dest_width > available_width {
+            // Draw the whole name clipped to the space it has, shifted along
+            let start = self.marquee_starts.get(&train.destination).copied().unwrap_or(self.clock);
+            let offset = marquee_offset(dest_width - available_width, self.clock.saturating_sub(start));
+            let mut clip = FrameBuffer::with_size(available_width, fb.height());
+            clip.draw_text(&train.destination, -offset, y + 4, text_color, dest_style, CHAR_SPACING);
+            self.blit_framebuffer(fb, &clip, station_x, 0);
+        } else {
+            let dest_text = self.truncate_text(&font, &train.destination, available_width, dest_style);
+            fb.draw_text(&dest_text, station_x, y + 4, text_color, dest_style, CHAR_SPACING);
+        }
 
         // Draw time
-        fb.draw_text(&time_text, time_x, y + 4, time_color, false, CHAR_SPACING);
+        fb.draw_text(&time_text, time_x, y + 4, time_color, arrival_style, CHAR_SPACING);
+    }
+
+    /// Draw plain banner text centered at `y`, truncated to the display.
+    fn render_banner_text(&self, fb: &mut FrameBuffer, text: &str, y: i32, color: Rgb) {
+        let font = fonts::get_font();
+        let style = self.styles.alert;
+        let text = self.truncate_text(&font, text, self.width, style);
+        let width = font.measure_text(&text, CHAR_SPACING, style) as i32;
+        let x = ((self.width as i32 - width) / 2).max(0);
+        fb.draw_text(&text, x, y, color, style, CHAR_SPACING);
+    }
+
+    /// Render a banner centered in the bottom row.
+    fn render_banner_row(&self, fb: &mut FrameBuffer, banner: &Banner) {
+        let font = fonts::get_font();
+        let style = self.styles.alert;
+        let y = self.dock_y() + TOP_ROW_Y_ADJUST + 4;
+
+        let (key, route, is_express) = match banner {
+            Banner::LeaveNow { route, is_express } => ("leave_now", route, is_express),
+            Banner::UseInstead { route, is_express } => ("use_instead", route, is_express),
+            Banner::Setup { ssid } => {
+                let text = self.strings.get("setup_wifi").replace("{ssid}", ssid);
+                self.render_banner_text(fb, &text, y, COLOR_GREEN);
+                return;
+            }
+            Banner::CheckConfig => {
+                self.render_banner_text(fb, self.strings.get("check_config"), y, COLOR_ORANGE);
+                return;
+            }
+        };
+        let template = self.strings.get(key);
+        let (before, after) = template.split_once("{route}").unwrap_or((template, ""));
+        let before = before.trim_end();
+        let after = after.trim_start();
+        let icon_width = font.route_bullet(route, *is_express).map_or(0, |i| i.width as i32);
+
+        let text_width = |text: &str| font.measure_text(text, CHAR_SPACING, style) as i32;
+        let mut width = text_width(before) + icon_width + text_width(after);
+        if !before.is_empty() {
+            width += TEXT_TO_ICON_GAP;
+        }
+        if !after.is_empty() {
+            width += ICON_TO_TEXT_GAP;
+        }
+
+        let mut x = ((self.width as i32 - width) / 2).max(0);
+        if !before.is_empty() {
+            x += fb.draw_text(before, x, y, COLOR_GREEN, style, CHAR_SPACING) as i32;
+            x += TEXT_TO_ICON_GAP;
+        }
+        self.render_route_icon(fb, route, *is_express, x, y);
+        x += icon_width;
+        if !after.is_empty() {
+            fb.draw_text(after, x + ICON_TO_TEXT_GAP, y, COLOR_GREEN, style, CHAR_SPACING);
+        }
+    }
+
+    /// The trip connection from this train, if it's on the first leg.
+    fn connection_for(&self, train: &Train) -> Option<&Connection> {
+        self.connections
+            .iter()
+            .find(|c| c.route == train.route && c.departs_at == train.arrival_timestamp)
+    }
+
+    /// Draw a pixel-art glyph with its top-left corner at (x, y).
+    fn draw_glyph(fb: &mut FrameBuffer, glyph: &[&str], x: i32, y: i32, color: Rgb) {
+        for (dy, row) in glyph.iter().enumerate() {
+            for (dx, _) in row.bytes().enumerate().filter(|&(_, b)| b == b'#') {
+                fb.set_pixel(x + dx as i32, y + dy as i32, color);
+            }
+        }
+    }
+
+    /// (time, text) colors for a train row: red while arriving (the time
+    /// flashing to black), else green or the route's color.
+    fn row_colors(&self, train: &Train, flash_state: bool) -> (Rgb, Rgb) {
+        let is_arriving = train.minutes == 0;
+        if is_arriving && flash_state {
+            (COLOR_BLACK, COLOR_RED) // Flash to black
+        } else if is_arriving {
+            (COLOR_RED, COLOR_RED)
+        } else {
+            let color = if self.color_by_route {
+                colors::route_color(&train.route).unwrap_or(COLOR_GREEN)
+            } else {
+                COLOR_GREEN
+            };
+            (color, color)
+        }
+    }
+
+    /// Right-edge text for a train row, per `arrival_format`.
+    fn time_text(&self, train: &Train) -> String {
+        match self.arrival_format {
+            ArrivalFormat::Minutes => self.minutes_text(train),
+            ArrivalFormat::Clock => self.clock_text(train),
+            ArrivalFormat::Hybrid => {
+                if train.minutes > self.clock_after_minutes && train.minutes < EMPTY_TRAIN_SENTINEL {
+                    self.clock_text(train)
+                } else {
+                    self.minutes_text(train)
+                }
+            }
+            ArrivalFormat::Alternate if self.alternate_clock => self.clock_text(train),
+            ArrivalFormat::Alternate => self.minutes_text(train),
+        }
+    }
+
+    /// Countdown text ("5min"), "---min" for placeholders.
+    fn minutes_text(&self, train: &Train) -> String {
+        let min = self.strings.get("min");
+        if train.minutes >= EMPTY_TRAIN_SENTINEL {
+            format!("---{}", min)
+        } else if train.minutes == 0 {
+            self.arriving_text(train)
+        } else {
+            format!("{}{}", train.minutes, min)
+        }
+    }
+
+    /// Right-edge text for a train arriving within the minute.
+    fn arriving_text(&self, train: &Train) -> String {
+        let due = self.strings.get("due");
+        match self.arriving_text {
+            ArrivingText::Auto if !due.is_empty() => due.to_string(),
+            ArrivingText::Auto | ArrivingText::Minutes => format!("0{}", self.strings.get("min")),
+            ArrivingText::Now => self.strings.get("now").to_string(),
+            ArrivingText::Due if due.is_empty() => "Due".to_string(),
+            ArrivingText::Due => due.to_string(),
+            ArrivingText::Seconds => {
+                let secs = (train.arrival_timestamp - crate::unix_now_secs() as f64).clamp(0.0, 59.0);
+                format!("{}{}", secs as u32, self.strings.get("sec"))
+            }
+        }
+    }
+
+    /// Arrival clock time in local time ("8:42" in `en`), or "--:--" for placeholders.
+    fn clock_text(&self, train: &Train) -> String {
+        if train.minutes >= EMPTY_TRAIN_SENTINEL {
+            return "--:--".to_string();
+        }
+        let format = self.strings.get("clock_format");
+        self.timezone
+            .from_timestamp(train.arrival_timestamp as i64)
+            .filter(|_| train.arrival_timestamp > 0.0)
+            .map(|t| t.format(format).to_string())
+            .unwrap_or_else(|| "--:--".to_string())
     }
 
     /// Render a scrolling alert in the bottom row.
@@ -158,19 +1246,36 @@ impl Renderer {
         scroll_offset: f32,
     ) {
         let routes_key = Self::routes_key(&alert.affected_routes);
+        let font_generation = fonts::font_generation();
+        let style = if alert.priority <= self.styles.critical_priority {
+            self.styles.critical_alert
+        } else {
+            self.styles.alert
+        };
 
         // Check cache
         let need_render = match &self.alert_cache {
-            Some(cached) => cached.text != alert.text || cached.routes_key != routes_key,
+            Some(cached) => {
+                cached.text != alert.text
+                    || cached.routes_key != routes_key
+                    || cached.style != style
+                    || cached.font_generation != font_generation
+            }
             None => true,
         };
 
         if need_render {
-            let alert_buf = self.render_alert_with_icons(&alert.text);
+            let alert_buf = if alert.is_ticker {
+                self.render_ticker(&alert.text, style)
+            } else {
+                self.render_alert_with_icons(&alert.text, style)
+            };
             self.last_alert_width = alert_buf.width() as i32;
             self.alert_cache = Some(AlertCacheEntry {
                 text: alert.text.clone(),
                 routes_key,
+                style,
+                font_generation,
                 buffer: alert_buf,
             });
         }
@@ -178,34 +1283,47 @@ impl Renderer {
         let alert_buf = &self.alert_cache.as_ref().unwrap().buffer;
 
         // Scroll: text starts off-screen right, moves left
-        let x_pos = DISPLAY_WIDTH as i32 - scroll_offset as i32;
+        let x_pos = self.width as f32 - scroll_offset;
 
-        // Only render if still visible (y=15 to fit 17px tall alert in bottom half)
-        if x_pos > -(alert_buf.width() as i32) {
-            self.blit_framebuffer(fb, alert_buf, x_pos, ALERT_ROW_Y);
+        // Only render if still visible (raised 1px to fit the 17px tall alert in the bottom row)
+        let y = self.dock_y() - ALERT_ROW_RAISE;
+        if x_pos > -(alert_buf.width() as f32) - 1.0 {
+            if self.smooth_scroll {
+                self.blit_framebuffer_subpixel(fb, alert_buf, x_pos, y);
+            } else {
+                let x = self.width as i32 - scroll_offset as i32;
+                self.blit_framebuffer(fb, alert_buf, x, y);
+            }
         }
     }
 
     /// Get total scroll distance needed for current alert to fully cross the screen.
     pub fn get_scroll_complete_distance(&self) -> i32 {
-        DISPLAY_WIDTH as i32 + self.last_alert_width + 10
+        self.width as i32 + self.last_alert_width + 10
     }
 
     /// Render alert text with inline route icons for `[route]` patterns.
-    fn render_alert_with_icons(
+    pub fn render_alert_with_icons(
         &self,
         text: &str,
+        style: FontStyle,
     ) -> FrameBuffer {
         let font = fonts::get_font();
         let alert_color = colors::COLOR_ORANGE;
 
+        let marked = if self.route_names {
+            route_names::mark_route_names(text)
+        } else {
+            text.into()
+        };
+        let text = marked.as_ref();
         let matches: Vec<_> = self.route_pattern.find_iter(text).collect();
 
         if matches.is_empty() {
-            // No route patterns — render as simple italic text
-            let width = font.measure_text(text, CHAR_SPACING, true).max(1);
+            // No route patterns — render as simple text
+            let width = font.measure_text(text, CHAR_SPACING, style).max(1);
             let mut buf = FrameBuffer::with_size(width, 17);
-            buf.draw_text(text, 0, 1, alert_color, true, CHAR_SPACING);
+            buf.draw_text(text, 0, 1, alert_color, style, CHAR_SPACING);
             return buf;
         }
 
@@ -236,11 +1354,11 @@ impl Renderer {
             .iter()
             .filter_map(|p| match p {
                 AlertPart::Text(t) => {
-                    let w = font.measure_text(t, CHAR_SPACING, true);
+                    let w = font.measure_text(t, CHAR_SPACING, style);
                     Some(RenderedPart::Text(t.clone(), w))
                 }
                 AlertPart::Icon { route, is_express } => {
-                    Self::lookup_icon(font, route, *is_express)
+                    font.route_bullet(route, *is_express)
                         .map(|i| RenderedPart::Icon(route.clone(), *is_express, i.width))
                 }
             })
@@ -266,13 +1384,13 @@ impl Renderer {
 
             match part {
                 RenderedPart::Text(t, _w) => {
-                    let drawn = buf.draw_text(t, x_pos, 1, alert_color, true, CHAR_SPACING);
+                    let drawn = buf.draw_text(t, x_pos, 1, alert_color, style, CHAR_SPACING);
                     x_pos += drawn as i32;
                 }
                 RenderedPart::Icon(route, is_express, _w) => {
-                    if let Some(icon) = Self::lookup_icon(font, route, *is_express) {
+                    if let Some(icon) = font.route_bullet(route, *is_express) {
                         let y = 1 - icon.baseline_offset;
-                        buf.blit_icon(icon, x_pos, y);
+                        buf.blit_icon(&icon, x_pos, y);
                         x_pos += icon.width as i32;
                     }
                 }
@@ -282,6 +1400,32 @@ impl Renderer {
         buf
     }
 
+    /// Render a ticker (alerts joined by `TICKER_SEPARATOR`, see
+    /// `alerts::ticker_alert`) as one buffer, with a diamond between alerts.
+    pub fn render_ticker(&self, text: &str, style: FontStyle) -> FrameBuffer {
+        let parts: Vec<FrameBuffer> = text
+            .split(TICKER_SEPARATOR)
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+            .map(|t| self.render_alert_with_icons(t, style))
+            .collect();
+        let separator_width = GLYPH_TICKER_SEPARATOR[0].len() as i32 + 2 * TICKER_GAP;
+        let width = parts.iter().map(|p| p.width() as i32).sum::<i32>()
+            + separator_width * (parts.len() as i32 - 1).max(0);
+
+        let mut buf = FrameBuffer::with_size(width.max(1) as usize, 17);
+        let mut x = 0;
+        for (i, part) in parts.iter().enumerate() {
+            if i > 0 {
+                Self::draw_glyph(&mut buf, &GLYPH_TICKER_SEPARATOR, x + TICKER_GAP, 6, COLOR_ORANGE);
+                x += separator_width;
+            }
+            self.blit_framebuffer(&mut buf, part, x, 0);
+            x += part.width() as i32;
+        }
+        buf
+    }
+
     /// Render a route icon at (x, y) with baseline offset.
     fn render_route_icon(
         &self,
@@ -291,24 +1435,49 @@ impl Renderer {
         x: i32,
         y: i32,
     ) {
-        if let Some(icon) = Self::lookup_icon(fonts::get_font(), route, is_express) {
-            fb.blit_icon(icon, x, y - icon.baseline_offset);
+        if let Some(icon) = fonts::get_font().route_bullet(route, is_express) {
+            fb.blit_icon(&icon, x, y - icon.baseline_offset);
+            if self.stale_routes.contains(route) {
+                let corner = x + icon.width as i32 - GLYPH_STALE[0].len() as i32;
+                Self::draw_stale_marker(fb, corner, y - icon.baseline_offset);
+            }
         }
     }
 
-    /// Look up a route icon with express fallback to local variant.
-    fn lookup_icon<'a>(
-        font: &'a MtaFont,
-        route: &str,
-        is_express: bool,
-    ) -> Option<&'a fonts::RouteIcon> {
-        font.get_route_icon(route, is_express)
-            .or_else(|| font.get_route_icon(route, false))
+    /// Mark a route whose feed is down, top-left corner at (x, y).
+    fn draw_stale_marker(fb: &mut FrameBuffer, x: i32, y: i32) {
+        Self::draw_glyph(fb, &GLYPH_STALE, x, y, COLOR_ORANGE);
     }
 
     /// Truncate text to fit within max_width pixels.
-    fn truncate_text(&self, font: &MtaFont, text: &str, max_width: usize) -> String {
-        if font.measure_text(text, CHAR_SPACING, false) <= max_width {
+    fn truncate_text(
+        &self,
+        font: &MtaFont,
+        text: &str,
+        max_width: usize,
+        style: FontStyle,
+    ) -> String {
+        Self::fit_text(text, max_width, |t| font.measure_text(t, CHAR_SPACING, style))
+    }
+
+    /// Draw `text` in the small font (list and board layouts); returns
+    /// the width drawn.
+    fn draw_small(fb: &mut FrameBuffer, text: &str, x: i32, y: i32, color: Rgb) -> usize {
+        fb.draw_text_sized(text, x, y, color, FontSize::Small, CHAR_SPACING)
+    }
+
+    fn measure_small(text: &str) -> usize {
+        fonts::get_font().measure_text_sized(text, CHAR_SPACING, FontSize::Small)
+    }
+
+    /// `truncate_text` for the small font, without trailing spaces.
+    fn truncate_small(text: &str, max_width: usize) -> String {
+        Self::fit_text(text, max_width, Self::measure_small).trim_end().to_string()
+    }
+
+    /// The longest prefix of `text` `measure` puts within `max_width`.
+    fn fit_text(text: &str, max_width: usize, measure: impl Fn(&str) -> usize) -> String {
+        if measure(text) <= max_width {
             return text.to_string();
         }
 
@@ -318,7 +1487,7 @@ impl Renderer {
         while lo < hi {
             let mid = (lo + hi).div_ceil(2);
             let candidate: String = text.chars().take(mid).collect();
-            if font.measure_text(&candidate, CHAR_SPACING, false) <= max_width {
+            if measure(&candidate) <= max_width {
                 lo = mid;
             } else {
                 hi = mid - 1;
@@ -339,6 +1508,37 @@ impl Renderer {
         }
     }
 
+    /// Blit at a fractional x: each column is blended with its left
+    /// neighbour by the fractional part, so motion between whole-pixel
+    /// positions shows as a brightness shift instead of a jump.
+    fn blit_framebuffer_subpixel(&self, dst: &mut FrameBuffer, src: &FrameBuffer, x: f32, y: i32) {
+        let base = x.floor();
+        let frac = x - base;
+        if frac < 1.0 / 255.0 {
+            self.blit_framebuffer(dst, src, base as i32, y);
+            return;
+        }
+
+        let blend = |left: u8, right: u8| -> u8 {
+            (right as f32 * (1.0 - frac) + left as f32 * frac).round() as u8
+        };
+        let black = (0, 0, 0);
+        // Only the columns that land on screen
+        let first = (-(base as i32)).max(0) as usize;
+        let last = src.width().min((dst.width() as i32 - base as i32).max(0) as usize);
+        for sy in 0..src.height() {
+            // Column sx of the output mixes src[sx] with src[sx - 1]
+            for sx in first..=last {
+                let right = if sx < src.width() { src.get_pixel(sx, sy) } else { black };
+                let left = if sx > 0 { src.get_pixel(sx - 1, sy) } else { black };
+                let px = (blend(left.0, right.0), blend(left.1, right.1), blend(left.2, right.2));
+                if px != black {
+                    dst.set_pixel(base as i32 + sx as i32, y + sy as i32, px);
+                }
+            }
+        }
+    }
+
     /// Build a stable string key from a set of routes (for cache comparison).
     fn routes_key(routes: &std::collections::HashSet<String>) -> String {
         let mut sorted: Vec<&str> = routes.iter().map(|s| s.as_str()).collect();
@@ -366,6 +1566,12 @@ impl Renderer {
     }
 }
 
+impl Default for Renderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 // -- Internal types for alert rendering --
 
 enum AlertPart {
@@ -403,6 +1609,14 @@ impl RenderedPart {
     }
 }
 
+/// Pixels a marquee of `overflow` extra pixels is scrolled, `elapsed` after
+/// it started: hold at the start, scroll, hold at the end, then again.
+fn marquee_offset(overflow: usize, elapsed: Duration) -> i32 {
+    let scroll = overflow as f32 / MARQUEE_SPEED;
+    let t = elapsed.as_secs_f32() % (2.0 * MARQUEE_PAUSE + scroll);
+    ((t - MARQUEE_PAUSE).clamp(0.0, scroll) * MARQUEE_SPEED).round() as i32
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -418,6 +1632,8 @@ mod tests {
             arrival_timestamp: 0.0,
             direction: Direction::Uptown,
             stop_id: "127N".into(),
+            delay_secs: None,
+            trip_id: String::new(),
         }
     }
 
@@ -454,6 +1670,119 @@ mod tests {
         assert!(has_pixels, "frame should have some non-black pixels");
     }
 
+    #[test]
+    fn test_render_frame_reuses_unchanged_frame() {
+        let mut renderer = Renderer::new();
+        let mut snapshot = DisplaySnapshot {
+            trains: vec![make_train("1", "Van Cortlandt Park", 2, false)],
+            alerts: Vec::new(),
+            fetched_at: 1000.0,
+        };
+
+        let first = renderer.render_frame(&snapshot, 0, false, 0.0, false, None);
+        let drawn = renderer.frame_generation();
+        let again = renderer.render_frame(&snapshot, 0, false, 0.0, false, None);
+        assert_eq!(again.fingerprint(), first.fingerprint());
+        assert_eq!(renderer.frame_generation(), drawn, "same inputs: not redrawn");
+
+        renderer.render_frame(&snapshot, 0, true, 0.0, false, None);
+        assert_eq!(renderer.frame_generation(), drawn + 1, "flash phase");
+        snapshot.trains[0].minutes = 1;
+        let updated = renderer.render_frame(&snapshot, 0, true, 0.0, false, None);
+        assert_eq!(renderer.frame_generation(), drawn + 2, "new snapshot");
+        assert_ne!(updated.fingerprint(), first.fingerprint());
+        renderer.set_color_by_route(true);
+        renderer.render_frame(&snapshot, 0, true, 0.0, false, None);
+        assert_eq!(renderer.frame_generation(), drawn + 3, "setting changed");
+        renderer.set_banner(None);
+        renderer.render_frame(&snapshot, 0, true, 0.0, false, None);
+        assert_eq!(renderer.frame_generation(), drawn + 3, "same banner");
+    }
+
+    #[test]
+    fn test_alerts_page_redrawn_on_new_alerts() {
+        let mut renderer = Renderer::new();
+        let alert = |priority| Alert {
+            text: "No trains".into(),
+            affected_routes: HashSet::from(["A".to_string()]),
+            priority,
+            alert_id: "test".into(),
+            url: None,
+            is_ticker: false,
+        };
+        let alerts = Arc::new(vec![alert(NO_SERVICE_PRIORITY)]);
+        let first = renderer.render_alerts_page(&alerts);
+        assert_eq!(renderer.render_alerts_page(&alerts).fingerprint(), first.fingerprint());
+        let delays = renderer.render_alerts_page(&Arc::new(vec![alert(SIGNIFICANT_DELAYS_PRIORITY)]));
+        assert_ne!(delays.fingerprint(), first.fingerprint());
+    }
+
+    #[test]
+    fn test_hidden_layers_are_skipped() {
+        let mut renderer = Renderer::new();
+        let snapshot = DisplaySnapshot {
+            trains: vec![make_train("1", "Van Cortlandt Park", 2, false), make_train("2", "Wakefield", 5, false)],
+            alerts: Vec::new(),
+            fetched_at: 1000.0,
+        };
+        let trains_only = renderer.render_frame(&snapshot, 0, false, 0.0, false, None);
+        renderer.set_banner(Some(Banner::CheckConfig));
+        let with_banner = renderer.render_frame(&snapshot, 0, false, 0.0, false, None);
+        assert_ne!(with_banner.raw_pixels(), trains_only.raw_pixels());
+
+        let mut layers = LayerStack::default();
+        layers.set_visible(Layer::Notice, false);
+        renderer.set_layers(layers.clone());
+        let frame = renderer.render_frame(&snapshot, 0, false, 0.0, false, None);
+        assert_eq!(frame.raw_pixels(), trains_only.raw_pixels(), "the cycling train keeps its row");
+
+        layers.set_visible(Layer::Notice, true);
+        layers.set_visible(Layer::Trains, false);
+        renderer.set_layers(layers);
+        let frame = renderer.render_frame(&snapshot, 0, false, 0.0, false, None);
+        assert!(frame.raw_pixels()[..192 * 16 * 3].iter().all(|&b| b == 0), "no train rows");
+        assert_eq!(frame.raw_pixels()[192 * 16 * 3..], with_banner.raw_pixels()[192 * 16 * 3..]);
+    }
+
+    #[test]
+    fn test_flip_rows_swaps_headline_and_dock() {
+        let mut renderer = Renderer::new();
+        let snapshot = DisplaySnapshot {
+            trains: vec![make_train("1", "Van Cortlandt Park", 2, false), make_train("2", "Wakefield", 5, false)],
+            alerts: Vec::new(),
+            fetched_at: 1000.0,
+        };
+        let band = |fb: &FrameBuffer, top: usize| fb.raw_pixels()[top * 192 * 3..(top + 16) * 192 * 3].to_vec();
+        let normal = renderer.render_frame(&snapshot, 0, false, 0.0, false, None);
+        renderer.set_flip_rows(true);
+        let flipped = renderer.render_frame(&snapshot, 0, false, 0.0, false, None);
+        assert_eq!(band(&flipped, 16), band(&normal, 0), "next train at the bottom");
+        assert_eq!(band(&flipped, 0), band(&normal, 16), "cycling train on top");
+    }
+
+    #[test]
+    fn test_stale_route_marks_bullet() {
+        let mut renderer = Renderer::new();
+        let snapshot = DisplaySnapshot {
+            trains: vec![make_train("N", "Astoria", 2, false), make_train("1", "Van Cortlandt Park", 5, false)],
+            alerts: Vec::new(),
+            fetched_at: 1000.0,
+        };
+        let orange = |fb: &FrameBuffer| {
+            (0..fb.height()).flat_map(|y| (0..fb.width()).map(move |x| (x, y))).filter(|&(x, y)| fb.get_pixel(x, y) == COLOR_ORANGE).count()
+        };
+        let fresh = renderer.render_frame(&snapshot, 0, false, 0.0, false, None);
+        renderer.set_stale_routes(HashSet::from(["N".to_string()]));
+        let stale = renderer.render_frame(&snapshot, 0, false, 0.0, false, None);
+        assert_eq!(orange(&stale) - orange(&fresh), 4, "a 2x2 dot on the N bullet only");
+
+        renderer.set_layout(Layout::List);
+        let stale = renderer.render_frame(&snapshot, 0, false, 0.0, false, None);
+        renderer.set_stale_routes(HashSet::new());
+        let fresh = renderer.render_frame(&snapshot, 0, false, 0.0, false, None);
+        assert_eq!(orange(&stale) - orange(&fresh), 4, "the same dot in the list layout");
+    }
+
     #[test]
     fn test_render_frame_empty_snapshot() {
         let mut renderer = Renderer::new();
@@ -499,17 +1828,31 @@ mod tests {
 
         let buf = renderer.render_alert_with_icons(
             "Delays on [1] [2] trains due to signal problems",
+            FontStyle::Italic,
         );
 
         assert!(buf.width() > 0);
         assert_eq!(buf.height(), 17);
     }
 
+    #[test]
+    fn test_render_ticker() {
+        let renderer = Renderer::new();
+        let first = renderer.render_alert_with_icons("Delays on [1]", FontStyle::Italic);
+        let second = renderer.render_alert_with_icons("No [6] trains", FontStyle::Italic);
+        let ticker = renderer.render_ticker("Delays on [1] \u{25C6} No [6] trains", FontStyle::Italic);
+        assert_eq!(ticker.width(), first.width() + second.width() + 5 + 2 * TICKER_GAP as usize);
+        // The diamond's widest row sits in the gap after the first alert
+        let diamond = first.width() + TICKER_GAP as usize;
+        assert_eq!(ticker.get_pixel(diamond, 8), COLOR_ORANGE);
+        assert_eq!(ticker.get_pixel(diamond - 1, 8), COLOR_BLACK);
+    }
+
     #[test]
     fn test_render_alert_no_icons() {
         let renderer = Renderer::new();
 
-        let buf = renderer.render_alert_with_icons("Service change in effect");
+        let buf = renderer.render_alert_with_icons("Service change in effect", FontStyle::Italic);
 
         assert!(buf.width() > 0);
         assert_eq!(buf.height(), 17);
@@ -521,15 +1864,57 @@ mod tests {
         let font = fonts::get_font();
 
         let text = "Van Cortlandt Park-242 St";
-        let truncated = renderer.truncate_text(font, text, 80);
+        let truncated = renderer.truncate_text(&font, text, 80, FontStyle::Regular);
         assert!(
-            font.measure_text(&truncated, CHAR_SPACING, false) <= 80,
+            font.measure_text(&truncated, CHAR_SPACING, FontStyle::Regular) <= 80,
             "truncated text should fit within 80px"
         );
 
         // Short text should not be truncated
         let short = "42 St";
-        assert_eq!(renderer.truncate_text(font, short, 200), short);
+        assert_eq!(renderer.truncate_text(&font, short, 200, FontStyle::Regular), short);
+    }
+
+    #[test]
+    fn test_font_styles_per_element() {
+        let mut renderer = Renderer::new();
+        let font = fonts::get_font();
+        // Just too long for the row in regular, fits condensed
+        let dest = "Coney Island-Stillwell Av";
+        let snapshot = DisplaySnapshot {
+            trains: vec![make_train("F", dest, 5, false)],
+            alerts: Vec::new(),
+            fetched_at: 0.0,
+        };
+        let regular = font.measure_text(dest, CHAR_SPACING, FontStyle::Regular);
+        assert!(regular > 140);
+        assert!(font.measure_text(dest, CHAR_SPACING, FontStyle::Condensed) + 20 < regular);
+
+        let truncated = renderer.render_frame(&snapshot, 0, false, 0.0, false, None);
+        renderer.set_font_styles(FontStyles {
+            destination_overflow: FontStyle::Condensed,
+            ..FontStyles::default()
+        });
+        let condensed = renderer.render_frame(&snapshot, 0, false, 0.0, false, None);
+        assert_ne!(truncated.raw_pixels(), condensed.raw_pixels());
+
+        // Critical alerts switch style; the cache must notice
+        let alert = Alert {
+            text: "No trains".into(),
+            affected_routes: HashSet::new(),
+            priority: 1,
+            alert_id: "test".into(),
+            url: None,
+            is_ticker: false,
+        };
+        renderer.render_frame(&snapshot, 0, false, 0.0, true, Some(&alert));
+        let italic_width = renderer.last_alert_width;
+        renderer.set_font_styles(FontStyles {
+            critical_alert: FontStyle::Bold,
+            ..FontStyles::default()
+        });
+        renderer.render_frame(&snapshot, 0, false, 0.0, true, Some(&alert));
+        assert!(renderer.last_alert_width > italic_width);
     }
 
     #[test]
@@ -543,6 +1928,8 @@ mod tests {
             affected_routes: routes,
             priority: 1,
             alert_id: "test".into(),
+            url: None,
+            is_ticker: false,
         };
 
         let snapshot = DisplaySnapshot {
@@ -558,6 +1945,45 @@ mod tests {
         assert!(dist > 192, "scroll distance should exceed screen width");
     }
 
+    #[test]
+    fn test_subpixel_scroll() {
+        let mut renderer = Renderer::new();
+        let alert = Alert {
+            text: "Delays".into(),
+            affected_routes: HashSet::new(),
+            priority: 5,
+            alert_id: "test".into(),
+            url: None,
+            is_ticker: false,
+        };
+        let snapshot = DisplaySnapshot {
+            trains: vec![make_train("1", "Test", 0, false)],
+            alerts: vec![alert.clone()],
+            fetched_at: 0.0,
+        };
+        let bottom = |fb: &FrameBuffer| -> Vec<Rgb> {
+            ((ROW_HEIGHT - ALERT_ROW_RAISE) as usize..32)
+                .flat_map(|y| (0..DISPLAY_WIDTH).map(move |x| (x, y)))
+                .map(|(x, y)| fb.get_pixel(x, y))
+                .collect()
+        };
+
+        // Whole-pixel offsets render identically either way
+        let smooth = renderer.render_frame(&snapshot, 0, false, 100.0, true, Some(&alert));
+        renderer.set_smooth_scroll(false);
+        let stepped = renderer.render_frame(&snapshot, 0, false, 100.0, true, Some(&alert));
+        assert_eq!(bottom(&smooth), bottom(&stepped));
+
+        // Half-pixel offset: blended, dimmer pixels appear
+        renderer.set_smooth_scroll(true);
+        let half = renderer.render_frame(&snapshot, 0, false, 100.5, true, Some(&alert));
+        assert_ne!(bottom(&half), bottom(&smooth));
+        let orange = colors::COLOR_ORANGE;
+        assert!(bottom(&half)
+            .iter()
+            .any(|&p| p != COLOR_BLACK && p.0 < orange.0));
+    }
+
     #[test]
     fn test_render_frame_with_alert_scroll() {
         let mut renderer = Renderer::new();
@@ -569,6 +1995,8 @@ mod tests {
             affected_routes: routes,
             priority: 1,
             alert_id: "test".into(),
+            url: None,
+            is_ticker: false,
         };
 
         let snapshot = DisplaySnapshot {
@@ -597,6 +2025,465 @@ mod tests {
         assert!(differs, "different scroll offsets should produce different frames");
     }
 
+    #[test]
+    fn test_clock_text() {
+        let renderer = Renderer::new();
+        let mut train = make_train("1", "Test", 5, false);
+        assert_eq!(renderer.clock_text(&train), "--:--", "no timestamp");
+
+        train.arrival_timestamp = 1_700_000_000.0;
+        let text = renderer.clock_text(&train);
+        let (h, m) = text.split_once(':').unwrap();
+        assert!((1..=12).contains(&h.parse::<u32>().unwrap()));
+        assert_eq!(m.len(), 2);
+
+        train.minutes = EMPTY_TRAIN_SENTINEL;
+        assert_eq!(renderer.clock_text(&train), "--:--");
+    }
+
+    #[test]
+    fn test_arrival_format_changes_right_edge() {
+        let mut train = make_train("1", "Van Cortlandt Park", 5, false);
+        train.arrival_timestamp = 1_700_000_000.0;
+        let snapshot = DisplaySnapshot {
+            trains: vec![train],
+            alerts: Vec::new(),
+            fetched_at: 0.0,
+        };
+        let right_edge = |fb: &FrameBuffer| -> Vec<_> {
+            (0..16)
+                .flat_map(|y| (150..192).map(move |x| (x, y)))
+                .map(|(x, y)| fb.get_pixel(x, y))
+                .collect()
+        };
+
+        let mut renderer = Renderer::new();
+        let minutes = renderer.render_frame(&snapshot, 0, false, 0.0, false, None);
+
+        renderer.set_arrival_format(ArrivalFormat::Clock);
+        let clock = renderer.render_frame(&snapshot, 0, false, 0.0, false, None);
+        assert_ne!(right_edge(&minutes), right_edge(&clock));
+
+        renderer.set_arrival_format(ArrivalFormat::Alternate);
+        let first = renderer.render_frame(&snapshot, 0, false, 0.0, false, None);
+        renderer.set_alternate_phase(true);
+        let second = renderer.render_frame(&snapshot, 0, false, 0.0, false, None);
+        assert_eq!(right_edge(&first), right_edge(&minutes));
+        assert_eq!(right_edge(&second), right_edge(&clock));
+    }
+
+    #[test]
+    fn test_hybrid_format_switches_at_threshold() {
+        let now = 1_700_000_000.0;
+        let mut near = make_train("1", "Test", 20, false);
+        near.arrival_timestamp = now + 20.0 * 60.0;
+        let mut far = make_train("1", "Test", 37, false);
+        far.arrival_timestamp = now + 37.0 * 60.0;
+        let render = |renderer: &mut Renderer, train: &Train| {
+            let snapshot = DisplaySnapshot {
+                trains: vec![train.clone()],
+                alerts: Vec::new(),
+                fetched_at: 0.0,
+            };
+            renderer.render_frame(&snapshot, 0, false, 0.0, false, None)
+        };
+        // Top row only: the padded bottom row differs between formats
+        let top_row = DISPLAY_WIDTH * 3 * ROW_HEIGHT as usize;
+        let same = |a: &FrameBuffer, b: &FrameBuffer| {
+            a.raw_pixels()[..top_row] == b.raw_pixels()[..top_row]
+        };
+
+        let mut minutes = Renderer::new();
+        let mut clock = Renderer::new();
+        clock.set_arrival_format(ArrivalFormat::Clock);
+        let mut hybrid = Renderer::new();
+        hybrid.set_arrival_format(ArrivalFormat::Hybrid);
+        hybrid.set_clock_after_minutes(20);
+
+        // At the threshold: still a countdown
+        assert!(same(&render(&mut hybrid, &near), &render(&mut minutes, &near)));
+        // Beyond it: clock time
+        assert!(same(&render(&mut hybrid, &far), &render(&mut clock, &far)));
+    }
+
+    #[test]
+    fn test_due_string_replaces_zero_minutes() {
+        let snapshot = DisplaySnapshot {
+            trains: vec![make_train("1", "Test", 0, false)],
+            alerts: Vec::new(),
+            fetched_at: 0.0,
+        };
+        let mut default = Renderer::new();
+        let mut custom = Renderer::new();
+        let overrides = std::collections::BTreeMap::from([("due".to_string(), "Due".to_string())]);
+        custom.set_strings(Strings::new("en", &overrides).unwrap());
+
+        let a = default.render_frame(&snapshot, 0, false, 0.0, false, None);
+        let b = custom.render_frame(&snapshot, 0, false, 0.0, false, None);
+        assert_ne!(a.raw_pixels(), b.raw_pixels());
+    }
+
+    #[test]
+    fn test_arriving_text_options() {
+        let snapshot = DisplaySnapshot {
+            trains: vec![make_train("1", "Test", 0, false)],
+            alerts: Vec::new(),
+            fetched_at: 0.0,
+        };
+        let top_row = DISPLAY_WIDTH * 3 * ROW_HEIGHT as usize;
+        let render = |locale: &str, overrides: &[(&str, &str)], text: ArrivingText| {
+            let overrides = overrides.iter().map(|&(k, v)| (k.to_string(), v.to_string())).collect();
+            let mut renderer = Renderer::new();
+            renderer.set_strings(Strings::new(locale, &overrides).unwrap());
+            renderer.set_arriving_text(text);
+            renderer.render_frame(&snapshot, 0, false, 0.0, false, None).raw_pixels()[..top_row].to_vec()
+        };
+
+        let zero_min = render("en", &[], ArrivingText::Auto);
+        assert_eq!(render("es", &[], ArrivingText::Minutes), zero_min, "ignores the locale's due");
+        assert_ne!(render("es", &[], ArrivingText::Auto), zero_min);
+        assert_eq!(render("en", &[], ArrivingText::Due), render("en", &[("due", "Due")], ArrivingText::Auto));
+        assert_eq!(render("en", &[], ArrivingText::Now), render("en", &[("due", "Now")], ArrivingText::Auto));
+        // Arrival time long past: clamped to 0 seconds
+        assert_eq!(render("en", &[], ArrivingText::Seconds), render("en", &[("due", "0s")], ArrivingText::Auto));
+    }
+
+    #[test]
+    fn test_banner_replaces_cycling_row_but_not_alerts() {
+        let snapshot = DisplaySnapshot {
+            trains: vec![make_train("1", "Test", 5, false), make_train("6", "Test", 7, false)],
+            alerts: Vec::new(),
+            fetched_at: 0.0,
+        };
+        let alert = Alert {
+            text: "Delays".into(),
+            affected_routes: HashSet::new(),
+            priority: 1,
+            alert_id: "test".into(),
+            url: None,
+            is_ticker: false,
+        };
+        let bottom = |fb: &FrameBuffer| fb.raw_pixels()[DISPLAY_WIDTH * 3 * ROW_HEIGHT as usize..].to_vec();
+
+        let mut renderer = Renderer::new();
+        let cycling = renderer.render_frame(&snapshot, 0, false, 0.0, false, None);
+        let alerting = renderer.render_frame(&snapshot, 0, false, 40.0, true, Some(&alert));
+        renderer.set_banner(Some(Banner::LeaveNow { route: "6".into(), is_express: false }));
+        let banner = renderer.render_frame(&snapshot, 0, false, 0.0, false, None);
+        assert_ne!(bottom(&banner), bottom(&cycling));
+        assert!(bottom(&banner).iter().any(|&p| p != 0));
+        let still_alerting = renderer.render_frame(&snapshot, 0, false, 40.0, true, Some(&alert));
+        assert_eq!(bottom(&still_alerting), bottom(&alerting));
+    }
+
+    #[test]
+    fn test_by_direction_layout() {
+        let mut downtown = make_train("1", "South Ferry", 2, false);
+        downtown.direction = Direction::Downtown;
+        let snapshot = DisplaySnapshot {
+            trains: vec![downtown, make_train("6", "Pelham Bay", 4, false)],
+            alerts: Vec::new(),
+            fetched_at: 0.0,
+        };
+        let half = DISPLAY_WIDTH * 3 * ROW_HEIGHT as usize;
+        // Bottom row without its "N." row number
+        let bottom = |fb: &FrameBuffer| -> Vec<u8> {
+            fb.raw_pixels()[half..]
+                .chunks(DISPLAY_WIDTH * 3)
+                .flat_map(|row| row[16 * 3..].to_vec())
+                .collect()
+        };
+
+        // Same rows as pinning uptown on top with the downtown train cycling below
+        let mut renderer = Renderer::new();
+        renderer.set_top_row(vec![TopRowRule::Direction(Direction::Uptown)]);
+        let pinned = renderer.render_frame(&snapshot, 0, false, 0.0, false, None);
+        renderer.set_layout(Layout::ByDirection);
+        let by_direction = renderer.render_frame(&snapshot, 3, false, 0.0, false, None);
+        assert_eq!(by_direction.raw_pixels()[..half], pinned.raw_pixels()[..half]);
+        assert_eq!(bottom(&by_direction), bottom(&pinned));
+        assert_ne!(by_direction.raw_pixels()[half..], pinned.raw_pixels()[half..], "row number");
+    }
+
+    #[test]
+    fn test_connection_glyph_on_first_leg_rows() {
+        let mut first_leg = make_train("L", "Canarsie-Rockaway Pkwy", 4, false);
+        first_leg.arrival_timestamp = 1_700_000_240.0;
+        let snapshot = DisplaySnapshot {
+            trains: vec![first_leg.clone()],
+            alerts: Vec::new(),
+            fetched_at: 0.0,
+        };
+        let departs_at = first_leg.arrival_timestamp;
+        let connection = |status| Connection {
+            route: "L".into(),
+            departs_at,
+            ready_at: 0.0,
+            next_route: "4".into(),
+            next_departs_at: 0.0,
+            wait_secs: 0.0,
+            status,
+        };
+        let top = |fb: &FrameBuffer| fb.raw_pixels()[..DISPLAY_WIDTH * 3 * ROW_HEIGHT as usize].to_vec();
+
+        let mut renderer = Renderer::new();
+        let plain = renderer.render_frame(&snapshot, 0, false, 0.0, false, None);
+        renderer.set_connections(vec![connection(ConnectionStatus::Infeasible)]);
+        let infeasible = renderer.render_frame(&snapshot, 0, false, 0.0, false, None);
+        assert_ne!(top(&plain), top(&infeasible));
+        assert!((0..16).any(|y| (0..DISPLAY_WIDTH).any(|x| infeasible.get_pixel(x, y) == COLOR_RED)));
+
+        renderer.set_connections(vec![connection(ConnectionStatus::Feasible)]);
+        assert_ne!(top(&infeasible), top(&renderer.render_frame(&snapshot, 0, false, 0.0, false, None)));
+
+        // Other trains are left alone
+        first_leg.arrival_timestamp += 60.0;
+        let later = DisplaySnapshot { trains: vec![first_leg], ..snapshot };
+        renderer.set_connections(Vec::new());
+        let a = renderer.render_frame(&later, 0, false, 0.0, false, None);
+        renderer.set_connections(vec![connection(ConnectionStatus::Feasible)]);
+        assert_eq!(a.raw_pixels(), renderer.render_frame(&later, 0, false, 0.0, false, None).raw_pixels());
+    }
+
+    #[test]
+    fn test_delay_marker() {
+        let mut train = make_train("A", "Far Rockaway", 6, false);
+        train.delay_secs = Some(8 * 60);
+        let snapshot = DisplaySnapshot {
+            trains: vec![train],
+            alerts: Vec::new(),
+            fetched_at: 0.0,
+        };
+        let has_orange = |fb: &FrameBuffer| {
+            (0..ROW_HEIGHT as usize).any(|y| (0..DISPLAY_WIDTH).any(|x| fb.get_pixel(x, y) == COLOR_ORANGE))
+        };
+
+        let mut renderer = Renderer::new();
+        assert!(!has_orange(&renderer.render_frame(&snapshot, 0, false, 0.0, false, None)), "off by default");
+        renderer.set_delay_marker_minutes(5);
+        assert!(has_orange(&renderer.render_frame(&snapshot, 0, false, 0.0, false, None)));
+        renderer.set_delay_marker_minutes(10);
+        assert!(!has_orange(&renderer.render_frame(&snapshot, 0, false, 0.0, false, None)));
+    }
+
+    #[test]
+    fn test_color_by_route() {
+        let snapshot = DisplaySnapshot {
+            trains: vec![make_train("7", "Flushing", 4, false), make_train("7", "Flushing", 0, false)],
+            alerts: Vec::new(),
+            fetched_at: 0.0,
+        };
+        let purple = colors::route_color("7").unwrap();
+        // Time text at the right edge of the top row
+        let time_colors = |fb: &FrameBuffer| -> HashSet<Rgb> {
+            (0..ROW_HEIGHT as usize)
+                .flat_map(|y| (DISPLAY_WIDTH - 16..DISPLAY_WIDTH).map(move |x| (x, y)))
+                .map(|(x, y)| fb.get_pixel(x, y))
+                .filter(|&c| c != COLOR_BLACK)
+                .collect()
+        };
+
+        let mut renderer = Renderer::new();
+        let fb = renderer.render_frame(&snapshot, 0, false, 0.0, false, None);
+        assert_eq!(time_colors(&fb), HashSet::from([COLOR_GREEN]));
+        renderer.set_color_by_route(true);
+        let fb = renderer.render_frame(&snapshot, 0, false, 0.0, false, None);
+        assert_eq!(time_colors(&fb), HashSet::from([purple]));
+    }
+
+    #[test]
+    fn test_marquee_offset() {
+        let at = Duration::from_secs_f32;
+        assert_eq!(marquee_offset(40, at(0.0)), 0);
+        assert_eq!(marquee_offset(40, at(MARQUEE_PAUSE)), 0, "holds at the start");
+        assert_eq!(marquee_offset(40, at(MARQUEE_PAUSE + 1.0)), 20);
+        assert_eq!(marquee_offset(40, at(MARQUEE_PAUSE + 2.0)), 40);
+        assert_eq!(marquee_offset(40, at(2.0 * MARQUEE_PAUSE + 1.9)), 40, "holds at the end");
+        assert_eq!(marquee_offset(40, at(2.0 * MARQUEE_PAUSE + 2.1)), 0, "starts over");
+    }
+
+    #[test]
+    fn test_marquee_scrolls_long_destinations() {
+        let snapshot = DisplaySnapshot {
+            trains: vec![make_train("1", "Van Cortlandt Park-242 St Via Broadway", 4, false)],
+            alerts: Vec::new(),
+            fetched_at: 0.0,
+        };
+        let top_row = DISPLAY_WIDTH * 3 * ROW_HEIGHT as usize;
+        let mut renderer = Renderer::new();
+        renderer.set_marquee(true);
+        let mut frame_at = |secs: f32| {
+            renderer.set_clock(Duration::from_secs_f32(secs));
+            renderer.render_frame(&snapshot, 0, false, 0.0, false, None).raw_pixels()[..top_row].to_vec()
+        };
+
+        let start = frame_at(0.0);
+        assert_eq!(frame_at(MARQUEE_PAUSE / 2.0), start, "holds at the start");
+        assert_ne!(frame_at(MARQUEE_PAUSE + 1.0), start, "then scrolls");
+    }
+
+    #[test]
+    fn test_board_layout_flips_changed_rows() {
+        let mut snapshot = DisplaySnapshot {
+            trains: vec![make_train("1", "Van Cortlandt Park", 4, false), make_train("2", "Wakefield", 6, false)],
+            alerts: Vec::new(),
+            fetched_at: 0.0,
+        };
+        let mut renderer = Renderer::new();
+        renderer.set_layout(Layout::Board);
+        renderer.set_size(DISPLAY_WIDTH, 64);
+        let row = |fb: &FrameBuffer, n: usize| {
+            let row_bytes = DISPLAY_WIDTH * 3 * LIST_ROW_HEIGHT as usize;
+            fb.raw_pixels()[n * row_bytes..(n + 1) * row_bytes].to_vec()
+        };
+
+        let before = renderer.render_frame(&snapshot, 0, false, 0.0, false, None);
+        assert!(row(&before, 0).iter().any(|&b| b > 0), "heading");
+        assert!(row(&before, 2).iter().any(|&b| b > 0), "second train");
+        assert!(row(&before, 3).iter().all(|&b| b == 0), "no third train");
+
+        snapshot.trains[1].destination = "Flatbush Av".to_string();
+        renderer.render_frame(&snapshot, 0, false, 0.0, false, None);
+        renderer.set_clock(Duration::from_secs_f32(BOARD_FLIP_SECS / 4.0));
+        let folding = renderer.render_frame(&snapshot, 0, false, 0.0, false, None);
+        assert_eq!(row(&folding, 1), row(&before, 1), "unchanged row stays put");
+        assert_ne!(row(&folding, 2), row(&before, 2), "changed row flips");
+
+        renderer.set_clock(Duration::from_secs_f32(BOARD_FLIP_SECS));
+        let after = renderer.render_frame(&snapshot, 0, false, 0.0, false, None);
+        let mut fresh = Renderer::new();
+        fresh.set_layout(Layout::Board);
+        fresh.set_size(DISPLAY_WIDTH, 64);
+        assert_eq!(row(&after, 2), row(&fresh.render_frame(&snapshot, 0, false, 0.0, false, None), 2), "flip done");
+    }
+
+    #[test]
+    fn test_truncate_small() {
+        let fitted = Renderer::truncate_small("Van Cortlandt Park", 40);
+        assert!(Renderer::measure_small(&fitted) <= 40);
+        assert!("Van Cortlandt Park".starts_with(&fitted) && !fitted.ends_with(' '));
+        assert_eq!(Renderer::truncate_small("Bay", 40), "Bay");
+    }
+
+    #[test]
+    fn test_list_layout() {
+        let snapshot = DisplaySnapshot {
+            trains: vec![
+                make_train("1", "Van Cortlandt Park", 2, false),
+                make_train("2", "Wakefield", 5, true),
+                make_train("3", "Harlem", 8, false),
+                make_train("1", "Van Cortlandt Park", 11, false),
+                make_train("2", "Wakefield", 14, true),
+            ],
+            alerts: Vec::new(),
+            fetched_at: 0.0,
+        };
+        let lit_rows = |fb: &FrameBuffer| -> Vec<usize> {
+            (0..fb.height() / LIST_ROW_HEIGHT as usize)
+                .filter(|&row| {
+                    (row * 8..row * 8 + 8).any(|y| (LIST_DEST_X as usize..fb.width()).any(|x| fb.get_pixel(x, y) != COLOR_BLACK))
+                })
+                .collect()
+        };
+
+        let mut renderer = Renderer::new();
+        renderer.set_layout(Layout::List);
+        let fb = renderer.render_frame(&snapshot, 0, false, 0.0, false, None);
+        assert_eq!(lit_rows(&fb), vec![0, 1, 2, 3], "four trains at once");
+        let red = colors::route_color("1").unwrap();
+        assert!((0..8).any(|y| (0..3).any(|x| fb.get_pixel(x, y) == red)), "route label in its color");
+
+        // Two trains left: no placeholder rows
+        let two = DisplaySnapshot { trains: snapshot.trains[..2].to_vec(), ..snapshot.clone() };
+        assert_eq!(lit_rows(&renderer.render_frame(&two, 0, false, 0.0, false, None)), vec![0, 1]);
+
+        // A banner takes the bottom row; the list shrinks above it
+        renderer.set_banner(Some(Banner::CheckConfig));
+        let fb = renderer.render_frame(&snapshot, 0, false, 0.0, false, None);
+        let banner_row = (16..32).any(|y| (0..DISPLAY_WIDTH).any(|x| fb.get_pixel(x, y) != COLOR_BLACK));
+        assert!(banner_row);
+        assert_eq!(lit_rows(&fb)[..2], [0, 1]);
+    }
+
+    #[test]
+    fn test_cycle_slide_transition() {
+        let snapshot = DisplaySnapshot {
+            trains: vec![
+                make_train("1", "Van Cortlandt Park", 2, false),
+                make_train("2", "Wakefield", 5, false),
+                make_train("3", "Harlem", 9, false),
+            ],
+            alerts: Vec::new(),
+            fetched_at: 0.0,
+        };
+        let mut renderer = Renderer::new();
+        let mut frame = |transition| {
+            renderer.set_cycle_transition(transition);
+            renderer.render_frame(&snapshot, 1, false, 0.0, false, None).raw_pixels().to_vec()
+        };
+        let previous = {
+            let mut renderer = Renderer::new();
+            renderer.render_frame(&snapshot, 0, false, 0.0, false, None).raw_pixels().to_vec()
+        };
+        let current = frame(None);
+
+        assert_eq!(frame(Some((0, 0.0))), previous, "starts on the outgoing train");
+        assert_eq!(frame(Some((0, 1.0))), current);
+        let halfway = frame(Some((0, 0.5)));
+        assert_ne!(halfway, previous);
+        assert_ne!(halfway, current);
+        let top = DISPLAY_WIDTH * 3 * ROW_HEIGHT as usize;
+        assert_eq!(halfway[..top], current[..top], "top row stays put");
+    }
+
+    #[test]
+    fn test_do_not_disturb_indicator() {
+        let snapshot = DisplaySnapshot {
+            trains: vec![make_train("A", "Far Rockaway", 6, false)],
+            alerts: Vec::new(),
+            fetched_at: 0.0,
+        };
+        let corner = |fb: &FrameBuffer| fb.get_pixel(DISPLAY_WIDTH - 1, DISPLAY_HEIGHT - 1);
+
+        let mut renderer = Renderer::new();
+        assert_eq!(corner(&renderer.render_frame(&snapshot, 0, false, 0.0, false, None)), (0, 0, 0));
+        renderer.set_do_not_disturb(true);
+        assert_eq!(corner(&renderer.render_frame(&snapshot, 0, false, 0.0, false, None)), COLOR_ORANGE);
+    }
+
+    #[test]
+    fn test_larger_panel_geometry() {
+        let snapshot = DisplaySnapshot {
+            trains: vec![
+                make_train("1", "Van Cortlandt Park", 2, false),
+                make_train("2", "Wakefield", 5, false),
+                make_train("3", "Harlem", 9, false),
+                make_train("4", "Woodlawn", 12, false),
+            ],
+            alerts: Vec::new(),
+            fetched_at: 0.0,
+        };
+        let mut renderer = Renderer::new();
+        renderer.set_size(256, 64);
+        let fb = renderer.render_frame(&snapshot, 0, false, 0.0, false, None);
+        assert_eq!((fb.width(), fb.height()), (256, 64));
+
+        let lit = |rows: std::ops::Range<usize>, columns: std::ops::Range<usize>| {
+            rows.clone().any(|y| columns.clone().any(|x| fb.get_pixel(x, y) != COLOR_BLACK))
+        };
+        for row in 0..4 {
+            let rows = row * ROW_HEIGHT as usize..(row + 1) * ROW_HEIGHT as usize;
+            assert!(lit(rows.clone(), 0..64), "row {} drawn", row);
+            assert!(lit(rows, 224..256), "row {} time at the right edge", row);
+        }
+
+        // The cycling bottom row starts after the trains shown above it
+        let mut bottom_of = |cycle_index| {
+            renderer.render_frame(&snapshot, cycle_index, false, 0.0, false, None).raw_pixels()[256 * 3 * 48..].to_vec()
+        };
+        assert_ne!(bottom_of(0), bottom_of(1));
+    }
+
     #[test]
     #[ignore]
     fn test_render_ppm_output() {
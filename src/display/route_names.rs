@@ -0,0 +1,98 @@
+//! Route names written as plain text in alerts ("4 and 5 trains").
+//!
+//! MTA alerts mark most routes up as `[4]`, but not all. Route letters and
+//! digits directly before "train(s)", alone or as a list, are bracketed here
+//! so the alert renderer draws them as bullets. Cases that read as plain
+//! English are left alone: "A train" opening a sentence, and counts such as
+//! "every 2 trains".
+
+use std::borrow::Cow;
+use std::sync::OnceLock;
+
+use regex::{Captures, Regex};
+
+/// Single-character routes as they're written in alert text.
+const ROUTE: &str = "[1-7ABCDEFGJLMNQRSWZ]";
+
+/// Words before a lone number that make it a count, not a route.
+const COUNT_WORDS: &[&str] = &[
+    "every", "of", "than", "only", "about", "approximately", "first", "last", "next", "few", "to",
+    "up",
+];
+
+fn patterns() -> &'static (Regex, Regex) {
+    static PATTERNS: OnceLock<(Regex, Regex)> = OnceLock::new();
+    PATTERNS.get_or_init(|| {
+        let sep = r"(?:\s*,\s*(?:and\s+|or\s+)?|\s+(?:and|or|&)\s+|\s*/\s*)";
+        let list = Regex::new(&format!(r"\b({ROUTE}(?:{sep}{ROUTE})*)(\s+trains?)\b")).unwrap();
+        let token = Regex::new(&format!(r"\b({ROUTE})\b")).unwrap();
+        (list, token)
+    })
+}
+
+/// Whether a single-route match reads as ordinary English instead.
+fn is_plain_english(before: &str, route: &str, trains: &str) -> bool {
+    let before = before.trim_end();
+    // "A train is delayed." — an article, unless plural ("A trains are...")
+    if route == "A" && trains.trim() == "train" {
+        return before.is_empty() || before.ends_with(['.', '!', '?', ':']);
+    }
+    // "every 2 trains"
+    if route.chars().all(|c| c.is_ascii_digit()) {
+        let word = before
+            .rsplit(|c: char| !c.is_alphanumeric())
+            .next()
+            .unwrap_or("")
+            .to_lowercase();
+        return COUNT_WORDS.contains(&word.as_str());
+    }
+    false
+}
+
+/// `text` with plain route names before "train(s)" bracketed as `[route]`.
+pub fn mark_route_names(text: &str) -> Cow<'_, str> {
+    let (list, token) = patterns();
+    list.replace_all(text, |caps: &Captures| {
+        let whole = caps.get(0).unwrap();
+        let routes = &caps[1];
+        let trains = &caps[2];
+        let before = &text[..whole.start()];
+        if before.ends_with('[') || is_plain_english(before, routes, trains) {
+            return whole.as_str().to_string();
+        }
+        format!("{}{}", token.replace_all(routes, "[$1]"), trains)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_marks_route_lists() {
+        assert_eq!(mark_route_names("No 1 trains between 96 St and 137 St"), "No [1] trains between 96 St and 137 St");
+        assert_eq!(mark_route_names("A and C trains run local"), "[A] and [C] trains run local");
+        assert_eq!(mark_route_names("4, 5 and 6 trains are delayed"), "[4], [5] and [6] trains are delayed");
+        assert_eq!(mark_route_names("Take the N/R train instead."), "Take the [N]/[R] train instead.");
+        assert_eq!(mark_route_names("Use nearby [2] and 3 trains"), "Use nearby [2] and [3] trains");
+    }
+
+    #[test]
+    fn test_leaves_plain_english_alone() {
+        for text in [
+            "A train is stuck near 59 St.",
+            "Expect delays. A train has mechanical problems",
+            "Every 2 trains skip Cortlandt St",
+            "Only 3 trains per hour",
+            "Up to 10 trains are affected",
+            "[1] trains are delayed",
+            "Trains are running with delays",
+            "Board the a train",
+        ] {
+            assert_eq!(mark_route_names(text), text);
+        }
+        // Plural or mid-sentence "A" is the route
+        assert_eq!(mark_route_names("A trains run express"), "[A] trains run express");
+        assert_eq!(mark_route_names("Take the A train"), "Take the [A] train");
+    }
+}
@@ -0,0 +1,99 @@
+//! `terminal` display backend: the active area drawn on stdout with ANSI
+//! truecolor half blocks (two LED rows per text line), for watching the sign
+//! over SSH. Needs a terminal at least `active_width` columns wide.
+
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+use super::framebuffer::FrameBuffer;
+use super::matrix::{DisplayTarget, Viewport};
+
+/// Redraw at most this often; a terminal can't keep up with 60fps.
+const MIN_REDRAW: Duration = Duration::from_millis(100);
+
+pub struct TerminalDisplay {
+    brightness: u8,
+    viewport: Viewport,
+    last_drawn: Option<(Vec<u8>, Instant)>,
+}
+
+impl TerminalDisplay {
+    pub fn new(brightness: u8, viewport: Viewport) -> Self {
+        tracing::info!(
+            "Terminal display initialized ({}x{}, brightness={})",
+            viewport.width, viewport.height, brightness
+        );
+        TerminalDisplay { brightness, viewport, last_drawn: None }
+    }
+}
+
+/// ANSI text for `pixels` (`width` columns, row-major RGB): cursor home,
+/// then one line per pair of rows, upper pixel as foreground of '▀'.
+fn ansi_frame(pixels: &[u8], width: usize, brightness: u8) -> String {
+    let dim = |c: u8| c as u32 * brightness.min(100) as u32 / 100;
+    let height = pixels.len() / (width * 3).max(1);
+    let at = |x: usize, y: usize| {
+        let i = (y * width + x) * 3;
+        match pixels.get(i..i + 3) {
+            Some(p) if y < height => (dim(p[0]), dim(p[1]), dim(p[2])),
+            _ => (0, 0, 0),
+        }
+    };
+
+    let mut out = String::from("\x1b[H");
+    for y in (0..height).step_by(2) {
+        for x in 0..width {
+            let (top, bottom) = (at(x, y), at(x, y + 1));
+            out.push_str(&format!(
+                "\x1b[38;2;{};{};{}m\x1b[48;2;{};{};{}m\u{2580}",
+                top.0, top.1, top.2, bottom.0, bottom.1, bottom.2
+            ));
+        }
+        out.push_str("\x1b[0m\n");
+    }
+    out
+}
+
+impl DisplayTarget for TerminalDisplay {
+    fn swap(&mut self, frame: &FrameBuffer) {
+        let pixels = self.viewport.active_pixels(frame);
+        let unchanged = self.last_drawn.as_ref().is_some_and(|(last, at)| {
+            at.elapsed() < MIN_REDRAW || *last == pixels.as_ref()
+        });
+        if unchanged {
+            return;
+        }
+        let width = self.viewport.width.min(frame.width());
+        let text = ansi_frame(&pixels, width, self.brightness);
+        let mut stdout = std::io::stdout().lock();
+        let _ = stdout.write_all(text.as_bytes()).and_then(|_| stdout.flush());
+        self.last_drawn = Some((pixels.into_owned(), Instant::now()));
+    }
+
+    fn set_brightness(&mut self, brightness: u8) {
+        self.brightness = brightness;
+        self.last_drawn = None;
+    }
+
+    fn set_viewport(&mut self, viewport: Viewport) {
+        self.viewport = viewport;
+        self.last_drawn = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ansi_frame_pairs_rows() {
+        // 1x3: red over green, then blue over nothing
+        let pixels = [200, 0, 0, 0, 200, 0, 0, 0, 200];
+        let text = ansi_frame(&pixels, 1, 50);
+        assert_eq!(
+            text,
+            "\x1b[H\x1b[38;2;100;0;0m\x1b[48;2;0;100;0m\u{2580}\x1b[0m\n\
+             \x1b[38;2;0;0;100m\x1b[48;2;0;0;0m\u{2580}\x1b[0m\n"
+        );
+    }
+}
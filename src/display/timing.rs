@@ -0,0 +1,106 @@
+//! Frame timing over sliding windows, so a rendering change that eats into
+//! the frame budget shows up on real hardware (see `/api/display/timing`).
+//!
+//! The render loop records each frame's work time (render + swap) and the
+//! gap since the previous frame started; a stall is a gap well past the
+//! frame interval, e.g. a blocked swap or a descheduled render thread.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+/// Frame work times over the last `window`.
+pub struct FrameTimings {
+    window: Duration,
+    /// (frame start, work µs, µs since the previous frame started).
+    samples: VecDeque<(Instant, u32, u32)>,
+    last_start: Option<Instant>,
+}
+
+/// Percentiles for one window, in microseconds.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TimingReport {
+    pub window_secs: u64,
+    pub frames: usize,
+    pub p50_us: u32,
+    pub p95_us: u32,
+    pub p99_us: u32,
+    pub max_us: u32,
+    /// Longest time between two frame starts.
+    pub longest_stall_us: u32,
+}
+
+impl FrameTimings {
+    pub fn new(window: Duration) -> Self {
+        FrameTimings {
+            window,
+            samples: VecDeque::new(),
+            last_start: None,
+        }
+    }
+
+    /// Record a frame that started at `start` and took `work`.
+    pub fn record(&mut self, start: Instant, work: Duration) {
+        let gap = self.last_start.map_or(0, |last| micros(start.saturating_duration_since(last)));
+        self.last_start = Some(start);
+        self.samples.push_back((start, micros(work), gap));
+        while self
+            .samples
+            .front()
+            .is_some_and(|&(at, _, _)| start.saturating_duration_since(at) > self.window)
+        {
+            self.samples.pop_front();
+        }
+    }
+
+    /// Percentiles over the window (nearest rank).
+    pub fn report(&self) -> TimingReport {
+        let mut work: Vec<u32> = self.samples.iter().map(|&(_, w, _)| w).collect();
+        work.sort_unstable();
+        let percentile = |p: usize| match work.len() {
+            0 => 0,
+            n => work[((n * p).div_ceil(100)).clamp(1, n) - 1],
+        };
+        TimingReport {
+            window_secs: self.window.as_secs(),
+            frames: work.len(),
+            p50_us: percentile(50),
+            p95_us: percentile(95),
+            p99_us: percentile(99),
+            max_us: work.last().copied().unwrap_or(0),
+            longest_stall_us: self.samples.iter().map(|&(_, _, gap)| gap).max().unwrap_or(0),
+        }
+    }
+}
+
+fn micros(d: Duration) -> u32 {
+    d.as_micros().min(u32::MAX as u128) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentiles_and_window() {
+        let mut timings = FrameTimings::new(Duration::from_secs(1));
+        let t0 = Instant::now();
+        let ms = Duration::from_millis;
+        // 100 frames 10ms apart taking 1..=100ms of work
+        for i in 0..100u64 {
+            timings.record(t0 + ms(10 * i), ms(i + 1));
+        }
+        let report = timings.report();
+        assert_eq!(report.frames, 100);
+        assert_eq!((report.p50_us, report.p95_us, report.p99_us), (50_000, 95_000, 99_000));
+        assert_eq!(report.max_us, 100_000);
+        assert_eq!(report.longest_stall_us, 10_000);
+
+        // A late frame: stall recorded, older frames age out of the window
+        timings.record(t0 + ms(1500), ms(2));
+        let report = timings.report();
+        assert_eq!(report.longest_stall_us, 510_000);
+        assert_eq!(report.frames, 51);
+    }
+}
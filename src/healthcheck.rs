@@ -0,0 +1,133 @@
+//! `subway-sign healthcheck [URL | --port PORT | --instance NAME |
+//! --config-dir DIR]`: probe a running sign and exit 0/1.
+//!
+//! Lets Docker `HEALTHCHECK` and Kubernetes exec probes use the binary
+//! itself instead of curl, which scratch images don't have.
+
+use std::path::Path;
+use std::time::Duration;
+
+use crate::config::{self, DEFAULT_WEB_PORT};
+use crate::instance::Instance;
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Run the probe against the sign `args` pick and return the process exit code.
+pub async fn run(args: &[String]) -> i32 {
+    let url = match target_url(args) {
+        Ok(url) => url,
+        Err(e) => {
+            eprintln!("subway-sign healthcheck: {}", e);
+            return 2;
+        }
+    };
+    match probe(&url).await {
+        Ok(()) => {
+            println!("healthy");
+            0
+        }
+        Err(reason) => {
+            eprintln!("unhealthy: {}", reason);
+            1
+        }
+    }
+}
+
+async fn probe(url: &str) -> Result<(), String> {
+    let client = reqwest::Client::builder()
+        .timeout(TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let response = client
+        .get(url)
+        .send()
+        .await
+        .map_err(|e| format!("request failed: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("HTTP {}", response.status()));
+    }
+    let body: serde_json::Value = response
+        .json()
+        .await
+        .map_err(|e| format!("bad response: {}", e))?;
+    evaluate(&body)
+}
+
+/// The healthz URL to probe: given outright, on `--port`, or on the
+/// `web.port` of the config the daemon would use (the default sign's, or
+/// an instance's).
+fn target_url(args: &[String]) -> Result<String, String> {
+    let port = match args {
+        [url] if !url.starts_with("--") => return Ok(url.clone()),
+        [flag, port] if flag == "--port" => port
+            .parse::<u16>()
+            .map_err(|_| format!("invalid port '{}'", port))?,
+        _ => {
+            let config_path = match Instance::from_args(args)? {
+                Some(instance) => instance.dir.join("config.json"),
+                None => config::find_config_path(),
+            };
+            configured_port(&config_path)
+        }
+    };
+    Ok(format!("http://127.0.0.1:{}/api/healthz", port))
+}
+
+/// `web.port` from the config file, or the default when it's unset or
+/// can't be read. Only that key is read, so the rest needn't validate.
+fn configured_port(path: &Path) -> u16 {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|json| serde_json::from_str::<serde_json::Value>(&json).ok())
+        .and_then(|config| config["web"]["port"].as_u64())
+        .and_then(|port| u16::try_from(port).ok())
+        .unwrap_or(DEFAULT_WEB_PORT)
+}
+
+/// Healthy iff `/api/healthz` reports `ok`.
+fn evaluate(body: &serde_json::Value) -> Result<(), String> {
+    if body["ok"].as_bool() == Some(true) {
+        return Ok(());
+    }
+    Err(body["reason"]
+        .as_str()
+        .unwrap_or("healthz did not report ok")
+        .to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_evaluate() {
+        assert!(evaluate(&json!({"ok": true, "reason": null})).is_ok());
+        assert_eq!(
+            evaluate(&json!({"ok": false, "reason": "render stale 30s"})).unwrap_err(),
+            "render stale 30s"
+        );
+        assert!(evaluate(&json!({})).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_is_unhealthy() {
+        assert_eq!(run(&["http://127.0.0.1:9/api/healthz".into()]).await, 1);
+    }
+
+    #[test]
+    fn test_target_url() {
+        let args = |a: &[&str]| a.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(target_url(&args(&["http://sign:8080/api/healthz"])).unwrap(), "http://sign:8080/api/healthz");
+        assert_eq!(target_url(&args(&["--port", "5002"])).unwrap(), "http://127.0.0.1:5002/api/healthz");
+        assert!(target_url(&args(&["--port", "x"])).is_err());
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("config.json"), r#"{"web": {"port": 5003}}"#).unwrap();
+        let config_dir = dir.path().to_string_lossy();
+        assert_eq!(
+            target_url(&args(&["--config-dir", &config_dir])).unwrap(),
+            "http://127.0.0.1:5003/api/healthz"
+        );
+    }
+}
@@ -0,0 +1,85 @@
+//! Several signs from one binary: `subway-sign --instance NAME` (or
+//! `--config-dir DIR`) runs an instance out of its own directory, so its
+//! `config.json` and everything stored next to it (analytics, screenshots,
+//! uploads, restart handoff, crash reports) stay separate from the others.
+//! Each instance needs its own `web.port`.
+
+use std::path::PathBuf;
+
+/// Where instances live when picked by name.
+pub const INSTANCES_DIR: &str = "instances";
+
+/// A named sign instance and its directory.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Instance {
+    pub name: String,
+    pub dir: PathBuf,
+}
+
+impl Instance {
+    /// Parse the daemon's arguments: nothing (the default, unnamed sign),
+    /// `--instance NAME` (`instances/NAME`) or `--config-dir DIR` (named
+    /// after the directory).
+    pub fn from_args(args: &[String]) -> Result<Option<Instance>, String> {
+        let (flag, value) = match args {
+            [] => return Ok(None),
+            [flag, value] => (flag.as_str(), value.as_str()),
+            _ => return Err(format!("expected --instance NAME or --config-dir DIR, got '{}'", args.join(" "))),
+        };
+        match flag {
+            "--instance" => {
+                let valid = !value.is_empty()
+                    && value.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+                if !valid {
+                    return Err(format!("instance name must be letters, digits, '-' or '_', got '{}'", value));
+                }
+                Ok(Some(Instance {
+                    name: value.to_string(),
+                    dir: PathBuf::from(INSTANCES_DIR).join(value),
+                }))
+            }
+            "--config-dir" => {
+                let dir = PathBuf::from(value);
+                let name = std::fs::canonicalize(&dir)
+                    .unwrap_or_else(|_| dir.clone())
+                    .file_name()
+                    .map(|n| n.to_string_lossy().into_owned())
+                    .ok_or_else(|| format!("can't name an instance after '{}'", value))?;
+                Ok(Some(Instance { name, dir }))
+            }
+            _ => Err(format!("unknown option '{}'", flag)),
+        }
+    }
+
+    /// Make the instance directory the working directory, remembering
+    /// where the process started so a re-exec restart finds it again.
+    pub fn enter(&self) -> Result<(), String> {
+        if let Ok(launch) = std::env::current_dir() {
+            let _ = crate::lifecycle::LAUNCH_DIR.set(launch);
+        }
+        std::env::set_current_dir(&self.dir)
+            .map_err(|e| format!("can't enter instance directory {}: {}", self.dir.display(), e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Option<Instance>, String> {
+        Instance::from_args(&args.iter().map(|a| a.to_string()).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn test_instance_args() {
+        assert_eq!(parse(&[]), Ok(None));
+        assert_eq!(
+            parse(&["--instance", "uptown"]),
+            Ok(Some(Instance { name: "uptown".into(), dir: PathBuf::from("instances/uptown") }))
+        );
+        assert_eq!(parse(&["--config-dir", "/etc/signs/downtown"]).unwrap().unwrap().name, "downtown");
+        assert!(parse(&["--instance", "../etc"]).is_err());
+        assert!(parse(&["--instance"]).is_err());
+        assert!(parse(&["--verbose", "x"]).is_err());
+    }
+}
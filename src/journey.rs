@@ -0,0 +1,117 @@
+//! "Leave now" planning: is this the moment to walk out the door?
+//!
+//! With a walk time per station, a train is catchable if it reaches the
+//! platform after you would. The prompt shows only while leaving right now
+//! is necessary as well as sufficient: the train is catchable but arrives
+//! within `window_minutes` of you, so waiting any longer would miss it.
+
+use serde::Serialize;
+
+use crate::config::JourneyConfig;
+use crate::models::Train;
+
+/// The train to leave for.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LeaveNow {
+    pub route: String,
+    pub is_express: bool,
+    pub stop_id: String,
+    /// Seconds between reaching the platform and the train arriving.
+    pub spare_secs: f64,
+}
+
+/// Walk time in seconds to the station serving `stop_id`, if configured.
+fn walk_secs(journey: &JourneyConfig, stop_id: &str) -> Option<f64> {
+    let base_id = stop_id.trim_end_matches(['N', 'S']);
+    journey.walk_minutes.get(base_id).map(|&m| m as f64 * 60.0)
+}
+
+/// Preference rank of a route (lower is better); `None` if it doesn't
+/// go where the journey is headed.
+fn route_rank(journey: &JourneyConfig, route: &str) -> Option<usize> {
+    if journey.routes.is_empty() {
+        return Some(0);
+    }
+    journey.routes.iter().position(|r| r.eq_ignore_ascii_case(route))
+}
+
+/// Train to leave for at unix time `now`: the most preferred route among
+/// trains you can just make, soonest first. `None` when nothing needs you
+/// to leave yet (or the journey isn't configured).
+pub fn leave_now(trains: &[Train], journey: &JourneyConfig, now: f64) -> Option<LeaveNow> {
+    let window = journey.window_minutes as f64 * 60.0;
+    trains
+        .iter()
+        .filter(|t| journey.direction.is_none_or(|d| t.direction == d))
+        .filter_map(|t| {
+            let rank = route_rank(journey, &t.route)?;
+            let spare = t.arrival_timestamp - now - walk_secs(journey, &t.stop_id)?;
+            (0.0..=window).contains(&spare).then_some((rank, spare, t))
+        })
+        .min_by(|a, b| a.0.cmp(&b.0).then(a.2.arrival_timestamp.total_cmp(&b.2.arrival_timestamp)))
+        .map(|(_, spare, t)| LeaveNow {
+            route: t.route.clone(),
+            is_express: t.is_express,
+            stop_id: t.stop_id.clone(),
+            spare_secs: spare,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Direction;
+    use std::collections::BTreeMap;
+
+    const NOW: f64 = 1_700_000_000.0;
+
+    fn train(route: &str, stop_id: &str, secs_away: f64) -> Train {
+        Train {
+            route: route.into(),
+            destination: "Test".into(),
+            minutes: (secs_away / 60.0) as i32,
+            is_express: false,
+            arrival_timestamp: NOW + secs_away,
+            direction: if stop_id.ends_with('S') { Direction::Downtown } else { Direction::Uptown },
+            stop_id: stop_id.into(),
+            delay_secs: None,
+            trip_id: String::new(),
+        }
+    }
+
+    fn journey(routes: &[&str]) -> JourneyConfig {
+        JourneyConfig {
+            walk_minutes: BTreeMap::from([("635".to_string(), 5), ("R20".to_string(), 8)]),
+            routes: routes.iter().map(|r| r.to_string()).collect(),
+            ..JourneyConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_only_prompts_inside_window() {
+        let j = journey(&[]);
+        // 5 min walk, 2 min window: trains 5-7 min out prompt
+        assert!(leave_now(&[train("6", "635S", 4.0 * 60.0)], &j, NOW).is_none(), "too late");
+        assert!(leave_now(&[train("6", "635S", 9.0 * 60.0)], &j, NOW).is_none(), "not yet");
+        let go = leave_now(&[train("6", "635S", 6.0 * 60.0)], &j, NOW).unwrap();
+        assert_eq!((go.route.as_str(), go.spare_secs), ("6", 60.0));
+        // Unconfigured station never prompts
+        assert!(leave_now(&[train("1", "127S", 6.0 * 60.0)], &j, NOW).is_none());
+        assert!(leave_now(&[train("6", "635S", 6.0 * 60.0)], &JourneyConfig::default(), NOW).is_none());
+    }
+
+    #[test]
+    fn test_prefers_routes_in_order() {
+        let trains = [
+            train("4", "635S", 5.5 * 60.0),
+            train("6", "635S", 6.5 * 60.0),
+            train("N", "R20S", 9.0 * 60.0),
+        ];
+        assert_eq!(leave_now(&trains, &journey(&[]), NOW).unwrap().route, "4");
+        assert_eq!(leave_now(&trains, &journey(&["6", "4"]), NOW).unwrap().route, "6");
+        assert_eq!(leave_now(&trains, &journey(&["n"]), NOW).unwrap().stop_id, "R20S");
+
+        let uptown = JourneyConfig { direction: Some(Direction::Uptown), ..journey(&[]) };
+        assert!(leave_now(&trains, &uptown, NOW).is_none());
+    }
+}
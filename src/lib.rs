@@ -0,0 +1,117 @@
+//! NYC subway arrival sign.
+//!
+//! The sign core is usable on its own, e.g. for an e-paper board or a
+//! terminal app:
+//!
+//! - [`mta::client::MtaClient`] fetches arrivals and alerts from the MTA's
+//!   GTFS-RT feeds; [`mta::stations`] resolves station names to stop IDs.
+//! - [`Renderer`] draws a [`DisplaySnapshot`] into a 192x32 [`FrameBuffer`];
+//!   [`display::export`] turns frames into PNGs.
+//! - [`selection`], [`trip`] and [`journey`] pick which trains to show and
+//!   what to say about them.
+//! - [`config::Config`] is the sign's JSON config.
+//!
+//! ```
+//! use subway_sign::{Direction, DisplaySnapshot, Renderer, Train};
+//!
+//! let snapshot = DisplaySnapshot {
+//!     trains: vec![Train {
+//!         route: "6".into(),
+//!         destination: "Brooklyn Bridge-City Hall".into(),
+//!         minutes: 3,
+//!         is_express: false,
+//!         arrival_timestamp: 0.0,
+//!         direction: Direction::Downtown,
+//!         stop_id: "635S".into(),
+//!         delay_secs: None,
+//!         trip_id: String::new(),
+//!     }],
+//!     alerts: Vec::new(),
+//!     fetched_at: 0.0,
+//! };
+//! let frame = Renderer::new().render_frame(&snapshot, 0, false, 0.0, false, None);
+//! let png = subway_sign::display::export::framebuffer_to_png(&frame, 4).unwrap();
+//! # assert!(!png.is_empty());
+//! ```
+//!
+//! ```no_run
+//! # async fn arrivals() -> Result<(), String> {
+//! use std::collections::HashSet;
+//! use subway_sign::mta::{client::MtaClient, stations};
+//!
+//! let stops = stations::get_stop_ids_for_station("Grand Central-42 St");
+//! let routes: HashSet<String> = ["4", "5", "6"].iter().map(|r| r.to_string()).collect();
+//! let mut client = MtaClient::new()?;
+//! for train in client.fetch_trains(&stops, &routes, 5).await {
+//!     println!("{} {} {} min", train.route, train.destination, train.minutes);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The rest — web UI, hardware, background tasks — is the daemon run by the
+//! `subway-sign` binary (`daemon::run`), built with the default `daemon`
+//! feature.
+
+// Sign core
+pub mod config;
+pub mod display;
+pub mod journey;
+pub mod models;
+pub mod mta;
+pub mod selection;
+pub mod timezone;
+pub mod trip;
+
+// Daemon
+#[cfg(feature = "daemon")]
+pub mod analytics;
+#[cfg(feature = "daemon")]
+pub mod buttons;
+#[cfg(feature = "daemon")]
+pub mod calendar;
+#[cfg(feature = "daemon")]
+pub mod crash;
+#[cfg(feature = "daemon")]
+pub mod daemon;
+#[cfg(feature = "daemon")]
+pub mod healthcheck;
+#[cfg(feature = "daemon")]
+pub mod instance;
+#[cfg(feature = "daemon")]
+pub mod lifecycle;
+#[cfg(feature = "daemon")]
+pub mod logging;
+#[cfg(feature = "daemon")]
+pub mod netinfo;
+#[cfg(feature = "persistence")]
+pub mod persistence;
+#[cfg(feature = "daemon")]
+pub mod screenshot;
+#[cfg(feature = "daemon")]
+pub mod setup;
+#[cfg(feature = "daemon")]
+mod state;
+#[cfg(feature = "daemon")]
+pub mod telemetry;
+#[cfg(feature = "daemon")]
+pub mod web;
+
+pub use display::framebuffer::FrameBuffer;
+pub use display::renderer::Renderer;
+pub use models::{Alert, Direction, DisplaySnapshot, Train};
+#[cfg(feature = "daemon")]
+pub use state::{
+    apply_config, away_today, config_mismatch_secs, display_off, load_station_database, set_do_not_disturb, AppState,
+    CONFIG_MISMATCH_AFTER_SECS, FRAME_UPDATES_CAPACITY,
+};
+
+use std::time::SystemTime;
+
+/// Current time as seconds since the Unix epoch.
+pub fn unix_now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
@@ -0,0 +1,349 @@
+//! Process lifecycle: confirmed restarts and display handoff across them.
+//!
+//! A restart is two-step: the client first asks for a short-lived
+//! confirmation token, then posts it back. Once confirmed, the requested
+//! mode is recorded and shutdown is triggered; `main` performs the actual
+//! restart after all tasks have stopped cleanly.
+//!
+//! On shutdown (or panic) the last frame and snapshot are written to a
+//! handoff file. The next process shows that frame with a "restarting"
+//! badge until its first fetch lands, so the sign doesn't go dark.
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+
+use serde::{Deserialize, Serialize};
+
+use crate::display::colors::{COLOR_BLACK, COLOR_ORANGE};
+use crate::display::framebuffer::{FrameBuffer, DISPLAY_HEIGHT, DISPLAY_WIDTH};
+use crate::models::DisplaySnapshot;
+
+/// Exit code signalling "restart me" to the supervisor (EX_TEMPFAIL).
+///
+/// The systemd unit uses `Restart=always`, so any exit restarts the
+/// service; the distinct code makes intentional restarts obvious in logs.
+pub const RESTART_EXIT_CODE: i32 = 75;
+
+/// How long a confirmation token stays valid.
+pub const TOKEN_TTL: Duration = Duration::from_secs(30);
+
+/// How the process restarts itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RestartMode {
+    /// Exit with `RESTART_EXIT_CODE` and let systemd start a fresh process.
+    Exit,
+    /// Re-exec the current binary in place (same PID, no supervisor needed).
+    Exec,
+}
+
+/// Pending confirmation token and the confirmed restart request, if any.
+pub struct RestartControl {
+    token: Mutex<Option<(String, Instant)>>,
+    requested: Mutex<Option<RestartMode>>,
+}
+
+impl RestartControl {
+    pub fn new() -> Self {
+        RestartControl {
+            token: Mutex::new(None),
+            requested: Mutex::new(None),
+        }
+    }
+
+    /// Issue a fresh confirmation token, replacing any outstanding one.
+    pub fn issue_token(&self) -> String {
+        let token = random_token();
+        let mut slot = self.token.lock().unwrap_or_else(|e| e.into_inner());
+        *slot = Some((token.clone(), Instant::now()));
+        token
+    }
+
+    /// Check and consume a confirmation token. Tokens are single-use.
+    pub fn confirm(&self, token: &str) -> bool {
+        let mut slot = self.token.lock().unwrap_or_else(|e| e.into_inner());
+        match slot.take() {
+            Some((expected, issued)) if expected == token && issued.elapsed() <= TOKEN_TTL => true,
+            Some(other) => {
+                // Wrong token: keep the outstanding one unless it has expired
+                if other.1.elapsed() <= TOKEN_TTL {
+                    *slot = Some(other);
+                }
+                false
+            }
+            None => false,
+        }
+    }
+
+    /// Record a confirmed restart (performed by `main` after shutdown).
+    pub fn request(&self, mode: RestartMode) {
+        *self.requested.lock().unwrap_or_else(|e| e.into_inner()) = Some(mode);
+    }
+
+    /// The confirmed restart mode, if a restart was requested.
+    pub fn requested(&self) -> Option<RestartMode> {
+        *self.requested.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Default for RestartControl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Working directory the process was started in, when it has since moved
+/// into an instance directory (see `instance`).
+pub static LAUNCH_DIR: std::sync::OnceLock<PathBuf> = std::sync::OnceLock::new();
+
+/// Restart the process. Never returns.
+///
+/// `Exec` falls back to `Exit` if the binary cannot be re-executed.
+pub fn restart_process(mode: RestartMode) -> ! {
+    #[cfg(unix)]
+    if mode == RestartMode::Exec {
+        use std::os::unix::process::CommandExt;
+
+        match std::env::current_exe() {
+            Ok(exe) => {
+                tracing::info!("Re-executing {}", exe.display());
+                let mut command = std::process::Command::new(exe);
+                command.args(std::env::args_os().skip(1));
+                // Arguments may be relative to where the process started
+                if let Some(dir) = LAUNCH_DIR.get() {
+                    command.current_dir(dir);
+                }
+                let err = command.exec();
+                tracing::error!("Re-exec failed ({}), exiting for supervisor restart", err);
+            }
+            Err(e) => {
+                tracing::error!("Cannot locate own binary ({}), exiting for supervisor restart", e);
+            }
+        }
+    }
+
+    tracing::info!("Exiting with code {} for supervisor restart", RESTART_EXIT_CODE);
+    std::process::exit(RESTART_EXIT_CODE)
+}
+
+/// Handoff file name, kept next to config.json.
+const HANDOFF_FILE: &str = ".restart-state.json";
+
+/// Handoffs older than this are ignored (the sign was off, not restarting).
+pub const HANDOFF_MAX_AGE: Duration = Duration::from_secs(120);
+
+/// Display state written by a stopping process for its successor.
+#[derive(Serialize, Deserialize)]
+struct Handoff {
+    /// Unix seconds when the handoff was written.
+    saved_at: u64,
+    /// Raw framebuffer pixels (row-major RGB).
+    pixels: Vec<u8>,
+    /// Frame size (older handoffs are always 192x32).
+    #[serde(default = "default_handoff_width")]
+    width: usize,
+    #[serde(default = "default_handoff_height")]
+    height: usize,
+    snapshot: DisplaySnapshot,
+}
+
+fn default_handoff_width() -> usize {
+    DISPLAY_WIDTH
+}
+fn default_handoff_height() -> usize {
+    DISPLAY_HEIGHT
+}
+
+/// Display state restored from a previous process.
+pub struct Restored {
+    pub frame: FrameBuffer,
+    pub snapshot: DisplaySnapshot,
+}
+
+/// Location of the handoff file for a given config path.
+pub fn handoff_path(config_path: &Path) -> PathBuf {
+    match config_path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.join(HANDOFF_FILE),
+        _ => PathBuf::from(HANDOFF_FILE),
+    }
+}
+
+/// Persist the current frame and snapshot for the next process.
+///
+/// Written to a temp file and renamed so a crash mid-write can't leave a
+/// truncated handoff behind.
+pub fn save_handoff(
+    path: &Path,
+    frame: &FrameBuffer,
+    snapshot: &DisplaySnapshot,
+) -> Result<(), String> {
+    let handoff = Handoff {
+        saved_at: crate::unix_now_secs(),
+        pixels: frame.raw_pixels().to_vec(),
+        width: frame.width(),
+        height: frame.height(),
+        snapshot: snapshot.clone(),
+    };
+    let json = serde_json::to_vec(&handoff).map_err(|e| format!("serialize: {}", e))?;
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, json).map_err(|e| format!("write {}: {}", tmp.display(), e))?;
+    std::fs::rename(&tmp, path).map_err(|e| format!("rename {}: {}", path.display(), e))
+}
+
+/// Load and delete the handoff file, if a recent one exists.
+///
+/// Train minutes are recomputed from arrival timestamps and departed trains
+/// are dropped, so the restored snapshot is as current as it can be.
+pub fn take_handoff(path: &Path) -> Option<Restored> {
+    let bytes = std::fs::read(path).ok()?;
+    let _ = std::fs::remove_file(path);
+
+    let handoff: Handoff = match serde_json::from_slice(&bytes) {
+        Ok(h) => h,
+        Err(e) => {
+            tracing::warn!("[RESTORE] Ignoring unreadable handoff: {}", e);
+            return None;
+        }
+    };
+
+    let age = crate::unix_now_secs().saturating_sub(handoff.saved_at);
+    if age > HANDOFF_MAX_AGE.as_secs() {
+        tracing::info!("[RESTORE] Ignoring handoff from {}s ago", age);
+        return None;
+    }
+
+    let frame = FrameBuffer::from_raw_parts(handoff.pixels, handoff.width, handoff.height)?;
+    let mut snapshot = handoff.snapshot;
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64();
+    snapshot.trains.retain(|t| t.arrival_timestamp >= now);
+    for train in &mut snapshot.trains {
+        train.minutes = ((train.arrival_timestamp - now) / 60.0).max(0.0) as i32;
+    }
+
+    Some(Restored { frame, snapshot })
+}
+
+/// Mark a restored frame as stale: a small orange pip in the top-right
+/// corner, blinking with the display's flash state.
+pub fn draw_restart_badge(frame: &mut FrameBuffer, lit: bool) {
+    const SIZE: i32 = 4;
+    let x0 = frame.width() as i32 - SIZE - 1;
+    // Black surround keeps the pip legible over whatever is underneath
+    for y in 0..=SIZE {
+        for x in x0 - 1..x0 + SIZE + 1 {
+            frame.set_pixel(x, y, COLOR_BLACK);
+        }
+    }
+    if lit {
+        for y in 0..SIZE {
+            for x in x0..x0 + SIZE {
+                frame.set_pixel(x, y, COLOR_ORANGE);
+            }
+        }
+    }
+}
+
+/// Unpredictable-enough hex token (not a credential — just proof of intent).
+fn random_token() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u128(nanos);
+    hasher.write_u32(std::process::id());
+    format!("{:016x}", hasher.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_confirm_single_use() {
+        let control = RestartControl::new();
+        let token = control.issue_token();
+        assert_eq!(token.len(), 16);
+        assert!(!control.confirm("wrong"));
+        assert!(control.confirm(&token), "wrong guess must not burn the real token");
+        assert!(!control.confirm(&token), "token is single-use");
+    }
+
+    #[test]
+    fn test_reissue_invalidates_old_token() {
+        let control = RestartControl::new();
+        let old = control.issue_token();
+        let new = control.issue_token();
+        assert_ne!(old, new);
+        assert!(!control.confirm(&old));
+        assert!(control.confirm(&new));
+    }
+
+    #[test]
+    fn test_expired_token_rejected() {
+        let control = RestartControl::new();
+        let token = control.issue_token();
+        {
+            let mut slot = control.token.lock().unwrap();
+            slot.as_mut().unwrap().1 = Instant::now() - TOKEN_TTL - Duration::from_secs(1);
+        }
+        assert!(!control.confirm(&token));
+    }
+
+    #[test]
+    fn test_handoff_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = handoff_path(&dir.path().join("config.json"));
+        assert_eq!(path, dir.path().join(HANDOFF_FILE));
+
+        let mut frame = FrameBuffer::new();
+        frame.set_pixel(10, 10, COLOR_ORANGE);
+        let now = crate::unix_now_secs() as f64;
+        let mut departed = crate::models::Train::empty();
+        departed.arrival_timestamp = now - 120.0;
+        let mut upcoming = crate::models::Train::empty();
+        upcoming.arrival_timestamp = now + 330.0;
+        let snapshot = DisplaySnapshot {
+            trains: vec![departed, upcoming],
+            alerts: Vec::new(),
+            fetched_at: now,
+        };
+
+        save_handoff(&path, &frame, &snapshot).unwrap();
+        let restored = take_handoff(&path).unwrap();
+        assert_eq!(restored.frame.get_pixel(10, 10), COLOR_ORANGE);
+        assert_eq!(restored.snapshot.trains.len(), 1, "departed train dropped");
+        assert_eq!(restored.snapshot.trains[0].minutes, 5);
+        assert!(!path.exists(), "handoff is consumed on load");
+        assert!(take_handoff(&path).is_none());
+    }
+
+    #[test]
+    fn test_stale_handoff_ignored() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(HANDOFF_FILE);
+        let handoff = Handoff {
+            saved_at: crate::unix_now_secs() - HANDOFF_MAX_AGE.as_secs() - 1,
+            pixels: FrameBuffer::new().raw_pixels().to_vec(),
+            width: DISPLAY_WIDTH,
+            height: DISPLAY_HEIGHT,
+            snapshot: DisplaySnapshot::empty(),
+        };
+        std::fs::write(&path, serde_json::to_vec(&handoff).unwrap()).unwrap();
+        assert!(take_handoff(&path).is_none());
+    }
+
+    #[test]
+    fn test_request_recorded() {
+        let control = RestartControl::new();
+        assert_eq!(control.requested(), None);
+        control.request(RestartMode::Exec);
+        assert_eq!(control.requested(), Some(RestartMode::Exec));
+    }
+}
@@ -0,0 +1,264 @@
+//! In-process log broadcast for the web UI.
+//!
+//! `LogHub` is a `tracing` layer that captures the sign's events into a
+//! bounded ring buffer (recent history) and a broadcast channel (live
+//! subscribers such as the `/ws/logs` WebSocket). It has its own filter, so
+//! each stream picks its level without touching the console's `RUST_LOG`.
+//! `InstancePrefix` tags console lines with the instance name when several
+//! signs share a host.
+
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::sync::{Arc, Mutex};
+
+use serde::Serialize;
+use tokio::sync::broadcast;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields};
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::filter::{EnvFilter, Targets};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+
+/// Number of recent lines kept for late subscribers.
+const RECENT_CAPACITY: usize = 500;
+
+/// Broadcast channel depth before slow subscribers start lagging.
+const CHANNEL_CAPACITY: usize = 1024;
+
+/// Console (and OTLP) filter: `RUST_LOG`, or the sign's info lines.
+pub fn console_filter() -> EnvFilter {
+    EnvFilter::try_from_default_env().unwrap_or_else(|_| "subway_sign=info".parse().unwrap())
+}
+
+/// What `LogHub` sees: the sign's lines down to debug, whatever `RUST_LOG`
+/// says; each stream then applies its own level.
+pub fn stream_filter() -> Targets {
+    Targets::new().with_target("subway_sign", Level::DEBUG)
+}
+
+/// One captured log event.
+#[derive(Debug, Clone, Serialize)]
+pub struct LogLine {
+    /// RFC 3339 local timestamp.
+    pub timestamp: String,
+    pub level: String,
+    pub target: String,
+    pub message: String,
+}
+
+impl LogLine {
+    /// Parsed level (unknown strings are treated as TRACE).
+    pub fn level(&self) -> Level {
+        self.level.parse().unwrap_or(Level::TRACE)
+    }
+}
+
+struct Inner {
+    sender: broadcast::Sender<LogLine>,
+    recent: Mutex<VecDeque<LogLine>>,
+}
+
+/// Cloneable handle to the log broadcast; also the `tracing` layer itself.
+#[derive(Clone)]
+pub struct LogHub {
+    inner: Arc<Inner>,
+}
+
+impl LogHub {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+        LogHub {
+            inner: Arc::new(Inner {
+                sender,
+                recent: Mutex::new(VecDeque::with_capacity(RECENT_CAPACITY)),
+            }),
+        }
+    }
+
+    /// Subscribe to lines logged from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<LogLine> {
+        self.inner.sender.subscribe()
+    }
+
+    /// Snapshot of the most recent lines, oldest first.
+    pub fn recent(&self) -> Vec<LogLine> {
+        let recent = self.inner.recent.lock().unwrap_or_else(|e| e.into_inner());
+        recent.iter().cloned().collect()
+    }
+
+    /// Like `recent`, but gives up instead of waiting for the lock (for the
+    /// panic hook, which may run while the lock is held).
+    pub fn try_recent(&self) -> Option<Vec<LogLine>> {
+        let recent = match self.inner.recent.try_lock() {
+            Ok(recent) => recent,
+            Err(std::sync::TryLockError::Poisoned(e)) => e.into_inner(),
+            Err(std::sync::TryLockError::WouldBlock) => return None,
+        };
+        Some(recent.iter().cloned().collect())
+    }
+
+    /// Broadcast a line, also keeping it in the backlog if `keep`.
+    fn publish(&self, line: LogLine, keep: bool) {
+        if keep {
+            let mut recent = self.inner.recent.lock().unwrap_or_else(|e| e.into_inner());
+            if recent.len() == RECENT_CAPACITY {
+                recent.pop_front();
+            }
+            recent.push_back(line.clone());
+        }
+        // No receivers is fine — nobody has the log view open
+        let _ = self.inner.sender.send(line);
+    }
+}
+
+impl Default for LogHub {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S: Subscriber> Layer<S> for LogHub {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        // Debug lines only go to open streams; the backlog (and so crash
+        // reports) keeps to info and above
+        let metadata = event.metadata();
+        let verbose = *metadata.level() > Level::INFO;
+        if verbose && self.inner.sender.receiver_count() == 0 {
+            return;
+        }
+
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+        self.publish(
+            LogLine {
+                timestamp: chrono::Local::now().to_rfc3339(),
+                level: metadata.level().to_string(),
+                target: metadata.target().to_string(),
+                message: visitor.finish(),
+            },
+            !verbose,
+        );
+    }
+}
+
+/// Console event format that starts each line with `[name]` (nothing when
+/// unnamed), then formats the event as `inner` would.
+pub struct InstancePrefix<F> {
+    pub name: Option<String>,
+    pub inner: F,
+}
+
+impl<S, N, F> FormatEvent<S, N> for InstancePrefix<F>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+    F: FormatEvent<S, N>,
+{
+    fn format_event(&self, ctx: &FmtContext<'_, S, N>, mut writer: Writer<'_>, event: &Event<'_>) -> std::fmt::Result {
+        if let Some(name) = &self.name {
+            write!(writer, "[{}] ", name)?;
+        }
+        self.inner.format_event(ctx, writer, event)
+    }
+}
+
+/// Collects the `message` field plus any extra `key=value` fields.
+#[derive(Default)]
+struct MessageVisitor {
+    message: String,
+    fields: String,
+}
+
+impl MessageVisitor {
+    fn finish(self) -> String {
+        if self.fields.is_empty() {
+            self.message
+        } else if self.message.is_empty() {
+            self.fields.trim_start().to_string()
+        } else {
+            format!("{}{}", self.message, self.fields)
+        }
+    }
+}
+
+impl Visit for MessageVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message.push_str(value);
+        } else {
+            let _ = write!(self.fields, " {}={}", field.name(), value);
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{:?}", value);
+        } else {
+            let _ = write!(self.fields, " {}={:?}", field.name(), value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[test]
+    fn test_captures_events() {
+        let hub = LogHub::new();
+        let mut rx = hub.subscribe();
+        let subscriber = tracing_subscriber::registry().with(hub.clone());
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!("[TEST] hello {}", 42);
+            tracing::warn!(route = "A", "feed stale");
+        });
+
+        let recent = hub.recent();
+        assert_eq!(recent.len(), 2);
+        assert_eq!(recent[0].message, "[TEST] hello 42");
+        assert_eq!(recent[0].level(), Level::INFO);
+        assert_eq!(recent[1].message, "feed stale route=A");
+        assert_eq!(recent[1].level(), Level::WARN);
+
+        let live = rx.try_recv().unwrap();
+        assert_eq!(live.message, "[TEST] hello 42");
+    }
+
+    #[test]
+    fn test_debug_lines_skip_backlog() {
+        let hub = LogHub::new();
+        let mut rx = hub.subscribe();
+        let subscriber = tracing_subscriber::registry().with(hub.clone());
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::debug!("[TEST] detail");
+        });
+
+        assert!(hub.recent().is_empty());
+        assert_eq!(rx.try_recv().unwrap().message, "[TEST] detail");
+    }
+
+    #[test]
+    fn test_recent_is_bounded() {
+        let hub = LogHub::new();
+        for i in 0..RECENT_CAPACITY + 10 {
+            hub.publish(
+                LogLine {
+                    timestamp: String::new(),
+                    level: "INFO".into(),
+                    target: "test".into(),
+                    message: i.to_string(),
+                },
+                true,
+            );
+        }
+        let recent = hub.recent();
+        assert_eq!(recent.len(), RECENT_CAPACITY);
+        assert_eq!(recent[0].message, "10");
+    }
+}
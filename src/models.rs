@@ -1,15 +1,19 @@
 use std::collections::HashSet;
 use std::sync::OnceLock;
 
+use serde::{Deserialize, Serialize};
+
 /// Direction a train is traveling.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum Direction {
-    Uptown,   // N
+    #[serde(alias = "uptown")]
+    Uptown, // N
+    #[serde(alias = "downtown")]
     Downtown, // S
 }
 
 /// A single train arrival.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Train {
     pub route: String,
     pub destination: String,
@@ -18,6 +22,13 @@ pub struct Train {
     pub arrival_timestamp: f64,
     pub direction: Direction,
     pub stop_id: String,
+    /// Seconds behind schedule at this stop (negative = early), when the
+    /// feed or the static schedule tells.
+    #[serde(default)]
+    pub delay_secs: Option<i32>,
+    /// GTFS-RT trip ID (empty when the feed gives none); see `TripDetail`.
+    #[serde(default)]
+    pub trip_id: String,
 }
 
 impl Train {
@@ -31,24 +42,60 @@ impl Train {
             arrival_timestamp: 0.0,
             direction: Direction::Uptown,
             stop_id: String::new(),
+            delay_secs: None,
+            trip_id: String::new(),
         }
     }
 }
 
+/// The rest of a displayed train's trip, from its GTFS-RT TripUpdate
+/// (`GET /api/trains/{trip_id}`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TripDetail {
+    pub trip_id: String,
+    pub route: String,
+    pub direction: Direction,
+    pub is_express: bool,
+    /// Delay the feed states at the next stop, in seconds.
+    pub delay_secs: Option<i32>,
+    /// Upcoming stops, in order.
+    pub stops: Vec<TripStop>,
+}
+
+/// One upcoming stop of a `TripDetail`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TripStop {
+    pub stop_id: String,
+    pub station: Option<String>,
+    /// Unix seconds.
+    pub arrival: Option<i64>,
+    pub departure: Option<i64>,
+    pub delay_secs: Option<i32>,
+    /// Track the train is using (or scheduled to), from the NYCT feed
+    /// extension, when it says.
+    pub track: Option<String>,
+}
+
 /// A service alert message.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Alert {
     pub text: String,
     pub affected_routes: HashSet<String>,
     pub priority: i32,
     pub alert_id: String,
+    /// Link to the full alert, when the feed gives one.
+    #[serde(default)]
+    pub url: Option<String>,
+    /// Several alerts joined into one ticker (see `alerts::ticker_alert`).
+    #[serde(default)]
+    pub is_ticker: bool,
 }
 
 /// Complete immutable snapshot of all data needed to render a frame.
 ///
 /// Passed from the fetch task to the render thread via ArcSwap.
 /// Being fully immutable eliminates data races.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct DisplaySnapshot {
     pub trains: Vec<Train>,
     pub alerts: Vec<Alert>,
@@ -73,6 +120,17 @@ impl DisplaySnapshot {
         )
     }
 
+    /// Stop IDs trains are arriving at, in order of each one's next train.
+    pub fn platforms(&self) -> Vec<&str> {
+        let mut platforms: Vec<&str> = Vec::new();
+        for train in &self.trains {
+            if !platforms.contains(&train.stop_id.as_str()) {
+                platforms.push(&train.stop_id);
+            }
+        }
+        platforms
+    }
+
     /// Get trains #2 through #(count+1) for bottom row cycling.
     /// Skips first train (shown on top row), takes next `count` trains.
     pub fn get_cycling_trains(&self, count: usize) -> Vec<Train> {
@@ -158,6 +216,8 @@ mod tests {
                     arrival_timestamp: 1000.0,
                     direction: Direction::Uptown,
                     stop_id: "127N".into(),
+                    delay_secs: None,
+                    trip_id: String::new(),
                 },
                 Train {
                     route: "2".into(),
@@ -167,6 +227,8 @@ mod tests {
                     arrival_timestamp: 1180.0,
                     direction: Direction::Uptown,
                     stop_id: "127N".into(),
+                    delay_secs: None,
+                    trip_id: String::new(),
                 },
             ],
             alerts: Vec::new(),
@@ -188,6 +250,8 @@ mod tests {
                 arrival_timestamp: 0.0,
                 direction: Direction::Uptown,
                 stop_id: "".into(),
+                delay_secs: None,
+                trip_id: String::new(),
             }],
             alerts: Vec::new(),
             fetched_at: 0.0,
@@ -210,6 +274,8 @@ mod tests {
                 arrival_timestamp: 0.0,
                 direction: Direction::Uptown,
                 stop_id: "".into(),
+                delay_secs: None,
+                trip_id: String::new(),
             });
         }
         let snap = DisplaySnapshot {
@@ -1,6 +1,9 @@
 use std::collections::{HashMap, HashSet};
 use std::time::Instant;
 
+use serde::Serialize;
+use tracing::{debug, debug_span};
+
 use crate::models::Alert;
 
 /// GTFS-RT effect enum → priority mapping.
@@ -20,12 +23,77 @@ pub(crate) fn effect_priority(effect_value: i32) -> i32 {
     }
 }
 
+/// Priority of NO_SERVICE alerts (see `effect_priority`).
+pub const NO_SERVICE_PRIORITY: i32 = 1;
+
+/// Priority of SIGNIFICANT_DELAYS alerts.
+pub const SIGNIFICANT_DELAYS_PRIORITY: i32 = 3;
+
+/// Routes named by a NO_SERVICE alert.
+pub fn suspended_routes(alerts: &[Alert]) -> HashSet<String> {
+    alerts
+        .iter()
+        .filter(|a| a.priority == NO_SERVICE_PRIORITY)
+        .flat_map(|a| a.affected_routes.iter().cloned())
+        .collect()
+}
+
+/// Separates alerts in a ticker (drawn as a diamond, see `ticker_alert`).
+pub const TICKER_SEPARATOR: char = '\u{25C6}';
+
+/// All of `alerts` as one ticker alert: texts joined by `TICKER_SEPARATOR`,
+/// the most urgent priority and every affected route.
+pub fn ticker_alert(alerts: &[Alert]) -> Option<Alert> {
+    let first = alerts.first()?;
+    let separator = format!(" {} ", TICKER_SEPARATOR);
+    Some(Alert {
+        text: alerts
+            .iter()
+            .map(|a| a.text.replace(TICKER_SEPARATOR, " "))
+            .collect::<Vec<_>>()
+            .join(&separator),
+        affected_routes: alerts.iter().flat_map(|a| a.affected_routes.iter().cloned()).collect(),
+        priority: alerts.iter().map(|a| a.priority).min().unwrap_or(first.priority),
+        alert_id: "ticker".to_string(),
+        url: None,
+        is_ticker: true,
+    })
+}
+
 /// Cooldown period — don't show same alert for this long.
 const COOLDOWN_SECONDS: u64 = 300; // 5 minutes
 
 /// Maximum alerts to queue.
 const MAX_QUEUE_SIZE: usize = 10;
 
+/// Why an alert is or isn't next on the sign (see `AlertManager::decisions`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertStatus {
+    /// First in line for the next arriving train.
+    Next,
+    /// Waiting behind more urgent alerts.
+    Queued,
+    /// Already shown this cycle; back after the cycle restarts.
+    ShownThisCycle,
+    /// Shown recently; skipped until the cooldown ends.
+    Cooldown,
+    /// Cut because the queue holds only the most urgent `MAX_QUEUE_SIZE`.
+    QueueFull,
+}
+
+/// One fetched alert and what the manager is doing with it.
+#[derive(Debug, Clone, Serialize)]
+pub struct AlertDecision {
+    pub alert_id: String,
+    pub text: String,
+    pub priority: i32,
+    pub status: AlertStatus,
+    /// Unix seconds the cooldown ends, while on cooldown.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cooldown_until: Option<u64>,
+}
+
 /// Manages alert filtering, prioritization, and cooldown tracking.
 pub struct AlertManager {
     /// Cooldown tracking: alert_key → last displayed instant.
@@ -38,6 +106,12 @@ pub struct AlertManager {
     shown_this_cycle: HashSet<String>,
     /// Last cleanup instant.
     last_cleanup: Instant,
+    /// Keys of the alerts in the previous fetch (`None` before the first).
+    previous_keys: Option<HashSet<String>>,
+    /// NO_SERVICE alerts new in a fetch, waiting to interrupt the display.
+    urgent: Vec<Alert>,
+    /// Every alert in the last fetch, for `decisions`.
+    fetched: Vec<Alert>,
 }
 
 impl AlertManager {
@@ -48,25 +122,39 @@ impl AlertManager {
             queue_index: 0,
             shown_this_cycle: HashSet::new(),
             last_cleanup: Instant::now(),
+            previous_keys: None,
+            urgent: Vec::new(),
+            fetched: Vec::new(),
         }
     }
 
     /// Filter alerts by priority and apply cooldown.
     pub fn filter_and_sort(&mut self, alerts: &[Alert]) -> Vec<Alert> {
+        let _span = debug_span!("filter_and_sort", fetched = alerts.len()).entered();
         self.cleanup_cooldowns();
+        self.track_new(alerts);
+        self.fetched = alerts.to_vec();
 
         // Filter by cooldown
-        let mut non_cooled: Vec<Alert> = alerts
-            .iter()
-            .filter(|a| !self.is_on_cooldown(a))
-            .cloned()
-            .collect();
+        let (mut non_cooled, cooling): (Vec<Alert>, Vec<Alert>) =
+            alerts.iter().cloned().partition(|a| !self.is_on_cooldown(a));
 
         // Sort by priority (lower = more important)
         non_cooled.sort_by_key(|a| a.priority);
 
         // Cap queue size
-        non_cooled.truncate(MAX_QUEUE_SIZE);
+        let dropped = non_cooled.split_off(non_cooled.len().min(MAX_QUEUE_SIZE));
+
+        // Explain the queue when it changes, not on every poll
+        if non_cooled != self.queue {
+            for alert in &cooling {
+                debug!(alert = %Self::alert_key(alert), "[ALERTS] On cooldown, not queued");
+            }
+            for alert in &dropped {
+                debug!(alert = %Self::alert_key(alert), priority = alert.priority, "[ALERTS] Queue full, dropped");
+            }
+            debug!(queued = non_cooled.len(), "[ALERTS] Queue updated");
+        }
 
         // Update queue
         self.queue = non_cooled.clone();
@@ -77,11 +165,35 @@ impl AlertManager {
         non_cooled
     }
 
+    /// Queue NO_SERVICE alerts that weren't in the previous fetch for
+    /// `take_urgent`. The first fetch only sets the baseline.
+    fn track_new(&mut self, alerts: &[Alert]) {
+        let keys: HashSet<String> = alerts.iter().map(Self::alert_key).collect();
+        self.urgent.retain(|a| keys.contains(&Self::alert_key(a)));
+        if let Some(previous) = &self.previous_keys {
+            let new = alerts
+                .iter()
+                .filter(|a| a.priority == NO_SERVICE_PRIORITY && !previous.contains(&Self::alert_key(a)));
+            for alert in new {
+                debug!(alert = %Self::alert_key(alert), "[ALERTS] New suspension, will interrupt");
+                self.urgent.push(alert.clone());
+            }
+        }
+        self.previous_keys = Some(keys);
+    }
+
+    /// Next newly arrived NO_SERVICE alert to show right away, regardless
+    /// of the rotation and cooldowns. Each is returned once.
+    pub fn take_urgent(&mut self) -> Option<Alert> {
+        (!self.urgent.is_empty()).then(|| self.urgent.remove(0))
+    }
+
     /// Get the next alert to display from the queue.
     pub fn get_next_alert(&self) -> Option<&Alert> {
         if self.queue.is_empty() {
             return None;
         }
+        let _span = debug_span!("get_next_alert", queued = self.queue.len()).entered();
 
         let mut checked = 0;
         let mut idx = self.queue_index;
@@ -90,8 +202,14 @@ impl AlertManager {
             let key = Self::alert_key(alert);
 
             if !self.shown_this_cycle.contains(&key) && !self.is_on_cooldown(alert) {
+                debug!(alert = %key, "[ALERTS] Next up");
                 return Some(alert);
             }
+            debug!(
+                alert = %key,
+                shown_this_cycle = self.shown_this_cycle.contains(&key),
+                "[ALERTS] Skipped"
+            );
 
             idx = (idx + 1) % self.queue.len();
             checked += 1;
@@ -110,6 +228,7 @@ impl AlertManager {
     /// Mark an alert as displayed, starting its cooldown.
     pub fn mark_displayed(&mut self, alert: &Alert) {
         let key = Self::alert_key(alert);
+        debug!(alert = %key, cooldown_secs = COOLDOWN_SECONDS, "[ALERTS] Shown, cooldown started");
         self.cooldowns.insert(key.clone(), Instant::now());
         self.shown_this_cycle.insert(key);
         self.advance_queue();
@@ -131,12 +250,61 @@ impl AlertManager {
             .all(|a| self.shown_this_cycle.contains(&Self::alert_key(a)))
     }
 
+    /// The current queue, most urgent first (cooldowns aside).
+    pub fn queued(&self) -> &[Alert] {
+        &self.queue
+    }
+
+    /// Every alert from the last fetch, most urgent first, with why it is
+    /// or isn't going to be shown.
+    pub fn decisions(&self) -> Vec<AlertDecision> {
+        let next = self.get_next_alert().map(Self::alert_key);
+        let now = crate::unix_now_secs();
+        let mut decisions: Vec<AlertDecision> = self
+            .fetched
+            .iter()
+            .map(|alert| {
+                let key = Self::alert_key(alert);
+                let cooldown_left = self
+                    .cooldowns
+                    .get(&key)
+                    .map(|shown| COOLDOWN_SECONDS.saturating_sub(shown.elapsed().as_secs()))
+                    .filter(|left| *left > 0);
+                let status = if cooldown_left.is_some() {
+                    AlertStatus::Cooldown
+                } else if !self.queue.iter().any(|a| Self::alert_key(a) == key) {
+                    AlertStatus::QueueFull
+                } else if self.shown_this_cycle.contains(&key) {
+                    AlertStatus::ShownThisCycle
+                } else if next.as_ref() == Some(&key) {
+                    AlertStatus::Next
+                } else {
+                    AlertStatus::Queued
+                };
+                AlertDecision {
+                    alert_id: alert.alert_id.clone(),
+                    text: alert.text.clone(),
+                    priority: alert.priority,
+                    status,
+                    cooldown_until: cooldown_left.map(|left| now + left),
+                }
+            })
+            .collect();
+        decisions.sort_by_key(|d| d.priority);
+        decisions
+    }
+
     /// Number of alerts currently in queue.
     #[cfg(test)]
     pub(crate) fn queue_size(&self) -> usize {
         self.queue.len()
     }
 
+    /// Every alert from the last fetch, cooldowns aside.
+    pub fn fetched(&self) -> &[Alert] {
+        &self.fetched
+    }
+
     /// Check if there are any displayable alerts (not on cooldown).
     pub fn has_alerts(&self) -> bool {
         self.queue.iter().any(|a| !self.is_on_cooldown(a))
@@ -173,6 +341,12 @@ impl AlertManager {
     }
 }
 
+impl Default for AlertManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -183,6 +357,8 @@ mod tests {
             affected_routes: HashSet::from(["1".to_string()]),
             priority,
             alert_id: id.to_string(),
+            url: None,
+            is_ticker: false,
         }
     }
 
@@ -209,6 +385,34 @@ mod tests {
         assert_eq!(filtered[2].priority, 5);
     }
 
+    #[test]
+    fn test_decisions_explain_each_alert() {
+        let mut mgr = AlertManager::new();
+        let mut alerts: Vec<Alert> = (0..MAX_QUEUE_SIZE as i32 + 1)
+            .map(|i| make_alert(&format!("a{}", i), &format!("Alert {}", i), i + 2))
+            .collect();
+        alerts.push(make_alert("shown", "Shown earlier", 1));
+        mgr.filter_and_sort(&alerts);
+        let shown = mgr.get_next_alert().unwrap().clone();
+        mgr.mark_displayed(&shown);
+
+        let decisions = mgr.decisions();
+        let status = |id: &str| decisions.iter().find(|d| d.alert_id == id).unwrap().status;
+        assert_eq!(status("a0"), AlertStatus::Next);
+        assert_eq!(status("a1"), AlertStatus::Queued);
+        assert_eq!(status("a10"), AlertStatus::QueueFull, "least urgent past the cap");
+        let shown = &decisions[0];
+        assert_eq!((shown.alert_id.as_str(), shown.status), ("shown", AlertStatus::Cooldown), "most urgent first");
+        assert!(shown.cooldown_until.unwrap() >= crate::unix_now_secs() + COOLDOWN_SECONDS - 1);
+
+        // Cooldown over, but the cycle hasn't restarted
+        let long_ago = Instant::now().checked_sub(std::time::Duration::from_secs(COOLDOWN_SECONDS)).unwrap();
+        mgr.cooldowns.insert("shown".to_string(), long_ago);
+        mgr.filter_and_sort(&alerts);
+        assert_eq!(mgr.decisions()[0].status, AlertStatus::ShownThisCycle);
+        assert_eq!(mgr.decisions()[0].cooldown_until, None);
+    }
+
     #[test]
     fn test_get_next_alert() {
         let mut mgr = AlertManager::new();
@@ -292,4 +496,49 @@ mod tests {
         mgr.filter_and_sort(&alerts);
         assert_eq!(mgr.queue_size(), MAX_QUEUE_SIZE);
     }
+
+    #[test]
+    fn test_suspended_routes() {
+        let mut suspension = make_alert("a1", "No [6] trains", NO_SERVICE_PRIORITY);
+        suspension.affected_routes = HashSet::from(["6".to_string(), "6X".to_string()]);
+        let delays = make_alert("a2", "Delays on [1]", 3);
+        assert_eq!(
+            suspended_routes(&[suspension, delays]),
+            HashSet::from(["6".to_string(), "6X".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_ticker_alert() {
+        assert!(ticker_alert(&[]).is_none());
+        let mut delays = make_alert("a1", "Delays on [1]", 3);
+        delays.affected_routes = HashSet::from(["2".to_string()]);
+        let ticker = ticker_alert(&[delays, make_alert("a2", "No [6] trains", NO_SERVICE_PRIORITY)]).unwrap();
+        assert!(ticker.is_ticker);
+        assert_eq!(ticker.text, "Delays on [1] \u{25C6} No [6] trains");
+        assert_eq!(ticker.priority, NO_SERVICE_PRIORITY);
+        assert_eq!(ticker.affected_routes, HashSet::from(["1".to_string(), "2".to_string()]));
+    }
+
+    #[test]
+    fn test_new_no_service_alert_is_urgent_once() {
+        let mut mgr = AlertManager::new();
+        let delays = make_alert("a1", "Delays", 3);
+        let suspended = make_alert("a2", "No [1] trains", NO_SERVICE_PRIORITY);
+        mgr.filter_and_sort(&[delays.clone(), suspended.clone()]);
+        assert!(mgr.take_urgent().is_none(), "first fetch is the baseline");
+
+        let new = make_alert("a3", "No [2] trains", NO_SERVICE_PRIORITY);
+        let minor = make_alert("a4", "Elevator out", 8);
+        mgr.filter_and_sort(&[delays.clone(), suspended.clone(), new.clone(), minor]);
+        assert_eq!(mgr.take_urgent().unwrap().alert_id, "a3");
+        assert!(mgr.take_urgent().is_none());
+        mgr.filter_and_sort(&[delays.clone(), suspended.clone(), new]);
+        assert!(mgr.take_urgent().is_none(), "still active is not new");
+
+        // Resolved before it could be shown
+        mgr.filter_and_sort(&[delays.clone(), make_alert("a5", "No [3] trains", NO_SERVICE_PRIORITY)]);
+        mgr.filter_and_sort(&[delays]);
+        assert!(mgr.take_urgent().is_none());
+    }
 }
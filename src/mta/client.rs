@@ -1,4 +1,5 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
 use std::time::Instant;
 
 use prost::Message;
@@ -6,9 +7,11 @@ use reqwest::Client;
 use tokio::task::JoinSet;
 use tracing::{debug, warn};
 
-use crate::models::{Alert, Direction, Train};
+use crate::models::{Alert, Direction, Train, TripDetail, TripStop};
 use crate::mta::alerts::effect_priority;
 use crate::mta::feeds;
+use crate::mta::schedule::ServiceSchedule;
+use crate::selection::{self, ServiceFilter};
 
 /// Generated protobuf types from gtfs-realtime.proto.
 #[allow(clippy::all, clippy::doc_overindented_list_items, clippy::doc_lazy_continuation)]
@@ -22,6 +25,10 @@ const CACHE_TTL_SECONDS: u64 = 300;
 /// Minimum interval between logging the same error source.
 const ERROR_LOG_INTERVAL_SECS: u64 = 300;
 
+/// Arrivals of one route at one platform closer together than this are the
+/// same train (overlapping feeds), unless configured otherwise.
+pub const DEFAULT_DEDUP_WINDOW_SECS: u64 = 45;
+
 /// MTA alerts feed URL.
 const ALERTS_URL: &str =
     "https://api-endpoint.mta.info/Dataservice/mtagtfsfeeds/camsys%2Fsubway-alerts";
@@ -29,6 +36,7 @@ const ALERTS_URL: &str =
 /// Cached feed data.
 struct FeedCacheEntry {
     trains: Vec<Train>,
+    trips: Vec<TripDetail>,
     fetched_at: Instant,
 }
 
@@ -38,6 +46,18 @@ struct BackoffState {
     retry_after: Instant,
 }
 
+/// What the most recent `fetch_trains` saw.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FetchSummary {
+    /// Feeds fetched and decoded.
+    pub feeds_ok: usize,
+    /// Feeds that failed or were skipped while backing off.
+    pub feeds_failed: usize,
+    /// Trips on the requested routes anywhere in the fetched feeds,
+    /// whether or not they stop at the requested stops.
+    pub route_trips: usize,
+}
+
 /// MTA API client with connection pooling, caching, and exponential backoff.
 ///
 /// Never panics — all errors are handled internally and logged.
@@ -49,6 +69,16 @@ pub struct MtaClient {
     alerts_etag: Option<String>,
     backoff: HashMap<String, BackoffState>,
     last_error_log: HashMap<String, Instant>,
+    last_summary: FetchSummary,
+    /// Feed URLs that failed or were skipped on the last `fetch_trains`.
+    failed_feeds: HashSet<String>,
+    /// Static timetable for delay estimates (see `set_schedule`).
+    schedule: Arc<Option<ServiceSchedule>>,
+    dedup_window_secs: u64,
+    /// Express/local preference per route (see `set_route_service`).
+    route_service: BTreeMap<String, ServiceFilter>,
+    /// Trips of the trains the last `fetch_trains` returned, by trip ID.
+    trips: HashMap<String, TripDetail>,
 }
 
 impl MtaClient {
@@ -68,9 +98,31 @@ impl MtaClient {
             alerts_etag: None,
             backoff: HashMap::new(),
             last_error_log: HashMap::new(),
+            last_summary: FetchSummary::default(),
+            failed_feeds: HashSet::new(),
+            schedule: Arc::new(None),
+            dedup_window_secs: DEFAULT_DEDUP_WINDOW_SECS,
+            route_service: BTreeMap::new(),
+            trips: HashMap::new(),
         })
     }
 
+    /// Use this static timetable to work out delays the feed doesn't state.
+    pub fn set_schedule(&mut self, schedule: Arc<Option<ServiceSchedule>>) {
+        self.schedule = schedule;
+    }
+
+    /// Merge arrivals of one route at one platform within `secs` of each other.
+    pub fn set_dedup_window(&mut self, secs: u64) {
+        self.dedup_window_secs = secs;
+    }
+
+    /// Leave out the unwanted class of these routes' trains (before
+    /// `max_count` applies, so they don't take rows).
+    pub fn set_route_service(&mut self, filters: BTreeMap<String, ServiceFilter>) {
+        self.route_service = filters;
+    }
+
     /// Fetch upcoming trains for given stops and routes in parallel.
     pub async fn fetch_trains(
         &mut self,
@@ -83,10 +135,14 @@ impl MtaClient {
         );
 
         let mut join_set = JoinSet::new();
+        let mut summary = FetchSummary::default();
+        let mut failed_feeds = HashSet::new();
 
         // Spawn parallel fetch tasks
         for url in &feed_urls {
             if !self.should_fetch(url) {
+                summary.feeds_failed += 1;
+                failed_feeds.insert(url.clone());
                 continue; // In backoff — skip, use cache later
             }
 
@@ -94,38 +150,48 @@ impl MtaClient {
             let url = url.clone();
             let stop_ids = stop_ids.to_vec();
             let routes = routes.clone();
+            let schedule = self.schedule.clone();
 
             join_set.spawn(async move {
-                let result = fetch_single_feed(&http, &url, &stop_ids, &routes).await;
+                let result = fetch_single_feed(&http, &url, &stop_ids, &routes, &schedule).await;
                 (url, result)
             });
         }
 
         let mut all_trains: Vec<Train> = Vec::new();
+        let mut trips: HashMap<String, TripDetail> = HashMap::new();
 
         // Collect results
         while let Some(result) = join_set.join_next().await {
             match result {
-                Ok((url, Ok(trains))) => {
+                Ok((url, Ok((trains, feed_trips, route_trips)))) => {
+                    summary.feeds_ok += 1;
+                    summary.route_trips += route_trips;
                     self.record_success(&url);
                     self.feed_cache.insert(
                         url,
                         FeedCacheEntry {
                             trains: trains.clone(),
+                            trips: feed_trips.clone(),
                             fetched_at: Instant::now(),
                         },
                     );
                     all_trains.extend(trains);
+                    trips.extend(feed_trips.into_iter().map(|t| (t.trip_id.clone(), t)));
                 }
                 Ok((url, Err(e))) => {
+                    summary.feeds_failed += 1;
+                    failed_feeds.insert(url.clone());
                     self.log_error(&format!("feed_{}", url), &format!("Error fetching {}: {}", url, e));
                     self.record_failure(&url);
                     // Use cached data as fallback
                     if let Some(cached) = self.feed_cache.get(&url) {
                         all_trains.extend(cached.trains.clone());
+                        trips.extend(cached.trips.iter().map(|t| (t.trip_id.clone(), t.clone())));
                     }
                 }
                 Err(e) => {
+                    summary.feeds_failed += 1;
                     warn!("Feed fetch task panicked: {}", e);
                 }
             }
@@ -136,12 +202,17 @@ impl MtaClient {
             if !self.should_fetch(url) {
                 if let Some(cached) = self.feed_cache.get(url) {
                     all_trains.extend(cached.trains.clone());
+                    trips.extend(cached.trips.iter().map(|t| (t.trip_id.clone(), t.clone())));
                 }
             }
         }
 
         // Cleanup stale cache entries
         self.cleanup_feed_cache();
+        self.last_summary = summary;
+        self.failed_feeds = failed_feeds;
+
+        all_trains.retain(|t| selection::service_allows(&self.route_service, t));
 
         // Sort and deduplicate
         all_trains.sort_by(|a, b| {
@@ -149,8 +220,18 @@ impl MtaClient {
                 .partial_cmp(&b.arrival_timestamp)
                 .unwrap_or(std::cmp::Ordering::Equal)
         });
-        let unique = deduplicate_trains(all_trains);
-        unique.into_iter().take(max_count).collect()
+        let unique = deduplicate_trains(all_trains, self.dedup_window_secs as f64);
+        let shown: Vec<Train> = unique.into_iter().take(max_count).collect();
+        self.trips = shown
+            .iter()
+            .filter_map(|t| trips.remove_entry(&t.trip_id))
+            .collect();
+        shown
+    }
+
+    /// Trips of the trains the last `fetch_trains` returned, by trip ID.
+    pub fn trips(&self) -> &HashMap<String, TripDetail> {
+        &self.trips
     }
 
     /// Fetch service alerts for given routes.
@@ -258,6 +339,8 @@ impl MtaClient {
                             affected_routes: relevant.clone(),
                             priority,
                             alert_id: entity.id.clone(),
+                            url: alert_url(alert_proto),
+                            is_ticker: false,
                         });
                     }
                 }
@@ -269,6 +352,23 @@ impl MtaClient {
         alert_objects
     }
 
+    /// Outcome of the last `fetch_trains` call.
+    pub fn last_summary(&self) -> FetchSummary {
+        self.last_summary
+    }
+
+    /// Of `routes`, those whose feed failed (or was skipped while backing
+    /// off) on the last `fetch_trains`, sorted; their trains are from cache.
+    pub fn degraded_routes(&self, routes: &HashSet<String>) -> Vec<String> {
+        let mut degraded: Vec<String> = routes
+            .iter()
+            .filter(|r| feeds::feed_url_for_route(r).is_some_and(|url| self.failed_feeds.contains(&url)))
+            .cloned()
+            .collect();
+        degraded.sort();
+        degraded
+    }
+
     fn should_fetch(&self, feed_id: &str) -> bool {
         match self.backoff.get(feed_id) {
             Some(state) => Instant::now() >= state.retry_after,
@@ -321,7 +421,8 @@ async fn fetch_single_feed(
     url: &str,
     stop_ids: &[String],
     routes: &HashSet<String>,
-) -> Result<Vec<Train>, String> {
+    schedule: &Option<ServiceSchedule>,
+) -> Result<(Vec<Train>, Vec<TripDetail>, usize), String> {
     let response = http
         .get(url)
         .send()
@@ -345,9 +446,31 @@ async fn fetch_single_feed(
         .duration_since(std::time::UNIX_EPOCH)
         .unwrap_or_default()
         .as_secs_f64();
+    let (trains, route_trips) = trains_from_feed(&feed, stop_ids, routes, now_secs, schedule.as_ref());
+    let trip_ids: HashSet<&str> = trains.iter().map(|t| t.trip_id.as_str()).collect();
+    let trips = trip_details(&feed, &trip_ids, now_secs);
+
+    debug!("Feed {} returned {} trains", url, trains.len());
+    Ok((trains, trips, route_trips))
+}
+
+/// Upcoming arrivals at `stop_ids` in a decoded feed, plus how many trips
+/// the feed has on `routes` at all. Cancelled trips and stops the train will
+/// skip are left out. Delays come from the feed when it states them, else
+/// from comparing with `schedule`.
+pub fn trains_from_feed(
+    feed: &transit_realtime::FeedMessage,
+    stop_ids: &[String],
+    routes: &HashSet<String>,
+    now_secs: f64,
+    schedule: Option<&ServiceSchedule>,
+) -> (Vec<Train>, usize) {
+    use transit_realtime::trip_descriptor::ScheduleRelationship as TripRelationship;
+    use transit_realtime::trip_update::stop_time_update::ScheduleRelationship as StopRelationship;
 
     let stop_id_set: HashSet<&str> = stop_ids.iter().map(|s| s.as_str()).collect();
     let mut trains = Vec::new();
+    let mut route_trips = 0;
 
     for entity in &feed.entity {
         let Some(ref trip_update) = entity.trip_update else {
@@ -358,10 +481,24 @@ async fn fetch_single_feed(
         if !routes.contains(route_id) {
             continue;
         }
+        if matches!(
+            trip.schedule_relationship(),
+            TripRelationship::Canceled | TripRelationship::Deleted
+        ) {
+            continue;
+        }
+        route_trips += 1;
 
         let is_express = detect_express(trip, route_id);
+        // Skipped stops and stops without predictions aren't arrivals
+        let served = |st: &&transit_realtime::trip_update::StopTimeUpdate| {
+            !matches!(
+                st.schedule_relationship(),
+                StopRelationship::Skipped | StopRelationship::NoData
+            )
+        };
 
-        for stop_time in &trip_update.stop_time_update {
+        for stop_time in trip_update.stop_time_update.iter().filter(served) {
             let stop_id = stop_time.stop_id.as_deref().unwrap_or("");
             if !stop_id_set.contains(stop_id) {
                 continue;
@@ -380,6 +517,16 @@ async fn fetch_single_feed(
 
             let mins = ((arrival_ts - now_secs) / 60.0).max(0.0) as i32;
 
+            let delay_secs = stop_time.arrival.as_ref().and_then(|a| a.delay).or_else(|| {
+                let scheduled = schedule?.scheduled_arrival(
+                    trip.trip_id.as_deref()?,
+                    trip.start_date.as_deref(),
+                    stop_id,
+                )?;
+                let delay = (arrival_ts as i64).saturating_sub(scheduled);
+                Some(delay.clamp(i32::MIN as i64, i32::MAX as i64) as i32)
+            });
+
             // Direction from stop_id suffix
             let direction = if stop_id.ends_with('S') {
                 Direction::Downtown
@@ -391,11 +538,11 @@ async fn fetch_single_feed(
             let destination = trip_update
                 .stop_time_update
                 .iter()
+                .filter(served)
                 .max_by_key(|st| st.stop_sequence.unwrap_or(0))
                 .and_then(|st| st.stop_id.as_deref())
                 .and_then(crate::mta::stations::station_name_for_stop_id)
-                .unwrap_or("Unknown")
-                .to_string();
+                .unwrap_or_else(|| "Unknown".to_string());
 
             trains.push(Train {
                 route: route_id.to_string(),
@@ -405,14 +552,77 @@ async fn fetch_single_feed(
                 arrival_timestamp: arrival_ts,
                 direction,
                 stop_id: stop_id.to_string(),
+                delay_secs,
+                trip_id: trip.trip_id.clone().unwrap_or_default(),
             });
 
             break; // Only first matching stop per trip
         }
     }
 
-    debug!("Feed {} returned {} trains", url, trains.len());
-    Ok(trains)
+    (trains, route_trips)
+}
+
+/// Remaining stops of the trips in `trip_ids`, as the feed predicts them.
+/// Stops already passed, skipped, or without predictions are left out.
+pub fn trip_details(
+    feed: &transit_realtime::FeedMessage,
+    trip_ids: &HashSet<&str>,
+    now_secs: f64,
+) -> Vec<TripDetail> {
+    use transit_realtime::trip_update::stop_time_update::ScheduleRelationship as StopRelationship;
+
+    feed.entity
+        .iter()
+        .filter_map(|entity| entity.trip_update.as_ref())
+        .filter_map(|trip_update| {
+            let trip = &trip_update.trip;
+            let trip_id = trip.trip_id.as_deref().filter(|id| trip_ids.contains(id))?;
+            let route_id = trip.route_id.as_deref().unwrap_or("");
+            let stops: Vec<TripStop> = trip_update
+                .stop_time_update
+                .iter()
+                .filter(|st| {
+                    !matches!(
+                        st.schedule_relationship(),
+                        StopRelationship::Skipped | StopRelationship::NoData
+                    )
+                })
+                .map(|st| {
+                    let stop_id = st.stop_id.clone().unwrap_or_default();
+                    let arrival = st.arrival.as_ref().and_then(|e| e.time);
+                    let departure = st.departure.as_ref().and_then(|e| e.time);
+                    let track = st
+                        .nyct_stop_time_update
+                        .as_ref()
+                        .and_then(|n| n.actual_track.clone().or_else(|| n.scheduled_track.clone()));
+                    TripStop {
+                        station: crate::mta::stations::station_name_for_stop_id(&stop_id),
+                        arrival,
+                        departure,
+                        delay_secs: [&st.arrival, &st.departure]
+                            .into_iter()
+                            .find_map(|e| e.as_ref()?.delay),
+                        track,
+                        stop_id,
+                    }
+                })
+                .filter(|stop| stop.departure.or(stop.arrival).is_some_and(|ts| ts as f64 > now_secs))
+                .collect();
+            let direction = match stops.first() {
+                Some(stop) if stop.stop_id.ends_with('S') => Direction::Downtown,
+                _ => Direction::Uptown,
+            };
+            Some(TripDetail {
+                trip_id: trip_id.to_string(),
+                route: route_id.to_string(),
+                direction,
+                is_express: detect_express(trip, route_id),
+                delay_secs: stops.first().and_then(|stop| stop.delay_secs),
+                stops,
+            })
+        })
+        .collect()
 }
 
 /// Detect if a train is running express service.
@@ -429,19 +639,42 @@ fn detect_express(
         .unwrap_or(false)
 }
 
-/// Remove duplicate trains (same route/destination within same minute).
-fn deduplicate_trains(trains: Vec<Train>) -> Vec<Train> {
-    let mut unique = Vec::new();
-    let mut seen: HashSet<(String, String, i32)> = HashSet::new();
+/// An alert's details link: its English translation, else the first.
+fn alert_url(alert: &transit_realtime::Alert) -> Option<String> {
+    let translations = &alert.url.as_ref()?.translation;
+    translations
+        .iter()
+        .find(|t| t.language.as_deref() == Some("en"))
+        .or(translations.first())
+        .map(|t| t.text.trim().to_string())
+        .filter(|url| !url.is_empty())
+}
+
+/// How much a train tells beyond its arrival time; the richer of two
+/// duplicates is kept.
+fn richness(train: &Train) -> u8 {
+    u8::from(train.destination != "Unknown") * 2 + u8::from(train.delay_secs.is_some())
+}
+
+/// Merge duplicate trains: same route at the same platform, arriving within
+/// `window_secs` of each other (trains sorted by arrival).
+fn deduplicate_trains(trains: Vec<Train>, window_secs: f64) -> Vec<Train> {
+    let mut unique: Vec<Train> = Vec::new();
 
     for train in trains {
-        let key = (
-            train.route.clone(),
-            train.destination.clone(),
-            train.minutes,
-        );
-        if seen.insert(key) {
-            unique.push(train);
+        let duplicate = unique.iter_mut().rev().find(|kept| {
+            kept.route == train.route
+                && kept.direction == train.direction
+                && kept.stop_id == train.stop_id
+                && (train.arrival_timestamp - kept.arrival_timestamp).abs() <= window_secs
+        });
+        match duplicate {
+            Some(kept) => {
+                if richness(&train) > richness(kept) {
+                    *kept = train;
+                }
+            }
+            None => unique.push(train),
         }
     }
 
@@ -463,6 +696,8 @@ mod tests {
                 arrival_timestamp: 1000.0,
                 direction: Direction::Uptown,
                 stop_id: "127N".into(),
+                delay_secs: None,
+                trip_id: String::new(),
             },
             Train {
                 route: "1".into(),
@@ -472,6 +707,8 @@ mod tests {
                 arrival_timestamp: 1000.0,
                 direction: Direction::Uptown,
                 stop_id: "127N".into(),
+                delay_secs: None,
+                trip_id: String::new(),
             },
             Train {
                 route: "2".into(),
@@ -481,10 +718,29 @@ mod tests {
                 arrival_timestamp: 1120.0,
                 direction: Direction::Downtown,
                 stop_id: "127S".into(),
+                delay_secs: None,
+                trip_id: String::new(),
             },
         ];
-        let unique = deduplicate_trains(trains);
+        let unique = deduplicate_trains(trains.clone(), 45.0);
         assert_eq!(unique.len(), 2);
+
+        // Near-simultaneous copies merge, keeping the one with a destination
+        let mut near = trains[0].clone();
+        near.arrival_timestamp = 1030.0;
+        near.minutes = 4;
+        let mut unknown = trains[0].clone();
+        unknown.destination = "Unknown".into();
+        let unique = deduplicate_trains(vec![unknown.clone(), near.clone()], 45.0);
+        assert_eq!(unique.len(), 1);
+        assert_eq!(unique[0].destination, "Uptown");
+        assert_eq!(unique[0].arrival_timestamp, 1030.0);
+
+        // ...but not beyond the window or at another platform
+        assert_eq!(deduplicate_trains(vec![unknown.clone(), near.clone()], 20.0).len(), 2);
+        let mut other_platform = near;
+        other_platform.stop_id = "725N".into();
+        assert_eq!(deduplicate_trains(vec![unknown, other_platform], 45.0).len(), 2);
     }
 
     #[test]
@@ -512,6 +768,25 @@ mod tests {
         assert!(!detect_express(&route_1, "1"));
     }
 
+    #[test]
+    fn test_alert_url() {
+        use transit_realtime::translated_string::Translation;
+        let translation = |text: &str, language: Option<&str>| Translation {
+            text: text.into(),
+            language: language.map(Into::into),
+        };
+        let alert = |translation: Vec<Translation>| transit_realtime::Alert {
+            url: Some(transit_realtime::TranslatedString { translation }),
+            ..Default::default()
+        };
+
+        let both = alert(vec![translation("https://mta.info/es", Some("es")), translation("https://mta.info/en", Some("en"))]);
+        assert_eq!(alert_url(&both).as_deref(), Some("https://mta.info/en"));
+        assert_eq!(alert_url(&alert(vec![translation(" https://mta.info ", None)])).as_deref(), Some("https://mta.info"));
+        assert_eq!(alert_url(&alert(vec![translation("", None)])), None);
+        assert_eq!(alert_url(&transit_realtime::Alert::default()), None);
+    }
+
     #[test]
     fn test_client_creation() {
         let client = MtaClient::new().unwrap();
@@ -520,6 +795,16 @@ mod tests {
         assert!(client.backoff.is_empty());
     }
 
+    #[test]
+    fn test_degraded_routes() {
+        let mut client = MtaClient::new().unwrap();
+        let routes: HashSet<String> = ["1", "2", "N", "Q"].iter().map(|r| r.to_string()).collect();
+        assert!(client.degraded_routes(&routes).is_empty());
+
+        client.failed_feeds.insert(feeds::feed_url_for_route("N").unwrap());
+        assert_eq!(client.degraded_routes(&routes), vec!["N", "Q"], "only the NQRW feed is down");
+    }
+
     #[test]
     fn test_backoff_logic() {
         let mut client = MtaClient::new().unwrap();
@@ -532,4 +817,185 @@ mod tests {
         client.record_success("test");
         assert!(client.should_fetch("test"));
     }
+
+    #[test]
+    fn test_cancelled_trips_and_skipped_stops() {
+        use transit_realtime::trip_descriptor::ScheduleRelationship as TripRelationship;
+        use transit_realtime::trip_update::stop_time_update::ScheduleRelationship as StopRelationship;
+        use transit_realtime::trip_update::{StopTimeEvent, StopTimeUpdate};
+
+        let stop = |id: &str, seq: u32, time: i64, rel: StopRelationship| StopTimeUpdate {
+            stop_sequence: Some(seq),
+            stop_id: Some(id.into()),
+            arrival: Some(StopTimeEvent { time: Some(time), ..Default::default() }),
+            schedule_relationship: Some(rel as i32),
+            ..Default::default()
+        };
+        let entity = |id: &str, rel: TripRelationship, stops: Vec<StopTimeUpdate>| transit_realtime::FeedEntity {
+            id: id.into(),
+            trip_update: Some(transit_realtime::TripUpdate {
+                trip: transit_realtime::TripDescriptor {
+                    trip_id: Some(id.into()),
+                    route_id: Some("1".into()),
+                    schedule_relationship: Some(rel as i32),
+                    ..Default::default()
+                },
+                stop_time_update: stops,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let feed = transit_realtime::FeedMessage {
+            header: transit_realtime::FeedHeader {
+                gtfs_realtime_version: "2.0".into(),
+                ..Default::default()
+            },
+            entity: vec![
+                entity("running", TripRelationship::Scheduled, vec![
+                    stop("127N", 1, 1300, StopRelationship::Scheduled),
+                    stop("101N", 2, 2000, StopRelationship::Scheduled),
+                    stop("103N", 3, 2100, StopRelationship::Skipped),
+                ]),
+                entity("cancelled", TripRelationship::Canceled, vec![
+                    stop("127N", 1, 1400, StopRelationship::Scheduled),
+                ]),
+                entity("skipping", TripRelationship::Scheduled, vec![
+                    stop("127N", 1, 1500, StopRelationship::Skipped),
+                    stop("101N", 2, 2200, StopRelationship::Scheduled),
+                ]),
+                entity("no_data", TripRelationship::Scheduled, vec![
+                    stop("127N", 1, 1600, StopRelationship::NoData),
+                ]),
+            ],
+        };
+        let routes: HashSet<String> = ["1".to_string()].into();
+        let (trains, route_trips) = trains_from_feed(&feed, &["127N".to_string()], &routes, 1000.0, None);
+        assert_eq!(trains.len(), 1);
+        assert_eq!(trains[0].arrival_timestamp, 1300.0);
+        assert_eq!(trains[0].destination, "Van Cortlandt Park-242 St", "skipped terminal isn't the destination");
+        assert_eq!(route_trips, 3, "cancelled trips don't count");
+        assert_eq!(trains[0].delay_secs, None);
+
+        // A stated delay is passed through
+        let mut delayed = feed.clone();
+        let update = delayed.entity[0].trip_update.as_mut().unwrap();
+        update.stop_time_update[0].arrival.as_mut().unwrap().delay = Some(420);
+        update.stop_time_update[0].nyct_stop_time_update = Some(transit_realtime::NyctStopTimeUpdate {
+            scheduled_track: Some("1".into()),
+            actual_track: Some("3".into()),
+        });
+        let (trains, _) = trains_from_feed(&delayed, &["127N".to_string()], &routes, 1000.0, None);
+        assert_eq!(trains[0].delay_secs, Some(420));
+
+        // The displayed train's trip: its remaining served stops
+        assert_eq!(trains[0].trip_id, "running");
+        let details = trip_details(&delayed, &HashSet::from(["running"]), 1000.0);
+        assert_eq!(details.len(), 1);
+        let stops: Vec<&str> = details[0].stops.iter().map(|s| s.stop_id.as_str()).collect();
+        assert_eq!(stops, ["127N", "101N"], "skipped stops left out");
+        assert_eq!(details[0].delay_secs, Some(420));
+        assert_eq!(details[0].stops[1].station.as_deref(), Some("Van Cortlandt Park-242 St"));
+        assert_eq!(details[0].stops[0].track.as_deref(), Some("3"), "actual track over scheduled");
+        assert_eq!(details[0].stops[1].track, None);
+        let later = trip_details(&delayed, &HashSet::from(["running"]), 1500.0);
+        assert_eq!(later[0].stops.len(), 1, "passed stops left out");
+    }
+
+    use proptest::prelude::*;
+
+    /// Stop time updates with arbitrary times, delays and (possibly
+    /// undefined) schedule relationships.
+    fn any_stop_time() -> impl Strategy<Value = transit_realtime::trip_update::StopTimeUpdate> {
+        use transit_realtime::trip_update::{StopTimeEvent, StopTimeUpdate};
+        let event = (any::<Option<i64>>(), any::<Option<i32>>())
+            .prop_map(|(time, delay)| StopTimeEvent { time, delay, ..Default::default() });
+        (
+            any::<Option<u32>>(),
+            prop::option::of(prop_oneof![Just("127N".to_string()), Just("127S".to_string()), "\\PC{0,5}"]),
+            prop::option::of(event),
+            prop::option::of(-2i32..6),
+        )
+            .prop_map(|(stop_sequence, stop_id, arrival, schedule_relationship)| StopTimeUpdate {
+                stop_sequence,
+                stop_id,
+                arrival,
+                schedule_relationship,
+                ..Default::default()
+            })
+    }
+
+    fn any_entity() -> impl Strategy<Value = transit_realtime::FeedEntity> {
+        (
+            prop::option::of(prop_oneof![Just("1".to_string()), Just("6X".to_string()), "\\PC{0,3}"]),
+            prop::option::of(prop_oneof![Just("036000_1..N03R".to_string()), "[0-9_.NSX]{0,12}", "\\PC{0,8}"]),
+            prop::option::of(prop_oneof![Just("20260302".to_string()), "\\PC{0,8}"]),
+            prop::option::of(-2i32..8),
+            prop::collection::vec(any_stop_time(), 0..6),
+        )
+            .prop_map(|(route_id, trip_id, start_date, relationship, stop_time_update)| {
+                transit_realtime::FeedEntity {
+                    id: "e".into(),
+                    trip_update: Some(transit_realtime::TripUpdate {
+                        trip: transit_realtime::TripDescriptor {
+                            trip_id,
+                            route_id,
+                            start_date,
+                            schedule_relationship: relationship,
+                            ..Default::default()
+                        },
+                        stop_time_update,
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                }
+            })
+    }
+
+    proptest! {
+        /// Malformed feeds yield fewer trains, never a panic, and every
+        /// train is upcoming at a requested stop on a requested route.
+        #[test]
+        fn prop_trains_from_feed(
+            entity in prop::collection::vec(any_entity(), 0..8),
+            now in prop_oneof![Just(1_772_470_000.0f64), any::<i64>().prop_map(|t| t as f64)],
+            times in prop::collection::vec("[0-9]{1,10}:[0-9]{1,3}:[0-9]{1,3}", 2),
+        ) {
+            let stop_times = format!(
+                "trip_id,arrival_time,departure_time,stop_id,stop_sequence\nT_036000_1..N03R,{0},{0},127N,1\nT_036000_1..N03R,{1},{1},101N,2\n",
+                times[0], times[1]
+            );
+            let schedule = ServiceSchedule::parse(
+                Some("service_id,monday,tuesday,wednesday,thursday,friday,saturday,sunday,start_date,end_date\nW,1,1,1,1,1,1,1,20260101,20261231\n".as_bytes()),
+                None::<&[u8]>,
+                "route_id,trip_id,service_id\n1,T_036000_1..N03R,W\n".as_bytes(),
+                stop_times.as_bytes(),
+                &["127N".to_string()].into(),
+            )
+            .unwrap();
+
+            let feed = transit_realtime::FeedMessage {
+                header: transit_realtime::FeedHeader { gtfs_realtime_version: "2.0".into(), ..Default::default() },
+                entity,
+            };
+            let stop_ids = ["127N".to_string(), "127S".to_string()];
+            let routes: HashSet<String> = ["1".to_string(), "6X".to_string()].into();
+            let (trains, route_trips) = trains_from_feed(&feed, &stop_ids, &routes, now, Some(&schedule));
+            prop_assert!(trains.len() <= route_trips);
+            for train in &trains {
+                prop_assert!(routes.contains(&train.route));
+                prop_assert!(stop_ids.contains(&train.stop_id));
+                prop_assert!(train.arrival_timestamp > now);
+                prop_assert!(train.minutes >= 0);
+            }
+            deduplicate_trains(trains, DEFAULT_DEDUP_WINDOW_SECS as f64);
+        }
+
+        #[test]
+        fn prop_decode_arbitrary_bytes(bytes in prop::collection::vec(any::<u8>(), 0..256)) {
+            if let Ok(feed) = transit_realtime::FeedMessage::decode(bytes.as_slice()) {
+                let routes: HashSet<String> = ["1".to_string()].into();
+                trains_from_feed(&feed, &["127N".to_string()], &routes, 0.0, None);
+            }
+        }
+    }
 }
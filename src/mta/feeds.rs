@@ -32,6 +32,11 @@ pub(crate) fn feed_id_for_route(route: &str) -> Option<&'static str> {
 pub(crate) const MTA_FEED_BASE_URL: &str =
     "https://api-endpoint.mta.info/Dataservice/mtagtfsfeeds/nyct%2Fgtfs";
 
+/// Feed URL carrying a route.
+pub(crate) fn feed_url_for_route(route: &str) -> Option<String> {
+    feed_id_for_route(route).map(|suffix| format!("{}{}", MTA_FEED_BASE_URL, suffix))
+}
+
 /// Returns deduplicated feed URLs needed for a set of routes.
 pub fn feed_urls_for_routes(routes: &[String]) -> Vec<String> {
     let mut seen = std::collections::HashSet::new();
@@ -0,0 +1,172 @@
+//! The feed benchmark's fixture, `benches/fixtures/gtfs-ace.pb`: a
+//! synthetic feed the size and shape of the MTA's A/C/E feed, built from
+//! the station database.
+//!
+//! The test checks the checked-in file is what `feed` builds. After
+//! changing it, regenerate with `UPDATE_FIXTURE=1 cargo test fixture`.
+
+use std::path::PathBuf;
+
+use prost::Message;
+
+use super::client::transit_realtime::trip_update::{StopTimeEvent, StopTimeUpdate};
+use super::client::transit_realtime::{
+    FeedEntity, FeedHeader, FeedMessage, TripDescriptor, TripUpdate, VehiclePosition,
+};
+use super::stations;
+
+/// Header timestamp; the bench judges arrivals against it.
+const FEED_TIME: u64 = 1_760_000_000;
+
+/// Service date of every trip.
+const START_DATE: &str = "20251009";
+
+/// Routes in the feed: feed route ID, the station database's name for it,
+/// the stop ID prefixes it runs over, and how many trips it has.
+const ROUTES: [(&str, &str, &str, usize); 5] = [
+    ("A", "A", "AH", 90),
+    ("C", "C", "A", 45),
+    ("E", "E", "AEFG", 60),
+    ("H", "S", "H", 12),
+    ("FS", "S", "S", 8),
+];
+
+/// Minutes between trip origins.
+const HEADWAY_MINUTES: usize = 7;
+
+/// Seconds between stops, and how long a train dwells at each.
+const RUN_SECS: i64 = 75;
+const DWELL_SECS: i64 = 20;
+
+/// Stop IDs (without direction) along `route`, in stop ID order.
+fn route_stops(db_route: &str, prefixes: &str) -> Vec<String> {
+    let mut stops: Vec<String> = stations::get_station_database()
+        .iter()
+        .filter(|s| s.routes.iter().any(|r| r == db_route))
+        .flat_map(|s| s.stop_ids.iter())
+        .filter(|id| id.starts_with(|c| prefixes.contains(c)))
+        .map(|id| id[..id.len() - 1].to_string())
+        .collect();
+    stops.sort();
+    stops.dedup();
+    stops
+}
+
+fn event(time: i64) -> Option<StopTimeEvent> {
+    Some(StopTimeEvent {
+        time: Some(time),
+        ..Default::default()
+    })
+}
+
+/// Trip `n` of `route`: southbound trips run in stop order, northbound ones
+/// the other way, each part of the way along already.
+fn trip(route: &str, stops: &[String], n: usize, id: &mut usize) -> [FeedEntity; 2] {
+    let south = n.is_multiple_of(2);
+    let direction = if south { 'S' } else { 'N' };
+    let minutes = n * HEADWAY_MINUTES;
+    let descriptor = TripDescriptor {
+        trip_id: Some(format!("{:06}_{}..{}{:02}R", (20 * 60 + minutes) * 100, route, direction, n % 100)),
+        route_id: Some(route.to_string()),
+        start_time: Some(format!("{:02}:{:02}:00", 20 + minutes / 60, minutes % 60)),
+        start_date: Some(START_DATE.to_string()),
+        ..Default::default()
+    };
+
+    let mut ahead: Vec<&String> = stops.iter().collect();
+    if !south {
+        ahead.reverse();
+    }
+    let passed = (n * HEADWAY_MINUTES) % ahead.len();
+    let ahead = &ahead[passed..];
+    let mut time = FEED_TIME as i64 + 20 + 30 * (n % 5) as i64;
+    let stop_time_update = ahead
+        .iter()
+        .enumerate()
+        .map(|(i, stop)| {
+            let first = i == 0;
+            let last = i == ahead.len() - 1;
+            if !first {
+                time += RUN_SECS;
+            }
+            let arrival = (!first).then_some(time).and_then(event);
+            if !first && !last {
+                time += DWELL_SECS;
+            }
+            StopTimeUpdate {
+                stop_id: Some(format!("{}{}", stop, direction)),
+                arrival,
+                departure: (!last).then_some(time).and_then(event),
+                ..Default::default()
+            }
+        })
+        .collect();
+
+    let mut entity = |trip_update, vehicle| {
+        *id += 1;
+        FeedEntity {
+            id: format!("{:06}", id),
+            trip_update,
+            vehicle,
+            ..Default::default()
+        }
+    };
+    [
+        entity(
+            Some(TripUpdate {
+                trip: descriptor.clone(),
+                stop_time_update,
+                ..Default::default()
+            }),
+            None,
+        ),
+        entity(
+            None,
+            Some(VehiclePosition {
+                trip: Some(descriptor),
+                current_stop_sequence: Some(passed as u32),
+                stop_id: Some(format!("{}{}", ahead[0], direction)),
+                timestamp: Some(FEED_TIME - 40),
+                ..Default::default()
+            }),
+        ),
+    ]
+}
+
+/// The fixture feed: a trip update and vehicle position per trip.
+fn feed() -> FeedMessage {
+    let mut id = 0;
+    let mut entity = Vec::new();
+    for (route, db_route, prefixes, trips) in ROUTES {
+        let stops = route_stops(db_route, prefixes);
+        for n in 0..trips {
+            entity.extend(trip(route, &stops, n, &mut id));
+        }
+    }
+    FeedMessage {
+        header: FeedHeader {
+            gtfs_realtime_version: "1.0".to_string(),
+            timestamp: Some(FEED_TIME),
+            ..Default::default()
+        },
+        entity,
+    }
+}
+
+fn fixture_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("benches/fixtures/gtfs-ace.pb")
+}
+
+#[test]
+fn test_bench_feed_fixture() {
+    let bytes = feed().encode_to_vec();
+    if std::env::var_os("UPDATE_FIXTURE").is_some() {
+        std::fs::write(fixture_path(), &bytes).unwrap();
+        return;
+    }
+    let checked_in = std::fs::read(fixture_path()).unwrap();
+    assert!(
+        checked_in == bytes,
+        "benches/fixtures/gtfs-ace.pb is out of date; regenerate with UPDATE_FIXTURE=1 cargo test fixture"
+    );
+}
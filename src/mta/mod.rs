@@ -1,4 +1,7 @@
 pub mod alerts;
 pub mod client;
 pub mod feeds;
+#[cfg(test)]
+mod fixture;
+pub mod schedule;
 pub mod stations;
@@ -0,0 +1,555 @@
+//! Static GTFS schedule: which routes have scheduled service right now.
+//!
+//! The MTA publishes its timetable as a static GTFS zip. From it we keep, per
+//! route, the service calendars it runs on and the times of day its trips
+//! cover, so routes with no service (the B at night, the W on weekends)
+//! can be dropped from fetches without hand-written `route_schedule` windows.
+//! Scheduled times at the configured stops are kept too, for comparing
+//! against predictions (see `scheduled_arrival`).
+
+use std::collections::{HashMap, HashSet};
+use std::io::Read;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use chrono::{Datelike, NaiveDate, NaiveDateTime, Timelike};
+use serde::Deserialize;
+use tokio::io::AsyncWriteExt;
+
+use crate::timezone::Timezone;
+
+/// A route counts as running this long before its first scheduled trip
+/// starts, so upcoming trains are fetched in time to show.
+const LEAD_SECS: u32 = 30 * 60;
+
+/// ...and this long after its last trip ends (late trains).
+const GRACE_SECS: u32 = 15 * 60;
+
+/// Gaps between trips shorter than this don't break a span of service.
+const MERGE_GAP_SECS: u32 = 60 * 60;
+
+/// One service calendar (`calendar.txt` row plus `calendar_dates.txt`
+/// exceptions).
+#[derive(Debug, Default)]
+struct Service {
+    /// Monday first.
+    weekdays: [bool; 7],
+    start: Option<NaiveDate>,
+    end: Option<NaiveDate>,
+    added: HashSet<NaiveDate>,
+    removed: HashSet<NaiveDate>,
+}
+
+impl Service {
+    fn runs_on(&self, date: NaiveDate) -> bool {
+        if self.removed.contains(&date) {
+            return false;
+        }
+        if self.added.contains(&date) {
+            return true;
+        }
+        let in_range = self.start.is_some_and(|s| date >= s) && self.end.is_some_and(|e| date <= e);
+        in_range && self.weekdays[date.weekday().num_days_from_monday() as usize]
+    }
+}
+
+/// Merged spans of service, as seconds after midnight of the service day
+/// (past 24:00 for trips running after midnight).
+type Spans = Vec<(u32, u32)>;
+
+/// Scheduled seconds after midnight per stop, for one trip.
+type StopTimes = HashMap<String, u32>;
+
+/// Scheduled service per route, from a static GTFS feed.
+#[derive(Debug, Default)]
+pub struct ServiceSchedule {
+    services: Vec<Service>,
+    /// Route → (service index, its spans) per calendar the route runs on.
+    routes: HashMap<String, Vec<(usize, Spans)>>,
+    /// Trip key (see `trip_key`) → (service index, times at the stops
+    /// parsed for) per calendar the trip runs on.
+    stop_times: HashMap<String, Vec<(usize, StopTimes)>>,
+    /// Zone the GTFS times are wall-clock times in.
+    timezone: Timezone,
+}
+
+#[derive(Deserialize)]
+struct CalendarRow {
+    service_id: String,
+    monday: u8,
+    tuesday: u8,
+    wednesday: u8,
+    thursday: u8,
+    friday: u8,
+    saturday: u8,
+    sunday: u8,
+    start_date: String,
+    end_date: String,
+}
+
+#[derive(Deserialize)]
+struct CalendarDateRow {
+    service_id: String,
+    date: String,
+    exception_type: u8,
+}
+
+#[derive(Deserialize)]
+struct TripRow {
+    route_id: String,
+    service_id: String,
+    trip_id: String,
+}
+
+#[derive(Deserialize)]
+struct StopTimeRow {
+    trip_id: String,
+    stop_id: String,
+    arrival_time: Option<String>,
+    departure_time: Option<String>,
+}
+
+fn parse_date(s: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(s.trim(), "%Y%m%d").ok()
+}
+
+/// Longest GTFS time accepted, in hours; far beyond any real trip, small
+/// enough that span arithmetic can't overflow.
+const MAX_TIME_HOURS: u32 = 240;
+
+/// GTFS time ("25:10:00" is 1:10 the next morning) as seconds.
+fn parse_time(s: &str) -> Option<u32> {
+    let mut parts = s.trim().split(':').map(|p| p.parse::<u32>().ok());
+    let (h, m, sec) = (parts.next()??, parts.next()??, parts.next()??);
+    (h <= MAX_TIME_HOURS && m < 60 && sec < 60).then(|| h * 3600 + m * 60 + sec)
+}
+
+/// Trip ID reduced to what static and realtime IDs share: the origin time
+/// and route/direction, e.g. "AFA25GEN-1037-Weekday-00_036000_1..S03R" and
+/// "036000_1..S" both give "036000_1..S".
+fn trip_key(trip_id: &str) -> Option<String> {
+    let mut parts = trip_id.rsplit('_');
+    let path = parts.next()?;
+    let origin = parts.next()?;
+    let path = match path.find("..") {
+        Some(i) => path.get(..i + 3).unwrap_or(path),
+        None => path,
+    };
+    Some(format!("{}_{}", origin, path))
+}
+
+/// Unix time of GTFS time `secs` on service date `date` (measured from noon
+/// minus 12 hours, so DST changes don't shift it), in `tz`.
+fn service_time(date: NaiveDate, secs: u32, tz: Timezone) -> Option<i64> {
+    let noon = tz.timestamp(date.and_hms_opt(12, 0, 0)?)?;
+    Some(noon - 12 * 3600 + secs as i64)
+}
+
+/// Static route IDs for express variants ("6X", "FX") fold into the route.
+fn base_route(route_id: &str) -> &str {
+    match route_id.strip_suffix('X') {
+        Some(base) if !base.is_empty() => base,
+        _ => route_id,
+    }
+}
+
+fn rows<T: serde::de::DeserializeOwned>(
+    name: &str,
+    reader: impl Read,
+) -> impl Iterator<Item = Result<T, String>> {
+    let name = name.to_string();
+    csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(reader)
+        .into_deserialize()
+        .map(move |r| r.map_err(|e| format!("{}: {}", name, e)))
+}
+
+/// `ServiceSchedule::parse` a file at a time, so each can be streamed
+/// straight out of the zip.
+#[derive(Default)]
+struct Parser {
+    services: Vec<Service>,
+    service_ids: HashMap<String, usize>,
+    /// trip → (route, service)
+    trip_keys: HashMap<String, (String, usize)>,
+    /// trip → first/last stop time
+    trip_spans: HashMap<String, (u32, u32)>,
+    /// trip → times at our stops
+    at_stops: HashMap<String, StopTimes>,
+}
+
+impl Parser {
+    fn service_index(&mut self, id: String) -> usize {
+        let services = &mut self.services;
+        *self.service_ids.entry(id).or_insert_with(|| {
+            services.push(Service::default());
+            services.len() - 1
+        })
+    }
+
+    fn calendar(&mut self, reader: impl Read) -> Result<(), String> {
+        for row in rows::<CalendarRow>("calendar.txt", reader) {
+            let row = row?;
+            let i = self.service_index(row.service_id);
+            let service = &mut self.services[i];
+            service.weekdays = [
+                row.monday, row.tuesday, row.wednesday, row.thursday,
+                row.friday, row.saturday, row.sunday,
+            ]
+            .map(|d| d == 1);
+            service.start = parse_date(&row.start_date);
+            service.end = parse_date(&row.end_date);
+        }
+        Ok(())
+    }
+
+    fn calendar_dates(&mut self, reader: impl Read) -> Result<(), String> {
+        for row in rows::<CalendarDateRow>("calendar_dates.txt", reader) {
+            let row = row?;
+            let Some(date) = parse_date(&row.date) else { continue };
+            let i = self.service_index(row.service_id);
+            match row.exception_type {
+                1 => self.services[i].added.insert(date),
+                2 => self.services[i].removed.insert(date),
+                _ => continue,
+            };
+        }
+        Ok(())
+    }
+
+    fn trips(&mut self, reader: impl Read) -> Result<(), String> {
+        for row in rows::<TripRow>("trips.txt", reader) {
+            let row = row?;
+            let i = self.service_index(row.service_id);
+            self.trip_keys.insert(row.trip_id, (base_route(&row.route_id).to_string(), i));
+        }
+        Ok(())
+    }
+
+    /// One row at a time: only each trip's span and its times at `stops`
+    /// are kept.
+    fn stop_times(&mut self, reader: impl Read, stops: &HashSet<String>) -> Result<(), String> {
+        for row in rows::<StopTimeRow>("stop_times.txt", reader) {
+            let row = row?;
+            let times = [&row.arrival_time, &row.departure_time].map(|t| t.as_deref().and_then(parse_time));
+            let (Some(first), Some(last)) = (times.iter().flatten().min(), times.iter().flatten().max()) else {
+                continue;
+            };
+            if stops.contains(&row.stop_id) {
+                self.at_stops.entry(row.trip_id.clone()).or_default().insert(row.stop_id, *first);
+            }
+            match self.trip_spans.get_mut(&row.trip_id) {
+                Some(span) => *span = (span.0.min(*first), span.1.max(*last)),
+                None => {
+                    self.trip_spans.insert(row.trip_id, (*first, *last));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(mut self) -> ServiceSchedule {
+        let mut stop_times: HashMap<String, Vec<(usize, StopTimes)>> = HashMap::new();
+        for (trip, times) in self.at_stops {
+            if let (Some(key), Some((_, service))) = (trip_key(&trip), self.trip_keys.get(&trip)) {
+                stop_times.entry(key).or_default().push((*service, times));
+            }
+        }
+
+        let mut grouped: HashMap<(String, usize), Spans> = HashMap::new();
+        for (trip, span) in self.trip_spans {
+            if let Some(key) = self.trip_keys.remove(&trip) {
+                grouped.entry(key).or_default().push(span);
+            }
+        }
+        let mut routes: HashMap<String, Vec<(usize, Spans)>> = HashMap::new();
+        for ((route, service), mut spans) in grouped {
+            spans.sort_unstable();
+            let mut merged: Spans = Vec::new();
+            for (start, end) in spans {
+                match merged.last_mut() {
+                    Some(last) if start <= last.1 + MERGE_GAP_SECS => last.1 = last.1.max(end),
+                    _ => merged.push((start, end)),
+                }
+            }
+            routes.entry(route).or_default().push((service, merged));
+        }
+
+        ServiceSchedule {
+            services: self.services,
+            routes,
+            stop_times,
+            timezone: Timezone::System,
+        }
+    }
+}
+
+impl ServiceSchedule {
+    /// Parse the GTFS files that matter, keeping scheduled times at `stops`.
+    /// `calendar` or `calendar_dates` may be missing (feeds may use either
+    /// alone).
+    pub fn parse(
+        calendar: Option<impl Read>,
+        calendar_dates: Option<impl Read>,
+        trips: impl Read,
+        stop_times: impl Read,
+        stops: &HashSet<String>,
+    ) -> Result<Self, String> {
+        let mut parser = Parser::default();
+        if let Some(calendar) = calendar {
+            parser.calendar(calendar)?;
+        }
+        if let Some(calendar_dates) = calendar_dates {
+            parser.calendar_dates(calendar_dates)?;
+        }
+        parser.trips(trips)?;
+        parser.stop_times(stop_times, stops)?;
+        Ok(parser.finish())
+    }
+
+    /// Read GTFS times (and "today") in `timezone` rather than the system zone.
+    pub fn with_timezone(mut self, timezone: Timezone) -> Self {
+        self.timezone = timezone;
+        self
+    }
+
+    /// Number of routes and service calendars loaded.
+    pub fn counts(&self) -> (usize, usize) {
+        (self.routes.len(), self.services.len())
+    }
+
+    /// Parse a static GTFS zip on disk, keeping times at `stops`. Blocking.
+    /// Each file is decompressed as it's parsed, never held whole (the
+    /// MTA's stop_times.txt is over 100 MB).
+    pub fn from_zip(path: &Path, stops: &HashSet<String>) -> Result<Self, String> {
+        let file = std::fs::File::open(path).map_err(|e| format!("open {}: {}", path.display(), e))?;
+        let mut zip = zip::ZipArchive::new(std::io::BufReader::new(file))
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+        let mut parser = Parser::default();
+        if let Some(calendar) = zip_entry(&mut zip, "calendar.txt")? {
+            parser.calendar(calendar)?;
+        }
+        if let Some(calendar_dates) = zip_entry(&mut zip, "calendar_dates.txt")? {
+            parser.calendar_dates(calendar_dates)?;
+        }
+        parser.trips(zip_entry(&mut zip, "trips.txt")?.ok_or("trips.txt missing")?)?;
+        parser.stop_times(zip_entry(&mut zip, "stop_times.txt")?.ok_or("stop_times.txt missing")?, stops)?;
+        Ok(parser.finish())
+    }
+
+    /// Scheduled arrival (unix secs) of a realtime trip at `stop_id`.
+    /// `start_date` ("YYYYMMDD", from the trip descriptor) picks between
+    /// calendars; without it, today's is assumed.
+    pub fn scheduled_arrival(&self, trip_id: &str, start_date: Option<&str>, stop_id: &str) -> Option<i64> {
+        let date = match start_date {
+            Some(d) => parse_date(d)?,
+            None => self.timezone.today(),
+        };
+        self.stop_times
+            .get(&trip_key(trip_id)?)?
+            .iter()
+            .find(|(service, _)| self.services[*service].runs_on(date))
+            .and_then(|(_, times)| times.get(stop_id))
+            .and_then(|&secs| service_time(date, secs, self.timezone))
+    }
+
+    /// Whether `route` has scheduled service around local time `now`;
+    /// `None` if the feed doesn't know the route or doesn't cover the date
+    /// (an expired download says nothing about today).
+    pub fn is_scheduled(&self, route: &str, now: NaiveDateTime) -> Option<bool> {
+        let today = now.date();
+        if !self.services.iter().any(|s| s.runs_on(today)) {
+            return None;
+        }
+        let yesterday = today.pred_opt()?;
+        let secs = now.num_seconds_from_midnight();
+        let covers = |spans: &[(u32, u32)], t: u32| {
+            spans.iter().any(|&(start, end)| t + LEAD_SECS >= start && t <= end + GRACE_SECS)
+        };
+        let spans = self.routes.get(route)?;
+        Some(spans.iter().any(|(service, spans)| {
+            let service = &self.services[*service];
+            (service.runs_on(today) && covers(spans, secs))
+                || (service.runs_on(yesterday) && covers(spans, secs + 86_400))
+        }))
+    }
+
+    /// Configured routes the schedule says aren't running at `now`.
+    pub fn unscheduled(&self, routes: &[String], now: NaiveDateTime) -> Vec<String> {
+        routes
+            .iter()
+            .filter(|r| self.is_scheduled(r, now) == Some(false))
+            .cloned()
+            .collect()
+    }
+}
+
+/// The zip entry `name`, if present.
+fn zip_entry<'a, R: Read + std::io::Seek>(
+    zip: &'a mut zip::ZipArchive<R>,
+    name: &str,
+) -> Result<Option<zip::read::ZipFile<'a>>, String> {
+    match zip.by_name(name) {
+        Ok(entry) => Ok(Some(entry)),
+        Err(zip::result::ZipError::FileNotFound) => Ok(None),
+        Err(e) => Err(format!("{}: {}", name, e)),
+    }
+}
+
+/// Whether the cached zip is missing or older than `max_age`.
+pub fn cache_is_stale(path: &Path, max_age: Duration) -> bool {
+    std::fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| SystemTime::now().duration_since(t).ok())
+        .is_none_or(|age| age > max_age)
+}
+
+/// Download the static feed to `path` (via a temp file, so a failed download
+/// keeps the previous copy), a chunk at a time.
+pub async fn download(url: &str, path: &Path) -> Result<(), String> {
+    let http = reqwest::Client::builder()
+        .user_agent("NYC-SubwaySign-Rust/1.0")
+        .timeout(Duration::from_secs(300))
+        .build()
+        .map_err(|e| format!("HTTP client: {}", e))?;
+    let mut response = http
+        .get(url)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("GET {}: {}", url, e))?;
+    let tmp = path.with_extension("zip.tmp");
+    let write_err = |e: std::io::Error| format!("write {}: {}", tmp.display(), e);
+    let mut file = tokio::fs::File::create(&tmp).await.map_err(write_err)?;
+    while let Some(chunk) = response.chunk().await.map_err(|e| format!("GET {}: {}", url, e))? {
+        file.write_all(&chunk).await.map_err(write_err)?;
+    }
+    file.flush().await.map_err(write_err)?;
+    drop(file);
+    tokio::fs::rename(&tmp, path)
+        .await
+        .map_err(|e| format!("rename {}: {}", tmp.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CALENDAR: &str = "service_id,monday,tuesday,wednesday,thursday,friday,saturday,sunday,start_date,end_date
+Weekday,1,1,1,1,1,0,0,20260101,20261231
+Weekend,0,0,0,0,0,1,1,20260101,20261231
+";
+    const CALENDAR_DATES: &str = "service_id,date,exception_type
+Weekday,20260525,2
+Weekend,20260525,1
+";
+    const TRIPS: &str = "route_id,trip_id,service_id,trip_headsign
+B,B1,Weekday,\"Brighton Beach, Bklyn\"
+B,B2,Weekday,Bedford Park Blvd
+D,D1,Weekday,Coney Island
+D,D2,Weekend,Coney Island
+6X,6X1,Weekday,Brooklyn Bridge
+";
+    const STOP_TIMES: &str = "trip_id,arrival_time,departure_time,stop_id,stop_sequence
+B1,06:00:00,06:00:00,D40N,1
+B1,07:00:00,07:00:00,D14N,2
+B2,21:30:00,21:30:00,D40N,1
+B2,22:30:00,22:30:00,D14N,2
+D1,23:30:00,23:30:00,D43N,1
+D1,25:10:00,25:10:00,D14N,2
+D2,12:00:00,12:00:00,D43N,1
+D2,13:00:00,13:00:00,D14N,2
+6X1,07:00:00,07:00:00,601N,1
+6X1,07:40:00,07:40:00,640N,2
+";
+    /// A trip with a full static trip ID, for matching realtime trips.
+    const TIMETABLE_TRIPS: &str = "route_id,trip_id,service_id,trip_headsign
+B,BFA26GEN-2038-Weekday-00_036000_B..N46R,Weekday,Bedford Park Blvd
+";
+    const TIMETABLE_STOP_TIMES: &str = "trip_id,arrival_time,departure_time,stop_id,stop_sequence
+BFA26GEN-2038-Weekday-00_036000_B..N46R,06:00:00,06:00:00,D40N,1
+BFA26GEN-2038-Weekday-00_036000_B..N46R,07:00:00,07:00:00,D14N,2
+";
+
+    fn parse(trips: &str, stop_times: &str) -> ServiceSchedule {
+        ServiceSchedule::parse(
+            Some(CALENDAR.as_bytes()),
+            Some(CALENDAR_DATES.as_bytes()),
+            trips.as_bytes(),
+            stop_times.as_bytes(),
+            &["D14N".to_string()].into(),
+        )
+        .unwrap()
+    }
+
+    fn schedule() -> ServiceSchedule {
+        parse(TRIPS, STOP_TIMES)
+    }
+
+    fn at(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    #[test]
+    fn test_routes_follow_calendar_and_time_of_day() {
+        let s = schedule();
+        // 2026-03-06 is a Friday
+        assert_eq!(s.is_scheduled("B", at("2026-03-06 12:00")), Some(false), "gap between trips");
+        assert_eq!(s.is_scheduled("B", at("2026-03-06 05:45")), Some(true), "shortly before first trip");
+        assert_eq!(s.is_scheduled("B", at("2026-03-06 22:00")), Some(true));
+        assert_eq!(s.is_scheduled("B", at("2026-03-07 22:00")), Some(false), "no weekend service");
+        assert_eq!(s.is_scheduled("D", at("2026-03-07 01:00")), Some(true), "Friday trip past midnight");
+        assert_eq!(s.is_scheduled("D", at("2026-03-07 12:30")), Some(true));
+        assert_eq!(s.is_scheduled("6", at("2026-03-06 07:20")), Some(true), "express variant folds in");
+        assert_eq!(s.is_scheduled("Q", at("2026-03-06 12:00")), None, "unknown route");
+        assert_eq!(s.is_scheduled("B", at("2027-03-05 07:00")), None, "feed expired");
+
+        assert_eq!(
+            s.unscheduled(&["B".to_string(), "D".to_string(), "Q".to_string()], at("2026-03-07 12:30")),
+            ["B"]
+        );
+    }
+
+    #[test]
+    fn test_scheduled_arrival_matches_realtime_trip() {
+        let s = parse(TIMETABLE_TRIPS, TIMETABLE_STOP_TIMES);
+        let expected = service_time(NaiveDate::from_ymd_opt(2026, 3, 6).unwrap(), 7 * 3600, Timezone::System);
+        assert!(expected.is_some());
+        assert_eq!(s.scheduled_arrival("036000_B..N", Some("20260306"), "D14N"), expected);
+        assert_eq!(s.scheduled_arrival("036000_B..N46R", Some("20260306"), "D14N"), expected);
+        assert_eq!(s.scheduled_arrival("036000_B..N", Some("20260306"), "D40N"), None, "stop not kept");
+        assert_eq!(s.scheduled_arrival("036000_B..N", Some("20260307"), "D14N"), None, "no weekend trip");
+        assert_eq!(trip_key("036000_B..N46R").as_deref(), Some("036000_B..N"));
+    }
+
+    #[test]
+    fn test_calendar_exceptions() {
+        let s = schedule();
+        // Memorial Day (a Monday) runs the weekend schedule
+        assert_eq!(s.is_scheduled("B", at("2026-05-25 06:30")), Some(false));
+        assert_eq!(s.is_scheduled("D", at("2026-05-25 12:30")), Some(true));
+        assert_eq!(s.is_scheduled("B", at("2026-05-26 06:30")), Some(true));
+    }
+
+    #[test]
+    fn test_from_zip() {
+        use std::io::Write;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("gtfs.zip");
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(&path).unwrap());
+        let files = [("calendar.txt", CALENDAR), ("trips.txt", TIMETABLE_TRIPS), ("stop_times.txt", TIMETABLE_STOP_TIMES)];
+        for (name, contents) in files {
+            zip.start_file(name, zip::write::SimpleFileOptions::default()).unwrap();
+            zip.write_all(contents.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+
+        let s = ServiceSchedule::from_zip(&path, &["D14N".to_string()].into()).unwrap();
+        assert_eq!(s.is_scheduled("B", at("2026-03-06 06:30")), Some(true));
+        assert_eq!(s.is_scheduled("B", at("2026-05-25 06:30")), Some(true), "no calendar_dates.txt");
+        assert!(s.scheduled_arrival("036000_B..N", Some("20260306"), "D14N").is_some());
+    }
+}
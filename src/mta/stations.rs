@@ -1,7 +1,11 @@
+use arc_swap::ArcSwap;
 use regex::Regex;
 use serde::Deserialize;
 use std::collections::HashMap;
-use std::sync::OnceLock;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
 
 /// Raw station record from the JSON database.
 #[derive(Debug, Clone, Deserialize)]
@@ -23,20 +27,80 @@ const STATION_DB_JSON: &str =
 
 /// Loaded station database + fuzzy index.
 struct StationDb {
-    stations: Vec<Station>,
+    stations: Arc<[Station]>,
+    /// File it was read from (`None` = embedded).
+    source: Option<PathBuf>,
     /// Lookup index: normalized name → index into `stations`.
     index: HashMap<String, usize>,
     /// Reverse lookup: base stop ID (without N/S suffix) → station name.
     stop_id_to_name: HashMap<String, String>,
+    /// Per-station name words for `suggest`, parallel to `stations`.
+    words: Vec<Vec<NameWord>>,
+    /// Per-station `search_key`, parallel to `stations`.
+    keys: Vec<String>,
 }
 
-static STATION_DB: OnceLock<StationDb> = OnceLock::new();
+/// One word of a station name, normalized, with its character span.
+struct NameWord {
+    text: String,
+    /// Unabbreviated form (see `full_word`).
+    full: String,
+    start: usize,
+    end: usize,
+}
+
+static STATION_DB: OnceLock<ArcSwap<StationDb>> = OnceLock::new();
+
+fn embedded_db() -> StationDb {
+    let stations: Vec<Station> =
+        serde_json::from_str(STATION_DB_JSON).expect("embedded station DB is valid JSON");
+    StationDb::new(stations, None)
+}
+
+fn db_slot() -> &'static ArcSwap<StationDb> {
+    STATION_DB.get_or_init(|| ArcSwap::from_pointee(embedded_db()))
+}
+
+fn get_db() -> Arc<StationDb> {
+    db_slot().load_full()
+}
+
+/// Stations from a database file in the embedded copy's format, checked to
+/// be usable: at least one station, each with a name and stop IDs.
+fn load_database(path: &Path) -> Result<Vec<Station>, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let stations: Vec<Station> =
+        serde_json::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e))?;
+    if stations.is_empty() {
+        return Err(format!("{}: no stations", path.display()));
+    }
+    if let Some(bad) = stations.iter().find(|s| s.name.trim().is_empty() || s.stop_ids.is_empty()) {
+        return Err(format!("{}: station '{}' needs a name and stop IDs", path.display(), bad.name));
+    }
+    Ok(stations)
+}
 
-fn get_db() -> &'static StationDb {
-    STATION_DB.get_or_init(|| {
-        let stations: Vec<Station> =
-            serde_json::from_str(STATION_DB_JSON).expect("embedded station DB is valid JSON");
+/// Use the station database in `path` (`None` = the embedded one) for
+/// lookups from now on; returns the station count. Config station names
+/// resolve against it, so switch before loading the config. On error the
+/// database in use stays.
+pub fn set_station_database(path: Option<&Path>) -> Result<usize, String> {
+    let db = match path {
+        Some(path) => StationDb::new(load_database(path)?, Some(path.to_path_buf())),
+        None => embedded_db(),
+    };
+    let count = db.stations.len();
+    db_slot().store(Arc::new(db));
+    Ok(count)
+}
 
+/// The file the station database in use was read from (`None` = embedded).
+pub fn station_database_path() -> Option<PathBuf> {
+    get_db().source.clone()
+}
+
+impl StationDb {
+    fn new(stations: Vec<Station>, source: Option<PathBuf>) -> Self {
         let mut index = HashMap::new();
         for (i, station) in stations.iter().enumerate() {
             // Exact lowercase
@@ -44,6 +108,8 @@ fn get_db() -> &'static StationDb {
             // Normalized form
             let normalized = normalize_station_name(&station.name);
             index.entry(normalized).or_insert(i);
+            // Punctuation-free form
+            index.entry(search_key(&station.name)).or_insert(i);
         }
 
         // Build reverse index: base stop ID → station name
@@ -57,16 +123,43 @@ fn get_db() -> &'static StationDb {
             }
         }
 
-        StationDb { stations, index, stop_id_to_name }
-    })
+        let words = stations.iter().map(|s| name_words(&s.name)).collect();
+        let keys = stations.iter().map(|s| search_key(&s.name)).collect();
+
+        StationDb { stations: stations.into(), source, index, stop_id_to_name, words, keys }
+    }
+}
+
+/// Words the station names abbreviate.
+const ABBREVIATIONS: [(&str, &str); 7] = [
+    ("street", "st"),
+    ("avenue", "av"),
+    ("square", "sq"),
+    ("parkway", "pkwy"),
+    ("boulevard", "blvd"),
+    ("heights", "hts"),
+    ("drive", "dr"),
+];
+
+/// Lowercase with accents stripped (NFKD) and dash variants (en dash, em
+/// dash, minus) as '-'.
+fn fold(s: &str) -> String {
+    s.nfkd()
+        .filter(|c| !is_combining_mark(*c))
+        .map(|c| match c {
+            '\u{2010}'..='\u{2015}' | '\u{2212}' => '-',
+            c => c,
+        })
+        .collect::<String>()
+        .to_lowercase()
 }
 
 /// Normalize a station name for fuzzy matching.
 ///
+/// - Lowercase, accents stripped, dash variants unified (see `fold`)
 /// - Remove ordinal suffixes (1st → 1, 2nd → 2, etc.)
 /// - Standardize spacing/punctuation around dashes
-/// - Common abbreviations (street → st, avenue → av, square → sq)
-/// - Lowercase
+/// - Common abbreviations (street → st, avenue → av, parkway → pkwy, ...)
 fn normalize_station_name(name: &str) -> String {
     static RE_ORDINAL: OnceLock<Regex> = OnceLock::new();
     static RE_DASH_SPACES: OnceLock<Regex> = OnceLock::new();
@@ -79,7 +172,7 @@ fn normalize_station_name(name: &str) -> String {
     let re_multi_space =
         RE_MULTI_SPACE.get_or_init(|| Regex::new(r"\s+").unwrap());
 
-    let mut s = name.to_lowercase();
+    let mut s = fold(name);
     // Remove ordinal suffixes
     s = re_ordinal.replace_all(&s, "$1").to_string();
     // Standardize dashes
@@ -87,15 +180,153 @@ fn normalize_station_name(name: &str) -> String {
     // Collapse whitespace
     s = re_multi_space.replace_all(&s, " ").to_string();
     // Common abbreviations
-    s = s.replace("street", "st");
-    s = s.replace("avenue", "av");
-    s = s.replace("square", "sq");
+    for (word, abbreviation) in ABBREVIATIONS {
+        s = s.replace(word, abbreviation);
+    }
     s.trim().to_string()
 }
 
+/// `normalize_station_name` with all punctuation as single spaces, so
+/// "Cathedral Pkwy (110 St)" and "Cathedral Parkway–110th Street" agree.
+fn search_key(name: &str) -> String {
+    normalize_station_name(name)
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Normalize one word for autocomplete matching: folded, ordinal suffix
+/// dropped, and the same abbreviations as `normalize_station_name`.
+fn normalize_word(word: &str) -> String {
+    let lower = fold(word);
+    let digits = lower.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    let suffix = &lower[digits.len()..];
+    if !digits.is_empty()
+        && digits.chars().all(|c| c.is_ascii_digit())
+        && matches!(suffix, "st" | "nd" | "rd" | "th")
+    {
+        return digits.to_string();
+    }
+    ABBREVIATIONS
+        .iter()
+        .find(|(word, _)| *word == lower)
+        .map_or(lower, |(_, abbreviation)| abbreviation.to_string())
+}
+
+/// A word spelled out, for prefix-matching a word still being typed:
+/// "Av" → "avenue", "42" → "42nd".
+fn full_word(word: &str) -> String {
+    let lower = fold(word);
+    if !lower.is_empty() && lower.chars().all(|c| c.is_ascii_digit()) {
+        let suffix = match lower.as_bytes() {
+            [.., b'1', _] => "th",
+            [.., b'1'] => "st",
+            [.., b'2'] => "nd",
+            [.., b'3'] => "rd",
+            _ => "th",
+        };
+        return lower + suffix;
+    }
+    ABBREVIATIONS
+        .iter()
+        .find(|(_, abbreviation)| *abbreviation == lower)
+        .map_or(lower, |(word, _)| word.to_string())
+}
+
+/// Split a station name into normalized words with character spans.
+fn name_words(name: &str) -> Vec<NameWord> {
+    let mut words = Vec::new();
+    let mut start = None;
+    let chars: Vec<char> = name.chars().collect();
+    for (i, c) in chars.iter().chain(std::iter::once(&' ')).enumerate() {
+        match (start, c.is_alphanumeric()) {
+            (None, true) => start = Some(i),
+            (Some(s), false) => {
+                let word: String = chars[s..i].iter().collect();
+                let (text, full) = (normalize_word(&word), full_word(&word));
+                words.push(NameWord { text, full, start: s, end: i });
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    words
+}
+
+/// An autocomplete match: the station and the character spans of its name
+/// that matched the query.
+pub struct Suggestion {
+    pub station: Station,
+    pub spans: Vec<(usize, usize)>,
+}
+
+/// Stations whose name words start with every word typed, best first:
+/// names beginning with the first query word, then more whole-word hits,
+/// then shorter names. The last word may be half typed, so it also matches
+/// spelled-out words ("42n", "aven"). Pre-tokenized, so cheap enough to call
+/// per keystroke.
+pub fn suggest(query: &str, limit: usize, filter: impl Fn(&Station) -> bool) -> Vec<Suggestion> {
+    let db = get_db();
+    let query = name_words(query);
+    if query.is_empty() {
+        return Vec::new();
+    }
+
+    let mut ranked: Vec<_> = db
+        .stations
+        .iter()
+        .zip(&db.words)
+        .filter(|(station, _)| filter(station))
+        .filter_map(|(station, words)| {
+            let mut used = vec![false; words.len()];
+            let mut spans = Vec::with_capacity(query.len());
+            let mut whole = 0;
+            for (n, q) in query.iter().enumerate() {
+                let last = n == query.len() - 1;
+                let prefix = |word: &NameWord| {
+                    word.text.starts_with(q.text.as_str()) || (last && word.full.starts_with(q.full.as_str()))
+                };
+                // Whole-word hit first, so "st" doesn't use up "st" in "sty"
+                let free = |i: &usize| !used[*i];
+                let i = (0..words.len())
+                    .filter(free)
+                    .find(|&i| words[i].text == q.text)
+                    .or_else(|| (0..words.len()).filter(free).find(|&i| prefix(&words[i])))?;
+                used[i] = true;
+                let word = &words[i];
+                if word.text == q.text {
+                    whole += 1;
+                    spans.push((word.start, word.end));
+                } else {
+                    // Typed length, or the normalized one if that's what matched
+                    let len = if word.text.starts_with(q.text.as_str()) {
+                        q.text.chars().count()
+                    } else {
+                        q.end - q.start
+                    };
+                    spans.push((word.start, (word.start + len).min(word.end)));
+                }
+            }
+            spans.sort_unstable();
+            let leading = used.first().copied().unwrap_or(false);
+            let rank = (!leading, std::cmp::Reverse(whole), station.name.len());
+            Some((rank, station, spans))
+        })
+        .collect();
+
+    ranked.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.name.cmp(&b.1.name)));
+    ranked
+        .into_iter()
+        .take(limit)
+        .map(|(_, station, spans)| Suggestion { station: station.clone(), spans })
+        .collect()
+}
+
 /// Get all stop IDs for a station name with fuzzy matching.
 ///
-/// Tries matching in order: exact → dash-normalized → full-normalized → substring.
+/// Tries matching in order: exact → dash-normalized → full-normalized →
+/// punctuation-free → substring.
 pub fn get_stop_ids_for_station(station_name: &str) -> Vec<String> {
     let db = get_db();
     if station_name.is_empty() {
@@ -121,39 +352,42 @@ pub fn get_stop_ids_for_station(station_name: &str) -> Vec<String> {
         return db.stations[idx].stop_ids.clone();
     }
 
-    // Substring match
-    let normalized_query = normalized.replace('-', " ");
-    for (indexed_name, &idx) in &db.index {
-        let indexed_normalized = indexed_name.replace('-', " ");
-        if normalized_query.contains(&indexed_normalized)
-            || indexed_normalized.contains(&normalized_query)
-        {
-            return db.stations[idx].stop_ids.clone();
-        }
+    // Punctuation-free match
+    let key = search_key(station_name);
+    if let Some(&idx) = db.index.get(&key) {
+        return db.stations[idx].stop_ids.clone();
     }
 
-    Vec::new()
+    // Substring match
+    if key.is_empty() {
+        return Vec::new();
+    }
+    db.keys
+        .iter()
+        .position(|indexed| key.contains(indexed.as_str()) || indexed.contains(key.as_str()))
+        .map(|idx| db.stations[idx].stop_ids.clone())
+        .unwrap_or_default()
 }
 
 /// Get the full station database.
-pub fn get_station_database() -> &'static [Station] {
-    &get_db().stations
+pub fn get_station_database() -> Arc<[Station]> {
+    Arc::clone(&get_db().stations)
 }
 
 /// Look up station name from a stop ID (e.g., "635N" → "Times Sq-42 St").
 ///
 /// Strips the N/S direction suffix before matching.
-pub fn station_name_for_stop_id(stop_id: &str) -> Option<&'static str> {
+pub fn station_name_for_stop_id(stop_id: &str) -> Option<String> {
     let db = get_db();
     let base = stop_id.trim_end_matches(['N', 'S']);
-    db.stop_id_to_name.get(base).map(|s| s.as_str())
+    db.stop_id_to_name.get(base).cloned()
 }
 
 /// Look up routes served at a station by name.
 pub fn get_routes_for_station(station_name: &str) -> Vec<String> {
     let db = get_db();
     let name_lower = station_name.to_lowercase();
-    for station in &db.stations {
+    for station in db.stations.iter() {
         if station.name.to_lowercase() == name_lower {
             return station.routes.clone();
         }
@@ -171,6 +405,20 @@ mod tests {
         assert_eq!(normalize_station_name("Times Sq - 42 St"), "times sq-42 st");
         assert_eq!(normalize_station_name("1st Avenue"), "1 av");
         assert_eq!(normalize_station_name("  103 St  "), "103 st");
+        assert_eq!(normalize_station_name("Cathedral Parkway\u{2013}110th Street"), "cathedral pkwy-110 st");
+        assert_eq!(normalize_station_name("Caf\u{e9} Av"), "cafe av");
+        assert_eq!(search_key("Cathedral Pkwy (110 St)"), "cathedral pkwy 110 st");
+    }
+
+    #[test]
+    fn test_punctuation_and_accent_variants() {
+        let ids = get_stop_ids_for_station("Cathedral Pkwy (110 St)");
+        assert!(!ids.is_empty());
+        assert_eq!(get_stop_ids_for_station("Cathedral Parkway\u{2013}110th Street"), ids);
+        assert_eq!(get_stop_ids_for_station("cath\u{e9}dral pkwy 110 st"), ids);
+
+        let hit = &suggest("cath\u{e9}dral parkway", 1, |_| true)[0];
+        assert_eq!(hit.station.name, "Cathedral Pkwy (110 St)");
     }
 
     #[test]
@@ -182,6 +430,22 @@ mod tests {
         assert!(has_times_sq, "should contain Times Sq");
     }
 
+    #[test]
+    fn test_load_database_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("stations.json");
+        let station = r#"{"name":"Main St","stop_ids":["X01"],"routes":["X"],"lat":0,"lon":0,"borough":"Q","platform_count":1}"#;
+        std::fs::write(&path, format!("[{}]", station)).unwrap();
+        let stations = load_database(&path).unwrap();
+        assert_eq!(StationDb::new(stations, None).index.get("main st"), Some(&0));
+
+        std::fs::write(&path, "[]").unwrap();
+        assert!(load_database(&path).is_err(), "empty");
+        std::fs::write(&path, format!("[{}]", station.replace(r#"["X01"]"#, "[]"))).unwrap();
+        assert!(load_database(&path).is_err(), "no stop IDs");
+        assert!(load_database(&dir.path().join("missing.json")).is_err());
+    }
+
     #[test]
     fn test_exact_lookup() {
         let ids = get_stop_ids_for_station("Times Sq-42 St");
@@ -207,6 +471,52 @@ mod tests {
         assert!(!routes.is_empty(), "Times Sq should have routes");
     }
 
+    #[test]
+    fn test_suggest_ranks_and_highlights() {
+        let names = |q: &str| -> Vec<String> {
+            suggest(q, 5, |_| true).into_iter().map(|s| s.station.name).collect()
+        };
+        assert_eq!(names("times")[0], "Times Sq-42 St");
+        // Ordinals and spelled-out words match the abbreviated names
+        assert!(names("42nd street").iter().any(|n| n == "Times Sq-42 St"));
+        assert!(names("herald sq").iter().any(|n| n == "34 St-Herald Sq"));
+        assert!(names("zzz").is_empty());
+        assert!(names("  ").is_empty());
+
+        let hit = &suggest("herald 34", 1, |_| true)[0];
+        assert_eq!(hit.station.name, "34 St-Herald Sq");
+        assert_eq!(hit.spans, vec![(0, 2), (6, 12)]);
+
+        let partial = &suggest("tim", 1, |_| true)[0];
+        assert_eq!(partial.spans, vec![(0, 3)]);
+
+        let typed = &suggest("times squ", 1, |_| true)[0];
+        assert_eq!(typed.station.name, "Times Sq-42 St");
+        assert_eq!(typed.spans, vec![(0, 5), (6, 8)]);
+
+        let only_l = suggest("av", 50, |s| s.routes.iter().any(|r| r == "L"));
+        assert!(!only_l.is_empty());
+        assert!(only_l.iter().all(|s| s.station.routes.iter().any(|r| r == "L")));
+    }
+
+    #[test]
+    fn test_suggest_while_typing() {
+        for (typed, station) in [
+            ("times square", "Times Sq-42 St"),
+            ("42nd street", "Times Sq-42 St"),
+            ("8 avenue", "8 Av"),
+            ("grand central 42nd", "Grand Central-42 St"),
+        ] {
+            for end in 1..=typed.len() {
+                let query = &typed[..end];
+                let names: Vec<String> =
+                    suggest(query, usize::MAX, |_| true).into_iter().map(|s| s.station.name).collect();
+                assert!(names.iter().any(|n| n == station), "'{}' should suggest {}", query, station);
+            }
+            assert_eq!(suggest(typed, 1, |_| true)[0].station.name, station, "'{}'", typed);
+        }
+    }
+
     #[test]
     fn test_empty_query() {
         assert!(get_stop_ids_for_station("").is_empty());
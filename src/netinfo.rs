@@ -0,0 +1,78 @@
+//! The sign's own address, shown on the panel on request (GPIO button or
+//! `POST /api/display/show-ip`) for finding a headless sign on the network.
+
+use std::net::{IpAddr, UdpSocket};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use tracing::info;
+
+use crate::AppState;
+
+/// How long the address stays on the panel.
+pub const SHOW_IP_DURATION: Duration = Duration::from_secs(10);
+
+/// Text for the two display rows, shown until `until`.
+#[derive(Debug, Clone)]
+pub struct InfoScreen {
+    pub lines: [String; 2],
+    pub until: Instant,
+}
+
+/// Address of the interface holding the default route. Connecting a UDP
+/// socket only picks a route; nothing is sent.
+pub fn local_ip() -> Option<IpAddr> {
+    let socket = UdpSocket::bind("0.0.0.0:0").ok()?;
+    socket.connect("192.0.2.1:9").ok()?;
+    socket.local_addr().ok().map(|a| a.ip()).filter(|ip| !ip.is_unspecified())
+}
+
+/// The machine's hostname, if the platform exposes it.
+pub fn hostname() -> Option<String> {
+    std::fs::read_to_string("/proc/sys/kernel/hostname")
+        .ok()
+        .or_else(|| std::env::var("HOSTNAME").ok())
+        .map(|h| h.trim().to_string())
+        .filter(|h| !h.is_empty())
+}
+
+/// "192.168.1.23:5001" over "subway-sign.local:5001".
+fn address_lines(ip: Option<IpAddr>, hostname: Option<&str>, port: u16) -> [String; 2] {
+    let top = match ip {
+        Some(ip) => format!("{}:{}", ip, port),
+        None => "No network".to_string(),
+    };
+    let bottom = match hostname {
+        Some(host) if host.contains('.') => format!("{}:{}", host, port),
+        Some(host) => format!("{}.local:{}", host, port),
+        None => String::new(),
+    };
+    [top, bottom]
+}
+
+/// Put the address on the panel for `SHOW_IP_DURATION`; returns the lines.
+pub fn show_ip(state: &AppState) -> [String; 2] {
+    let lines = address_lines(local_ip(), hostname().as_deref(), state.config.load().web.port);
+    info!("[DISPLAY] Showing address: {} / {}", lines[0], lines[1]);
+    state.info_screen.store(Arc::new(Some(InfoScreen {
+        lines: lines.clone(),
+        until: Instant::now() + SHOW_IP_DURATION,
+    })));
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_address_lines() {
+        let ip = Some("192.168.1.23".parse().unwrap());
+        assert_eq!(
+            address_lines(ip, Some("lobby-sign"), 5001),
+            ["192.168.1.23:5001", "lobby-sign.local:5001"]
+        );
+        assert_eq!(address_lines(None, Some("sign.example.org"), 5001)[..], ["No network", "sign.example.org:5001"]);
+        assert_eq!(address_lines(ip, None, 80)[1], "");
+    }
+}
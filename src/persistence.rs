@@ -0,0 +1,158 @@
+//! Embedded SQLite database for features that keep history.
+//!
+//! One file (`database.path` in config) shared by every user, instead of
+//! each feature keeping its own JSON files. The schema is versioned with
+//! `PRAGMA user_version`: `MIGRATIONS[i]` upgrades version `i` to `i + 1`,
+//! runs exactly once and in its own transaction. Append new migrations;
+//! never edit shipped ones.
+//!
+//! The restart handoff stays a plain file: it is written from the panic
+//! hook, where taking the connection lock could deadlock.
+
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use rusqlite::Connection;
+
+use crate::config::DatabaseConfig;
+
+/// Schema migrations, oldest first.
+const MIGRATIONS: &[&str] = &[
+    // 1: analytics day totals and observed arrivals
+    "CREATE TABLE analytics_days (
+        date TEXT PRIMARY KEY,
+        accuracy TEXT NOT NULL,
+        service TEXT NOT NULL
+    );
+    CREATE TABLE arrivals (
+        date TEXT NOT NULL,
+        route TEXT NOT NULL,
+        direction TEXT NOT NULL,
+        stop_id TEXT NOT NULL,
+        arrived_at REAL NOT NULL
+    );
+    CREATE INDEX arrivals_by_date ON arrivals (date, arrived_at);",
+];
+
+/// Shared connection. Calls block, so use it from `spawn_blocking` in
+/// async code.
+#[derive(Debug)]
+pub struct Database {
+    conn: Mutex<Connection>,
+}
+
+impl Database {
+    /// Open (or create) the database file and bring the schema up to date.
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let conn = Connection::open(path).map_err(|e| format!("open {}: {}", path.display(), e))?;
+        // WAL keeps readers (web API) from blocking the analytics writer
+        conn.pragma_update(None, "journal_mode", "WAL")
+            .map_err(|e| format!("enable WAL: {}", e))?;
+        Self::with_connection(conn)
+    }
+
+    /// Private in-memory database (tests).
+    #[cfg(test)]
+    pub fn open_in_memory() -> Result<Self, String> {
+        Self::with_connection(Connection::open_in_memory().map_err(|e| e.to_string())?)
+    }
+
+    fn with_connection(mut conn: Connection) -> Result<Self, String> {
+        migrate(&mut conn)?;
+        Ok(Database {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Run `f` with the connection locked.
+    pub fn with<T>(
+        &self,
+        f: impl FnOnce(&mut Connection) -> rusqlite::Result<T>,
+    ) -> Result<T, String> {
+        let mut conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        f(&mut conn).map_err(|e| e.to_string())
+    }
+
+    /// Current schema version (number of migrations applied).
+    pub fn schema_version(&self) -> Result<usize, String> {
+        self.with(|conn| user_version(conn))
+    }
+}
+
+fn user_version(conn: &Connection) -> rusqlite::Result<usize> {
+    conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+}
+
+/// Apply any migrations newer than the file's `user_version`.
+fn migrate(conn: &mut Connection) -> Result<(), String> {
+    let current = user_version(conn).map_err(|e| format!("read schema version: {}", e))?;
+    if current > MIGRATIONS.len() {
+        return Err(format!(
+            "database schema version {} is newer than this build ({})",
+            current,
+            MIGRATIONS.len()
+        ));
+    }
+    for (i, sql) in MIGRATIONS.iter().enumerate().skip(current) {
+        let version = i + 1;
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        tx.execute_batch(sql)
+            .and_then(|_| tx.pragma_update(None, "user_version", version))
+            .and_then(|_| tx.commit())
+            .map_err(|e| format!("migration {}: {}", version, e))?;
+        tracing::info!("[DB] Migrated schema to version {}", version);
+    }
+    Ok(())
+}
+
+/// Open the configured database, if any. Errors are logged and leave
+/// features on their file-based fallbacks.
+pub fn open_configured(config: &DatabaseConfig) -> Option<Arc<Database>> {
+    let path = config.path.as_ref()?;
+    match Database::open(path) {
+        Ok(db) => {
+            tracing::info!("[DB] Using {}", path.display());
+            Some(Arc::new(db))
+        }
+        Err(e) => {
+            tracing::error!("[DB] Cannot open database ({}), using JSON files", e);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrations_run_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sign.db");
+        let db = Database::open(&path).unwrap();
+        assert_eq!(db.schema_version().unwrap(), MIGRATIONS.len());
+        db.with(|conn| {
+            conn.execute(
+                "INSERT INTO analytics_days VALUES ('2026-03-01', '{}', '{}')",
+                [],
+            )
+        })
+        .unwrap();
+        drop(db);
+
+        // Reopening keeps data and doesn't re-run migrations
+        let db = Database::open(&path).unwrap();
+        let days: i64 = db
+            .with(|conn| conn.query_row("SELECT COUNT(*) FROM analytics_days", [], |r| r.get(0)))
+            .unwrap();
+        assert_eq!(days, 1);
+    }
+
+    #[test]
+    fn test_rejects_newer_schema() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.pragma_update(None, "user_version", MIGRATIONS.len() + 1)
+            .unwrap();
+        assert!(migrate(&mut conn).unwrap_err().contains("newer"));
+    }
+}
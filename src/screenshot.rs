@@ -0,0 +1,89 @@
+//! Screenshots of the live display, on demand and as a periodic time-lapse.
+//!
+//! The render loop publishes every frame to `AppState::latest_frame`; this
+//! module just encodes whatever is there to a timestamped PNG on disk.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use tracing::{info, warn};
+
+use crate::config::ScreenshotConfig;
+use crate::display::export;
+use crate::display::framebuffer::FrameBuffer;
+use crate::AppState;
+
+/// How often the time-lapse task re-checks the (hot-reloadable) interval.
+const TIMELAPSE_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Save a frame as `<dir>/sign-YYYYmmdd-HHMMSS-mmm.png`, creating `dir` if needed.
+///
+/// Blocking (fs + encode) — call via `spawn_blocking` from async code.
+pub fn save_screenshot(frame: &FrameBuffer, config: &ScreenshotConfig) -> Result<PathBuf, String> {
+    std::fs::create_dir_all(&config.dir)
+        .map_err(|e| format!("create {}: {}", config.dir.display(), e))?;
+
+    let bytes = export::framebuffer_to_png(frame, config.scale)?;
+    let name = format!(
+        "sign-{}.png",
+        chrono::Local::now().format("%Y%m%d-%H%M%S-%3f")
+    );
+    let path = config.dir.join(name);
+    std::fs::write(&path, bytes).map_err(|e| format!("write {}: {}", path.display(), e))?;
+    Ok(path)
+}
+
+/// Capture the current frame to disk.
+pub async fn capture(state: &AppState) -> Result<PathBuf, String> {
+    let frame = state.latest_frame.load_full();
+    let config = state.config.load_full();
+    tokio::task::spawn_blocking(move || save_screenshot(&frame, &config.screenshots))
+        .await
+        .map_err(|e| format!("screenshot task failed: {}", e))?
+}
+
+/// Time-lapse task — captures every `screenshots.timelapse_minutes` (0 = off).
+pub async fn timelapse_task(state: Arc<AppState>) {
+    let mut interval = tokio::time::interval(TIMELAPSE_POLL_INTERVAL);
+    let mut last_capture = Instant::now();
+
+    loop {
+        tokio::select! {
+            _ = state.shutdown.cancelled() => {
+                info!("[SCREENSHOT] Shutting down");
+                break;
+            }
+            _ = interval.tick() => {
+                let minutes = state.config.load().screenshots.timelapse_minutes;
+                if minutes == 0 || last_capture.elapsed() < Duration::from_secs(minutes * 60) {
+                    continue;
+                }
+                last_capture = Instant::now();
+                match capture(&state).await {
+                    Ok(path) => info!("[SCREENSHOT] Time-lapse frame saved: {}", path.display()),
+                    Err(e) => warn!("[SCREENSHOT] Time-lapse capture failed: {}", e),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_save_screenshot_writes_png() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = ScreenshotConfig {
+            dir: dir.path().join("shots"),
+            timelapse_minutes: 0,
+            scale: 2,
+        };
+        let path = save_screenshot(&FrameBuffer::new(), &config).unwrap();
+        assert!(path.starts_with(&config.dir));
+        let bytes = std::fs::read(&path).unwrap();
+        assert!(bytes.starts_with(b"\x89PNG"));
+    }
+}
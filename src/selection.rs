@@ -0,0 +1,359 @@
+//! Which trains a snapshot puts where.
+//!
+//! The top row pins one train chosen by an ordered list of rules; the bottom
+//! row cycles through the rest in arrival order. Pure functions over the
+//! snapshot, so the renderer and API handlers agree on the layout.
+//!
+//! Per-route express/local preferences (`ServiceFilter`) drop trains of
+//! the unwanted class before any of this, as trains are fetched.
+//!
+//! Service alerts feed in through `Suspension`: trains on routes with a
+//! NO_SERVICE alert sink behind running ones, and when the rider's primary
+//! route is suspended the suggested alternate takes the top row.
+
+use std::collections::{BTreeMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::AlternatesConfig;
+use crate::models::{Direction, DisplaySnapshot, Train};
+
+/// One top-row preference. Rules are tried in order; the first that matches
+/// any train picks the soonest such train.
+///
+/// Config form: `[{"stop": "635S"}, {"route": "Q"}, {"direction": "uptown"}, "soonest"]`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TopRowRule {
+    /// Soonest train overall.
+    Soonest,
+    /// Soonest train heading this way.
+    Direction(Direction),
+    /// Soonest train on this route.
+    Route(String),
+    /// Soonest train at this stop ID ("635S"), or at either direction of a
+    /// platform given without the N/S suffix ("635").
+    Stop(String),
+}
+
+impl TopRowRule {
+    fn matches(&self, train: &Train) -> bool {
+        match self {
+            TopRowRule::Soonest => true,
+            TopRowRule::Direction(direction) => train.direction == *direction,
+            TopRowRule::Route(route) => train.route.eq_ignore_ascii_case(route),
+            TopRowRule::Stop(stop) => stop_matches(&train.stop_id, stop),
+        }
+    }
+}
+
+/// Whether `stop_id` ("635S") is `stop`, or on platform `stop` ("635").
+pub fn stop_matches(stop_id: &str, stop: &str) -> bool {
+    stop_id.eq_ignore_ascii_case(stop)
+        || stop_id
+            .get(..stop_id.len().saturating_sub(1))
+            .is_some_and(|platform| platform.eq_ignore_ascii_case(stop))
+}
+
+/// Index of the top-row train: the first rule matching any train wins,
+/// falling back to the soonest train overall. `None` when there are no trains.
+///
+/// Assumes `trains` is sorted by arrival (as fetched).
+pub fn top_train_index(trains: &[Train], rules: &[TopRowRule]) -> Option<usize> {
+    rules
+        .iter()
+        .find_map(|rule| trains.iter().position(|t| rule.matches(t)))
+        .or(if trains.is_empty() { None } else { Some(0) })
+}
+
+/// Which trains of a route to show, by `Train::is_express`.
+///
+/// Config form: `"route_service": {"7": "express", "6": "local"}`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ServiceFilter {
+    #[default]
+    Both,
+    Express,
+    Local,
+}
+
+impl ServiceFilter {
+    pub fn allows(self, train: &Train) -> bool {
+        match self {
+            ServiceFilter::Both => true,
+            ServiceFilter::Express => train.is_express,
+            ServiceFilter::Local => !train.is_express,
+        }
+    }
+}
+
+/// Whether `train` passes its route's filter (routes without one show both).
+pub fn service_allows(filters: &BTreeMap<String, ServiceFilter>, train: &Train) -> bool {
+    filters
+        .iter()
+        .find(|(route, _)| route.eq_ignore_ascii_case(&train.route))
+        .is_none_or(|(_, filter)| filter.allows(train))
+}
+
+/// Routes currently suspended, and what to ride instead.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Suspension {
+    /// Routes under a NO_SERVICE alert.
+    pub routes: HashSet<String>,
+    /// Soonest train on the best alternate, when the primary route is
+    /// suspended.
+    pub instead: Option<Train>,
+}
+
+impl Suspension {
+    /// Work out the alternate for `suspended` routes from the current trains.
+    pub fn new(routes: HashSet<String>, trains: &[Train], alternates: &AlternatesConfig) -> Self {
+        let is_suspended = |route: &str| routes.iter().any(|r| r.eq_ignore_ascii_case(route));
+        let instead = alternates
+            .primary
+            .as_deref()
+            .filter(|primary| is_suspended(primary))
+            .and_then(|primary| {
+                let rank = |t: &Train| {
+                    if alternates.routes.is_empty() {
+                        Some(0)
+                    } else {
+                        alternates.routes.iter().position(|r| r.eq_ignore_ascii_case(&t.route))
+                    }
+                };
+                trains
+                    .iter()
+                    .filter(|t| !t.route.eq_ignore_ascii_case(primary) && !is_suspended(&t.route))
+                    .filter_map(|t| Some((rank(t)?, t)))
+                    .min_by_key(|&(rank, _)| rank)
+                    .map(|(_, t)| t.clone())
+            });
+        Suspension { routes, instead }
+    }
+
+    fn suspends(&self, train: &Train) -> bool {
+        self.routes.iter().any(|r| r.eq_ignore_ascii_case(&train.route))
+    }
+}
+
+/// Top-row train plus the next `count` other trains for the bottom row,
+/// padded with placeholders. Suspended trains go last, and a suggested
+/// alternate outranks the `rules`.
+pub fn arrange(
+    snapshot: &DisplaySnapshot,
+    rules: &[TopRowRule],
+    suspension: &Suspension,
+    count: usize,
+) -> (Train, Vec<Train>) {
+    let (mut trains, stopped): (Vec<Train>, Vec<Train>) =
+        snapshot.trains.iter().cloned().partition(|t| !suspension.suspends(t));
+    let running = trains.len();
+    trains.extend(stopped);
+
+    let instead = suspension.instead.iter().map(|t| TopRowRule::Route(t.route.clone()));
+    let rules: Vec<TopRowRule> = instead.chain(rules.iter().cloned()).collect();
+    let top = top_train_index(&trains[..running], &rules)
+        .or(if trains.is_empty() { None } else { Some(0) });
+
+    let mut rest: Vec<Train> = trains
+        .iter()
+        .enumerate()
+        .filter(|&(i, _)| Some(i) != top)
+        .map(|(_, t)| t.clone())
+        .take(count)
+        .collect();
+    rest.resize_with(count, Train::empty);
+
+    let top = top.map_or_else(Train::empty, |i| trains.swap_remove(i));
+    (top, rest)
+}
+
+/// Reorder the cycling trains so those at one platform (stop ID) come
+/// round together: the top train's platform first, then the rest by their
+/// next arrival. Arrival order holds within a platform; suspended trains
+/// and placeholders stay at the back.
+pub fn group_by_platform(snapshot: &DisplaySnapshot, top: &Train, rest: &mut [Train], suspension: &Suspension) {
+    let mut platforms = snapshot.platforms();
+    platforms.retain(|&p| p != top.stop_id);
+    platforms.insert(0, &top.stop_id);
+    rest.sort_by_key(|t| {
+        let platform = platforms.iter().position(|&p| p == t.stop_id).unwrap_or(platforms.len());
+        (t.route.is_empty(), suspension.suspends(t), platform)
+    });
+}
+
+/// Soonest uptown and downtown trains, for the by-direction layout.
+/// Suspended trains only fill a row when nothing else is heading that way.
+pub fn by_direction(snapshot: &DisplaySnapshot, suspension: &Suspension) -> (Train, Train) {
+    let next = |direction: Direction| {
+        let mut heading = snapshot.trains.iter().filter(|t| t.direction == direction);
+        heading
+            .clone()
+            .find(|t| !suspension.suspends(t))
+            .or_else(|| heading.next())
+            .map_or_else(Train::empty, Train::clone)
+    };
+    (next(Direction::Uptown), next(Direction::Downtown))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn train(route: &str, direction: Direction, minutes: i32) -> Train {
+        Train {
+            route: route.into(),
+            destination: "Test".into(),
+            minutes,
+            is_express: false,
+            arrival_timestamp: 0.0,
+            direction,
+            stop_id: String::new(),
+            delay_secs: None,
+            trip_id: String::new(),
+        }
+    }
+
+    fn snapshot() -> DisplaySnapshot {
+        DisplaySnapshot {
+            trains: vec![
+                train("N", Direction::Downtown, 1),
+                train("Q", Direction::Uptown, 3),
+                train("N", Direction::Uptown, 6),
+            ],
+            alerts: Vec::new(),
+            fetched_at: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_rules_with_fallback() {
+        let trains = snapshot().trains;
+        assert_eq!(top_train_index(&trains, &[]), Some(0));
+        let uptown = TopRowRule::Direction(Direction::Uptown);
+        assert_eq!(top_train_index(&trains, std::slice::from_ref(&uptown)), Some(1));
+        let rules = [TopRowRule::Route("w".into()), TopRowRule::Route("n".into()), uptown];
+        assert_eq!(top_train_index(&trains, &rules), Some(0), "first matching rule wins");
+        assert_eq!(top_train_index(&trains, &[TopRowRule::Route("W".into())]), Some(0));
+        assert_eq!(top_train_index(&[], &[TopRowRule::Soonest]), None);
+    }
+
+    #[test]
+    fn test_service_filter() {
+        let express = Train { is_express: true, ..train("7", Direction::Downtown, 2) };
+        let local = train("7", Direction::Downtown, 4);
+        let other = train("N", Direction::Downtown, 5);
+        let filters = BTreeMap::from([("7".to_string(), ServiceFilter::Express)]);
+        assert!(service_allows(&filters, &express));
+        assert!(!service_allows(&filters, &local));
+        assert!(service_allows(&filters, &other), "unfiltered routes show both");
+        let filters = BTreeMap::from([("7".to_string(), ServiceFilter::Local)]);
+        assert!(!service_allows(&filters, &express));
+        assert!(service_allows(&filters, &local));
+    }
+
+    #[test]
+    fn test_stop_rule() {
+        let at = |stop: &str, minutes| Train { stop_id: stop.into(), ..train("6", Direction::Downtown, minutes) };
+        let trains = [at("635S", 1), at("R20S", 2), at("R20N", 4)];
+        assert_eq!(top_train_index(&trains, &[TopRowRule::Stop("r20n".into())]), Some(2));
+        assert_eq!(top_train_index(&trains, &[TopRowRule::Stop("R20".into())]), Some(1), "either direction");
+        assert_eq!(top_train_index(&trains, &[TopRowRule::Stop("R2".into())]), Some(0), "no prefix matches");
+    }
+
+    #[test]
+    fn test_arrange_moves_pinned_train_out_of_rotation() {
+        let (top, rest) = arrange(&snapshot(), &[TopRowRule::Route("Q".into())], &Suspension::default(), 6);
+        assert_eq!(top.route, "Q");
+        assert_eq!(rest.len(), 6);
+        assert_eq!((rest[0].minutes, rest[1].minutes), (1, 6));
+        assert_eq!(rest[2].minutes, Train::empty().minutes);
+    }
+
+    #[test]
+    fn test_suspension_promotes_alternate() {
+        let snap = snapshot();
+        let alternates = AlternatesConfig {
+            primary: Some("N".into()),
+            routes: vec!["R".into(), "Q".into()],
+        };
+        let n_suspended = HashSet::from(["N".to_string()]);
+
+        let suspension = Suspension::new(n_suspended.clone(), &snap.trains, &alternates);
+        assert_eq!(suspension.instead.as_ref().unwrap().route, "Q");
+        let (top, rest) = arrange(&snap, &[TopRowRule::Route("N".into())], &suspension, 3);
+        assert_eq!(top.route, "Q");
+        assert_eq!((rest[0].route.as_str(), rest[0].minutes), ("N", 1));
+        assert_eq!((rest[1].route.as_str(), rest[1].minutes), ("N", 6));
+
+        // No suggestion unless the primary route is the suspended one
+        let q_suspended = HashSet::from(["Q".to_string()]);
+        let suspension = Suspension::new(q_suspended, &snap.trains, &alternates);
+        assert!(suspension.instead.is_none());
+        let (top, rest) = arrange(&snap, &[TopRowRule::Route("Q".into())], &suspension, 3);
+        assert_eq!(top.route, "N", "suspended trains never take the top row");
+        assert_eq!(rest[1].route, "Q");
+
+        // Everything suspended: still show what there is
+        let all = Suspension::new(HashSet::from(["N".to_string(), "Q".to_string()]), &snap.trains, &alternates);
+        assert_eq!(arrange(&snap, &[], &all, 3).0.minutes, 1);
+        let any = AlternatesConfig { routes: Vec::new(), ..alternates };
+        assert!(Suspension::new(n_suspended, &snap.trains, &any).instead.is_some());
+    }
+
+    #[test]
+    fn test_group_by_platform() {
+        let at = |route: &str, stop: &str, minutes| Train { stop_id: stop.into(), ..train(route, Direction::Uptown, minutes) };
+        let snap = DisplaySnapshot {
+            trains: vec![at("1", "127N", 1), at("7", "725N", 2), at("2", "127N", 3), at("N", "R16N", 4), at("7", "725N", 5)],
+            alerts: Vec::new(),
+            fetched_at: 0.0,
+        };
+        assert_eq!(snap.platforms(), vec!["127N", "725N", "R16N"]);
+
+        let (top, mut rest) = arrange(&snap, &[TopRowRule::Route("7".into())], &Suspension::default(), 5);
+        group_by_platform(&snap, &top, &mut rest, &Suspension::default());
+        let order: Vec<(&str, i32)> = rest.iter().map(|t| (t.route.as_str(), t.minutes)).collect();
+        assert_eq!(order[..4], [("7", 5), ("1", 1), ("2", 3), ("N", 4)], "top train's platform first");
+        assert!(rest[4].route.is_empty(), "placeholder stays last");
+
+        let suspension = Suspension { routes: HashSet::from(["7".to_string()]), instead: None };
+        let (top, mut rest) = arrange(&snap, &[], &suspension, 4);
+        group_by_platform(&snap, &top, &mut rest, &suspension);
+        let routes: Vec<&str> = rest.iter().map(|t| t.route.as_str()).collect();
+        assert_eq!(routes, ["2", "N", "7", "7"]);
+    }
+
+    #[test]
+    fn test_by_direction() {
+        let snap = snapshot();
+        let (up, down) = by_direction(&snap, &Suspension::default());
+        assert_eq!((up.route.as_str(), up.minutes), ("Q", 3));
+        assert_eq!((down.route.as_str(), down.minutes), ("N", 1));
+
+        let suspension = Suspension { routes: HashSet::from(["Q".to_string()]), instead: None };
+        assert_eq!(by_direction(&snap, &suspension).0.minutes, 6, "running train first");
+        let suspension = Suspension { routes: HashSet::from(["N".to_string()]), instead: None };
+        assert_eq!(by_direction(&snap, &suspension).1.route, "N", "only train that way");
+
+        let empty = DisplaySnapshot { trains: Vec::new(), ..snap };
+        assert_eq!(by_direction(&empty, &Suspension::default()).0.minutes, Train::empty().minutes);
+    }
+
+    #[test]
+    fn test_rule_config_form() {
+        let rules: Vec<TopRowRule> =
+            serde_json::from_str(r#"[{"stop": "R20N"}, {"route": "Q"}, {"direction": "uptown"}, "soonest"]"#)
+                .unwrap();
+        assert_eq!(
+            rules,
+            vec![
+                TopRowRule::Stop("R20N".into()),
+                TopRowRule::Route("Q".into()),
+                TopRowRule::Direction(Direction::Uptown),
+                TopRowRule::Soonest
+            ]
+        );
+    }
+}
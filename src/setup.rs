@@ -0,0 +1,387 @@
+//! Wi-Fi provisioning for a sign that boots with no network (`config.setup`).
+//!
+//! With `setup.wifi_portal` on and still no default route a minute after
+//! startup, the sign opens its own access point through NetworkManager,
+//! answers every DNS name with its own address so phones pop up a captive
+//! portal, and serves a page on port 80 asking for the home network's
+//! credentials. The panel
+//! shows which network to join. Once the sign is online the portal shuts
+//! down; everything else was running all along and simply starts getting
+//! data.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use axum::extract::{Form, State};
+use axum::response::Html;
+use axum::routing::{get, post};
+use axum::Router;
+use serde::Deserialize;
+use tokio::process::Command;
+use tokio::sync::mpsc;
+use tracing::{error, info, warn};
+
+use crate::config::SetupConfig;
+use crate::AppState;
+
+/// The portal listens on plain HTTP so captive-portal probes find it.
+const PORTAL_ADDR: &str = "0.0.0.0:80";
+
+/// NetworkManager connection name for the setup access point.
+const HOTSPOT_CONNECTION: &str = "subway-sign-setup";
+
+/// Address NetworkManager gives the host on a shared (hotspot) connection.
+const HOTSPOT_GATEWAY: &str = "10.42.0.1";
+
+/// Makes the hotspot's dnsmasq resolve every name to the sign. NetworkManager
+/// only reads this directory, so the service unit has to leave it writable.
+const DNSMASQ_CONF: &str = "/etc/NetworkManager/dnsmasq-shared.d/subway-sign-portal.conf";
+
+/// How long a sign that boots offline waits for a network (slow DHCP, say)
+/// before opening the portal.
+const BOOT_GRACE: Duration = Duration::from_secs(60);
+
+/// How long to wait for a new Wi-Fi connection to come up.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(45);
+
+/// How often to check whether the sign got online some other way
+/// (e.g. an Ethernet cable).
+const ONLINE_POLL: Duration = Duration::from_secs(10);
+
+/// Whether the kernel routing table (`/proc/net/route`) has a default route.
+fn has_default_route(route_table: &str) -> bool {
+    route_table.lines().skip(1).any(|line| {
+        let mut fields = line.split_whitespace();
+        let _iface = fields.next();
+        fields.next() == Some("00000000")
+    })
+}
+
+/// Whether the sign has a network to reach the MTA feeds through.
+pub fn online() -> bool {
+    std::fs::read_to_string("/proc/net/route").is_ok_and(|t| has_default_route(&t))
+}
+
+/// Parse `nmcli -t -f SSID,SIGNAL device wifi list`: one entry per network
+/// name (strongest signal), strongest first, hidden networks skipped.
+fn parse_scan(output: &str) -> Vec<(String, u8)> {
+    let mut networks: Vec<(String, u8)> = Vec::new();
+    for line in output.lines() {
+        let Some((ssid, signal)) = line.rsplit_once(':') else {
+            continue;
+        };
+        // Terse mode escapes ':' and '\' in values
+        let ssid = ssid.replace("\\:", ":").replace("\\\\", "\\");
+        let signal = signal.trim().parse().unwrap_or(0);
+        if ssid.is_empty() {
+            continue;
+        }
+        match networks.iter_mut().find(|(s, _)| *s == ssid) {
+            Some(existing) => existing.1 = existing.1.max(signal),
+            None => networks.push((ssid, signal)),
+        }
+    }
+    networks.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    networks
+}
+
+fn hotspot_args(setup: &SetupConfig) -> Vec<String> {
+    let mut args: Vec<String> = ["device", "wifi", "hotspot", "ifname", &setup.interface]
+        .into_iter()
+        .chain(["con-name", HOTSPOT_CONNECTION, "ssid", &setup.ssid])
+        .map(String::from)
+        .collect();
+    if let Some(password) = &setup.password {
+        args.extend(["password".to_string(), password.clone()]);
+    }
+    args
+}
+
+fn connect_args(interface: &str, credentials: &Credentials) -> Vec<String> {
+    let mut args: Vec<String> = ["device", "wifi", "connect", &credentials.ssid, "ifname", interface]
+        .into_iter()
+        .map(String::from)
+        .collect();
+    if !credentials.password.is_empty() {
+        args.extend(["password".to_string(), credentials.password.clone()]);
+    }
+    args
+}
+
+/// Run nmcli, returning stdout or a message with its stderr.
+async fn nmcli(args: &[String]) -> Result<String, String> {
+    let output = Command::new("nmcli")
+        .args(args)
+        .output()
+        .await
+        .map_err(|e| format!("cannot run nmcli: {}", e))?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+/// Home network credentials from the portal form.
+#[derive(Debug, Clone, Deserialize)]
+struct Credentials {
+    ssid: String,
+    #[serde(default)]
+    password: String,
+}
+
+struct Portal {
+    setup_ssid: String,
+    /// Networks seen before the access point went up.
+    networks: Vec<(String, u8)>,
+    /// Outcome of the last attempt, shown on the page.
+    message: Mutex<Option<String>>,
+    submit: mpsc::Sender<Credentials>,
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn render_page(networks: &[(String, u8)], message: Option<&str>) -> String {
+    let options: String = networks
+        .iter()
+        .map(|(ssid, _)| format!("<option value=\"{}\">", escape_html(ssid)))
+        .collect();
+    let message = message
+        .map(|m| format!("<p class=\"msg\">{}</p>", escape_html(m)))
+        .unwrap_or_default();
+    format!(
+        r#"<!DOCTYPE html>
+<html><head><meta charset="utf-8"><meta name="viewport" content="width=device-width, initial-scale=1">
+<title>Subway Sign setup</title>
+<style>body{{font-family:sans-serif;background:#111;color:#eee;max-width:420px;margin:2em auto;padding:0 1em}}
+input,button{{width:100%;box-sizing:border-box;font-size:1.1em;padding:.6em;margin:.3em 0 1em}}
+button{{background:#0039A6;color:#fff;border:0;border-radius:4px}}.msg{{color:#FCCC0A}}</style></head>
+<body><h1>Subway Sign setup</h1>{message}
+<p>Choose the Wi-Fi network the sign should use.</p>
+<form method="post" action="/connect">
+<label>Network<input name="ssid" list="networks" required autocomplete="off"></label>
+<datalist id="networks">{options}</datalist>
+<label>Password<input name="password" type="password" autocomplete="off"></label>
+<button type="submit">Connect</button></form></body></html>"#
+    )
+}
+
+/// Every path gets the form, which is what captive-portal probes look for.
+async fn portal_page(State(portal): State<Arc<Portal>>) -> Html<String> {
+    let message = portal.message.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    Html(render_page(&portal.networks, message.as_deref()))
+}
+
+async fn portal_connect(
+    State(portal): State<Arc<Portal>>,
+    Form(credentials): Form<Credentials>,
+) -> Html<String> {
+    let ssid = escape_html(&credentials.ssid);
+    // A second submit while the first is being tried is dropped
+    let _ = portal.submit.try_send(credentials);
+    Html(format!(
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><meta name=\"viewport\" \
+         content=\"width=device-width, initial-scale=1\"></head>\
+         <body style=\"font-family:sans-serif\"><h1>Connecting to {}&hellip;</h1>\
+         <p>This network will disappear. Arrivals appear on the sign once it's online; \
+         if the sign asks again, rejoin <b>{}</b> and check the password.</p></body></html>",
+        ssid,
+        escape_html(&portal.setup_ssid)
+    ))
+}
+
+async fn scan(interface: &str) -> Vec<(String, u8)> {
+    let args: Vec<String> = ["-t", "-f", "SSID,SIGNAL", "device", "wifi", "list", "ifname", interface, "--rescan", "yes"]
+        .into_iter()
+        .map(String::from)
+        .collect();
+    match nmcli(&args).await {
+        Ok(output) => parse_scan(&output),
+        Err(e) => {
+            warn!("[SETUP] Wi-Fi scan failed: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+async fn start_hotspot(setup: &SetupConfig) -> Result<(), String> {
+    let written = async {
+        if let Some(dir) = std::path::Path::new(DNSMASQ_CONF).parent() {
+            tokio::fs::create_dir_all(dir).await?;
+        }
+        tokio::fs::write(DNSMASQ_CONF, format!("address=/#/{}\n", HOTSPOT_GATEWAY)).await
+    };
+    if let Err(e) = written.await {
+        warn!("[SETUP] Cannot write {} ({}); phones won't open the page by themselves", DNSMASQ_CONF, e);
+    }
+    nmcli(&hotspot_args(setup)).await.map(|_| ())
+}
+
+async fn stop_hotspot() {
+    let args = ["connection", "delete", HOTSPOT_CONNECTION].map(String::from);
+    let _ = nmcli(&args).await;
+    let _ = tokio::fs::remove_file(DNSMASQ_CONF).await;
+}
+
+/// Wait until the sign is online, up to `timeout`.
+async fn wait_online(timeout: Duration) -> bool {
+    let deadline = tokio::time::Instant::now() + timeout;
+    while tokio::time::Instant::now() < deadline {
+        if online() {
+            return true;
+        }
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+    online()
+}
+
+/// Run the setup portal if it's enabled and the sign is still offline
+/// `BOOT_GRACE` after starting; returns once the sign is online (or
+/// immediately otherwise).
+pub async fn portal_task(state: Arc<AppState>) {
+    let setup = state.config.load().setup.clone();
+    if !setup.wifi_portal {
+        return;
+    }
+    tokio::select! {
+        _ = state.shutdown.cancelled() => return,
+        got_online = wait_online(BOOT_GRACE) => {
+            if got_online {
+                return;
+            }
+        }
+    }
+    if nmcli(&["--version".to_string()]).await.is_err() {
+        warn!("[SETUP] No network, but the Wi-Fi portal needs NetworkManager (nmcli)");
+        return;
+    }
+
+    info!("[SETUP] No network — starting Wi-Fi setup portal '{}'", setup.ssid);
+    let networks = scan(&setup.interface).await;
+    let (submit, mut submitted) = mpsc::channel(1);
+    let portal = Arc::new(Portal {
+        setup_ssid: setup.ssid.clone(),
+        networks,
+        message: Mutex::new(None),
+        submit,
+    });
+
+    let listener = match tokio::net::TcpListener::bind(PORTAL_ADDR).await {
+        Ok(l) => l,
+        Err(e) => {
+            error!("[SETUP] Cannot bind {}: {}", PORTAL_ADDR, e);
+            return;
+        }
+    };
+    let app = Router::new()
+        .route("/connect", post(portal_connect))
+        .fallback(get(portal_page))
+        .with_state(portal.clone());
+    let portal_done = state.shutdown.child_token();
+    let server = tokio::spawn({
+        let done = portal_done.clone();
+        async move {
+            axum::serve(listener, app)
+                .with_graceful_shutdown(async move { done.cancelled().await })
+                .await
+                .ok();
+        }
+    });
+
+    'portal: loop {
+        if let Err(e) = start_hotspot(&setup).await {
+            error!("[SETUP] Cannot start access point: {}", e);
+            break;
+        }
+        state.setup_ssid.store(Arc::new(Some(setup.ssid.clone())));
+
+        let mut poll = tokio::time::interval(ONLINE_POLL);
+        let credentials = loop {
+            tokio::select! {
+                _ = state.shutdown.cancelled() => break 'portal,
+                Some(credentials) = submitted.recv() => break credentials,
+                _ = poll.tick() => {
+                    if online() {
+                        break 'portal;
+                    }
+                }
+            }
+        };
+
+        // The access point shares the radio, so it has to go first (after
+        // the "Connecting" page has reached the phone)
+        info!("[SETUP] Joining Wi-Fi network '{}'", credentials.ssid);
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        stop_hotspot().await;
+        let joined = match nmcli(&connect_args(&setup.interface, &credentials)).await {
+            Ok(_) => wait_online(CONNECT_TIMEOUT).await,
+            Err(e) => {
+                warn!("[SETUP] nmcli connect failed: {}", e);
+                false
+            }
+        };
+        if joined {
+            info!("[SETUP] Online via '{}'", credentials.ssid);
+            break;
+        }
+        warn!("[SETUP] Could not join '{}'; reopening setup portal", credentials.ssid);
+        *portal.message.lock().unwrap_or_else(|e| e.into_inner()) = Some(format!(
+            "Couldn't connect to \"{}\". Check the network name and password.",
+            credentials.ssid
+        ));
+    }
+
+    stop_hotspot().await;
+    state.setup_ssid.store(Arc::new(None));
+    portal_done.cancel();
+    let _ = server.await;
+    info!("[SETUP] Setup portal stopped");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_has_default_route() {
+        let header = "Iface\tDestination\tGateway \tFlags\tRefCnt\tUse\tMetric\tMask\t\tMTU\tWindow\tIRTT\n";
+        let lan = "wlan0\t0000A8C0\t00000000\t0001\t0\t0\t600\t00FFFFFF\t0\t0\t0\n";
+        let default = "wlan0\t00000000\t0100A8C0\t0003\t0\t0\t600\t00000000\t0\t0\t0\n";
+        assert!(!has_default_route(header));
+        assert!(!has_default_route(&format!("{header}{lan}")));
+        assert!(has_default_route(&format!("{header}{lan}{default}")));
+    }
+
+    #[test]
+    fn test_parse_scan() {
+        let output = "Home:40\nHome:72\n:80\nCafe\\: Free:55\nOffice:72\n";
+        assert_eq!(
+            parse_scan(output),
+            vec![("Home".into(), 72), ("Office".into(), 72), ("Cafe: Free".into(), 55)]
+        );
+    }
+
+    #[test]
+    fn test_nmcli_args() {
+        let mut setup = SetupConfig::default();
+        assert_eq!(hotspot_args(&setup).last().unwrap(), "SubwaySign-Setup");
+        setup.password = Some("subwaysign".into());
+        assert_eq!(hotspot_args(&setup)[7..], ["ssid", "SubwaySign-Setup", "password", "subwaysign"]);
+
+        let open = Credentials { ssid: "Cafe".into(), password: String::new() };
+        assert_eq!(connect_args("wlan0", &open), ["device", "wifi", "connect", "Cafe", "ifname", "wlan0"]);
+    }
+
+    #[test]
+    fn test_page_escapes_network_names() {
+        let page = render_page(&[("<b>Evil</b>".into(), 50)], Some("Couldn't connect to \"x\""));
+        assert!(page.contains("&lt;b&gt;Evil&lt;/b&gt;"));
+        assert!(!page.contains("<b>Evil"));
+        assert!(page.contains("&quot;x&quot;"));
+    }
+}
@@ -0,0 +1,168 @@
+//! State shared by the daemon's tasks and web handlers, and the helpers
+//! that act on it.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use arc_swap::ArcSwap;
+use tokio::sync::broadcast;
+use tokio_util::sync::CancellationToken;
+
+use crate::{config, models, mta, netinfo, telemetry, DisplaySnapshot, FrameBuffer};
+use crate::analytics::Analytics;
+use crate::config::Config;
+use crate::display::activity::FrameActivity;
+use crate::display::timing::TimingReport;
+use crate::lifecycle::RestartControl;
+use crate::logging::LogHub;
+use crate::mta::alerts::AlertManager;
+use crate::web::access_log::RequestLog;
+use crate::web::raw_frame::FrameHistory;
+
+/// Shared application state — lock-free reads via ArcSwap.
+pub struct AppState {
+    pub config: ArcSwap<Config>,
+    pub snapshot: ArcSwap<DisplaySnapshot>,
+    /// Trips of the trains in `snapshot`, by trip ID.
+    pub trips: ArcSwap<HashMap<String, models::TripDetail>>,
+    /// Second-leg arrivals at the trip's transfer station (see `trip`).
+    pub transfer_trains: ArcSwap<Vec<models::Train>>,
+    /// Most recent frame pushed to the display (published by the render loop).
+    pub latest_frame: ArcSwap<FrameBuffer>,
+    /// Every rendered frame, while anyone is subscribed (`/ws/preview`).
+    pub frame_updates: broadcast::Sender<Arc<FrameBuffer>>,
+    /// Whether the picture is changing (sampled by the render loop).
+    pub frame_activity: Mutex<FrameActivity>,
+    /// Frame work time percentiles per window (published by the render loop).
+    pub frame_timing: ArcSwap<Vec<TimingReport>>,
+    /// The fetch behind the latest snapshot (see `telemetry`).
+    pub fetch_trace: ArcSwap<telemetry::FetchTrace>,
+    /// Fetch-to-panel numbers for the snapshot on screen (published by the
+    /// render loop).
+    pub pipeline: ArcSwap<telemetry::PipelineReport>,
+    pub alert_manager: Mutex<AlertManager>,
+    /// Every alert from the last fetch, for the alerts page; replaced only
+    /// when the set changes.
+    pub fetched_alerts: ArcSwap<Vec<models::Alert>>,
+    /// Routes with a NO_SERVICE alert (from the unfiltered alert feed).
+    pub suspended_routes: ArcSwap<HashSet<String>>,
+    /// Routes in service now, per `route_schedule` and the static GTFS
+    /// schedule (see `route_schedule_task`).
+    pub active_routes: ArcSwap<Vec<String>>,
+    /// Active routes whose feed failed on the last train fetch, sorted;
+    /// their trains are from cache.
+    pub degraded_routes: ArcSwap<Vec<String>>,
+    /// Static GTFS timetable, when `static_gtfs` is enabled and loaded.
+    pub service_schedule: ArcSwap<Option<mta::schedule::ServiceSchedule>>,
+    /// Setup access point name while the Wi-Fi portal is open (see `setup`).
+    pub setup_ssid: ArcSwap<Option<String>>,
+    /// Full-screen text (the sign's address) overriding normal rendering.
+    pub info_screen: ArcSwap<Option<netinfo::InfoScreen>>,
+    /// Arrival tracking and today's service stats.
+    pub analytics: Mutex<Analytics>,
+    /// Recent + live log lines for the web UI.
+    pub logs: LogHub,
+    /// Recent HTTP requests (see `web::access_log`).
+    pub http_log: RequestLog,
+    /// Frames recently served as raw bytes (see `web::raw_frame`).
+    pub served_frames: FrameHistory,
+    pub config_path: PathBuf,
+    /// Instance name when running one of several signs (see `instance`).
+    pub instance: Option<String>,
+    pub shutdown: CancellationToken,
+    /// Confirmed process restart requested via the web API.
+    pub restart: RestartControl,
+    pub config_changed: tokio::sync::Notify,
+    /// Wakes the fleet sync task for an immediate poll of the source.
+    pub fleet_sync: tokio::sync::Notify,
+    pub last_fetch_success: AtomicU64,
+    pub last_render_tick: AtomicU64,
+    /// Since when (unix secs) fetches have succeeded with trips on the
+    /// configured routes but none at the configured stops; 0 otherwise.
+    pub no_match_since: AtomicU64,
+    /// Local dates the `calendar` feed marks as away (sign dark all day).
+    pub away_days: ArcSwap<HashSet<chrono::NaiveDate>>,
+    /// Alerts and journey/alternate banners paused, train times only (see
+    /// `set_do_not_disturb`).
+    pub do_not_disturb: AtomicBool,
+    /// A `POST /api/record` capture is running (one at a time).
+    pub recording: AtomicBool,
+    /// Held while `favorites.json` is read, changed and written back.
+    pub favorites_lock: Mutex<()>,
+}
+
+/// Frames a slow `frame_updates` subscriber may fall behind before it
+/// skips ahead.
+pub const FRAME_UPDATES_CAPACITY: usize = 4;
+
+/// Whether the `calendar` feed marks today as away.
+pub fn away_today(state: &AppState) -> bool {
+    let config = state.config.load();
+    config.calendar.is_some() && state.away_days.load().contains(&config.tz().today())
+}
+
+/// Whether the panel should be dark now: `display.off_hours`, or an away
+/// day from the `calendar` feed (unless it sets `away_brightness`).
+pub fn display_off(state: &AppState) -> bool {
+    let config = state.config.load();
+    let away_dark = config.calendar.as_ref().is_some_and(|c| c.away_brightness.is_none());
+    config.off_hours_now() || (away_dark && away_today(state))
+}
+
+/// Switch to the station database the config at `config_path` names, if
+/// that's not the one in use. Config station names resolve against it, so
+/// call this before loading the config.
+pub fn load_station_database(config_path: &Path) {
+    let path = config::stations_path(config_path);
+    if path == mta::stations::station_database_path() {
+        return;
+    }
+    match mta::stations::set_station_database(path.as_deref()) {
+        Ok(count) => match &path {
+            Some(path) => tracing::info!("[STATIONS] {} stations from {}", count, path.display()),
+            None => tracing::info!("[STATIONS] Back to the embedded database ({} stations)", count),
+        },
+        Err(e) => tracing::warn!("[STATIONS] {}; keeping the station database in use", e),
+    }
+}
+
+/// Make `config` current. The fetch task re-fetches only when it changes
+/// what is fetched (see `Config::diff`), so display-only edits don't churn
+/// the trains on screen.
+pub fn apply_config(state: &AppState, config: Config) -> config::ConfigChange {
+    let change = config.diff(&state.config.load());
+    state.config.store(Arc::new(config));
+    if change.data {
+        state.config_changed.notify_one();
+    }
+    change
+}
+
+/// Turn do-not-disturb on or off (`None` toggles); returns the new state.
+/// Shared by the web API and GPIO buttons.
+pub fn set_do_not_disturb(state: &AppState, enabled: Option<bool>) -> bool {
+    let enabled = match enabled {
+        Some(enabled) => enabled,
+        None => !state.do_not_disturb.load(Ordering::Relaxed),
+    };
+    if state.do_not_disturb.swap(enabled, Ordering::Relaxed) != enabled {
+        tracing::info!("[DISPLAY] Do not disturb {}", if enabled { "on" } else { "off" });
+    }
+    enabled
+}
+
+/// How long nothing may match before the station/routes config is blamed
+/// (short gaps happen, e.g. a reroute around one stop).
+pub const CONFIG_MISMATCH_AFTER_SECS: u64 = 20 * 60;
+
+/// Seconds the configured stops have matched no trains while the feeds were
+/// up and running the configured routes, once that has lasted
+/// `CONFIG_MISMATCH_AFTER_SECS`. Feed outages and routes that aren't
+/// running (nights, weekends) never count.
+pub fn config_mismatch_secs(state: &AppState, now: u64) -> Option<u64> {
+    let since = state.no_match_since.load(Ordering::Relaxed);
+    let elapsed = now.saturating_sub(since);
+    (since != 0 && elapsed >= CONFIG_MISMATCH_AFTER_SECS).then_some(elapsed)
+}
@@ -0,0 +1,181 @@
+//! Tracing from MTA feed to pixels.
+//!
+//! Each train fetch gets an ID and a `fetch_cycle` span that lives until its
+//! snapshot leaves the screen, with a `fetch` child for the feed requests and
+//! a `draw` child opened when the first frame from the snapshot reaches the
+//! panel. The render loop counts the frames drawn from each snapshot; the
+//! latest numbers are served with the frame timing (`/api/display/timing`).
+//!
+//! With the `otlp` feature, setting `OTEL_EXPORTER_OTLP_ENDPOINT` also
+//! exports the spans over OTLP/HTTP.
+
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tracing::{debug, field, info_span, Span};
+
+/// The fetch behind the current snapshot (published by the fetch task).
+pub struct FetchTrace {
+    pub id: u64,
+    /// When the feed requests started.
+    pub started: Instant,
+    /// How long the requests took, up to the snapshot being stored.
+    pub fetch_time: Duration,
+    /// The snapshot's `fetched_at`, to match it in the render loop.
+    pub fetched_at: f64,
+    /// The `fetch_cycle` span.
+    pub span: Span,
+}
+
+impl FetchTrace {
+    /// Before the first fetch.
+    pub fn none() -> Self {
+        FetchTrace {
+            id: 0,
+            started: Instant::now(),
+            fetch_time: Duration::ZERO,
+            fetched_at: 0.0,
+            span: Span::none(),
+        }
+    }
+}
+
+/// Pipeline numbers for the snapshot on screen.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct PipelineReport {
+    /// 0 before the first fetch, or if the snapshot couldn't be matched.
+    pub fetch_id: u64,
+    pub fetch_ms: u64,
+    /// Feed requests started to first frame on the panel.
+    pub first_frame_ms: u64,
+    pub frames: u64,
+}
+
+/// Render-side tracking of the snapshot being drawn.
+#[derive(Default)]
+pub struct SnapshotFrames {
+    fetched_at: Option<f64>,
+    report: PipelineReport,
+    /// The `draw` span, closed when the snapshot is replaced.
+    draw: Option<Span>,
+}
+
+impl SnapshotFrames {
+    /// Count a frame pushed at `now` from the snapshot fetched at
+    /// `fetched_at`; `trace` is the latest fetch.
+    pub fn record(&mut self, fetched_at: f64, trace: &FetchTrace, now: Instant) {
+        if self.fetched_at != Some(fetched_at) {
+            self.finish();
+            // A fetch that landed since this frame's snapshot was loaded
+            // isn't the one drawn
+            let matched = trace.fetched_at == fetched_at;
+            let first_frame = now.saturating_duration_since(trace.started);
+            self.fetched_at = Some(fetched_at);
+            self.report = PipelineReport {
+                fetch_id: if matched { trace.id } else { 0 },
+                fetch_ms: if matched { trace.fetch_time.as_millis() as u64 } else { 0 },
+                first_frame_ms: if matched { first_frame.as_millis() as u64 } else { 0 },
+                frames: 0,
+            };
+            if matched && trace.id != 0 {
+                debug!(
+                    "[PIPELINE] Fetch {} on the panel {}ms after its requests started",
+                    trace.id,
+                    first_frame.as_millis()
+                );
+                self.draw = Some(info_span!(
+                    parent: &trace.span,
+                    "draw",
+                    fetch_id = trace.id,
+                    first_frame_ms = first_frame.as_millis() as u64,
+                    frames = field::Empty
+                ));
+            }
+        }
+        self.report.frames += 1;
+    }
+
+    /// Close the current snapshot's `draw` span with its frame count.
+    fn finish(&mut self) {
+        if let Some(draw) = self.draw.take() {
+            draw.record("frames", self.report.frames);
+        }
+    }
+
+    pub fn report(&self) -> PipelineReport {
+        self.report.clone()
+    }
+}
+
+/// OTLP/HTTP export of spans, when `OTEL_EXPORTER_OTLP_ENDPOINT` is set
+/// (the exporter reads the other `OTEL_*` variables itself).
+#[cfg(feature = "otlp")]
+pub fn otlp_layer<S>() -> Option<impl tracing_subscriber::Layer<S>>
+where
+    S: tracing::Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>,
+{
+    use opentelemetry::trace::TracerProvider as _;
+
+    std::env::var_os("OTEL_EXPORTER_OTLP_ENDPOINT")?;
+    // Tracing isn't set up yet
+    let exporter = match opentelemetry_otlp::SpanExporter::builder().with_http().build() {
+        Ok(exporter) => exporter,
+        Err(e) => {
+            eprintln!("OTLP export disabled: {}", e);
+            return None;
+        }
+    };
+    let provider = opentelemetry_sdk::trace::SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(opentelemetry_sdk::Resource::builder().with_service_name("subway-sign").build())
+        .build();
+    let tracer = provider.tracer("subway-sign");
+    let _ = OTLP_PROVIDER.set(provider);
+    Some(tracing_opentelemetry::layer().with_tracer(tracer))
+}
+
+#[cfg(feature = "otlp")]
+static OTLP_PROVIDER: std::sync::OnceLock<opentelemetry_sdk::trace::SdkTracerProvider> =
+    std::sync::OnceLock::new();
+
+/// Send any spans still batched for export.
+pub fn shutdown() {
+    #[cfg(feature = "otlp")]
+    if let Some(provider) = OTLP_PROVIDER.get() {
+        if let Err(e) = provider.shutdown() {
+            eprintln!("OTLP shutdown: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_frames_follow_fetches() {
+        let t0 = Instant::now();
+        let at = |ms| t0 + Duration::from_millis(ms);
+        let trace = FetchTrace {
+            id: 7,
+            started: t0,
+            fetch_time: Duration::from_millis(120),
+            fetched_at: 1000.0,
+            span: Span::none(),
+        };
+        let mut frames = SnapshotFrames::default();
+
+        frames.record(1000.0, &trace, at(150));
+        frames.record(1000.0, &trace, at(166));
+        let report = frames.report();
+        assert_eq!((report.fetch_id, report.fetch_ms, report.first_frame_ms, report.frames), (7, 120, 150, 2));
+
+        // The next fetch stored its trace before this frame's snapshot was
+        // replaced: not counted as that fetch's
+        let next = FetchTrace { id: 8, fetched_at: 1030.0, ..trace };
+        frames.record(1015.0, &next, at(200));
+        assert_eq!(frames.report().fetch_id, 0);
+        frames.record(1030.0, &next, at(216));
+        assert_eq!((frames.report().fetch_id, frames.report().frames), (8, 1));
+    }
+}
@@ -0,0 +1,82 @@
+//! The sign's time zone: `timezone` in the config (an IANA name such as
+//! "America/New_York"), or the system's when unset.
+//!
+//! Everything that turns timestamps into wall-clock time — arrival clock
+//! times, `route_schedule` windows, static GTFS times, analytics days —
+//! goes through this, so a Pi whose system zone is UTC or wrong still shows
+//! local times and changes for DST on the right night.
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Timezone {
+    /// The system's local time (`TZ`, `/etc/localtime`).
+    #[default]
+    System,
+    Named(Tz),
+}
+
+impl Timezone {
+    /// Parse an IANA zone name; empty means the system zone.
+    pub fn parse(name: &str) -> Result<Self, String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Ok(Timezone::System);
+        }
+        name.parse::<Tz>()
+            .map(Timezone::Named)
+            .map_err(|_| format!("unknown time zone '{}' (expected an IANA name like America/New_York)", name))
+    }
+
+    /// Wall-clock time now.
+    pub fn now(&self) -> NaiveDateTime {
+        self.local(Utc::now())
+    }
+
+    pub fn today(&self) -> NaiveDate {
+        self.now().date()
+    }
+
+    /// Wall-clock time of `utc`.
+    pub fn local(&self, utc: DateTime<Utc>) -> NaiveDateTime {
+        match self {
+            Timezone::System => utc.with_timezone(&chrono::Local).naive_local(),
+            Timezone::Named(tz) => utc.with_timezone(tz).naive_local(),
+        }
+    }
+
+    /// Wall-clock time of a unix timestamp.
+    pub fn from_timestamp(&self, secs: i64) -> Option<NaiveDateTime> {
+        DateTime::from_timestamp(secs, 0).map(|t| self.local(t))
+    }
+
+    /// Unix time of a wall-clock time: the earlier one when the clocks go
+    /// back, None in the hour skipped when they go forward.
+    pub fn timestamp(&self, local: NaiveDateTime) -> Option<i64> {
+        match self {
+            Timezone::System => chrono::Local.from_local_datetime(&local).earliest().map(|t| t.timestamp()),
+            Timezone::Named(tz) => tz.from_local_datetime(&local).earliest().map(|t| t.timestamp()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_named_zone_follows_dst() {
+        let ny = Timezone::parse("America/New_York").unwrap();
+        let at = |s: &str| NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap();
+        // 2026-03-08: clocks go forward at 2am; 2026-11-01: back at 2am
+        assert_eq!(ny.from_timestamp(1_772_949_600), Some(at("2026-03-08 01:00")));
+        assert_eq!(ny.from_timestamp(1_772_949_600 + 3600), Some(at("2026-03-08 03:00")));
+        assert_eq!(ny.timestamp(at("2026-03-08 02:30")), None, "skipped hour");
+        assert_eq!(ny.timestamp(at("2026-11-01 01:30")), Some(1_793_511_000), "earlier of two");
+        assert_eq!(ny.timestamp(at("2026-07-01 12:00")).and_then(|t| ny.from_timestamp(t)), Some(at("2026-07-01 12:00")));
+
+        assert_eq!(Timezone::parse("").unwrap(), Timezone::System);
+        assert!(Timezone::parse("Mars/Olympus_Mons").is_err());
+    }
+}
@@ -0,0 +1,146 @@
+//! Connection evaluator for a two-leg trip (`config.trip`).
+//!
+//! For each first-leg train at this sign's station, estimate when it
+//! reaches the transfer station (`ride_minutes` later), add the platform
+//! change, and look for the next second-leg train in the transfer
+//! station's own arrivals. The renderer draws the result as a status glyph
+//! on that train's row.
+
+use serde::Serialize;
+
+use crate::config::{TripConfig, TripLeg};
+use crate::models::Train;
+
+/// Waits shorter than this (seconds) leave no slack for prediction error.
+const TIGHT_WAIT_SECS: f64 = 60.0;
+
+/// Second-leg arrivals fetched at the transfer station.
+pub const TRANSFER_TRAINS: usize = 20;
+
+/// Whether a first-leg train makes the connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConnectionStatus {
+    /// A second-leg train comes within the maximum wait.
+    Feasible,
+    /// Feasible, but with under a minute to spare.
+    Tight,
+    /// The next second-leg train is more than the maximum wait away.
+    Infeasible,
+}
+
+/// The connection from one first-leg train.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Connection {
+    pub route: String,
+    /// First-leg arrival at this sign's station (identifies the train).
+    pub departs_at: f64,
+    /// When you'd be on the second-leg platform.
+    pub ready_at: f64,
+    pub next_route: String,
+    pub next_departs_at: f64,
+    pub wait_secs: f64,
+    pub status: ConnectionStatus,
+}
+
+fn on_leg(train: &Train, leg: &TripLeg) -> bool {
+    leg.routes.iter().any(|r| r.eq_ignore_ascii_case(&train.route))
+        && leg.direction.is_none_or(|d| train.direction == d)
+}
+
+/// Connections for each first-leg train in `home`, given second-leg
+/// arrivals at the transfer station. Trains past the end of the transfer
+/// predictions have no entry (unknown, rather than infeasible).
+pub fn connections(home: &[Train], transfer: &[Train], trip: &TripConfig) -> Vec<Connection> {
+    let change_secs = (trip.ride_minutes + trip.transfer_minutes) as f64 * 60.0;
+    let max_wait = trip.max_wait_minutes as f64 * 60.0;
+
+    home.iter()
+        .filter(|t| on_leg(t, &trip.first))
+        .filter_map(|first| {
+            let ready_at = first.arrival_timestamp + change_secs;
+            let next = transfer
+                .iter()
+                .filter(|t| on_leg(t, &trip.second) && t.arrival_timestamp >= ready_at)
+                .min_by(|a, b| a.arrival_timestamp.total_cmp(&b.arrival_timestamp))?;
+            let wait_secs = next.arrival_timestamp - ready_at;
+            let status = if wait_secs > max_wait {
+                ConnectionStatus::Infeasible
+            } else if wait_secs < TIGHT_WAIT_SECS {
+                ConnectionStatus::Tight
+            } else {
+                ConnectionStatus::Feasible
+            };
+            Some(Connection {
+                route: first.route.clone(),
+                departs_at: first.arrival_timestamp,
+                ready_at,
+                next_route: next.route.clone(),
+                next_departs_at: next.arrival_timestamp,
+                wait_secs,
+                status,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Direction;
+
+    fn train(route: &str, direction: Direction, at_min: f64) -> Train {
+        Train {
+            route: route.into(),
+            destination: "Test".into(),
+            minutes: at_min as i32,
+            is_express: false,
+            arrival_timestamp: at_min * 60.0,
+            direction,
+            stop_id: String::new(),
+            delay_secs: None,
+            trip_id: String::new(),
+        }
+    }
+
+    fn trip() -> TripConfig {
+        TripConfig {
+            first: TripLeg { routes: vec!["L".into()], direction: None },
+            transfer_station: "14 St-Union Sq".into(),
+            second: TripLeg {
+                routes: vec!["4".into(), "5".into()],
+                direction: Some(Direction::Uptown),
+            },
+            ride_minutes: 4,
+            transfer_minutes: 2,
+            max_wait_minutes: 8,
+        }
+    }
+
+    #[test]
+    fn test_connection_status_per_first_leg_train() {
+        use ConnectionStatus::*;
+        let home = [
+            train("L", Direction::Downtown, 1.0),  // ready at 7
+            train("G", Direction::Downtown, 2.0),  // not on the first leg
+            train("L", Direction::Downtown, 5.5),  // ready at 11.5
+            train("L", Direction::Downtown, 10.0), // ready at 16
+            train("L", Direction::Downtown, 30.0), // beyond transfer predictions
+        ];
+        let transfer = [
+            train("4", Direction::Uptown, 9.0),
+            train("5", Direction::Downtown, 12.0), // wrong direction
+            train("5", Direction::Uptown, 12.0),
+            train("4", Direction::Uptown, 25.0),
+        ];
+        let c = connections(&home, &transfer, &trip());
+        let summary: Vec<_> = c.iter().map(|c| (c.departs_at / 60.0, c.status)).collect();
+        assert_eq!(summary, vec![(1.0, Feasible), (5.5, Tight), (10.0, Infeasible)]);
+        assert_eq!((c[1].next_route.as_str(), c[1].wait_secs), ("5", 30.0));
+    }
+
+    #[test]
+    fn test_no_transfer_data_means_no_verdict() {
+        assert!(connections(&[train("L", Direction::Downtown, 1.0)], &[], &trip()).is_empty());
+    }
+}
@@ -0,0 +1,163 @@
+//! HTTP access log.
+//!
+//! Every request passes through `access_log`, which keeps the most recent
+//! ones in a ring buffer (served at `/api/logs/http`) and, depending on
+//! `web.access_log`, writes an `[HTTP]` line to the log.
+
+use std::collections::{HashMap, VecDeque};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use axum::extract::{ConnectInfo, Request, State};
+use axum::http::header;
+use axum::middleware::Next;
+use axum::response::Response;
+use serde::Serialize;
+use tracing::{info, warn};
+
+use crate::config::AccessLogLevel;
+use crate::AppState;
+
+/// Number of recent requests kept.
+const RECENT_CAPACITY: usize = 500;
+
+/// One handled request.
+#[derive(Debug, Clone, Serialize)]
+pub struct RequestRecord {
+    /// RFC 3339 local timestamp.
+    pub timestamp: String,
+    pub method: String,
+    /// Path only; query strings are left out.
+    pub path: String,
+    pub status: u16,
+    pub latency_ms: f64,
+    pub client: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+/// Bounded buffer of recent requests.
+pub struct RequestLog {
+    recent: Mutex<VecDeque<RequestRecord>>,
+}
+
+impl RequestLog {
+    pub fn new() -> Self {
+        RequestLog {
+            recent: Mutex::new(VecDeque::with_capacity(RECENT_CAPACITY)),
+        }
+    }
+
+    fn push(&self, record: RequestRecord) {
+        let mut recent = self.recent.lock().unwrap_or_else(|e| e.into_inner());
+        if recent.len() == RECENT_CAPACITY {
+            recent.pop_front();
+        }
+        recent.push_back(record);
+    }
+
+    /// Snapshot of the most recent requests, newest first.
+    pub fn recent(&self) -> Vec<RequestRecord> {
+        let recent = self.recent.lock().unwrap_or_else(|e| e.into_inner());
+        recent.iter().rev().cloned().collect()
+    }
+}
+
+impl Default for RequestLog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Request counts by key, busiest first (ties by key).
+pub fn tally<'a>(keys: impl Iterator<Item = &'a str>) -> Vec<(String, usize)> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for key in keys {
+        *counts.entry(key).or_default() += 1;
+    }
+    let mut counts: Vec<(String, usize)> =
+        counts.into_iter().map(|(k, n)| (k.to_string(), n)).collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    counts
+}
+
+/// Middleware: time the request, record it, and log it per `web.access_log`.
+pub async fn access_log(State(state): State<Arc<AppState>>, request: Request, next: Next) -> Response {
+    let started = Instant::now();
+    let method = request.method().to_string();
+    let path = request.uri().path().to_string();
+    let client = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip().to_string());
+    let user_agent = request
+        .headers()
+        .get(header::USER_AGENT)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+
+    let response = next.run(request).await;
+
+    let status = response.status();
+    let latency_ms = started.elapsed().as_secs_f64() * 1000.0;
+    let client_label = client.as_deref().unwrap_or("-");
+    let failed = status.is_client_error() || status.is_server_error();
+    match (state.config.load().web.access_log, failed) {
+        (AccessLogLevel::All, false) => {
+            info!("[HTTP] {} {} {} {} {:.1}ms", client_label, method, path, status.as_u16(), latency_ms);
+        }
+        (AccessLogLevel::All | AccessLogLevel::Errors, true) => {
+            warn!("[HTTP] {} {} {} {} {:.1}ms", client_label, method, path, status.as_u16(), latency_ms);
+        }
+        _ => {}
+    }
+
+    state.http_log.push(RequestRecord {
+        timestamp: chrono::Local::now().to_rfc3339(),
+        method,
+        path,
+        status: status.as_u16(),
+        latency_ms,
+        client,
+        user_agent,
+    });
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(path: &str) -> RequestRecord {
+        RequestRecord {
+            timestamp: String::new(),
+            method: "GET".into(),
+            path: path.into(),
+            status: 200,
+            latency_ms: 1.0,
+            client: None,
+            user_agent: None,
+        }
+    }
+
+    #[test]
+    fn test_recent_is_bounded_newest_first() {
+        let log = RequestLog::new();
+        for i in 0..RECENT_CAPACITY + 5 {
+            log.push(record(&format!("/{}", i)));
+        }
+        let recent = log.recent();
+        assert_eq!(recent.len(), RECENT_CAPACITY);
+        assert_eq!(recent[0].path, format!("/{}", RECENT_CAPACITY + 4));
+        assert_eq!(recent.last().unwrap().path, "/5");
+    }
+
+    #[test]
+    fn test_tally_busiest_first() {
+        let paths = ["/api/status", "/api/config", "/api/status", "/", "/api/status", "/"];
+        assert_eq!(
+            tally(paths.into_iter()),
+            vec![("/api/status".into(), 3), ("/".into(), 2), ("/api/config".into(), 1)]
+        );
+    }
+}
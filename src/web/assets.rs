@@ -0,0 +1,285 @@
+//! Uploaded assets (icons, animation frames, fonts).
+//!
+//! Files live flat in `web.assets_dir`, so a config can point at them by
+//! path, e.g. `"icons": {"custom": {"HOME": "uploads/home.png"}}`. Uploads
+//! and deletes need `Authorization: Bearer <web.upload_token>`.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use axum::body::Bytes;
+use axum::extract::{Path as UrlPath, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde_json::json;
+use tracing::{info, warn};
+
+use crate::config::WebConfig;
+use crate::AppState;
+
+/// Longest accepted asset file name.
+const MAX_NAME_LEN: usize = 64;
+
+/// Accepted asset types, by file extension.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum AssetKind {
+    Png,
+    Gif,
+    /// Font or icon metadata JSON (see `icons.font_path`).
+    Json,
+    /// BDF bitmap font.
+    Bdf,
+}
+
+impl AssetKind {
+    fn from_name(name: &str) -> Option<Self> {
+        match name.rsplit_once('.')?.1.to_ascii_lowercase().as_str() {
+            "png" => Some(AssetKind::Png),
+            "gif" => Some(AssetKind::Gif),
+            "json" => Some(AssetKind::Json),
+            "bdf" => Some(AssetKind::Bdf),
+            _ => None,
+        }
+    }
+
+    fn mime(self) -> &'static str {
+        match self {
+            AssetKind::Png => "image/png",
+            AssetKind::Gif => "image/gif",
+            AssetKind::Json => "application/json",
+            AssetKind::Bdf => "application/x-font-bdf",
+        }
+    }
+
+    /// Whether `bytes` look like this kind of file.
+    fn matches(self, bytes: &[u8]) -> bool {
+        match self {
+            AssetKind::Png => bytes.starts_with(b"\x89PNG\r\n\x1a\n"),
+            AssetKind::Gif => bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a"),
+            AssetKind::Json => serde_json::from_slice::<serde_json::Value>(bytes).is_ok(),
+            AssetKind::Bdf => bytes.starts_with(b"STARTFONT"),
+        }
+    }
+}
+
+/// Plain file names only: letters, digits, `.`, `-`, `_`, not starting
+/// with a dot (no paths, no hidden files).
+fn is_safe_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= MAX_NAME_LEN
+        && !name.starts_with('.')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'))
+}
+
+/// Check an upload's name, size, and content before it touches the disk.
+fn validate_upload(name: &str, bytes: &[u8], max_bytes: usize) -> Result<AssetKind, (StatusCode, String)> {
+    if !is_safe_name(name) {
+        return Err((StatusCode::BAD_REQUEST, format!("Invalid asset name '{}'", name)));
+    }
+    let kind = AssetKind::from_name(name).ok_or_else(|| {
+        (StatusCode::UNSUPPORTED_MEDIA_TYPE, "Assets must be .png, .gif, .json or .bdf".to_string())
+    })?;
+    if bytes.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "Empty upload".to_string()));
+    }
+    if bytes.len() > max_bytes {
+        return Err((
+            StatusCode::PAYLOAD_TOO_LARGE,
+            format!("Upload is {} bytes, limit is {} (web.max_upload_kb)", bytes.len(), max_bytes),
+        ));
+    }
+    if !kind.matches(bytes) {
+        return Err((
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            format!("Content is not a valid {:?} file", kind),
+        ));
+    }
+    Ok(kind)
+}
+
+/// Require the configured bearer token.
+fn authorize(headers: &HeaderMap, web: &WebConfig) -> Result<(), (StatusCode, String)> {
+    let Some(expected) = web.upload_token.as_deref().filter(|t| !t.is_empty()) else {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "Uploads are disabled (set web.upload_token)".to_string(),
+        ));
+    };
+    let given = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    if given != Some(expected) {
+        return Err((StatusCode::UNAUTHORIZED, "Missing or wrong upload token".to_string()));
+    }
+    Ok(())
+}
+
+fn error_response((status, message): (StatusCode, String)) -> Response {
+    (status, Json(json!({ "success": false, "message": message }))).into_response()
+}
+
+/// Write via a temp file and rename, so a half-written asset is never served.
+fn store(dir: &Path, name: &str, bytes: &[u8]) -> std::io::Result<PathBuf> {
+    std::fs::create_dir_all(dir)?;
+    let path = dir.join(name);
+    let tmp = dir.join(format!(".{}.tmp", name));
+    std::fs::write(&tmp, bytes)?;
+    std::fs::rename(&tmp, &path)?;
+    Ok(path)
+}
+
+/// GET /api/assets — list uploaded assets.
+pub async fn list(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let dir = state.config.load().web.assets_dir.clone();
+    let assets = tokio::task::spawn_blocking(move || {
+        let mut assets: Vec<serde_json::Value> = std::fs::read_dir(&dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter_map(|entry| {
+                let name = entry.file_name().into_string().ok()?;
+                let meta = entry.metadata().ok()?;
+                (is_safe_name(&name) && meta.is_file()).then(|| {
+                    json!({
+                        "name": name,
+                        "size": meta.len(),
+                        "path": dir.join(&name),
+                    })
+                })
+            })
+            .collect();
+        assets.sort_by(|a, b| a["name"].as_str().cmp(&b["name"].as_str()));
+        assets
+    })
+    .await
+    .unwrap_or_default();
+
+    Json(json!({ "success": true, "assets": assets }))
+}
+
+/// GET /api/assets/{name} — serve an uploaded asset.
+pub async fn get(State(state): State<Arc<AppState>>, UrlPath(name): UrlPath<String>) -> Response {
+    let Some(kind) = AssetKind::from_name(&name).filter(|_| is_safe_name(&name)) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let path = state.config.load().web.assets_dir.join(&name);
+    match tokio::fs::read(&path).await {
+        Ok(bytes) => (
+            [(header::CONTENT_TYPE, kind.mime()), (header::CACHE_CONTROL, "no-cache")],
+            bytes,
+        )
+            .into_response(),
+        Err(_) => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+/// POST /api/assets/{name} — store the request body as an asset, replacing
+/// any file of that name. Returns the path to use in the config.
+pub async fn upload(
+    State(state): State<Arc<AppState>>,
+    UrlPath(name): UrlPath<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let config = state.config.load();
+    if let Err(e) = authorize(&headers, &config.web) {
+        warn!("[WEB] Asset upload rejected: {}", e.1);
+        return error_response(e);
+    }
+    let max_bytes = config.web.max_upload_kb as usize * 1024;
+    if let Err(e) = validate_upload(&name, &body, max_bytes) {
+        return error_response(e);
+    }
+
+    let dir = config.web.assets_dir.clone();
+    let size = body.len();
+    let result = tokio::task::spawn_blocking({
+        let name = name.clone();
+        move || store(&dir, &name, &body)
+    })
+    .await;
+    match result {
+        Ok(Ok(path)) => {
+            info!("[WEB] Stored asset {} ({} bytes)", path.display(), size);
+            (
+                StatusCode::CREATED,
+                Json(json!({ "success": true, "name": name, "size": size, "path": path })),
+            )
+                .into_response()
+        }
+        Ok(Err(e)) => error_response((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to store asset: {}", e),
+        )),
+        Err(e) => error_response((StatusCode::INTERNAL_SERVER_ERROR, format!("Asset write failed: {}", e))),
+    }
+}
+
+/// DELETE /api/assets/{name} — remove an uploaded asset.
+pub async fn delete(
+    State(state): State<Arc<AppState>>,
+    UrlPath(name): UrlPath<String>,
+    headers: HeaderMap,
+) -> Response {
+    let config = state.config.load();
+    if let Err(e) = authorize(&headers, &config.web) {
+        return error_response(e);
+    }
+    if !is_safe_name(&name) {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+    match tokio::fs::remove_file(config.web.assets_dir.join(&name)).await {
+        Ok(()) => {
+            info!("[WEB] Deleted asset {}", name);
+            Json(json!({ "success": true })).into_response()
+        }
+        Err(_) => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PNG: &[u8] = b"\x89PNG\r\n\x1a\n rest of file";
+
+    #[test]
+    fn test_validate_upload() {
+        assert_eq!(validate_upload("home.png", PNG, 1024), Ok(AssetKind::Png));
+        assert_eq!(validate_upload("font.json", b"{\"glyphs\": {}}", 1024), Ok(AssetKind::Json));
+
+        let status = |name: &str, bytes: &[u8], max: usize| validate_upload(name, bytes, max).unwrap_err().0;
+        assert_eq!(status("../config.json", b"{}", 1024), StatusCode::BAD_REQUEST);
+        assert_eq!(status(".hidden.png", PNG, 1024), StatusCode::BAD_REQUEST);
+        assert_eq!(status("run.sh", b"#!/bin/sh", 1024), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        assert_eq!(status("fake.png", b"GIF89a...", 1024), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        assert_eq!(status("home.png", PNG, 8), StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(status("home.png", b"", 1024), StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn test_authorize() {
+        let mut headers = HeaderMap::new();
+        let mut web = WebConfig::default();
+        assert_eq!(authorize(&headers, &web).unwrap_err().0, StatusCode::FORBIDDEN);
+
+        web.upload_token = Some("s3cret".into());
+        assert_eq!(authorize(&headers, &web).unwrap_err().0, StatusCode::UNAUTHORIZED);
+        headers.insert(header::AUTHORIZATION, "Bearer wrong".parse().unwrap());
+        assert_eq!(authorize(&headers, &web).unwrap_err().0, StatusCode::UNAUTHORIZED);
+        headers.insert(header::AUTHORIZATION, "Bearer s3cret".parse().unwrap());
+        assert!(authorize(&headers, &web).is_ok());
+    }
+
+    #[test]
+    fn test_store_replaces_atomically() {
+        let dir = tempfile::tempdir().unwrap();
+        let assets = dir.path().join("uploads");
+        let path = store(&assets, "home.png", b"one").unwrap();
+        store(&assets, "home.png", b"two").unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"two");
+        assert_eq!(std::fs::read_dir(&assets).unwrap().count(), 1, "no temp files left behind");
+    }
+}
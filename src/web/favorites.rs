@@ -0,0 +1,258 @@
+//! Saved station setups ("Home", "Gym", ...), so switching the sign between
+//! them is one request instead of a station search.
+//!
+//! Each favorite is a named copy of the config's `station` block, kept in
+//! `favorites.json` next to `config.json`. Applying one writes that block
+//! into the config (with history, see `config::save_config`) and applies it.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use axum::extract::{Path as UrlPath, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::{info, warn};
+
+use crate::config::{self, Config};
+use crate::AppState;
+
+/// File holding the favorites, next to the config file.
+pub const FAVORITES_FILE: &str = "favorites.json";
+
+/// Longest accepted favorite name.
+const MAX_NAME_LEN: usize = 40;
+
+/// Most favorites kept.
+const MAX_FAVORITES: usize = 32;
+
+/// A named `station` block.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Favorite {
+    pub name: String,
+    pub station: serde_json::Value,
+}
+
+/// Where the favorites for the config at `config_path` are kept.
+pub fn favorites_path(config_path: &Path) -> PathBuf {
+    match config_path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.join(FAVORITES_FILE),
+        _ => PathBuf::from(FAVORITES_FILE),
+    }
+}
+
+/// Favorites in `path`; none if the file doesn't exist yet.
+fn load(path: &Path) -> Result<Vec<Favorite>, String> {
+    match std::fs::read_to_string(path) {
+        Ok(text) => serde_json::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(format!("{}: {}", path.display(), e)),
+    }
+}
+
+/// Write via a temp file and rename, so a crash never leaves half a list.
+fn store(path: &Path, favorites: &[Favorite]) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(favorites).map_err(|e| e.to_string())?;
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, json)
+        .and_then(|_| std::fs::rename(&tmp, path))
+        .map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// The config file at `config_path` with its `station` block replaced,
+/// checked to load.
+fn with_station(config_path: &Path, station: &serde_json::Value) -> Result<(String, Config), String> {
+    let text = std::fs::read_to_string(config_path).map_err(|e| format!("Can't read config: {}", e))?;
+    let mut value: serde_json::Value =
+        serde_json::from_str(&text).map_err(|e| format!("Can't parse config: {}", e))?;
+    value["station"] = station.clone();
+    let json = serde_json::to_string_pretty(&value).unwrap_or_default();
+    let config = Config::from_json(&json).map_err(|e| format!("Invalid station: {}", e))?;
+    Ok((json, config))
+}
+
+/// Add `favorite`, replacing one with the same name (keeping its place).
+fn upsert(favorites: &mut Vec<Favorite>, favorite: Favorite) -> Result<(), String> {
+    let name = favorite.name.trim();
+    if name.is_empty() || name.chars().count() > MAX_NAME_LEN {
+        return Err(format!("Favorite names must be 1-{} characters", MAX_NAME_LEN));
+    }
+    let favorite = Favorite { name: name.to_string(), ..favorite };
+    match favorites.iter().position(|f| f.name == favorite.name) {
+        Some(i) => favorites[i] = favorite,
+        None if favorites.len() >= MAX_FAVORITES => {
+            return Err(format!("At most {} favorites; delete one first", MAX_FAVORITES))
+        }
+        None => favorites.push(favorite),
+    }
+    Ok(())
+}
+
+fn error_response(status: StatusCode, message: String) -> Response {
+    (status, Json(json!({ "success": false, "message": message }))).into_response()
+}
+
+/// Run blocking file work, mapping a failed task to an error message.
+async fn blocking<T: Send + 'static>(
+    f: impl FnOnce() -> Result<T, (StatusCode, String)> + Send + 'static,
+) -> Result<T, (StatusCode, String)> {
+    tokio::task::spawn_blocking(f)
+        .await
+        .unwrap_or_else(|e| Err((StatusCode::INTERNAL_SERVER_ERROR, format!("Task failed: {}", e))))
+}
+
+fn internal(message: String) -> (StatusCode, String) {
+    (StatusCode::INTERNAL_SERVER_ERROR, message)
+}
+
+/// GET /api/favorites — saved station setups.
+pub async fn list(State(state): State<Arc<AppState>>) -> Response {
+    let path = favorites_path(&state.config_path);
+    match blocking(move || load(&path).map_err(internal)).await {
+        Ok(favorites) => Json(json!({ "success": true, "favorites": favorites })).into_response(),
+        Err((status, message)) => error_response(status, message),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct SaveFavorite {
+    name: String,
+    /// A `station` block; omitted = the station currently configured.
+    station: Option<serde_json::Value>,
+}
+
+/// POST /api/favorites — save a favorite, replacing one of the same name.
+pub async fn save(State(state): State<Arc<AppState>>, Json(body): Json<SaveFavorite>) -> Response {
+    let config_path = state.config_path.clone();
+    let lock_state = state.clone();
+    let result = blocking(move || {
+        let station = match body.station {
+            Some(station) => station,
+            None => {
+                let text = std::fs::read_to_string(&config_path)
+                    .map_err(|e| internal(format!("Can't read config: {}", e)))?;
+                let value: serde_json::Value = serde_json::from_str(&text)
+                    .map_err(|e| internal(format!("Can't parse config: {}", e)))?;
+                value["station"].clone()
+            }
+        };
+        with_station(&config_path, &station).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+        let path = favorites_path(&config_path);
+        let _guard = lock_state.favorites_lock.lock().unwrap_or_else(|e| e.into_inner());
+        let mut favorites = load(&path).map_err(internal)?;
+        let favorite = Favorite { name: body.name, station };
+        upsert(&mut favorites, favorite).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+        store(&path, &favorites).map_err(internal)?;
+        Ok(favorites)
+    })
+    .await;
+
+    match result {
+        Ok(favorites) => {
+            info!("[WEB] Saved favorites ({} total)", favorites.len());
+            Json(json!({ "success": true, "favorites": favorites })).into_response()
+        }
+        Err((status, message)) => error_response(status, message),
+    }
+}
+
+/// DELETE /api/favorites/{name} — forget a favorite.
+pub async fn delete(State(state): State<Arc<AppState>>, UrlPath(name): UrlPath<String>) -> Response {
+    let path = favorites_path(&state.config_path);
+    let lock_state = state.clone();
+    let result = blocking(move || {
+        let _guard = lock_state.favorites_lock.lock().unwrap_or_else(|e| e.into_inner());
+        let mut favorites = load(&path).map_err(internal)?;
+        let before = favorites.len();
+        favorites.retain(|f| f.name != name);
+        if favorites.len() == before {
+            return Err((StatusCode::NOT_FOUND, format!("No favorite '{}'", name)));
+        }
+        store(&path, &favorites).map_err(internal)?;
+        Ok(favorites)
+    })
+    .await;
+
+    match result {
+        Ok(favorites) => Json(json!({ "success": true, "favorites": favorites })).into_response(),
+        Err((status, message)) => error_response(status, message),
+    }
+}
+
+/// POST /api/favorites/{name}/apply — switch the sign to a favorite.
+pub async fn apply(State(state): State<Arc<AppState>>, UrlPath(name): UrlPath<String>) -> Response {
+    let config_path = state.config_path.clone();
+    let result = blocking({
+        let name = name.clone();
+        move || {
+            let favorites = load(&favorites_path(&config_path)).map_err(internal)?;
+            let favorite = favorites
+                .into_iter()
+                .find(|f| f.name == name)
+                .ok_or_else(|| (StatusCode::NOT_FOUND, format!("No favorite '{}'", name)))?;
+            // The station database may have changed since it was saved
+            let (json, config) =
+                with_station(&config_path, &favorite.station).map_err(|e| (StatusCode::CONFLICT, e))?;
+            config::save_config(&config_path, &json).map_err(|e| internal(format!("Failed to save config: {}", e)))?;
+            Ok(config)
+        }
+    })
+    .await;
+
+    match result {
+        Ok(config) => {
+            info!("[WEB] Applied favorite '{}'", name);
+            crate::apply_config(&state, config);
+            Json(json!({ "success": true, "message": format!("Switched to '{}'.", name) })).into_response()
+        }
+        Err((status, message)) => {
+            warn!("[WEB] Applying favorite '{}' failed: {}", name, message);
+            error_response(status, message)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn favorite(name: &str, station: &str) -> Favorite {
+        Favorite { name: name.into(), station: json!({ "station_name": station }) }
+    }
+
+    #[test]
+    fn test_upsert_and_store() {
+        let mut favorites = Vec::new();
+        upsert(&mut favorites, favorite(" Home ", "Times Sq-42 St")).unwrap();
+        upsert(&mut favorites, favorite("Gym", "14 St-Union Sq")).unwrap();
+        upsert(&mut favorites, favorite("Home", "96 St")).unwrap();
+        assert_eq!(favorites, vec![favorite("Home", "96 St"), favorite("Gym", "14 St-Union Sq")]);
+        assert!(upsert(&mut favorites, favorite("  ", "96 St")).is_err());
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = favorites_path(&dir.path().join("config.json"));
+        assert_eq!(load(&path).unwrap(), Vec::new(), "no file yet");
+        store(&path, &favorites).unwrap();
+        assert_eq!(load(&path).unwrap(), favorites);
+    }
+
+    #[test]
+    fn test_with_station_validates() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.json");
+        std::fs::write(
+            &path,
+            r#"{"station":{"station_name":"Times Sq-42 St","routes":["1"]},"display":{"brightness":0.5,"max_trains":6,"show_alerts":true},"web":{"port":5002}}"#,
+        )
+        .unwrap();
+
+        let (json, config) =
+            with_station(&path, &json!({ "station_name": "14 St-Union Sq", "routes": ["4"] })).unwrap();
+        assert_eq!(config.routes, vec!["4"]);
+        assert!(json.contains("5002"), "other settings kept");
+        assert!(with_station(&path, &json!({ "station_name": "Nowhere" })).is_err());
+    }
+}
@@ -1,16 +1,33 @@
-use std::sync::atomic::Ordering;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
 use axum::extract::{Path, Query, State};
-use axum::http::StatusCode;
-use axum::response::IntoResponse;
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
 use axum::Json;
 use serde::Deserialize;
 use serde_json::json;
 use tracing::{info, warn};
 
-use crate::config::Config;
-use crate::mta::stations;
+use crate::analytics::accuracy::AccuracyStats;
+use crate::analytics::store::{arrivals_csv, DayRecord, DATE_FORMAT};
+use crate::config::{Config, DisplayBackend};
+use crate::crash;
+use crate::journey;
+use crate::display::fonts::{FontSize, FontStyle};
+use crate::display::framebuffer::FrameBuffer;
+use crate::display::renderer::{Renderer, CHAR_SPACING};
+use crate::display::i18n::Strings;
+use crate::display::{colors, export, fonts, glyphs};
+use crate::lifecycle::{self, RestartMode};
+use crate::models::{Alert, Direction, DisplaySnapshot, Train};
+use crate::mta::client::MtaClient;
+use crate::mta::{feeds, stations};
+use crate::netinfo;
+use crate::selection::Suspension;
+use crate::trip;
+use crate::web::access_log;
 use crate::{unix_now_secs, AppState};
 
 #[derive(Deserialize)]
@@ -22,6 +39,102 @@ pub struct StationSearchParams {
     multi_platform_only: Option<String>,
 }
 
+#[derive(Deserialize)]
+pub struct StationSuggestParams {
+    q: Option<String>,
+    limit: Option<usize>,
+    route: Option<String>,
+    borough: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct IconParams {
+    express: Option<String>,
+    scale: Option<usize>,
+}
+
+#[derive(Deserialize)]
+pub struct TextPreviewParams {
+    text: Option<String>,
+    style: Option<FontStyle>,
+    italic: Option<String>,
+    size: Option<FontSize>,
+    color: Option<String>,
+    scale: Option<usize>,
+}
+
+/// Query for GET /api/fonts/metrics.
+#[derive(Deserialize)]
+pub struct FontMetricsParams {
+    text: Option<String>,
+    style: Option<FontStyle>,
+    size: Option<FontSize>,
+    spacing: Option<i32>,
+}
+
+/// Body of POST /api/restart.
+#[derive(Deserialize, Default)]
+pub struct RestartRequest {
+    token: Option<String>,
+    mode: Option<RestartMode>,
+}
+
+/// Body of POST /api/preview/frame. Every field is optional.
+#[derive(Deserialize)]
+pub struct FramePreviewRequest {
+    /// Candidate config (same shape as POST /api/config); arrivals are
+    /// fetched for it with a throwaway client when `trains` is absent.
+    config: Option<serde_json::Value>,
+    /// Fake arrivals to render instead of live data.
+    trains: Option<Vec<PreviewTrain>>,
+    /// Alert text to show in the bottom row (supports `[route]` icons).
+    alert: Option<String>,
+    /// Alert scroll offset in pixels (default: alert starts at the left edge).
+    alert_scroll: Option<f32>,
+    cycle_index: Option<usize>,
+    flash: Option<bool>,
+    /// With `arrival_format: "alternate"`, render the clock-time half.
+    alternate_clock: Option<bool>,
+    scale: Option<usize>,
+}
+
+#[derive(Deserialize)]
+pub struct PreviewTrain {
+    route: String,
+    destination: String,
+    minutes: i32,
+    #[serde(default)]
+    is_express: bool,
+    #[serde(default)]
+    direction: Option<String>,
+}
+
+impl PreviewTrain {
+    fn into_train(self) -> Train {
+        let direction = match self.direction.as_deref().map(str::to_lowercase).as_deref() {
+            Some("downtown") | Some("s") => Direction::Downtown,
+            _ => Direction::Uptown,
+        };
+        Train {
+            route: self.route,
+            destination: self.destination,
+            minutes: self.minutes,
+            is_express: self.is_express,
+            arrival_timestamp: (crate::unix_now_secs() as i64 + self.minutes as i64 * 60) as f64,
+            direction,
+            stop_id: String::new(),
+            delay_secs: None,
+            trip_id: String::new(),
+        }
+    }
+}
+
+/// Longest text accepted by the text preview endpoint (keeps PNGs small).
+const MAX_PREVIEW_TEXT_CHARS: usize = 200;
+
+/// Default upscale factor for PNG endpoints (one LED → 4x4 screen pixels).
+const DEFAULT_PNG_SCALE: usize = 4;
+
 /// GET /api/config — return current config as JSON.
 pub async fn get_config(State(state): State<Arc<AppState>>) -> impl IntoResponse {
     let config = state.config.load();
@@ -38,8 +151,13 @@ pub async fn get_config(State(state): State<Arc<AppState>>) -> impl IntoResponse
 /// POST /api/config — validate and save new config.
 pub async fn update_config(
     State(state): State<Arc<AppState>>,
-    Json(body): Json<serde_json::Value>,
+    Json(mut body): Json<serde_json::Value>,
 ) -> impl IntoResponse {
+    // Secrets come back redacted (see `config_to_json`); keep the current ones,
+    // and the fields only the file may set
+    let current = config_json(&state.config.load());
+    crate::config::restore_secrets(&mut body, &current);
+    crate::config::keep_file_only(&mut body, &current);
     let validated_json = serde_json::to_string_pretty(&body).unwrap_or_default();
 
     let new_config = match Config::from_json(&validated_json) {
@@ -55,15 +173,14 @@ pub async fn update_config(
     // Atomic write via spawn_blocking (sync fs ops: rename, sync_all)
     let write_result = tokio::task::spawn_blocking({
         let path = state.config_path.clone();
-        move || crate::config::atomic_write_config(&path, &validated_json)
+        move || crate::config::save_config(&path, &validated_json)
     })
     .await;
 
     match write_result {
         Ok(Ok(_)) => {
             info!("[WEB] Config saved (atomic)");
-            state.config.store(Arc::new(new_config));
-            state.config_changed.notify_one();
+            crate::apply_config(&state, new_config);
             (
                 StatusCode::OK,
                 Json(json!({
@@ -89,6 +206,61 @@ pub async fn update_config(
     }
 }
 
+#[derive(Deserialize)]
+pub struct ConfigDiffParams {
+    against: String,
+}
+
+/// GET /api/config/diff?against=backup|history_N — field-level changes
+/// restoring that version would make to the active config (`history_1` is
+/// the version saved most recently before the current one). Both sides are
+/// shown as `GET /api/config` shows them, secrets redacted.
+pub async fn get_config_diff(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<ConfigDiffParams>,
+) -> Response {
+    let path = state.config_path.clone();
+    let other = match params.against.as_str() {
+        "backup" => Some(path.with_extension("json.bak")),
+        against => match against.strip_prefix("history_").and_then(|n| n.parse::<usize>().ok()) {
+            Some(n) if n >= 1 => crate::config::config_history(&path).into_iter().nth(n - 1),
+            _ => {
+                return bad_request(format!(
+                    "against must be 'backup' or 'history_N' (N >= 1), got '{}'",
+                    against
+                ))
+            }
+        },
+    };
+    let Some(other) = other.filter(|p| p.exists()) else {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(json!({ "success": false, "error": format!("no config version '{}'", params.against) })),
+        )
+            .into_response();
+    };
+
+    match Config::load_version(&path, &other) {
+        Ok(prior) => Json(json!({
+            "success": true,
+            "against": params.against,
+            "changes": crate::config::diff_json(
+                &config_to_json(&state.config.load()),
+                &config_to_json(&prior),
+            ),
+        }))
+        .into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "success": false,
+                "error": format!("Can't load {}: {}", other.display(), e),
+            })),
+        )
+            .into_response(),
+    }
+}
+
 /// GET /api/status — service status, current station, routes.
 pub async fn get_status(State(state): State<Arc<AppState>>) -> impl IntoResponse {
     let config = state.config.load();
@@ -101,16 +273,61 @@ pub async fn get_status(State(state): State<Arc<AppState>>) -> impl IntoResponse
         "status": {
             "service": "Running",
             "status_class": "running",
+            "instance": state.instance,
             "station": station,
             "routes": config.routes,
+            "active_routes": **state.active_routes.load(),
+            "route_service": config.display.route_service,
+            "unscheduled_routes": state.service_schedule.load().as_ref().as_ref().map(|s| {
+                s.unscheduled(&config.routes, config.tz().now())
+            }),
             "brightness": config.display.brightness,
             "max_trains": config.display.max_trains,
+            "do_not_disturb": state.do_not_disturb.load(Ordering::Relaxed),
             "last_update": last_update,
             "uptime": format!("trains: {}, alerts: {}", snapshot.trains.len(), snapshot.alerts.len()),
+            "last_crash": crash::latest_report(&crash::crash_dir(&state.config_path)),
+            "leave_now": journey::leave_now(&snapshot.trains, &config.journey, unix_now_secs() as f64),
+            "suspension": Suspension::new(
+                (**state.suspended_routes.load()).clone(),
+                &snapshot.trains,
+                &config.alternates,
+            ),
+            "trip": config.trip.as_ref().map(|t| json!({
+                "transfer_station": t.transfer_station,
+                "connections": trip::connections(&snapshot.trains, &state.transfer_trains.load(), t),
+            })),
         }
     }))
 }
 
+/// GET /api/trains — upcoming trains as shown, with `delay_secs` (seconds
+/// behind schedule, null when unknown).
+pub async fn get_trains(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let snapshot = state.snapshot.load();
+    Json(json!({
+        "trains": snapshot.trains,
+        "fetched_at": snapshot.fetched_at,
+    }))
+}
+
+/// GET /api/trains/{trip_id} — remaining stops of a displayed train's trip.
+pub async fn get_trip(
+    State(state): State<Arc<AppState>>,
+    Path(trip_id): Path<String>,
+) -> impl IntoResponse {
+    match state.trips.load().get(&trip_id) {
+        Some(trip) => (StatusCode::OK, Json(json!({"success": true, "trip": trip}))),
+        None => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "success": false,
+                "error": format!("Trip '{}' is not among the displayed trains", trip_id)
+            })),
+        ),
+    }
+}
+
 /// GET /api/debug/snapshot — dump current train + alert data for verification.
 pub async fn get_debug_snapshot(State(state): State<Arc<AppState>>) -> impl IntoResponse {
     let snapshot = state.snapshot.load();
@@ -148,6 +365,272 @@ pub async fn get_debug_snapshot(State(state): State<Arc<AppState>>) -> impl Into
     }))
 }
 
+/// Query for GET /api/logs/http.
+#[derive(Deserialize)]
+pub struct HttpLogParams {
+    /// Most recent requests to list (default 100); the tallies always
+    /// cover the whole buffer.
+    limit: Option<usize>,
+}
+
+/// GET /api/logs/http — recent requests, newest first, with request counts
+/// per path and per client.
+pub async fn get_http_log(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<HttpLogParams>,
+) -> impl IntoResponse {
+    let recent = state.http_log.recent();
+    let by_path = access_log::tally(recent.iter().map(|r| r.path.as_str()));
+    let by_client = access_log::tally(recent.iter().map(|r| r.client.as_deref().unwrap_or("-")));
+    let since = recent.last().map(|r| r.timestamp.clone());
+    let limit = params.limit.unwrap_or(100);
+    Json(json!({
+        "success": true,
+        "since": since,
+        "total": recent.len(),
+        "by_path": by_path.into_iter().map(|(path, count)| json!({"path": path, "count": count})).collect::<Vec<_>>(),
+        "by_client": by_client.into_iter().map(|(client, count)| json!({"client": client, "count": count})).collect::<Vec<_>>(),
+        "requests": &recent[..recent.len().min(limit)],
+    }))
+}
+
+/// Query for GET /api/analytics.
+#[derive(Deserialize)]
+pub struct AnalyticsParams {
+    /// Days of history including today (default 7).
+    days: Option<u32>,
+}
+
+/// GET /api/analytics — countdown accuracy (predicted vs actual arrival)
+/// per day, plus totals over the requested range.
+pub async fn get_analytics(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<AnalyticsParams>,
+) -> impl IntoResponse {
+    let config = state.config.load();
+    let days = params
+        .days
+        .unwrap_or(7)
+        .clamp(1, config.analytics.retention_days.max(1));
+    let (store, today, today_date, tracking) = {
+        let analytics = state.analytics.lock().unwrap_or_else(|e| e.into_inner());
+        (
+            analytics.store().clone(),
+            analytics.today().clone(),
+            analytics.today_date(),
+            analytics.tracking(),
+        )
+    };
+
+    let from = today_date - chrono::Days::new(days as u64 - 1);
+    let yesterday = today_date - chrono::Days::new(1);
+    let mut records = if days > 1 {
+        tokio::task::spawn_blocking(move || store.load_range(from, yesterday))
+            .await
+            .unwrap_or_default()
+    } else {
+        Vec::new()
+    };
+    records.push(today);
+
+    let mut total = AccuracyStats::default();
+    for record in &records {
+        total.merge(&record.accuracy);
+    }
+    let days_json: Vec<serde_json::Value> = records
+        .iter()
+        .map(|r| json!({"date": r.date, "accuracy": r.accuracy.report()}))
+        .collect();
+
+    Json(json!({
+        "enabled": config.analytics.enabled,
+        "tracking": tracking,
+        "from": from.format(DATE_FORMAT).to_string(),
+        "to": today_date.format(DATE_FORMAT).to_string(),
+        "total": total.report(),
+        "days": days_json,
+    }))
+}
+
+/// Query for GET /api/analytics/headways.
+#[derive(Deserialize)]
+pub struct HeadwayParams {
+    /// `YYYY-MM-DD` (default today).
+    date: Option<String>,
+}
+
+/// GET /api/analytics/headways — per-route train counts and average
+/// headways by hour for one day.
+pub async fn get_headways(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<HeadwayParams>,
+) -> Response {
+    let (store, today, today_date) = {
+        let analytics = state.analytics.lock().unwrap_or_else(|e| e.into_inner());
+        (
+            analytics.store().clone(),
+            analytics.today().clone(),
+            analytics.today_date(),
+        )
+    };
+
+    let date = match params.date.as_deref() {
+        None => today_date,
+        Some(s) => match chrono::NaiveDate::parse_from_str(s, DATE_FORMAT) {
+            Ok(d) => d,
+            Err(_) => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(json!({"error": format!("invalid date '{}', expected YYYY-MM-DD", s)})),
+                )
+                    .into_response()
+            }
+        },
+    };
+    let record = if date == today_date {
+        Some(today)
+    } else {
+        tokio::task::spawn_blocking(move || store.load(date))
+            .await
+            .unwrap_or_default()
+    };
+    let Some(record) = record else {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(json!({"error": format!("no analytics recorded for {}", date)})),
+        )
+            .into_response();
+    };
+
+    Json(json!({
+        "date": record.date,
+        "today": date == today_date,
+        "service": record.service.report(),
+    }))
+    .into_response()
+}
+
+/// Query for GET /api/analytics/arrivals.csv.
+#[derive(Deserialize)]
+pub struct ArrivalsCsvParams {
+    /// First day, `YYYY-MM-DD` (default today).
+    from: Option<String>,
+    /// Last day, inclusive (default today).
+    to: Option<String>,
+}
+
+/// GET /api/analytics/arrivals.csv — observed arrivals (route, direction,
+/// timestamp) for a date range, for spreadsheets. The range may span at most
+/// `analytics.retention_days` (older days are gone anyway).
+pub async fn get_arrivals_csv(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<ArrivalsCsvParams>,
+) -> Response {
+    let (store, today, today_date) = {
+        let analytics = state.analytics.lock().unwrap_or_else(|e| e.into_inner());
+        (
+            analytics.store().clone(),
+            analytics.today().clone(),
+            analytics.today_date(),
+        )
+    };
+
+    let parse = |value: Option<&str>| match value {
+        None => Ok(today_date),
+        Some(s) => chrono::NaiveDate::parse_from_str(s, DATE_FORMAT)
+            .map_err(|_| format!("invalid date '{}', expected YYYY-MM-DD", s)),
+    };
+    let max_days = state.config.load().analytics.retention_days.max(1) as i64;
+    let range = parse(params.from.as_deref()).and_then(|from| {
+        let to = parse(params.to.as_deref())?;
+        if from > to {
+            return Err(format!("from ({}) is after to ({})", from, to));
+        }
+        let days = (to - from).num_days() + 1;
+        if days > max_days {
+            return Err(format!("range is {} days, at most {} allowed", days, max_days));
+        }
+        Ok((from, to))
+    });
+    let (from, to) = match range {
+        Ok(r) => r,
+        Err(e) => return (StatusCode::BAD_REQUEST, Json(json!({"error": e}))).into_response(),
+    };
+
+    // Today's file lags the live record by up to a flush interval
+    let tz = state.config.load().tz();
+    let csv = tokio::task::spawn_blocking(move || {
+        let mut records: Vec<DayRecord> = store
+            .load_range(from, to)
+            .into_iter()
+            .filter(|r| r.date != today.date)
+            .collect();
+        if (from..=to).contains(&today_date) {
+            records.push(today);
+        }
+        arrivals_csv(&records, tz)
+    })
+    .await
+    .unwrap_or_default();
+
+    let disposition = format!("attachment; filename=\"arrivals-{}-to-{}.csv\"", from, to);
+    (
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        csv,
+    )
+        .into_response()
+}
+
+/// GET /api/font/coverage — characters the font lacks, including those
+/// seen in live alert and destination text.
+pub async fn get_font_coverage() -> impl IntoResponse {
+    Json(glyphs::coverage_report(&fonts::get_font()))
+}
+
+/// GET /api/fonts/metrics — glyph widths, left padding and icon sizes, so
+/// layout can be done off-device with the same math as the sign.
+///
+/// With `text` (plus optional `style` or `size`, and `spacing`, default the
+/// sign's), also measures it: total width and each character's x offset.
+pub async fn get_font_metrics(Query(params): Query<FontMetricsParams>) -> Response {
+    let font = fonts::get_font();
+    let mut body = json!({
+        "success": true,
+        "generation": fonts::font_generation(),
+        "spacing": CHAR_SPACING,
+        "metrics": font.metrics(),
+    });
+    if let Some(text) = params.text.filter(|t| !t.is_empty()) {
+        if text.chars().count() > MAX_PREVIEW_TEXT_CHARS {
+            return bad_request(format!(
+                "text must be at most {} characters",
+                MAX_PREVIEW_TEXT_CHARS
+            ));
+        }
+        let style = params.style.unwrap_or_default();
+        let size = params.size.unwrap_or_default();
+        let spacing = params.spacing.unwrap_or(CHAR_SPACING);
+        let mut offsets = Vec::new();
+        let width = match size {
+            FontSize::Regular => font.layout_text(&text, spacing, style, |_, x| offsets.push(x)),
+            FontSize::Small => font.layout_text_sized(&text, spacing, size, |_, x| offsets.push(x)),
+        };
+        body["measure"] = json!({
+            "text": text,
+            "style": style,
+            "size": size,
+            "spacing": spacing,
+            "width": width,
+            "offsets": offsets,
+        });
+    }
+    Json(body).into_response()
+}
+
 /// GET /api/stations/complete — search/filter complete station database.
 pub async fn get_complete_stations(
     Query(params): Query<StationSearchParams>,
@@ -192,6 +675,77 @@ pub async fn get_complete_stations(
     }))
 }
 
+/// Suggestions returned when the client doesn't ask for a number.
+const DEFAULT_SUGGESTIONS: usize = 8;
+/// Most suggestions returned per query.
+const MAX_SUGGESTIONS: usize = 25;
+
+/// GET /api/stations/suggest?q=herald — ranked station matches for a
+/// search box, with the matched character spans of each name.
+pub async fn suggest_stations(Query(params): Query<StationSuggestParams>) -> impl IntoResponse {
+    let query = params.q.unwrap_or_default();
+    let limit = params.limit.unwrap_or(DEFAULT_SUGGESTIONS).clamp(1, MAX_SUGGESTIONS);
+    let route = params.route.filter(|r| !r.is_empty());
+    let borough = params.borough.filter(|b| !b.is_empty());
+
+    let suggestions: Vec<serde_json::Value> = stations::suggest(&query, limit, |s| {
+        route.as_ref().is_none_or(|r| s.routes.contains(r))
+            && borough.as_ref().is_none_or(|b| &s.borough == b)
+    })
+    .into_iter()
+    .map(|m| {
+        json!({
+            "name": m.station.name,
+            "routes": m.station.routes,
+            "stop_ids": m.station.stop_ids,
+            "borough": m.station.borough,
+            "matches": m.spans,
+        })
+    })
+    .collect();
+
+    Json(json!({
+        "success": true,
+        "query": query,
+        "suggestions": suggestions,
+    }))
+}
+
+/// GET /api/routes — every route in the station database with its bullet
+/// color, express capability, GTFS-RT feed, and the stations it serves.
+pub async fn get_routes() -> impl IntoResponse {
+    let all_stations = stations::get_station_database();
+    let mut served: std::collections::BTreeMap<&str, Vec<&str>> = Default::default();
+    for station in all_stations.iter() {
+        for route in &station.routes {
+            served.entry(route.as_str()).or_default().push(station.name.as_str());
+        }
+    }
+    let mut routes: Vec<_> = served.into_iter().collect();
+    // 1-7, then lettered routes, then SIR
+    routes.sort_by_key(|(route, _)| (route.len(), *route));
+
+    let routes: Vec<serde_json::Value> = routes
+        .into_iter()
+        .map(|(route, mut names)| {
+            names.sort_unstable();
+            json!({
+                "route": route,
+                "color": colors::route_color(route).map(colors::rgb_to_hex),
+                "express_capable": colors::is_express_capable(route),
+                "feed": feeds::feed_id_for_route(route).map(|suffix| format!("gtfs{}", suffix)),
+                "station_count": names.len(),
+                "stations": names,
+            })
+        })
+        .collect();
+
+    Json(json!({
+        "success": true,
+        "routes": routes,
+    }))
+}
+
 /// GET /api/stations/lookup/:station_name — look up stop IDs for a station.
 pub async fn lookup_station(Path(station_name): Path<String>) -> impl IntoResponse {
     let stop_ids = stations::get_stop_ids_for_station(&station_name);
@@ -222,14 +776,14 @@ pub async fn lookup_station(Path(station_name): Path<String>) -> impl IntoRespon
     )
 }
 
-/// POST /api/restart — trigger config reload (not process restart).
-pub async fn restart(State(state): State<Arc<AppState>>) -> impl IntoResponse {
-    info!("[WEB] Restart requested — reloading config");
+/// POST /api/reload — reload config from disk (no process restart).
+pub async fn reload(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    info!("[WEB] Reload requested");
 
+    crate::load_station_database(&state.config_path);
     match Config::load(&state.config_path) {
         Ok(new_config) => {
-            state.config.store(Arc::new(new_config));
-            state.config_changed.notify_one();
+            crate::apply_config(&state, new_config);
             Json(json!({
                 "success": true,
                 "message": "Configuration reloaded successfully"
@@ -242,6 +796,147 @@ pub async fn restart(State(state): State<Arc<AppState>>) -> impl IntoResponse {
     }
 }
 
+/// POST /api/restart — restart the whole process, with confirmation.
+///
+/// Without a `token` this only issues a confirmation token. Posting
+/// `{"token": ..., "mode": "exit"|"exec"}` within 30s performs the restart
+/// after a graceful shutdown (`exit` is the default, relying on systemd).
+pub async fn restart(
+    State(state): State<Arc<AppState>>,
+    body: Option<Json<RestartRequest>>,
+) -> impl IntoResponse {
+    let request = body.map(|Json(b)| b).unwrap_or_default();
+
+    let Some(token) = request.token else {
+        let token = state.restart.issue_token();
+        return (
+            StatusCode::OK,
+            Json(json!({
+                "success": true,
+                "confirm_token": token,
+                "expires_in": lifecycle::TOKEN_TTL.as_secs(),
+                "message": "POST this confirm_token back as \"token\" to restart",
+            })),
+        );
+    };
+
+    if !state.restart.confirm(&token) {
+        warn!("[WEB] Restart rejected: invalid or expired token");
+        return (
+            StatusCode::FORBIDDEN,
+            Json(json!({
+                "success": false,
+                "message": "Invalid or expired confirmation token",
+            })),
+        );
+    }
+
+    let mode = request.mode.unwrap_or(RestartMode::Exit);
+    info!("[WEB] Process restart confirmed (mode: {:?})", mode);
+    state.restart.request(mode);
+
+    // Let this response flush before the server starts shutting down
+    let shutdown = state.shutdown.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(std::time::Duration::from_millis(250)).await;
+        shutdown.cancel();
+    });
+
+    (
+        StatusCode::ACCEPTED,
+        Json(json!({ "success": true, "message": "Restarting" })),
+    )
+}
+
+/// GET /api/fleet/config — this sign's config for fleet members to pull.
+///
+/// Only served when `fleet.publish` is set. The publisher's own `fleet`
+/// section is left out; members keep theirs. So is its private `calendar`.
+pub async fn get_fleet_config(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let config = state.config.load();
+    if !config.fleet.publish {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(json!({ "success": false, "message": "Fleet publishing is disabled on this sign" })),
+        );
+    }
+
+    let mut config_json = config_to_json(&config);
+    if let Some(obj) = config_json.as_object_mut() {
+        obj.remove("fleet");
+        // Unusable without its (redacted) URL
+        obj.remove("calendar");
+    }
+    (
+        StatusCode::OK,
+        Json(json!({
+            "success": true,
+            "config": config_json,
+            "last_modified": config_file_mtime(&state),
+        })),
+    )
+}
+
+/// POST /api/fleet/sync — poll the fleet source now instead of waiting.
+///
+/// Lets an operator (or a script on the publishing sign) push changes out
+/// to members immediately.
+pub async fn trigger_fleet_sync(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let config = state.config.load();
+    match config.fleet.source_url() {
+        Some(source) => {
+            info!("[WEB] Fleet sync requested");
+            state.fleet_sync.notify_one();
+            (
+                StatusCode::ACCEPTED,
+                Json(json!({ "success": true, "message": format!("Syncing from {}", source) })),
+            )
+        }
+        None => (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "success": false, "message": "No fleet.source configured" })),
+        ),
+    }
+}
+
+/// POST /api/display/show-ip — show the sign's address on the panel for
+/// a few seconds.
+pub async fn show_ip(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let lines = netinfo::show_ip(&state);
+    Json(json!({
+        "success": true,
+        "lines": lines,
+        "seconds": netinfo::SHOW_IP_DURATION.as_secs(),
+    }))
+}
+
+#[derive(Deserialize)]
+pub struct DoNotDisturbBody {
+    /// Omitted: toggle.
+    enabled: Option<bool>,
+}
+
+/// GET /api/display/do-not-disturb — whether alerts and banners are paused.
+pub async fn get_do_not_disturb(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    Json(json!({
+        "success": true,
+        "enabled": state.do_not_disturb.load(Ordering::Relaxed),
+    }))
+}
+
+/// POST /api/display/do-not-disturb — pause alerts and banners, keeping
+/// train times (`{"enabled": bool}`; no body toggles).
+pub async fn set_do_not_disturb(
+    State(state): State<Arc<AppState>>,
+    body: Option<Json<DoNotDisturbBody>>,
+) -> impl IntoResponse {
+    let enabled = body.and_then(|Json(b)| b.enabled);
+    Json(json!({
+        "success": true,
+        "enabled": crate::set_do_not_disturb(&state, enabled),
+    }))
+}
+
 /// GET /api/healthz — liveness check with fetch and render heartbeats.
 pub async fn healthz(State(state): State<Arc<AppState>>) -> impl IntoResponse {
     let now = unix_now_secs();
@@ -250,28 +945,481 @@ pub async fn healthz(State(state): State<Arc<AppState>>) -> impl IntoResponse {
     let fetch_age = now - state.last_fetch_success.load(Ordering::Relaxed);
     let render_age = now - state.last_render_tick.load(Ordering::Relaxed);
 
-    let fetch_stale = fetch_age > config.refresh.trains_interval * 3;
+    // Fetches pause while the display is off
+    let display_off = crate::display_off(&state);
+    let fetch_stale = !display_off && fetch_age > config.refresh.trains_interval * 3;
     let render_stale = render_age > 10;
     let ok = !fetch_stale && !render_stale;
 
+    // Only meaningful while fetches are current
+    let mismatch = crate::config_mismatch_secs(&state, now).filter(|_| !fetch_stale);
+    let degraded_routes = state.degraded_routes.load_full();
+
+    let activity = state.frame_activity.lock().unwrap_or_else(|e| e.into_inner());
+    let frame_hash = activity.current().map(|h| format!("{:016x}", h));
+    // No panel to watch when headless; the render heartbeat still counts
+    let headless = config.panel.backend == DisplayBackend::Headless;
+    let display_active = (!headless).then(|| activity.is_active(now));
+    let display_unchanged = activity.unchanged_secs(now).filter(|_| !headless);
+
     let reason = match (fetch_stale, render_stale) {
         (true, true) => Some(format!("fetch stale {}s, render stale {}s", fetch_age, render_age)),
         (true, false) => Some(format!("fetch stale {}s", fetch_age)),
         (false, true) => Some(format!("render stale {}s", render_age)),
-        (false, false) => None,
+        (false, false) => mismatch
+            .map(|secs| {
+                format!(
+                    "no trains at the configured stops for {}s while the feeds are running those routes; check station/routes config",
+                    secs
+                )
+            })
+            .or_else(|| {
+                (!degraded_routes.is_empty())
+                    .then(|| format!("feed down for {}, showing cached trains", degraded_routes.join(", ")))
+            }),
     };
 
     Json(json!({
         "ok": ok,
+        "instance": state.instance,
         "fetch_age_seconds": fetch_age,
         "render_age_seconds": render_age,
-        "degraded": fetch_stale && !render_stale,
+        "degraded": (fetch_stale && !render_stale) || mismatch.is_some() || !degraded_routes.is_empty(),
+        "degraded_routes": *degraded_routes,
+        "config_mismatch_seconds": mismatch,
         "reason": reason,
+        "display_off": display_off,
+        "headless": headless,
+        "display_active": display_active,
+        "display_unchanged_seconds": display_unchanged,
+        "frame_hash": frame_hash,
+        "frames_fingerprint": format!("{:016x}", activity.fingerprint()),
+        "frame_changes": activity.changes(),
+    }))
+}
+
+/// GET /api/icons/{route}.png — render a route bullet from the font's icon data.
+///
+/// `?express=true` selects the diamond variant (falls back to the circle),
+/// `?scale=N` upscales each pixel to an NxN block (1-16, default 4).
+pub async fn get_route_icon_png(
+    Path(file): Path<String>,
+    Query(params): Query<IconParams>,
+) -> Response {
+    let Some(route) = file.strip_suffix(".png") else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let route = route.to_uppercase();
+    let is_express = params.express.unwrap_or_default().to_lowercase() == "true";
+
+    let font = fonts::get_font();
+    let Some(icon) = font.get_route_icon(&route, is_express) else {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "success": false,
+                "error": format!("No icon for route '{}'", route),
+            })),
+        )
+            .into_response();
+    };
+
+    let scale = params.scale.unwrap_or(DEFAULT_PNG_SCALE);
+    match export::icon_to_png(icon, scale) {
+        Ok(bytes) => (
+            [
+                (header::CONTENT_TYPE, "image/png"),
+                // Icons can be recolored or hot-reloaded at runtime
+                (header::CACHE_CONTROL, "no-cache"),
+            ],
+            bytes,
+        )
+            .into_response(),
+        Err(e) => png_error(e),
+    }
+}
+
+/// GET /api/preview/text — render arbitrary text in the sign font as a PNG.
+///
+/// Query: `text` (required), `style` (`regular`, `italic`, `bold`,
+/// `condensed`; `italic=true` is shorthand for alert style), `size=small`
+/// for the 8px font, `color` (name or hex, default green), `scale` (1-16,
+/// default 4).
+pub async fn preview_text(Query(params): Query<TextPreviewParams>) -> Response {
+    let text = params.text.unwrap_or_default();
+    if text.is_empty() {
+        return bad_request("text parameter is required".to_string());
+    }
+    if text.chars().count() > MAX_PREVIEW_TEXT_CHARS {
+        return bad_request(format!(
+            "text must be at most {} characters",
+            MAX_PREVIEW_TEXT_CHARS
+        ));
+    }
+
+    let color = match params.color.as_deref() {
+        None | Some("") => colors::COLOR_GREEN,
+        Some(c) => match colors::parse_color(c) {
+            Some(rgb) => rgb,
+            None => return bad_request(format!("Invalid color '{}'", c)),
+        },
+    };
+    let style = params.style.unwrap_or(
+        if params.italic.unwrap_or_default().to_lowercase() == "true" {
+            FontStyle::Italic
+        } else {
+            FontStyle::Regular
+        },
+    );
+
+    let font = fonts::get_font();
+    let fb = match params.size.unwrap_or_default() {
+        FontSize::Regular => {
+            let width = font.measure_text(&text, CHAR_SPACING, style).max(1);
+            let mut fb = FrameBuffer::with_size(width, fonts::FONT_HEIGHT);
+            fb.draw_text(&text, 0, 0, color, style, CHAR_SPACING);
+            fb
+        }
+        size => {
+            let width = font.measure_text_sized(&text, CHAR_SPACING, size).max(1);
+            let mut fb = FrameBuffer::with_size(width, size.height());
+            fb.draw_text_sized(&text, 0, 0, color, size, CHAR_SPACING);
+            fb
+        }
+    };
+
+    match export::framebuffer_to_png(&fb, params.scale.unwrap_or(DEFAULT_PNG_SCALE)) {
+        Ok(bytes) => png_response(bytes),
+        Err(e) => png_error(e),
+    }
+}
+
+/// POST /api/preview/frame — render what the sign would show as a PNG.
+///
+/// Uses a private `Renderer`, so live display state is never touched.
+/// Data source precedence: `trains` → arrivals fetched for `config` →
+/// the live snapshot.
+pub async fn preview_frame(
+    State(state): State<Arc<AppState>>,
+    Json(body): Json<FramePreviewRequest>,
+) -> Response {
+    // Candidate config (if any) also decides display options like arrival format
+    let candidate = match body.config {
+        Some(config_value) => {
+            let config_json = serde_json::to_string(&config_value).unwrap_or_default();
+            match Config::from_json(&config_json) {
+                Ok(cfg) => Some(cfg),
+                Err(e) => return bad_request(format!("Invalid config: {}", e)),
+            }
+        }
+        None => None,
+    };
+    let display_config = candidate
+        .as_ref()
+        .map_or_else(|| state.config.load().display.clone(), |c| c.display.clone());
+    let (width, height) = candidate
+        .as_ref()
+        .map_or_else(|| state.config.load().panel.frame_size(), |c| c.panel.frame_size());
+
+    let trains = if let Some(trains) = body.trains {
+        trains.into_iter().map(PreviewTrain::into_train).collect()
+    } else if let Some(config) = candidate {
+        let mut client = match MtaClient::new() {
+            Ok(c) => c,
+            Err(e) => {
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(json!({ "success": false, "error": e })),
+                )
+                    .into_response()
+            }
+        };
+        let routes: HashSet<String> = config.routes.iter().cloned().collect();
+        client
+            .fetch_trains(
+                &config.all_stop_ids(),
+                &routes,
+                config.display.max_trains as usize,
+            )
+            .await
+    } else {
+        state.snapshot.load().trains.clone()
+    };
+
+    let alert = body.alert.filter(|t| !t.is_empty()).map(|text| Alert {
+        text,
+        affected_routes: HashSet::new(),
+        priority: 1,
+        alert_id: "preview".to_string(),
+        url: None,
+        is_ticker: false,
+    });
+
+    let snapshot = DisplaySnapshot {
+        trains,
+        alerts: alert.iter().cloned().collect(),
+        fetched_at: 0.0,
+    };
+
+    let mut renderer = Renderer::new();
+    renderer.set_size(width, height);
+    renderer.set_arrival_format(display_config.arrival_format);
+    renderer.set_clock_after_minutes(display_config.clock_after_minutes);
+    renderer.set_font_styles(display_config.fonts.clone());
+    renderer.set_smooth_scroll(display_config.smooth_scroll);
+    renderer.set_top_row(display_config.top_row.clone());
+    if let Ok(strings) = Strings::new(&display_config.locale, &display_config.strings) {
+        renderer.set_strings(strings);
+    }
+    renderer.set_alternate_phase(body.alternate_clock.unwrap_or(false));
+    let frame = renderer.render_frame(
+        &snapshot,
+        body.cycle_index.unwrap_or(0),
+        body.flash.unwrap_or(false),
+        body.alert_scroll.unwrap_or(width as f32),
+        alert.is_some(),
+        alert.as_ref(),
+    );
+
+    match export::framebuffer_to_png(&frame, body.scale.unwrap_or(DEFAULT_PNG_SCALE)) {
+        Ok(bytes) => png_response(bytes),
+        Err(e) => png_error(e),
+    }
+}
+
+/// Query for GET /api/display/frame.png.
+#[derive(Deserialize)]
+pub struct FramePngParams {
+    scale: Option<usize>,
+}
+
+/// GET /api/display/timing — render loop frame work time percentiles and
+/// longest stall over the last minute and the last five minutes, and how
+/// the snapshot on screen got there (see `telemetry`).
+pub async fn get_frame_timing(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    Json(json!({
+        "success": true,
+        "windows": **state.frame_timing.load(),
+        "pipeline": **state.pipeline.load(),
+    }))
+}
+
+/// GET /api/display/frame.png — what the sign shows right now, for `<img>`
+/// embeds and dashboard camera cards that poll.
+pub async fn get_frame_png(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<FramePngParams>,
+) -> Response {
+    let frame = state.latest_frame.load_full();
+    match export::framebuffer_to_png(&frame, params.scale.unwrap_or(DEFAULT_PNG_SCALE)) {
+        Ok(bytes) => (
+            [
+                (header::CONTENT_TYPE, "image/png"),
+                (header::CACHE_CONTROL, "no-store"),
+            ],
+            bytes,
+        )
+            .into_response(),
+        Err(e) => png_error(e),
+    }
+}
+
+/// GET /api/frame.png — the current arrivals rendered on request with the
+/// configured display options, whatever the panel shows (off hours, info
+/// screens, idle clock). Alerts and banners are left out.
+pub async fn get_snapshot_png(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<FramePngParams>,
+) -> Response {
+    let config = state.config.load_full();
+    let snapshot = state.snapshot.load_full();
+    let mut renderer = Renderer::new();
+    renderer.apply_config(&config);
+    renderer.set_suspension(Suspension::new(
+        (**state.suspended_routes.load()).clone(),
+        &snapshot.trains,
+        &config.alternates,
+    ));
+    renderer.set_stale_routes(state.degraded_routes.load().iter().cloned().collect());
+    let frame = renderer.render_frame(&snapshot, 0, false, 0.0, false, None);
+    match export::framebuffer_to_png(&frame, params.scale.unwrap_or(DEFAULT_PNG_SCALE)) {
+        Ok(bytes) => (
+            [
+                (header::CONTENT_TYPE, "image/png"),
+                (header::CACHE_CONTROL, "no-store"),
+            ],
+            bytes,
+        )
+            .into_response(),
+        Err(e) => png_error(e),
+    }
+}
+
+/// Longest recording `POST /api/record` makes.
+const MAX_RECORD_SECONDS: u64 = 30;
+
+/// Recordings keep at most this many frames per second.
+const RECORD_FPS: u32 = 30;
+
+/// Query for POST /api/record.
+#[derive(Deserialize)]
+pub struct RecordParams {
+    seconds: Option<u64>,
+    scale: Option<usize>,
+}
+
+/// Clears `AppState::recording` when a recording ends, however it ends.
+struct RecordingGuard<'a>(&'a AtomicBool);
+
+impl Drop for RecordingGuard<'_> {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::Release);
+    }
+}
+
+/// POST /api/record?seconds=10 — capture the frames the sign shows for that
+/// long (1-30 s) and return them as an animated PNG. Repeated frames are
+/// merged, so still stretches cost little. One recording runs at a time;
+/// another gets 409.
+pub async fn record(State(state): State<Arc<AppState>>, Query(params): Query<RecordParams>) -> Response {
+    let seconds = params.seconds.unwrap_or(10);
+    if !(1..=MAX_RECORD_SECONDS).contains(&seconds) {
+        return bad_request(format!("seconds must be 1-{}", MAX_RECORD_SECONDS));
+    }
+    if state.recording.swap(true, Ordering::AcqRel) {
+        return (
+            StatusCode::CONFLICT,
+            Json(json!({ "success": false, "error": "A recording is already running" })),
+        )
+            .into_response();
+    }
+    let _recording = RecordingGuard(&state.recording);
+
+    let mut rx = state.frame_updates.subscribe();
+    let start = tokio::time::Instant::now();
+    let deadline = start + std::time::Duration::from_secs(seconds);
+    let min_gap = std::time::Duration::from_secs(1) / RECORD_FPS;
+    // (frame, when it appeared), starting from the frame on screen now
+    let mut frames = vec![(state.latest_frame.load_full(), start)];
+    loop {
+        let frame = tokio::select! {
+            _ = tokio::time::sleep_until(deadline) => break,
+            _ = state.shutdown.cancelled() => break,
+            frame = rx.recv() => frame,
+        };
+        let frame = match frame {
+            Ok(frame) => frame,
+            Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+        };
+        let now = tokio::time::Instant::now();
+        let (last, shown_at) = frames.last().expect("seeded above");
+        let same_size = (frame.width(), frame.height()) == (last.width(), last.height());
+        if !same_size {
+            break; // Panel resized: the recording ends here
+        }
+        if now - *shown_at >= min_gap && frame.fingerprint() != last.fingerprint() {
+            frames.push((frame, now));
+        }
+    }
+    let end = tokio::time::Instant::now().min(deadline);
+
+    let scale = params.scale.unwrap_or(DEFAULT_PNG_SCALE);
+    let result = tokio::task::spawn_blocking(move || {
+        let timed: Vec<(&FrameBuffer, std::time::Duration)> = frames
+            .iter()
+            .enumerate()
+            .map(|(i, (frame, at))| {
+                let until = frames.get(i + 1).map_or(end, |(_, next)| *next);
+                (&**frame, until.saturating_duration_since(*at))
+            })
+            .collect();
+        export::frames_to_apng(&timed, scale).map(|bytes| (bytes, timed.len()))
+    })
+    .await
+    .unwrap_or_else(|e| Err(format!("Task failed: {}", e)));
+
+    match result {
+        Ok((bytes, count)) => {
+            info!("[WEB] Recorded {}s ({} frames, {} KB)", seconds, count, bytes.len() / 1024);
+            (
+                [
+                    (header::CONTENT_TYPE, "image/png"),
+                    (header::CONTENT_DISPOSITION, "inline; filename=\"subway-sign.png\""),
+                    (header::CACHE_CONTROL, "no-store"),
+                ],
+                bytes,
+            )
+                .into_response()
+        }
+        Err(e) => png_error(e),
+    }
+}
+
+/// GET /api/alerts/state — every fetched alert with why it is or isn't
+/// going to be shown (next, queued, shown this cycle, on cooldown until a
+/// time, or cut from a full queue).
+pub async fn get_alert_state(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let config = state.config.load();
+    let decisions = state
+        .alert_manager
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .decisions();
+    Json(json!({
+        "success": true,
+        "show_alerts": config.display.show_alerts,
+        "ticker": config.display.alert_ticker.enabled,
+        "alerts": decisions,
     }))
 }
 
+/// POST /api/display/screenshot — save the current frame as a timestamped PNG.
+pub async fn take_screenshot(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    match crate::screenshot::capture(&state).await {
+        Ok(path) => {
+            info!("[WEB] Screenshot saved: {}", path.display());
+            (
+                StatusCode::OK,
+                Json(json!({ "success": true, "path": path.display().to_string() })),
+            )
+        }
+        Err(e) => {
+            warn!("[WEB] Screenshot failed: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "success": false, "message": format!("Screenshot failed: {}", e) })),
+            )
+        }
+    }
+}
+
 // -- Helper functions --
 
+/// 200 response carrying PNG bytes.
+fn png_response(bytes: Vec<u8>) -> Response {
+    ([(header::CONTENT_TYPE, "image/png")], bytes).into_response()
+}
+
+/// 400 response with a JSON error message.
+fn bad_request(message: String) -> Response {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(json!({ "success": false, "error": message })),
+    )
+        .into_response()
+}
+
+/// 500 response for a failed PNG encode.
+fn png_error(e: String) -> Response {
+    warn!("[WEB] PNG encode failed: {}", e);
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(json!({ "success": false, "error": e })),
+    )
+        .into_response()
+}
+
 /// Get config file mtime as RFC 3339 string (for last_modified / last_update).
 fn config_file_mtime(state: &AppState) -> Option<String> {
     std::fs::metadata(&state.config_path)
@@ -283,7 +1431,14 @@ fn config_file_mtime(state: &AppState) -> Option<String> {
         })
 }
 
+/// The config as sent to clients, with secrets redacted.
 fn config_to_json(config: &Config) -> serde_json::Value {
+    let mut json = config_json(config);
+    crate::config::redact_secrets(&mut json);
+    json
+}
+
+fn config_json(config: &Config) -> serde_json::Value {
     let station = if config.station_stops.len() == 1 {
         json!({
             "uptown_stop_id": config.station_stops[0].0,
@@ -304,15 +1459,24 @@ fn config_to_json(config: &Config) -> serde_json::Value {
 
     json!({
         "station": station,
-        "display": {
-            "brightness": config.display.brightness,
-            "max_trains": config.display.max_trains,
-            "show_alerts": config.display.show_alerts,
-        },
-        "refresh": {
-            "trains_interval": config.refresh.trains_interval,
-            "alerts_interval": config.refresh.alerts_interval,
-        },
+        "display": config.display,
+        "refresh": config.refresh,
+        "screenshots": config.screenshots,
+        "fleet": config.fleet,
+        "panel": config.panel,
+        "icons": config.icons,
+        "analytics": config.analytics,
+        "database": config.database,
+        "journey": config.journey,
+        "trip": config.trip,
+        "alternates": config.alternates,
+        "web": config.web,
+        "setup": config.setup,
+        "buttons": config.buttons,
+        "route_schedule": config.route_schedule,
+        "static_gtfs": config.static_gtfs,
+        "calendar": config.calendar,
+        "timezone": config.timezone,
     })
 }
 
@@ -1,2 +1,7 @@
+pub mod access_log;
+pub mod assets;
+pub mod favorites;
 pub mod handlers;
+pub mod raw_frame;
 pub mod server;
+pub mod ws;
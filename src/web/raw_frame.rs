@@ -0,0 +1,216 @@
+//! Raw frame API for external renderers.
+//!
+//! `GET /api/display/framebuffer.bin` returns the frame the sign renders
+//! (before `display.post` processing): row-major RGB888, `X-Frame-Width` x
+//! `X-Frame-Height` pixels. Each response carries an `ETag` naming the frame; a poller can
+//! send it back as `If-None-Match` (304 while nothing changed) or as
+//! `?base=` to get only the bytes that changed since that frame.
+//!
+//! Diff bodies (`X-Frame-Encoding: diff`) are a sequence of runs, each a
+//! big-endian u32 byte offset into the raw buffer, a big-endian u16 length,
+//! then that many bytes to overwrite. If the base frame is no longer known
+//! the full frame is sent instead (`X-Frame-Encoding: raw`).
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use axum::extract::{Query, State};
+use axum::http::{header, HeaderMap, HeaderName, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use serde::Deserialize;
+
+use crate::display::framebuffer::FrameBuffer;
+use crate::AppState;
+
+/// Served frames remembered as diff bases.
+const HISTORY_CAPACITY: usize = 16;
+
+/// Unchanged bytes between two changes that are sent anyway rather than
+/// starting a new run (a run header costs 6 bytes).
+const MERGE_GAP: usize = 6;
+
+/// Longest run; longer changes are split.
+const MAX_RUN: usize = u16::MAX as usize;
+
+/// Recently served frames, by tag.
+pub struct FrameHistory {
+    frames: Mutex<VecDeque<(String, Arc<FrameBuffer>)>>,
+}
+
+impl FrameHistory {
+    pub fn new() -> Self {
+        FrameHistory {
+            frames: Mutex::new(VecDeque::with_capacity(HISTORY_CAPACITY)),
+        }
+    }
+
+    fn remember(&self, tag: &str, frame: &Arc<FrameBuffer>) {
+        let mut frames = self.frames.lock().unwrap_or_else(|e| e.into_inner());
+        if frames.iter().any(|(t, _)| t == tag) {
+            return;
+        }
+        if frames.len() == HISTORY_CAPACITY {
+            frames.pop_front();
+        }
+        frames.push_back((tag.to_string(), frame.clone()));
+    }
+
+    fn get(&self, tag: &str) -> Option<Arc<FrameBuffer>> {
+        let frames = self.frames.lock().unwrap_or_else(|e| e.into_inner());
+        frames.iter().find(|(t, _)| t == tag).map(|(_, f)| f.clone())
+    }
+}
+
+impl Default for FrameHistory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Quoted ETag for a frame.
+fn frame_tag(frame: &FrameBuffer) -> String {
+    format!("\"{:016x}\"", frame.fingerprint())
+}
+
+/// Runs of bytes in `new` that differ from `old` (same length).
+fn encode_diff(old: &[u8], new: &[u8]) -> Vec<u8> {
+    debug_assert_eq!(old.len(), new.len());
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < new.len() {
+        if old[i] == new[i] {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        let mut end = i + 1;
+        // Extend over changes, absorbing short unchanged gaps
+        let mut j = end;
+        while j < new.len() && j - start < MAX_RUN {
+            if old[j] != new[j] {
+                end = j + 1;
+            } else if j - end >= MERGE_GAP {
+                break;
+            }
+            j += 1;
+        }
+        out.extend_from_slice(&(start as u32).to_be_bytes());
+        out.extend_from_slice(&((end - start) as u16).to_be_bytes());
+        out.extend_from_slice(&new[start..end]);
+        i = end;
+    }
+    out
+}
+
+#[derive(Deserialize)]
+pub struct FramebufferParams {
+    /// ETag of a frame the client already has; asks for a diff against it.
+    base: Option<String>,
+}
+
+/// GET `/api/display/framebuffer.bin?base=<etag>` — the current frame as raw
+/// RGB888 bytes, or as a diff against a frame the client already has.
+pub async fn get_framebuffer(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<FramebufferParams>,
+    headers: HeaderMap,
+) -> Response {
+    let frame = state.latest_frame.load_full();
+    let tag = frame_tag(&frame);
+    state.served_frames.remember(&tag, &frame);
+
+    let etag = HeaderValue::from_str(&tag).expect("hex tag is a valid header");
+    let unchanged = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.split(',').any(|t| t.trim() == tag));
+    if unchanged {
+        return (StatusCode::NOT_MODIFIED, [(header::ETAG, etag)]).into_response();
+    }
+
+    // Accept the tag with or without its quotes
+    let base = params
+        .base
+        .map(|b| format!("\"{}\"", b.trim_matches('"')))
+        .and_then(|b| state.served_frames.get(&b).map(|f| (b, f)))
+        .filter(|(_, f)| f.width() == frame.width() && f.height() == frame.height());
+    let (encoding, body, base_tag) = match base {
+        Some((base_tag, old)) => ("diff", encode_diff(old.raw_pixels(), frame.raw_pixels()), Some(base_tag)),
+        None => ("raw", frame.raw_pixels().to_vec(), None),
+    };
+
+    let mut response = (
+        [
+            (header::CONTENT_TYPE, HeaderValue::from_static("application/octet-stream")),
+            (header::CACHE_CONTROL, HeaderValue::from_static("no-store")),
+            (header::ETAG, etag),
+        ],
+        body,
+    )
+        .into_response();
+    let extra = response.headers_mut();
+    extra.insert(HeaderName::from_static("x-frame-width"), frame.width().into());
+    extra.insert(HeaderName::from_static("x-frame-height"), frame.height().into());
+    extra.insert(HeaderName::from_static("x-frame-encoding"), HeaderValue::from_static(encoding));
+    if let Some(base_tag) = base_tag.and_then(|t| HeaderValue::from_str(&t).ok()) {
+        extra.insert(HeaderName::from_static("x-frame-base"), base_tag);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Reference decoder for the documented diff format.
+    fn apply_diff(base: &[u8], diff: &[u8]) -> Vec<u8> {
+        let mut out = base.to_vec();
+        let mut rest = diff;
+        while !rest.is_empty() {
+            let offset = u32::from_be_bytes(rest[..4].try_into().unwrap()) as usize;
+            let len = u16::from_be_bytes(rest[4..6].try_into().unwrap()) as usize;
+            out[offset..offset + len].copy_from_slice(&rest[6..6 + len]);
+            rest = &rest[6 + len..];
+        }
+        out
+    }
+
+    #[test]
+    fn test_diff_roundtrip() {
+        let old = FrameBuffer::new();
+        let mut new = old.clone();
+        new.set_pixel(0, 0, (255, 0, 0));
+        new.set_pixel(2, 0, (0, 255, 0)); // close enough to share a run
+        new.set_pixel(100, 20, (1, 2, 3));
+        let diff = encode_diff(old.raw_pixels(), new.raw_pixels());
+        assert_eq!(apply_diff(old.raw_pixels(), &diff), new.raw_pixels());
+        // Two runs: pixels 0-2 merged, then the lone pixel
+        assert_eq!(diff.len(), (6 + 8) + (6 + 3));
+
+        assert!(encode_diff(old.raw_pixels(), old.raw_pixels()).is_empty());
+
+        let full = FrameBuffer::from_raw_pixels(vec![9; old.raw_pixels().len()]).unwrap();
+        let diff = encode_diff(old.raw_pixels(), full.raw_pixels());
+        assert_eq!(apply_diff(old.raw_pixels(), &diff), full.raw_pixels());
+    }
+
+    #[test]
+    fn test_frame_tag_and_history() {
+        let a = Arc::new(FrameBuffer::new());
+        let mut b = FrameBuffer::new();
+        b.set_pixel(5, 5, (1, 1, 1));
+        let b = Arc::new(b);
+        assert_ne!(frame_tag(&a), frame_tag(&b));
+        assert_eq!(frame_tag(&a), frame_tag(&FrameBuffer::new()));
+
+        let history = FrameHistory::new();
+        history.remember(&frame_tag(&a), &a);
+        assert!(history.get(&frame_tag(&a)).is_some());
+        for i in 0..HISTORY_CAPACITY {
+            let mut f = FrameBuffer::new();
+            f.set_pixel(i as i32, 0, (7, 7, 7));
+            history.remember(&frame_tag(&f), &Arc::new(f));
+        }
+        assert!(history.get(&frame_tag(&a)).is_none(), "oldest frame evicted");
+    }
+}
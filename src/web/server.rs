@@ -1,16 +1,21 @@
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::io::Write;
+use std::net::SocketAddr;
+use std::sync::{Arc, OnceLock};
 
-use axum::extract::DefaultBodyLimit;
-use axum::http::{header, StatusCode};
-use axum::response::{IntoResponse, Response};
-use axum::routing::{get, post};
-use axum::Router;
+use axum::extract::{DefaultBodyLimit, Request, State};
+use axum::http::{header, HeaderMap, HeaderValue, Method, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Redirect, Response};
+use axum::routing::{delete, get, post};
+use axum::{Json, Router};
 use rust_embed::Embed;
+use serde_json::json;
 use tracing::info;
 
-use crate::AppState;
+use crate::{config, AppState};
 
-use super::handlers;
+use super::{access_log, assets, favorites, handlers, raw_frame, ws};
 
 /// Embedded web assets (HTML, CSS, JS, icons).
 #[derive(Embed)]
@@ -18,38 +23,89 @@ use super::handlers;
 #[prefix = ""]
 struct WebAssets;
 
-/// Run the axum web server on 0.0.0.0:5001.
+/// Run the axum web server on 0.0.0.0, port `web.port` (default 5001).
 pub async fn run(state: Arc<AppState>) {
     let app = Router::new()
         // API routes
         .route("/api/config", get(handlers::get_config).post(handlers::update_config))
+        .route("/api/config/diff", get(handlers::get_config_diff))
         .route("/api/status", get(handlers::get_status))
         .route("/api/healthz", get(handlers::healthz))
+        .route("/api/reload", post(handlers::reload))
         .route("/api/restart", post(handlers::restart))
+        .route("/api/fleet/config", get(handlers::get_fleet_config))
+        .route("/api/fleet/sync", post(handlers::trigger_fleet_sync))
+        .route("/api/routes", get(handlers::get_routes))
         .route("/api/stations/complete", get(handlers::get_complete_stations))
+        .route("/api/stations/suggest", get(handlers::suggest_stations))
         .route("/api/stations/lookup/{station_name}", get(handlers::lookup_station))
+        .route("/api/trains", get(handlers::get_trains))
+        .route("/api/alerts/state", get(handlers::get_alert_state))
+        .route("/api/trains/{trip_id}", get(handlers::get_trip))
         .route("/api/debug/snapshot", get(handlers::get_debug_snapshot))
+        .route("/api/logs/http", get(handlers::get_http_log))
+        .route("/api/analytics", get(handlers::get_analytics))
+        .route("/api/analytics/headways", get(handlers::get_headways))
+        .route("/api/analytics/arrivals.csv", get(handlers::get_arrivals_csv))
+        .route("/api/font/coverage", get(handlers::get_font_coverage))
+        .route("/api/fonts/metrics", get(handlers::get_font_metrics))
+        .route("/api/icons/{file}", get(handlers::get_route_icon_png))
+        .route("/api/preview/text", get(handlers::preview_text))
+        .route("/api/preview/frame", post(handlers::preview_frame))
+        .route("/api/display/screenshot", post(handlers::take_screenshot))
+        .route("/api/display/frame.png", get(handlers::get_frame_png))
+        .route("/api/frame.png", get(handlers::get_snapshot_png))
+        .route("/api/record", post(handlers::record))
+        .route("/api/display/timing", get(handlers::get_frame_timing))
+        .route("/api/display/framebuffer.bin", get(raw_frame::get_framebuffer))
+        .route("/api/display/show-ip", post(handlers::show_ip))
+        .route(
+            "/api/display/do-not-disturb",
+            get(handlers::get_do_not_disturb).post(handlers::set_do_not_disturb),
+        )
+        .route("/api/favorites", get(favorites::list).post(favorites::save))
+        .route("/api/favorites/{name}", delete(favorites::delete))
+        .route("/api/favorites/{name}/apply", post(favorites::apply))
+        .route("/api/assets", get(assets::list))
+        .route(
+            "/api/assets/{name}",
+            get(assets::get)
+                .post(assets::upload)
+                .delete(assets::delete)
+                // Uploads need more than the global limit; the configured
+                // web.max_upload_kb is enforced in the handler
+                .layer(DefaultBodyLimit::max(config::MAX_UPLOAD_KB as usize * 1024)),
+        )
+        // WebSockets
+        .route("/ws/logs", get(ws::logs))
+        .route("/ws/frames", get(ws::frames))
+        .route("/ws/preview", get(ws::preview))
         // Static files and index
         .route("/", get(serve_index))
+        .route("/service", get(serve_service))
+        .route("/embed", get(serve_embed))
         .fallback(get(serve_static))
         // Middleware
         .layer(DefaultBodyLimit::max(65536)) // 64KB max request body
+        .layer(middleware::from_fn_with_state(state.clone(), read_only_guard))
+        .layer(middleware::from_fn_with_state(state.clone(), access_log::access_log))
         // Shared state
         .with_state(state.clone());
 
-    let listener = match tokio::net::TcpListener::bind("0.0.0.0:5001").await {
+    let port = state.config.load().web.port;
+    let listener = match tokio::net::TcpListener::bind(("0.0.0.0", port)).await {
         Ok(l) => {
-            info!("[WEB] Server listening on http://0.0.0.0:5001");
+            info!("[WEB] Server listening on http://0.0.0.0:{}", port);
             l
         }
         Err(e) => {
-            tracing::error!("[WEB] Failed to bind port 5001: {}", e);
+            tracing::error!("[WEB] Failed to bind port {}: {}", port, e);
             return;
         }
     };
 
     let shutdown = state.shutdown.clone();
-    axum::serve(listener, app)
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
         .with_graceful_shutdown(async move { shutdown.cancelled().await })
         .await
         .ok();
@@ -57,31 +113,208 @@ pub async fn run(state: Arc<AppState>) {
     info!("[WEB] Server stopped");
 }
 
+/// Pages and endpoints that show or edit configuration, hidden in
+/// read-only mode.
+const CONFIG_UI_PATHS: &[&str] = &[
+    "/",
+    "/api/config",
+    "/api/config/diff",
+    "/api/debug/snapshot",
+    "/api/favorites",
+    "/api/fleet/config",
+    "/api/logs/http",
+    "/ws/logs",
+];
+
+/// What read-only mode does with a request.
+#[derive(Debug, PartialEq)]
+enum ReadOnlyAction {
+    Allow,
+    /// A write: refuse with 403.
+    Forbid,
+    /// Config UI: the index page goes to the live sign, the rest 404.
+    Hide,
+}
+
+fn read_only_action(method: &Method, path: &str) -> ReadOnlyAction {
+    if !matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS) {
+        ReadOnlyAction::Forbid
+    } else if CONFIG_UI_PATHS.contains(&path) {
+        ReadOnlyAction::Hide
+    } else {
+        ReadOnlyAction::Allow
+    }
+}
+
+/// Enforce `web.read_only`. Checked per request, so toggling it in the
+/// config file takes effect on the next hot reload.
+async fn read_only_guard(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if !state.config.load().web.read_only {
+        return next.run(request).await;
+    }
+    match read_only_action(request.method(), request.uri().path()) {
+        ReadOnlyAction::Allow => next.run(request).await,
+        ReadOnlyAction::Forbid => (
+            StatusCode::FORBIDDEN,
+            Json(json!({
+                "success": false,
+                "message": "This sign is read-only (web.read_only in config.json); changes are disabled",
+            })),
+        )
+            .into_response(),
+        ReadOnlyAction::Hide if request.uri().path() == "/" => {
+            Redirect::temporary("/embed").into_response()
+        }
+        ReadOnlyAction::Hide => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
 /// Serve the main index.html page.
-async fn serve_index() -> Response {
-    serve_embedded_file("templates/index.html").await
+async fn serve_index(headers: HeaderMap) -> Response {
+    serve_embedded_file("templates/index.html", &headers)
+}
+
+/// Serve the "today's service" analytics page.
+async fn serve_service(headers: HeaderMap) -> Response {
+    serve_embedded_file("templates/service.html", &headers)
+}
+
+/// Serve the chrome-free live sign, for iframes.
+async fn serve_embed(headers: HeaderMap) -> Response {
+    serve_embedded_file("templates/embed.html", &headers)
 }
 
 /// Serve static files from embedded assets.
-async fn serve_static(uri: axum::http::Uri) -> Response {
+async fn serve_static(uri: axum::http::Uri, headers: HeaderMap) -> Response {
     let path = uri.path().trim_start_matches('/');
-    serve_embedded_file(path).await
-}
-
-/// Look up and serve an embedded file with appropriate content type.
-async fn serve_embedded_file(path: &str) -> Response {
-    match WebAssets::get(path) {
-        Some(file) => {
-            let mime = mime_for_path(path);
-            (
-                StatusCode::OK,
-                [(header::CONTENT_TYPE, mime)],
-                file.data.to_vec(),
-            )
-                .into_response()
+    serve_embedded_file(path, &headers)
+}
+
+/// Look up and serve an embedded file with content type, ETag, and caching headers.
+///
+/// Answers `If-None-Match` revalidation with 304 and serves the gzip
+/// pre-compressed copy when the client accepts it.
+fn serve_embedded_file(path: &str, headers: &HeaderMap) -> Response {
+    let Some(file) = WebAssets::get(path) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+
+    let etag = etag_for_hash(&file.metadata.sha256_hash());
+    let mut response_headers = HeaderMap::new();
+    response_headers.insert(
+        header::CACHE_CONTROL,
+        HeaderValue::from_static(cache_control_for_path(path)),
+    );
+    response_headers.insert(header::VARY, HeaderValue::from_static("Accept-Encoding"));
+    if let Ok(value) = HeaderValue::from_str(&etag) {
+        response_headers.insert(header::ETAG, value);
+    }
+
+    let if_none_match = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok());
+    if if_none_match.is_some_and(|inm| etag_matches(inm, &etag)) {
+        return (StatusCode::NOT_MODIFIED, response_headers).into_response();
+    }
+
+    response_headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static(mime_for_path(path)),
+    );
+
+    if accepts_gzip(headers) {
+        if let Some(gz) = gzipped_assets().get(path) {
+            response_headers.insert(header::CONTENT_ENCODING, HeaderValue::from_static("gzip"));
+            return (StatusCode::OK, response_headers, gz.clone()).into_response();
         }
-        None => StatusCode::NOT_FOUND.into_response(),
     }
+
+    (StatusCode::OK, response_headers, file.data.to_vec()).into_response()
+}
+
+/// Weak ETag from the content hash embedded at build time.
+///
+/// Weak because the same tag covers both the identity and gzip encodings.
+fn etag_for_hash(hash: &[u8; 32]) -> String {
+    let hex: String = hash[..8].iter().map(|b| format!("{:02x}", b)).collect();
+    format!("W/\"{}\"", hex)
+}
+
+/// Weak comparison of an `If-None-Match` header value against our ETag.
+fn etag_matches(if_none_match: &str, etag: &str) -> bool {
+    let ours = etag.trim_start_matches("W/");
+    if_none_match
+        .split(',')
+        .map(str::trim)
+        .any(|tag| tag == "*" || tag.trim_start_matches("W/") == ours)
+}
+
+/// Caching policy: pages and the service worker always revalidate (so a
+/// deploy shows up immediately); other assets are cached for a day.
+fn cache_control_for_path(path: &str) -> &'static str {
+    if path.ends_with(".html") || path.ends_with("service-worker.js") {
+        "no-cache"
+    } else {
+        "public, max-age=86400"
+    }
+}
+
+/// Whether the request's `Accept-Encoding` allows gzip.
+fn accepts_gzip(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::ACCEPT_ENCODING)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| {
+            v.split(',').any(|enc| {
+                let mut parts = enc.trim().split(';');
+                let name = parts.next().unwrap_or("").trim();
+                let rejected = parts
+                    .any(|p| matches!(p.trim(), "q=0" | "q=0.0" | "q=0.00" | "q=0.000"));
+                (name == "gzip" || name == "*") && !rejected
+            })
+        })
+}
+
+/// Gzip copies of text assets, compressed once on first request.
+///
+/// Only kept when compression actually saves space.
+fn gzipped_assets() -> &'static HashMap<String, Vec<u8>> {
+    static GZIPPED: OnceLock<HashMap<String, Vec<u8>>> = OnceLock::new();
+    GZIPPED.get_or_init(|| {
+        let mut map = HashMap::new();
+        for path in WebAssets::iter() {
+            if !is_compressible(mime_for_path(&path)) {
+                continue;
+            }
+            let Some(file) = WebAssets::get(&path) else {
+                continue;
+            };
+            let mut encoder =
+                flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::best());
+            if encoder.write_all(&file.data).is_err() {
+                continue;
+            }
+            if let Ok(gz) = encoder.finish() {
+                if gz.len() < file.data.len() {
+                    map.insert(path.to_string(), gz);
+                }
+            }
+        }
+        map
+    })
+}
+
+/// Text-like MIME types worth compressing (images are already compressed).
+fn is_compressible(mime: &str) -> bool {
+    mime.starts_with("text/")
+        || mime.starts_with("application/javascript")
+        || mime.starts_with("application/json")
+        || mime.starts_with("application/manifest+json")
+        || mime == "image/svg+xml"
 }
 
 /// Determine MIME type from file extension.
@@ -98,3 +331,63 @@ fn mime_for_path(path: &str) -> &'static str {
         _ => "application/octet-stream",
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_only_action() {
+        use ReadOnlyAction::*;
+        assert_eq!(read_only_action(&Method::POST, "/api/config"), Forbid);
+        assert_eq!(read_only_action(&Method::POST, "/api/preview/frame"), Forbid);
+        assert_eq!(read_only_action(&Method::DELETE, "/api/anything"), Forbid);
+        assert_eq!(read_only_action(&Method::GET, "/api/config"), Hide);
+        assert_eq!(read_only_action(&Method::GET, "/"), Hide);
+        assert_eq!(read_only_action(&Method::GET, "/api/fleet/config"), Hide);
+        assert_eq!(read_only_action(&Method::GET, "/api/status"), Allow);
+        assert_eq!(read_only_action(&Method::GET, "/embed"), Allow);
+        assert_eq!(read_only_action(&Method::HEAD, "/static/css/style.css"), Allow);
+    }
+
+    #[test]
+    fn test_etag_matches() {
+        let etag = etag_for_hash(&[0xab; 32]);
+        assert_eq!(etag, "W/\"abababababababab\"");
+        assert!(etag_matches(&etag, &etag));
+        assert!(etag_matches("\"abababababababab\"", &etag), "weak comparison ignores W/");
+        assert!(etag_matches("\"other\", W/\"abababababababab\"", &etag));
+        assert!(etag_matches("*", &etag));
+        assert!(!etag_matches("\"other\"", &etag));
+    }
+
+    #[test]
+    fn test_accepts_gzip() {
+        let mut headers = HeaderMap::new();
+        assert!(!accepts_gzip(&headers));
+        headers.insert(header::ACCEPT_ENCODING, HeaderValue::from_static("gzip, deflate, br"));
+        assert!(accepts_gzip(&headers));
+        headers.insert(header::ACCEPT_ENCODING, HeaderValue::from_static("gzip;q=0, br"));
+        assert!(!accepts_gzip(&headers));
+    }
+
+    #[test]
+    fn test_gzipped_assets_decompress_to_original() {
+        use std::io::Read;
+
+        let gz = gzipped_assets()
+            .get("templates/index.html")
+            .expect("index.html should be pre-compressed");
+        let mut decoded = Vec::new();
+        flate2::read::GzDecoder::new(gz.as_slice())
+            .read_to_end(&mut decoded)
+            .unwrap();
+        let original = WebAssets::get("templates/index.html").unwrap();
+        assert_eq!(decoded, original.data.as_ref());
+    }
+
+    #[test]
+    fn test_images_not_compressed() {
+        assert!(!gzipped_assets().contains_key("static/icon-192.png"));
+    }
+}
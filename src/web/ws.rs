@@ -0,0 +1,260 @@
+//! WebSocket endpoints.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Query, State};
+use axum::response::Response;
+use serde::Deserialize;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::time::MissedTickBehavior;
+use tracing::Level;
+
+use crate::config::MAX_FRAME_FPS;
+use crate::display::framebuffer::FrameBuffer;
+use crate::logging::LogLine;
+use crate::AppState;
+
+/// Frame rate for `/ws/frames` when the client doesn't ask for one.
+const DEFAULT_FRAME_FPS: u32 = 10;
+
+#[derive(Deserialize)]
+pub struct LogStreamParams {
+    /// Minimum level to stream (`error`, `warn`, `info`, `debug`, `trace`).
+    level: Option<String>,
+}
+
+/// GET /ws/logs?level=info — stream log lines as JSON text messages.
+///
+/// Sends the recent backlog first, then live lines until the client
+/// disconnects or the server shuts down.
+pub async fn logs(
+    ws: WebSocketUpgrade,
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<LogStreamParams>,
+) -> Response {
+    let min_level = params
+        .level
+        .and_then(|l| l.parse::<Level>().ok())
+        .unwrap_or(Level::INFO);
+    ws.on_upgrade(move |socket| stream_logs(socket, state, min_level))
+}
+
+async fn stream_logs(mut socket: WebSocket, state: Arc<AppState>, min_level: Level) {
+    // Subscribe before reading the backlog so nothing falls in between
+    let mut rx = state.logs.subscribe();
+
+    for line in state.logs.recent() {
+        if !send_line(&mut socket, &line, min_level).await {
+            return;
+        }
+    }
+
+    loop {
+        tokio::select! {
+            _ = state.shutdown.cancelled() => {
+                let _ = socket.send(Message::Close(None)).await;
+                break;
+            }
+            incoming = socket.recv() => {
+                // Client closed (or errored); ignore anything it sends us
+                if matches!(incoming, None | Some(Err(_)) | Some(Ok(Message::Close(_)))) {
+                    break;
+                }
+            }
+            line = rx.recv() => match line {
+                Ok(line) => {
+                    if !send_line(&mut socket, &line, min_level).await {
+                        break;
+                    }
+                }
+                Err(RecvError::Lagged(skipped)) => {
+                    let notice = LogLine {
+                        timestamp: chrono::Local::now().to_rfc3339(),
+                        level: "WARN".to_string(),
+                        target: "subway_sign::web".to_string(),
+                        message: format!("[WEB] Log stream lagged, {} lines dropped", skipped),
+                    };
+                    if !send_line(&mut socket, &notice, Level::TRACE).await {
+                        break;
+                    }
+                }
+                Err(RecvError::Closed) => break,
+            },
+        }
+    }
+}
+
+/// Send one line if it passes the level filter. Returns false once the socket is gone.
+async fn send_line(socket: &mut WebSocket, line: &LogLine, min_level: Level) -> bool {
+    // tracing orders levels by verbosity: ERROR < WARN < INFO < DEBUG < TRACE
+    if line.level() > min_level {
+        return true;
+    }
+    let Ok(text) = serde_json::to_string(line) else {
+        return true;
+    };
+    socket.send(Message::Text(text.into())).await.is_ok()
+}
+
+#[derive(Deserialize)]
+pub struct FrameStreamParams {
+    /// Frames per second (1-30, default 10).
+    fps: Option<u32>,
+}
+
+/// GET /ws/frames?fps=10 — stream what the sign shows as binary messages.
+///
+/// Each message is one frame: width and height as big-endian u16, then
+/// row-major RGB bytes. Frames identical to the last one sent are skipped,
+/// so a static sign costs almost nothing.
+pub async fn frames(
+    ws: WebSocketUpgrade,
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<FrameStreamParams>,
+) -> Response {
+    let fps = params.fps.unwrap_or(DEFAULT_FRAME_FPS).clamp(1, MAX_FRAME_FPS);
+    ws.on_upgrade(move |socket| stream_frames(socket, state, fps))
+}
+
+async fn stream_frames(mut socket: WebSocket, state: Arc<AppState>, fps: u32) {
+    let mut interval = tokio::time::interval(Duration::from_secs(1) / fps);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    let mut last_sent: Option<Arc<FrameBuffer>> = None;
+
+    loop {
+        tokio::select! {
+            _ = state.shutdown.cancelled() => {
+                let _ = socket.send(Message::Close(None)).await;
+                break;
+            }
+            incoming = socket.recv() => {
+                if matches!(incoming, None | Some(Err(_)) | Some(Ok(Message::Close(_)))) {
+                    break;
+                }
+            }
+            _ = interval.tick() => {
+                let frame = state.latest_frame.load_full();
+                if last_sent.as_ref().is_some_and(|l| l.raw_pixels() == frame.raw_pixels()) {
+                    continue;
+                }
+                if socket.send(Message::Binary(encode_frame(&frame).into())).await.is_err() {
+                    break;
+                }
+                last_sent = Some(frame);
+            }
+        }
+    }
+}
+
+/// Encoding of `/ws/preview` frames.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PreviewFormat {
+    /// A PNG image per message, at one pixel per LED.
+    #[default]
+    Png,
+    /// The `/ws/frames` format.
+    Raw,
+}
+
+#[derive(Deserialize)]
+pub struct PreviewParams {
+    #[serde(default)]
+    format: PreviewFormat,
+}
+
+/// GET /ws/preview?format=png — stream each frame the render loop draws, at
+/// up to `web.preview_fps`, as binary PNG (or `raw`) messages.
+///
+/// Frames come from the render loop as they're drawn; only the newest is
+/// sent at each tick, and only if it changed.
+pub async fn preview(
+    ws: WebSocketUpgrade,
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<PreviewParams>,
+) -> Response {
+    let fps = state.config.load().web.preview_fps.clamp(1, MAX_FRAME_FPS);
+    ws.on_upgrade(move |socket| stream_preview(socket, state, fps, params.format))
+}
+
+async fn stream_preview(mut socket: WebSocket, state: Arc<AppState>, fps: u32, format: PreviewFormat) {
+    let mut rx = state.frame_updates.subscribe();
+    let mut interval = tokio::time::interval(Duration::from_secs(1) / fps);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    // Start from the frame on screen now rather than waiting for the next
+    let mut pending: Option<Arc<FrameBuffer>> = Some(state.latest_frame.load_full());
+    let mut last_sent: Option<Arc<FrameBuffer>> = None;
+
+    loop {
+        tokio::select! {
+            _ = state.shutdown.cancelled() => {
+                let _ = socket.send(Message::Close(None)).await;
+                break;
+            }
+            incoming = socket.recv() => {
+                if matches!(incoming, None | Some(Err(_)) | Some(Ok(Message::Close(_)))) {
+                    break;
+                }
+            }
+            frame = rx.recv() => match frame {
+                Ok(frame) => pending = Some(frame),
+                // Behind: newer frames are still queued
+                Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => break,
+            },
+            _ = interval.tick() => {
+                let Some(frame) = pending.take() else { continue };
+                if last_sent.as_ref().is_some_and(|l| l.raw_pixels() == frame.raw_pixels()) {
+                    continue;
+                }
+                let Some(message) = encode_preview(&frame, format) else { continue };
+                if socket.send(Message::Binary(message.into())).await.is_err() {
+                    break;
+                }
+                last_sent = Some(frame);
+            }
+        }
+    }
+}
+
+fn encode_preview(frame: &FrameBuffer, format: PreviewFormat) -> Option<Vec<u8>> {
+    match format {
+        PreviewFormat::Png => crate::display::export::framebuffer_to_png(frame, 1).ok(),
+        PreviewFormat::Raw => Some(encode_frame(frame)),
+    }
+}
+
+/// Wire format of one `/ws/frames` message.
+fn encode_frame(frame: &FrameBuffer) -> Vec<u8> {
+    let pixels = frame.raw_pixels();
+    let mut message = Vec::with_capacity(4 + pixels.len());
+    message.extend_from_slice(&(frame.width() as u16).to_be_bytes());
+    message.extend_from_slice(&(frame.height() as u16).to_be_bytes());
+    message.extend_from_slice(pixels);
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_frame() {
+        let mut frame = FrameBuffer::with_size(3, 2);
+        frame.set_pixel(2, 1, (1, 2, 3));
+        let message = encode_frame(&frame);
+        assert_eq!(&message[..4], &[0, 3, 0, 2]);
+        assert_eq!(message.len(), 4 + 3 * 2 * 3);
+        assert_eq!(&message[message.len() - 3..], &[1, 2, 3]);
+    }
+
+    #[test]
+    fn test_encode_preview() {
+        let frame = FrameBuffer::new();
+        let png = encode_preview(&frame, PreviewFormat::Png).unwrap();
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        assert_eq!(encode_preview(&frame, PreviewFormat::Raw).unwrap(), encode_frame(&frame));
+    }
+}